@@ -151,15 +151,23 @@ pub fn path_all_scenarios(name: &MapName) -> String {
     path(format!("system/{}/scenarios/{}", name.city, name.map))
 }
 
-/// Extract the map and scenario name from a path. Crashes if the input is strange.
-pub fn parse_scenario_path(path: &str) -> (MapName, String) {
+pub fn path_population(name: &MapName) -> String {
+    path(format!("system/{}/population/{}.bin", name.city, name.map))
+}
+
+/// Extract the map and scenario name from a path like `.../<city>/scenarios/<map>/<name>.bin`.
+/// None if the file isn't in a scenarios directory.
+pub fn parse_scenario_path(path: &str) -> Option<(MapName, String)> {
     // TODO regex
     let parts = path.split("/").collect::<Vec<_>>();
+    if parts.len() < 4 || parts[parts.len() - 3] != "scenarios" {
+        return None;
+    }
     let city = parts[parts.len() - 4];
     let map = parts[parts.len() - 2];
     let scenario = basename(parts[parts.len() - 1]);
     let map_name = MapName::new(city, map);
-    (map_name, scenario)
+    Some((map_name, scenario))
 }
 
 // Player data (Players edit this)
@@ -200,6 +208,14 @@ pub fn path_popdat() -> String {
     path("input/seattle/popdat.bin")
 }
 
+pub fn path_osm_fixes(city: &str) -> String {
+    path(format!("input/{}/osm_fixes.json", city))
+}
+
+pub fn path_neighborhoods(city: &str) -> String {
+    path(format!("input/{}/neighborhoods", city))
+}
+
 pub fn path_raw_map(name: &MapName) -> String {
     path(format!("input/{}/raw_maps/{}.bin", name.city, name.map))
 }
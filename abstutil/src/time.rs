@@ -66,12 +66,17 @@ impl Progress {
             return Some((elapsed, line));
         } else if elapsed_seconds(self.last_printed_at) >= PROGRESS_FREQUENCY_SECONDS {
             self.last_printed_at = Instant::now();
+            let elapsed = elapsed_seconds(self.started_at);
+            // Assume the remaining items take as long as the ones so far
+            let remaining = elapsed / (self.processed_items as f64)
+                * ((self.total_items - self.processed_items) as f64);
             let line = format!(
-                "{}: {}/{}... {}",
+                "{}: {}/{}... {} (about {} left)",
                 self.label,
                 prettyprint_usize(self.processed_items),
                 prettyprint_usize(self.total_items),
-                prettyprint_time(elapsed_seconds(self.started_at))
+                prettyprint_time(elapsed),
+                prettyprint_time(remaining)
             );
             clear_current_line();
             print!("{}", line);
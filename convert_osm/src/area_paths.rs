@@ -0,0 +1,134 @@
+use std::collections::BTreeMap;
+
+use abstutil::{Tags, Timer};
+use geom::{Distance, Line};
+use map_model::raw::{OriginalRoad, RawIntersection, RawMap, RawRoad};
+use map_model::{osm, AreaType, IntersectionType};
+
+use crate::PathsThroughAreas;
+
+// An intersection can be this far outside of an area and still lead into it
+const MAX_DIST_TO_ENTRANCE: Distance = Distance::const_meters(20.0);
+// How often to check that a new path stays inside the area
+const STEP_SIZE: Distance = Distance::const_meters(5.0);
+
+/// Without paths mapped inside a big park or plaza, pedestrians route around its edge. Connect
+/// every walkable intersection along the edge to a new point in the middle with straight footways,
+/// as long as the whole path stays inside the area.
+pub fn add_paths_through_areas(map: &mut RawMap, opts: &PathsThroughAreas, timer: &mut Timer) {
+    let areas: Vec<_> = map
+        .areas
+        .iter()
+        .filter(|a| match opts {
+            PathsThroughAreas::None => false,
+            PathsThroughAreas::ParksAndPlazas => {
+                a.area_type == AreaType::Park || a.area_type == AreaType::PedestrianPlaza
+            }
+            PathsThroughAreas::Only(ids) => ids.contains(&a.osm_id),
+        })
+        .map(|a| {
+            (
+                a.osm_id,
+                a.polygon.clone(),
+                a.osm_tags.get(osm::NAME).cloned(),
+            )
+        })
+        .collect();
+
+    let mut num_areas = 0;
+    let mut num_paths = 0;
+    timer.start_iter("add paths through areas", areas.len());
+    for (id, polygon, name) in areas {
+        timer.next();
+        if map
+            .roads
+            .values()
+            .any(|r| r.center_points.iter().any(|pt| polygon.contains_pt(*pt)))
+        {
+            continue;
+        }
+
+        let center = polygon.polylabel();
+        let bounds = polygon.get_bounds();
+        let mut entrances = Vec::new();
+        for (i, intersection) in &map.intersections {
+            let pt = intersection.point;
+            if intersection.intersection_type == IntersectionType::Border
+                || pt.x() < bounds.min_x - MAX_DIST_TO_ENTRANCE.inner_meters()
+                || pt.x() > bounds.max_x + MAX_DIST_TO_ENTRANCE.inner_meters()
+                || pt.y() < bounds.min_y - MAX_DIST_TO_ENTRANCE.inner_meters()
+                || pt.y() > bounds.max_y + MAX_DIST_TO_ENTRANCE.inner_meters()
+            {
+                continue;
+            }
+            let walkable = map.roads_per_intersection(*i).into_iter().any(|r| {
+                let tags = &map.roads[&r].osm_tags;
+                !tags.is("foot", "no")
+                    && tags
+                        .get(osm::HIGHWAY)
+                        .map(|hwy| osm::RoadRank::from_highway(hwy) != osm::RoadRank::Highway)
+                        .unwrap_or(false)
+            });
+            if !walkable {
+                continue;
+            }
+            if let Some(line) = Line::new(pt, center) {
+                let mut dist = MAX_DIST_TO_ENTRANCE;
+                let mut inside = line.length() > dist;
+                while inside && dist < line.length() {
+                    inside = polygon.contains_pt(line.must_dist_along(dist));
+                    dist += STEP_SIZE;
+                }
+                if inside {
+                    entrances.push(*i);
+                }
+            }
+        }
+        // A single entrance doesn't make a shortcut to anywhere
+        if entrances.len() < 2 {
+            continue;
+        }
+
+        let center_id = map.new_osm_node_id(-1);
+        map.intersections.insert(
+            center_id,
+            RawIntersection {
+                point: center,
+                intersection_type: IntersectionType::StopSign,
+                elevation: Distance::ZERO,
+                rail_crossing: false,
+            },
+        );
+        for i in entrances {
+            let osm_way_id = map.new_osm_way_id(-1);
+            let mut osm_tags = Tags::new(BTreeMap::new());
+            osm_tags.insert(osm::HIGHWAY, "footway");
+            osm_tags.insert(osm::OSM_WAY_ID, osm_way_id.0.to_string());
+            osm_tags.insert(osm::ENDPT_FWD, "true");
+            osm_tags.insert(osm::ENDPT_BACK, "true");
+            osm_tags.insert(osm::PATH_THROUGH_AREA, id.to_string());
+            if let Some(ref name) = name {
+                osm_tags.insert(osm::NAME, format!("Path through {}", name));
+            }
+            map.roads.insert(
+                OriginalRoad {
+                    osm_way_id,
+                    i1: i,
+                    i2: center_id,
+                },
+                RawRoad {
+                    center_points: vec![map.intersections[&i].point, center],
+                    osm_tags,
+                    turn_restrictions: Vec::new(),
+                    complicated_turn_restrictions: Vec::new(),
+                },
+            );
+            num_paths += 1;
+        }
+        num_areas += 1;
+    }
+    timer.note(format!(
+        "Added {} paths through {} parks and plazas",
+        num_paths, num_areas
+    ));
+}
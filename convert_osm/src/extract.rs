@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use osm::{NodeID, OsmID, RelationID, WayID};
 
@@ -16,6 +16,8 @@ pub struct OsmExtract {
     pub roads: Vec<(WayID, RawRoad)>,
     /// Traffic signals to the direction they apply (or just true if unspecified)
     pub traffic_signals: HashMap<HashablePt2D, bool>,
+    /// Where roads and paths cross railroad tracks at the same level
+    pub rail_crossings: HashSet<HashablePt2D>,
     pub osm_node_ids: HashMap<HashablePt2D, NodeID>,
     /// (ID, restriction type, from way ID, via node ID, to way ID)
     pub simple_turn_restrictions: Vec<(RestrictionType, WayID, NodeID, WayID)>,
@@ -23,6 +25,8 @@ pub struct OsmExtract {
     pub complicated_turn_restrictions: Vec<(RelationID, WayID, WayID, WayID)>,
     /// (location, amenity)
     pub amenities: Vec<(Pt2D, Amenity)>,
+    /// Unclipped ferry routes
+    pub ferries: Vec<(WayID, Vec<Pt2D>, Tags)>,
 }
 
 pub fn extract_osm(map: &mut RawMap, opts: &Options, timer: &mut Timer) -> OsmExtract {
@@ -36,10 +40,12 @@ pub fn extract_osm(map: &mut RawMap, opts: &Options, timer: &mut Timer) -> OsmEx
     let mut out = OsmExtract {
         roads: Vec::new(),
         traffic_signals: HashMap::new(),
+        rail_crossings: HashSet::new(),
         osm_node_ids: HashMap::new(),
         simple_turn_restrictions: Vec::new(),
         complicated_turn_restrictions: Vec::new(),
         amenities: Vec::new(),
+        ferries: Vec::new(),
     };
 
     timer.start_iter("processing OSM nodes", doc.nodes.len());
@@ -52,6 +58,12 @@ pub fn extract_osm(map: &mut RawMap, opts: &Options, timer: &mut Timer) -> OsmEx
             out.traffic_signals
                 .insert(node.pt.to_hashable(), !backwards);
         }
+        if node
+            .tags
+            .is_any("railway", vec!["level_crossing", "crossing"])
+        {
+            out.rail_crossings.insert(node.pt.to_hashable());
+        }
         for amenity in get_bldg_amenities(&node.tags) {
             out.amenities.push((node.pt, amenity));
         }
@@ -60,15 +72,19 @@ pub fn extract_osm(map: &mut RawMap, opts: &Options, timer: &mut Timer) -> OsmEx
     // and cycleways
     let mut extra_footways = ExtraShapes { shapes: Vec::new() };
     let mut extra_service_roads = ExtraShapes { shapes: Vec::new() };
+    // Footways that might lead to stairs, bridges, or tunnels
+    let mut dropped_footways: Vec<(WayID, Vec<Pt2D>, Tags)> = Vec::new();
 
     let mut coastline_groups: Vec<(WayID, Vec<Pt2D>)> = Vec::new();
     let mut memorial_areas: Vec<Polygon> = Vec::new();
+    let fixes = osm::TagFixes::load(&opts.name.city, timer);
     timer.start_iter("processing OSM ways", doc.ways.len());
     for (id, way) in &mut doc.ways {
         timer.next();
         let id = *id;
 
         way.tags.insert(osm::OSM_WAY_ID, id.0.to_string());
+        fixes.apply(id, &mut way.tags);
 
         if is_road(&mut way.tags, opts) {
             // TODO Hardcoding these overrides. OSM is correct, these don't have
@@ -88,6 +104,9 @@ pub fn extract_osm(map: &mut RawMap, opts: &Options, timer: &mut Timer) -> OsmEx
                 },
             ));
             continue;
+        } else if way.tags.is("route", "ferry") {
+            out.ferries.push((id, way.pts.clone(), way.tags.clone()));
+            continue;
         } else if way.tags.is(osm::HIGHWAY, "service") {
             // If we got here, is_road didn't interpret it as a normal road
             map.parking_aisles.push((id, way.pts.clone()));
@@ -104,6 +123,13 @@ pub fn extract_osm(map: &mut RawMap, opts: &Options, timer: &mut Timer) -> OsmEx
                 points: map.gps_bounds.convert_back(&way.pts),
                 attributes: way.tags.inner().clone(),
             });
+            if opts.map_config.inferred_sidewalks
+                && !way.tags.is("foot", "no")
+                && (way.tags.is(osm::HIGHWAY, "footway")
+                    || way.tags.is_any("foot", vec!["yes", "designated"]))
+            {
+                dropped_footways.push((id, way.pts.clone(), way.tags.clone()));
+            }
         } else if way.tags.is("natural", "coastline") && !way.tags.is("place", "island") {
             coastline_groups.push((id, way.pts.clone()));
             continue;
@@ -147,6 +173,8 @@ pub fn extract_osm(map: &mut RawMap, opts: &Options, timer: &mut Timer) -> OsmEx
         }
     }
 
+    crate::walking_links::add_approaches(&mut out.roads, dropped_footways, timer);
+
     if map.name.city != "oneshot"
         && ((map.name.city == "seattle" && map.name.map == "huge_seattle")
             || map.name.city != "seattle")
@@ -434,11 +462,19 @@ fn is_road(tags: &mut Tags, opts: &Options) -> bool {
         return false;
     }
 
-    if (highway == "cycleway" || highway == "footway" || highway == "path" || highway == "steps")
+    let walking_link = if (highway == "cycleway"
+        || highway == "footway"
+        || highway == "path"
+        || highway == "steps")
         && opts.map_config.inferred_sidewalks
     {
-        return false;
-    }
+        if !crate::walking_links::is_walking_link(tags) {
+            return false;
+        }
+        true
+    } else {
+        false
+    };
     if (highway == "cycleway" || highway == "path")
         && !tags.is_any("foot", vec!["yes", "designated"])
     {
@@ -461,6 +497,12 @@ fn is_road(tags: &mut Tags, opts: &Options) -> bool {
 
     // It's a road! Now fill in some possibly missing data.
 
+    if walking_link {
+        // Just a sidewalk; there's no parking or other sidewalks to infer
+        tags.insert(osm::WALKING_LINK, "true");
+        return true;
+    }
+
     // If there's no parking data in OSM already, then assume no parking and mark that it's
     // inferred.
     if !tags.contains_key(osm::PARKING_LEFT)
@@ -530,6 +572,11 @@ fn get_area_type(tags: &Tags) -> Option<AreaType> {
         return Some(AreaType::Park);
     }
 
+    if tags.is("place", "square") || (tags.is(osm::HIGHWAY, "pedestrian") && tags.is("area", "yes"))
+    {
+        return Some(AreaType::PedestrianPlaza);
+    }
+
     if tags.is("natural", "water") || tags.is("waterway", "riverbank") {
         return Some(AreaType::Water);
     }
@@ -0,0 +1,98 @@
+use abstutil::{Counter, Tags, Timer};
+use geom::{Distance, Duration, PolyLine, Pt2D, Speed};
+use map_model::osm::{self, WayID};
+use map_model::raw::{RawFerryRoute, RawMap};
+use map_model::IntersectionType;
+
+// How far can a terminal be from where the ferry route ends?
+const MAX_DIST_TO_TERMINAL: Distance = Distance::const_meters(100.0);
+
+/// Ferry routes become border-like terminals at the ends of the road network. The route itself
+/// isn't drawn or pathfound over; agents just leave the map at one terminal and, if the other end
+/// is also on the map, appear there later.
+pub fn add_ferry_routes(
+    map: &mut RawMap,
+    ferries: Vec<(WayID, Vec<Pt2D>, Tags)>,
+    timer: &mut Timer,
+) {
+    let mut degree = Counter::new();
+    for id in map.roads.keys() {
+        degree.inc(id.i1);
+        degree.inc(id.i2);
+    }
+
+    for (id, pts, tags) in ferries {
+        let mut terminals = Vec::new();
+        for pt in [pts[0], *pts.last().unwrap()].iter().cloned() {
+            if !map.boundary_polygon.contains_pt(pt) {
+                continue;
+            }
+            // Boarding happens at a dead-end, so the terminal can act like a border
+            if let Some(i) = map
+                .intersections
+                .iter()
+                .filter(|(i, _)| degree.get(**i) == 1)
+                .map(|(i, intersection)| (*i, intersection.point.dist_to(pt)))
+                .filter(|(_, dist)| *dist <= MAX_DIST_TO_TERMINAL)
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                .map(|(i, _)| i)
+            {
+                if !terminals.contains(&i) {
+                    terminals.push(i);
+                }
+            }
+        }
+        if terminals.is_empty() {
+            timer.warn(format!("Ferry {} doesn't reach a road on the map", id));
+            continue;
+        }
+        for i in &terminals {
+            map.intersections.get_mut(i).unwrap().intersection_type = IntersectionType::Border;
+        }
+
+        let crossing_time = tags
+            .get("duration")
+            .and_then(|x| parse_duration(x))
+            .unwrap_or_else(|| {
+                // If it's unknown, assume a slow ferry
+                let length = PolyLine::unchecked_new(pts.clone()).length();
+                length / Speed::miles_per_hour(10.0)
+            });
+        let headway = tags
+            .get("interval")
+            .and_then(|x| parse_duration(x))
+            .filter(|x| *x > Duration::ZERO)
+            .unwrap_or_else(|| Duration::minutes(30));
+        map.ferry_routes.push(RawFerryRoute {
+            name: tags
+                .get(osm::NAME)
+                .cloned()
+                .unwrap_or_else(|| format!("Ferry {}", id)),
+            osm_way_id: id,
+            terminals,
+            crossing_time,
+            headway,
+            allows_vehicles: tags.is_any("motor_vehicle", vec!["yes", "designated"])
+                || tags.is("motorcar", "yes"),
+        });
+    }
+    timer.note(format!("Found {} ferry routes", map.ferry_routes.len()));
+}
+
+/// OSM durations are "mm", "hh:mm", or "hh:mm:ss".
+fn parse_duration(x: &str) -> Option<Duration> {
+    let parts: Vec<f64> = x
+        .split(':')
+        .map(|part| part.trim().parse::<f64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    match parts.len() {
+        1 => Some(Duration::minutes(1) * parts[0]),
+        2 => Some(Duration::hours(1) * parts[0] + Duration::minutes(1) * parts[1]),
+        3 => Some(
+            Duration::hours(1) * parts[0]
+                + Duration::minutes(1) * parts[1]
+                + Duration::seconds(parts[2]),
+        ),
+        _ => None,
+    }
+}
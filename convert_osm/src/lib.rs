@@ -7,8 +7,10 @@ use map_model::raw::RawMap;
 use map_model::{osm, Amenity, MapConfig};
 use serde::{Deserialize, Serialize};
 
+mod area_paths;
 mod clip;
 mod extract;
+mod ferries;
 pub mod osm_geom;
 mod parking;
 pub mod reader;
@@ -16,6 +18,7 @@ mod snappy;
 mod split_ways;
 mod srtm;
 mod transit;
+mod walking_links;
 
 pub struct Options {
     pub osm_input: String,
@@ -33,6 +36,18 @@ pub struct Options {
     pub elevation: Option<String>,
     /// OSM railway=rail will be included as light rail if so. Cosmetic only.
     pub include_railroads: bool,
+    pub paths_through_areas: PathsThroughAreas,
+}
+
+/// Which parks and plazas can pedestrians cut straight across? Only areas without any paths mapped
+/// inside get new ones.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum PathsThroughAreas {
+    None,
+    /// Every park and pedestrian plaza
+    ParksAndPlazas,
+    /// Just these areas
+    Only(Vec<osm::OsmID>),
 }
 
 /// What roads will have on-street parking lanes? Data from
@@ -67,7 +82,14 @@ pub enum PublicOffstreetParking {
 #[derive(Clone, Serialize, Deserialize)]
 pub enum PrivateOffstreetParking {
     FixedPerBldg(usize),
+    /// Guess from the building's OSM tags. Houses have a garage or driveway, detached garages and
+    /// buildings tagged with underground or multi-storey parking have spots in proportion to
+    /// their size, and shops, offices, sheds, and the like have none. Buildings that don't fall
+    /// into any of these get `default` spots.
     // TODO Based on the number of residents?
+    FromTags {
+        default: usize,
+    },
 }
 
 pub fn convert(opts: Options, timer: &mut abstutil::Timer) -> RawMap {
@@ -79,13 +101,16 @@ pub fn convert(opts: Options, timer: &mut abstutil::Timer) -> RawMap {
         map.gps_bounds = gps_bounds;
     }
 
-    let extract = extract::extract_osm(&mut map, &opts, timer);
+    let mut extract = extract::extract_osm(&mut map, &opts, timer);
+    let ferries = std::mem::take(&mut extract.ferries);
     let (amenities, pt_to_road) = split_ways::split_up_roads(&mut map, extract, timer);
     clip::clip_map(&mut map, timer);
 
     // Need to do a first pass of removing cul-de-sacs here, or we wind up with loop PolyLines when
     // doing the parking hint matching.
     abstutil::retain_btreemap(&mut map.roads, |r, _| r.i1 != r.i2);
+    walking_links::remove_dead_ends(&mut map, timer);
+    ferries::add_ferry_routes(&mut map, ferries, timer);
 
     let all_routes = map.bus_routes.drain(..).collect::<Vec<_>>();
     let mut routes = Vec::new();
@@ -112,6 +137,8 @@ pub fn convert(opts: Options, timer: &mut abstutil::Timer) -> RawMap {
 
     snappy::snap_cycleways(&mut map, timer);
 
+    area_paths::add_paths_through_areas(&mut map, &opts.paths_through_areas, timer);
+
     map.config = opts.map_config;
     map
 }
@@ -1,4 +1,4 @@
-use abstutil::Timer;
+use abstutil::{Tags, Timer};
 use geom::{Distance, FindClosest, PolyLine};
 use kml::ExtraShapes;
 use map_model::osm;
@@ -191,30 +191,86 @@ fn use_offstreet_parking(map: &mut RawMap, path: String, timer: &mut Timer) {
 }
 
 fn apply_private_offstreet_parking(map: &mut RawMap, policy: &PrivateOffstreetParking) {
-    match policy {
-        PrivateOffstreetParking::FixedPerBldg(n) => {
-            for b in map.buildings.values_mut() {
-                if b.public_garage_name.is_none() {
-                    assert_eq!(b.num_parking_spots, 0);
-
-                    // Is it a parking garage?
-                    if b.osm_tags.is("building", "parking") || b.osm_tags.is("amenity", "parking") {
-                        let levels = b
-                            .osm_tags
-                            .get("parking:levels")
-                            .or_else(|| b.osm_tags.get("building:levels"))
-                            .and_then(|x| x.parse::<usize>().ok())
-                            .unwrap_or(1);
-                        // For multi-story garages, assume every floor has the same capacity. Guess
-                        // 1 spot per 30m^2.
-                        b.num_parking_spots = ((b.polygon.area() / 30.0) as usize) * levels;
-                        // Not useful to list this
-                        b.amenities.retain(|a| a.amenity_type != "parking");
-                    } else {
-                        b.num_parking_spots = *n;
-                    }
-                }
-            }
+    for b in map.buildings.values_mut() {
+        if b.public_garage_name.is_some() {
+            continue;
         }
+        assert_eq!(b.num_parking_spots, 0);
+
+        // Is it a parking garage?
+        if b.osm_tags.is("building", "parking") || b.osm_tags.is("amenity", "parking") {
+            let levels = b
+                .osm_tags
+                .get("parking:levels")
+                .or_else(|| b.osm_tags.get("building:levels"))
+                .and_then(|x| x.parse::<usize>().ok())
+                .unwrap_or(1);
+            // For multi-story garages, assume every floor has the same capacity. Guess 1 spot per
+            // 30m^2.
+            b.num_parking_spots = ((b.polygon.area() / 30.0) as usize) * levels;
+            // Not useful to list this
+            b.amenities.retain(|a| a.amenity_type != "parking");
+            continue;
+        }
+
+        b.num_parking_spots = match policy {
+            PrivateOffstreetParking::FixedPerBldg(n) => *n,
+            PrivateOffstreetParking::FromTags { default } => {
+                estimate_private_spots(&b.osm_tags, b.polygon.area(), *default)
+            }
+        };
+    }
+}
+
+fn estimate_private_spots(tags: &Tags, area: f64, default: usize) -> usize {
+    // Parking inside or under some other kind of building. Same guess as for garages.
+    if tags.is_any("parking", vec!["underground", "multi-storey", "rooftop"])
+        || tags.contains_key("parking:levels")
+    {
+        let levels = tags
+            .get("parking:levels")
+            .and_then(|x| x.parse::<usize>().ok())
+            .unwrap_or(1);
+        return ((area / 30.0) as usize).max(1) * levels;
+    }
+    // A detached garage or carport. Guess 1 spot per 15m^2.
+    if tags.is_any("building", vec!["garage", "garages", "carport"]) {
+        return ((area / 15.0) as usize).max(1);
+    }
+    // A garage or driveway
+    if tags.is_any(
+        "building",
+        vec![
+            "house",
+            "detached",
+            "semidetached_house",
+            "bungalow",
+            "farm",
+        ],
+    ) {
+        return 2;
+    }
+    // Rowhouses, businesses, and small structures usually rely on the street or a public lot
+    if tags.is_any(
+        "building",
+        vec![
+            "terrace",
+            "retail",
+            "commercial",
+            "office",
+            "kiosk",
+            "supermarket",
+            "church",
+            "shed",
+            "hut",
+            "roof",
+            "greenhouse",
+            "service",
+            "construction",
+            "ruins",
+        ],
+    ) {
+        return 0;
     }
+    default
 }
@@ -23,8 +23,13 @@ pub fn split_up_roads(
             let pt = raw_pt.to_hashable();
             let count = counts_per_pt.inc(pt);
 
-            // All start and endpoints of ways are also intersections.
-            if count == 2 || idx == 0 || idx == r.center_points.len() - 1 {
+            // All start and endpoints of ways are also intersections. So are rail crossings, so
+            // they can close when a train passes.
+            if count == 2
+                || idx == 0
+                || idx == r.center_points.len() - 1
+                || input.rail_crossings.contains(&pt)
+            {
                 if !pt_to_intersection.contains_key(&pt) {
                     let id = input.osm_node_ids[&pt];
                     pt_to_intersection.insert(pt, id);
@@ -45,6 +50,7 @@ pub fn split_up_roads(
                 },
                 // Filled out later
                 elevation: Distance::ZERO,
+                rail_crossing: input.rail_crossings.contains(pt),
             },
         );
     }
@@ -0,0 +1,114 @@
+use std::collections::{BTreeSet, HashSet};
+
+use abstutil::{Counter, Tags, Timer};
+use geom::{HashablePt2D, Pt2D};
+use map_model::osm::{self, WayID};
+use map_model::raw::{RawMap, RawRoad};
+
+// How many ordinary footways can lead from a walking link to the nearest road?
+const MAX_APPROACH_HOPS: usize = 3;
+
+/// When sidewalks are inferred along roads, footways are usually dropped, since most of them just
+/// duplicate those sidewalks. But stairs, footbridges, and underpasses often connect places that
+/// are far apart along roads -- across a highway, river, or hill -- so keep them.
+pub fn is_walking_link(tags: &Tags) -> bool {
+    tags.is(osm::HIGHWAY, "steps")
+        || tags.get("bridge").map(|x| x != "no").unwrap_or(false)
+        || tags.get("tunnel").map(|x| x != "no").unwrap_or(false)
+}
+
+/// Bridges and stairs usually don't touch a road directly; a few short footways lead to them.
+/// Starting from the walking links already in `roads`, keep any of the otherwise dropped
+/// `footways` within a few hops of them.
+pub fn add_approaches(
+    roads: &mut Vec<(WayID, RawRoad)>,
+    mut footways: Vec<(WayID, Vec<Pt2D>, Tags)>,
+    timer: &mut Timer,
+) {
+    let mut frontier: HashSet<HashablePt2D> = roads
+        .iter()
+        .filter(|(_, r)| r.osm_tags.contains_key(osm::WALKING_LINK))
+        .flat_map(|(_, r)| r.center_points.iter().map(|pt| pt.to_hashable()))
+        .collect();
+
+    let mut num_approaches = 0;
+    for _ in 0..MAX_APPROACH_HOPS {
+        let (touching, rest): (Vec<_>, Vec<_>) = footways
+            .into_iter()
+            .partition(|(_, pts, _)| pts.iter().any(|pt| frontier.contains(&pt.to_hashable())));
+        footways = rest;
+        if touching.is_empty() {
+            break;
+        }
+
+        frontier.clear();
+        for (id, pts, mut osm_tags) in touching {
+            frontier.extend(pts.iter().map(|pt| pt.to_hashable()));
+            osm_tags.insert(osm::WALKING_LINK, "true");
+            roads.push((
+                id,
+                RawRoad {
+                    center_points: pts,
+                    osm_tags,
+                    turn_restrictions: Vec::new(),
+                    complicated_turn_restrictions: Vec::new(),
+                },
+            ));
+            num_approaches += 1;
+        }
+    }
+    timer.note(format!(
+        "Kept {} footways leading to stairs, bridges, and tunnels",
+        num_approaches
+    ));
+}
+
+/// Walking links that don't wind up connecting to anything just clutter the map, so repeatedly
+/// remove the ones with a dead-end.
+pub fn remove_dead_ends(map: &mut RawMap, timer: &mut Timer) {
+    let mut num_removed = 0;
+    let mut endpts = BTreeSet::new();
+    loop {
+        let mut degree = Counter::new();
+        for id in map.roads.keys() {
+            degree.inc(id.i1);
+            degree.inc(id.i2);
+        }
+        let dead_ends: Vec<_> = map
+            .roads
+            .iter()
+            .filter(|(id, r)| {
+                r.osm_tags.contains_key(osm::WALKING_LINK)
+                    && (degree.get(id.i1) == 1 || degree.get(id.i2) == 1)
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        if dead_ends.is_empty() {
+            break;
+        }
+        for id in dead_ends {
+            map.roads.remove(&id).unwrap();
+            endpts.insert(id.i1);
+            endpts.insert(id.i2);
+            num_removed += 1;
+        }
+    }
+
+    for i in endpts {
+        if map.can_delete_intersection(i) {
+            map.delete_intersection(i);
+        }
+    }
+
+    let remaining: BTreeSet<WayID> = map
+        .roads
+        .iter()
+        .filter(|(_, r)| r.osm_tags.contains_key(osm::WALKING_LINK))
+        .map(|(id, _)| id.osm_way_id)
+        .collect();
+    timer.note(format!(
+        "Removed {} dead-end pieces of walking links, {} ways remain",
+        num_removed,
+        remaining.len()
+    ));
+}
@@ -10,7 +10,7 @@ use map_gui::colors::ColorScheme;
 use map_gui::options::Options;
 use map_gui::render::{unzoomed_agent_radius, AgentCache, DrawMap, DrawOptions, Renderable};
 use map_model::{IntersectionID, Map, Traversable};
-use sim::{AgentID, Analytics, Scenario, Sim, SimCallback, SimFlags};
+use sim::{AgentID, Analytics, BikeShareUsage, Scenario, Sim, SimCallback, SimFlags};
 use widgetry::{Canvas, EventCtx, GfxCtx, Prerender, SharedAppState, State};
 
 use crate::challenges::HighScore;
@@ -73,6 +73,10 @@ impl App {
                     "- road_thruput: {} bytes",
                     prettyprint_usize(serialized_size_bytes(&a.road_thruput))
                 );
+                println!(
+                    "- directed_road_thruput: {} bytes",
+                    prettyprint_usize(serialized_size_bytes(&a.directed_road_thruput))
+                );
                 println!(
                     "- intersection_thruput: {} bytes",
                     prettyprint_usize(serialized_size_bytes(&a.intersection_thruput))
@@ -113,6 +117,14 @@ impl App {
                     "- intersection_delays: {} bytes",
                     prettyprint_usize(serialized_size_bytes(&a.intersection_delays))
                 );
+                println!(
+                    "- bridge_delays: {} bytes",
+                    prettyprint_usize(serialized_size_bytes(&a.bridge_delays))
+                );
+                println!(
+                    "- spillbacks: {} bytes",
+                    prettyprint_usize(serialized_size_bytes(&a.spillbacks))
+                );
                 println!(
                     "- parking_lane_changes: {} bytes",
                     prettyprint_usize(serialized_size_bytes(&a.parking_lane_changes))
@@ -132,6 +144,7 @@ impl App {
         let mut sample_intersection: Option<String> = None;
 
         g.clear(self.cs.void_background);
+        g.set_render_layer("map");
         g.redraw(&draw_map.boundary_polygon);
 
         if g.canvas.cam_zoom < self.opts.min_zoom_for_detail && !g.is_screencap() {
@@ -169,6 +182,7 @@ impl App {
                 g.draw_polygon(self.cs.selected, map.get_b(id).polygon.clone());
             }
 
+            g.set_render_layer("agents");
             let mut cache = self.primary.agents.borrow_mut();
             cache.draw_unzoomed_agents(g, self);
 
@@ -200,6 +214,11 @@ impl App {
             let mut drawn_all_areas = false;
 
             for obj in objects {
+                g.set_render_layer(if obj.get_id().agent_id().is_some() {
+                    "agents"
+                } else {
+                    "map"
+                });
                 obj.draw(g, self, &opts);
 
                 match obj.get_id() {
@@ -234,6 +253,8 @@ impl App {
             }
         }
 
+        g.set_render_layer("other");
+
         if let Some(i) = sample_intersection {
             g.set_screencap_naming_hint(i);
         }
@@ -287,7 +308,7 @@ impl App {
         for obj in objects {
             match obj.get_id() {
                 ID::Area(_) => {
-                    if !debug_mode {
+                    if !debug_mode && ctx.canvas.cam_zoom < self.opts.min_zoom_for_detail {
                         continue;
                     }
                 }
@@ -396,6 +417,13 @@ impl App {
             for on in &agents_on {
                 agents.populate_if_needed(*on, map, &self.primary.sim, &self.cs, prerender);
             }
+            // With too many agents on screen, switch to simpler shapes
+            let num_visible = agents_on.iter().map(|on| agents.get(*on).len()).sum();
+            if agents.set_num_visible(num_visible) {
+                for on in &agents_on {
+                    agents.populate_if_needed(*on, map, &self.primary.sim, &self.cs, prerender);
+                }
+            }
         }
 
         for on in agents_on {
@@ -568,6 +596,8 @@ pub struct PerMap {
     pub dirty_from_edits: bool,
     /// Any ScenarioModifiers in effect?
     pub has_modified_trips: bool,
+    /// Only filled out if a ScenarioModifier added a bike share system.
+    pub bike_share: Option<BikeShareUsage>,
 
     /// Sometimes we need the map before any edits have been applied. Cache it here.
     pub unedited_map: RefCell<Option<Map>>,
@@ -613,6 +643,7 @@ impl PerMap {
             sim_cb: None,
             dirty_from_edits: false,
             has_modified_trips: false,
+            bike_share: None,
             unedited_map: RefCell::new(None),
             layer: None,
             suspended_sim: None,
@@ -0,0 +1,346 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use maplit::hashset;
+
+use abstutil::{Counter, MultiMap};
+use geom::{ArrowCap, Distance, PolyLine, Polygon};
+use map_model::{osm, AreaID, AreaType, BuildingID, IntersectionID, LaneID, Map, RoadID, TurnType};
+use sim::TripEndpoint;
+use widgetry::{Color, GeomBatch};
+
+use crate::app::App;
+
+/// Many buildings grouped into a single neighborhood "block", a park, or a single border.
+pub struct Block {
+    pub id: BlockID,
+    // A block is either some buildings, a park with any buildings inside of it, or a single
+    // border. Might be worth expressing that more clearly.
+    pub bldgs: HashSet<BuildingID>,
+    pub borders: HashSet<IntersectionID>,
+    pub area: Option<AreaID>,
+    pub shape: Polygon,
+}
+
+pub type BlockID = usize;
+
+/// Every building and border in the map, grouped into blocks.
+pub struct Blocks {
+    pub bldg_to_block: HashMap<BuildingID, BlockID>,
+    pub border_to_block: HashMap<IntersectionID, BlockID>,
+    pub blocks: Vec<Block>,
+}
+
+impl Blocks {
+    pub fn new(app: &App) -> Blocks {
+        let (bldg_to_block, border_to_block, blocks) = group_bldgs(app);
+        Blocks {
+            bldg_to_block,
+            border_to_block,
+            blocks,
+        }
+    }
+
+    /// Which block does a trip start or end in?
+    pub fn lookup(&self, endpt: &TripEndpoint) -> Option<BlockID> {
+        match endpt {
+            TripEndpoint::Bldg(b) => self.bldg_to_block.get(b).cloned(),
+            TripEndpoint::Border(i) => self.border_to_block.get(i).cloned(),
+            TripEndpoint::SuddenlyAppear(_) => None,
+        }
+    }
+
+    /// Draws an arrow between the centers of two blocks for every pair of blocks with some trips
+    /// between them, scaled by the number of trips. Trips within the same block aren't drawn.
+    pub fn draw_desire_lines(
+        &self,
+        counts: &Counter<(BlockID, BlockID)>,
+        color: Color,
+    ) -> GeomBatch {
+        let mut batch = GeomBatch::new();
+        // Draw the biggest flows last, so they're on top
+        let mut flows: Vec<(&(BlockID, BlockID), &usize)> = counts
+            .borrow()
+            .iter()
+            .filter(|((from, to), _)| from != to)
+            .collect();
+        flows.sort_by_key(|(_, cnt)| **cnt);
+        let max = flows.last().map(|(_, cnt)| **cnt).unwrap_or(0);
+        for ((from, to), cnt) in flows {
+            let pct = (*cnt as f64) / (max as f64);
+            let thickness = Distance::meters(2.0) + Distance::meters(28.0) * pct;
+            if let Ok(pl) = PolyLine::new(vec![
+                self.blocks[*from].shape.polylabel(),
+                self.blocks[*to].shape.polylabel(),
+            ]) {
+                batch.push(
+                    color.alpha(0.3 + 0.5 * pct as f32),
+                    pl.make_arrow(thickness, ArrowCap::Triangle),
+                );
+            }
+        }
+        batch
+    }
+}
+
+// This tries to group buildings into neighborhood "blocks". Much of the time, that's a smallish
+// region bounded by 4 roads. But there are plenty of places with stranger shapes, or buildings
+// near the border of the map. The fallback is currently to just group those buildings that share
+// the same sidewalk.
+fn group_bldgs(
+    app: &App,
+) -> (
+    HashMap<BuildingID, BlockID>,
+    HashMap<IntersectionID, BlockID>,
+    Vec<Block>,
+) {
+    let map = &app.primary.map;
+    let mut bldg_to_block = HashMap::new();
+    let mut blocks = Vec::new();
+
+    // Snap blocks to parks, so they don't get split up between the surrounding blocks. Buildings
+    // inside a park belong to it.
+    for a in map.all_areas() {
+        if a.area_type == AreaType::Park {
+            blocks.push(Block {
+                id: blocks.len(),
+                bldgs: HashSet::new(),
+                borders: HashSet::new(),
+                area: Some(a.id),
+                shape: a.polygon.clone(),
+            });
+        }
+    }
+    for b in map.all_buildings() {
+        let center = b.polygon.center();
+        if let Some(block) = blocks.iter_mut().find(|x| x.shape.contains_pt(center)) {
+            block.bldgs.insert(b.id);
+            bldg_to_block.insert(b.id, block.id);
+        }
+    }
+
+    for mut group in partition_sidewalk_loops(app) {
+        group.bldgs.retain(|b| !bldg_to_block.contains_key(b));
+        if group.bldgs.is_empty() {
+            continue;
+        }
+        let block_id = blocks.len();
+        let mut polygons = Vec::new();
+        let mut lanes = HashSet::new();
+        for b in &group.bldgs {
+            bldg_to_block.insert(*b, block_id);
+            let bldg = map.get_b(*b);
+            if group.proper {
+                lanes.insert(bldg.sidewalk());
+            } else {
+                polygons.push(bldg.polygon.clone());
+            }
+        }
+        if group.proper {
+            // TODO Even better, glue the loop of sidewalks together and fill that area.
+            for l in lanes {
+                polygons.push(app.primary.draw_map.get_l(l).polygon.clone());
+            }
+        }
+        blocks.push(Block {
+            id: block_id,
+            bldgs: group.bldgs,
+            borders: HashSet::new(),
+            area: None,
+            shape: Polygon::convex_hull(polygons),
+        });
+    }
+
+    let mut border_to_block = HashMap::new();
+    for i in map.all_incoming_borders() {
+        let id = blocks.len();
+        border_to_block.insert(i.id, id);
+        blocks.push(Block {
+            id,
+            bldgs: HashSet::new(),
+            borders: hashset! { i.id },
+            area: None,
+            shape: build_shape_for_border(i, BorderType::Incoming, map),
+        });
+    }
+    for i in map.all_outgoing_borders() {
+        if let Some(incoming_border_id) = border_to_block.get(&i.id) {
+            let two_way_border = &mut blocks[*incoming_border_id];
+            two_way_border.shape = build_shape_for_border(i, BorderType::Both, map);
+            continue;
+        }
+        let id = blocks.len();
+        border_to_block.insert(i.id, id);
+
+        blocks.push(Block {
+            id,
+            bldgs: HashSet::new(),
+            borders: hashset! { i.id },
+            area: None,
+            shape: build_shape_for_border(i, BorderType::Outgoing, map),
+        });
+    }
+
+    (bldg_to_block, border_to_block, blocks)
+}
+
+enum BorderType {
+    Incoming,
+    Outgoing,
+    Both,
+}
+
+fn build_shape_for_border(
+    border: &map_model::Intersection,
+    border_type: BorderType,
+    map: &Map,
+) -> Polygon {
+    let start = border.polygon.center();
+
+    let road = map.get_r(*border.roads.iter().next().unwrap());
+    let center_line = road.get_dir_change_pl(map);
+    let angle = if road.src_i == border.id {
+        center_line.first_line().angle().opposite()
+    } else {
+        center_line.first_line().angle()
+    };
+
+    let length = Distance::meters(150.0);
+    let thickness = Distance::meters(30.0);
+    let end = start.project_away(length, angle);
+
+    match border_type {
+        BorderType::Incoming => {
+            PolyLine::must_new(vec![end, start]).make_arrow(thickness, geom::ArrowCap::Triangle)
+        }
+        BorderType::Outgoing => {
+            PolyLine::must_new(vec![start, end]).make_arrow(thickness, geom::ArrowCap::Triangle)
+        }
+        BorderType::Both => PolyLine::must_new(vec![start, end])
+            .make_double_arrow(thickness, geom::ArrowCap::Triangle),
+    }
+}
+
+struct Loop {
+    bldgs: HashSet<BuildingID>,
+    // True if it's a "proper" block, false if it's a hack.
+    proper: bool,
+    roads: HashSet<RoadID>,
+}
+
+fn partition_sidewalk_loops(app: &App) -> Vec<Loop> {
+    let map = &app.primary.map;
+
+    let mut groups = Vec::new();
+    let mut todo_bldgs: BTreeSet<BuildingID> = map.all_buildings().iter().map(|b| b.id).collect();
+    let mut remainder = HashSet::new();
+
+    let mut sidewalk_to_bldgs = MultiMap::new();
+    for b in map.all_buildings() {
+        sidewalk_to_bldgs.insert(b.sidewalk(), b.id);
+    }
+
+    while !todo_bldgs.is_empty() {
+        let mut sidewalks = HashSet::new();
+        let mut bldgs = HashSet::new();
+        let mut current_l = map.get_b(*todo_bldgs.iter().next().unwrap()).sidewalk();
+        let mut current_i = map.get_l(current_l).src_i;
+
+        let ok = loop {
+            sidewalks.insert(current_l);
+            for b in sidewalk_to_bldgs.get(current_l) {
+                bldgs.insert(*b);
+                // TODO I wanted to assert that we haven't assigned this one yet, but...
+                todo_bldgs.remove(b);
+            }
+
+            // Chase SharedSidewalkCorners. There should be zero or one new options for corners.
+            let turns = map
+                .get_turns_from_lane(current_l)
+                .into_iter()
+                .filter(|t| {
+                    t.turn_type == TurnType::SharedSidewalkCorner && t.id.parent != current_i
+                })
+                .collect::<Vec<_>>();
+            if turns.is_empty() {
+                // TODO If we're not a loop, maybe toss this out. It's arbitrary that we didn't go
+                // look the other way.
+                break false;
+            } else if turns.len() == 1 {
+                current_l = turns[0].id.dst;
+                current_i = turns[0].id.parent;
+                if sidewalks.contains(&current_l) {
+                    // Loop closed!
+                    break true;
+                }
+            } else {
+                panic!(
+                    "Too many SharedSidewalkCorners from ({}, {})",
+                    current_l, current_i
+                );
+            };
+        };
+
+        if ok {
+            groups.push(Loop {
+                bldgs,
+                proper: true,
+                roads: sidewalks.into_iter().map(|l| map.get_l(l).parent).collect(),
+            });
+        } else {
+            remainder.extend(bldgs);
+        }
+    }
+
+    // Merge adjacent residential blocks
+    loop {
+        // Find a pair of blocks that have at least one residential road in common.
+        let mut any = false;
+        for mut idx1 in 0..groups.len() {
+            for mut idx2 in 0..groups.len() {
+                // This is O(n^3) on original groups.len(). In practice, it's fine, as long as we
+                // don't start the search over from scratch after making a single merge. Starting
+                // over is really wasteful, because it's guaranteed that nothing there has changed.
+                if idx1 >= groups.len() || idx2 >= groups.len() {
+                    break;
+                }
+
+                if idx1 != idx2
+                    && groups[idx1]
+                        .roads
+                        .intersection(&groups[idx2].roads)
+                        .any(|r| map.get_r(*r).get_rank() == osm::RoadRank::Local)
+                {
+                    // Indexing gets messed up, so remove the larger one
+                    if idx1 > idx2 {
+                        std::mem::swap(&mut idx1, &mut idx2);
+                    }
+                    let merge = groups.remove(idx2);
+                    groups[idx1].bldgs.extend(merge.bldgs);
+                    groups[idx1].roads.extend(merge.roads);
+                    any = true;
+                }
+            }
+        }
+        if !any {
+            break;
+        }
+    }
+
+    // For all the weird remainders, just group them based on sidewalk.
+    let mut per_sidewalk: MultiMap<LaneID, BuildingID> = MultiMap::new();
+    for b in remainder {
+        per_sidewalk.insert(map.get_b(b).sidewalk(), b);
+    }
+    for (_, bldgs) in per_sidewalk.consume() {
+        let r = map
+            .get_l(map.get_b(*bldgs.iter().next().unwrap()).sidewalk())
+            .parent;
+        groups.push(Loop {
+            bldgs: bldgs.into_iter().collect(),
+            proper: false,
+            roads: hashset! { r },
+        });
+    }
+
+    groups
+}
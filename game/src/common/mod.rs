@@ -7,6 +7,7 @@ use widgetry::{
     ScreenDims, ScreenPt, ScreenRectangle, Text, VerticalAlignment, Widget,
 };
 
+pub use self::blocks::{Block, BlockID, Blocks};
 pub use self::minimap::Minimap;
 pub use self::warp::Warping;
 use crate::app::App;
@@ -15,6 +16,7 @@ use crate::helpers::{list_names, ID};
 use crate::info::InfoPanel;
 pub use crate::info::{ContextualActions, Tab};
 
+mod blocks;
 mod minimap;
 mod warp;
 
@@ -0,0 +1,318 @@
+use geom::{Duration, Pt2D, Time};
+use map_gui::tools::PopupMsg;
+use sim::{AgentID, CarStatus, PedestrianID, PersonID, PersonState, TripID, TripResult};
+use widgetry::{
+    Btn, Checkbox, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, State, Text,
+    TextExt, UpdateType, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::CommonState;
+
+/// Look up any agent, trip, or person by number and dump everything the simulation knows about
+/// them, even if they're not on screen or not on the map at all.
+pub struct Inspector {
+    panel: Panel,
+    target: Option<Target>,
+    // When the state was last described
+    time: Time,
+}
+
+#[derive(Clone, Copy)]
+enum Target {
+    Agent(AgentID),
+    Trip(TripID),
+    Person(PersonID),
+}
+
+impl Inspector {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        Box::new(Inspector {
+            panel: Panel::new(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Inspect simulation state").small_heading().draw(ctx),
+                    Btn::close(ctx),
+                ]),
+                "Example: c42 is Car #42. Also p for pedestrian, t for trip, P for person"
+                    .draw_text(ctx),
+                Widget::row(vec![
+                    Widget::text_entry(ctx, String::new(), true).named("input"),
+                    Btn::text_fg("Inspect").build_def(ctx, Key::Enter),
+                ]),
+                Checkbox::switch(ctx, "follow (run the simulation)", Key::F, false),
+                Text::new().draw(ctx).named("state"),
+            ]))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .build(ctx),
+            target: None,
+            time: app.primary.sim.time(),
+        })
+    }
+
+    fn parse(app: &App, line: &str) -> Option<Target> {
+        let idx = line.get(1..)?.parse::<usize>().ok()?;
+        match line.chars().next()? {
+            'c' => Some(Target::Agent(AgentID::Car(
+                app.primary.sim.lookup_car_id(idx)?,
+            ))),
+            'p' => Some(Target::Agent(AgentID::Pedestrian(PedestrianID(idx)))),
+            't' => {
+                let (finished, unfinished) = app.primary.sim.num_trips();
+                if idx < finished + unfinished {
+                    Some(Target::Trip(TripID(idx)))
+                } else {
+                    None
+                }
+            }
+            'P' => {
+                let person = PersonID(idx);
+                app.primary.sim.lookup_person(person)?;
+                Some(Target::Person(person))
+            }
+            _ => None,
+        }
+    }
+
+    fn describe(&mut self, ctx: &mut EventCtx, app: &App) {
+        self.time = app.primary.sim.time();
+        let mut txt = Text::new();
+        if let Some(target) = self.target {
+            for line in describe(app, target) {
+                txt.add(Line(line));
+            }
+        }
+        self.panel.replace(
+            ctx,
+            "state",
+            txt.wrap_to_pct(ctx, 30).draw(ctx).named("state"),
+        );
+    }
+
+    fn position(&self, app: &App) -> Option<Pt2D> {
+        let sim = &app.primary.sim;
+        let map = &app.primary.map;
+        match self.target? {
+            Target::Agent(id) => sim.canonical_pt_for_agent(id, map),
+            Target::Trip(id) => sim.get_canonical_pt_per_trip(id, map).ok(),
+            Target::Person(id) => sim.get_canonical_pt_per_person(id, map),
+        }
+    }
+}
+
+impl State<App> for Inspector {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Inspect" => {
+                    let input = self.panel.text_box("input");
+                    match Inspector::parse(app, &input) {
+                        Some(target) => {
+                            self.target = Some(target);
+                            self.describe(ctx, app);
+                            if let Some(pt) = self.position(app) {
+                                ctx.canvas.center_on_map_pt(pt);
+                            }
+                        }
+                        None => {
+                            return Transition::Push(PopupMsg::new(
+                                ctx,
+                                "Bad ID",
+                                vec![format!("{} isn't a valid ID", input)],
+                            ));
+                        }
+                    }
+                }
+                _ => unreachable!(),
+            },
+            _ => {}
+        }
+
+        if self.panel.is_checked("follow (run the simulation)") {
+            if let Some(dt) = ctx.input.nonblocking_is_update_event() {
+                ctx.input.use_update_event();
+                app.primary.sim.time_limited_step(
+                    &app.primary.map,
+                    dt,
+                    Duration::seconds(0.033),
+                    &mut app.primary.sim_cb,
+                );
+            }
+            if let Some(pt) = self.position(app) {
+                ctx.canvas.center_on_map_pt(pt);
+            }
+            ctx.request_update(UpdateType::Game);
+        }
+
+        if app.primary.sim.time() != self.time {
+            self.describe(ctx, app);
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.panel.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+fn describe(app: &App, target: Target) -> Vec<String> {
+    let sim = &app.primary.sim;
+    let map = &app.primary.map;
+    let mut lines = vec![format!("At {}", sim.time())];
+    match target {
+        Target::Agent(id) => {
+            lines.push(format!("{}", id));
+            match sim.canonical_pt_for_agent(id, map) {
+                Some(pt) => lines.push(format!("Position: {}", pt)),
+                None => lines.push("Not on the map right now".to_string()),
+            }
+
+            let mut active = false;
+            match id {
+                AgentID::Car(car) => {
+                    if let Some(parked) = sim.lookup_parked_car(car) {
+                        lines.push(format!(
+                            "Parked at {:?} since {}",
+                            parked.spot, parked.parked_since
+                        ));
+                    } else if let Some(input) = sim.get_draw_car(car, map) {
+                        active = input.status == CarStatus::Moving;
+                        lines.push(format!("On {}", input.on));
+                        if let Some(t) = input.waiting_for_turn {
+                            lines.push(format!("Waiting to start {}", t));
+                        }
+                    }
+                    if let Some(owner) = sim.get_owner_of_car(car) {
+                        lines.push(format!(
+                            "Owned by {}, {}",
+                            owner,
+                            describe_person_state(app, owner)
+                        ));
+                    }
+                }
+                AgentID::Pedestrian(ped) => {
+                    if let Some(input) = sim.get_draw_ped(ped, map) {
+                        active = true;
+                        lines.push(format!("On {}", input.on));
+                        if let Some(t) = input.waiting_for_turn {
+                            lines.push(format!("Waiting to start {}", t));
+                        }
+                        if input.waiting_for_bus {
+                            lines.push("Waiting for a bus".to_string());
+                        }
+                    }
+                }
+                AgentID::BusPassenger(_, _) => {}
+            }
+
+            if active {
+                let props = sim.agent_properties(id);
+                lines.push(format!(
+                    "Crossed {} of {}",
+                    props.dist_crossed, props.total_dist
+                ));
+                lines.push(format!(
+                    "Waiting here for {}, {} total on this leg",
+                    props.waiting_here, props.total_waiting
+                ));
+                if let Some((delay, blocked)) = sim.get_blocked_on(map).remove(&id) {
+                    lines.push(format!("Blocked for {}: {}", delay, blocked));
+                }
+            }
+
+            if let Some(path) = sim.get_path(id) {
+                let steps = path.get_steps();
+                lines.push(format!(
+                    "Plan: {} steps left, {} to go",
+                    steps.len(),
+                    path.total_length() - path.crossed_so_far()
+                ));
+                for step in steps.iter().take(5) {
+                    lines.push(format!("- {:?}", step));
+                }
+                if steps.len() > 5 {
+                    lines.push("- ...".to_string());
+                }
+            }
+
+            if let Some(trip) = sim.agent_to_trip(id) {
+                lines.extend(describe_trip(app, trip));
+            }
+        }
+        Target::Trip(trip) => {
+            lines.extend(describe_trip(app, trip));
+            match sim.trip_to_agent(trip) {
+                TripResult::Ok(a) => lines.push(format!("Currently {}", a)),
+                TripResult::ModeChange => lines.push("Between modes".to_string()),
+                TripResult::TripDone => lines.push("Finished".to_string()),
+                TripResult::TripNotStarted => lines.push("Hasn't started".to_string()),
+                TripResult::TripCancelled => lines.push("Cancelled".to_string()),
+                TripResult::TripDoesntExist => lines.push("Doesn't exist".to_string()),
+            }
+            if let Some((total, blocked, dist)) = sim.finished_trip_details(trip) {
+                lines.push(format!(
+                    "Took {}, {} of that blocked, covering {}",
+                    total, blocked, dist
+                ));
+            }
+        }
+        Target::Person(person) => {
+            let p = sim.get_person(person);
+            lines.push(format!(
+                "{}: {}",
+                person,
+                describe_person_state(app, person)
+            ));
+            lines.push(format!("Persona: {:?}", p.persona));
+            for v in &p.vehicles {
+                lines.push(format!("Owns {}", v.id));
+            }
+            for trip in &p.trips {
+                let info = sim.trip_info(*trip);
+                lines.push(format!(
+                    "{} ({}) departs at {}",
+                    trip,
+                    info.mode.ongoing_verb(),
+                    info.departure
+                ));
+            }
+        }
+    }
+    lines
+}
+
+fn describe_trip(app: &App, trip: TripID) -> Vec<String> {
+    let info = app.primary.sim.trip_info(trip);
+    let mut lines = vec![
+        format!(
+            "{} by {}, {:?}",
+            trip,
+            app.primary.sim.trip_to_person(trip),
+            info.purpose
+        ),
+        format!(
+            "Departs at {}, {}",
+            info.departure,
+            info.mode.ongoing_verb()
+        ),
+        format!("From {:?} to {:?}", info.start, info.end),
+    ];
+    if let Some(reason) = info.cancellation_reason {
+        lines.push(format!("Cancelled: {}", reason));
+    }
+    lines
+}
+
+fn describe_person_state(app: &App, person: PersonID) -> String {
+    match app.primary.sim.get_person(person).state {
+        PersonState::Trip(t) => format!("currently on {}", t),
+        PersonState::Inside(b) => format!("inside {}", b),
+        PersonState::OffMap => "off the map".to_string(),
+    }
+}
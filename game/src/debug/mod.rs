@@ -21,6 +21,7 @@ use crate::sandbox::GameplayMode;
 
 mod blocked_by;
 mod floodfill;
+mod inspector;
 mod objects;
 pub mod path_counter;
 mod polygons;
@@ -55,6 +56,7 @@ impl DebugMode {
                 Checkbox::switch(ctx, "show areas", Key::Num4, true),
                 Checkbox::switch(ctx, "show labels", Key::Num5, false),
                 Checkbox::switch(ctx, "show route for all agents", Key::R, false),
+                Checkbox::switch(ctx, "show rendering costs", Key::Num6, false),
                 Widget::col(vec![
                     Btn::text_fg("unhide everything").build_def(ctx, lctrl(Key::H)),
                     Btn::text_fg("screenshot everything").build_def(ctx, None),
@@ -70,6 +72,7 @@ impl DebugMode {
                     Btn::text_fg("find large intersections").build_def(ctx, None),
                     Btn::text_fg("sim internal stats").build_def(ctx, None),
                     Btn::text_fg("blocked-by graph").build_def(ctx, Key::B),
+                    Btn::text_fg("inspect agents and trips by ID").build_def(ctx, Key::I),
                 ]),
                 Text::from_all(vec![
                     Line("Hold "),
@@ -255,6 +258,9 @@ impl State<App> for DebugMode {
                 "blocked-by graph" => {
                     return Transition::Push(blocked_by::Viewer::new(ctx, app));
                 }
+                "inspect agents and trips by ID" => {
+                    return Transition::Push(inspector::Inspector::new(ctx, app));
+                }
                 _ => unreachable!(),
             },
             Outcome::Changed => {
@@ -365,10 +371,39 @@ impl State<App> for DebugMode {
             self.panel.draw(g);
             self.common.draw(g, app);
             self.tool_panel.draw(g);
+
+            if self.panel.is_checked("show rendering costs") {
+                draw_render_costs(g, app);
+            }
         }
     }
 }
 
+/// Shows how many triangles each layer drew last frame, to find what's making frames slow.
+fn draw_render_costs(g: &mut GfxCtx, app: &App) {
+    let costs = g.prerender.last_frame_costs();
+    let mut txt = Text::from(Line(format!(
+        "{} triangles last frame",
+        abstutil::prettyprint_usize(costs.total())
+    )));
+    if let Some(budget) = g.prerender.get_triangle_budget() {
+        let line = Line(format!("Budget is {}", abstutil::prettyprint_usize(budget)));
+        txt.add(if costs.total() > budget {
+            line.fg(Color::RED)
+        } else {
+            line.secondary()
+        });
+    }
+    for line in costs.describe() {
+        txt.add(Line(line));
+    }
+    // Drawn in screen-space, so zooming doesn't change the text size
+    let draw = g.upload(txt.bg(app.cs.panel_bg).render(g).translate(10.0, 10.0));
+    g.fork_screenspace();
+    g.redraw(&draw);
+    g.unfork();
+}
+
 impl ShowObject for DebugMode {
     fn show(&self, obj: &ID) -> bool {
         if self.hidden.contains(obj) {
@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+
+use geom::{Distance, GPSBounds, LonLat, Polygon, Ring};
+use map_gui::tools::PopupMsg;
+use widgetry::{
+    Btn, Choice, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Panel, State, Text, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::app::Transition;
+use crate::common::CommonState;
+
+const INPUT_COLOR: Color = Color::YELLOW;
+const RESULT_COLOR: Color = Color::BLUE.alpha(0.6);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Operation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+/// Combines two boundary polygons for this city into a new neighborhood, like "everywhere except
+/// downtown," instead of drawing the overlapping shapes by hand.
+pub struct CombinePolygons {
+    panel: Panel,
+    // Every boundary and imported neighborhood, by name
+    inputs: BTreeMap<String, Vec<Polygon>>,
+    result: Vec<Polygon>,
+    draw: Drawable,
+}
+
+impl CombinePolygons {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let inputs = all_boundaries(app);
+        let names: Vec<String> = inputs.keys().cloned().collect();
+        if names.is_empty() {
+            return PopupMsg::new(
+                ctx,
+                "No polygons",
+                vec!["This city doesn't have any boundaries or neighborhoods yet"],
+            );
+        }
+
+        let mut state = CombinePolygons {
+            panel: Panel::new(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Combine polygons").small_heading().draw(ctx),
+                    Btn::close(ctx),
+                ]),
+                Widget::row(vec![
+                    Widget::dropdown(
+                        ctx,
+                        "first",
+                        names[0].clone(),
+                        Choice::strings(names.clone()),
+                    ),
+                    Widget::dropdown(
+                        ctx,
+                        "operation",
+                        Operation::Difference,
+                        vec![
+                            Choice::new("and also", Operation::Union),
+                            Choice::new("where it overlaps", Operation::Intersection),
+                            Choice::new("except", Operation::Difference),
+                        ],
+                    ),
+                    Widget::dropdown(
+                        ctx,
+                        "second",
+                        names[names.len() - 1].clone(),
+                        Choice::strings(names),
+                    ),
+                ]),
+                Widget::row(vec![
+                    "Name:".draw_text(ctx).centered_vert(),
+                    Widget::text_entry(ctx, "combined".to_string(), false).named("name"),
+                ]),
+                Text::new().draw(ctx).named("status"),
+                Btn::text_bg2("save as a neighborhood").build_def(ctx, Key::Enter),
+            ]))
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+            .build(ctx),
+            inputs,
+            result: Vec::new(),
+            draw: ctx.upload(GeomBatch::new()),
+        };
+        state.recalculate(ctx);
+        Box::new(state)
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx) {
+        let first = &self.inputs[&self.panel.dropdown_value::<String, _>("first")];
+        let second = &self.inputs[&self.panel.dropdown_value::<String, _>("second")];
+        self.result = match self.panel.dropdown_value("operation") {
+            Operation::Union => Polygon::union_areas(first, second),
+            Operation::Intersection => Polygon::intersect_areas(first, second),
+            Operation::Difference => Polygon::subtract_areas(first, second),
+        };
+
+        let mut batch = GeomBatch::new();
+        for p in first.iter().chain(second.iter()) {
+            if let Ok(outline) = p.to_outline(Distance::meters(5.0)) {
+                batch.push(INPUT_COLOR, outline);
+            }
+        }
+        for p in &self.result {
+            batch.push(RESULT_COLOR, p.clone());
+        }
+        self.draw = ctx.upload(batch);
+
+        let txt = if self.result.is_empty() {
+            Text::from(Line("Nothing is left").fg(Color::RED))
+        } else {
+            Text::from(Line(format!(
+                "{} pieces, {:.2} km²",
+                self.result.len(),
+                self.result.iter().map(|p| p.area()).sum::<f64>() / 1_000_000.0
+            )))
+        };
+        let status = txt.draw(ctx);
+        self.panel.replace(ctx, "status", status);
+    }
+}
+
+impl State<App> for CombinePolygons {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "save as a neighborhood" => {
+                    let name = self.panel.text_box("name");
+                    if self.result.is_empty() || name.is_empty() {
+                        return Transition::Keep;
+                    }
+                    let dir = abstutil::path_neighborhoods(app.primary.map.get_city_name());
+                    let path = format!("{}/{}.poly", dir, name);
+                    let result = std::fs::create_dir_all(&dir)
+                        .map_err(|err| err.into())
+                        .and_then(|_| {
+                            write_polygons(&path, &self.result, app.primary.map.get_gps_bounds())
+                        });
+                    return Transition::Replace(PopupMsg::new(
+                        ctx,
+                        "Neighborhood saved",
+                        vec![match result {
+                            Ok(()) => format!("Saved {}", path),
+                            Err(err) => format!("Couldn't save {}: {}", path, err),
+                        }],
+                    ));
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed => {
+                self.recalculate(ctx);
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+/// The boundaries used to import this city and any neighborhoods imported or saved for it, by
+/// name. Neighborhoods win when the names match.
+pub fn all_boundaries(app: &App) -> BTreeMap<String, Vec<Polygon>> {
+    let map = &app.primary.map;
+    let mut results = BTreeMap::new();
+    for dir in &[
+        abstutil::path(format!("../importer/config/{}", map.get_city_name())),
+        abstutil::path_neighborhoods(map.get_city_name()),
+    ] {
+        for path in abstutil::list_dir(dir.clone()) {
+            if !path.ends_with(".poly") {
+                continue;
+            }
+            if let Ok(polygons) = read_polygons(&path, map.get_gps_bounds()) {
+                if !polygons.is_empty() {
+                    results.insert(abstutil::basename(&path), polygons);
+                }
+            }
+        }
+    }
+    results
+}
+
+/// Reads an Osmosis polygon file, possibly with several pieces and holes, into map-space.
+pub fn read_polygons(path: &str, gps: &GPSBounds) -> Result<Vec<Polygon>, String> {
+    let mut results = Vec::new();
+    for rings in LonLat::read_osmosis_polygons(path).map_err(|err| err.to_string())? {
+        let mut rings = rings
+            .into_iter()
+            .map(|pts| Ring::new(gps.convert(&pts)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let outer = rings.remove(0);
+        results.push(Polygon::with_holes(outer, rings));
+    }
+    Ok(results)
+}
+
+fn write_polygons(
+    path: &str,
+    polygons: &[Polygon],
+    gps: &GPSBounds,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut all_rings = Vec::new();
+    for p in polygons {
+        all_rings.push(
+            p.get_rings()?
+                .into_iter()
+                .map(|ring| gps.convert_back(ring.points()))
+                .collect(),
+        );
+    }
+    LonLat::write_osmosis_polygons(path, &all_rings)
+}
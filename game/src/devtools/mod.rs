@@ -13,11 +13,14 @@ use crate::app::{App, Transition};
 use crate::helpers::nice_map_name;
 
 mod collisions;
+mod combine;
 mod destinations;
 mod kml;
+mod partition;
+pub mod playback;
 mod polygon;
 mod scenario;
-mod story;
+pub mod story;
 
 pub struct DevToolsMode {
     panel: Panel,
@@ -42,6 +45,8 @@ impl DevToolsMode {
                 Widget::custom_row(vec![
                     Btn::text_fg("edit a polygon").build_def(ctx, Key::E),
                     Btn::text_fg("draw a polygon").build_def(ctx, Key::P),
+                    Btn::text_fg("check neighborhood partition").build_def(ctx, Key::N),
+                    Btn::text_fg("combine polygons").build_def(ctx, Key::B),
                     Btn::text_fg("load scenario").build_def(ctx, Key::W),
                     Btn::text_fg("view KML").build_def(ctx, Key::K),
                     Btn::text_fg("story maps").build_def(ctx, Key::S),
@@ -84,9 +89,10 @@ impl State<App> for DevToolsMode {
                         .filter(|path| path.ends_with(".poly"))
                         .map(|path| Choice::new(abstutil::basename(&path), path))
                         .collect(),
-                        Box::new(|path, ctx, _| match LonLat::read_osmosis_polygon(&path) {
+                        Box::new(|path, ctx, app| match LonLat::read_osmosis_polygon(&path) {
                             Ok(pts) => Transition::Replace(polygon::PolygonEditor::new(
                                 ctx,
+                                app,
                                 abstutil::basename(path),
                                 pts,
                             )),
@@ -100,10 +106,17 @@ impl State<App> for DevToolsMode {
                 "draw a polygon" => {
                     return Transition::Push(polygon::PolygonEditor::new(
                         ctx,
+                        app,
                         "name goes here".to_string(),
                         Vec::new(),
                     ));
                 }
+                "check neighborhood partition" => {
+                    return Transition::Push(partition::PartitionChecker::new(ctx, app));
+                }
+                "combine polygons" => {
+                    return Transition::Push(combine::CombinePolygons::new(ctx, app));
+                }
                 "load scenario" => {
                     return Transition::Push(ChooseSomething::new(
                         ctx,
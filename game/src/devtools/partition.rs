@@ -0,0 +1,279 @@
+use std::collections::BTreeMap;
+
+use abstutil::prettyprint_usize;
+use geom::{Distance, FindClosest, Polygon, Pt2D};
+use map_model::BuildingID;
+use widgetry::{
+    Btn, Checkbox, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Panel, State, Text, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::app::Transition;
+use crate::common::CommonState;
+
+/// Treats the neighborhoods imported for this city, or its boundary polygons if there are none, as
+/// neighborhoods that should partition the map.
+/// Finds buildings that belong to no neighborhood or several, and can assign every building to
+/// exactly one, so that counts aggregated by neighborhood (like an origin-destination matrix) add
+/// up correctly.
+pub struct PartitionChecker {
+    panel: Panel,
+    zones: Vec<Zone>,
+    // For every building, all of the zones containing its center
+    containing: BTreeMap<BuildingID, Vec<usize>>,
+    // Filled out after auto-assigning
+    assignment: Option<BTreeMap<BuildingID, usize>>,
+    draw: Drawable,
+}
+
+struct Zone {
+    name: String,
+    // Neighborhoods made by combining others can have several pieces
+    polygons: Vec<Polygon>,
+}
+
+impl Zone {
+    fn contains_pt(&self, pt: Pt2D) -> bool {
+        self.polygons.iter().any(|p| p.contains_pt(pt))
+    }
+
+    fn area(&self) -> f64 {
+        self.polygons.iter().map(|p| p.area()).sum()
+    }
+}
+
+impl PartitionChecker {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let map = &app.primary.map;
+        let mut paths = abstutil::list_dir(abstutil::path_neighborhoods(map.get_city_name()));
+        if !paths.iter().any(|path| path.ends_with(".poly")) {
+            paths = abstutil::list_dir(abstutil::path(format!(
+                "../importer/config/{}",
+                map.get_city_name()
+            )));
+        }
+        let mut zones = Vec::new();
+        for path in paths {
+            if !path.ends_with(".poly") {
+                continue;
+            }
+            if let Ok(polygons) = super::combine::read_polygons(&path, map.get_gps_bounds()) {
+                zones.push(Zone {
+                    name: abstutil::basename(&path),
+                    polygons,
+                });
+            }
+        }
+
+        let mut containing = BTreeMap::new();
+        for b in map.all_buildings() {
+            let center = b.polygon.center();
+            containing.insert(
+                b.id,
+                zones
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, z)| z.contains_pt(center))
+                    .map(|(idx, _)| idx)
+                    .collect(),
+            );
+        }
+
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Neighborhood partition").small_heading().draw(ctx),
+                Btn::close(ctx),
+            ]),
+            Text::from_multiline(vec![
+                Line("Every building should belong to exactly one neighborhood"),
+                Line("Neighborhoods come from import_neighborhoods, or this city's boundaries")
+                    .secondary(),
+            ])
+            .draw(ctx),
+        ];
+        for zone in &zones {
+            col.push(Checkbox::switch(ctx, &zone.name, None, true));
+        }
+        col.push(Text::new().draw(ctx).named("results"));
+        col.push(Widget::row(vec![
+            Btn::text_bg2("auto-assign leftovers").build_def(ctx, Key::A),
+            Btn::text_fg("save partition").build_def(ctx, None),
+        ]));
+
+        let mut state = PartitionChecker {
+            panel: Panel::new(Widget::col(col))
+                .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+                .build(ctx),
+            zones,
+            containing,
+            assignment: None,
+            draw: ctx.upload(GeomBatch::new()),
+        };
+        state.recalculate(ctx, app);
+        Box::new(state)
+    }
+
+    fn included(&self) -> Vec<bool> {
+        self.zones
+            .iter()
+            .map(|z| self.panel.is_checked(&z.name))
+            .collect()
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        let map = &app.primary.map;
+        let included = self.included();
+        let mut batch = GeomBatch::new();
+        for (zone, include) in self.zones.iter().zip(included.iter()) {
+            if *include {
+                for p in &zone.polygons {
+                    if let Ok(outline) = p.to_outline(Distance::meters(5.0)) {
+                        batch.push(Color::BLACK, outline);
+                    }
+                }
+            }
+        }
+
+        let mut txt = Text::new();
+        if let Some(ref assignment) = self.assignment {
+            for (b, zone) in assignment {
+                batch.push(
+                    app.cs.rotating_color_plot(*zone).alpha(0.8),
+                    map.get_b(*b).polygon.clone(),
+                );
+            }
+            txt.add(Line(format!(
+                "Every building assigned to one of {} neighborhoods",
+                included.iter().filter(|x| **x).count()
+            )));
+        } else {
+            let mut gaps = 0;
+            let mut overlaps = 0;
+            for (b, zones) in &self.containing {
+                let num = zones.iter().filter(|z| included[**z]).count();
+                let color = if num == 0 {
+                    gaps += 1;
+                    Color::RED
+                } else if num > 1 {
+                    overlaps += 1;
+                    Color::ORANGE
+                } else {
+                    continue;
+                };
+                batch.push(color, map.get_b(*b).polygon.clone());
+            }
+            txt.add(Line(format!(
+                "{} buildings aren't in any neighborhood",
+                prettyprint_usize(gaps)
+            )));
+            txt.add(Line(format!(
+                "{} buildings are in more than one",
+                prettyprint_usize(overlaps)
+            )));
+        }
+        self.draw = ctx.upload(batch);
+        let results = txt.draw(ctx);
+        self.panel.replace(ctx, "results", results);
+    }
+
+    /// Buildings in exactly one neighborhood stay there. Buildings in several go to the smallest
+    /// one, since it's the most specific. Buildings in none go to the nearest neighborhood.
+    fn auto_assign(&self, app: &App) -> BTreeMap<BuildingID, usize> {
+        let map = &app.primary.map;
+        let included = self.included();
+
+        let mut bounds = map.get_bounds().clone();
+        for zone in &self.zones {
+            for p in &zone.polygons {
+                for pt in p.points() {
+                    bounds.update(*pt);
+                }
+            }
+        }
+        // Keyed by zone and piece
+        let mut closest: FindClosest<(usize, usize)> = FindClosest::new(&bounds);
+        for (idx, zone) in self.zones.iter().enumerate() {
+            if included[idx] {
+                for (piece, p) in zone.polygons.iter().enumerate() {
+                    closest.add((idx, piece), p.points());
+                }
+            }
+        }
+        let max_dist = Distance::meters(bounds.width() + bounds.height());
+
+        let mut assignment = BTreeMap::new();
+        for (b, zones) in &self.containing {
+            let zones: Vec<usize> = zones.iter().cloned().filter(|z| included[*z]).collect();
+            let zone = if zones.len() == 1 {
+                Some(zones[0])
+            } else if zones.len() > 1 {
+                zones.into_iter().min_by(|a, b| {
+                    self.zones[*a]
+                        .area()
+                        .partial_cmp(&self.zones[*b].area())
+                        .unwrap()
+                })
+            } else {
+                let center: Pt2D = map.get_b(*b).polygon.center();
+                closest
+                    .closest_pt(center, max_dist)
+                    .map(|((idx, _), _)| idx)
+            };
+            if let Some(zone) = zone {
+                assignment.insert(*b, zone);
+            }
+        }
+        assignment
+    }
+}
+
+impl State<App> for PartitionChecker {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "auto-assign leftovers" => {
+                    self.assignment = Some(self.auto_assign(app));
+                    self.recalculate(ctx, app);
+                }
+                "save partition" => {
+                    let assignment = self
+                        .assignment
+                        .clone()
+                        .unwrap_or_else(|| self.auto_assign(app));
+                    let mut per_zone: BTreeMap<String, Vec<BuildingID>> = BTreeMap::new();
+                    for (b, zone) in assignment {
+                        per_zone
+                            .entry(self.zones[zone].name.clone())
+                            .or_default()
+                            .push(b);
+                    }
+                    let path = format!(
+                        "{}_neighborhoods.json",
+                        app.primary.map.get_name().as_filename()
+                    );
+                    abstutil::write_json(path, &per_zone);
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed => {
+                self.assignment = None;
+                self.recalculate(ctx, app);
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
@@ -0,0 +1,191 @@
+use geom::{Circle, Distance, Line, Time};
+use map_gui::render::unzoomed_agent_radius;
+use map_model::SIDEWALK_THICKNESS;
+use sim::{AgentID, Playback, VehicleType};
+use widgetry::{
+    Btn, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line as TextLine,
+    Outcome, Panel, Slider, Spinner, State, TextExt, UpdateType, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+
+/// Animates a run recorded with `--record_playback`, without loading or stepping a simulation.
+pub struct PlaybackViewer {
+    playback: Playback,
+    time: Time,
+    paused: bool,
+    // What the time slider was last set to, to notice when it's dragged
+    slider_pct: f64,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+    panel: Panel,
+}
+
+impl PlaybackViewer {
+    pub fn new(ctx: &mut EventCtx, app: &mut App, playback: Playback) -> Box<dyn State<App>> {
+        assert_eq!(
+            &playback.map_name,
+            app.primary.map.get_name(),
+            "The playback is for a different map"
+        );
+        // Only the map is needed; drop any simulation that's loaded
+        app.primary.clear_sim();
+
+        let (start, _) = playback
+            .time_range()
+            .expect("The playback doesn't have any frames");
+        let mut viewer = PlaybackViewer {
+            panel: Panel::new(Widget::col(vec![
+                Widget::row(vec![
+                    TextLine("Playback").small_heading().draw(ctx),
+                    Btn::close(ctx),
+                ]),
+                "".draw_text(ctx).named("time"),
+                Slider::area(ctx, 0.2 * ctx.canvas.window_width, 0.0).named("scrub"),
+                Widget::row(vec![
+                    Btn::text_bg2("play").build(ctx, "play/pause", Key::Space),
+                    "Speed:".draw_text(ctx).centered_vert(),
+                    Spinner::new(ctx, (1, 3600), 60).named("speed"),
+                ]),
+            ]))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .build(ctx),
+            playback,
+            time: start,
+            paused: true,
+            slider_pct: 0.0,
+            unzoomed: ctx.upload(GeomBatch::new()),
+            zoomed: ctx.upload(GeomBatch::new()),
+        };
+        viewer.set_time(ctx, app, start, false);
+        Box::new(viewer)
+    }
+
+    fn set_time(&mut self, ctx: &mut EventCtx, app: &App, time: Time, move_slider: bool) {
+        let (start, end) = self.playback.time_range().unwrap();
+        self.time = time.max(start).min(end);
+        let pct = if end == start {
+            0.0
+        } else {
+            (self.time - start) / (end - start)
+        };
+
+        let mut unzoomed = GeomBatch::new();
+        let mut zoomed = GeomBatch::new();
+        let agents = self.playback.agents_at(self.time);
+        for agent in &agents {
+            let vt = agent.id.to_vehicle_type();
+            let color = match vt {
+                Some(VehicleType::Car) => app.cs.unzoomed_car,
+                Some(VehicleType::Bike) => app.cs.unzoomed_bike,
+                Some(VehicleType::Bus) | Some(VehicleType::Train) => app.cs.unzoomed_bus,
+                None => app.cs.unzoomed_pedestrian,
+            };
+            // Agents waiting at an intersection are dimmed
+            let color = if agent.waiting_for_turn {
+                color.lerp(Color::BLACK, 0.4)
+            } else {
+                color
+            };
+
+            unzoomed.push(
+                color,
+                Circle::new(agent.pos, unzoomed_agent_radius(vt)).to_polygon(),
+            );
+            if let AgentID::Pedestrian(_) = agent.id {
+                zoomed.push(
+                    color,
+                    Circle::new(agent.pos, SIDEWALK_THICKNESS / 4.0).to_polygon(),
+                );
+            } else if let Some(body) = Line::new(
+                agent
+                    .pos
+                    .project_away(agent.length, agent.facing.opposite()),
+                agent.pos,
+            ) {
+                zoomed.push(color, body.make_polygons(Distance::meters(2.0)));
+            }
+        }
+        self.unzoomed = ctx.upload(unzoomed);
+        self.zoomed = ctx.upload(zoomed);
+
+        self.panel.replace(
+            ctx,
+            "time",
+            format!("{} agents at {}", agents.len(), self.time)
+                .draw_text(ctx)
+                .named("time"),
+        );
+        if move_slider {
+            self.panel.replace(
+                ctx,
+                "scrub",
+                Slider::area(ctx, 0.2 * ctx.canvas.window_width, pct).named("scrub"),
+            );
+        }
+        self.slider_pct = pct;
+    }
+}
+
+impl State<App> for PlaybackViewer {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "play/pause" => {
+                    self.paused = !self.paused;
+                    self.panel.replace(
+                        ctx,
+                        "play/pause",
+                        Btn::text_bg2(if self.paused { "play" } else { "pause" }).build(
+                            ctx,
+                            "play/pause",
+                            Key::Space,
+                        ),
+                    );
+                }
+                _ => unreachable!(),
+            },
+            _ => {}
+        }
+
+        let pct = self.panel.slider("scrub").get_percent();
+        if pct != self.slider_pct {
+            let (start, end) = self.playback.time_range().unwrap();
+            self.set_time(ctx, app, start + (end - start) * pct, false);
+        }
+
+        if !self.paused {
+            if let Some(real_dt) = ctx.input.nonblocking_is_update_event() {
+                ctx.input.use_update_event();
+                let dt = real_dt * (self.panel.spinner("speed") as f64);
+                let time = self.time + dt;
+                self.set_time(ctx, app, time, true);
+                if self.time == self.playback.time_range().unwrap().1 {
+                    self.paused = true;
+                    self.panel.replace(
+                        ctx,
+                        "play/pause",
+                        Btn::text_bg2("play").build(ctx, "play/pause", Key::Space),
+                    );
+                }
+            }
+            ctx.request_update(UpdateType::Game);
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+        self.panel.draw(g);
+    }
+}
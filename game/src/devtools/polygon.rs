@@ -1,7 +1,8 @@
-use geom::{Circle, Distance, LonLat, Pt2D, Ring};
+use geom::{Circle, Distance, FindClosest, LonLat, Pt2D, Ring};
+use map_model::RoadID;
 use widgetry::{
-    Btn, Color, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, State, Text,
-    VerticalAlignment, Widget,
+    Btn, Checkbox, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Panel, State, Text, VerticalAlignment, Widget,
 };
 
 use crate::app::App;
@@ -14,24 +15,78 @@ const POINT_COLOR: Color = Color::RED;
 const POLYGON_COLOR: Color = Color::BLUE.alpha(0.6);
 const POINT_TO_MOVE: Color = Color::CYAN;
 const LAST_PLACED_POINT: Color = Color::GREEN;
+const OTHER_BOUNDARY_COLOR: Color = Color::YELLOW;
 
 pub struct PolygonEditor {
     panel: Panel,
     name: String,
     points: Vec<LonLat>,
     mouseover_pt: Option<usize>,
+    // A new point can be inserted between this point and the next one
+    mouseover_edge: Option<usize>,
     moving_pt: bool,
+
+    // Points snap to road center-lines and the edges of other boundaries in this city
+    snap_to: FindClosest<SnapTo>,
+    draw_other_boundaries: Drawable,
+    // Why the last change was rejected
+    warning: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum SnapTo {
+    Road(RoadID),
+    Boundary(usize),
 }
 
 impl PolygonEditor {
-    pub fn new(ctx: &mut EventCtx, name: String, mut points: Vec<LonLat>) -> Box<dyn State<App>> {
+    pub fn new(
+        ctx: &mut EventCtx,
+        app: &App,
+        name: String,
+        mut points: Vec<LonLat>,
+    ) -> Box<dyn State<App>> {
         points.pop();
-        Box::new(PolygonEditor {
+
+        let map = &app.primary.map;
+        // Other boundaries often extend past the map
+        let mut bounds = map.get_bounds().clone();
+        let mut other_boundaries = Vec::new();
+        for path in abstutil::list_dir(abstutil::path(format!(
+            "../importer/config/{}",
+            map.get_city_name()
+        ))) {
+            if !path.ends_with(".poly") || abstutil::basename(&path) == name {
+                continue;
+            }
+            if let Ok(pts) = LonLat::read_osmosis_polygon(&path) {
+                let pts = map.get_gps_bounds().convert(&pts);
+                for pt in &pts {
+                    bounds.update(*pt);
+                }
+                other_boundaries.push(pts);
+            }
+        }
+        let mut snap_to = FindClosest::new(&bounds);
+        for r in map.all_roads() {
+            snap_to.add(SnapTo::Road(r.id), r.center_pts.points());
+        }
+        let mut batch = GeomBatch::new();
+        for (idx, pts) in other_boundaries.into_iter().enumerate() {
+            snap_to.add(SnapTo::Boundary(idx), &pts);
+            if let Ok(ring) = Ring::new(pts) {
+                batch.push(OTHER_BOUNDARY_COLOR, ring.to_outline(POINT_RADIUS / 2.0));
+            }
+        }
+
+        let mut editor = PolygonEditor {
             panel: Panel::new(Widget::col(vec![
                 Widget::row(vec![
                     Line("Polygon editor").small_heading().draw(ctx),
                     Btn::close(ctx),
                 ]),
+                Checkbox::switch(ctx, "snap to roads and other boundaries", Key::S, true),
+                Text::new().draw(ctx).named("status"),
                 Btn::text_fg("export as an Osmosis polygon filter").build_def(ctx, Key::X),
             ]))
             .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
@@ -39,20 +94,86 @@ impl PolygonEditor {
             name,
             points,
             mouseover_pt: None,
+            mouseover_edge: None,
             moving_pt: false,
-        })
+            snap_to,
+            draw_other_boundaries: ctx.upload(batch),
+            warning: None,
+        };
+        editor.update_status(ctx, app);
+        Box::new(editor)
+    }
+
+    fn snap(&self, ctx: &EventCtx, pt: Pt2D) -> Pt2D {
+        if self.panel.is_checked("snap to roads and other boundaries") {
+            if let Some((_, snapped)) = self
+                .snap_to
+                .closest_pt(pt, POINT_RADIUS / ctx.canvas.cam_zoom)
+            {
+                return snapped;
+            }
+        }
+        pt
+    }
+
+    /// Only keeps the new points if they don't make the polygon cross itself.
+    fn change(&mut self, ctx: &mut EventCtx, app: &App, points: Vec<LonLat>) {
+        if is_simple(&app.primary.map.get_gps_bounds().convert(&points)) {
+            self.points = points;
+            self.warning = None;
+        } else {
+            self.warning = Some("The polygon can't cross itself".to_string());
+        }
+        self.update_status(ctx, app);
+    }
+
+    fn update_status(&mut self, ctx: &mut EventCtx, app: &App) {
+        let mut txt = Text::new();
+        if self.points.len() >= 3 {
+            let mut pts = app.primary.map.get_gps_bounds().convert(&self.points);
+            pts.push(pts[0]);
+            if let Ok(ring) = Ring::new(pts) {
+                let area = ring.to_polygon().area();
+                txt.add(Line(if app.opts.units.metric {
+                    format!("Area: {:.2} km²", area / 1_000_000.0)
+                } else {
+                    format!("Area: {:.2} square miles", area / 2_589_988.11)
+                }));
+            }
+        } else {
+            txt.add(Line("Place at least 3 points").secondary());
+        }
+        if let Some(ref warning) = self.warning {
+            txt.add(Line(warning).fg(Color::RED));
+        }
+        let status = txt.draw(ctx);
+        self.panel.replace(ctx, "status", status);
+    }
+
+    fn edges(&self, app: &App) -> Vec<Option<geom::Line>> {
+        let pts = app.primary.map.get_gps_bounds().convert(&self.points);
+        if pts.len() < 2 {
+            return Vec::new();
+        }
+        // Don't close the polygon until there are enough points
+        let num_edges = if pts.len() == 2 { 1 } else { pts.len() };
+        (0..num_edges)
+            .map(|idx| geom::Line::new(pts[idx], pts[(idx + 1) % pts.len()]))
+            .collect()
     }
 }
 
 impl State<App> for PolygonEditor {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
-        let gps_bounds = app.primary.map.get_gps_bounds();
+        let gps_bounds = app.primary.map.get_gps_bounds().clone();
 
         ctx.canvas_movement();
 
         if self.moving_pt {
             if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
-                self.points[self.mouseover_pt.unwrap()] = pt.to_gps(gps_bounds);
+                let mut points = self.points.clone();
+                points[self.mouseover_pt.unwrap()] = self.snap(ctx, pt).to_gps(&gps_bounds);
+                self.change(ctx, app, points);
             }
             if ctx.input.key_released(Key::LeftControl) {
                 self.moving_pt = false;
@@ -80,22 +201,43 @@ impl State<App> for PolygonEditor {
             _ => {}
         }
 
+        self.mouseover_edge = None;
         if let Some(cursor) = ctx.canvas.get_cursor_in_map_space() {
-            self.mouseover_pt = self.points.iter().position(|pt| {
-                Circle::new(pt.to_pt(gps_bounds), POINT_RADIUS / ctx.canvas.cam_zoom)
-                    .contains_pt(cursor)
-            });
+            let radius = POINT_RADIUS / ctx.canvas.cam_zoom;
+            self.mouseover_pt = self
+                .points
+                .iter()
+                .position(|pt| Circle::new(pt.to_pt(&gps_bounds), radius).contains_pt(cursor));
+            if self.mouseover_pt.is_none() {
+                self.mouseover_edge = self.edges(app).into_iter().position(|line| {
+                    line.map(|l| l.project_pt(cursor).dist_to(cursor) < radius)
+                        .unwrap_or(false)
+                });
+            }
         } else {
             self.mouseover_pt = None;
         }
         // TODO maybe click-and-drag is more intuitive
-        if self.mouseover_pt.is_some() {
+        if let Some(idx) = self.mouseover_pt {
             if ctx.input.pressed(Key::LeftControl) {
                 self.moving_pt = true;
+            } else if ctx.input.pressed(Key::Backspace) {
+                let mut points = self.points.clone();
+                points.remove(idx);
+                self.mouseover_pt = None;
+                self.change(ctx, app, points);
             }
         } else if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
-            if app.per_obj.left_click(ctx, "add a new point") {
-                self.points.push(pt.to_gps(gps_bounds));
+            if let Some(idx) = self.mouseover_edge {
+                if app.per_obj.left_click(ctx, "insert a new point") {
+                    let mut points = self.points.clone();
+                    points.insert(idx + 1, self.snap(ctx, pt).to_gps(&gps_bounds));
+                    self.change(ctx, app, points);
+                }
+            } else if app.per_obj.left_click(ctx, "add a new point") {
+                let mut points = self.points.clone();
+                points.push(self.snap(ctx, pt).to_gps(&gps_bounds));
+                self.change(ctx, app, points);
             }
         }
 
@@ -103,6 +245,8 @@ impl State<App> for PolygonEditor {
     }
 
     fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw_other_boundaries);
+
         let pts: Vec<Pt2D> = app.primary.map.get_gps_bounds().convert(&self.points);
 
         if pts.len() == 2 {
@@ -116,6 +260,14 @@ impl State<App> for PolygonEditor {
             pts.push(pts[0]);
             g.draw_polygon(POLYGON_COLOR, Ring::must_new(pts).to_polygon());
         }
+        if let Some(idx) = self.mouseover_edge {
+            if let Some(Some(line)) = self.edges(app).get(idx) {
+                g.draw_polygon(
+                    POINT_TO_MOVE,
+                    line.make_polygons(POINT_RADIUS / g.canvas.cam_zoom),
+                );
+            }
+        }
         for (idx, pt) in pts.iter().enumerate() {
             let color = if Some(idx) == self.mouseover_pt {
                 POINT_TO_MOVE
@@ -135,10 +287,41 @@ impl State<App> for PolygonEditor {
             CommonState::draw_custom_osd(
                 g,
                 app,
-                Text::from(Line("hold left Control to move point")),
+                Text::from(Line(
+                    "hold left Control to move point, Backspace to delete it",
+                )),
             );
         } else {
             CommonState::draw_osd(g, app);
         }
     }
 }
+
+/// True if connecting the points in order and then back to the first doesn't cross itself or
+/// repeat any point.
+fn is_simple(pts: &[Pt2D]) -> bool {
+    if pts.len() < 2 {
+        return true;
+    }
+    let mut lines = Vec::new();
+    for idx in 0..pts.len() {
+        match geom::Line::new(pts[idx], pts[(idx + 1) % pts.len()]) {
+            Some(l) => lines.push(l),
+            None => {
+                return false;
+            }
+        }
+    }
+    for i in 0..lines.len() {
+        for j in (i + 1)..lines.len() {
+            // Neighboring edges share a point
+            if j == i + 1 || (i == 0 && j == lines.len() - 1) {
+                continue;
+            }
+            if lines[i].intersection(&lines[j]).is_some() {
+                return false;
+            }
+        }
+    }
+    true
+}
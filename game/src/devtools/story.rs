@@ -517,18 +517,18 @@ impl Marker {
 
 // TODO This should totally be an widgetry tool
 // TODO Simplify points
-struct Lasso {
+pub struct Lasso {
     pl: PolyLine,
 }
 
 impl Lasso {
-    fn new(pt: Pt2D) -> Lasso {
+    pub fn new(pt: Pt2D) -> Lasso {
         Lasso {
             pl: PolyLine::must_new(vec![pt, pt.offset(0.1, 0.0)]),
         }
     }
 
-    fn event(&mut self, ctx: &mut EventCtx) -> Option<Ring> {
+    pub fn event(&mut self, ctx: &mut EventCtx) -> Option<Ring> {
         if ctx.input.left_mouse_button_released() {
             return Some(simplify(self.pl.points().clone()));
         }
@@ -553,7 +553,7 @@ impl Lasso {
         None
     }
 
-    fn draw(&self, g: &mut GfxCtx) {
+    pub fn draw(&self, g: &mut GfxCtx) {
         g.draw_polygon(
             Color::RED.alpha(0.8),
             self.pl
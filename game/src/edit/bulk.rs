@@ -2,14 +2,14 @@ use maplit::btreeset;
 
 use geom::Speed;
 use map_gui::tools::PopupMsg;
-use map_model::{LaneType, RoadID};
+use map_model::{Direction, DrivingSide, EditCmd, LaneID, LaneType, Map, RoadID};
 use widgetry::{
-    hotkeys, Btn, Choice, Color, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Key, Line,
-    Outcome, Panel, State, Text, TextExt, VerticalAlignment, Widget,
+    hotkeys, Btn, Checkbox, Choice, Color, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Key,
+    Line, Outcome, Panel, State, Text, TextExt, VerticalAlignment, Widget,
 };
 
 use crate::app::{App, Transition};
-use crate::edit::select::RoadSelector;
+use crate::edit::select::{parse_road_filter, RoadSelector};
 use crate::edit::{apply_map_edits, speed_limit_choices, try_change_lt, ConfirmDiscard};
 
 pub struct BulkSelect {
@@ -20,15 +20,25 @@ pub struct BulkSelect {
 impl BulkSelect {
     pub fn new(ctx: &mut EventCtx, app: &mut App, start: RoadID) -> Box<dyn State<App>> {
         let selector = RoadSelector::new(ctx, app, btreeset! {start});
-        let panel = make_select_panel(ctx, &selector);
+        let panel = make_select_panel(ctx, &selector, String::new());
         Box::new(BulkSelect { panel, selector })
     }
+
+    fn redo_panel(&mut self, ctx: &mut EventCtx) {
+        let filter = self.panel.text_box("filter");
+        self.panel = make_select_panel(ctx, &self.selector, filter);
+    }
 }
 
-fn make_select_panel(ctx: &mut EventCtx, selector: &RoadSelector) -> Panel {
+fn make_select_panel(ctx: &mut EventCtx, selector: &RoadSelector, filter: String) -> Panel {
     Panel::new(Widget::col(vec![
         Line("Edit many roads").small_heading().draw(ctx),
         selector.make_controls(ctx),
+        Widget::row(vec![
+            Widget::text_entry(ctx, filter, false).named("filter"),
+            Btn::text_fg("select matching").build_def(ctx, None),
+        ]),
+        Text::from(Line("Example: residential AND speed>25").secondary()).draw(ctx),
         Widget::row(vec![
             if selector.roads.is_empty() {
                 Btn::text_fg("Edit 0 roads").inactive(ctx)
@@ -68,6 +78,27 @@ impl State<App> for BulkSelect {
                         self.selector.preview.take().unwrap(),
                     ));
                 }
+                "select matching" => {
+                    let query =
+                        match parse_road_filter(&self.panel.text_box("filter"), &app.opts.units) {
+                            Ok(query) => query,
+                            Err(err) => {
+                                return Transition::Push(PopupMsg::new(
+                                    ctx,
+                                    "Bad filter",
+                                    vec![err],
+                                ));
+                            }
+                        };
+                    if self.selector.select_matching(ctx, app, &query) == 0 {
+                        return Transition::Push(PopupMsg::new(
+                            ctx,
+                            "No matches",
+                            vec!["No more roads match that filter"],
+                        ));
+                    }
+                    self.redo_panel(ctx);
+                }
                 "export roads to shared-row" => {
                     let path = crate::debug::shared_row::export(
                         self.selector.roads.iter().cloned().collect(),
@@ -96,13 +127,13 @@ impl State<App> for BulkSelect {
                 }
                 x => {
                     if self.selector.event(ctx, app, Some(x)) {
-                        self.panel = make_select_panel(ctx, &self.selector);
+                        self.redo_panel(ctx);
                     }
                 }
             },
             _ => {
                 if self.selector.event(ctx, app, None) {
-                    self.panel = make_select_panel(ctx, &self.selector);
+                    self.redo_panel(ctx);
                 }
             }
         }
@@ -146,6 +177,13 @@ impl BulkEdit {
                         Widget::dropdown(ctx, "speed limit", None, choices),
                     ])
                 },
+                Checkbox::switch(ctx, "add bike lanes where there's room", None, false),
+                Text::from(Line(
+                    "Turns the curbside parking lane, or else one of several driving lanes, into \
+                     a bike lane",
+                ).secondary())
+                .wrap_to_pct(ctx, 30)
+                .draw(ctx),
                 Widget::row(vec![
                     Btn::text_bg2("Finish").build_def(ctx, Key::Enter),
                     Btn::plaintext_custom(
@@ -178,6 +216,7 @@ impl State<App> for BulkEdit {
                         && get_lt_transformations(&self.panel)
                             .into_iter()
                             .all(|(lt1, lt2)| lt1.is_none() && lt2.is_none())
+                        && !self.panel.is_checked("add bike lanes where there's room")
                     {
                         return Transition::Pop;
                     }
@@ -190,6 +229,7 @@ impl State<App> for BulkEdit {
                         &self.roads,
                         self.panel.dropdown_value("speed limit"),
                         get_lt_transformations(&self.panel),
+                        self.panel.is_checked("add bike lanes where there's room"),
                     ));
                 }
                 "add another lane type transformation" => {
@@ -278,10 +318,14 @@ fn make_bulk_edits(
     roads: &Vec<RoadID>,
     speed_limit: Option<Speed>,
     lt_transformations: Vec<(Option<LaneType>, Option<LaneType>)>,
+    add_bike_lanes: bool,
 ) -> Box<dyn State<App>> {
     let mut speed_changes = 0;
     let mut lt_changes = 0;
+    let mut bike_lanes = 0;
     let mut errors = Vec::new();
+    // Everything changed here becomes one command, so it can be undone at once
+    let num_cmds_before = app.primary.map.get_edits().commands.len();
     ctx.loading_screen("change lane types", |ctx, timer| {
         if let Some(speed) = speed_limit {
             let mut edits = app.primary.map.get_edits().clone();
@@ -331,6 +375,38 @@ fn make_bulk_edits(
                 }
             }
         }
+
+        if add_bike_lanes {
+            timer.start_iter("add bike lanes", roads.len());
+            for r in roads {
+                timer.next();
+                for dir in &[Direction::Fwd, Direction::Back] {
+                    let l = if let Some(l) = find_room_for_bike_lane(&app.primary.map, *r, *dir) {
+                        l
+                    } else {
+                        continue;
+                    };
+                    match try_change_lt(ctx, &mut app.primary.map, l, LaneType::Biking) {
+                        Ok(cmd) => {
+                            let mut edits = app.primary.map.get_edits().clone();
+                            edits.commands.push(cmd);
+                            apply_map_edits(ctx, app, edits);
+                            bike_lanes += 1;
+                        }
+                        Err(err) => {
+                            errors.push(err);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut edits = app.primary.map.get_edits().clone();
+        if edits.commands.len() > num_cmds_before + 1 {
+            let cmds = edits.commands.split_off(num_cmds_before);
+            edits.commands.push(EditCmd::Bulk { cmds });
+            apply_map_edits(ctx, app, edits);
+        }
     });
 
     // TODO Need to express the errors in some form that we can union here.
@@ -343,6 +419,9 @@ fn make_bulk_edits(
             speed.to_string(&app.opts.units)
         ));
     }
+    if add_bike_lanes {
+        results.push(format!("Added {} bike lanes", bike_lanes));
+    }
     results.push(format!(
         "Changed {} lane types, encountered {} problems",
         lt_changes,
@@ -351,3 +430,47 @@ fn make_bulk_edits(
 
     PopupMsg::new(ctx, "Edited roads", results)
 }
+
+/// Roads can't be widened, so a bike lane has to replace something. Prefer the parking lane
+/// nearest the curb, then the curbside driving lane if there's another one for cars. Nothing is
+/// changed if there's already a bike lane going this way.
+fn find_room_for_bike_lane(map: &Map, r: RoadID, dir: Direction) -> Option<LaneID> {
+    // From the curb inwards
+    let mut lanes: Vec<(LaneID, LaneType)> = map
+        .get_r(r)
+        .lanes_ltr()
+        .into_iter()
+        .filter(|(_, d, lt)| {
+            *d == dir
+                && [
+                    LaneType::Driving,
+                    LaneType::Parking,
+                    LaneType::Biking,
+                    LaneType::Bus,
+                ]
+                .contains(lt)
+        })
+        .map(|(l, _, lt)| (l, lt))
+        .collect();
+    if (map.get_config().driving_side == DrivingSide::Right) == (dir == Direction::Fwd) {
+        lanes.reverse();
+    }
+
+    let num_driving = lanes
+        .iter()
+        .filter(|(_, lt)| *lt == LaneType::Driving)
+        .count();
+    if num_driving == 0 || lanes.iter().any(|(_, lt)| *lt == LaneType::Biking) {
+        return None;
+    }
+    if let Some((l, _)) = lanes.iter().find(|(_, lt)| *lt == LaneType::Parking) {
+        return Some(*l);
+    }
+    if num_driving >= 2 {
+        return lanes
+            .into_iter()
+            .find(|(_, lt)| *lt == LaneType::Driving)
+            .map(|(l, _)| l);
+    }
+    None
+}
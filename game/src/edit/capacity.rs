@@ -0,0 +1,149 @@
+use geom::Duration;
+use map_model::{EditCmd, IntersectionCapacity, IntersectionID};
+use widgetry::{
+    Btn, Choice, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, Spinner, State,
+    Text, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::app::Transition;
+use crate::common::CommonState;
+use crate::edit::apply_map_edits;
+
+/// Tune how many vehicles make it through one intersection, to match observed capacities, and
+/// whether drivers there block the box.
+pub struct CapacityEditor {
+    i: IntersectionID,
+    panel: Panel,
+}
+
+impl CapacityEditor {
+    pub fn new(ctx: &mut EventCtx, app: &App, i: IntersectionID) -> Box<dyn State<App>> {
+        let intersection = app.primary.map.get_i(i);
+        let capacity = &intersection.capacity;
+
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Intersection capacity").small_heading().draw(ctx),
+                Btn::close(ctx),
+            ]),
+            Line(i.to_string()).draw(ctx),
+        ];
+        if intersection.is_traffic_signal() {
+            col.push(Widget::row(vec![
+                "All-red clearance".draw_text(ctx).centered_vert(),
+                Spinner::duration(
+                    ctx,
+                    (Duration::ZERO, Duration::seconds(10.0)),
+                    Duration::seconds(0.1),
+                    capacity.all_red_clearance,
+                )
+                .named("all-red"),
+            ]));
+            col.push(Widget::row(vec![
+                "Startup lost time".draw_text(ctx).centered_vert(),
+                Spinner::duration(
+                    ctx,
+                    (Duration::ZERO, Duration::seconds(10.0)),
+                    Duration::seconds(0.1),
+                    capacity.startup_lost_time,
+                )
+                .named("lost time"),
+            ]));
+        }
+        col.push(Widget::row(vec![
+            "Saturation headway".draw_text(ctx).centered_vert(),
+            Spinner::duration(
+                ctx,
+                (Duration::ZERO, Duration::seconds(10.0)),
+                Duration::seconds(0.1),
+                capacity.saturation_headway,
+            )
+            .named("headway"),
+        ]));
+        col.push(
+            Text::from_multiline(vec![
+                Line("Vehicles from the same lane start turning at least this far apart")
+                    .secondary(),
+                Line("0 means no limit").secondary(),
+            ])
+            .draw(ctx),
+        );
+        col.push(Widget::row(vec![
+            "Blocking the box:".draw_text(ctx).centered_vert(),
+            Widget::dropdown(
+                ctx,
+                "block the box",
+                capacity.dont_block_the_box,
+                vec![
+                    Choice::new("simulation default", None),
+                    Choice::new("drivers wait for room to exit", Some(true)),
+                    Choice::new("drivers enter anyway", Some(false)),
+                ],
+            ),
+        ]));
+        col.push(Widget::row(vec![
+            Btn::text_fg("reset").build_def(ctx, None),
+            Btn::text_bg2("Apply").build_def(ctx, Key::Enter),
+        ]));
+
+        Box::new(CapacityEditor {
+            i,
+            panel: Panel::new(Widget::col(col))
+                .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+                .build(ctx),
+        })
+    }
+
+    fn apply(&self, ctx: &mut EventCtx, app: &mut App, new: IntersectionCapacity) {
+        let old = app.primary.map.get_i(self.i).capacity.clone();
+        if old == new {
+            return;
+        }
+        let mut edits = app.primary.map.get_edits().clone();
+        edits.commands.push(EditCmd::ChangeIntersectionCapacity {
+            i: self.i,
+            old,
+            new,
+        });
+        apply_map_edits(ctx, app, edits);
+    }
+}
+
+impl State<App> for CapacityEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "reset" => {
+                    self.apply(ctx, app, IntersectionCapacity::default());
+                    return Transition::Pop;
+                }
+                "Apply" => {
+                    let mut new = IntersectionCapacity::default();
+                    if self.panel.has_widget("all-red") {
+                        new.all_red_clearance = self.panel.duration_spinner("all-red");
+                        new.startup_lost_time = self.panel.duration_spinner("lost time");
+                    }
+                    new.saturation_headway = self.panel.duration_spinner("headway");
+                    new.dont_block_the_box = self.panel.dropdown_value("block the box");
+                    self.apply(ctx, app, new);
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.panel.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::BTreeSet;
+
+use map_model::{EditCmd, IntersectionID, RoadID};
+use widgetry::{
+    Btn, Checkbox, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Panel, State, Text, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::app::Transition;
+use crate::common::CommonState;
+use crate::edit::{apply_map_edits, check_sidewalk_connectivity};
+
+/// Choose which roads pedestrians can cross at one intersection. Crosswalks are guessed during
+/// import, so this fixes crossings that exist in reality but were missed, or the opposite.
+pub struct CrosswalkEditor {
+    i: IntersectionID,
+    panel: Panel,
+    missing: Drawable,
+}
+
+impl CrosswalkEditor {
+    pub fn new(ctx: &mut EventCtx, app: &App, i: IntersectionID) -> Box<dyn State<App>> {
+        let intersection = app.primary.map.get_i(i);
+
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Crosswalks").small_heading().draw(ctx),
+                Btn::close(ctx),
+            ]),
+            Line(i.to_string()).draw(ctx),
+            Text::from(Line("Roads pedestrians can cross here").secondary()).draw(ctx),
+        ];
+        for r in &intersection.roads {
+            col.push(Checkbox::switch(
+                ctx,
+                label(app, *r),
+                None,
+                !intersection.no_crosswalks.contains(r),
+            ));
+        }
+        col.push(Widget::row(vec![
+            if intersection.no_crosswalks != intersection.orig_no_crosswalks {
+                Btn::text_fg("reset to default").build_def(ctx, None)
+            } else {
+                Btn::text_fg("reset to default").inactive(ctx)
+            },
+            Btn::text_bg2("Apply").build_def(ctx, Key::Enter),
+        ]));
+
+        let panel = Panel::new(Widget::col(col))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .build(ctx);
+        let missing = draw_missing(ctx, app, i, &intersection.no_crosswalks);
+        Box::new(CrosswalkEditor { i, panel, missing })
+    }
+
+    fn no_crosswalks(&self, app: &App) -> BTreeSet<RoadID> {
+        app.primary
+            .map
+            .get_i(self.i)
+            .roads
+            .iter()
+            .filter(|r| !self.panel.is_checked(&label(app, **r)))
+            .cloned()
+            .collect()
+    }
+
+    fn apply(
+        &self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        new: BTreeSet<RoadID>,
+    ) -> Option<Box<dyn State<App>>> {
+        let old = app.primary.map.get_i(self.i).no_crosswalks.clone();
+        if old == new {
+            return None;
+        }
+        let cmd = EditCmd::ChangeCrosswalks {
+            i: self.i,
+            old,
+            new,
+        };
+        if let Some(err) = check_sidewalk_connectivity(ctx, app, cmd.clone()) {
+            return Some(err);
+        }
+        let mut edits = app.primary.map.get_edits().clone();
+        edits.commands.push(cmd);
+        apply_map_edits(ctx, app, edits);
+        None
+    }
+}
+
+impl State<App> for CrosswalkEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "reset to default" => {
+                    let orig = app.primary.map.get_i(self.i).orig_no_crosswalks.clone();
+                    return match self.apply(ctx, app, orig) {
+                        Some(err) => Transition::Replace(err),
+                        None => Transition::Pop,
+                    };
+                }
+                "Apply" => {
+                    let new = self.no_crosswalks(app);
+                    return match self.apply(ctx, app, new) {
+                        Some(err) => Transition::Replace(err),
+                        None => Transition::Pop,
+                    };
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed => {
+                let missing = self.no_crosswalks(app);
+                self.missing = draw_missing(ctx, app, self.i, &missing);
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.missing);
+        self.panel.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+fn label(app: &App, r: RoadID) -> String {
+    // Roads on either side of an intersection often have the same name
+    format!(
+        "{} (#{})",
+        app.primary
+            .map
+            .get_r(r)
+            .get_name(app.opts.language.as_ref()),
+        r.0
+    )
+}
+
+fn draw_missing(
+    ctx: &mut EventCtx,
+    app: &App,
+    i: IntersectionID,
+    missing: &BTreeSet<RoadID>,
+) -> Drawable {
+    let mut batch = GeomBatch::new();
+    batch.push(
+        Color::YELLOW.alpha(0.5),
+        app.primary.map.get_i(i).polygon.clone(),
+    );
+    for r in missing {
+        batch.push(
+            Color::RED.alpha(0.5),
+            app.primary
+                .map
+                .get_r(*r)
+                .get_thick_polygon(&app.primary.map),
+        );
+    }
+    ctx.upload(batch)
+}
@@ -1,5 +1,5 @@
 use map_gui::render::Renderable;
-use map_model::{EditCmd, LaneID, LaneType, Map};
+use map_model::{EditCmd, LaneID, LaneType, Map, TrafficCalmingKind};
 use widgetry::{
     Btn, Choice, Color, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, State,
     Text, TextExt, VerticalAlignment, Widget,
@@ -8,9 +8,12 @@ use widgetry::{
 use crate::app::App;
 use crate::app::Transition;
 use crate::common::CommonState;
+use crate::edit::traffic_calming::TrafficCalmingEditor;
+use crate::edit::work_zones::WorkZoneEditor;
 use crate::edit::zones::ZoneEditor;
 use crate::edit::{
     apply_map_edits, can_edit_lane, maybe_edit_intersection, speed_limit_choices, try_change_lt,
+    NoteEditor,
 };
 use crate::helpers::ID;
 use crate::sandbox::GameplayMode;
@@ -99,6 +102,17 @@ impl LaneEditor {
                 ])
             },
             Btn::text_fg("Change access restrictions").build_def(ctx, Key::A),
+            Btn::text_fg(format!(
+                "Traffic calming ({})",
+                parent.traffic_calming.len()
+            ))
+            .build(ctx, "Traffic calming", Key::H),
+            Btn::text_fg(format!("Work zones ({})", parent.work_zones.len())).build(
+                ctx,
+                "Work zones",
+                Key::W,
+            ),
+            Btn::text_fg("Note about this road").build_def(ctx, Key::N),
             Btn::text_bg2("Finish").build_def(ctx, Key::Escape),
         ];
 
@@ -157,6 +171,28 @@ impl State<App> for LaneEditor {
                         app.primary.map.get_l(self.l).parent,
                     ));
                 }
+                "Traffic calming" => {
+                    return Transition::Push(TrafficCalmingEditor::new(
+                        ctx,
+                        app,
+                        app.primary.map.get_l(self.l).parent,
+                        TrafficCalmingKind::SpeedHump,
+                    ));
+                }
+                "Work zones" => {
+                    return Transition::Push(WorkZoneEditor::new(
+                        ctx,
+                        app,
+                        app.primary.map.get_l(self.l).parent,
+                    ));
+                }
+                "Note about this road" => {
+                    return Transition::Push(NoteEditor::new(
+                        ctx,
+                        app,
+                        ID::Road(app.primary.map.get_l(self.l).parent),
+                    ));
+                }
                 "Finish" => {
                     return Transition::Pop;
                 }
@@ -5,7 +5,7 @@ use maplit::btreeset;
 use abstutil::{prettyprint_usize, Timer};
 use geom::Speed;
 use map_gui::options::OptionsPanel;
-use map_gui::render::DrawMap;
+use map_gui::render::{DrawBusStop, DrawMap};
 use map_gui::tools::{ChooseSomething, PopupMsg};
 use map_model::{EditCmd, IntersectionID, LaneID, LaneType, MapEdits};
 use widgetry::{
@@ -13,8 +13,12 @@ use widgetry::{
     Outcome, Panel, State, Text, TextExt, VerticalAlignment, Widget,
 };
 
+pub use self::capacity::CapacityEditor;
 pub use self::cluster_traffic_signals::ClusterTrafficSignalEditor;
+pub use self::crosswalks::CrosswalkEditor;
 pub use self::lanes::LaneEditor;
+pub use self::notes::{get_note, NoteEditor};
+pub use self::osm_tags::OsmTagEditor;
 pub use self::routes::RouteEditor;
 pub use self::stop_signs::StopSignEditor;
 pub use self::traffic_signals::TrafficSignalEditor;
@@ -26,13 +30,20 @@ use crate::helpers::{grey_out_map, ID};
 use crate::sandbox::{GameplayMode, SandboxMode, TimeWarpScreen};
 
 mod bulk;
+mod capacity;
 mod cluster_traffic_signals;
+mod crosswalks;
 mod lanes;
+mod notes;
+mod osm_tags;
+mod print;
 mod routes;
 mod select;
 mod stop_signs;
+mod traffic_calming;
 mod traffic_signals;
 mod validate;
+mod work_zones;
 mod zones;
 
 pub struct EditMode {
@@ -45,8 +56,8 @@ pub struct EditMode {
     // Retained state from the SandboxMode that spawned us
     mode: GameplayMode,
 
-    // edits name, number of commands
-    changelist_key: (String, usize),
+    // edits name, number of commands, number of notes
+    changelist_key: (String, usize, usize),
 
     unzoomed: Drawable,
     zoomed: Drawable,
@@ -66,7 +77,7 @@ impl EditMode {
             orig_edits: edits.clone(),
             orig_dirty,
             mode,
-            changelist_key: (edits.edits_name.clone(), edits.commands.len()),
+            changelist_key: changelist_key(edits),
             unzoomed: layer.unzoomed,
             zoomed: layer.zoomed,
         })
@@ -75,8 +86,11 @@ impl EditMode {
     fn quit(&self, ctx: &mut EventCtx, app: &mut App) -> Transition {
         let old_sim = app.primary.suspended_sim.take().unwrap();
 
-        // If nothing changed, short-circuit
-        if app.primary.map.get_edits() == &self.orig_edits {
+        // If nothing changed, short-circuit. Notes don't affect the simulation.
+        let edits = app.primary.map.get_edits();
+        if edits.commands == self.orig_edits.commands
+            && edits.merge_zones == self.orig_edits.merge_zones
+        {
             app.primary.sim = old_sim;
             app.primary.dirty_from_edits = self.orig_dirty;
             // Could happen if we load some edits, then load whatever we entered edit mode with.
@@ -143,7 +157,7 @@ impl State<App> for EditMode {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
         {
             let edits = app.primary.map.get_edits();
-            let changelist_key = (edits.edits_name.clone(), edits.commands.len());
+            let changelist_key = changelist_key(edits);
             if self.changelist_key != changelist_key {
                 self.changelist_key = changelist_key;
                 self.changelist = make_changelist(ctx, app);
@@ -198,6 +212,7 @@ impl State<App> for EditMode {
                             Choice::string("open a saved proposal").multikey(lctrl(Key::L)),
                             Choice::string("create a blank proposal"),
                             Choice::string("save this proposal as..."),
+                            Choice::string("export a printable map"),
                             Choice::string("delete this proposal and remove all edits")
                                 .fg(Color::hex("#EB3223")),
                         ],
@@ -263,6 +278,9 @@ impl State<App> for EditMode {
                                 Some(Transition::Pop),
                                 Box::new(|_, _| {}),
                             )),
+                            "export a printable map" => {
+                                Transition::Replace(print::PrintMap::new(ctx))
+                            }
                             "delete this proposal and remove all edits" => {
                                 abstutil::delete_file(abstutil::path_edits(
                                     app.primary.map.get_name(),
@@ -675,10 +693,33 @@ pub fn apply_map_edits(ctx: &mut EventCtx, app: &mut App, edits: MapEdits) {
         modified_intersections.insert(t.parent);
     }
 
+    // Turn arrows are drawn on the lanes leading into an intersection, and stop bars depend on
+    // how the intersection is controlled
+    for i in &modified_intersections {
+        lanes_of_modified_turns.extend(app.primary.map.get_i(*i).incoming_lanes.clone());
+    }
+    for l in lanes_of_modified_turns {
+        app.primary.draw_map.lanes[l.0].clear_rendering();
+    }
+
     for i in modified_intersections {
         app.primary.draw_map.intersections[i.0].clear_rendering();
     }
 
+    // Creating a route can add stops, and undoing that removes them again
+    let map = &app.primary.map;
+    let draw_map = &mut app.primary.draw_map;
+    let cs = &app.cs;
+    draw_map
+        .bus_stops
+        .retain(|id, _| map.maybe_get_bs(*id).is_some());
+    for bs in map.all_bus_stops().values() {
+        draw_map
+            .bus_stops
+            .entry(bs.id)
+            .or_insert_with(|| DrawBusStop::new(ctx, bs, map, cs));
+    }
+
     if app.primary.layer.as_ref().and_then(|l| l.name()) == Some("map edits") {
         app.primary.layer = Some(Box::new(crate::layer::map::Static::edits(ctx, app)));
     }
@@ -748,6 +789,14 @@ pub fn maybe_edit_intersection(
     None
 }
 
+fn changelist_key(edits: &MapEdits) -> (String, usize, usize) {
+    (
+        edits.edits_name.clone(),
+        edits.commands.len(),
+        edits.road_notes.len() + edits.intersection_notes.len(),
+    )
+}
+
 fn make_changelist(ctx: &mut EventCtx, app: &App) -> Panel {
     // TODO Support redo. Bit harder here to reset the redo_stack when the edits
     // change, because nested other places modify it too.
@@ -771,6 +820,10 @@ fn make_changelist(ctx: &mut EventCtx, app: &App) -> Panel {
             ),
         ),
     ];
+    let num_notes = edits.road_notes.len() + edits.intersection_notes.len();
+    if num_notes > 0 {
+        col.push(format!("{} notes explain the changes", num_notes).draw_text(ctx));
+    }
 
     if edits.commands.len() > 5 {
         col.push(format!("{} more...", edits.commands.len() - 5).draw_text(ctx));
@@ -807,8 +860,15 @@ fn make_changelist(ctx: &mut EventCtx, app: &App) -> Panel {
 fn cmd_to_id(cmd: &EditCmd) -> Option<ID> {
     match cmd {
         EditCmd::ChangeRoad { r, .. } => Some(ID::Road(*r)),
-        EditCmd::ChangeIntersection { i, .. } => Some(ID::Intersection(*i)),
-        EditCmd::ChangeRouteSchedule { .. } => None,
+        EditCmd::ChangeIntersection { i, .. }
+        | EditCmd::ChangeIntersectionCapacity { i, .. }
+        | EditCmd::ChangeCrosswalks { i, .. } => Some(ID::Intersection(*i)),
+        EditCmd::ChangeRouteSchedule { .. }
+        | EditCmd::ChangeRouteStops { .. }
+        | EditCmd::ChangeRouteDetours { .. }
+        | EditCmd::CreateRoute { .. }
+        | EditCmd::DeleteRoute { .. } => None,
+        EditCmd::Bulk { cmds } => cmds.iter().find_map(cmd_to_id),
     }
 }
 
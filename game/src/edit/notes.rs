@@ -0,0 +1,142 @@
+use map_model::EditNote;
+use widgetry::{
+    Btn, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, State, Text, TextExt,
+    VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::CommonState;
+use crate::edit::apply_map_edits;
+use crate::helpers::ID;
+
+/// Attach a note to a road or intersection, explaining to reviewers why part of a proposal
+/// changed there.
+pub struct NoteEditor {
+    id: ID,
+    panel: Panel,
+}
+
+impl NoteEditor {
+    /// The ID must be a road or intersection.
+    pub fn new(ctx: &mut EventCtx, app: &App, id: ID) -> Box<dyn State<App>> {
+        let note = get_note(app, &id);
+        let name = match id {
+            ID::Road(r) => r.to_string(),
+            ID::Intersection(i) => i.to_string(),
+            _ => unreachable!(),
+        };
+        let mut col = vec![
+            Widget::row(vec![
+                Line(format!("Note on {}", name)).small_heading().draw(ctx),
+                Btn::close(ctx),
+            ]),
+            Widget::text_entry(
+                ctx,
+                note.map(|n| n.text.clone()).unwrap_or_default(),
+                true,
+            )
+            .named("text"),
+            "Photos or links, separated by spaces".draw_text(ctx),
+            Widget::text_entry(
+                ctx,
+                note.map(|n| n.links.join(" ")).unwrap_or_default(),
+                false,
+            )
+            .named("links"),
+            Text::from(
+                Line("Notes are saved with the proposal, but don't change the map").secondary(),
+            )
+            .draw(ctx),
+        ];
+        col.push(Widget::row(vec![
+            if note.is_some() {
+                Btn::text_fg("delete note").build_def(ctx, None)
+            } else {
+                Btn::text_fg("delete note").inactive(ctx)
+            },
+            Btn::text_bg2("Apply").build_def(ctx, Key::Enter),
+        ]));
+
+        Box::new(NoteEditor {
+            id,
+            panel: Panel::new(Widget::col(col))
+                .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+                .build(ctx),
+        })
+    }
+
+    fn apply(&self, ctx: &mut EventCtx, app: &mut App, note: Option<EditNote>) {
+        let mut edits = app.primary.map.get_edits().clone();
+        match self.id {
+            ID::Road(r) => {
+                if let Some(note) = note {
+                    edits.road_notes.insert(r, note);
+                } else {
+                    edits.road_notes.remove(&r);
+                }
+            }
+            ID::Intersection(i) => {
+                if let Some(note) = note {
+                    edits.intersection_notes.insert(i, note);
+                } else {
+                    edits.intersection_notes.remove(&i);
+                }
+            }
+            _ => unreachable!(),
+        }
+        apply_map_edits(ctx, app, edits);
+    }
+}
+
+impl State<App> for NoteEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "delete note" => {
+                    self.apply(ctx, app, None);
+                    return Transition::Pop;
+                }
+                "Apply" => {
+                    let text = self.panel.text_box("text").trim().to_string();
+                    let links: Vec<String> = self
+                        .panel
+                        .text_box("links")
+                        .split_whitespace()
+                        .map(|x| x.to_string())
+                        .collect();
+                    let note = if text.is_empty() && links.is_empty() {
+                        None
+                    } else {
+                        Some(EditNote { text, links })
+                    };
+                    self.apply(ctx, app, note);
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.panel.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+/// The note attached to a road or intersection, if there is one.
+pub fn get_note<'a>(app: &'a App, id: &ID) -> Option<&'a EditNote> {
+    let edits = app.primary.map.get_edits();
+    match id {
+        ID::Road(r) => edits.road_notes.get(r),
+        ID::Intersection(i) => edits.intersection_notes.get(i),
+        _ => None,
+    }
+}
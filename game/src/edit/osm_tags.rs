@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+
+use abstutil::Timer;
+use map_gui::tools::PopupMsg;
+use map_model::osm::TagFixes;
+use map_model::{EditCmd, EditRoad, RoadID};
+use widgetry::{
+    Btn, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, State, Text, TextExt,
+    VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::CommonState;
+use crate::edit::apply_map_edits;
+
+/// Correct the OSM tags of the way a road came from, like a wrong number of lanes. The road is
+/// regenerated from the new tags right away when possible, and the fix is saved so the importer
+/// applies it the next time this area is imported.
+pub struct OsmTagEditor {
+    r: RoadID,
+    panel: Panel,
+}
+
+impl OsmTagEditor {
+    pub fn new(ctx: &mut EventCtx, app: &App, r: RoadID) -> Box<dyn State<App>> {
+        let road = app.primary.map.get_r(r);
+
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Fix OSM tags").small_heading().draw(ctx),
+                Btn::close(ctx),
+            ]),
+            Line(road.orig_id.osm_way_id.to_string()).draw(ctx),
+            Text::from(Line("Leave a value blank to remove that tag").secondary()).draw(ctx),
+        ];
+        for (k, v) in road.osm_tags.inner() {
+            // Tags added by the importer aren't in OSM
+            if k.starts_with("abst:") {
+                continue;
+            }
+            col.push(Widget::row(vec![
+                Line(k).draw(ctx).centered_vert(),
+                Widget::text_entry(ctx, v.clone(), false).named(format!("value {}", k)),
+            ]));
+        }
+        col.push(Widget::row(vec![
+            "New tag".draw_text(ctx).centered_vert(),
+            Widget::text_entry(ctx, String::new(), false).named("new key"),
+            "=".draw_text(ctx).centered_vert(),
+            Widget::text_entry(ctx, String::new(), false).named("new value"),
+        ]));
+        col.push(Btn::text_bg2("Apply").build_def(ctx, Key::Enter));
+
+        Box::new(OsmTagEditor {
+            r,
+            panel: Panel::new(Widget::col(col))
+                .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+                .build(ctx),
+        })
+    }
+
+    /// The tags that differ from the road's current ones
+    fn changed_tags(&self, app: &App) -> BTreeMap<String, String> {
+        let mut changes = BTreeMap::new();
+        for (k, v) in app.primary.map.get_r(self.r).osm_tags.inner() {
+            let name = format!("value {}", k);
+            if !self.panel.has_widget(&name) {
+                continue;
+            }
+            let value = self.panel.text_box(&name).trim().to_string();
+            if &value != v {
+                changes.insert(k.clone(), value);
+            }
+        }
+        let key = self.panel.text_box("new key").trim().to_string();
+        if !key.is_empty() {
+            changes.insert(key, self.panel.text_box("new value").trim().to_string());
+        }
+        changes
+    }
+
+    /// Saves the fix, then rebuilds every road from the same way. Returns true if some road's
+    /// number of lanes changed, which only takes effect after importing again.
+    fn apply(&self, ctx: &mut EventCtx, app: &mut App, changes: BTreeMap<String, String>) -> bool {
+        let map = &app.primary.map;
+        let way = map.get_r(self.r).orig_id.osm_way_id;
+        let city = map.get_name().city.clone();
+
+        let mut fixes = TagFixes::load(&city, &mut Timer::throwaway());
+        fixes.ways.entry(way).or_default().extend(changes);
+        fixes.save(&city);
+
+        let mut edits = map.get_edits().clone();
+        let mut needs_import = false;
+        for road in map.all_roads() {
+            if road.orig_id.osm_way_id != way {
+                continue;
+            }
+            let mut tags = road.osm_tags.clone();
+            fixes.apply(way, &mut tags);
+            let old = map.get_r_edit(road.id);
+            let mut new = EditRoad::from_osm_tags(&tags, map.get_config());
+            new.traffic_calming = old.traffic_calming.clone();
+            new.work_zones = old.work_zones.clone();
+            // Lanes can be changed in place, but not added or removed
+            if new.lanes_ltr.len() != old.lanes_ltr.len() {
+                needs_import = true;
+                continue;
+            }
+            if new != old {
+                edits.commands.push(EditCmd::ChangeRoad {
+                    r: road.id,
+                    old,
+                    new,
+                });
+            }
+        }
+        apply_map_edits(ctx, app, edits);
+        needs_import
+    }
+}
+
+impl State<App> for OsmTagEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Apply" => {
+                    let changes = self.changed_tags(app);
+                    if changes.is_empty() {
+                        return Transition::Pop;
+                    }
+                    if self.apply(ctx, app, changes) {
+                        return Transition::Replace(PopupMsg::new(
+                            ctx,
+                            "Fix saved",
+                            vec![
+                                "The new tags change the number of lanes.".to_string(),
+                                "This will take effect the next time the map is imported."
+                                    .to_string(),
+                            ],
+                        ));
+                    }
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.panel.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
@@ -0,0 +1,327 @@
+use geom::{Bounds, Distance, Polygon};
+use map_gui::tools::PopupMsg;
+use map_model::AreaType;
+use widgetry::{
+    Btn, Checkbox, Color, EventCtx, Fill, GeomBatch, GfxCtx, HorizontalAlignment, Line, Outcome,
+    Panel, ScreenDims, State, Text, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+
+// Width of the exported page, in points. The height matches the shape of the window.
+const PAGE_WIDTH: f64 = 1000.0;
+const MARGIN: f64 = 10.0;
+
+const LAYERS: [&str; 7] = [
+    "areas",
+    "roads and intersections",
+    "buildings",
+    "parking lots",
+    "changes in this proposal",
+    "legend",
+    "scale bar",
+];
+
+/// Exports whatever's currently on screen as a vector image, for printing or including in a
+/// report. The map can still be moved around to frame the export.
+pub struct PrintMap {
+    panel: Panel,
+}
+
+impl PrintMap {
+    pub fn new(ctx: &mut EventCtx) -> Box<dyn State<App>> {
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Export a printable map").small_heading().draw(ctx),
+                Btn::close(ctx),
+            ]),
+            "Everything in the current view is exported. Move the map to frame it.".draw_text(ctx),
+        ];
+        for layer in &LAYERS {
+            col.push(Checkbox::switch(ctx, *layer, None, true));
+        }
+        col.push(Widget::row(vec![
+            Btn::text_bg2("Export to SVG").build_def(ctx, None),
+            Btn::text_bg2("Export to PDF").build_def(ctx, None),
+        ]));
+        Box::new(PrintMap {
+            panel: Panel::new(Widget::col(col))
+                .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+                .build(ctx),
+        })
+    }
+
+    fn export(&self, ctx: &EventCtx, app: &App, pdf: bool) -> Result<String, std::io::Error> {
+        let (batch, dims) = make_page(ctx, app, &|layer| self.panel.is_checked(layer));
+        let path = format!(
+            "{}_{}.{}",
+            app.primary.map.get_name().as_filename(),
+            app.primary.map.get_edits().edits_name,
+            if pdf { "pdf" } else { "svg" }
+        );
+        if pdf {
+            std::fs::write(&path, batch.to_pdf(dims))?;
+        } else {
+            std::fs::write(&path, batch.to_svg(dims))?;
+        }
+        Ok(path)
+    }
+}
+
+impl State<App> for PrintMap {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => Transition::Pop,
+                "Export to SVG" | "Export to PDF" => {
+                    Transition::Push(match self.export(ctx, app, x == "Export to PDF") {
+                        Ok(path) => {
+                            PopupMsg::new(ctx, "Map exported", vec![format!("Saved {}", path)])
+                        }
+                        Err(err) => PopupMsg::new(ctx, "Export failed", vec![err.to_string()]),
+                    })
+                }
+                _ => unreachable!(),
+            },
+            _ => Transition::Keep,
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+    }
+}
+
+// The current view, scaled to the page, plus the legend and scale bar on top
+fn make_page(ctx: &EventCtx, app: &App, show: &dyn Fn(&str) -> bool) -> (GeomBatch, ScreenDims) {
+    let map = &app.primary.map;
+    let cs = &app.cs;
+    let view = ctx.canvas.get_screen_bounds();
+    let scale = PAGE_WIDTH / view.width();
+    let dims = ScreenDims::new(PAGE_WIDTH, view.height() * scale);
+
+    let mut map_batch = GeomBatch::new();
+    map_batch.push(
+        plain(&cs.map_background, Color::WHITE),
+        view.get_rectangle(),
+    );
+    let mut legend: Vec<(&str, Color)> = Vec::new();
+
+    if show("areas") {
+        for a in map.all_areas() {
+            if overlaps(&view, &a.polygon) {
+                map_batch.push(area_color(a.area_type, app), a.polygon.clone());
+            }
+        }
+        legend.push(("park", area_color(AreaType::Park, app)));
+        legend.push(("water", area_color(AreaType::Water, app)));
+    }
+    if show("parking lots") {
+        for pl in map.all_parking_lots() {
+            if overlaps(&view, &pl.polygon) {
+                map_batch.push(cs.parking_lot, pl.polygon.clone());
+            }
+        }
+        legend.push(("parking lot", cs.parking_lot));
+    }
+    if show("roads and intersections") {
+        // Like the unzoomed map, draw everything in z-order
+        let mut pieces: Vec<(isize, Polygon, Color)> = Vec::new();
+        for r in map.all_roads() {
+            let polygon = r.get_thick_polygon(map);
+            if overlaps(&view, &polygon) {
+                let color = if r.is_light_rail() {
+                    cs.light_rail_track
+                } else if r.is_private() {
+                    cs.private_road
+                } else {
+                    cs.unzoomed_road_surface(r.get_rank())
+                };
+                pieces.push((r.zorder, polygon, color));
+            }
+        }
+        for i in map.all_intersections() {
+            if overlaps(&view, &i.polygon) {
+                let color = if i.is_stop_sign() {
+                    cs.unzoomed_road_surface(i.get_rank(map))
+                } else {
+                    cs.unzoomed_interesting_intersection
+                };
+                pieces.push((i.get_zorder(map), i.polygon.clone(), color));
+            }
+        }
+        pieces.sort_by_key(|(z, _, _)| *z);
+        for (_, polygon, color) in pieces {
+            map_batch.push(color, polygon);
+        }
+        legend.push(("traffic signal", cs.unzoomed_interesting_intersection));
+    }
+    if show("buildings") {
+        for b in map.all_buildings() {
+            if overlaps(&view, &b.polygon) {
+                map_batch.push(
+                    if b.amenities.is_empty() {
+                        cs.residential_building
+                    } else {
+                        cs.commerical_building
+                    },
+                    b.polygon.clone(),
+                );
+            }
+        }
+        legend.push(("residential building", cs.residential_building));
+        legend.push(("commercial building", cs.commerical_building));
+    }
+    if show("changes in this proposal") {
+        let edits = map.get_edits();
+        let (lanes, roads) = edits.changed_lanes(map);
+        for l in lanes {
+            map_batch.push(
+                cs.edits_layer,
+                map.get_l(l)
+                    .lane_center_pts
+                    .make_polygons(map.get_l(l).width),
+            );
+        }
+        for r in roads {
+            map_batch.push(cs.edits_layer, map.get_r(r).get_thick_polygon(map));
+        }
+        for i in edits.original_intersections.keys() {
+            map_batch.push(cs.edits_layer, map.get_i(*i).polygon.clone());
+        }
+        legend.push(("changed in this proposal", cs.edits_layer));
+    }
+
+    let mut page = map_batch.translate(-view.min_x, -view.min_y).scale(scale);
+    if show("legend") {
+        page.append(make_legend(ctx, app, legend));
+    }
+    if show("scale bar") {
+        let bar = make_scale_bar(ctx, app, view.width(), scale);
+        let bar_dims = bar.get_dims();
+        page.append(bar.translate(
+            PAGE_WIDTH - bar_dims.width - MARGIN,
+            dims.height - bar_dims.height - MARGIN,
+        ));
+    }
+    (page, dims)
+}
+
+// A box in the top-left with the proposal's name and a swatch for everything shown
+fn make_legend(ctx: &EventCtx, app: &App, entries: Vec<(&str, Color)>) -> GeomBatch {
+    let mut batch = GeomBatch::new();
+    let title = Text::from(
+        Line(format!(
+            "{} ({})",
+            app.primary.map.get_edits().edits_name,
+            app.primary.map.get_name().describe()
+        ))
+        .fg(Color::BLACK),
+    )
+    .render_to_batch(ctx.prerender);
+    let mut y = 2.0 * MARGIN + title.get_dims().height;
+    let mut width = title.get_dims().width;
+    batch.append(title.translate(2.0 * MARGIN, 2.0 * MARGIN));
+    for (label, color) in entries {
+        let txt = Text::from(Line(label).fg(Color::BLACK)).render_to_batch(ctx.prerender);
+        let txt_dims = txt.get_dims();
+        y += MARGIN / 2.0;
+        batch.push(
+            color,
+            Polygon::rectangle(txt_dims.height, txt_dims.height).translate(2.0 * MARGIN, y),
+        );
+        batch.append(txt.translate(3.0 * MARGIN + txt_dims.height, y));
+        width = width.max(MARGIN + txt_dims.height + txt_dims.width);
+        y += txt_dims.height;
+    }
+
+    let mut legend = GeomBatch::new();
+    legend.push(
+        Color::WHITE.alpha(0.9),
+        Polygon::rectangle(width + 2.0 * MARGIN, y).translate(MARGIN, MARGIN),
+    );
+    legend.append(batch);
+    legend
+}
+
+// A bar with a round length, no more than a quarter of the view's width
+fn make_scale_bar(ctx: &EventCtx, app: &App, view_width: f64, scale: f64) -> GeomBatch {
+    let choices: Vec<Distance> = if app.opts.units.metric {
+        vec![
+            10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1000.0, 2000.0, 5000.0, 10000.0,
+        ]
+        .into_iter()
+        .map(Distance::meters)
+        .collect()
+    } else {
+        // Distances under a tenth of a mile are described in feet
+        vec![50.0, 100.0, 200.0, 500.0]
+            .into_iter()
+            .map(|ft| Distance::meters(ft / 3.28084))
+            .chain(
+                vec![0.1, 0.2, 0.5, 1.0, 2.0, 5.0]
+                    .into_iter()
+                    .map(Distance::miles),
+            )
+            .collect()
+    };
+    let length = choices
+        .iter()
+        .rev()
+        .find(|d| d.inner_meters() <= view_width / 4.0)
+        .cloned()
+        .unwrap_or(choices[0]);
+    let bar_width = length.inner_meters() * scale;
+
+    let mut batch = GeomBatch::new();
+    let label = Text::from(Line(length.to_string(&app.opts.units)).fg(Color::BLACK))
+        .render_to_batch(ctx.prerender);
+    let label_dims = label.get_dims();
+    let width = bar_width.max(label_dims.width);
+    batch.push(
+        Color::WHITE.alpha(0.9),
+        Polygon::rectangle(width + 2.0 * MARGIN, label_dims.height + 3.0 * MARGIN),
+    );
+    batch.append(label.translate(MARGIN, MARGIN / 2.0));
+    // Alternating black and white halves, like a surveyor's scale bar
+    let y = label_dims.height + MARGIN;
+    for (idx, color) in vec![Color::BLACK, Color::WHITE].into_iter().enumerate() {
+        batch.push(
+            color,
+            Polygon::rectangle(bar_width / 2.0, MARGIN / 2.0)
+                .translate(MARGIN + (idx as f64) * bar_width / 2.0, y),
+        );
+    }
+    if let Ok(outline) = Polygon::rectangle(bar_width, MARGIN / 2.0)
+        .translate(MARGIN, y)
+        .to_outline(Distance::meters(0.5))
+    {
+        batch.push(Color::BLACK, outline);
+    }
+    batch
+}
+
+fn overlaps(view: &Bounds, polygon: &Polygon) -> bool {
+    let b = polygon.get_bounds();
+    b.min_x <= view.max_x && b.max_x >= view.min_x && b.min_y <= view.max_y && b.max_y >= view.min_y
+}
+
+fn area_color(area_type: AreaType, app: &App) -> Color {
+    // Textures can't be exported, so use something close
+    match area_type {
+        AreaType::Park => plain(&app.cs.grass, Color::hex("#94C84A")),
+        AreaType::Water => plain(&app.cs.water, Color::hex("#A4C8EA")),
+        AreaType::Island => plain(&app.cs.map_background, Color::WHITE),
+        AreaType::MedianStrip => plain(&app.cs.median_strip, Color::hex("#94C84A")),
+        AreaType::PedestrianPlaza => plain(&app.cs.pedestrian_plaza, Color::grey(0.9)),
+    }
+}
+
+fn plain(fill: &Fill, fallback: Color) -> Color {
+    match fill {
+        Fill::Color(c) => *c,
+        _ => fallback,
+    }
+}
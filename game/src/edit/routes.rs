@@ -1,25 +1,43 @@
-use geom::{Duration, Time};
-use map_model::{BusRouteID, EditCmd};
+use std::collections::BTreeSet;
+
+use geom::{Circle, Distance, Duration, Time};
+use map_gui::tools::PopupMsg;
+use map_model::{BusRoute, BusRouteID, BusStopID, EditCmd, PathConstraints, RoadID};
 use widgetry::{
-    Btn, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, Spinner, State, TextExt,
-    VerticalAlignment, Widget,
+    Btn, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome,
+    Panel, Spinner, State, Text, TextExt, VerticalAlignment, Widget,
 };
 
 use crate::app::App;
 use crate::app::Transition;
+use crate::common::CommonState;
 use crate::edit::apply_map_edits;
+use crate::helpers::ID;
+
+const DEFAULT_FREQ_MINS: isize = 60;
 
+/// Change a route's schedule, or reroute it by skipping stops, detouring to new ones, and
+/// detouring around roads.
 pub struct RouteEditor {
     panel: Panel,
     route: BusRouteID,
+    stops: Vec<BusStopID>,
+    avoid_roads: BTreeSet<RoadID>,
+    // Is there some pair of stops without a path between them?
+    broken: bool,
+    draw_route: Drawable,
 }
 
 impl RouteEditor {
     pub fn new(ctx: &mut EventCtx, app: &mut App, id: BusRouteID) -> Box<dyn State<App>> {
         app.primary.current_selection = None;
+        // Previewing a reroute needs pathfinding
+        ctx.loading_screen("apply edits", |_, timer| {
+            app.primary.map.recalculate_pathfinding_after_edits(timer);
+        });
 
         let route = app.primary.map.get_br(id);
-        Box::new(RouteEditor {
+        let mut editor = RouteEditor {
             panel: Panel::new(Widget::col(vec![
                 Widget::row(vec![
                     Line("Route editor").small_heading().draw(ctx),
@@ -29,14 +47,111 @@ impl RouteEditor {
                 // TODO This UI needs design, just something to start plumbing the edits
                 Widget::row(vec![
                     "Frequency in minutes".draw_text(ctx),
-                    Spinner::new(ctx, (1, 120), 60).named("freq_mins"),
+                    Spinner::new(ctx, (1, 120), DEFAULT_FREQ_MINS).named("freq_mins"),
                 ]),
+                Text::new().draw(ctx).named("stops"),
                 Btn::text_bg2("Apply").build_def(ctx, Key::Enter),
             ]))
-            .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
             .build(ctx),
             route: id,
-        })
+            stops: route.stops.clone(),
+            avoid_roads: route.avoid_roads.clone(),
+            broken: false,
+            draw_route: ctx.upload(GeomBatch::new()),
+        };
+        editor.recalculate(ctx, app);
+        Box::new(editor)
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        let map = &app.primary.map;
+        let route = map.get_br(self.route);
+
+        let mut batch = GeomBatch::new();
+        self.broken = false;
+        for req in route.all_steps_with_stops(&self.stops, map) {
+            if let Some(pl) = BusRoute::pathfind_avoiding(req.clone(), &self.avoid_roads, map)
+                .and_then(|path| path.trace(map, req.start.dist_along(), None))
+            {
+                batch.push(app.cs.unzoomed_bus, pl.make_polygons(Distance::meters(3.0)));
+            } else {
+                self.broken = true;
+            }
+        }
+        for r in &self.avoid_roads {
+            batch.push(Color::RED.alpha(0.5), map.get_r(*r).get_thick_polygon(map));
+        }
+        for bs in &self.stops {
+            batch.push(
+                Color::CYAN,
+                Circle::new(map.get_bs(*bs).sidewalk_pos.pt(map), Distance::meters(5.0))
+                    .to_polygon(),
+            );
+        }
+        self.draw_route = ctx.upload(batch);
+
+        let mut col = vec![Line("Stops").small_heading().draw(ctx)];
+        for (idx, bs) in self.stops.iter().enumerate() {
+            let label = format!("{}. {}", idx + 1, map.get_bs(*bs).name).draw_text(ctx);
+            // A route needs at least two stops
+            if self.stops.len() > 2 {
+                col.push(Widget::row(vec![
+                    label,
+                    Btn::plaintext("skip")
+                        .build(ctx, format!("skip stop {}", idx + 1), None)
+                        .align_right(),
+                ]));
+            } else {
+                col.push(label);
+            }
+        }
+        col.push(
+            Text::from_multiline(vec![
+                Line("Zoom in and click a stop to detour the route there").secondary(),
+                Line("Click a road to detour around it").secondary(),
+            ])
+            .draw(ctx),
+        );
+        if self.broken {
+            col.push(
+                Line("Some stops can't be reached from the previous stop")
+                    .fg(Color::RED)
+                    .draw(ctx),
+            );
+        }
+        if self.stops != route.orig_stops {
+            col.push(Btn::text_fg("restore original stops").build_def(ctx, None));
+        }
+        if !self.avoid_roads.is_empty() {
+            col.push(Btn::text_fg("remove all detours").build_def(ctx, None));
+        }
+        self.panel.replace(ctx, "stops", Widget::col(col));
+    }
+
+    /// Put a new stop wherever it adds the least straight-line distance to the route.
+    fn insert_stop(&mut self, app: &App, new: BusStopID) {
+        let map = &app.primary.map;
+        let pt = |bs: BusStopID| map.get_bs(bs).sidewalk_pos.pt(map);
+        let new_pt = pt(new);
+        let mut best: Option<(usize, Distance)> = None;
+        for idx in 0..=self.stops.len() {
+            let cost = match (
+                idx.checked_sub(1).map(|i| pt(self.stops[i])),
+                self.stops.get(idx).map(|bs| pt(*bs)),
+            ) {
+                (Some(prev), Some(next)) => {
+                    prev.dist_to(new_pt) + new_pt.dist_to(next) - prev.dist_to(next)
+                }
+                (Some(prev), None) => prev.dist_to(new_pt),
+                (None, Some(next)) => new_pt.dist_to(next),
+                (None, None) => Distance::ZERO,
+            };
+            if best.map(|(_, dist)| cost < dist).unwrap_or(true) {
+                best = Some((idx, cost));
+            }
+        }
+        self.stops.insert(best.unwrap().0, new);
     }
 }
 
@@ -44,31 +159,111 @@ impl State<App> for RouteEditor {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
         ctx.canvas_movement();
 
+        if ctx.redo_mouseover() {
+            app.recalculate_current_selection(ctx);
+            let route = app.primary.map.get_br(self.route);
+            match app.primary.current_selection {
+                Some(ID::BusStop(bs))
+                    if !self.stops.contains(&bs)
+                        && app.primary.map.get_bs(bs).is_train_stop
+                            == (route.route_type == PathConstraints::Train) => {}
+                Some(ID::Lane(l)) => {
+                    app.primary.current_selection = Some(ID::Road(app.primary.map.get_l(l).parent));
+                }
+                Some(ID::Road(_)) => {}
+                _ => {
+                    app.primary.current_selection = None;
+                }
+            }
+        }
+        match app.primary.current_selection {
+            Some(ID::BusStop(bs)) if app.per_obj.left_click(ctx, "detour to this stop") => {
+                self.insert_stop(app, bs);
+                self.recalculate(ctx, app);
+            }
+            Some(ID::Road(r)) => {
+                if self.avoid_roads.contains(&r) {
+                    if app.per_obj.left_click(ctx, "use this road again") {
+                        self.avoid_roads.remove(&r);
+                        self.recalculate(ctx, app);
+                    }
+                } else if app.per_obj.left_click(ctx, "detour around this road") {
+                    self.avoid_roads.insert(r);
+                    self.recalculate(ctx, app);
+                }
+            }
+            _ => {}
+        }
+
         match self.panel.event(ctx) {
             Outcome::Clicked(x) => match x.as_ref() {
                 "close" => {
                     return Transition::Pop;
                 }
+                "restore original stops" => {
+                    self.stops = app.primary.map.get_br(self.route).orig_stops.clone();
+                    self.recalculate(ctx, app);
+                }
+                "remove all detours" => {
+                    self.avoid_roads.clear();
+                    self.recalculate(ctx, app);
+                }
                 "Apply" => {
-                    let freq = Duration::minutes(self.panel.spinner("freq_mins") as usize);
-                    let mut now = Time::START_OF_DAY;
-                    let mut hourly_times = Vec::new();
-                    while now <= Time::START_OF_DAY + Duration::hours(24) {
-                        hourly_times.push(now);
-                        now += freq;
+                    if self.broken {
+                        return Transition::Push(PopupMsg::new(
+                            ctx,
+                            "Error",
+                            vec!["Some stops can't be reached from the previous stop"],
+                        ));
                     }
 
+                    let route = app.primary.map.get_br(self.route);
                     let mut edits = app.primary.map.get_edits().clone();
-                    edits.commands.push(EditCmd::ChangeRouteSchedule {
-                        id: self.route,
-                        old: app.primary.map.get_br(self.route).spawn_times.clone(),
-                        new: hourly_times,
-                    });
+                    let new_stops = self.stops != route.stops;
+                    let new_detours = self.avoid_roads != route.avoid_roads;
+                    let reroute = new_stops || new_detours;
+                    let freq_mins = self.panel.spinner("freq_mins");
+                    if new_stops {
+                        edits.commands.push(EditCmd::ChangeRouteStops {
+                            id: self.route,
+                            old: route.stops.clone(),
+                            new: self.stops.clone(),
+                        });
+                    }
+                    if new_detours {
+                        edits.commands.push(EditCmd::ChangeRouteDetours {
+                            id: self.route,
+                            old: route.avoid_roads.clone(),
+                            new: self.avoid_roads.clone(),
+                        });
+                    }
+                    // If only the route changed, keep the original schedule
+                    if freq_mins != DEFAULT_FREQ_MINS || !reroute {
+                        let freq = Duration::minutes(freq_mins as usize);
+                        let mut now = Time::START_OF_DAY;
+                        let mut hourly_times = Vec::new();
+                        while now <= Time::START_OF_DAY + Duration::hours(24) {
+                            hourly_times.push(now);
+                            now += freq;
+                        }
+                        edits.commands.push(EditCmd::ChangeRouteSchedule {
+                            id: self.route,
+                            old: route.spawn_times.clone(),
+                            new: hourly_times,
+                        });
+                    }
                     apply_map_edits(ctx, app, edits);
 
                     return Transition::Pop;
                 }
-                _ => unreachable!(),
+                x => {
+                    if let Some(idx) = x.strip_prefix("skip stop ") {
+                        self.stops.remove(idx.parse::<usize>().unwrap() - 1);
+                        self.recalculate(ctx, app);
+                    } else {
+                        unreachable!()
+                    }
+                }
             },
             _ => {}
         }
@@ -76,7 +271,9 @@ impl State<App> for RouteEditor {
         Transition::Keep
     }
 
-    fn draw(&self, g: &mut GfxCtx, _: &App) {
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw_route);
         self.panel.draw(g);
+        CommonState::draw_osd(g, app);
     }
 }
@@ -1,10 +1,12 @@
 use std::collections::BTreeSet;
 
-use map_model::{IntersectionID, RoadID};
+use geom::{Speed, UnitFmt};
+use map_model::{osm, IntersectionID, RoadID, RoadQuery};
 use widgetry::{Btn, Color, Drawable, EventCtx, GeomBatch, GfxCtx, Key, RewriteColor, Widget};
 
 use crate::app::App;
 use crate::common::CommonState;
+use crate::devtools::story::Lasso;
 use crate::helpers::{intersections_from_roads, ID};
 
 pub struct RoadSelector {
@@ -22,6 +24,8 @@ pub enum Mode {
     },
     Paint,
     Erase,
+    /// Draw a loop around roads to select them
+    Lasso(Option<Lasso>),
 }
 
 impl RoadSelector {
@@ -70,6 +74,15 @@ impl RoadSelector {
                     Key::R,
                 )
             },
+            if let Mode::Lasso(_) = self.mode {
+                Widget::draw_svg_transform(
+                    ctx,
+                    "system/assets/tools/select.svg",
+                    RewriteColor::ChangeAll(Color::hex("#4CA7E9")),
+                )
+            } else {
+                Btn::svg_def("system/assets/tools/select.svg").build(ctx, "lasso", Key::L)
+            },
             if let Mode::Pan = self.mode {
                 Widget::draw_svg_transform(
                     ctx,
@@ -103,12 +116,26 @@ impl RoadSelector {
         self.preview = Some(ctx.upload(batch));
     }
 
+    /// Adds every road matching the query to the selection. Returns how many were added.
+    pub fn select_matching(&mut self, ctx: &mut EventCtx, app: &App, query: &RoadQuery) -> usize {
+        let map = &app.primary.map;
+        let before = self.roads.len();
+        self.roads.extend(
+            query
+                .roads(map)
+                .into_iter()
+                .filter(|r| !map.get_r(*r).is_light_rail()),
+        );
+        self.roads_changed(ctx, app);
+        self.roads.len() - before
+    }
+
     // Pass None. Returns true if anything changed.
     pub fn event(&mut self, ctx: &mut EventCtx, app: &mut App, clicked: Option<&str>) -> bool {
         if ctx.redo_mouseover() {
             app.primary.current_selection = app.mouseover_unzoomed_roads_and_intersections(ctx);
             match self.mode {
-                Mode::Pan => {
+                Mode::Pan | Mode::Lasso(_) => {
                     app.primary.current_selection = None;
                 }
                 Mode::Route { .. } => {
@@ -145,6 +172,20 @@ impl RoadSelector {
                     self.dragging = true;
                 }
             }
+            Mode::Lasso(None) => {
+                if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
+                    if ctx.input.left_mouse_button_pressed() {
+                        self.mode = Mode::Lasso(Some(Lasso::new(pt)));
+                    }
+                }
+            }
+            Mode::Lasso(Some(ref mut lasso)) => {
+                if let Some(ring) = lasso.event(ctx) {
+                    self.mode = Mode::Lasso(None);
+                    let query = RoadQuery::Within(ring.to_polygon());
+                    return self.select_matching(ctx, app, &query) > 0;
+                }
+            }
         }
 
         if self.dragging {
@@ -166,7 +207,7 @@ impl RoadSelector {
                             false
                         }
                     }
-                    Mode::Route { .. } | Mode::Pan => unreachable!(),
+                    Mode::Route { .. } | Mode::Pan | Mode::Lasso(_) => unreachable!(),
                 };
                 if change {
                     self.roads_changed(ctx, app);
@@ -193,6 +234,12 @@ impl RoadSelector {
                     self.mode = Mode::Pan;
                     return true;
                 }
+                "lasso" => {
+                    app.primary.current_selection = None;
+                    self.dragging = false;
+                    self.mode = Mode::Lasso(None);
+                    return true;
+                }
                 "select along route" => {
                     app.primary.current_selection = None;
                     self.dragging = false;
@@ -283,6 +330,7 @@ impl RoadSelector {
                 Mode::Paint => Some("system/assets/tools/pencil.svg"),
                 Mode::Erase => Some("system/assets/tools/eraser.svg"),
                 Mode::Route { .. } => Some("system/assets/timeline/start_pos.svg"),
+                Mode::Lasso(_) => Some("system/assets/tools/select.svg"),
             } {
                 let mut batch = GeomBatch::new();
                 batch.append(
@@ -308,7 +356,57 @@ impl RoadSelector {
                 g.redraw(p);
             }
         }
+        if let Mode::Lasso(Some(ref lasso)) = self.mode {
+            lasso.draw(g);
+        }
 
         CommonState::draw_osd(g, app);
     }
 }
+
+/// Parses a few simple conditions on roads, all of which must hold, like `residential AND
+/// speed>25`. A bare word is the OSM highway type, `key=value` checks any OSM tag, and `speed`
+/// compares the speed limit with `<`, `>`, or `=`, in the player's units.
+pub fn parse_road_filter(input: &str, units: &UnitFmt) -> Result<RoadQuery, String> {
+    let mut conditions = Vec::new();
+    for word in input.split_whitespace() {
+        if word.eq_ignore_ascii_case("and") {
+            continue;
+        }
+        if let Some(rest) = word.strip_prefix("speed") {
+            let mut chars = rest.chars();
+            let op = chars.next();
+            let value = chars
+                .as_str()
+                .parse::<f64>()
+                .map_err(|_| format!("{} needs a number after the comparison", word))?;
+            let speed = if units.metric {
+                Speed::km_per_hour(value)
+            } else {
+                Speed::miles_per_hour(value)
+            };
+            conditions.push(match op {
+                Some('<') => RoadQuery::SlowerThan(speed),
+                Some('>') => RoadQuery::FasterThan(speed),
+                Some('=') => RoadQuery::And(vec![
+                    RoadQuery::SlowerThan(speed).negate(),
+                    RoadQuery::FasterThan(speed).negate(),
+                ]),
+                _ => {
+                    return Err(format!("{} should compare with <, >, or =", word));
+                }
+            });
+        } else if let Some(idx) = word.find('=') {
+            conditions.push(RoadQuery::Tag(
+                word[..idx].to_string(),
+                word[idx + 1..].to_string(),
+            ));
+        } else {
+            conditions.push(RoadQuery::Tag(osm::HIGHWAY.to_string(), word.to_string()));
+        }
+    }
+    if conditions.is_empty() {
+        return Err("The filter is empty".to_string());
+    }
+    Ok(RoadQuery::And(conditions))
+}
@@ -16,7 +16,11 @@ use widgetry::{
 use crate::app::App;
 use crate::app::Transition;
 use crate::common::CommonState;
-use crate::edit::{apply_map_edits, check_sidewalk_connectivity, TrafficSignalEditor};
+use crate::edit::{
+    apply_map_edits, check_sidewalk_connectivity, CapacityEditor, CrosswalkEditor, NoteEditor,
+    TrafficSignalEditor,
+};
+use crate::helpers::ID;
 use crate::sandbox::GameplayMode;
 
 // TODO For now, individual turns can't be manipulated. Banning turns could be useful, but I'm not
@@ -62,6 +66,9 @@ impl StopSignEditor {
             },
             Btn::text_fg("close intersection for construction").build_def(ctx, Key::C),
             Btn::text_fg("convert to traffic signal").build_def(ctx, None),
+            Btn::text_fg("tune capacity").build_def(ctx, None),
+            Btn::text_fg("edit crosswalks").build_def(ctx, None),
+            Btn::text_fg("note about this intersection").build_def(ctx, Key::N),
             Btn::text_fg("Finish").build_def(ctx, Key::Escape),
         ]))
         .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
@@ -183,6 +190,15 @@ impl State<App> for StopSignEditor {
                         self.mode.clone(),
                     ));
                 }
+                "tune capacity" => {
+                    return Transition::Push(CapacityEditor::new(ctx, app, self.id));
+                }
+                "edit crosswalks" => {
+                    return Transition::Push(CrosswalkEditor::new(ctx, app, self.id));
+                }
+                "note about this intersection" => {
+                    return Transition::Push(NoteEditor::new(ctx, app, ID::Intersection(self.id)));
+                }
                 _ => unreachable!(),
             },
             _ => {}
@@ -0,0 +1,190 @@
+use geom::{Circle, Distance, PolyLine, Pt2D};
+use map_model::{RoadID, TrafficCalming, TrafficCalmingKind};
+use widgetry::{
+    Btn, Choice, Color, EventCtx, GfxCtx, HorizontalAlignment, Line, Outcome, Panel, State, Text,
+    TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::app::Transition;
+use crate::common::CommonState;
+use crate::edit::apply_map_edits;
+
+/// Add or remove speed humps, raised crossings, and chicanes along one road.
+pub struct TrafficCalmingEditor {
+    r: RoadID,
+    panel: Panel,
+    // Where a new feature would go, along the road's center
+    hovering: Option<Distance>,
+}
+
+impl TrafficCalmingEditor {
+    pub fn new(
+        ctx: &mut EventCtx,
+        app: &App,
+        r: RoadID,
+        kind: TrafficCalmingKind,
+    ) -> Box<dyn State<App>> {
+        let road = app.primary.map.get_r(r);
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Traffic calming").small_heading().draw(ctx),
+                Btn::close(ctx),
+            ]),
+            Line(road.get_name(app.opts.language.as_ref())).draw(ctx),
+            Widget::row(vec![
+                "Add a".draw_text(ctx).centered_vert(),
+                Widget::dropdown(
+                    ctx,
+                    "kind",
+                    kind,
+                    TrafficCalmingKind::all()
+                        .into_iter()
+                        .map(|k| Choice::new(k.describe(), k))
+                        .collect(),
+                ),
+            ]),
+            Text::from_multiline(vec![
+                Line("Click along the road to place it"),
+                if kind == TrafficCalmingKind::SpeedCamera {
+                    Line("Speeding drivers slow to the speed limit near it").secondary()
+                } else {
+                    Line(format!(
+                        "Vehicles slow to {} near it",
+                        kind.max_speed(road.speed_limit).to_string(&app.opts.units)
+                    ))
+                    .secondary()
+                },
+            ])
+            .draw(ctx),
+        ];
+        for (idx, tc) in road.traffic_calming.iter().enumerate() {
+            col.push(Widget::row(vec![
+                format!(
+                    "{}. {} {} from the start",
+                    idx + 1,
+                    tc.kind.describe(),
+                    tc.dist_along
+                )
+                .draw_text(ctx),
+                Btn::plaintext("remove")
+                    .build(ctx, format!("remove {}", idx + 1), None)
+                    .align_right(),
+            ]));
+        }
+
+        Box::new(TrafficCalmingEditor {
+            r,
+            panel: Panel::new(Widget::col(col))
+                .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+                .build(ctx),
+            hovering: None,
+        })
+    }
+
+    fn edit(
+        &self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        f: impl Fn(&mut Vec<TrafficCalming>),
+    ) -> Transition {
+        let mut edits = app.primary.map.get_edits().clone();
+        edits
+            .commands
+            .push(app.primary.map.edit_road_cmd(self.r, |new| {
+                f(&mut new.traffic_calming);
+                new.traffic_calming
+                    .sort_by(|a, b| a.dist_along.partial_cmp(&b.dist_along).unwrap());
+            }));
+        apply_map_edits(ctx, app, edits);
+        let kind = self.panel.dropdown_value("kind");
+        Transition::Replace(TrafficCalmingEditor::new(ctx, app, self.r, kind))
+    }
+}
+
+impl State<App> for TrafficCalmingEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if ctx.redo_mouseover() {
+            self.hovering = None;
+            if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
+                let road = app.primary.map.get_r(self.r);
+                if road.get_thick_polygon(&app.primary.map).contains_pt(pt) {
+                    self.hovering = Some(closest_dist_along(&road.center_pts, pt));
+                }
+            }
+        }
+        if let Some(dist_along) = self.hovering {
+            let kind: TrafficCalmingKind = self.panel.dropdown_value("kind");
+            if app
+                .per_obj
+                .left_click(ctx, format!("add a {} here", kind.describe()))
+            {
+                return self.edit(ctx, app, |list| {
+                    list.push(TrafficCalming { kind, dist_along });
+                });
+            }
+        }
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                x => {
+                    if let Some(idx) = x.strip_prefix("remove ") {
+                        let idx = idx.parse::<usize>().unwrap() - 1;
+                        return self.edit(ctx, app, |list| {
+                            list.remove(idx);
+                        });
+                    } else {
+                        unreachable!()
+                    }
+                }
+            },
+            Outcome::Changed => {
+                let kind = self.panel.dropdown_value("kind");
+                return Transition::Replace(TrafficCalmingEditor::new(ctx, app, self.r, kind));
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        let map = &app.primary.map;
+        let road = map.get_r(self.r);
+        g.draw_polygon(
+            app.cs.perma_selected_object.alpha(0.5),
+            road.get_thick_polygon(map),
+        );
+        if let Some(dist_along) = self.hovering {
+            let (pt, _) = road.center_pts.must_dist_along(dist_along);
+            let kind: TrafficCalmingKind = self.panel.dropdown_value("kind");
+            g.draw_polygon(
+                Color::CYAN.alpha(0.5),
+                Circle::new(pt, kind.slow_zone() / 2.0).to_polygon(),
+            );
+        }
+        self.panel.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+fn closest_dist_along(pl: &PolyLine, pt: Pt2D) -> Distance {
+    let mut best: Option<(Distance, Distance)> = None;
+    let mut dist_so_far = Distance::ZERO;
+    for line in pl.lines() {
+        let proj = line.project_pt(pt);
+        let dist_away = proj.dist_to(pt);
+        if best.map(|(d, _)| dist_away < d).unwrap_or(true) {
+            best = Some((dist_away, dist_so_far + line.pt1().dist_to(proj)));
+        }
+        dist_so_far += line.length();
+    }
+    best.map(|(_, d)| d)
+        .unwrap_or(Distance::ZERO)
+        .min(pl.length())
+}
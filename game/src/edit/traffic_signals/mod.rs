@@ -17,7 +17,8 @@ use widgetry::{
 
 use crate::app::{App, ShowEverything, Transition};
 use crate::common::{CommonState, Warping};
-use crate::edit::{apply_map_edits, ConfirmDiscard};
+use crate::edit::{apply_map_edits, CapacityEditor, ConfirmDiscard, NoteEditor};
+use crate::helpers::ID;
 use crate::sandbox::GameplayMode;
 
 mod edits;
@@ -207,6 +208,16 @@ impl State<App> for TrafficSignalEditor {
                         self.original.clone(),
                     ));
                 }
+                if x == "Tune capacity" {
+                    return Transition::Push(CapacityEditor::new(ctx, app, canonical_signal.id));
+                }
+                if x == "Note about this intersection" {
+                    return Transition::Push(NoteEditor::new(
+                        ctx,
+                        app,
+                        ID::Intersection(canonical_signal.id),
+                    ));
+                }
                 if x == "Tune offsets between signals" {
                     return Transition::Push(offsets::ShowAbsolute::new(
                         ctx,
@@ -611,6 +622,8 @@ fn make_side_panel(
 
     if members.len() == 1 {
         col.push(Btn::text_bg2("Edit entire signal").build_def(ctx, Key::E));
+        col.push(Btn::text_fg("Tune capacity").build_def(ctx, None));
+        col.push(Btn::text_fg("Note about this intersection").build_def(ctx, Key::N));
     } else {
         col.push(Btn::text_bg2("Tune offsets between signals").build_def(ctx, Key::O));
     }
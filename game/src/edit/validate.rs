@@ -11,7 +11,7 @@ use crate::common::ColorDiscrete;
 // All of these take a candidate EditCmd to do, then see if it's valid. If they return None, it's
 // fine. They always leave the map in the original state without the new EditCmd.
 
-// Could be caused by closing intersections
+// Could be caused by closing intersections or removing crosswalks
 pub fn check_sidewalk_connectivity(
     ctx: &mut EventCtx,
     app: &mut App,
@@ -51,7 +51,7 @@ pub fn check_sidewalk_connectivity(
         ctx,
         "Error",
         vec![format!(
-            "Can't close this intersection; {} sidewalks disconnected",
+            "Can't make this change; {} sidewalks disconnected",
             num
         )],
         unzoomed,
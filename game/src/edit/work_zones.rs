@@ -0,0 +1,206 @@
+use std::collections::BTreeSet;
+
+use geom::{Distance, Duration, Time};
+use map_model::{Direction, RoadID, WorkZone};
+use widgetry::{
+    Btn, EventCtx, Form, FormChecks, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, State,
+    TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::app::Transition;
+use crate::common::CommonState;
+use crate::edit::apply_map_edits;
+
+/// Close some lanes along part of one road for part of the day, for construction.
+pub struct WorkZoneEditor {
+    r: RoadID,
+    panel: Panel,
+    form: FormChecks,
+    // The checkbox for each lane, left to right
+    lane_labels: Vec<String>,
+}
+
+impl WorkZoneEditor {
+    pub fn new(ctx: &mut EventCtx, app: &App, r: RoadID) -> Box<dyn State<App>> {
+        let road = app.primary.map.get_r(r);
+        let road_len = road.center_pts.length().inner_meters().floor() as isize;
+
+        let lane_labels: Vec<String> = road
+            .lanes_ltr()
+            .into_iter()
+            .enumerate()
+            .map(|(idx, (_, dir, lt))| lane_label(idx, lt.describe(), dir))
+            .collect();
+        let mut form = Form::new();
+        for label in &lane_labels {
+            form = form.checkbox(ctx, label, false);
+        }
+        let labels = lane_labels.clone();
+        let (form_widget, form) = form
+            .spinner(
+                ctx,
+                "start",
+                "Starts this many meters along",
+                (0, road_len),
+                0,
+            )
+            .spinner(
+                ctx,
+                "end",
+                "Ends this many meters along",
+                (0, road_len),
+                road_len,
+            )
+            .spinner(ctx, "start hour", "Closed from hour", (0, 23), 9)
+            .spinner(ctx, "end hour", "Reopens at hour", (1, 24), 15)
+            .check(move |values| {
+                if labels.iter().all(|label| !values.checkbox(label)) {
+                    return Err("Pick at least one lane to close".to_string());
+                }
+                if values.spinner("start") >= values.spinner("end") {
+                    return Err("The work zone has to end after it starts".to_string());
+                }
+                if values.spinner("start hour") >= values.spinner("end hour") {
+                    return Err("The lanes have to reopen after they close".to_string());
+                }
+                Ok(())
+            })
+            .build(ctx);
+
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Work zones").small_heading().draw(ctx),
+                Btn::close(ctx),
+            ]),
+            Line(road.get_name(app.opts.language.as_ref())).draw(ctx),
+        ];
+        for (idx, wz) in road.work_zones.iter().enumerate() {
+            col.push(Widget::row(vec![
+                format!(
+                    "{}. {}, {} to {} from the start",
+                    idx + 1,
+                    wz.describe(),
+                    wz.start_dist,
+                    wz.end_dist
+                )
+                .draw_text(ctx),
+                Btn::plaintext("remove")
+                    .build(ctx, format!("remove {}", idx + 1), None)
+                    .align_right(),
+            ]));
+        }
+        col.push(
+            Line("Close lanes for construction")
+                .small_heading()
+                .draw(ctx),
+        );
+        col.push(form_widget);
+        col.push(Btn::text_bg2("Add work zone").build_def(ctx, Key::Enter));
+
+        let mut panel = Panel::new(Widget::col(col))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .build(ctx);
+        form.update(ctx, &mut panel);
+        Box::new(WorkZoneEditor {
+            r,
+            panel,
+            form,
+            lane_labels,
+        })
+    }
+
+    fn edit(
+        &self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        f: impl Fn(&mut Vec<WorkZone>),
+    ) -> Transition {
+        let mut edits = app.primary.map.get_edits().clone();
+        edits.commands.push(
+            app.primary
+                .map
+                .edit_road_cmd(self.r, |new| f(&mut new.work_zones)),
+        );
+        apply_map_edits(ctx, app, edits);
+        Transition::Replace(WorkZoneEditor::new(ctx, app, self.r))
+    }
+}
+
+impl State<App> for WorkZoneEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Add work zone" => {
+                    let wz = match self.form.values(&self.panel) {
+                        Ok(values) => WorkZone {
+                            closed_lanes: self
+                                .lane_labels
+                                .iter()
+                                .enumerate()
+                                .filter(|(_, label)| values.checkbox(label))
+                                .map(|(idx, _)| idx)
+                                .collect::<BTreeSet<_>>(),
+                            start_dist: Distance::meters(values.spinner("start") as f64),
+                            end_dist: Distance::meters(values.spinner("end") as f64),
+                            start_time: Time::START_OF_DAY
+                                + Duration::hours(values.spinner("start hour") as usize),
+                            end_time: Time::START_OF_DAY
+                                + Duration::hours(values.spinner("end hour") as usize),
+                        },
+                        Err(_) => {
+                            return Transition::Keep;
+                        }
+                    };
+                    return self.edit(ctx, app, |list| {
+                        list.push(wz.clone());
+                    });
+                }
+                x => {
+                    if let Some(idx) = x.strip_prefix("remove ") {
+                        let idx = idx.parse::<usize>().unwrap() - 1;
+                        return self.edit(ctx, app, |list| {
+                            list.remove(idx);
+                        });
+                    } else {
+                        unreachable!()
+                    }
+                }
+            },
+            Outcome::Changed => {
+                self.form.update(ctx, &mut self.panel);
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        let map = &app.primary.map;
+        let road = map.get_r(self.r);
+        g.draw_polygon(
+            app.cs.perma_selected_object.alpha(0.5),
+            road.get_thick_polygon(map),
+        );
+        self.panel.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+fn lane_label(idx: usize, lt: &str, dir: Direction) -> String {
+    format!(
+        "Close lane {} ({}, {})",
+        idx + 1,
+        lt,
+        match dir {
+            Direction::Fwd => "forwards",
+            Direction::Back => "backwards",
+        }
+    )
+}
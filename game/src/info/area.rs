@@ -0,0 +1,54 @@
+use map_model::{AreaID, AreaType};
+use widgetry::{EventCtx, Line, Widget};
+
+use crate::app::App;
+use crate::info::{header_btns, make_table, Details};
+
+pub fn info(ctx: &EventCtx, app: &App, _: &mut Details, id: AreaID) -> Vec<Widget> {
+    let mut rows = vec![];
+    let a = app.primary.map.get_a(id);
+
+    rows.push(Widget::row(vec![
+        Line(match a.area_type {
+            AreaType::Park => "Park",
+            AreaType::Water => "Water",
+            AreaType::Island => "Island",
+            AreaType::MedianStrip => "Median strip",
+            AreaType::PedestrianPlaza => "Pedestrian plaza",
+        })
+        .small_heading()
+        .draw(ctx),
+        header_btns(ctx),
+    ]));
+
+    let mut kv = Vec::new();
+    if let Some(name) = a.osm_tags.get("name") {
+        kv.push(("Name".to_string(), name.to_string()));
+    }
+    // Map-space is in meters
+    kv.push((
+        "Size".to_string(),
+        format!("{:.1} hectares", a.polygon.area() / 10_000.0),
+    ));
+    if app.opts.dev {
+        kv.push(("ID".to_string(), id.to_string()));
+        if let Some(osm_id) = a.osm_id {
+            kv.push(("OSM ID".to_string(), osm_id.to_string()));
+        }
+    }
+    rows.extend(make_table(ctx, kv));
+
+    if !a.osm_tags.inner().is_empty() {
+        rows.push(Line("OSM tags").small_heading().draw(ctx).margin_above(10));
+        rows.extend(make_table(
+            ctx,
+            a.osm_tags
+                .inner()
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        ));
+    }
+
+    rows
+}
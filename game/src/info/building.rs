@@ -19,6 +19,7 @@ pub fn info(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BuildingID
     if let Some(ref names) = b.name {
         kv.push(("Name", names.get(app.opts.language.as_ref()).to_string()));
     }
+    kv.push(("Use", b.bldg_use.to_string()));
     if app.opts.dev {
         kv.push(("OSM ID", format!("{}", b.orig_id.inner())));
     }
@@ -1,7 +1,7 @@
 use abstutil::{prettyprint_usize, Counter};
-use geom::{Circle, Distance, Time};
+use geom::{Circle, Distance, Duration, Time};
 use map_model::{BusRoute, BusRouteID, BusStopID, PathStep};
-use sim::{AgentID, CarID};
+use sim::{AgentID, CarID, Timetable};
 use widgetry::{Btn, Color, EventCtx, Key, Line, RewriteColor, Text, TextExt, Widget};
 
 use crate::app::App;
@@ -109,8 +109,9 @@ pub fn bus_status(ctx: &mut EventCtx, app: &App, details: &mut Details, id: CarI
 
     rows.push(
         Line(format!(
-            "Currently has {} passengers",
+            "Currently has {} / {} passengers",
             app.primary.sim.num_transit_passengers(id),
+            app.primary.sim.transit_capacity(id),
         ))
         .draw(ctx),
     );
@@ -235,6 +236,33 @@ pub fn route(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BusRouteI
         .draw(ctx),
     );
 
+    if let Some(reverse) = route.find_reverse(map) {
+        let adherence = app.primary.sim.get_schedule_adherence(id);
+        let late: Vec<Duration> = adherence
+            .iter()
+            .map(|(_, dt)| *dt)
+            .filter(|dt| *dt > Duration::ZERO)
+            .collect();
+        let mut txt = Text::from(Line(format!(
+            "At the last stop, {} lay over and continue as {}",
+            route.plural_noun(),
+            map.get_br(reverse).full_name
+        )));
+        txt.add(
+            Line(format!(
+                "{} departures after a layover so far, {} late",
+                prettyprint_usize(adherence.len()),
+                prettyprint_usize(late.len())
+            ))
+            .secondary(),
+        );
+        if !late.is_empty() {
+            let total: Duration = late.iter().fold(Duration::ZERO, |sum, dt| sum + *dt);
+            txt.add(Line(format!("Average delay: {}", total / (late.len() as f64))).secondary());
+        }
+        rows.push(txt.wrap_to_pct(ctx, 20).draw(ctx));
+    }
+
     rows.push(format!("{} stops", route.stops.len()).draw_text(ctx));
     {
         let i = map.get_i(map.get_l(route.start).src_i);
@@ -290,13 +318,18 @@ pub fn route(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BusRouteI
     {
         rows.push(Btn::text_fg("Edit schedule").build(ctx, format!("edit {}", route.id), Key::E));
         rows.push(describe_schedule(route).draw(ctx));
+        rows.push(Btn::text_fg("Export timetable").build(
+            ctx,
+            format!("export timetable for {}", route.id),
+            None,
+        ));
     }
 
     // Draw the route, label stops, and show location of buses
     {
         let mut colorer = ColorNetwork::new(app);
         for req in route.all_steps(map) {
-            for step in map.pathfind(req).unwrap().get_steps() {
+            for step in route.pathfind(req, map).unwrap().get_steps() {
                 if let PathStep::Lane(l) = step {
                     colorer.add_l(*l, app.cs.unzoomed_bus);
                 }
@@ -337,6 +370,34 @@ pub fn route(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BusRouteI
     rows
 }
 
+/// Writes the route's timetable as a CSV file and a GTFS fragment, using how long buses have taken
+/// so far. Returns the paths written.
+pub fn export_timetable(app: &App, id: BusRouteID) -> Result<Vec<String>, String> {
+    let map = &app.primary.map;
+    let route = map.get_br(id);
+    let timetable = app.primary.sim.get_analytics().timetable(route)?;
+    let name = format!(
+        "timetable_{}_{}_{}",
+        map.get_name().as_filename(),
+        // Route names may have slashes or spaces
+        route
+            .short_name
+            .replace(|c: char| !c.is_ascii_alphanumeric(), "_"),
+        app.primary.sim.time().as_filename()
+    );
+
+    let csv_path = format!("{}.csv", name);
+    std::fs::write(&csv_path, timetable.to_csv(map)).map_err(|err| err.to_string())?;
+    let mut paths = vec![csv_path];
+    std::fs::create_dir_all(&name).map_err(|err| err.to_string())?;
+    for (file, contents) in Timetable::to_gtfs(&[timetable], map) {
+        let path = format!("{}/{}", name, file);
+        std::fs::write(&path, contents).map_err(|err| err.to_string())?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
 // TODO Unit test
 fn describe_schedule(route: &BusRoute) -> Text {
     let mut txt = Text::new();
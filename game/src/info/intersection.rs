@@ -12,8 +12,8 @@ use widgetry::{
 };
 
 use crate::app::App;
-use crate::helpers::color_for_agent_type;
-use crate::info::{header_btns, make_tabs, throughput, DataOptions, Details, Tab};
+use crate::helpers::{color_for_agent_type, ID};
+use crate::info::{draw_note, header_btns, make_tabs, throughput, DataOptions, Details, Tab};
 
 pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: IntersectionID) -> Vec<Widget> {
     let mut rows = header(ctx, app, details, id, Tab::IntersectionInfo(id));
@@ -32,7 +32,30 @@ pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: IntersectionID
     for r in road_names {
         txt.add(Line(format!("  {}", r)));
     }
+    if i.rail_crossing {
+        txt.add(Line("Railroad tracks cross here"));
+    }
     rows.push(txt.draw(ctx));
+    if let Some(note) = draw_note(ctx, app, ID::Intersection(id)) {
+        rows.push(note);
+    }
+
+    for route in app.primary.map.get_ferry_routes_at(id) {
+        let mut txt = Text::from(Line(format!("Ferry terminal for {}", route.name)));
+        txt.add(Line(format!(
+            "  Leaves {} times a day, crossing takes {}",
+            route.departures.len(),
+            route.crossing_time
+        )));
+        txt.add(Line(match route.other_terminal(id) {
+            Some(other) => format!("  Arrives at border #{}", other.0),
+            None => "  Arrives off-map".to_string(),
+        }));
+        if !route.allows_vehicles {
+            txt.add(Line("  Pedestrians only"));
+        }
+        rows.push(txt.draw(ctx));
+    }
 
     if app.opts.dev {
         rows.push(Btn::text_bg1("Open OSM node").build(ctx, format!("open {}", i.orig_id), None));
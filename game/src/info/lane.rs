@@ -1,11 +1,15 @@
 use std::collections::HashSet;
 
 use abstutil::prettyprint_usize;
+use geom::Duration;
 use map_model::{LaneID, PathConstraints};
 use widgetry::{Btn, EventCtx, Line, LinePlot, PlotOptions, Series, Text, TextExt, Widget};
 
 use crate::app::App;
-use crate::info::{header_btns, make_table, make_tabs, throughput, DataOptions, Details, Tab};
+use crate::helpers::ID;
+use crate::info::{
+    draw_note, header_btns, make_table, make_tabs, throughput, DataOptions, Details, Tab,
+};
 
 pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Vec<Widget> {
     let mut rows = header(ctx, app, details, id, Tab::LaneInfo(id));
@@ -55,7 +59,34 @@ pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Vec
 
     kv.push(("Length", l.length().to_string(&app.opts.units)));
 
+    if r.is_movable_bridge() {
+        let delays = app
+            .primary
+            .sim
+            .get_analytics()
+            .bridge_delays
+            .get(&r.id)
+            .map(|list| list.iter().map(|(_, d, _)| *d).collect::<Vec<_>>())
+            .unwrap_or_default();
+        kv.push((
+            "Waited for the bridge",
+            if delays.is_empty() {
+                "nobody".to_string()
+            } else {
+                let total = delays.iter().fold(Duration::ZERO, |sum, d| sum + *d);
+                format!(
+                    "{} commuters and vehicles, {} on average",
+                    prettyprint_usize(delays.len()),
+                    total / (delays.len() as f64)
+                )
+            },
+        ));
+    }
+
     rows.extend(make_table(ctx, kv));
+    if let Some(note) = draw_note(ctx, app, ID::Road(r.id)) {
+        rows.push(note);
+    }
 
     if l.is_parking() {
         let capacity = l.number_parking_spots();
@@ -163,11 +194,10 @@ pub fn debug(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Ve
 
     rows.extend(make_table(ctx, kv));
 
-    rows.push(Btn::text_bg1("Open OSM way").build(
-        ctx,
-        format!("open {}", r.orig_id.osm_way_id),
-        None,
-    ));
+    rows.push(Widget::row(vec![
+        Btn::text_bg1("Open OSM way").build(ctx, format!("open {}", r.orig_id.osm_way_id), None),
+        Btn::text_bg1("Fix OSM tags").build(ctx, format!("fix OSM tags for {}", r.id), None),
+    ]));
 
     let mut txt = Text::from(Line(""));
     txt.add(Line("Raw OpenStreetMap data"));
@@ -227,6 +257,21 @@ pub fn traffic(
         },
         &opts,
     ));
+    rows.push(Btn::text_fg("Who uses this road?").build(
+        ctx,
+        format!("select link for {}", r),
+        None,
+    ));
+    rows.push(Btn::text_fg("Record vehicle trajectories").build(
+        ctx,
+        format!("trajectories for {}", r),
+        None,
+    ));
+    rows.push(Btn::text_fg("Time-space diagram").build(
+        ctx,
+        format!("time-space diagram for {}", r),
+        None,
+    ));
 
     rows
 }
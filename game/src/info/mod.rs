@@ -3,7 +3,10 @@ use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 pub use trip::OpenTrip;
 
 use geom::{Circle, Distance, Time};
-use map_model::{AreaID, BuildingID, BusRouteID, BusStopID, IntersectionID, LaneID, ParkingLotID};
+use map_gui::tools::PopupMsg;
+use map_model::{
+    AreaID, BuildingID, BusRouteID, BusStopID, IntersectionID, LaneID, ParkingLotID, RoadID,
+};
 use sim::{
     AgentID, AgentType, Analytics, CarID, ParkingSpot, PedestrianID, PersonID, PersonState, TripID,
     VehicleType,
@@ -17,13 +20,13 @@ use crate::app::App;
 use crate::app::Transition;
 use crate::common::Warping;
 use crate::debug::path_counter::PathCounter;
-use crate::edit::{EditMode, RouteEditor};
+use crate::edit::{EditMode, OsmTagEditor, RouteEditor};
 use crate::helpers::{color_for_agent_type, hotkey_btn, open_browser, ID};
 use crate::sandbox::{dashboards, GameplayMode, SandboxMode, TimeWarpScreen};
 
+mod area;
 mod building;
 mod bus;
-mod debug;
 mod intersection;
 mod lane;
 mod parking_lot;
@@ -338,7 +341,7 @@ impl InfoPanel {
             Tab::BldgPeople(b) => (building::people(ctx, app, &mut details, b), false),
             Tab::ParkingLot(pl) => (parking_lot::info(ctx, app, &mut details, pl), true),
             Tab::Crowd(ref members) => (person::crowd(ctx, app, &mut details, members), true),
-            Tab::Area(a) => (debug::area(ctx, app, &mut details, a), true),
+            Tab::Area(a) => (area::info(ctx, app, &mut details, a), true),
             Tab::IntersectionInfo(i) => (intersection::info(ctx, app, &mut details, i), true),
             Tab::IntersectionTraffic(i, ref opts) => (
                 intersection::traffic(ctx, app, &mut details, i, opts),
@@ -568,6 +571,29 @@ impl InfoPanel {
                             )),
                         ])),
                     )
+                } else if let Some(x) = action.strip_prefix("export timetable for BusRoute #") {
+                    let id = BusRouteID(x.parse::<usize>().unwrap());
+                    let popup = match bus::export_timetable(app, id) {
+                        Ok(paths) => PopupMsg::new(
+                            ctx,
+                            "Timetable exported",
+                            std::iter::once("Wrote:".to_string()).chain(paths).collect(),
+                        ),
+                        Err(err) => PopupMsg::new(ctx, "Can't export timetable", vec![err]),
+                    };
+                    (false, Some(Transition::Push(popup)))
+                } else if let Some(x) = action.strip_prefix("fix OSM tags for Road #") {
+                    (
+                        false,
+                        Some(Transition::Multi(vec![
+                            Transition::Push(EditMode::new(ctx, app, ctx_actions.gameplay_mode())),
+                            Transition::Push(OsmTagEditor::new(
+                                ctx,
+                                app,
+                                RoadID(x.parse::<usize>().unwrap()),
+                            )),
+                        ])),
+                    )
                 } else if action == "Explore demand across all traffic signals" {
                     (
                         false,
@@ -575,6 +601,33 @@ impl InfoPanel {
                             ctx, app,
                         ))),
                     )
+                } else if let Some(x) = action.strip_prefix("select link for Road #") {
+                    (
+                        false,
+                        Some(Transition::Push(dashboards::SelectLink::new(
+                            ctx,
+                            app,
+                            RoadID(x.parse::<usize>().unwrap()),
+                        ))),
+                    )
+                } else if let Some(x) = action.strip_prefix("trajectories for Road #") {
+                    (
+                        false,
+                        Some(Transition::Push(dashboards::Trajectories::new(
+                            ctx,
+                            app,
+                            RoadID(x.parse::<usize>().unwrap()),
+                        ))),
+                    )
+                } else if let Some(x) = action.strip_prefix("time-space diagram for Road #") {
+                    (
+                        false,
+                        Some(Transition::Push(dashboards::CorridorRecorder::new(
+                            ctx,
+                            app,
+                            RoadID(x.parse::<usize>().unwrap()),
+                        ))),
+                    )
                 } else if let Some(x) = action.strip_prefix("routes across Intersection #") {
                     (
                         false,
@@ -635,6 +688,19 @@ fn make_table<I: Into<String>>(ctx: &EventCtx, rows: Vec<(I, String)>) -> Vec<Wi
         .collect()
 }
 
+/// Shows the note that the current proposal attached to a road or intersection, if there is one.
+fn draw_note(ctx: &EventCtx, app: &App, id: ID) -> Option<Widget> {
+    let note = crate::edit::get_note(app, &id)?;
+    let mut col = vec![Line("Note from the proposal").small_heading().draw(ctx)];
+    if !note.text.is_empty() {
+        col.push(note.text.as_str().draw_text(ctx));
+    }
+    for link in &note.links {
+        col.push(Btn::text_fg(link).build(ctx, format!("open {}", link), None));
+    }
+    Some(Widget::col(col).bg(app.cs.inner_panel).padding(16))
+}
+
 fn throughput<F: Fn(&Analytics) -> Vec<(AgentType, Vec<(Time, usize)>)>>(
     ctx: &EventCtx,
     app: &App,
@@ -279,9 +279,18 @@ pub fn bio(
     rows.push(Widget::draw_batch(ctx, batch).centered_horiz());
 
     let nickname = petname::Petnames::default().generate(&mut rng, 2, " ");
-    let age = rng.gen_range(5, 100);
-
-    let mut table = vec![("Nickname", nickname), ("Age", age.to_string())];
+    let (min_age, max_age) = person.persona.ages();
+    let age = rng.gen_range(min_age, max_age);
+
+    let mut table = vec![
+        ("Nickname", nickname),
+        ("Age", age.to_string()),
+        ("Walking speed", person.ped_speed.to_string(&app.opts.units)),
+        (
+            "Willing to walk",
+            person.max_walking_distance.to_string(&app.opts.units),
+        ),
+    ];
     if app.opts.dev {
         table.push(("Debug ID", format!("{:?}", person.orig_id)));
     }
@@ -397,7 +406,22 @@ pub fn schedule(
         last_t = trip.departure;
     }
     // Where do they spend the night?
-    let last_trip = app.primary.sim.trip_info(*person.trips.last().unwrap());
+    let last_trip = match person.trips.last() {
+        Some(t) => app.primary.sim.trip_info(*t),
+        None => {
+            // Somebody who never leaves
+            if let PersonState::Inside(b) = person.state {
+                rows.push(
+                    format!(
+                        "  Spends the whole day at {}",
+                        app.primary.map.get_b(b).address
+                    )
+                    .draw_text(ctx),
+                );
+            }
+            return rows;
+        }
+    };
     let at = match last_trip.end {
         TripEndpoint::Bldg(b) => {
             let b = app.primary.map.get_b(b);
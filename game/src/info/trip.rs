@@ -98,7 +98,20 @@ pub fn ongoing(
                 .container()
                 .force_width_pct(ctx, col_width),
             Widget::col(vec![
-                format!("{} here", props.waiting_here.to_string(&app.opts.units)).draw_text(ctx),
+                match app
+                    .primary
+                    .sim
+                    .get_blocked_on(&app.primary.map)
+                    .remove(&agent)
+                {
+                    Some((_, blocked)) => format!(
+                        "{} here, {}",
+                        props.waiting_here.to_string(&app.opts.units),
+                        blocked
+                    ),
+                    None => format!("{} here", props.waiting_here.to_string(&app.opts.units)),
+                }
+                .draw_text(ctx),
                 Text::from_all(vec![
                     if props.total_waiting != Duration::ZERO {
                         Line(format!(
@@ -0,0 +1,159 @@
+use std::collections::HashSet;
+
+use abstutil::prettyprint_usize;
+use geom::{Circle, Distance, Polygon, Pt2D, Time};
+use map_model::{BuildingID, IntersectionID};
+use sim::TripEndpoint;
+use widgetry::{
+    Btn, Checkbox, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line,
+    Outcome, Panel, Text, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::layer::{Layer, LayerOutcome};
+
+/// Highlights everybody currently moving whose trip started (or will end) in one block, as grouped
+/// by the commuter patterns dashboard.
+pub struct PeopleFromBlock {
+    time: Time,
+    bldgs: HashSet<BuildingID>,
+    borders: HashSet<IntersectionID>,
+    shape: Polygon,
+    // If false, then trips to this block
+    from_block: bool,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+    panel: Panel,
+}
+
+impl Layer for PeopleFromBlock {
+    fn name(&self) -> Option<&'static str> {
+        None
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Panel,
+    ) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            let mut new = PeopleFromBlock::new(
+                ctx,
+                app,
+                self.bldgs.clone(),
+                self.borders.clone(),
+                self.shape.clone(),
+                self.from_block,
+            );
+            new.panel.restore(ctx, &self.panel);
+            *self = new;
+        }
+
+        self.panel.align_above(ctx, minimap);
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                _ => unreachable!(),
+            },
+            _ => {
+                let from_block = self.panel.is_checked("from / to this block");
+                if from_block != self.from_block {
+                    *self = PeopleFromBlock::new(
+                        ctx,
+                        app,
+                        self.bldgs.clone(),
+                        self.borders.clone(),
+                        self.shape.clone(),
+                        from_block,
+                    );
+                    self.panel.align_above(ctx, minimap);
+                }
+            }
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.panel.draw(g);
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.unzoomed);
+    }
+}
+
+impl PeopleFromBlock {
+    pub fn new(
+        ctx: &mut EventCtx,
+        app: &App,
+        bldgs: HashSet<BuildingID>,
+        borders: HashSet<IntersectionID>,
+        shape: Polygon,
+        from_block: bool,
+    ) -> PeopleFromBlock {
+        let mut unzoomed = GeomBatch::new();
+        let mut zoomed = GeomBatch::new();
+        unzoomed.push(Color::PURPLE.alpha(0.5), shape.clone());
+        zoomed.push(Color::PURPLE.alpha(0.3), shape.clone());
+
+        let sim = &app.primary.sim;
+        let unzoomed_circle = Circle::new(Pt2D::new(0.0, 0.0), Distance::meters(10.0)).to_polygon();
+        let zoomed_circle = Circle::new(Pt2D::new(0.0, 0.0), Distance::meters(3.0)).to_polygon();
+        let mut cnt = 0;
+        for a in sim.get_unzoomed_agents(&app.primary.map) {
+            let trip = match sim.agent_to_trip(a.id) {
+                Some(t) => sim.trip_info(t),
+                // Buses and trains
+                None => continue,
+            };
+            let matches = match if from_block { trip.start } else { trip.end } {
+                TripEndpoint::Bldg(b) => bldgs.contains(&b),
+                TripEndpoint::Border(i) => borders.contains(&i),
+                TripEndpoint::SuddenlyAppear(_) => false,
+            };
+            if matches {
+                cnt += 1;
+                unzoomed.push(
+                    Color::YELLOW,
+                    unzoomed_circle.translate(a.pos.x(), a.pos.y()),
+                );
+                zoomed.push(
+                    Color::YELLOW.alpha(0.8),
+                    zoomed_circle.translate(a.pos.x(), a.pos.y()),
+                );
+            }
+        }
+
+        let panel = Panel::new(Widget::col(vec![
+            Widget::row(vec![
+                Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+                "People from a block".draw_text(ctx),
+                Btn::close(ctx),
+            ]),
+            Checkbox::toggle(ctx, "from / to this block", "from", "to", None, from_block),
+            Text::from(Line(format!(
+                "{} people currently on the move",
+                prettyprint_usize(cnt)
+            )))
+            .draw(ctx),
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+
+        PeopleFromBlock {
+            time: app.primary.sim.time(),
+            bldgs,
+            borders,
+            shape,
+            from_block,
+            unzoomed: ctx.upload(unzoomed),
+            zoomed: ctx.upload(zoomed),
+            panel,
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::BTreeSet;
+
+use abstutil::{prettyprint_usize, Counter};
+use geom::Time;
+use sim::TripMode;
+use widgetry::{
+    Btn, Checkbox, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line,
+    Outcome, Panel, Slider, Text, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::common::{BlockID, Blocks};
+use crate::helpers::checkbox_per_mode;
+use crate::layer::{Layer, LayerOutcome};
+
+/// Arrows between neighborhood blocks, scaled by the number of trips between them.
+pub struct DesireLines {
+    time: Time,
+    opts: Options,
+    blocks: Blocks,
+    draw: Drawable,
+    panel: Panel,
+}
+
+#[derive(Clone, PartialEq)]
+struct Options {
+    // If false, only trips that have started in the simulation so far
+    from_scenario: bool,
+    modes: BTreeSet<TripMode>,
+    depart_from: Time,
+    depart_until: Time,
+}
+
+impl Layer for DesireLines {
+    fn name(&self) -> Option<&'static str> {
+        Some("desire lines")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Panel,
+    ) -> Option<LayerOutcome> {
+        // The scenario doesn't change over time
+        if app.primary.sim.time() != self.time && !self.opts.from_scenario {
+            self.recalculate(ctx, app);
+        }
+
+        self.panel.align_above(ctx, minimap);
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                _ => unreachable!(),
+            },
+            _ => {
+                let new_opts = self.options(app);
+                if self.opts != new_opts {
+                    self.opts = new_opts;
+                    self.recalculate(ctx, app);
+                    self.panel.align_above(ctx, minimap);
+                }
+            }
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        self.panel.draw(g);
+        g.redraw(&self.draw);
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.draw);
+    }
+}
+
+impl DesireLines {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> DesireLines {
+        let blocks = ctx.loading_screen("group buildings into blocks", |_, _| Blocks::new(app));
+        let opts = Options {
+            from_scenario: true,
+            modes: TripMode::all().into_iter().collect(),
+            depart_from: Time::START_OF_DAY,
+            depart_until: app.primary.sim.get_end_of_day(),
+        };
+        let mut layer = DesireLines {
+            time: app.primary.sim.time(),
+            panel: make_panel(ctx, app, &opts),
+            opts,
+            blocks,
+            draw: ctx.upload(GeomBatch::new()),
+        };
+        layer.recalculate(ctx, app);
+        layer
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        let sim = &app.primary.sim;
+        let started_trips = &sim.get_analytics().started_trips;
+        let mut flows: Counter<(BlockID, BlockID)> = Counter::new();
+        let mut total = 0;
+        for (id, trip) in sim.all_trip_info() {
+            if !self.opts.from_scenario && !started_trips.contains_key(&id) {
+                continue;
+            }
+            if trip.departure < self.opts.depart_from
+                || trip.departure > self.opts.depart_until
+                || !self.opts.modes.contains(&trip.mode)
+            {
+                continue;
+            }
+            total += 1;
+            if let (Some(from), Some(to)) = (
+                self.blocks.lookup(&trip.start),
+                self.blocks.lookup(&trip.end),
+            ) {
+                flows.inc((from, to));
+            }
+        }
+        let within_blocks: usize = flows
+            .borrow()
+            .iter()
+            .filter(|((from, to), _)| from == to)
+            .map(|(_, cnt)| *cnt)
+            .sum();
+
+        let mut txt = Text::from(Line(format!("{} trips", prettyprint_usize(total))));
+        txt.add(
+            Line(format!(
+                "{} start and end in the same neighborhood, and aren't shown",
+                prettyprint_usize(within_blocks)
+            ))
+            .secondary(),
+        );
+
+        self.time = sim.time();
+        self.draw = ctx.upload(self.blocks.draw_desire_lines(&flows, Color::PURPLE));
+        self.panel.replace(ctx, "summary", txt.draw(ctx));
+    }
+
+    fn options(&self, app: &App) -> Options {
+        let end_of_day = app.primary.sim.get_end_of_day();
+        let mut modes = BTreeSet::new();
+        for m in TripMode::all() {
+            if self.panel.is_checked(m.ongoing_verb()) {
+                modes.insert(m);
+            }
+        }
+        Options {
+            from_scenario: self.panel.is_checked("scenario / simulated"),
+            modes,
+            depart_from: end_of_day.percent_of(self.panel.slider("depart from").get_percent()),
+            depart_until: end_of_day.percent_of(self.panel.slider("depart until").get_percent()),
+        }
+    }
+}
+
+fn make_panel(ctx: &mut EventCtx, app: &App, opts: &Options) -> Panel {
+    let end_of_day = app.primary.sim.get_end_of_day();
+    let pct = |t: Time| (t - Time::START_OF_DAY) / (end_of_day - Time::START_OF_DAY);
+
+    Panel::new(Widget::col(vec![
+        Widget::row(vec![
+            Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+            "Desire lines".draw_text(ctx),
+            Btn::close(ctx),
+        ]),
+        Checkbox::toggle(
+            ctx,
+            "scenario / simulated",
+            "scenario",
+            "simulated",
+            None,
+            opts.from_scenario,
+        ),
+        Widget::row(vec![
+            "Departing from:".draw_text(ctx).margin_right(20),
+            Slider::area(ctx, 0.15 * ctx.canvas.window_width, pct(opts.depart_from))
+                .named("depart from"),
+        ]),
+        Widget::row(vec![
+            "Departing until:".draw_text(ctx).margin_right(20),
+            Slider::area(ctx, 0.15 * ctx.canvas.window_width, pct(opts.depart_until))
+                .named("depart until"),
+        ]),
+        checkbox_per_mode(ctx, app, &opts.modes),
+        Text::new().draw(ctx).named("summary"),
+    ]))
+    .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+    .build(ctx)
+}
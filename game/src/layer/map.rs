@@ -284,18 +284,55 @@ impl Static {
     }
 
     pub fn no_sidewalks(ctx: &mut EventCtx, app: &App) -> Static {
-        let mut colorer = ColorDiscrete::new(app, vec![("no sidewalks", Color::RED)]);
-        for l in app.primary.map.all_lanes() {
-            if l.is_shoulder() {
-                colorer.add_r(l.parent, "no sidewalks");
+        let map = &app.primary.map;
+        let mut colorer = ColorDiscrete::new(
+            app,
+            vec![
+                ("no sidewalks", Color::RED),
+                ("sidewalk on one side", Color::YELLOW),
+            ],
+        );
+        // Pedestrians fall back to walking along the shoulder of these roads
+        let mut num_roads = 0;
+        let mut length = Distance::ZERO;
+        for r in map.all_roads() {
+            let shoulders: Vec<_> = r
+                .all_lanes()
+                .into_iter()
+                .filter(|l| map.get_l(*l).is_shoulder())
+                .collect();
+            if shoulders.is_empty() {
+                continue;
+            }
+            let category = if r
+                .all_lanes()
+                .into_iter()
+                .any(|l| map.get_l(l).is_sidewalk())
+            {
+                "sidewalk on one side"
+            } else {
+                "no sidewalks"
+            };
+            for l in shoulders {
+                colorer.add_l(l, category);
             }
+            num_roads += 1;
+            length += r.center_pts.length();
         }
         Static::new(
             ctx,
             colorer,
             "no sidewalks",
             "No sidewalks".to_string(),
-            Widget::nothing(),
+            Text::from_multiline(vec![
+                Line(format!(
+                    "{} roads ({}) rely on walking along the shoulder",
+                    prettyprint_usize(num_roads),
+                    length
+                )),
+                Line("Pedestrians avoid shoulders and walk more slowly on them").secondary(),
+            ])
+            .draw(ctx),
         )
     }
 
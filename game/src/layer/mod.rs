@@ -8,6 +8,8 @@ use crate::app::Transition;
 use crate::helpers::{grey_out_map, hotkey_btn};
 use crate::sandbox::dashboards;
 
+pub mod block_people;
+mod desire_lines;
 mod elevation;
 pub mod map;
 mod pandemic;
@@ -108,6 +110,9 @@ impl PickLayer {
                     btn("delay", Key::D),
                     btn("throughput", Key::T),
                     btn("traffic jams", Key::J),
+                    btn("lane speeds", Key::G),
+                    btn("spillback", Key::K),
+                    btn("congestion replay", Key::H),
                 ]),
                 Widget::col(vec![
                     "Map".draw_text(ctx),
@@ -142,6 +147,7 @@ impl PickLayer {
                     "Data".draw_text(ctx),
                     btn("traffic signal demand", Key::M),
                     btn("commuter patterns", Key::R),
+                    btn("desire lines", Key::I),
                 ]),
             ])
             .evenly_spaced(),
@@ -163,78 +169,15 @@ impl State<App> for PickLayer {
                 "None" => {
                     app.primary.layer = None;
                 }
-                "amenities" => {
-                    app.primary.layer = Some(Box::new(map::Static::amenities(ctx, app)));
-                }
-                "backpressure" => {
-                    app.primary.layer = Some(Box::new(traffic::Backpressure::new(ctx, app)));
-                }
-                "bike network" => {
-                    app.primary.layer = Some(Box::new(map::BikeNetwork::new(ctx, app)));
-                }
-                "delay" => {
-                    app.primary.layer = Some(Box::new(traffic::Delay::new(ctx, app)));
-                }
-                "elevation" => {
-                    app.primary.layer = Some(Box::new(elevation::Elevation::new(ctx, app)));
-                }
-                "map edits" => {
-                    app.primary.layer = Some(Box::new(map::Static::edits(ctx, app)));
-                }
-                "no sidewalks" => {
-                    app.primary.layer = Some(Box::new(map::Static::no_sidewalks(ctx, app)));
-                }
-                "pandemic model" => {
-                    app.primary.layer = Some(Box::new(pandemic::Pandemic::new(
-                        ctx,
-                        app,
-                        pandemic::Options {
-                            heatmap: Some(HeatmapOptions::new()),
-                            state: pandemic::SEIR::Infected,
-                        },
-                    )));
-                }
-                "blackholes" => {
-                    app.primary.layer = Some(Box::new(map::Static::blackholes(ctx, app)));
-                }
-                "congestion caps" => {
-                    app.primary.layer = Some(Box::new(map::CongestionCaps::new(ctx, app)));
-                }
-                "parking occupancy" => {
-                    app.primary.layer = Some(Box::new(parking::Occupancy::new(
-                        ctx, app, true, true, true, false, true,
-                    )));
-                }
-                "parking efficiency" => {
-                    app.primary.layer = Some(Box::new(parking::Efficiency::new(ctx, app)));
-                }
-                "population map" => {
-                    app.primary.layer = Some(Box::new(population::PopulationMap::new(
-                        ctx,
-                        app,
-                        population::Options {
-                            heatmap: Some(HeatmapOptions::new()),
-                        },
-                    )));
-                }
-                "throughput" => {
-                    app.primary.layer = Some(Box::new(traffic::Throughput::new(ctx, app)));
-                }
-                "traffic jams" => {
-                    app.primary.layer = Some(Box::new(traffic::TrafficJams::new(ctx, app)));
-                }
-                "transit network" => {
-                    app.primary.layer = Some(Box::new(transit::TransitNetwork::new(
-                        ctx, app, false, true, true,
-                    )));
-                }
                 "traffic signal demand" => {
                     return Transition::Replace(dashboards::TrafficSignalDemand::new(ctx, app));
                 }
                 "commuter patterns" => {
                     return Transition::Replace(dashboards::CommuterPatterns::new(ctx, app));
                 }
-                _ => unreachable!(),
+                name => {
+                    app.primary.layer = Some(layer_by_name(ctx, app, name).unwrap());
+                }
             },
             _ => {
                 if self.panel.clicked_outside(ctx) {
@@ -255,3 +198,49 @@ impl State<App> for PickLayer {
         self.panel.draw(g);
     }
 }
+
+/// Creates one of the layers listed in `PickLayer` by name, if it exists. Dashboards aren't
+/// layers.
+pub fn layer_by_name(ctx: &mut EventCtx, app: &mut App, name: &str) -> Option<Box<dyn Layer>> {
+    let layer: Box<dyn Layer> = match name {
+        "amenities" => Box::new(map::Static::amenities(ctx, app)),
+        "backpressure" => Box::new(traffic::Backpressure::new(ctx, app)),
+        "bike network" => Box::new(map::BikeNetwork::new(ctx, app)),
+        "delay" => Box::new(traffic::Delay::new(ctx, app)),
+        "desire lines" => Box::new(desire_lines::DesireLines::new(ctx, app)),
+        "elevation" => Box::new(elevation::Elevation::new(ctx, app)),
+        "map edits" => Box::new(map::Static::edits(ctx, app)),
+        "no sidewalks" => Box::new(map::Static::no_sidewalks(ctx, app)),
+        "pandemic model" => Box::new(pandemic::Pandemic::new(
+            ctx,
+            app,
+            pandemic::Options {
+                heatmap: Some(HeatmapOptions::new()),
+                state: pandemic::SEIR::Infected,
+            },
+        )),
+        "blackholes" => Box::new(map::Static::blackholes(ctx, app)),
+        "congestion caps" => Box::new(map::CongestionCaps::new(ctx, app)),
+        "parking occupancy" => Box::new(parking::Occupancy::new(
+            ctx, app, true, true, true, false, true,
+        )),
+        "parking efficiency" => Box::new(parking::Efficiency::new(ctx, app)),
+        "population map" => Box::new(population::PopulationMap::new(
+            ctx,
+            app,
+            population::Options {
+                heatmap: Some(HeatmapOptions::new()),
+            },
+        )),
+        "throughput" => Box::new(traffic::Throughput::new(ctx, app)),
+        "traffic jams" => Box::new(traffic::TrafficJams::new(ctx, app)),
+        "lane speeds" => Box::new(traffic::LaneSpeeds::new(ctx, app)),
+        "spillback" => Box::new(traffic::Spillback::new(ctx, app)),
+        "congestion replay" => Box::new(traffic::CongestionReplay::new(ctx, app)),
+        "transit network" => Box::new(transit::TransitNetwork::new(ctx, app, false, true, true)),
+        _ => {
+            return None;
+        }
+    };
+    Some(layer)
+}
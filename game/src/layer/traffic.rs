@@ -1,15 +1,16 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
+use instant::Instant;
 use maplit::btreeset;
 
 use abstutil::{prettyprint_usize, Counter};
 use geom::{Circle, Distance, Duration, Polygon, Pt2D, Time};
 use map_gui::render::unzoomed_agent_radius;
-use map_model::{IntersectionID, Map, Traversable};
+use map_model::{IntersectionID, LaneID, Map, RoadID, Traversable};
 use sim::VehicleType;
 use widgetry::{
     Btn, Checkbox, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line,
-    Outcome, Panel, Text, TextExt, VerticalAlignment, Widget,
+    Outcome, Panel, Slider, Text, TextExt, UpdateType, VerticalAlignment, Widget,
 };
 
 use crate::app::App;
@@ -583,3 +584,379 @@ impl Delay {
         }
     }
 }
+
+/// Colors lanes by how fast vehicles recently crossed them, compared to the speed limit.
+pub struct LaneSpeeds {
+    time: Time,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+    panel: Panel,
+}
+
+impl Layer for LaneSpeeds {
+    fn name(&self) -> Option<&'static str> {
+        Some("lane speeds")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Panel,
+    ) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = LaneSpeeds::new(ctx, app);
+        }
+
+        Layer::simple_event(ctx, minimap, &mut self.panel)
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.panel.draw(g);
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.unzoomed);
+    }
+}
+
+impl LaneSpeeds {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> LaneSpeeds {
+        let map = &app.primary.map;
+        let speeds = app
+            .primary
+            .sim
+            .get_analytics()
+            .recent_lane_speeds(app.primary.sim.time());
+
+        let mut colorer = ColorNetwork::new(app);
+        // When unzoomed, show the slowest direction of each road
+        let mut slowest_per_road: BTreeMap<RoadID, f64> = BTreeMap::new();
+        for (l, pct) in &speeds {
+            let lane = map.get_l(*l);
+            colorer.zoomed.push(
+                speed_color(*pct).alpha(0.4),
+                lane.lane_center_pts.make_polygons(lane.width),
+            );
+            let slowest = slowest_per_road.entry(lane.parent).or_insert(*pct);
+            *slowest = slowest.min(*pct);
+        }
+        for (r, pct) in slowest_per_road {
+            colorer
+                .unzoomed
+                .push(speed_color(pct), map.get_r(r).get_thick_polygon(map));
+        }
+        let (unzoomed, zoomed) = colorer.build(ctx);
+
+        let panel = Panel::new(Widget::col(vec![
+            Widget::row(vec![
+                Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+                "Lane speeds".draw_text(ctx),
+                Btn::close(ctx),
+            ]),
+            Text::from(
+                Line("Average speed of vehicles over the last 5 minutes, compared to the speed limit")
+                    .secondary(),
+            )
+            .wrap_to_pct(ctx, 15)
+            .draw(ctx),
+            format!("{} lanes measured", prettyprint_usize(speeds.len())).draw_text(ctx),
+            ColorLegend::row(ctx, speed_color(1.0), "free-flowing (80%+)"),
+            ColorLegend::row(ctx, speed_color(0.6), "slow (50-80%)"),
+            ColorLegend::row(ctx, speed_color(0.0), "congested (under 50%)"),
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+
+        LaneSpeeds {
+            time: app.primary.sim.time(),
+            unzoomed,
+            zoomed,
+            panel,
+        }
+    }
+}
+
+/// Like navigation apps, use only a few colors to describe the fraction of free-flow speed.
+fn speed_color(pct: f64) -> Color {
+    if pct >= 0.8 {
+        Color::GREEN
+    } else if pct >= 0.5 {
+        Color::YELLOW
+    } else {
+        Color::RED
+    }
+}
+
+/// Replays how congested each road was at any minute of the simulation so far, using speeds
+/// recorded in Analytics.
+pub struct CongestionReplay {
+    time: Time,
+    // If playing, the real time when playback started, and the simulated time at that moment
+    playing: Option<(Instant, Time)>,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+    panel: Panel,
+}
+
+impl Layer for CongestionReplay {
+    fn name(&self) -> Option<&'static str> {
+        Some("congestion replay")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Panel,
+    ) -> Option<LayerOutcome> {
+        let end = app.primary.sim.time();
+        if let Some((started, from)) = self.playing {
+            let time = from + REPLAY_SPEED * Duration::realtime_elapsed(started);
+            if time >= end {
+                self.playing = None;
+                self.change_time(ctx, app, end);
+            } else {
+                self.change_time(ctx, app, time);
+                ctx.request_update(UpdateType::Game);
+            }
+        }
+
+        self.panel.align_above(ctx, minimap);
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                "play" => {
+                    let from = if self.time >= end {
+                        Time::START_OF_DAY
+                    } else {
+                        self.time
+                    };
+                    self.playing = Some((Instant::now(), from));
+                    ctx.request_update(UpdateType::Game);
+                }
+                "pause" => {
+                    self.playing = None;
+                    self.change_time(ctx, app, self.time);
+                }
+                _ => unreachable!(),
+            },
+            _ => {
+                let time = end.percent_of(self.panel.slider("time").get_percent());
+                if self.playing.is_none()
+                    && time.max(self.time) - time.min(self.time) >= Duration::minutes(1)
+                {
+                    self.change_time(ctx, app, time);
+                }
+            }
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.panel.draw(g);
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.unzoomed);
+    }
+}
+
+// How much simulated time passes per second of real time during playback
+const REPLAY_SPEED: f64 = 600.0;
+
+impl CongestionReplay {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> CongestionReplay {
+        let mut layer = CongestionReplay {
+            time: Time::START_OF_DAY,
+            playing: None,
+            unzoomed: ctx.upload(GeomBatch::new()),
+            zoomed: ctx.upload(GeomBatch::new()),
+            panel: Panel::empty(ctx),
+        };
+        layer.change_time(ctx, app, Time::START_OF_DAY);
+        layer
+    }
+
+    fn change_time(&mut self, ctx: &mut EventCtx, app: &App, time: Time) {
+        let mut colorer = ColorNetwork::new(app);
+        for (r, pct) in app.primary.sim.get_analytics().road_speed_ratios_at(time) {
+            colorer.add_r(r, speed_color(pct));
+        }
+        let (unzoomed, zoomed) = colorer.build(ctx);
+        self.time = time;
+        self.unzoomed = unzoomed;
+        self.zoomed = zoomed;
+
+        let end = app.primary.sim.time();
+        self.panel = Panel::new(Widget::col(vec![
+            Widget::row(vec![
+                Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+                "Congestion replay".draw_text(ctx),
+                Btn::close(ctx),
+            ]),
+            Text::from(
+                Line("Average speed of vehicles on each road, compared to the speed limit")
+                    .secondary(),
+            )
+            .wrap_to_pct(ctx, 15)
+            .draw(ctx),
+            Widget::row(vec![
+                time.ampm_tostring().draw_text(ctx),
+                if self.playing.is_some() {
+                    Btn::text_bg2("pause").build_def(ctx, None)
+                } else {
+                    Btn::text_bg2("play").build_def(ctx, None)
+                },
+            ]),
+            Slider::area(
+                ctx,
+                0.15 * ctx.canvas.window_width,
+                if end == Time::START_OF_DAY {
+                    0.0
+                } else {
+                    time.to_percent(end).min(1.0)
+                },
+            )
+            .named("time"),
+            ColorLegend::row(ctx, speed_color(1.0), "free-flowing (80%+)"),
+            ColorLegend::row(ctx, speed_color(0.6), "slow (50-80%)"),
+            ColorLegend::row(ctx, speed_color(0.0), "congested (under 50%)"),
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+    }
+}
+
+/// Colors lanes by how long their queue has backed up into the upstream intersection, over the
+/// whole simulation so far. Lanes backed up right now are outlined.
+pub struct Spillback {
+    time: Time,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+    panel: Panel,
+}
+
+impl Layer for Spillback {
+    fn name(&self) -> Option<&'static str> {
+        Some("spillback")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Panel,
+    ) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = Spillback::new(ctx, app);
+        }
+
+        Layer::simple_event(ctx, minimap, &mut self.panel)
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.panel.draw(g);
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.unzoomed);
+    }
+}
+
+impl Spillback {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Spillback {
+        let map = &app.primary.map;
+        let now = app.primary.sim.time();
+
+        let mut totals: BTreeMap<LaneID, Duration> = BTreeMap::new();
+        for (l, list) in &app.primary.sim.get_analytics().spillbacks {
+            totals.insert(
+                *l,
+                list.iter().fold(Duration::ZERO, |sum, (_, dt)| sum + *dt),
+            );
+        }
+        let current = app.primary.sim.current_spillbacks();
+        for (l, since) in &current {
+            *totals.entry(*l).or_insert(Duration::ZERO) += now - *since;
+        }
+
+        let color = |total: Duration| {
+            app.cs
+                .good_to_bad_red
+                .eval((total / Duration::minutes(30)).min(1.0))
+        };
+        let mut colorer = ColorNetwork::new(app);
+        // When unzoomed, show the worst lane of each road
+        let mut worst_per_road: BTreeMap<RoadID, Duration> = BTreeMap::new();
+        for (l, total) in &totals {
+            let lane = map.get_l(*l);
+            colorer.zoomed.push(
+                color(*total).alpha(0.4),
+                lane.lane_center_pts.make_polygons(lane.width),
+            );
+            let worst = worst_per_road.entry(lane.parent).or_insert(*total);
+            if *total > *worst {
+                *worst = *total;
+            }
+        }
+        for (r, total) in worst_per_road {
+            colorer
+                .unzoomed
+                .push(color(total), map.get_r(r).get_thick_polygon(map));
+        }
+        // The queue reaches back into this intersection right now
+        for (l, _) in &current {
+            let i = map.get_i(map.get_l(*l).src_i);
+            if let Ok(outline) = i.polygon.to_outline(Distance::meters(2.0)) {
+                colorer.zoomed.push(Color::RED, outline);
+            }
+            if let Ok(outline) = i.polygon.to_outline(Distance::meters(5.0)) {
+                colorer.unzoomed.push(Color::RED, outline);
+            }
+        }
+        let (unzoomed, zoomed) = colorer.build(ctx);
+
+        let panel = Panel::new(Widget::col(vec![
+            Widget::row(vec![
+                Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+                "Spillback".draw_text(ctx),
+                Btn::close(ctx),
+            ]),
+            Text::from(
+                Line(
+                    "Total time each lane's queue backed up into the intersection before it. \
+                     Outlined intersections are blocked right now.",
+                )
+                .secondary(),
+            )
+            .wrap_to_pct(ctx, 15)
+            .draw(ctx),
+            format!(
+                "{} lanes backed up so far, {} right now",
+                prettyprint_usize(totals.len()),
+                prettyprint_usize(current.len())
+            )
+            .draw_text(ctx),
+            ColorLegend::gradient(ctx, &app.cs.good_to_bad_red, vec!["0", "10", "20", "30+"]),
+            "(minutes)".draw_text(ctx),
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+
+        Spillback {
+            time: now,
+            unzoomed,
+            zoomed,
+            panel,
+        }
+    }
+}
@@ -2,13 +2,15 @@
 extern crate log;
 
 use abstutil::CmdArgs;
+use geom::Time;
 use map_gui::options::Options;
 use sim::SimFlags;
 use widgetry::{EventCtx, State};
 
-use crate::app::{App, Flags};
+use crate::app::{App, Flags, Transition};
 use crate::pregame::TitleScreen;
-use crate::sandbox::{GameplayMode, SandboxMode};
+use crate::sandbox::{GameplayMode, SandboxMode, TimeWarpScreen};
+use crate::screenshot::ScreenshotFlags;
 
 mod app;
 mod challenges;
@@ -22,6 +24,7 @@ mod info;
 mod layer;
 mod pregame;
 mod sandbox;
+mod screenshot;
 
 pub fn main(mut args: CmdArgs) {
     if args.enabled("--prebake") {
@@ -46,7 +49,11 @@ pub fn main(mut args: CmdArgs) {
     if let Some(s) = args.optional_parse("--scale_factor", |s| s.parse::<f64>()) {
         settings.scale_factor(s);
     }
+    if let Some(n) = args.optional_parse("--triangle_budget", |s| s.parse::<usize>()) {
+        settings.triangle_budget(n);
+    }
     settings.loading_tips(map_gui::tools::loading_tips());
+    let screenshot = ScreenshotFlags::from_args(&mut args, &mut settings);
 
     let mut mode = None;
     if let Some(x) = args.optional("--challenge") {
@@ -76,19 +83,38 @@ pub fn main(mut args: CmdArgs) {
     // other scenarios loaed in the UI later.
     let modifiers = flags.sim_flags.modifiers.drain(..).collect();
 
-    if mode.is_none() && flags.sim_flags.load.contains("scenarios/") {
-        let (map_name, scenario) = abstutil::parse_scenario_path(&flags.sim_flags.load);
-        flags.sim_flags.load = map_name.path();
-        mode = Some(sandbox::GameplayMode::PlayScenario(
-            map_name, scenario, modifiers,
-        ));
+    if mode.is_none() {
+        if let Some((map_name, scenario)) = abstutil::parse_scenario_path(&flags.sim_flags.load) {
+            flags.sim_flags.load = map_name.path();
+            mode = Some(sandbox::GameplayMode::PlayScenario(
+                map_name, scenario, modifiers,
+            ));
+        }
+    }
+    // Animate a recorded run instead of simulating anything
+    let playback = args.optional("--playback").map(|path| {
+        sim::Playback::load(path.clone(), &mut abstutil::Timer::new("load playback"))
+            .unwrap_or_else(|err| panic!("Couldn't load playback {}: {}", path, err))
+    });
+    if let Some(ref playback) = playback {
+        flags.sim_flags.load = playback.map_name.path();
     }
     let start_with_edits = args.optional("--edits");
+    let start_time = args.optional_parse("--start_time", Time::parse);
 
     args.done();
 
     widgetry::run(settings, |ctx| {
-        setup_app(ctx, flags, opts, start_with_edits, mode)
+        setup_app(
+            ctx,
+            flags,
+            opts,
+            start_with_edits,
+            start_time,
+            mode,
+            playback,
+            screenshot,
+        )
     });
 }
 
@@ -97,12 +123,17 @@ fn setup_app(
     flags: Flags,
     opts: Options,
     start_with_edits: Option<String>,
+    start_time: Option<Time>,
     maybe_mode: Option<GameplayMode>,
+    playback: Option<sim::Playback>,
+    screenshot: Option<ScreenshotFlags>,
 ) -> (App, Vec<Box<dyn State<App>>>) {
     let title = !opts.dev
         && !flags.sim_flags.load.contains("player/save")
         && !flags.sim_flags.load.contains("/scenarios/")
-        && maybe_mode.is_none();
+        && maybe_mode.is_none()
+        && playback.is_none()
+        && screenshot.is_none();
     let mut app = App::new(flags, opts, ctx, title);
 
     // Handle savestates
@@ -137,12 +168,31 @@ fn setup_app(
         app.primary.clear_sim();
     }
 
-    let states: Vec<Box<dyn State<App>>> = if title {
+    let states: Vec<Box<dyn State<App>>> = if let Some(screenshot) = screenshot {
+        vec![screenshot.setup(ctx, &mut app)]
+    } else if let Some(playback) = playback {
+        vec![devtools::playback::PlaybackViewer::new(
+            ctx, &mut app, playback,
+        )]
+    } else if title {
         vec![Box::new(TitleScreen::new(ctx, &mut app))]
     } else {
         let mode = maybe_mode
             .unwrap_or_else(|| GameplayMode::Freeform(app.primary.map.get_name().clone()));
-        vec![SandboxMode::simple_new(ctx, &mut app, mode)]
+        if let Some(t) = start_time {
+            // Fast-forward once the scenario is loaded, so people studying the afternoon don't
+            // have to watch the morning first
+            vec![SandboxMode::async_new(
+                ctx,
+                &mut app,
+                mode,
+                Box::new(move |ctx, app| {
+                    vec![Transition::Push(TimeWarpScreen::new(ctx, app, t, None))]
+                }),
+            )]
+        } else {
+            vec![SandboxMode::simple_new(ctx, &mut app, mode)]
+        }
     };
     if let Some(ss) = savestate {
         // TODO This is weird, we're left in Freeform mode with the wrong UI. Can't instantiate
@@ -0,0 +1,140 @@
+use abstutil::{prettyprint_usize, Counter};
+use geom::Time;
+use map_model::BuildingID;
+use widgetry::{
+    Btn, Choice, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Outcome, Panel,
+    State, Text, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::app::Transition;
+use crate::common::{BlockID, Blocks, ColorLegend, CommonState};
+
+/// Where people picked up and left shared bikes and scooters, grouped by neighborhood block. Only
+/// available when a ScenarioModifier adds a bike share system.
+pub struct SharedVehicles {
+    panel: Panel,
+    blocks: Blocks,
+    draw: Drawable,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Show {
+    Pickups,
+    Dropoffs,
+    UnmetDemand,
+}
+
+impl SharedVehicles {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let usage = app.primary.bike_share.as_ref().unwrap();
+        let blocks = ctx.loading_screen("group buildings into blocks", |_, _| Blocks::new(app));
+
+        let moved: usize = usage.rebalancing.iter().map(|(_, cnt)| *cnt).sum();
+        let mut txt = Text::new();
+        txt.add(Line(format!(
+            "{} trips used a shared vehicle",
+            prettyprint_usize(usage.pickups.len())
+        )));
+        txt.add(Line(format!(
+            "{} more trips could have, but no vehicle was close enough",
+            prettyprint_usize(usage.unmet_demand.len())
+        )));
+        txt.add(Line(format!(
+            "The operator moved {} idle vehicles in {} rounds of rebalancing",
+            prettyprint_usize(moved),
+            usage.rebalancing.len()
+        )));
+        txt.add(
+            Line("These are planned at the start of the day; the simulation might differ")
+                .secondary(),
+        );
+
+        let mut state = SharedVehicles {
+            panel: Panel::new(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Bike and scooter share").small_heading().draw(ctx),
+                    Btn::close(ctx),
+                ]),
+                txt.draw(ctx),
+                Widget::dropdown(
+                    ctx,
+                    "show",
+                    Show::Pickups,
+                    vec![
+                        Choice::new("pickups", Show::Pickups),
+                        Choice::new("dropoffs", Show::Dropoffs),
+                        Choice::new("unmet demand", Show::UnmetDemand),
+                    ],
+                ),
+                ColorLegend::gradient(ctx, &app.cs.good_to_bad_red, vec!["0", "0"]).named("scale"),
+            ]))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .build(ctx),
+            blocks,
+            draw: ctx.upload(GeomBatch::new()),
+        };
+        state.recalculate(ctx, app, Show::Pickups);
+        Box::new(state)
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App, show: Show) {
+        let usage = app.primary.bike_share.as_ref().unwrap();
+        let events: &Vec<(Time, BuildingID)> = match show {
+            Show::Pickups => &usage.pickups,
+            Show::Dropoffs => &usage.dropoffs,
+            Show::UnmetDemand => &usage.unmet_demand,
+        };
+        let mut per_block: Counter<BlockID> = Counter::new();
+        for (_, b) in events {
+            if let Some(block) = self.blocks.bldg_to_block.get(b) {
+                per_block.inc(*block);
+            }
+        }
+
+        let max = per_block.max();
+        let mut batch = GeomBatch::new();
+        for (block, cnt) in per_block.borrow() {
+            let pct = (*cnt as f64) / (max as f64);
+            batch.push(
+                app.cs.good_to_bad_red.eval(pct).alpha(0.8),
+                self.blocks.blocks[*block].shape.clone(),
+            );
+        }
+        self.draw = ctx.upload(batch);
+        let scale = ColorLegend::gradient(
+            ctx,
+            &app.cs.good_to_bad_red,
+            vec!["0".to_string(), prettyprint_usize(max)],
+        );
+        self.panel.replace(ctx, "scale", scale);
+    }
+}
+
+impl State<App> for SharedVehicles {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed => {
+                let show = self.panel.dropdown_value("show");
+                self.recalculate(ctx, app, show);
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
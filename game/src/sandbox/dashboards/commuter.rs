@@ -1,10 +1,8 @@
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap};
 
-use maplit::hashset;
-
-use abstutil::{prettyprint_usize, Counter, MultiMap};
-use geom::{Distance, PolyLine, Polygon, Time};
-use map_model::{osm, BuildingID, BuildingType, IntersectionID, LaneID, Map, RoadID, TurnType};
+use abstutil::{prettyprint_usize, Counter};
+use geom::{Distance, Polygon, Time};
+use map_model::{BuildingID, BuildingType, IntersectionID};
 use sim::{TripEndpoint, TripInfo, TripMode};
 use widgetry::{
     Btn, Checkbox, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
@@ -13,8 +11,9 @@ use widgetry::{
 
 use crate::app::App;
 use crate::app::Transition;
-use crate::common::{ColorLegend, CommonState};
+use crate::common::{Block, BlockID, Blocks, ColorLegend, CommonState};
 use crate::helpers::checkbox_per_mode;
+use crate::layer::block_people::PeopleFromBlock;
 
 pub struct CommuterPatterns {
     bldg_to_block: HashMap<BuildingID, BlockID>,
@@ -47,16 +46,6 @@ struct PanelState<'a> {
     total_trips: usize,
 }
 
-// Group many buildings into a single block
-struct Block {
-    id: BlockID,
-    // A block is either some buildings or a single border. Might be worth expressing that more
-    // clearly.
-    bldgs: HashSet<BuildingID>,
-    borders: HashSet<IntersectionID>,
-    shape: Polygon,
-}
-
 #[derive(PartialEq)]
 struct Filter {
     // If false, then trips to this block
@@ -67,15 +56,16 @@ struct Filter {
     modes: BTreeSet<TripMode>,
 }
 
-type BlockID = usize;
-
 impl CommuterPatterns {
     pub fn new(ctx: &mut EventCtx, app: &mut App) -> Box<dyn State<App>> {
         assert!(app.primary.suspended_sim.is_none());
         app.primary.suspended_sim = Some(app.primary.clear_sim());
 
-        let (bldg_to_block, border_to_block, blocks) =
-            ctx.loading_screen("group buildings into blocks", |_, _| group_bldgs(app));
+        let Blocks {
+            bldg_to_block,
+            border_to_block,
+            blocks,
+        } = ctx.loading_screen("group buildings into blocks", |_, _| Blocks::new(app));
 
         let mut trips_from_block: Vec<Vec<TripInfo>> = std::iter::repeat_with(Vec::new)
             .take(blocks.len())
@@ -307,7 +297,13 @@ impl CommuterPatterns {
         }
     }
 
-    fn redraw_panel(&mut self, state: Option<&PanelState>, ctx: &mut EventCtx, app: &App) {
+    fn redraw_panel(
+        &mut self,
+        block_selection: BlockSelection,
+        state: Option<&PanelState>,
+        ctx: &mut EventCtx,
+        app: &App,
+    ) {
         if let Some(state) = state {
             let mut txt = Text::new();
             txt.add(Line(format!(
@@ -321,7 +317,11 @@ impl CommuterPatterns {
                 }
             }
 
-            self.panel.replace(ctx, "current", txt.draw(ctx));
+            let mut col = vec![txt.draw(ctx)];
+            if let BlockSelection::Locked { .. } = block_selection {
+                col.push(Btn::text_bg2("show these people live").build_def(ctx, None));
+            }
+            self.panel.replace(ctx, "current", Widget::col(col));
 
             let new_scale = ColorLegend::gradient(
                 ctx,
@@ -349,6 +349,21 @@ impl State<App> for CommuterPatterns {
                     app.primary.sim = app.primary.suspended_sim.take().unwrap();
                     return Transition::Pop;
                 }
+                "show these people live" => {
+                    if let BlockSelection::Locked { base, .. } = self.current_block.0 {
+                        app.primary.sim = app.primary.suspended_sim.take().unwrap();
+                        let block = &self.blocks[base];
+                        app.primary.layer = Some(Box::new(PeopleFromBlock::new(
+                            ctx,
+                            app,
+                            block.bldgs.clone(),
+                            block.borders.clone(),
+                            block.shape.clone(),
+                            self.filter.from_block,
+                        )));
+                        return Transition::Pop;
+                    }
+                }
                 _ => unreachable!(),
             },
             _ => {}
@@ -433,7 +448,7 @@ impl State<App> for CommuterPatterns {
         if filter != self.filter || block_selection != self.current_block.0 {
             self.filter = filter;
             let (drawable, per_block_counts) = self.build_block_drawable(block_selection, ctx, app);
-            self.redraw_panel(per_block_counts.as_ref(), ctx, app);
+            self.redraw_panel(block_selection, per_block_counts.as_ref(), ctx, app);
             self.current_block = (block_selection, drawable);
         }
         Transition::Keep
@@ -448,240 +463,6 @@ impl State<App> for CommuterPatterns {
     }
 }
 
-// This tries to group buildings into neighborhood "blocks". Much of the time, that's a smallish
-// region bounded by 4 roads. But there are plenty of places with stranger shapes, or buildings
-// near the border of the map. The fallback is currently to just group those buildings that share
-// the same sidewalk.
-fn group_bldgs(
-    app: &App,
-) -> (
-    HashMap<BuildingID, BlockID>,
-    HashMap<IntersectionID, BlockID>,
-    Vec<Block>,
-) {
-    let mut bldg_to_block = HashMap::new();
-    let mut blocks = Vec::new();
-
-    for group in partition_sidewalk_loops(app) {
-        let block_id = blocks.len();
-        let mut polygons = Vec::new();
-        let mut lanes = HashSet::new();
-        for b in &group.bldgs {
-            bldg_to_block.insert(*b, block_id);
-            let bldg = app.primary.map.get_b(*b);
-            if group.proper {
-                lanes.insert(bldg.sidewalk());
-            } else {
-                polygons.push(bldg.polygon.clone());
-            }
-        }
-        if group.proper {
-            // TODO Even better, glue the loop of sidewalks together and fill that area.
-            for l in lanes {
-                polygons.push(app.primary.draw_map.get_l(l).polygon.clone());
-            }
-        }
-        blocks.push(Block {
-            id: block_id,
-            bldgs: group.bldgs,
-            borders: HashSet::new(),
-            shape: Polygon::convex_hull(polygons),
-        });
-    }
-
-    let mut border_to_block = HashMap::new();
-    for i in app.primary.map.all_incoming_borders() {
-        let id = blocks.len();
-        border_to_block.insert(i.id, id);
-        blocks.push(Block {
-            id,
-            bldgs: HashSet::new(),
-            borders: hashset! { i.id },
-            shape: build_shape_for_border(i, BorderType::Incoming, &app.primary.map),
-        });
-    }
-    for i in app.primary.map.all_outgoing_borders() {
-        if let Some(incoming_border_id) = border_to_block.get(&i.id) {
-            let two_way_border = &mut blocks[*incoming_border_id];
-            two_way_border.shape = build_shape_for_border(i, BorderType::Both, &app.primary.map);
-            continue;
-        }
-        let id = blocks.len();
-        border_to_block.insert(i.id, id);
-
-        blocks.push(Block {
-            id,
-            bldgs: HashSet::new(),
-            borders: hashset! { i.id },
-            shape: build_shape_for_border(i, BorderType::Outgoing, &app.primary.map),
-        });
-    }
-
-    (bldg_to_block, border_to_block, blocks)
-}
-
-enum BorderType {
-    Incoming,
-    Outgoing,
-    Both,
-}
-
-fn build_shape_for_border(
-    border: &map_model::Intersection,
-    border_type: BorderType,
-    map: &Map,
-) -> Polygon {
-    let start = border.polygon.center();
-
-    let road = map.get_r(*border.roads.iter().next().unwrap());
-    let center_line = road.get_dir_change_pl(map);
-    let angle = if road.src_i == border.id {
-        center_line.first_line().angle().opposite()
-    } else {
-        center_line.first_line().angle()
-    };
-
-    let length = Distance::meters(150.0);
-    let thickness = Distance::meters(30.0);
-    let end = start.project_away(length, angle);
-
-    match border_type {
-        BorderType::Incoming => {
-            PolyLine::must_new(vec![end, start]).make_arrow(thickness, geom::ArrowCap::Triangle)
-        }
-        BorderType::Outgoing => {
-            PolyLine::must_new(vec![start, end]).make_arrow(thickness, geom::ArrowCap::Triangle)
-        }
-        BorderType::Both => PolyLine::must_new(vec![start, end])
-            .make_double_arrow(thickness, geom::ArrowCap::Triangle),
-    }
-}
-
-struct Loop {
-    bldgs: HashSet<BuildingID>,
-    // True if it's a "proper" block, false if it's a hack.
-    proper: bool,
-    roads: HashSet<RoadID>,
-}
-
-fn partition_sidewalk_loops(app: &App) -> Vec<Loop> {
-    let map = &app.primary.map;
-
-    let mut groups = Vec::new();
-    let mut todo_bldgs: BTreeSet<BuildingID> = map.all_buildings().iter().map(|b| b.id).collect();
-    let mut remainder = HashSet::new();
-
-    let mut sidewalk_to_bldgs = MultiMap::new();
-    for b in map.all_buildings() {
-        sidewalk_to_bldgs.insert(b.sidewalk(), b.id);
-    }
-
-    while !todo_bldgs.is_empty() {
-        let mut sidewalks = HashSet::new();
-        let mut bldgs = HashSet::new();
-        let mut current_l = map.get_b(*todo_bldgs.iter().next().unwrap()).sidewalk();
-        let mut current_i = map.get_l(current_l).src_i;
-
-        let ok = loop {
-            sidewalks.insert(current_l);
-            for b in sidewalk_to_bldgs.get(current_l) {
-                bldgs.insert(*b);
-                // TODO I wanted to assert that we haven't assigned this one yet, but...
-                todo_bldgs.remove(b);
-            }
-
-            // Chase SharedSidewalkCorners. There should be zero or one new options for corners.
-            let turns = map
-                .get_turns_from_lane(current_l)
-                .into_iter()
-                .filter(|t| {
-                    t.turn_type == TurnType::SharedSidewalkCorner && t.id.parent != current_i
-                })
-                .collect::<Vec<_>>();
-            if turns.is_empty() {
-                // TODO If we're not a loop, maybe toss this out. It's arbitrary that we didn't go
-                // look the other way.
-                break false;
-            } else if turns.len() == 1 {
-                current_l = turns[0].id.dst;
-                current_i = turns[0].id.parent;
-                if sidewalks.contains(&current_l) {
-                    // Loop closed!
-                    break true;
-                }
-            } else {
-                panic!(
-                    "Too many SharedSidewalkCorners from ({}, {})",
-                    current_l, current_i
-                );
-            };
-        };
-
-        if ok {
-            groups.push(Loop {
-                bldgs,
-                proper: true,
-                roads: sidewalks.into_iter().map(|l| map.get_l(l).parent).collect(),
-            });
-        } else {
-            remainder.extend(bldgs);
-        }
-    }
-
-    // Merge adjacent residential blocks
-    loop {
-        // Find a pair of blocks that have at least one residential road in common.
-        let mut any = false;
-        for mut idx1 in 0..groups.len() {
-            for mut idx2 in 0..groups.len() {
-                // This is O(n^3) on original groups.len(). In practice, it's fine, as long as we
-                // don't start the search over from scratch after making a single merge. Starting
-                // over is really wasteful, because it's guaranteed that nothing there has changed.
-                if idx1 >= groups.len() || idx2 >= groups.len() {
-                    break;
-                }
-
-                if idx1 != idx2
-                    && groups[idx1]
-                        .roads
-                        .intersection(&groups[idx2].roads)
-                        .any(|r| map.get_r(*r).get_rank() == osm::RoadRank::Local)
-                {
-                    // Indexing gets messed up, so remove the larger one
-                    if idx1 > idx2 {
-                        std::mem::swap(&mut idx1, &mut idx2);
-                    }
-                    let merge = groups.remove(idx2);
-                    groups[idx1].bldgs.extend(merge.bldgs);
-                    groups[idx1].roads.extend(merge.roads);
-                    any = true;
-                }
-            }
-        }
-        if !any {
-            break;
-        }
-    }
-
-    // For all the weird remainders, just group them based on sidewalk.
-    let mut per_sidewalk: MultiMap<LaneID, BuildingID> = MultiMap::new();
-    for b in remainder {
-        per_sidewalk.insert(map.get_b(b).sidewalk(), b);
-    }
-    for (_, bldgs) in per_sidewalk.consume() {
-        let r = map
-            .get_l(map.get_b(*bldgs.iter().next().unwrap()).sidewalk())
-            .parent;
-        groups.push(Loop {
-            bldgs: bldgs.into_iter().collect(),
-            proper: false,
-            roads: hashset! { r },
-        });
-    }
-
-    groups
-}
-
 fn make_panel(ctx: &mut EventCtx, app: &App) -> Panel {
     Panel::new(Widget::col(vec![
         Widget::row(vec![
@@ -9,6 +9,7 @@ use widgetry::{
 use crate::app::App;
 use crate::app::Transition;
 use crate::common::Tab;
+use crate::sandbox::dashboards::new_route::NewRoute;
 use crate::sandbox::dashboards::DashTab;
 use crate::sandbox::SandboxMode;
 
@@ -155,6 +156,7 @@ impl TransitRoutes {
             Line(format!("{} Transit routes", routes.len()))
                 .small_heading()
                 .draw(ctx),
+            Btn::text_fg("Create a new route").build_def(ctx, None),
             Widget::row(vec![
                 Widget::draw_svg(ctx, "system/assets/tools/search.svg"),
                 Autocomplete::new(
@@ -203,6 +205,8 @@ impl State<App> for TransitRoutes {
                     BusRouteID(x.parse::<usize>().unwrap())
                 } else if x == "close" {
                     return Transition::Pop;
+                } else if x == "Create a new route" {
+                    return Transition::Replace(NewRoute::new(ctx, app));
                 } else {
                     unreachable!()
                 }
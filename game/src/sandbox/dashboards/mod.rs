@@ -1,5 +1,12 @@
+pub use bike_share::SharedVehicles;
 pub use commuter::CommuterPatterns;
+pub use neighborhoods::NeighborhoodStats;
+pub use problems::Problems;
+pub use select_link::SelectLink;
+pub use time_space::CorridorRecorder;
+pub use traffic_counts::TrafficCounts;
 pub use traffic_signals::TrafficSignalDemand;
+pub use trajectories::Trajectories;
 pub use trip_table::FinishedTripTable;
 
 use widgetry::{Btn, Choice, EventCtx, Line, Panel, TextExt, Widget};
@@ -7,13 +14,21 @@ use widgetry::{Btn, Choice, EventCtx, Line, Panel, TextExt, Widget};
 use crate::app::App;
 use crate::app::Transition;
 
+mod bike_share;
 mod commuter;
 mod generic_trip_table;
 mod misc;
+mod neighborhoods;
+mod new_route;
 mod parking_overhead;
+mod problems;
+mod select_link;
 mod summaries;
 mod table;
+mod time_space;
+mod traffic_counts;
 mod traffic_signals;
+mod trajectories;
 mod trip_table;
 
 // Oh the dashboards melted, but we still had the radio
@@ -28,6 +43,10 @@ pub enum DashTab {
     TransitRoutes,
     CommuterPatterns,
     TrafficSignals,
+    TrafficCounts,
+    Neighborhoods,
+    BikeShare,
+    Problems,
 }
 
 impl DashTab {
@@ -40,10 +59,16 @@ impl DashTab {
             Choice::new("Transit Routes", DashTab::TransitRoutes),
             Choice::new("Commuter Patterns", DashTab::CommuterPatterns),
             Choice::new("Traffic Signal Demand", DashTab::TrafficSignals),
+            Choice::new("Screenline and Cordon Counts", DashTab::TrafficCounts),
+            Choice::new("Neighborhood Statistics", DashTab::Neighborhoods),
+            Choice::new("Problems", DashTab::Problems),
         ];
         if app.has_prebaked().is_none() {
             choices.remove(1);
         }
+        if app.primary.bike_share.is_some() {
+            choices.push(Choice::new("Bike and Scooter Share", DashTab::BikeShare));
+        }
         Widget::row(vec![
             Widget::draw_svg(ctx, "system/assets/meters/trip_histogram.svg"),
             Line("Data").big_heading_plain().draw(ctx),
@@ -82,6 +107,10 @@ impl DashTab {
             DashTab::TransitRoutes => misc::TransitRoutes::new(ctx, app),
             DashTab::CommuterPatterns => CommuterPatterns::new(ctx, app),
             DashTab::TrafficSignals => TrafficSignalDemand::new(ctx, app),
+            DashTab::TrafficCounts => TrafficCounts::new(ctx, app),
+            DashTab::Neighborhoods => NeighborhoodStats::new(ctx, app),
+            DashTab::BikeShare => SharedVehicles::new(ctx, app),
+            DashTab::Problems => Problems::new(ctx, app),
             DashTab::CancelledTripTable | DashTab::UnfinishedTripTable => unreachable!(),
         }))
     }
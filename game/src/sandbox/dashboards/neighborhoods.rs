@@ -0,0 +1,270 @@
+use std::collections::{BTreeMap, HashSet};
+
+use aabb_quadtree::QuadTree;
+
+use abstutil::{prettyprint_usize, Counter};
+use geom::{Circle, Distance, Pt2D};
+use map_model::{osm, BuildingType, LaneType, RoadID};
+use sim::{AgentID, AgentType, TripEndpoint};
+use widgetry::{
+    Btn, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Outcome, Panel,
+    State, Text, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::app::Transition;
+use crate::common::{BlockID, Blocks, CommonState};
+
+/// Click a neighborhood block to summarize what's inside of it: buildings, roads, parking, the
+/// people who live there, and the agents there right now.
+pub struct NeighborhoodStats {
+    panel: Panel,
+    blocks: Blocks,
+    // Every agent on the map right now, so counting agents inside a block doesn't have to check
+    // all of them
+    agents: QuadTree<(AgentID, Pt2D)>,
+    hovering: Option<BlockID>,
+    selected: Option<(BlockID, Drawable)>,
+}
+
+impl NeighborhoodStats {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let blocks = ctx.loading_screen("group buildings into blocks", |_, _| Blocks::new(app));
+
+        let map = &app.primary.map;
+        let mut agents = QuadTree::default(map.get_bounds().as_bbox());
+        for agent in app.primary.sim.get_unzoomed_agents(map) {
+            agents.insert_with_box(
+                (agent.id, agent.pos),
+                Circle::new(agent.pos, Distance::meters(1.0))
+                    .get_bounds()
+                    .as_bbox(),
+            );
+        }
+
+        Box::new(NeighborhoodStats {
+            panel: Panel::new(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Neighborhood statistics").small_heading().draw(ctx),
+                    Btn::close(ctx),
+                ]),
+                "Click a neighborhood".draw_text(ctx).named("stats"),
+            ]))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .build(ctx),
+            blocks,
+            agents,
+            hovering: None,
+            selected: None,
+        })
+    }
+
+    fn select(&mut self, ctx: &mut EventCtx, app: &App, id: BlockID) {
+        let block = &self.blocks.blocks[id];
+        let stats = describe(app, &self.blocks, &self.agents, id).draw(ctx);
+        self.panel.replace(ctx, "stats", stats);
+        self.selected = Some((
+            id,
+            ctx.upload(GeomBatch::from(vec![(
+                Color::YELLOW.alpha(0.5),
+                block.shape.clone(),
+            )])),
+        ));
+    }
+}
+
+impl State<App> for NeighborhoodStats {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if ctx.redo_mouseover() {
+            self.hovering = ctx.canvas.get_cursor_in_map_space().and_then(|pt| {
+                self.blocks
+                    .blocks
+                    .iter()
+                    .find(|b| (!b.bldgs.is_empty() || b.area.is_some()) && b.shape.contains_pt(pt))
+                    .map(|b| b.id)
+            });
+        }
+        if let Some(id) = self.hovering {
+            if app.per_obj.left_click(ctx, "summarize this neighborhood") {
+                self.select(ctx, app, id);
+            }
+        }
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        if let Some((_, ref draw)) = self.selected {
+            g.redraw(draw);
+        }
+        if let Some(id) = self.hovering {
+            g.draw_polygon(Color::CYAN.alpha(0.3), self.blocks.blocks[id].shape.clone());
+        }
+        self.panel.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+fn describe(app: &App, blocks: &Blocks, agents: &QuadTree<(AgentID, Pt2D)>, id: BlockID) -> Text {
+    let map = &app.primary.map;
+    let sim = &app.primary.sim;
+    let block = &blocks.blocks[id];
+    let mut txt = Text::new();
+
+    let mut residential = 0;
+    let mut commercial = 0;
+    let mut offstreet_spots = 0;
+    for b in &block.bldgs {
+        let bldg = map.get_b(*b);
+        match bldg.bldg_type {
+            BuildingType::Residential(_) => {
+                residential += 1;
+            }
+            BuildingType::ResidentialCommercial(_, _) => {
+                residential += 1;
+                commercial += 1;
+            }
+            BuildingType::Commercial(_) => {
+                commercial += 1;
+            }
+            BuildingType::Empty => {}
+        }
+        offstreet_spots += bldg.num_parking_spots();
+    }
+    if let Some(a) = block.area {
+        txt.add(
+            Line(
+                map.get_a(a)
+                    .osm_tags
+                    .get("name")
+                    .cloned()
+                    .unwrap_or_else(|| "Unnamed park".to_string()),
+            )
+            .small_heading(),
+        );
+    }
+    txt.add(
+        Line(format!(
+            "{} buildings",
+            prettyprint_usize(block.bldgs.len())
+        ))
+        .small_heading(),
+    );
+    txt.add(Line(format!(
+        "{} residential, {} commercial",
+        prettyprint_usize(residential),
+        prettyprint_usize(commercial)
+    )));
+
+    // Roads that buildings in the block face, plus anything else inside the block
+    let mut roads: HashSet<RoadID> = block
+        .bldgs
+        .iter()
+        .map(|b| map.get_l(map.get_b(*b).sidewalk()).parent)
+        .collect();
+    for r in map.all_roads() {
+        if block.shape.contains_pt(r.center_pts.middle()) {
+            roads.insert(r.id);
+        }
+    }
+    let mut length_per_rank: BTreeMap<osm::RoadRank, Distance> = BTreeMap::new();
+    let mut onstreet_spots = 0;
+    for r in &roads {
+        let road = map.get_r(*r);
+        *length_per_rank
+            .entry(road.get_rank())
+            .or_insert(Distance::ZERO) += road.center_pts.length();
+        for (l, _, lt) in road.lanes_ltr() {
+            if lt == LaneType::Parking {
+                onstreet_spots += map.get_l(l).number_parking_spots();
+            }
+        }
+    }
+    txt.add(Line("Roads").small_heading());
+    for (rank, length) in length_per_rank {
+        txt.add(Line(format!(
+            "{}: {}",
+            match rank {
+                osm::RoadRank::Local => "Local",
+                osm::RoadRank::Arterial => "Arterial",
+                osm::RoadRank::Highway => "Highway",
+            },
+            length.to_string(&app.opts.units)
+        )));
+    }
+
+    let lot_spots: usize = map
+        .all_parking_lots()
+        .iter()
+        .filter(|pl| block.shape.contains_pt(pl.polygon.center()))
+        .map(|pl| pl.capacity())
+        .sum();
+    txt.add(Line("Parking spots").small_heading());
+    txt.add(Line(format!(
+        "{} on-street, {} in lots, {} in buildings",
+        prettyprint_usize(onstreet_spots),
+        prettyprint_usize(lot_spots),
+        prettyprint_usize(offstreet_spots)
+    )));
+
+    txt.add(Line("Population").small_heading());
+    if sim.get_all_people().is_empty() {
+        txt.add(Line("No synthetic population loaded").secondary());
+    } else {
+        let mut residents = 0;
+        for person in sim.get_all_people() {
+            if let Some(t) = person.trips.first() {
+                if let TripEndpoint::Bldg(b) = sim.trip_info(*t).start {
+                    if block.bldgs.contains(&b) {
+                        residents += 1;
+                    }
+                }
+            }
+        }
+        let inside_bldgs: usize = block
+            .bldgs
+            .iter()
+            .map(|b| sim.bldg_to_people(*b).len())
+            .sum();
+        txt.add(Line(format!(
+            "{} people start their day here",
+            prettyprint_usize(residents)
+        )));
+        txt.add(Line(format!(
+            "{} are inside buildings here right now",
+            prettyprint_usize(inside_bldgs)
+        )));
+    }
+
+    let mut live: Counter<AgentType> = Counter::new();
+    for &((id, pt), _, _) in &agents.query(block.shape.get_bounds().as_bbox()) {
+        if block.shape.contains_pt(*pt) {
+            live.inc(id.to_type());
+        }
+    }
+    txt.add(Line(format!("Moving through at {}", sim.time().ampm_tostring())).small_heading());
+    if live.sum() == 0 {
+        txt.add(Line("Nobody").secondary());
+    }
+    for (agent_type, cnt) in live.consume() {
+        txt.add(Line(format!(
+            "{}: {}",
+            agent_type.noun(),
+            prettyprint_usize(cnt)
+        )));
+    }
+
+    txt
+}
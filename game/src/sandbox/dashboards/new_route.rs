@@ -0,0 +1,285 @@
+use geom::{Circle, Distance, Duration, Pt2D, Time};
+use map_gui::tools::PopupMsg;
+use map_model::{EditCmd, Lane, PathConstraints, PathRequest, Position};
+use widgetry::{
+    Btn, Color, Drawable, EventCtx, Form, FormChecks, FormValues, GeomBatch, GfxCtx,
+    HorizontalAlignment, Key, Line, Outcome, Panel, State, Text, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::app::Transition;
+use crate::common::CommonState;
+use crate::edit::apply_map_edits;
+use crate::helpers::ID;
+
+// Local bus stops are usually spaced about this far apart.
+const MIN_SPACING: Distance = Distance::const_meters(300.0);
+const MAX_SPACING: Distance = Distance::const_meters(600.0);
+
+/// Draw a brand new bus route by placing stops along streets, then pick how often and when buses
+/// run. The route is saved as a map edit, and buses start serving it in the current simulation.
+pub struct NewRoute {
+    panel: Panel,
+    form: FormChecks,
+    stops: Vec<Position>,
+    // Is there some pair of stops without a path between them?
+    broken: bool,
+    draw: Drawable,
+}
+
+impl NewRoute {
+    pub fn new(ctx: &mut EventCtx, app: &mut App) -> Box<dyn State<App>> {
+        app.primary.current_selection = None;
+
+        let (form_widget, form) = Form::new()
+            .text(ctx, "name", "Name:", "New route", |name| {
+                if name.is_empty() {
+                    Err("The route needs a name".to_string())
+                } else {
+                    Ok(())
+                }
+            })
+            .spinner(ctx, "headway", "Minutes between buses", (1, 120), 15)
+            .spinner(ctx, "first bus", "First bus at hour", (0, 23), 6)
+            .spinner(ctx, "last bus", "Last bus at hour", (0, 23), 22)
+            .check(|values| {
+                if values.spinner("first bus") > values.spinner("last bus") {
+                    Err("The first bus has to run before the last one".to_string())
+                } else {
+                    Ok(())
+                }
+            })
+            .build(ctx);
+
+        let mut state = NewRoute {
+            panel: Panel::new(Widget::col(vec![
+                Widget::row(vec![
+                    Line("New bus route").small_heading().draw(ctx),
+                    Btn::close(ctx),
+                ]),
+                form_widget,
+                Text::new().draw(ctx).named("stops"),
+            ]))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .build(ctx),
+            form,
+            stops: Vec::new(),
+            broken: false,
+            draw: ctx.upload(GeomBatch::new()),
+        };
+        state.recalculate(ctx, app);
+        Box::new(state)
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        let map = &app.primary.map;
+        let mut batch = GeomBatch::new();
+        let mut col = vec![Line("Stops").small_heading().draw(ctx)];
+
+        self.broken = false;
+        for (idx, pos) in self.stops.iter().enumerate() {
+            let name = map
+                .get_parent(pos.lane())
+                .get_name(app.opts.language.as_ref());
+            let mut txt = Text::from(Line(format!("{}. {}", idx + 1, name)));
+            if idx > 0 {
+                let req = PathRequest {
+                    start: self.stops[idx - 1],
+                    end: *pos,
+                    constraints: PathConstraints::Bus,
+                };
+                if let Some(pl) = map
+                    .pathfind(req.clone())
+                    .and_then(|path| path.trace(map, req.start.dist_along(), None))
+                {
+                    let spacing = pl.length();
+                    let line = Line(format!("{} from the previous stop", spacing));
+                    txt.add(if spacing < MIN_SPACING || spacing > MAX_SPACING {
+                        line.fg(Color::YELLOW)
+                    } else {
+                        line.secondary()
+                    });
+                    batch.push(app.cs.unzoomed_bus, pl.make_polygons(Distance::meters(3.0)));
+                } else {
+                    txt.add(Line("Buses can't reach this from the previous stop").fg(Color::RED));
+                    self.broken = true;
+                }
+            }
+            col.push(txt.draw(ctx));
+            batch.push(
+                Color::CYAN,
+                Circle::new(pos.pt(map), Distance::meters(5.0)).to_polygon(),
+            );
+        }
+
+        // Show where the next stop should go
+        if let Some(pos) = self.stops.last() {
+            let pt = pos.pt(map);
+            for radius in &[MIN_SPACING, MAX_SPACING] {
+                if let Ok(p) = Circle::new(pt, *radius).to_outline(Distance::meters(5.0)) {
+                    batch.push(Color::CYAN.alpha(0.5), p);
+                }
+            }
+        }
+        self.draw = ctx.upload(batch);
+
+        col.push(
+            Text::from_multiline(vec![
+                Line("Zoom in and click a lane buses can use to add a stop"),
+                Line(format!(
+                    "Stops are usually {} to {} apart",
+                    MIN_SPACING, MAX_SPACING
+                ))
+                .secondary(),
+            ])
+            .draw(ctx),
+        );
+        col.push(Widget::row(vec![
+            if self.stops.is_empty() {
+                Btn::text_fg("remove last stop").inactive(ctx)
+            } else {
+                Btn::text_fg("remove last stop").build_def(ctx, None)
+            },
+            if self.stops.len() < 2 || self.broken || self.form.values(&self.panel).is_err() {
+                Btn::text_bg2("Create route").inactive(ctx)
+            } else {
+                Btn::text_bg2("Create route").build_def(ctx, Key::Enter)
+            },
+        ]));
+        self.panel.replace(ctx, "stops", Widget::col(col));
+    }
+
+    fn spawn_times(&self, values: &FormValues) -> Vec<Time> {
+        let headway = Duration::minutes(values.spinner("headway") as usize);
+        let first = Time::START_OF_DAY + Duration::hours(values.spinner("first bus") as usize);
+        let last = Time::START_OF_DAY + Duration::hours(values.spinner("last bus") as usize);
+        let mut times = Vec::new();
+        let mut now = first;
+        while now <= last {
+            times.push(now);
+            now += headway;
+        }
+        times
+    }
+
+    fn create(&self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        let values = match self.form.values(&self.panel) {
+            Ok(values) => values,
+            Err(errors) => {
+                return Transition::Push(PopupMsg::new(ctx, "Error", errors));
+            }
+        };
+        let cmd = match app.primary.map.create_bus_route_cmd(
+            values.text("name"),
+            self.stops.clone(),
+            self.spawn_times(&values),
+        ) {
+            Ok(cmd) => cmd,
+            Err(err) => {
+                return Transition::Push(PopupMsg::new(ctx, "Error", vec![err]));
+            }
+        };
+        let id = match cmd {
+            EditCmd::CreateRoute { ref route, .. } => route.id,
+            _ => unreachable!(),
+        };
+
+        let mut edits = app.primary.map.get_edits().clone();
+        edits.commands.push(cmd);
+        apply_map_edits(ctx, app, edits);
+        ctx.loading_screen("add the new route", |_, timer| {
+            app.primary.map.recalculate_pathfinding_after_edits(timer);
+        });
+        app.primary
+            .sim
+            .start_new_bus_route(app.primary.map.get_br(id), &app.primary.map);
+        // The results no longer match a normal run
+        app.primary.dirty_from_edits = true;
+
+        let now = app.primary.sim.time();
+        let route = app.primary.map.get_br(id);
+        let num_buses = route.spawn_times.iter().filter(|t| **t >= now).count();
+        Transition::Replace(PopupMsg::new(
+            ctx,
+            "Route created",
+            vec![
+                format!(
+                    "{} buses will run on {} for the rest of the day",
+                    num_buses, route.full_name
+                ),
+                "The route is saved with your map edits".to_string(),
+            ],
+        ))
+    }
+}
+
+impl State<App> for NewRoute {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if ctx.redo_mouseover() {
+            app.recalculate_current_selection(ctx);
+            match app.primary.current_selection {
+                Some(ID::Lane(l))
+                    if PathConstraints::Bus.can_use(app.primary.map.get_l(l), &app.primary.map) => {
+                }
+                _ => {
+                    app.primary.current_selection = None;
+                }
+            }
+        }
+        if let Some(ID::Lane(l)) = app.primary.current_selection {
+            if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
+                if app.per_obj.left_click(ctx, "add a stop here") {
+                    self.stops.push(closest_pos(app.primary.map.get_l(l), pt));
+                    self.recalculate(ctx, app);
+                }
+            }
+        }
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "remove last stop" => {
+                    self.stops.pop();
+                    self.recalculate(ctx, app);
+                }
+                "Create route" => {
+                    return self.create(ctx, app);
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed => {
+                self.form.update(ctx, &mut self.panel);
+                self.recalculate(ctx, app);
+            }
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+// The position along a lane closest to a point
+fn closest_pos(lane: &Lane, pt: Pt2D) -> Position {
+    let mut best: Option<(Distance, Distance)> = None;
+    let mut dist_so_far = Distance::ZERO;
+    for line in lane.lane_center_pts.lines() {
+        let proj = line.project_pt(pt);
+        let dist_away = proj.dist_to(pt);
+        if best.map(|(d, _)| dist_away < d).unwrap_or(true) {
+            best = Some((dist_away, dist_so_far + line.pt1().dist_to(proj)));
+        }
+        dist_so_far += line.length();
+    }
+    let dist_along = best.map(|(_, d)| d).unwrap_or(Distance::ZERO);
+    Position::new(lane.id, dist_along.min(lane.length()))
+}
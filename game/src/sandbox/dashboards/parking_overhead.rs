@@ -1,3 +1,4 @@
+use abstutil::prettyprint_usize;
 use geom::Duration;
 use sim::{TripEndpoint, TripID, TripPhaseType};
 use widgetry::{Checkbox, EventCtx, Filler, Line, Panel, State, Text, Widget};
@@ -163,6 +164,30 @@ fn make_table(app: &App) -> Table<Entry, Filters> {
     table
 }
 
+// Where cars parked, compared to the baseline when pricing shifts drivers between the street and
+// lots
+fn parking_choices(app: &App) -> Text {
+    let now = app.primary.sim.time();
+    let (onstreet, lots) = app.primary.sim.get_analytics().parking_choices(now);
+    let mut txt = Text::from(Line(format!(
+        "Cars parked on the street {} times and in parking lots {} times",
+        prettyprint_usize(onstreet),
+        prettyprint_usize(lots)
+    )));
+    if app.has_prebaked().is_some() {
+        let (onstreet, lots) = app.prebaked().parking_choices(now);
+        txt.add(
+            Line(format!(
+                "By this time in the baseline: {} on the street, {} in lots",
+                prettyprint_usize(onstreet),
+                prettyprint_usize(lots)
+            ))
+            .secondary(),
+        );
+    }
+    txt
+}
+
 fn make_panel(ctx: &mut EventCtx, app: &App, table: &Table<Entry, Filters>) -> Panel {
     let mut col = vec![DashTab::ParkingOverhead.picker(ctx, app)];
     col.push(
@@ -191,6 +216,7 @@ fn make_panel(ctx: &mut EventCtx, app: &App, table: &Table<Entry, Filters>) -> P
         ])
         .evenly_spaced(),
     );
+    col.push(parking_choices(app).draw(ctx));
     col.push(table.render(ctx, app));
 
     Panel::new(Widget::col(col))
@@ -0,0 +1,355 @@
+use std::collections::BTreeMap;
+
+use abstutil::prettyprint_usize;
+use geom::Duration;
+use map_model::{IntersectionID, TurnID, TurnType};
+use sim::{TripID, TripMode};
+use widgetry::{Btn, DrawBaselayer, EventCtx, GfxCtx, Line, Outcome, Panel, State, Text, Widget};
+
+use crate::app::{App, Transition};
+use crate::common::Warping;
+use crate::helpers::ID;
+use crate::info::{OpenTrip, Tab};
+use crate::sandbox::dashboards::DashTab;
+use crate::sandbox::SandboxMode;
+
+// How many of each kind of problem to list
+const LIMIT: usize = 20;
+// Anybody stuck at an intersection for this long probably means gridlock
+const GRIDLOCK_THRESHOLD: Duration = Duration::const_seconds(5.0 * 60.0);
+
+/// After a run, lists the worst places and trips, so people know where to look without panning
+/// around the whole map.
+pub struct Problems {
+    panel: Panel,
+    // Indexed by the button clicked
+    targets: Vec<Target>,
+}
+
+enum Target {
+    Trip(TripID),
+    Warp(ID),
+}
+
+impl Problems {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let mut targets = Vec::new();
+        let mut col = vec![DashTab::Problems.picker(ctx, app)];
+
+        col.push(section(
+            ctx,
+            app,
+            if app.has_prebaked().is_some() {
+                "Trips slowed down the most"
+            } else {
+                "Trips delayed the most at intersections"
+            },
+            delayed_trips(app),
+            &mut targets,
+        ));
+        col.push(section(
+            ctx,
+            app,
+            "Gridlocked intersections",
+            gridlocked_intersections(app),
+            &mut targets,
+        ));
+        col.push(section(
+            ctx,
+            app,
+            "Agents stuck right now",
+            stuck_agents(app),
+            &mut targets,
+        ));
+        col.push(section(
+            ctx,
+            app,
+            "Most crowded transit segments",
+            crowded_transit(app),
+            &mut targets,
+        ));
+        col.push(section(
+            ctx,
+            app,
+            "Crosswalks where pedestrians wait the longest",
+            starved_crosswalks(app),
+            &mut targets,
+        ));
+
+        Box::new(Problems {
+            panel: Panel::new(Widget::col(col))
+                .exact_size_percent(90, 90)
+                .build(ctx),
+            targets,
+        })
+    }
+}
+
+impl State<App> for Problems {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => {
+                if x == "close" {
+                    return Transition::Pop;
+                }
+                let idx = x["problem ".len()..].parse::<usize>().unwrap();
+                match self.targets[idx] {
+                    Target::Trip(trip) => {
+                        let person = app.primary.sim.trip_to_person(trip);
+                        Transition::Multi(vec![
+                            Transition::Pop,
+                            Transition::ModifyState(Box::new(move |state, ctx, app| {
+                                let sandbox = state.downcast_mut::<SandboxMode>().unwrap();
+                                let mut actions = sandbox.contextual_actions();
+                                sandbox.controls.common.as_mut().unwrap().launch_info_panel(
+                                    ctx,
+                                    app,
+                                    Tab::PersonTrips(person, OpenTrip::single(trip)),
+                                    &mut actions,
+                                );
+                            })),
+                        ])
+                    }
+                    Target::Warp(ref id) => Transition::Multi(vec![
+                        Transition::Pop,
+                        Transition::Push(Warping::new(
+                            ctx,
+                            app.primary.canonical_point(id.clone()).unwrap(),
+                            Some(10.0),
+                            Some(id.clone()),
+                            &mut app.primary,
+                        )),
+                    ]),
+                }
+            }
+            Outcome::Changed => DashTab::Problems.transition(ctx, app, &self.panel).unwrap(),
+            _ => Transition::Keep,
+        }
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::Custom
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.clear(app.cs.dialog_bg);
+        self.panel.draw(g);
+    }
+}
+
+fn section(
+    ctx: &mut EventCtx,
+    app: &App,
+    title: &str,
+    problems: Vec<(String, Target)>,
+    targets: &mut Vec<Target>,
+) -> Widget {
+    let mut col = vec![Line(title).small_heading().draw(ctx)];
+    if problems.is_empty() {
+        col.push(Text::from(Line("None found").secondary()).draw(ctx));
+    }
+    for (label, target) in problems {
+        col.push(Btn::text_fg(label).build(ctx, format!("problem {}", targets.len()), None));
+        targets.push(target);
+    }
+    Widget::col(col).bg(app.cs.section_bg).padding(16)
+}
+
+fn delayed_trips(app: &App) -> Vec<(String, Target)> {
+    let mut trips: Vec<(TripID, TripMode, Duration, String)> = Vec::new();
+    if app.has_prebaked().is_some() {
+        for (id, before, after, mode) in app
+            .primary
+            .sim
+            .get_analytics()
+            .both_finished_trips(app.primary.sim.time(), app.prebaked())
+        {
+            if after > before {
+                trips.push((
+                    id,
+                    mode,
+                    after - before,
+                    format!("{} slower than before ({})", after - before, after),
+                ));
+            }
+        }
+    } else {
+        for (id, delays) in &app.primary.sim.get_analytics().trip_intersection_delays {
+            let total = delays
+                .values()
+                .fold(Duration::ZERO, |sum, x| sum + Duration::seconds(*x as f64));
+            let mode = app.primary.sim.trip_info(*id).mode;
+            trips.push((
+                *id,
+                mode,
+                total,
+                format!(
+                    "{} waiting at {} intersections",
+                    total,
+                    prettyprint_usize(delays.len())
+                ),
+            ));
+        }
+    }
+    trips.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap());
+    trips
+        .into_iter()
+        .take(LIMIT)
+        .map(|(id, mode, _, description)| {
+            (
+                format!("{} ({}): {}", id, mode.noun(), description),
+                Target::Trip(id),
+            )
+        })
+        .collect()
+}
+
+fn gridlocked_intersections(app: &App) -> Vec<(String, Target)> {
+    // Still stuck right now, or ever delayed too long at a traffic signal
+    let now = app.primary.sim.time();
+    let mut worst: BTreeMap<IntersectionID, (Duration, bool)> = BTreeMap::new();
+    for (i, since) in app.primary.sim.delayed_intersections(GRIDLOCK_THRESHOLD) {
+        worst.insert(i, (now - since, true));
+    }
+    for (i, delays) in &app.primary.sim.get_analytics().intersection_delays {
+        let max = delays
+            .iter()
+            .fold(Duration::ZERO, |max, (_, _, dt, _)| max.max(*dt));
+        if max >= GRIDLOCK_THRESHOLD {
+            let entry = worst.entry(*i).or_insert((max, false));
+            entry.0 = entry.0.max(max);
+        }
+    }
+
+    let mut list: Vec<(IntersectionID, Duration, bool)> = worst
+        .into_iter()
+        .map(|(i, (dt, stuck))| (i, dt, stuck))
+        .collect();
+    list.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    list.into_iter()
+        .take(LIMIT)
+        .map(|(i, dt, stuck)| {
+            (
+                if stuck {
+                    format!("{}: still stuck, for {}", i, dt)
+                } else {
+                    format!("{}: somebody waited {}", i, dt)
+                },
+                Target::Warp(ID::Intersection(i)),
+            )
+        })
+        .collect()
+}
+
+fn stuck_agents(app: &App) -> Vec<(String, Target)> {
+    let sim = &app.primary.sim;
+    sim.agents_blocked_for(GRIDLOCK_THRESHOLD, &app.primary.map)
+        .into_iter()
+        .take(LIMIT)
+        .map(|(a, dt, blocked)| {
+            (
+                format!("{} waiting {}: {}", a, dt, blocked),
+                match sim.agent_to_trip(a) {
+                    Some(trip) => Target::Trip(trip),
+                    None => Target::Warp(ID::from_agent(a)),
+                },
+            )
+        })
+        .collect()
+}
+
+fn crowded_transit(app: &App) -> Vec<(String, Target)> {
+    let map = &app.primary.map;
+    let analytics = app.primary.sim.get_analytics();
+
+    let mut departures = BTreeMap::new();
+    for (_, _, route, stop) in &analytics.bus_arrivals {
+        *departures.entry((*route, *stop)).or_insert(0) += 1;
+    }
+
+    // Everybody riding a route through a segment boarded at an earlier stop and hasn't gotten off
+    // yet. Average that over every vehicle that served the segment.
+    let mut segments = Vec::new();
+    for route in map.all_bus_routes() {
+        let mut riders: isize = 0;
+        for pair in route.stops.windows(2) {
+            let stop = pair[0];
+            riders += analytics
+                .passengers_boarding
+                .get(&stop)
+                .map(|list| list.iter().filter(|(_, r, _)| *r == route.id).count())
+                .unwrap_or(0) as isize;
+            riders -= analytics
+                .passengers_alighting
+                .get(&stop)
+                .map(|list| list.iter().filter(|(_, r)| *r == route.id).count())
+                .unwrap_or(0) as isize;
+            if let Some(vehicles) = departures.get(&(route.id, stop)) {
+                if riders > 0 {
+                    let avg = (riders as f64) / (*vehicles as f64);
+                    segments.push((avg, route, stop, pair[1]));
+                }
+            }
+        }
+    }
+
+    segments.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+    segments
+        .into_iter()
+        .take(LIMIT)
+        .map(|(avg, route, from, to)| {
+            (
+                format!(
+                    "{}: {:.1} riders per vehicle from {} to {}",
+                    route.short_name,
+                    avg,
+                    map.get_bs(from).name,
+                    map.get_bs(to).name
+                ),
+                Target::Warp(ID::BusStop(from)),
+            )
+        })
+        .collect()
+}
+
+fn starved_crosswalks(app: &App) -> Vec<(String, Target)> {
+    let map = &app.primary.map;
+    // Only long waits are recorded per trip, so this is the total of those
+    let mut waits: BTreeMap<TurnID, (Duration, usize)> = BTreeMap::new();
+    for delays in app
+        .primary
+        .sim
+        .get_analytics()
+        .trip_intersection_delays
+        .values()
+    {
+        for (t, secs) in delays {
+            if map.get_t(*t).turn_type == TurnType::Crosswalk {
+                let entry = waits.entry(*t).or_insert((Duration::ZERO, 0));
+                entry.0 += Duration::seconds(*secs as f64);
+                entry.1 += 1;
+            }
+        }
+    }
+
+    let mut list: Vec<(TurnID, Duration, usize)> = waits
+        .into_iter()
+        .map(|(t, (dt, cnt))| (t, dt, cnt))
+        .collect();
+    list.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    list.into_iter()
+        .take(LIMIT)
+        .map(|(t, total, cnt)| {
+            (
+                format!(
+                    "Crossing {} at {}: {} pedestrians waited {} on average",
+                    map.get_parent(t.src).get_name(app.opts.language.as_ref()),
+                    t.parent,
+                    prettyprint_usize(cnt),
+                    total / (cnt as f64)
+                ),
+                Target::Warp(ID::Intersection(t.parent)),
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,123 @@
+use abstutil::{prettyprint_usize, Counter, Parallelism};
+use map_model::{PathStep, RoadID};
+use sim::{TripEndpoint, TripMode};
+use widgetry::{
+    Btn, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Outcome, Panel,
+    State, Text, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::app::Transition;
+use crate::common::{BlockID, Blocks, CommonState};
+
+/// "Select link" analysis: for every trip over the whole day whose route uses one road, show where
+/// those trips start and end, grouped by neighborhood block. This shows who would be affected by
+/// closing the road.
+pub struct SelectLink {
+    panel: Panel,
+    draw: Drawable,
+}
+
+impl SelectLink {
+    pub fn new(ctx: &mut EventCtx, app: &App, r: RoadID) -> Box<dyn State<App>> {
+        let map = &app.primary.map;
+        let (blocks, trips) = ctx.loading_screen("find trips using this road", |_, timer| {
+            let blocks = Blocks::new(app);
+            let trips = timer
+                .parallelize(
+                    "predict routes",
+                    Parallelism::Fastest,
+                    app.primary.sim.all_trip_info(),
+                    |(_, trip)| {
+                        let path = TripEndpoint::path_req(
+                            trip.start.clone(),
+                            trip.end.clone(),
+                            trip.mode,
+                            map,
+                        )
+                        .and_then(|req| map.pathfind(req))?;
+                        if path.get_steps().iter().any(|step| match step {
+                            PathStep::Lane(l) | PathStep::ContraflowLane(l) => {
+                                map.get_l(*l).parent == r
+                            }
+                            PathStep::Turn(_) => false,
+                        }) {
+                            Some(trip)
+                        } else {
+                            None
+                        }
+                    },
+                )
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+            (blocks, trips)
+        });
+
+        let mut per_mode: Counter<TripMode> = Counter::new();
+        let mut flows: Counter<(BlockID, BlockID)> = Counter::new();
+        for trip in &trips {
+            per_mode.inc(trip.mode);
+            if let (Some(from), Some(to)) = (blocks.lookup(&trip.start), blocks.lookup(&trip.end)) {
+                flows.inc((from, to));
+            }
+        }
+
+        let mut batch = GeomBatch::new();
+        batch.push(Color::CYAN, map.get_r(r).get_thick_polygon(map));
+        batch.append(blocks.draw_desire_lines(&flows, Color::PURPLE));
+
+        let mut txt = Text::from(Line(format!(
+            "{} trips over the whole day use this road",
+            prettyprint_usize(trips.len())
+        )));
+        for (mode, cnt) in per_mode.consume() {
+            txt.add(Line(format!("{}: {}", mode.noun(), prettyprint_usize(cnt))).secondary());
+        }
+        txt.add(Line(
+            "Arrows show where these trips start and end, grouped by neighborhood",
+        ));
+
+        Box::new(SelectLink {
+            panel: Panel::new(Widget::col(vec![
+                Widget::row(vec![
+                    Line(format!(
+                        "Who uses {}?",
+                        map.get_r(r).get_name(app.opts.language.as_ref())
+                    ))
+                    .small_heading()
+                    .draw(ctx),
+                    Btn::close(ctx),
+                ]),
+                txt.draw(ctx),
+            ]))
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+            .build(ctx),
+            draw: ctx.upload(batch),
+        })
+    }
+}
+
+impl State<App> for SelectLink {
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
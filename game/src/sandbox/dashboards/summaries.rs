@@ -54,6 +54,8 @@ impl TripSummaries {
                     Widget::col(filters).padding(16).outline(2.0, Color::WHITE),
                     Widget::col(vec![
                         summary_boxes(ctx, app, &filter),
+                        mode_shifts(ctx, app, &filter),
+                        fare_revenue(ctx, app),
                         Widget::row(vec![
                             contingency_table(ctx, app, &filter),
                             scatter_plot(ctx, app, &filter),
@@ -218,6 +220,106 @@ fn summary_boxes(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
     .evenly_spaced()
 }
 
+fn mode_shifts(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
+    if app.has_prebaked().is_none() {
+        return Widget::nothing();
+    }
+
+    let shifts = app
+        .primary
+        .sim
+        .get_analytics()
+        .mode_shifts(app.primary.sim.time(), app.prebaked());
+    let mut changed: Vec<(TripMode, TripMode, usize)> = shifts
+        .changed
+        .into_iter()
+        .filter(|((before, after), _)| {
+            filter.modes.contains(before) || filter.modes.contains(after)
+        })
+        .map(|((before, after), trips)| (before, after, trips.len()))
+        .collect();
+    changed.sort_by_key(|(_, _, cnt)| std::cmp::Reverse(*cnt));
+
+    let mut txt = Text::from(Line("Mode shift").small_heading());
+    if changed.is_empty() {
+        txt.add(Line("Nobody switched modes").secondary());
+    }
+    for (before, after, cnt) in changed {
+        txt.add_appended(vec![
+            Line(format!("{} trips", prettyprint_usize(cnt))),
+            Line(" switched from "),
+            Line(before.ongoing_verb()).fg(color_for_mode(app, before)),
+            Line(" to "),
+            Line(after.ongoing_verb()).fg(color_for_mode(app, after)),
+        ]);
+    }
+    txt.add(
+        Line(format!(
+            "{} trips kept the same mode",
+            prettyprint_usize(shifts.unchanged)
+        ))
+        .secondary(),
+    );
+    if shifts.unmatched > 0 {
+        txt.add(
+            Line(format!(
+                "{} trips couldn't be compared, because different people took them",
+                prettyprint_usize(shifts.unmatched)
+            ))
+            .secondary(),
+        );
+    }
+    txt.draw(ctx).padding(16).outline(2.0, Color::WHITE)
+}
+
+fn fare_revenue(ctx: &mut EventCtx, app: &App) -> Widget {
+    if app.has_prebaked().is_none() {
+        return Widget::nothing();
+    }
+
+    let now = app.primary.sim.time();
+    let after = app.primary.sim.get_analytics().fare_revenue_per_route(now);
+    let before = app.prebaked().fare_revenue_per_route(now);
+    if after.is_empty() && before.is_empty() {
+        return Widget::nothing();
+    }
+    let total_after: usize = after.values().sum();
+    let total_before: usize = before.values().sum();
+
+    let mut changes: Vec<(String, isize)> = Vec::new();
+    for route in before.keys().chain(after.keys()).collect::<BTreeSet<_>>() {
+        let delta = (after.get(route).cloned().unwrap_or(0) as isize)
+            - (before.get(route).cloned().unwrap_or(0) as isize);
+        if delta != 0 {
+            changes.push((app.primary.map.get_br(*route).full_name.clone(), delta));
+        }
+    }
+    changes.sort_by_key(|(_, delta)| std::cmp::Reverse(delta.abs()));
+
+    let mut txt = Text::from(Line("Transit fares").small_heading());
+    txt.add(Line(format!(
+        "{} collected so far, {} before",
+        dollars(total_after as isize),
+        dollars(total_before as isize)
+    )));
+    for (name, delta) in changes.into_iter().take(5) {
+        txt.add(Line(format!("{}: {}", name, signed_dollars(delta))).secondary());
+    }
+    txt.draw(ctx).padding(16).outline(2.0, Color::WHITE)
+}
+
+fn dollars(cents: isize) -> String {
+    format!("${:.2}", (cents as f64) / 100.0)
+}
+
+fn signed_dollars(cents: isize) -> String {
+    if cents >= 0 {
+        format!("+{}", dollars(cents))
+    } else {
+        format!("-{}", dollars(-cents))
+    }
+}
+
 fn scatter_plot(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
     if app.has_prebaked().is_none() {
         return Widget::nothing();
@@ -0,0 +1,417 @@
+use std::collections::BTreeMap;
+
+use geom::{Distance, Duration, PolyLine, Polygon, Pt2D, Time};
+use map_model::{
+    DirectedRoadID, Direction, IntersectionID, LaneID, Map, MovementID, RoadID, TurnPriority,
+};
+use sim::{CarID, VehicleType};
+use widgetry::{
+    Btn, Color, DrawBaselayer, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line,
+    Outcome, Panel, Spinner, State, Text, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::CommonState;
+use crate::helpers::ID;
+use crate::sandbox::dashboards::trajectories::Recorder;
+
+/// A sequence of consecutive roads, traveled in one direction.
+#[derive(Clone)]
+struct Corridor {
+    /// Where the corridor starts and each road ends, so one more than the number of roads
+    intersections: Vec<IntersectionID>,
+    roads: Vec<DirectedRoadID>,
+    /// How far along the corridor each road starts
+    offsets: Vec<Distance>,
+    length: Distance,
+}
+
+impl Corridor {
+    fn new(map: &Map, from: IntersectionID, roads: Vec<RoadID>) -> Corridor {
+        let mut corridor = Corridor {
+            intersections: vec![from],
+            roads: Vec::new(),
+            offsets: Vec::new(),
+            length: Distance::ZERO,
+        };
+        for r in roads {
+            let r = map.get_r(r);
+            let i = *corridor.intersections.last().unwrap();
+            let (dir, next) = if r.src_i == i {
+                (Direction::Fwd, r.dst_i)
+            } else {
+                (Direction::Back, r.src_i)
+            };
+            corridor.roads.push(DirectedRoadID { id: r.id, dir });
+            corridor.intersections.push(next);
+            corridor.offsets.push(corridor.length);
+            corridor.length += r.center_pts.length();
+        }
+        corridor
+    }
+
+    fn reversed(&self, map: &Map) -> Corridor {
+        Corridor::new(
+            map,
+            *self.intersections.last().unwrap(),
+            self.roads.iter().rev().map(|dr| dr.id).collect(),
+        )
+    }
+
+    fn extend_to(&self, map: &Map, i: IntersectionID) -> Option<Corridor> {
+        let end = *self.intersections.last().unwrap();
+        if self.intersections.contains(&i) {
+            return None;
+        }
+        let mut roads: Vec<RoadID> = self.roads.iter().map(|dr| dr.id).collect();
+        roads.extend(map.simple_path_btwn(end, i)?);
+        Some(Corridor::new(map, self.intersections[0], roads))
+    }
+
+    /// Vehicle lanes heading along the corridor, with the index of their road
+    fn lanes(&self, map: &Map) -> Vec<(LaneID, usize)> {
+        let mut lanes = Vec::new();
+        for (idx, dr) in self.roads.iter().enumerate() {
+            for (l, dir, lt) in map.get_r(dr.id).lanes_ltr() {
+                if dir == dr.dir && lt.is_for_moving_vehicles() {
+                    lanes.push((l, idx));
+                }
+            }
+        }
+        lanes
+    }
+
+    /// Traffic signals where one road in the corridor meets the next, with the movement going
+    /// straight along the corridor.
+    fn signals(&self, map: &Map) -> Vec<(IntersectionID, MovementID)> {
+        let mut signals = Vec::new();
+        for (idx, pair) in self.roads.windows(2).enumerate() {
+            let i = self.intersections[idx + 1];
+            if map.maybe_get_traffic_signal(i).is_some() {
+                signals.push((
+                    i,
+                    MovementID {
+                        from: pair[0],
+                        to: pair[1],
+                        parent: i,
+                        crosswalk: false,
+                    },
+                ));
+            }
+        }
+        signals
+    }
+
+    fn draw(&self, map: &Map) -> GeomBatch {
+        let mut batch = GeomBatch::new();
+        for dr in &self.roads {
+            batch.push(
+                Color::CYAN.alpha(0.8),
+                map.get_r(dr.id).get_thick_polygon(map),
+            );
+        }
+        batch.push(
+            Color::GREEN,
+            map.get_i(self.intersections[0]).polygon.clone(),
+        );
+        batch.push(
+            Color::RED,
+            map.get_i(*self.intersections.last().unwrap())
+                .polygon
+                .clone(),
+        );
+        batch
+    }
+}
+
+/// Pick a corridor of roads, then run the simulation for a while to draw a time-space diagram of
+/// it.
+pub struct CorridorRecorder {
+    panel: Panel,
+    corridor: Corridor,
+    draw: Drawable,
+    recorder: Option<Recorder>,
+}
+
+impl CorridorRecorder {
+    pub fn new(ctx: &mut EventCtx, app: &mut App, road: RoadID) -> Box<dyn State<App>> {
+        let map = &app.primary.map;
+        let corridor = Corridor::new(map, map.get_r(road).src_i, vec![road]);
+        CorridorRecorder::from_corridor(ctx, app, corridor)
+    }
+
+    fn from_corridor(ctx: &mut EventCtx, app: &App, corridor: Corridor) -> Box<dyn State<App>> {
+        let map = &app.primary.map;
+        Box::new(CorridorRecorder {
+            panel: Panel::new(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Time-space diagram").small_heading().draw(ctx),
+                    Btn::close(ctx),
+                ]),
+                Text::from_multiline(vec![
+                    Line(format!(
+                        "{} roads, {} long, with {} traffic signals",
+                        corridor.roads.len(),
+                        corridor.length.to_string(&app.opts.units),
+                        corridor.signals(map).len()
+                    )),
+                    Line("Click an intersection to extend the corridor there").secondary(),
+                ])
+                .draw(ctx),
+                Btn::text_fg("reverse direction").build_def(ctx, None),
+                Widget::row(vec![
+                    "Minutes to record:".draw_text(ctx).centered_vert(),
+                    Spinner::new(ctx, (1, 120), 15).named("minutes"),
+                ]),
+                Btn::text_bg2("Start recording")
+                    .build_def(ctx, None)
+                    .named("controls"),
+            ]))
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+            .build(ctx),
+            draw: ctx.upload(corridor.draw(map)),
+            corridor,
+            recorder: None,
+        })
+    }
+}
+
+impl State<App> for CorridorRecorder {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if let Some(ref mut recorder) = self.recorder {
+            if recorder.event(ctx, app) {
+                return Transition::Replace(TimeSpaceDiagram::new(
+                    ctx,
+                    app,
+                    self.corridor.clone(),
+                    recorder,
+                ));
+            }
+            return Transition::Keep;
+        }
+
+        if ctx.redo_mouseover() {
+            app.primary.current_selection = app.mouseover_unzoomed_roads_and_intersections(ctx);
+            if let Some(ID::Intersection(_)) = app.primary.current_selection {
+            } else {
+                app.primary.current_selection = None;
+            }
+        }
+        if let Some(ID::Intersection(i)) = app.primary.current_selection {
+            if let Some(corridor) = self.corridor.extend_to(&app.primary.map, i) {
+                if app.per_obj.left_click(ctx, "extend the corridor here") {
+                    return Transition::Replace(CorridorRecorder::from_corridor(
+                        ctx, app, corridor,
+                    ));
+                }
+            }
+        }
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "reverse direction" => {
+                    let corridor = self.corridor.reversed(&app.primary.map);
+                    return Transition::Replace(CorridorRecorder::from_corridor(
+                        ctx, app, corridor,
+                    ));
+                }
+                "Start recording" => {
+                    let map = &app.primary.map;
+                    let minutes = self.panel.spinner("minutes") as f64;
+                    self.recorder = Some(Recorder::new(
+                        app,
+                        self.corridor
+                            .lanes(map)
+                            .into_iter()
+                            .map(|(l, _)| l)
+                            .collect(),
+                        self.corridor
+                            .signals(map)
+                            .into_iter()
+                            .map(|(i, _)| i)
+                            .collect(),
+                        Duration::minutes(1) * minutes,
+                    ));
+                    app.primary.current_selection = None;
+                    self.panel.replace(
+                        ctx,
+                        "controls",
+                        "Recording...".draw_text(ctx).named("controls"),
+                    );
+                }
+                _ => unreachable!(),
+            },
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+/// Shows where every vehicle was along a corridor over time, with the state of each traffic signal
+/// along the way. Lines crossing a red band mean vehicles had to stop there; good progression
+/// looks like platoons sliding through a series of green bands.
+struct TimeSpaceDiagram {
+    panel: Panel,
+}
+
+impl TimeSpaceDiagram {
+    fn new(
+        ctx: &mut EventCtx,
+        app: &App,
+        corridor: Corridor,
+        recorder: &Recorder,
+    ) -> Box<dyn State<App>> {
+        let map = &app.primary.map;
+        let width = 0.7 * ctx.canvas.window_width;
+        let height = 0.6 * ctx.canvas.window_height;
+        let duration = recorder.end - recorder.start;
+        let to_pt = |time: Time, dist: Distance| {
+            Pt2D::new(
+                width * ((time - recorder.start) / duration),
+                height * (1.0 - dist / corridor.length),
+            )
+        };
+
+        let mut batch = GeomBatch::new();
+        batch.push(app.cs.inner_panel, Polygon::rectangle(width, height));
+
+        // Signal bands
+        let band_height = 6.0;
+        for (i, movement) in corridor.signals(map) {
+            let idx = corridor.intersections.iter().position(|x| *x == i).unwrap();
+            let y = to_pt(recorder.start, corridor.offsets[idx]).y();
+            let signal = map.get_traffic_signal(i);
+            let stages = &recorder.signal_stages[&i];
+            // Merge consecutive samples in the same stage
+            let mut idx = 0;
+            while idx < stages.len() {
+                let (t1, stage) = stages[idx];
+                while idx < stages.len() && stages[idx].1 == stage {
+                    idx += 1;
+                }
+                let t2 = stages.get(idx).map(|(t, _)| *t).unwrap_or(recorder.end);
+                let color = match signal.stages[stage].get_priority_of_movement(movement) {
+                    TurnPriority::Protected => Color::GREEN,
+                    TurnPriority::Yield => Color::YELLOW,
+                    TurnPriority::Banned => Color::RED,
+                };
+                let x1 = to_pt(t1, Distance::ZERO).x();
+                let x2 = to_pt(t2, Distance::ZERO).x();
+                if x2 > x1 {
+                    batch.push(
+                        color.alpha(0.8),
+                        Polygon::rectangle(x2 - x1, band_height)
+                            .translate(x1, y - band_height / 2.0),
+                    );
+                }
+            }
+            batch.append(
+                Text::from(Line(i.to_string()))
+                    .render_to_batch(ctx.prerender)
+                    .scale(0.5)
+                    .translate(width + 5.0, y - 10.0),
+            );
+        }
+
+        // Trajectories. Stitch together each vehicle's position along the whole corridor.
+        let lane_to_road: BTreeMap<LaneID, usize> = corridor.lanes(map).into_iter().collect();
+        let mut per_vehicle: BTreeMap<CarID, Vec<(Time, Distance)>> = BTreeMap::new();
+        for ((car, l), samples) in &recorder.vehicles {
+            let offset = corridor.offsets[lane_to_road[l]];
+            per_vehicle
+                .entry(*car)
+                .or_default()
+                .extend(samples.iter().map(|(t, dist)| (*t, offset + *dist)));
+        }
+        for (car, mut samples) in per_vehicle {
+            samples.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+            let color = match car.1 {
+                VehicleType::Car => app.cs.unzoomed_car,
+                VehicleType::Bike => app.cs.unzoomed_bike,
+                VehicleType::Bus | VehicleType::Train => app.cs.unzoomed_bus,
+            };
+            // Break the line when a vehicle leaves the corridor and comes back later
+            let mut pts = Vec::new();
+            let mut last_time = recorder.start;
+            for (t, dist) in samples {
+                if t - last_time > Duration::seconds(30.0) {
+                    draw_trajectory(&mut batch, color, std::mem::take(&mut pts));
+                }
+                pts.push(to_pt(t, dist));
+                last_time = t;
+            }
+            draw_trajectory(&mut batch, color, pts);
+        }
+
+        let mut legend = Text::new();
+        legend.add_appended(vec![
+            Line("Green").fg(Color::GREEN),
+            Line(" means going straight along the corridor is protected, "),
+            Line("yellow").fg(Color::YELLOW),
+            Line(" means it's permitted, and "),
+            Line("red").fg(Color::RED),
+            Line(" means it isn't allowed"),
+        ]);
+        let panel = Panel::new(Widget::col(vec![
+            Widget::row(vec![
+                Line("Time-space diagram").small_heading().draw(ctx),
+                Btn::close(ctx),
+            ]),
+            format!(
+                "From {} (bottom) to {} (top), {} long",
+                corridor.intersections[0],
+                corridor.intersections.last().unwrap(),
+                corridor.length.to_string(&app.opts.units)
+            )
+            .draw_text(ctx),
+            Widget::draw_batch(ctx, batch),
+            Widget::row(vec![
+                recorder.start.to_string().draw_text(ctx),
+                recorder.end.to_string().draw_text(ctx).align_right(),
+            ]),
+            legend.draw(ctx),
+        ]))
+        .exact_size_percent(90, 90)
+        .build(ctx);
+        Box::new(TimeSpaceDiagram { panel })
+    }
+}
+
+fn draw_trajectory(batch: &mut GeomBatch, color: Color, pts: Vec<Pt2D>) {
+    if let Ok(pl) = PolyLine::new(pts) {
+        batch.push(color, pl.make_polygons(Distance::meters(1.5)));
+    }
+}
+
+impl State<App> for TimeSpaceDiagram {
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => Transition::Pop,
+                _ => unreachable!(),
+            },
+            _ => Transition::Keep,
+        }
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::Custom
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.clear(app.cs.dialog_bg);
+        self.panel.draw(g);
+    }
+}
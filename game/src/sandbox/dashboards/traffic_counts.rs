@@ -0,0 +1,297 @@
+use std::collections::{BTreeMap, HashMap};
+
+use abstutil::prettyprint_usize;
+use geom::{Circle, Distance, Duration, PolyLine, Pt2D, Ring, Time};
+use map_model::{DirectedRoadID, Direction, Map};
+use sim::AgentType;
+use widgetry::{
+    Btn, Checkbox, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
+    LinePlot, Outcome, Panel, PlotOptions, Series, State, Text, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::App;
+use crate::app::Transition;
+use crate::common::CommonState;
+use crate::helpers::color_for_agent_type;
+
+const POINT_RADIUS: Distance = Distance::const_meters(10.0);
+
+/// Draw a screenline (a line across some roads) or a cordon (a polygon around an area) and count
+/// how many agents crossed it in each direction, per hour and mode. This is how real traffic
+/// studies are usually specified.
+pub struct TrafficCounts {
+    panel: Panel,
+    screenline: bool,
+    pts: Vec<Pt2D>,
+    draw: Drawable,
+}
+
+impl TrafficCounts {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let mut state = TrafficCounts {
+            panel: Panel::empty(ctx),
+            screenline: true,
+            pts: Vec::new(),
+            draw: ctx.upload(GeomBatch::new()),
+        };
+        state.recalculate(ctx, app);
+        Box::new(state)
+    }
+
+    fn recalculate(&mut self, ctx: &mut EventCtx, app: &App) {
+        let map = &app.primary.map;
+        let mut batch = GeomBatch::new();
+        let crossings = if self.screenline {
+            match PolyLine::new(self.pts.clone()) {
+                Ok(pl) => {
+                    batch.push(Color::RED, pl.make_polygons(Distance::meters(5.0)));
+                    screenline_crossings(map, &pl)
+                }
+                Err(_) => Vec::new(),
+            }
+        } else if self.pts.len() >= 3 {
+            let mut pts = self.pts.clone();
+            pts.push(pts[0]);
+            match Ring::new(pts) {
+                Ok(ring) => {
+                    let polygon = ring.to_polygon();
+                    batch.push(Color::RED.alpha(0.3), polygon.clone());
+                    cordon_crossings(map, |pt| polygon.contains_pt(pt))
+                }
+                Err(_) => Vec::new(),
+            }
+        } else {
+            Vec::new()
+        };
+        for pt in &self.pts {
+            batch.push(Color::RED, Circle::new(*pt, POINT_RADIUS).to_polygon());
+        }
+        for (dr, _) in &crossings {
+            if dr.dir == Direction::Fwd {
+                batch.push(
+                    Color::CYAN.alpha(0.5),
+                    map.get_r(dr.id).get_thick_polygon(map),
+                );
+            }
+        }
+        self.draw = ctx.upload(batch);
+
+        let labels = if self.screenline {
+            ["Crossing from left to right", "Crossing from right to left"]
+        } else {
+            ["Entering the cordon", "Leaving the cordon"]
+        };
+        let mut col = vec![
+            Widget::row(vec![
+                Line("Screenline and cordon counts")
+                    .small_heading()
+                    .draw(ctx),
+                Btn::close(ctx),
+            ]),
+            Checkbox::toggle(
+                ctx,
+                "screenline / cordon",
+                "screenline",
+                "cordon",
+                None,
+                self.screenline,
+            ),
+            Text::from_multiline(vec![
+                Line("Click the map to add points"),
+                Line(if self.screenline {
+                    "Left and right are relative to the order you place points"
+                } else {
+                    "The cordon closes automatically"
+                })
+                .secondary(),
+            ])
+            .draw(ctx),
+            Btn::text_fg("clear points").build_def(ctx, Key::Backspace),
+        ];
+        if crossings.is_empty() {
+            col.push("Nothing crossed yet".draw_text(ctx));
+        } else {
+            col.push(
+                format!("{} roads crossed", prettyprint_usize(crossings.len() / 2)).draw_text(ctx),
+            );
+            let counts = count_crossings(app, &crossings);
+            for (idx, label) in labels.iter().enumerate() {
+                col.push(Line(*label).small_heading().draw(ctx));
+                let mut series = Vec::new();
+                let mut txt = Text::new();
+                for (agent_type, pts) in &counts[idx] {
+                    let total: usize = pts.iter().step_by(2).map(|(_, cnt)| *cnt).sum();
+                    txt.add(Line(format!(
+                        "{}: {}",
+                        agent_type.noun(),
+                        prettyprint_usize(total)
+                    )));
+                    series.push(Series {
+                        label: agent_type.noun().to_string(),
+                        color: color_for_agent_type(app, *agent_type),
+                        pts: pts.clone(),
+                    });
+                }
+                col.push(txt.draw(ctx));
+                col.push(LinePlot::new(ctx, series, PlotOptions::fixed()));
+            }
+        }
+
+        self.panel = Panel::new(Widget::col(col))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+            .build(ctx);
+    }
+}
+
+impl State<App> for TrafficCounts {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "clear points" => {
+                    self.pts.clear();
+                    self.recalculate(ctx, app);
+                }
+                _ => unreachable!(),
+            },
+            _ => {
+                let screenline = self.panel.is_checked("screenline / cordon");
+                if screenline != self.screenline {
+                    self.screenline = screenline;
+                    self.recalculate(ctx, app);
+                }
+            }
+        }
+
+        if let Some(pt) = ctx.canvas.get_cursor_in_map_space() {
+            if app.per_obj.left_click(ctx, "add a new point") {
+                self.pts.push(pt);
+                self.recalculate(ctx, app);
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+/// Returns every directed road crossing the screenline. 0 means crossing from the left side of the
+/// line to the right, as the points were placed; 1 is the opposite.
+fn screenline_crossings(map: &Map, screenline: &PolyLine) -> Vec<(DirectedRoadID, usize)> {
+    let mut results = Vec::new();
+    for r in map.all_roads() {
+        // Only count the first crossing, in case the screenline winds back over a road
+        if let Some(cross) = first_crossing(&r.center_pts, screenline) {
+            let fwd = if cross > 0.0 { 0 } else { 1 };
+            results.push((
+                DirectedRoadID {
+                    id: r.id,
+                    dir: Direction::Fwd,
+                },
+                fwd,
+            ));
+            results.push((
+                DirectedRoadID {
+                    id: r.id,
+                    dir: Direction::Back,
+                },
+                1 - fwd,
+            ));
+        }
+    }
+    results
+}
+
+// If the road crosses the screenline, returns the cross product of the screenline's direction and
+// the road's forwards direction at that point. Since map-space Y points down, positive means the
+// road goes from the left of the screenline to the right.
+fn first_crossing(road: &PolyLine, screenline: &PolyLine) -> Option<f64> {
+    for l1 in road.lines() {
+        for l2 in screenline.lines() {
+            if l1.intersection(&l2).is_some() {
+                return Some(cross(&l2, &l1));
+            }
+        }
+    }
+    None
+}
+
+fn cross(a: &geom::Line, b: &geom::Line) -> f64 {
+    let (dx1, dy1) = (a.pt2().x() - a.pt1().x(), a.pt2().y() - a.pt1().y());
+    let (dx2, dy2) = (b.pt2().x() - b.pt1().x(), b.pt2().y() - b.pt1().y());
+    dx1 * dy2 - dy1 * dx2
+}
+
+/// Returns every directed road crossing the cordon boundary. 0 means entering the cordon, 1 means
+/// leaving.
+fn cordon_crossings<F: Fn(Pt2D) -> bool>(map: &Map, inside: F) -> Vec<(DirectedRoadID, usize)> {
+    let mut results = Vec::new();
+    for r in map.all_roads() {
+        let src_inside = inside(map.get_i(r.src_i).polygon.center());
+        let dst_inside = inside(map.get_i(r.dst_i).polygon.center());
+        if src_inside == dst_inside {
+            continue;
+        }
+        let fwd = if dst_inside { 0 } else { 1 };
+        results.push((
+            DirectedRoadID {
+                id: r.id,
+                dir: Direction::Fwd,
+            },
+            fwd,
+        ));
+        results.push((
+            DirectedRoadID {
+                id: r.id,
+                dir: Direction::Back,
+            },
+            1 - fwd,
+        ));
+    }
+    results
+}
+
+/// For both directions, sums the hourly counts per agent type over all of the crossings. The
+/// result matches the shape of TimeSeriesCount::count_per_hour, so it can be plotted directly.
+fn count_crossings(
+    app: &App,
+    crossings: &[(DirectedRoadID, usize)],
+) -> Vec<Vec<(AgentType, Vec<(Time, usize)>)>> {
+    let lookup: HashMap<DirectedRoadID, usize> = crossings.iter().cloned().collect();
+    let mut per_hour: Vec<BTreeMap<(AgentType, usize), usize>> = vec![BTreeMap::new(); 2];
+    for ((dr, agent_type, hour), cnt) in
+        &app.primary.sim.get_analytics().directed_road_thruput.counts
+    {
+        if let Some(idx) = lookup.get(dr) {
+            *per_hour[*idx].entry((*agent_type, *hour)).or_insert(0) += cnt;
+        }
+    }
+
+    let last_hour = app.primary.sim.time().get_hours();
+    per_hour
+        .into_iter()
+        .map(|counts| {
+            let mut results = Vec::new();
+            for agent_type in AgentType::all() {
+                let mut pts = Vec::new();
+                for hour in 0..=last_hour {
+                    let cnt = counts.get(&(agent_type, hour)).cloned().unwrap_or(0);
+                    pts.push((Time::START_OF_DAY + Duration::hours(hour), cnt));
+                    pts.push((Time::START_OF_DAY + Duration::hours(hour + 1), cnt));
+                }
+                pts.pop();
+                results.push((agent_type, pts));
+            }
+            results
+        })
+        .collect()
+}
@@ -0,0 +1,260 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::Write;
+use std::time::Instant;
+
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Distance, Duration, Time};
+use map_gui::tools::PopupMsg;
+use map_model::{IntersectionID, LaneID, RoadID, Traversable};
+use sim::CarID;
+use widgetry::{
+    Btn, Color, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Outcome, Panel,
+    Spinner, State, Text, TextExt, UpdateType, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::CommonState;
+
+/// How often vehicle positions are sampled
+const FREQUENCY: Duration = Duration::const_seconds(1.0);
+
+/// Runs the simulation for a while, recording where every vehicle on one road is each second, then
+/// exports these time-space trajectories to a CSV file. Traffic engineers plot these to see how
+/// platoons of vehicles move through a corridor of signals.
+pub struct Trajectories {
+    panel: Panel,
+    road: RoadID,
+    draw: Drawable,
+    recorder: Option<Recorder>,
+}
+
+impl Trajectories {
+    pub fn new(ctx: &mut EventCtx, app: &App, road: RoadID) -> Box<dyn State<App>> {
+        let map = &app.primary.map;
+        let mut batch = GeomBatch::new();
+        batch.push(Color::CYAN, map.get_r(road).get_thick_polygon(map));
+
+        Box::new(Trajectories {
+            panel: Panel::new(Widget::col(vec![
+                Widget::row(vec![
+                    Line(format!(
+                        "Vehicle trajectories along {}",
+                        map.get_r(road).get_name(app.opts.language.as_ref())
+                    ))
+                    .small_heading()
+                    .draw(ctx),
+                    Btn::close(ctx),
+                ]),
+                Text::from_multiline(vec![
+                    Line("Runs the simulation, recording every vehicle on this road each second."),
+                    Line(
+                        "Distance is measured from where vehicles enter the road, in their \
+                         direction of travel.",
+                    )
+                    .secondary(),
+                ])
+                .wrap_to_pct(ctx, 30)
+                .draw(ctx),
+                Widget::row(vec![
+                    "Minutes to record:".draw_text(ctx).centered_vert(),
+                    Spinner::new(ctx, (1, 120), 15).named("minutes"),
+                ]),
+                Btn::text_bg2("Start recording")
+                    .build_def(ctx, None)
+                    .named("controls"),
+            ]))
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+            .build(ctx),
+            road,
+            draw: ctx.upload(batch),
+            recorder: None,
+        })
+    }
+}
+
+impl State<App> for Trajectories {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Start recording" => {
+                    let map = &app.primary.map;
+                    let lanes = map
+                        .get_r(self.road)
+                        .all_lanes()
+                        .into_iter()
+                        .filter(|l| map.get_l(*l).lane_type.is_for_moving_vehicles())
+                        .collect();
+                    let minutes = self.panel.spinner("minutes") as f64;
+                    self.recorder = Some(Recorder::new(
+                        app,
+                        lanes,
+                        Vec::new(),
+                        Duration::minutes(1) * minutes,
+                    ));
+                    self.panel.replace(
+                        ctx,
+                        "controls",
+                        "Recording...".draw_text(ctx).named("controls"),
+                    );
+                }
+                _ => unreachable!(),
+            },
+            _ => {}
+        }
+
+        if let Some(ref mut recorder) = self.recorder {
+            if recorder.event(ctx, app) {
+                let num_vehicles = recorder
+                    .vehicles
+                    .keys()
+                    .map(|(car, _)| *car)
+                    .collect::<BTreeSet<_>>()
+                    .len();
+                return Transition::Replace(match export(app, self.road, &recorder.vehicles) {
+                    Ok(path) => PopupMsg::new(
+                        ctx,
+                        "Trajectories exported",
+                        vec![format!(
+                            "{} vehicles recorded, exported to {}",
+                            prettyprint_usize(num_vehicles),
+                            path
+                        )],
+                    ),
+                    Err(err) => PopupMsg::new(ctx, "Export failed", vec![err.to_string()]),
+                });
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.redraw(&self.draw);
+        self.panel.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
+
+fn export(app: &App, road: RoadID, samples: &Samples) -> Result<String, std::io::Error> {
+    let map = &app.primary.map;
+    let r = map.get_r(road);
+    let path = format!(
+        "trajectories_{}_road{}_{}.csv",
+        map.get_name().as_filename(),
+        road.0,
+        app.primary.sim.time().as_filename()
+    );
+    let mut f = File::create(&path)?;
+    writeln!(
+        f,
+        "seconds,vehicle,lane,direction,meters_along_road,meters_per_second"
+    )?;
+    for ((car, l), pts) in samples {
+        for (idx, (t, dist)) in pts.iter().enumerate() {
+            // Estimate speed from the neighboring samples
+            let (t1, d1) = pts[idx.saturating_sub(1)];
+            let (t2, d2) = pts[(idx + 1).min(pts.len() - 1)];
+            let speed = if t2 > t1 {
+                format!(
+                    "{:.2}",
+                    (d2 - d1).inner_meters() / (t2 - t1).inner_seconds()
+                )
+            } else {
+                String::new()
+            };
+            writeln!(
+                f,
+                "{},{},{},{},{:.2},{}",
+                (*t - Time::START_OF_DAY).inner_seconds(),
+                car,
+                l.0,
+                r.dir(*l),
+                dist.inner_meters(),
+                speed
+            )?;
+        }
+    }
+    Ok(path)
+}
+
+/// For each vehicle and the lane it was on, where it was along the lane over time
+pub type Samples = BTreeMap<(CarID, LaneID), Vec<(Time, Distance)>>;
+
+/// Steps the simulation at a fixed frequency for a while, sampling where vehicles are on some
+/// lanes and which stage some traffic signals are in.
+pub struct Recorder {
+    lanes: Vec<LaneID>,
+    signals: Vec<IntersectionID>,
+    pub start: Time,
+    pub end: Time,
+    pub vehicles: Samples,
+    pub signal_stages: BTreeMap<IntersectionID, Vec<(Time, usize)>>,
+}
+
+impl Recorder {
+    pub fn new(
+        app: &App,
+        lanes: Vec<LaneID>,
+        signals: Vec<IntersectionID>,
+        duration: Duration,
+    ) -> Recorder {
+        let start = app.primary.sim.time();
+        let mut recorder = Recorder {
+            lanes,
+            signals,
+            start,
+            end: start + duration,
+            vehicles: BTreeMap::new(),
+            signal_stages: BTreeMap::new(),
+        };
+        recorder.sample(app);
+        recorder
+    }
+
+    /// Returns true once recording is done.
+    pub fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> bool {
+        if ctx.input.nonblocking_is_update_event().is_some() {
+            ctx.input.use_update_event();
+            // Sample at a fixed frequency, but don't freeze the UI
+            let started = Instant::now();
+            while app.primary.sim.time() < self.end
+                && Duration::realtime_elapsed(started) < Duration::seconds(0.033)
+            {
+                app.primary.sim.timed_step(
+                    &app.primary.map,
+                    FREQUENCY,
+                    &mut app.primary.sim_cb,
+                    &mut Timer::throwaway(),
+                );
+                self.sample(app);
+            }
+        }
+        if app.primary.sim.time() >= self.end {
+            return true;
+        }
+        ctx.request_update(UpdateType::Game);
+        false
+    }
+
+    fn sample(&mut self, app: &App) {
+        let now = app.primary.sim.time();
+        for l in &self.lanes {
+            for (car, dist) in app.primary.sim.get_car_positions_on(Traversable::Lane(*l)) {
+                self.vehicles
+                    .entry((car, *l))
+                    .or_default()
+                    .push((now, dist));
+            }
+        }
+        for i in &self.signals {
+            let (stage, _) = app.primary.sim.current_stage_and_remaining_time(*i);
+            self.signal_stages.entry(*i).or_default().push((now, stage));
+        }
+    }
+}
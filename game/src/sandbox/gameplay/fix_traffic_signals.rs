@@ -1,4 +1,5 @@
 use geom::{Duration, Time};
+use map_gui::options::KpiTargets;
 use map_model::IntersectionID;
 use widgetry::{
     Btn, Color, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, RewriteColor,
@@ -288,6 +289,13 @@ impl GameplayState for FixTrafficSignals {
         app.primary.sim_cb = None;
         app.primary.sim.unset_periodic_callback();
     }
+
+    fn kpi_targets(&self) -> Option<KpiTargets> {
+        Some(KpiTargets {
+            max_avg_delay: Some(Duration::minutes(1)),
+            ..Default::default()
+        })
+    }
 }
 
 fn make_meter(ctx: &mut EventCtx, app: &App, worst: Option<(IntersectionID, Duration)>) -> Panel {
@@ -167,11 +167,30 @@ pub fn make_change_traffic(
              afternoon. It'll be very quiet before 7am and between 10am to 5pm.",
         ),
     );
+    choices.push(
+        Choice::new("residents commuting", "residents".to_string()).tooltip(
+            "The same people live on this map every time, and those with jobs here go to work \
+             in the morning and return in the evening. Useful for following somebody before and \
+             after changing the map.",
+        ),
+    );
+    choices.push(
+        Choice::new("visits to parks on a sunny day", "sunny_day".to_string()).tooltip(
+            "Randomized people will visit every park between 10am and 5pm, staying for a few \
+             hours.",
+        ),
+    );
     choices.push(
         Choice::new("random unrealistic trips", "random".to_string()).tooltip(
             "Lots of trips will start at midnight, but not constantly appear through the day.",
         ),
     );
+    choices.push(
+        Choice::new("many random unrealistic trips", "big_run".to_string()).tooltip(
+            "Thousands of trips will start in the first hour after midnight, and lots of cars \
+             stay parked all day.",
+        ),
+    );
     choices.push(Choice::new(
         "none, except for buses -- you manually spawn traffic",
         "none".to_string(),
@@ -502,13 +521,16 @@ pub fn spawn_agents_around(i: IntersectionID, app: &mut App) {
     }
 
     let retry_if_no_room = false;
-    scenario.instantiate_without_retries(
-        &mut app.primary.sim,
-        map,
-        &mut rng,
-        retry_if_no_room,
-        &mut timer,
-    );
+    // Everything was just picked from the map
+    scenario
+        .instantiate_without_retries(
+            &mut app.primary.sim,
+            map,
+            &mut rng,
+            retry_if_no_room,
+            &mut timer,
+        )
+        .unwrap();
     app.primary.sim.tiny_step(map, &mut app.primary.sim_cb);
 }
 
@@ -2,8 +2,9 @@ use rand_xorshift::XorShiftRng;
 
 use abstutil::{MapName, Timer};
 use geom::Duration;
+use map_gui::options::KpiTargets;
 use map_model::{EditCmd, EditIntersection, Map, MapEdits};
-use sim::{OrigPersonID, Scenario, ScenarioGenerator, ScenarioModifier};
+use sim::{OrigPersonID, Population, Scenario, ScenarioGenerator, ScenarioModifier};
 use widgetry::{
     lctrl, Btn, Color, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, Panel, State, TextExt,
     Widget,
@@ -74,6 +75,11 @@ pub trait GameplayState: downcast_rs::Downcast {
     fn has_minimap(&self) -> bool {
         true
     }
+    /// Goals for the key numbers about the simulation, shown even if the player hasn't turned
+    /// them on. None uses the player's own goals.
+    fn kpi_targets(&self) -> Option<KpiTargets> {
+        None
+    }
 }
 downcast_rs::impl_downcast!(GameplayState);
 
@@ -108,10 +114,14 @@ impl GameplayMode {
             }
             _ => "weekday".to_string(),
         };
-        if name == "random" {
-            LoadScenario::Scenario(ScenarioGenerator::small_run(map).generate(map, &mut rng, timer))
+        // The scenario picker has always called small_run "random"
+        let preset = if name == "random" { "small_run" } else { &name };
+        if let Some(s) = ScenarioGenerator::preset(preset, map) {
+            LoadScenario::Scenario(s.generate(map, &mut rng, timer))
         } else if name == "home_to_work" {
             LoadScenario::Scenario(ScenarioGenerator::proletariat_robot(map, &mut rng, timer))
+        } else if name == "residents" {
+            LoadScenario::Scenario(Population::load_or_generate(map, timer).commutes(map, &mut rng))
         } else {
             LoadScenario::Path(abstutil::path_scenario(map.get_name(), &name))
         }
@@ -139,7 +149,7 @@ impl GameplayMode {
     }
 
     pub fn allows(&self, edits: &MapEdits) -> bool {
-        for cmd in &edits.commands {
+        for cmd in edits.commands.iter().flat_map(|cmd| cmd.flatten()) {
             match cmd {
                 EditCmd::ChangeRoad { .. } => {
                     if !self.can_edit_lanes() {
@@ -155,7 +165,14 @@ impl GameplayMode {
                     }
                     _ => {}
                 },
-                EditCmd::ChangeRouteSchedule { .. } => {}
+                EditCmd::ChangeRouteSchedule { .. }
+                | EditCmd::ChangeRouteStops { .. }
+                | EditCmd::ChangeRouteDetours { .. }
+                | EditCmd::CreateRoute { .. }
+                | EditCmd::DeleteRoute { .. }
+                | EditCmd::ChangeIntersectionCapacity { .. }
+                | EditCmd::ChangeCrosswalks { .. }
+                | EditCmd::Bulk { .. } => {}
             }
         }
         true
@@ -2,8 +2,9 @@ use std::collections::BTreeSet;
 
 use maplit::btreeset;
 
+use geom::Duration;
 use map_gui::tools::{ChooseSomething, CityPicker, PopupMsg};
-use sim::{ScenarioModifier, TripMode};
+use sim::{BikeShareConfig, ScenarioModifier, TripMode};
 use widgetry::{
     lctrl, Btn, Choice, Color, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel,
     Slider, Spinner, State, Text, TextExt, VerticalAlignment, Widget,
@@ -196,6 +197,46 @@ impl EditScenarioModifiers {
             Spinner::new(ctx, (2, 14), 2).named("repeat_days"),
             Btn::text_bg2("Repeat schedule multiple days").build_def(ctx, None),
         ]));
+        rows.push(Widget::row(vec![
+            Spinner::new(ctx, (10, 10_000), 500).named("bike_share_vehicles"),
+            Btn::text_bg2("Add shared bikes or scooters").build_def(ctx, None),
+        ]));
+        rows.push(Widget::row(vec![
+            "On-street $".draw_text(ctx).centered_vert(),
+            Spinner::new(ctx, (0, 50), 2).named("onstreet_dollars"),
+            "Lots $".draw_text(ctx).centered_vert(),
+            Spinner::new(ctx, (0, 50), 10).named("lot_dollars"),
+            Btn::text_bg2("Price parking").build_def(ctx, None),
+        ]));
+        rows.push(Widget::row(vec![
+            Spinner::new(ctx, (-100, 100), -20).named("car_ownership_pct"),
+            "%".draw_text(ctx).centered_vert(),
+            Btn::text_bg2("Change car ownership").build_def(ctx, None),
+        ]));
+        if !modifiers.contains(&ScenarioModifier::RespectCapacities) {
+            rows.push(Btn::text_bg2("Respect building capacities").build_def(ctx, None));
+        }
+        rows.push(Widget::row(vec![
+            Spinner::new(ctx, (1, 200), 10).named("sample_pct"),
+            Btn::text_bg2("Percent of people to simulate").build_def(ctx, None),
+        ]));
+        rows.push(Widget::row(vec![
+            Spinner::new(ctx, (0, 20), 2).named("trains_per_hour"),
+            Btn::text_bg2("Trains per hour").build_def(ctx, None),
+        ]));
+        rows.push(Widget::row(vec![
+            Spinner::new(ctx, (0, 48), 6).named("bridge_openings_per_day"),
+            "times a day for".draw_text(ctx).centered_vert(),
+            Spinner::new(ctx, (1, 60), 10).named("bridge_opening_minutes"),
+            "minutes".draw_text(ctx).centered_vert(),
+            Btn::text_bg2("Open movable bridges").build_def(ctx, None),
+        ]));
+        rows.push(Widget::row(vec![
+            "Give up after waiting".draw_text(ctx).centered_vert(),
+            Spinner::new(ctx, (1, 120), 30).named("abandon_trips_minutes"),
+            "minutes".draw_text(ctx).centered_vert(),
+            Btn::text_bg2("Abandon stuck trips").build_def(ctx, None),
+        ]));
         rows.push(Widget::horiz_separator(ctx, 0.5));
         rows.push(
             Widget::row(vec![
@@ -285,6 +326,87 @@ impl State<App> for EditScenarioModifiers {
                         self.modifiers.clone(),
                     ));
                 }
+                "Add shared bikes or scooters" => {
+                    let mut config = BikeShareConfig::default();
+                    config.num_vehicles = self.panel.spinner("bike_share_vehicles") as usize;
+                    self.modifiers.push(ScenarioModifier::BikeShare(config));
+                    return Transition::Replace(EditScenarioModifiers::new(
+                        ctx,
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    ));
+                }
+                "Price parking" => {
+                    self.modifiers.push(ScenarioModifier::PriceParking {
+                        onstreet_cents: 100 * self.panel.spinner("onstreet_dollars") as usize,
+                        lot_cents: 100 * self.panel.spinner("lot_dollars") as usize,
+                    });
+                    return Transition::Replace(EditScenarioModifiers::new(
+                        ctx,
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    ));
+                }
+                "Change car ownership" => {
+                    self.modifiers.push(ScenarioModifier::ChangeCarOwnership(
+                        self.panel.spinner("car_ownership_pct"),
+                    ));
+                    return Transition::Replace(EditScenarioModifiers::new(
+                        ctx,
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    ));
+                }
+                "Percent of people to simulate" => {
+                    self.modifiers.push(ScenarioModifier::SamplePeople(
+                        self.panel.spinner("sample_pct") as usize,
+                    ));
+                    return Transition::Replace(EditScenarioModifiers::new(
+                        ctx,
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    ));
+                }
+                "Trains per hour" => {
+                    self.modifiers.push(ScenarioModifier::TrainsPerHour(
+                        self.panel.spinner("trains_per_hour") as usize,
+                    ));
+                    return Transition::Replace(EditScenarioModifiers::new(
+                        ctx,
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    ));
+                }
+                "Open movable bridges" => {
+                    self.modifiers.push(ScenarioModifier::OpenBridges {
+                        per_day: self.panel.spinner("bridge_openings_per_day") as usize,
+                        minutes: self.panel.spinner("bridge_opening_minutes") as usize,
+                    });
+                    return Transition::Replace(EditScenarioModifiers::new(
+                        ctx,
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    ));
+                }
+                "Abandon stuck trips" => {
+                    self.modifiers
+                        .push(ScenarioModifier::AbandonTripsAfter(Duration::minutes(
+                            self.panel.spinner("abandon_trips_minutes") as usize,
+                        )));
+                    return Transition::Replace(EditScenarioModifiers::new(
+                        ctx,
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    ));
+                }
+                "Respect building capacities" => {
+                    self.modifiers.push(ScenarioModifier::RespectCapacities);
+                    return Transition::Replace(EditScenarioModifiers::new(
+                        ctx,
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    ));
+                }
                 x => {
                     if let Some(x) = x.strip_prefix("delete modifier ") {
                         self.modifiers.remove(x.parse::<usize>().unwrap() - 1);
@@ -6,8 +6,8 @@ use map_gui::tools::PopupMsg;
 use map_model::raw::OriginalRoad;
 use map_model::{osm, BuildingID, Map, Position};
 use sim::{
-    AgentID, Analytics, BorderSpawnOverTime, CarID, IndividTrip, PersonSpec, Scenario,
-    ScenarioGenerator, SpawnOverTime, TripEndpoint, TripMode, TripPurpose, VehicleType,
+    AgentID, Analytics, BorderSpawnOverTime, CarID, DepartureDistribution, IndividTrip, PersonSpec,
+    Scenario, ScenarioGenerator, SpawnOverTime, TripEndpoint, TripMode, TripPurpose, VehicleType,
 };
 use widgetry::{
     hotkeys, lctrl, Btn, Color, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel,
@@ -655,10 +655,12 @@ fn make_bike_lane_scenario(map: &Map) -> ScenarioGenerator {
         percent_use_transit: 0.0,
         start_time: Time::START_OF_DAY,
         stop_time: Time::START_OF_DAY + Duration::seconds(10.0),
+        departures: DepartureDistribution::Uniform,
         start_from_border: map.find_i_by_osm_id(osm::NodeID(3005680098)).unwrap(),
         goal: Some(TripEndpoint::Bldg(
             map.find_b_by_osm_id(bldg(217699501)).unwrap(),
         )),
+        destinations: Vec::new(),
     });
     s
 }
@@ -1161,12 +1163,22 @@ impl TutorialState {
                             num_agents: 1000,
                             start_time: Time::START_OF_DAY,
                             stop_time: Time::START_OF_DAY + Duration::hours(3),
+                            departures: DepartureDistribution::Uniform,
+                            origins: Vec::new(),
                             goal: None,
+                            destinations: Vec::new(),
+                            activities: Vec::new(),
                             percent_driving: 1.0,
                             percent_biking: 0.0,
                             percent_use_transit: 0.0,
                         }],
                         border_spawn_over_time: Vec::new(),
+                        area_visits: Vec::new(),
+                        venue_events: Vec::new(),
+                        destination_weights: None,
+                        through_traffic: Vec::new(),
+                        seed_parked_cars: None,
+                        phases: Vec::new(),
                     }
                     .generate(
                         &app.primary.map,
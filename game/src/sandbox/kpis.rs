@@ -0,0 +1,219 @@
+use abstutil::prettyprint_usize;
+use geom::{Duration, Polygon, Time};
+use map_gui::options::KpiTargets;
+use widgetry::{
+    Color, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Line, Panel, Text, VerticalAlignment,
+    Widget,
+};
+
+use crate::app::App;
+
+// How often to measure, in simulated time
+const SAMPLE_EVERY: Duration = Duration::const_seconds(5.0 * 60.0);
+// Transit vehicles leaving up to this late still count as on time
+const ON_TIME: Duration = Duration::const_seconds(5.0 * 60.0);
+// The sparklines show this many of the most recent measurements
+const SPARKLINE_SAMPLES: usize = 36;
+const SPARKLINE_WIDTH: f64 = 72.0;
+const SPARKLINE_HEIGHT: f64 = 20.0;
+
+/// A few key numbers about the whole simulation, each with a sparkline of its recent history.
+/// Numbers missing their target are shown in red.
+pub struct KpiStrip {
+    panel: Panel,
+    /// The challenge being played may set its own targets, instead of the player's
+    challenge_targets: Option<KpiTargets>,
+    /// What the panel was last drawn with
+    targets: KpiTargets,
+    history: Vec<(Time, Kpis)>,
+}
+
+#[derive(Clone)]
+struct Kpis {
+    avg_delay: Option<Duration>,
+    active_trips: usize,
+    transit_on_time: Option<f64>,
+    co2_kg: f64,
+}
+
+impl Kpis {
+    fn measure(app: &App) -> Kpis {
+        let sim = &app.primary.sim;
+        let now = sim.time();
+        let analytics = sim.get_analytics();
+        Kpis {
+            avg_delay: analytics.average_trip_delay(now),
+            active_trips: analytics
+                .active_agents(now)
+                .last()
+                .map(|(_, cnt)| *cnt)
+                .unwrap_or(0),
+            transit_on_time: sim.percent_transit_on_time(ON_TIME),
+            co2_kg: analytics.co2_emissions_kg(),
+        }
+    }
+}
+
+impl KpiStrip {
+    pub fn new(ctx: &mut EventCtx, app: &App, challenge_targets: Option<KpiTargets>) -> KpiStrip {
+        let mut strip = KpiStrip {
+            panel: Panel::empty(ctx),
+            targets: KpiTargets::default(),
+            challenge_targets,
+            history: Vec::new(),
+        };
+        strip.sample(ctx, app);
+        strip
+    }
+
+    fn current_targets(&self, app: &App) -> KpiTargets {
+        self.challenge_targets
+            .clone()
+            .unwrap_or_else(|| app.opts.kpi_targets.clone())
+    }
+
+    fn sample(&mut self, ctx: &mut EventCtx, app: &App) {
+        self.history
+            .push((app.primary.sim.time(), Kpis::measure(app)));
+        self.recreate_panel(ctx, app);
+    }
+
+    pub fn recreate_panel(&mut self, ctx: &mut EventCtx, app: &App) {
+        self.targets = self.current_targets(app);
+        let kpis = &self.history.last().unwrap().1;
+        let history: Vec<&Kpis> = self
+            .history
+            .iter()
+            .rev()
+            .take(SPARKLINE_SAMPLES)
+            .rev()
+            .map(|(_, k)| k)
+            .collect();
+
+        let mut col = vec![Line("Key numbers").small_heading().draw(ctx)];
+        col.push(kpi_row(
+            ctx,
+            "Average delay per trip",
+            kpis.avg_delay
+                .map(|dt| dt.to_string(&app.opts.units))
+                .unwrap_or_else(|| "none yet".to_string()),
+            match (kpis.avg_delay, self.targets.max_avg_delay) {
+                (Some(dt), Some(max)) => dt > max,
+                _ => false,
+            },
+            history
+                .iter()
+                .map(|k| k.avg_delay.unwrap_or(Duration::ZERO).inner_seconds())
+                .collect(),
+        ));
+        col.push(kpi_row(
+            ctx,
+            "Active trips",
+            prettyprint_usize(kpis.active_trips),
+            false,
+            history.iter().map(|k| k.active_trips as f64).collect(),
+        ));
+        col.push(kpi_row(
+            ctx,
+            "Transit on time",
+            kpis.transit_on_time
+                .map(|pct| format!("{}%", pct.round()))
+                .unwrap_or_else(|| "none yet".to_string()),
+            match (kpis.transit_on_time, self.targets.min_transit_on_time) {
+                (Some(pct), Some(min)) => pct < min,
+                _ => false,
+            },
+            history
+                .iter()
+                .map(|k| k.transit_on_time.unwrap_or(0.0))
+                .collect(),
+        ));
+        col.push(kpi_row(
+            ctx,
+            "CO2 so far",
+            if kpis.co2_kg >= 1000.0 {
+                format!("{:.1} tonnes", kpis.co2_kg / 1000.0)
+            } else {
+                format!("{}kg", kpis.co2_kg.round())
+            },
+            self.targets
+                .max_co2_kg
+                .map(|max| kpis.co2_kg > max)
+                .unwrap_or(false),
+            history.iter().map(|k| k.co2_kg).collect(),
+        ));
+
+        self.panel = Panel::new(Widget::col(col))
+            .aligned(HorizontalAlignment::Left, VerticalAlignment::Center)
+            .build(ctx);
+    }
+
+    pub fn event(&mut self, ctx: &mut EventCtx, app: &App) {
+        let now = app.primary.sim.time();
+        let last = self.history.last().unwrap().0;
+        if now < last {
+            // The simulation was reset
+            self.history.clear();
+            self.sample(ctx, app);
+        } else if now - last >= SAMPLE_EVERY {
+            self.sample(ctx, app);
+        } else if self.current_targets(app) != self.targets {
+            self.recreate_panel(ctx, app);
+        }
+        self.panel.event(ctx);
+    }
+
+    pub fn draw(&self, g: &mut GfxCtx) {
+        self.panel.draw(g);
+    }
+}
+
+fn kpi_row(
+    ctx: &mut EventCtx,
+    label: &str,
+    value: String,
+    missed_target: bool,
+    history: Vec<f64>,
+) -> Widget {
+    let mut txt = Text::from(Line(label).secondary());
+    txt.add(if missed_target {
+        Line(value).fg(Color::RED)
+    } else {
+        Line(value)
+    });
+    Widget::row(vec![
+        txt.draw(ctx),
+        sparkline(ctx, history, missed_target).align_right(),
+    ])
+}
+
+// Draws each value as a bar, scaled so the biggest fills the height
+fn sparkline(ctx: &mut EventCtx, values: Vec<f64>, missed_target: bool) -> Widget {
+    let color = if missed_target {
+        Color::RED
+    } else {
+        Color::hex("#4CA7E9")
+    };
+    let max = values.iter().cloned().fold(0.0, f64::max);
+    let bar_width = SPARKLINE_WIDTH / (SPARKLINE_SAMPLES as f64);
+
+    let mut batch = GeomBatch::new();
+    // Always take up the full size, even before there's much history
+    batch.push(
+        Color::INVISIBLE,
+        Polygon::rectangle(SPARKLINE_WIDTH, SPARKLINE_HEIGHT),
+    );
+    for (idx, value) in values.into_iter().enumerate() {
+        let height = if max > 0.0 {
+            (SPARKLINE_HEIGHT * value / max).max(1.0)
+        } else {
+            1.0
+        };
+        batch.push(
+            color,
+            Polygon::rectangle(0.8 * bar_width, height)
+                .translate((idx as f64) * bar_width, SPARKLINE_HEIGHT - height),
+        );
+    }
+    Widget::draw_batch(ctx, batch).centered_vert()
+}
@@ -8,12 +8,13 @@ use geom::{Circle, Distance, Pt2D, Time};
 use map_gui::load::{FileLoader, MapLoader};
 use map_gui::tools::{ChooseSomething, PopupMsg, TurnExplorer};
 use map_gui::AppLike;
-use sim::{Analytics, Scenario};
+use sim::{Analytics, Scenario, ScenarioModifier};
 use widgetry::{
     lctrl, Btn, Choice, Color, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
     Outcome, Panel, State, Text, TextExt, UpdateType, VerticalAlignment, Widget,
 };
 
+use self::kpis::KpiStrip;
 use self::misc_tools::{RoutePreview, TrafficRecorder};
 use crate::app::{App, Transition};
 use crate::common::{tool_panel, CommonState, ContextualActions, Minimap};
@@ -30,6 +31,7 @@ use map_gui::render::{unzoomed_agent_radius, UnzoomedAgents};
 
 pub mod dashboards;
 pub mod gameplay;
+mod kpis;
 mod misc_tools;
 mod speed;
 mod time_warp;
@@ -51,6 +53,7 @@ pub struct SandboxControls {
     time_panel: Option<TimePanel>,
     speed: Option<SpeedControls>,
     pub agent_meter: Option<AgentMeter>,
+    kpis: Option<KpiStrip>,
     minimap: Option<Minimap>,
 }
 
@@ -202,6 +205,18 @@ impl State<App> for SandboxMode {
                 return t;
             }
         }
+        // The player can turn these on or off from the settings at any time
+        let targets = self.gameplay.kpi_targets();
+        if (app.opts.show_kpis || targets.is_some()) != self.controls.kpis.is_some() {
+            self.controls.kpis = if self.controls.kpis.is_none() {
+                Some(KpiStrip::new(ctx, app, targets))
+            } else {
+                None
+            };
+        }
+        if let Some(ref mut k) = self.controls.kpis {
+            k.event(ctx, app);
+        }
 
         if self
             .controls
@@ -219,7 +234,9 @@ impl State<App> for SandboxMode {
 
     fn draw(&self, g: &mut GfxCtx, app: &App) {
         if let Some(ref l) = app.primary.layer {
+            g.set_render_layer(l.name().unwrap_or("layer"));
             l.draw(g, app);
+            g.set_render_layer("other");
         }
 
         if let Some(ref c) = self.controls.common {
@@ -239,6 +256,9 @@ impl State<App> for SandboxMode {
         if let Some(ref am) = self.controls.agent_meter {
             am.draw(g);
         }
+        if let Some(ref k) = self.controls.kpis {
+            k.draw(g);
+        }
         if let Some(ref m) = self.controls.minimap {
             m.draw(g, app);
         }
@@ -434,6 +454,13 @@ impl AgentMeter {
                     .align_right(),
             ]),
         ];
+        if unfinished == 0 && finished > 0 {
+            rows.push(
+                Btn::text_bg2("Jump to problems")
+                    .build_def(ctx, None)
+                    .centered_horiz(),
+            );
+        }
         // TODO This likely fits better in the top center panel, but no easy way to squeeze it into
         // the panel for all gameplay modes
         if let Some(n) = app.primary.sim.num_recorded_trips() {
@@ -473,6 +500,9 @@ impl AgentMeter {
                         ctx, app,
                     )));
                 }
+                "Jump to problems" => {
+                    return Some(Transition::Push(dashboards::Problems::new(ctx, app)));
+                }
                 "see why results are tentative" => {
                     return Some(Transition::Push(PopupMsg::new(
                         ctx,
@@ -709,23 +739,53 @@ impl State<App> for SandboxLoader {
                 }
                 LoadStage::GotScenario(mut scenario) => {
                     let scenario_name = scenario.scenario_name.clone();
-                    ctx.loading_screen("instantiate scenario", |_, mut timer| {
+                    let result = ctx.loading_screen("instantiate scenario", |_, mut timer| {
+                        app.primary.bike_share = None;
                         if let GameplayMode::PlayScenario(_, _, ref modifiers) = self.mode {
                             for m in modifiers {
-                                scenario = m.apply(&app.primary.map, scenario);
+                                // Hang onto the bike share usage for a dashboard
+                                if let ScenarioModifier::BikeShare(ref config) = m {
+                                    let (s, usage) = config.apply(&app.primary.map, scenario);
+                                    scenario = s;
+                                    app.primary.bike_share = Some(usage);
+                                } else if let ScenarioModifier::RespectCapacities = m {
+                                    let (s, usage) = scenario.respect_capacities(&app.primary.map);
+                                    scenario = s;
+                                    timer.note(format!(
+                                        "Respecting building capacities: {}",
+                                        usage.describe()
+                                    ));
+                                } else {
+                                    scenario = m.apply(&app.primary.map, scenario);
+                                }
                             }
                         }
 
+                        let mut rng = app.primary.current_flags.sim_flags.make_rng();
+                        let scenario = scenario.fix_missing_data(
+                            &app.primary.map,
+                            app.primary.current_flags.sim_flags.missing_data,
+                            &mut rng,
+                            timer,
+                        )?;
                         scenario.instantiate(
                             &mut app.primary.sim,
                             &app.primary.map,
-                            &mut app.primary.current_flags.sim_flags.make_rng(),
+                            &mut rng,
                             &mut timer,
                         );
                         app.primary
                             .sim
                             .tiny_step(&app.primary.map, &mut app.primary.sim_cb);
+                        Ok::<(), String>(())
                     });
+                    if let Err(err) = result {
+                        app.clear_everything(ctx);
+                        return Transition::Clear(vec![
+                            MainMenu::new(ctx, app),
+                            PopupMsg::new(ctx, "Couldn't load the scenario", vec![err]),
+                        ]);
+                    }
 
                     self.stage = Some(LoadStage::LoadingPrebaked(scenario_name));
                     continue;
@@ -873,6 +933,11 @@ impl SandboxControls {
             } else {
                 None
             },
+            kpis: if app.opts.show_kpis || gameplay.kpi_targets().is_some() {
+                Some(KpiStrip::new(ctx, app, gameplay.kpi_targets()))
+            } else {
+                None
+            },
             minimap: if gameplay.has_minimap() {
                 Some(Minimap::new(ctx, app))
             } else {
@@ -888,6 +953,9 @@ impl SandboxControls {
         if let Some(ref mut speed) = self.speed {
             speed.recreate_panel(ctx, app);
         }
+        if let Some(ref mut kpis) = self.kpis {
+            kpis.recreate_panel(ctx, app);
+        }
         if let Some(ref mut minimap) = self.minimap {
             minimap.recreate_panel(ctx, app);
         }
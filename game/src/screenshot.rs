@@ -0,0 +1,97 @@
+//! Renders one view of a map to a PNG file without opening a window, for documentation and for
+//! comparing a proposal before and after its edits.
+//!
+//! ```sh
+//! cargo run --bin game -- data/system/seattle/maps/montlake.bin --edits=bike_lanes \
+//!     --screenshot=montlake.png --screenshot_size=1920x1080 --center=-122.3045,47.6405 \
+//!     --zoom=2.0 --layer="bike network"
+//! ```
+
+use abstutil::CmdArgs;
+use geom::LonLat;
+use widgetry::{EventCtx, GfxCtx, ScreenDims, Settings, State};
+
+use crate::app::{App, Transition};
+use crate::layer::layer_by_name;
+
+pub struct ScreenshotFlags {
+    /// Centered on the whole map by default
+    center: Option<LonLat>,
+    /// By default, the whole map fits
+    zoom: Option<f64>,
+    layer: Option<String>,
+}
+
+impl ScreenshotFlags {
+    /// Returns None unless `--screenshot` is specified.
+    pub fn from_args(args: &mut CmdArgs, settings: &mut Settings) -> Option<ScreenshotFlags> {
+        let path = args.optional("--screenshot")?;
+        let size = args
+            .optional_parse("--screenshot_size", parse_size)
+            .unwrap_or_else(|| ScreenDims::new(1920.0, 1080.0));
+        settings.screenshot(path, size);
+        Some(ScreenshotFlags {
+            center: args.optional_parse("--center", parse_lon_lat),
+            zoom: args.optional_parse("--zoom", |s| s.parse::<f64>()),
+            layer: args.optional("--layer"),
+        })
+    }
+
+    /// Points the camera at the requested view and sets up the layer.
+    pub fn setup(self, ctx: &mut EventCtx, app: &mut App) -> Box<dyn State<App>> {
+        let bounds = app.primary.map.get_bounds();
+        ctx.canvas.cam_zoom = self.zoom.unwrap_or_else(|| {
+            (ctx.canvas.window_width / bounds.width())
+                .min(ctx.canvas.window_height / bounds.height())
+        });
+        let pt = match self.center {
+            Some(gps) => gps.to_pt(app.primary.map.get_gps_bounds()),
+            None => bounds.center(),
+        };
+        ctx.canvas.center_on_map_pt(pt);
+
+        if let Some(name) = self.layer {
+            app.primary.layer = Some(
+                layer_by_name(ctx, app, &name)
+                    .unwrap_or_else(|| panic!("--layer={} isn't a layer", name)),
+            );
+        }
+
+        Box::new(Screenshot)
+    }
+}
+
+fn parse_size(x: &str) -> Result<ScreenDims, String> {
+    let parts: Vec<&str> = x.split('x').collect();
+    if parts.len() != 2 {
+        return Err(format!("{} isn't WIDTHxHEIGHT", x));
+    }
+    let width = parts[0].parse::<f64>().map_err(|err| err.to_string())?;
+    let height = parts[1].parse::<f64>().map_err(|err| err.to_string())?;
+    Ok(ScreenDims::new(width, height))
+}
+
+fn parse_lon_lat(x: &str) -> Result<LonLat, String> {
+    let parts: Vec<&str> = x.split(',').collect();
+    if parts.len() != 2 {
+        return Err(format!("{} isn't LONGITUDE,LATITUDE", x));
+    }
+    let lon = parts[0].parse::<f64>().map_err(|err| err.to_string())?;
+    let lat = parts[1].parse::<f64>().map_err(|err| err.to_string())?;
+    Ok(LonLat::new(lon, lat))
+}
+
+/// Just the map and the layer; this is only drawn once.
+struct Screenshot;
+
+impl State<App> for Screenshot {
+    fn event(&mut self, _: &mut EventCtx, _: &mut App) -> Transition {
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        if let Some(ref l) = app.primary.layer {
+            l.draw(g, app);
+        }
+    }
+}
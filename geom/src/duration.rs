@@ -95,17 +95,8 @@ impl Duration {
             return Err(format!("Duration {}: no :'s", string).into());
         }
 
-        let mut seconds: f64 = 0.0;
-        if parts.last().unwrap().contains('.') {
-            let last_parts: Vec<&str> = parts.last().unwrap().split('.').collect();
-            if last_parts.len() != 2 {
-                return Err(format!("Duration {}: no . in last part", string).into());
-            }
-            seconds += last_parts[1].parse::<f64>()? / 10.0;
-            seconds += last_parts[0].parse::<f64>()?;
-        } else {
-            seconds += parts.last().unwrap().parse::<f64>()?;
-        }
+        // The seconds can have any number of decimal places, like "0.05"
+        let mut seconds: f64 = parts.last().unwrap().parse::<f64>()?;
 
         match parts.len() {
             1 => Ok(Duration::seconds(seconds)),
@@ -360,4 +351,16 @@ mod tests {
         assert_eq!("1m30.1s", Duration::seconds(90.123).to_string(&dont_round));
         assert_eq!("1m30s", Duration::seconds(90.123).to_string(&round));
     }
+
+    #[test]
+    fn parse_durations() {
+        assert_eq!(Duration::minutes(3), Duration::parse("3:00").unwrap());
+        assert_eq!(Duration::seconds(5.5), Duration::parse("5.5").unwrap());
+        assert_eq!(Duration::seconds(0.05), Duration::parse("0.05").unwrap());
+        assert_eq!(
+            Duration::seconds(3661.25),
+            Duration::parse("1:01:01.25").unwrap()
+        );
+        assert!(Duration::parse("1.2.3").is_err());
+    }
 }
@@ -0,0 +1,55 @@
+use aabb_quadtree::QuadTree;
+
+use crate::{Bounds, Polygon, Pt2D};
+
+/// A quad-tree to quickly find all points inside some polygon, instead of checking every point
+/// against every polygon.
+pub struct FindContained<K> {
+    quadtree: QuadTree<(K, Pt2D)>,
+}
+
+impl<K> FindContained<K>
+where
+    K: Clone + std::fmt::Debug,
+{
+    /// Creates the quad-tree, limited to points contained in the boundary.
+    pub fn new(bounds: &Bounds) -> FindContained<K> {
+        FindContained {
+            quadtree: QuadTree::default(bounds.as_bbox()),
+        }
+    }
+
+    /// Add a point to the quadtree, remembering some key associated with it.
+    pub fn add(&mut self, key: K, pt: Pt2D) {
+        let mut b = Bounds::new();
+        b.update(pt);
+        self.quadtree.insert_with_box((key, pt), b.as_bbox());
+    }
+
+    /// Like `add`, but also remembers the area around the point, so `overlapping` can find it.
+    /// The point should be inside the area.
+    pub fn add_area(&mut self, key: K, pt: Pt2D, area: &Polygon) {
+        self.quadtree
+            .insert_with_box((key, pt), area.get_bounds().as_bbox());
+    }
+
+    /// The keys of all points inside the polygon
+    pub fn contained_in(&self, polygon: &Polygon) -> Vec<K> {
+        self.quadtree
+            .query(polygon.get_bounds().as_bbox())
+            .into_iter()
+            .filter(|((_, pt), _, _)| polygon.contains_pt(*pt))
+            .map(|((key, _), _, _)| key.clone())
+            .collect()
+    }
+
+    /// The keys of everything whose area might overlap the polygon. Only bounding boxes are
+    /// compared, so callers should check the real shapes.
+    pub fn overlapping(&self, polygon: &Polygon) -> Vec<K> {
+        self.quadtree
+            .query(polygon.get_bounds().as_bbox())
+            .into_iter()
+            .map(|((key, _), _, _)| key.clone())
+            .collect()
+    }
+}
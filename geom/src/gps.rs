@@ -106,6 +106,125 @@ impl LonLat {
         writeln!(f, "END")?;
         Ok(())
     }
+
+    /// Like `read_osmosis_polygon`, but reads every section of the file. Each polygon is its outer
+    /// ring, followed by any holes, which are sections whose name starts with `!`. Holes belong to
+    /// the outer ring before them.
+    pub fn read_osmosis_polygons(path: &str) -> Result<Vec<Vec<Vec<LonLat>>>, Box<dyn Error>> {
+        let f = File::open(path)?;
+        let mut polygons: Vec<Vec<Vec<LonLat>>> = Vec::new();
+        // The section currently being read
+        let mut current: Option<(bool, Vec<LonLat>)> = None;
+        for (idx, line) in BufReader::new(f).lines().enumerate() {
+            let line = line?;
+            // The first line is the name of the whole file
+            if idx == 0 {
+                continue;
+            }
+            if let Some((is_hole, mut pts)) = current.take() {
+                if line == "END" {
+                    if is_hole {
+                        if let Some(polygon) = polygons.last_mut() {
+                            polygon.push(pts);
+                        } else {
+                            return Err(format!("{} starts with a hole", path).into());
+                        }
+                    } else {
+                        polygons.push(vec![pts]);
+                    }
+                } else {
+                    let parts = line.trim().split("    ").collect::<Vec<_>>();
+                    pts.push(LonLat::new(
+                        parts[0].parse::<f64>()?,
+                        parts[1].parse::<f64>()?,
+                    ));
+                    current = Some((is_hole, pts));
+                }
+            } else if line == "END" {
+                break;
+            } else {
+                current = Some((line.starts_with('!'), Vec::new()));
+            }
+        }
+        Ok(polygons)
+    }
+
+    /// Writes polygons, each one an outer ring followed by any holes, in the same format as
+    /// `write_osmosis_polygon`.
+    pub fn write_osmosis_polygons(
+        path: &str,
+        polygons: &Vec<Vec<Vec<LonLat>>>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut f = File::create(path)?;
+        writeln!(f, "boundary")?;
+        let mut section = 1;
+        for polygon in polygons {
+            for (idx, ring) in polygon.iter().enumerate() {
+                if idx == 0 {
+                    writeln!(f, "{}", section)?;
+                } else {
+                    writeln!(f, "!{}", section)?;
+                }
+                section += 1;
+                for pt in ring {
+                    writeln!(f, "     {}    {}", pt.x(), pt.y())?;
+                }
+                writeln!(f, "END")?;
+            }
+        }
+        writeln!(f, "END")?;
+        Ok(())
+    }
+
+    /// Reads every polygon from a GeoJSON file, along with the feature's `name` property, like
+    /// census tracts or planning zones. Each polygon in a multipolygon is returned separately,
+    /// with a number after the name. Holes are ignored.
+    pub fn read_geojson_polygons(path: &str) -> Result<Vec<(String, Vec<LonLat>)>, Box<dyn Error>> {
+        let raw = std::fs::read_to_string(path)?;
+        let features = match raw.parse::<geojson::GeoJson>()? {
+            geojson::GeoJson::Feature(feature) => vec![feature],
+            geojson::GeoJson::FeatureCollection(collection) => collection.features,
+            _ => return Err(format!("{} has no features", path).into()),
+        };
+
+        let mut results = Vec::new();
+        for feature in features {
+            let name = match feature
+                .properties
+                .as_ref()
+                .and_then(|props| props.get("name"))
+                .and_then(|name| name.as_str())
+            {
+                Some(name) => name.to_string(),
+                None => {
+                    return Err(format!("A feature in {} is missing a name", path).into());
+                }
+            };
+            let polygons = match feature.geometry.map(|g| g.value) {
+                Some(geojson::Value::Polygon(polygon)) => vec![polygon],
+                Some(geojson::Value::MultiPolygon(multi_polygon)) => multi_polygon,
+                // Points and lines can't be neighborhoods
+                _ => continue,
+            };
+            let numbered = polygons.len() > 1;
+            for (idx, mut rings) in polygons.into_iter().enumerate() {
+                if rings.is_empty() {
+                    continue;
+                }
+                let pts = rings
+                    .swap_remove(0)
+                    .into_iter()
+                    .map(|pt| LonLat::new(pt[0], pt[1]))
+                    .collect();
+                if numbered {
+                    results.push((format!("{} {}", name, idx + 1), pts));
+                } else {
+                    results.push((name.clone(), pts));
+                }
+            }
+        }
+        Ok(results)
+    }
 }
 
 impl fmt::Display for LonLat {
@@ -4,6 +4,7 @@ pub use crate::circle::Circle;
 pub use crate::distance::Distance;
 pub use crate::duration::Duration;
 pub use crate::find_closest::FindClosest;
+pub use crate::find_contained::FindContained;
 pub use crate::gps::LonLat;
 pub use crate::line::{InfiniteLine, Line};
 pub use crate::percent::Percent;
@@ -21,6 +22,7 @@ mod circle;
 mod distance;
 mod duration;
 mod find_closest;
+mod find_contained;
 mod gps;
 mod line;
 mod percent;
@@ -295,6 +295,32 @@ impl Polygon {
         from_multi(to_geo(self.points()).intersection(&to_geo(other.points())))
     }
 
+    /// The area covered by either group of polygons, with overlapping pieces merged. Unlike
+    /// `union`, holes are kept and the result has proper rings.
+    pub fn union_areas(a: &[Polygon], b: &[Polygon]) -> Vec<Polygon> {
+        from_multi_with_holes(to_multi_with_holes(a).union(&to_multi_with_holes(b)))
+    }
+
+    /// The area covered by both groups of polygons. Holes are kept.
+    pub fn intersect_areas(a: &[Polygon], b: &[Polygon]) -> Vec<Polygon> {
+        from_multi_with_holes(to_multi_with_holes(a).intersection(&to_multi_with_holes(b)))
+    }
+
+    /// The area covered by the first group of polygons, but not the second, like "everywhere
+    /// except downtown." This may leave holes.
+    pub fn subtract_areas(a: &[Polygon], b: &[Polygon]) -> Vec<Polygon> {
+        from_multi_with_holes(to_multi_with_holes(a).difference(&to_multi_with_holes(b)))
+    }
+
+    /// The outer ring, then any holes. Fails for polygons that weren't made from rings.
+    pub fn get_rings(&self) -> Result<Vec<Ring>, String> {
+        if let Some(ref rings) = self.rings {
+            Ok(rings.clone())
+        } else {
+            Ok(vec![Ring::new(self.points.clone())?])
+        }
+    }
+
     pub fn convex_hull(list: Vec<Polygon>) -> Polygon {
         let mp: geo::MultiPolygon<f64> = list.into_iter().map(|p| to_geo(p.points())).collect();
         from_geo(mp.convex_hull())
@@ -484,6 +510,50 @@ fn from_multi(multi: geo::MultiPolygon<f64>) -> Vec<Polygon> {
     multi.into_iter().map(from_geo).collect()
 }
 
+fn to_multi_with_holes(list: &[Polygon]) -> geo::MultiPolygon<f64> {
+    let ring_to_geo = |ring: &Ring| {
+        geo::LineString::from(
+            ring.points()
+                .iter()
+                .map(|pt| geo::Point::new(pt.x(), pt.y()))
+                .collect::<Vec<_>>(),
+        )
+    };
+    list.iter()
+        .map(|p| match p.get_rings() {
+            Ok(rings) => geo::Polygon::new(
+                ring_to_geo(&rings[0]),
+                rings[1..].iter().map(ring_to_geo).collect(),
+            ),
+            Err(_) => to_geo(p.points()),
+        })
+        .collect()
+}
+
+// Pieces too degenerate to form rings are skipped
+fn from_multi_with_holes(multi: geo::MultiPolygon<f64>) -> Vec<Polygon> {
+    let ring_from_geo = |ls: geo::LineString<f64>| {
+        Ring::new(
+            ls.into_points()
+                .into_iter()
+                .map(|pt| Pt2D::new(pt.x(), pt.y()))
+                .collect(),
+        )
+    };
+    let mut results = Vec::new();
+    for p in multi {
+        let (exterior, interiors) = p.into_inner();
+        if let Ok(outer) = ring_from_geo(exterior) {
+            let holes = interiors
+                .into_iter()
+                .filter_map(|ls| ring_from_geo(ls).ok())
+                .collect();
+            results.push(Polygon::with_holes(outer, holes));
+        }
+    }
+    results
+}
+
 fn downsize(input: Vec<usize>) -> Vec<u16> {
     let mut output = Vec::new();
     for x in input {
@@ -8,6 +8,10 @@
 // it's now 01:01:00.0
 // > curl http://localhost:1234/data/get-road-thruput
 // ... huge JSON blob
+// > curl http://localhost:1234/metrics
+// ... progress of the simulation, in Prometheus format
+// > curl http://localhost:1234/sim/get-progress
+// ... how far a running goto-time has gotten, and roughly how long it has left
 
 #[macro_use]
 extern crate log;
@@ -15,7 +19,9 @@ extern crate log;
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::TryFrom;
 use std::error::Error;
+use std::path::{Component, Path};
 use std::sync::RwLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use hyper::{Body, Request, Response, Server, StatusCode};
 use rand::SeedableRng;
@@ -29,8 +35,8 @@ use map_model::{
     MovementID, PermanentMapEdits, RoadID, TurnID,
 };
 use sim::{
-    AgentID, AgentType, ExternalPerson, PersonID, Scenario, ScenarioModifier, Sim, SimFlags,
-    SimOptions, TripID, TripMode, VehicleType,
+    AgentID, AgentType, ExternalPerson, MissingDataPolicy, PersonID, RunEstimate, Scenario,
+    ScenarioModifier, Sim, SimCallback, SimFlags, SimOptions, TripID, TripMode, VehicleType,
 };
 
 lazy_static::lazy_static! {
@@ -42,9 +48,12 @@ lazy_static::lazy_static! {
             modifiers: Vec::new(),
             edits: None,
             rng_seed: SimFlags::RNG_SEED,
+            missing_data: MissingDataPolicy::Abort,
             opts: SimOptions::default(),
         }
     });
+    // Separate from SIM, so it can be read while a long goto-time holds onto the simulation
+    static ref METRICS: RwLock<Metrics> = RwLock::new(Metrics::new());
 }
 
 #[tokio::main]
@@ -54,6 +63,9 @@ async fn main() {
     let rng_seed = args
         .optional_parse("--rng_seed", |s| s.parse())
         .unwrap_or(SimFlags::RNG_SEED);
+    let missing_data = args
+        .optional_parse("--missing_data", MissingDataPolicy::parse)
+        .unwrap_or_default();
     let opts = SimOptions::from_args(&mut args, rng_seed);
     let port = args.required("--port").parse::<u16>().unwrap();
     args.done();
@@ -61,9 +73,17 @@ async fn main() {
     {
         let mut load = LOAD.write().unwrap();
         load.rng_seed = rng_seed;
+        load.missing_data = missing_data;
         load.opts = opts;
 
-        let (map, sim) = load.setup(&mut timer);
+        let (map, sim) = match load.setup(&mut timer) {
+            Ok(pair) => pair,
+            Err(err) => {
+                println!("Couldn't load {}: {}", load.scenario, err);
+                std::process::exit(1);
+            }
+        };
+        METRICS.write().unwrap().update(&sim);
         *MAP.write().unwrap() = map;
         *SIM.write().unwrap() = sim;
     }
@@ -88,26 +108,42 @@ async fn serve_req(req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
             .map(|(k, v)| (k.to_string(), v.to_string()))
             .collect();
     let body = hyper::body::to_bytes(req).await?.to_vec();
+    // Scraped often, so don't log it
+    if path == "/metrics" {
+        return Ok(Response::new(Body::from(
+            METRICS.read().unwrap().to_prometheus(),
+        )));
+    }
+    // Also answered from METRICS, so it works during a long goto-time
+    if path == "/sim/get-progress" {
+        return Ok(Response::new(Body::from(abstutil::to_json(
+            &METRICS.read().unwrap().progress(),
+        ))));
+    }
     info!("Handling {}", path);
-    Ok(
-        match handle_command(
+    let result = {
+        let mut sim = SIM.write().unwrap();
+        let result = handle_command(
             &path,
             &params,
             &body,
-            &mut SIM.write().unwrap(),
+            &mut sim,
             &mut MAP.write().unwrap(),
             &mut LOAD.write().unwrap(),
-        ) {
-            Ok(resp) => Response::new(Body::from(resp)),
-            Err(err) => {
-                error!("{}: {}", path, err);
-                Response::builder()
-                    .status(StatusCode::BAD_REQUEST)
-                    .body(Body::from(format!("Bad command {}: {}", path, err)))
-                    .unwrap()
-            }
-        },
-    )
+        );
+        METRICS.write().unwrap().update(&sim);
+        result
+    };
+    Ok(match result {
+        Ok(resp) => Response::new(Body::from(resp)),
+        Err(err) => {
+            error!("{}: {}", path, err);
+            Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::from(format!("Bad command {}: {}", path, err)))
+                .unwrap()
+        }
+    })
 }
 
 fn handle_command(
@@ -121,7 +157,7 @@ fn handle_command(
     match path {
         // Controlling the simulation
         "/sim/reset" => {
-            let (new_map, new_sim) = load.setup(&mut Timer::new("reset sim"));
+            let (new_map, new_sim) = load.setup(&mut Timer::new("reset sim"))?;
             *map = new_map;
             *sim = new_sim;
             Ok(format!("sim reloaded"))
@@ -129,12 +165,14 @@ fn handle_command(
         "/sim/load" => {
             let args: LoadSim = abstutil::from_json(body)?;
 
-            load.scenario = args.scenario;
-            load.modifiers = args.modifiers;
-            load.edits = args.edits;
+            let mut new_load = load.clone();
+            new_load.scenario = args.scenario;
+            new_load.modifiers = args.modifiers;
+            new_load.edits = args.edits;
 
-            // Also reset
-            let (new_map, new_sim) = load.setup(&mut Timer::new("reset sim"));
+            // Also reset. If this fails, keep the previous flags and simulation.
+            let (new_map, new_sim) = new_load.setup(&mut Timer::new("reset sim"))?;
+            *load = new_load;
             *map = new_map;
             *sim = new_sim;
 
@@ -147,7 +185,16 @@ fn handle_command(
                 Err(format!("{} is in the past. call /sim/reset first?", t).into())
             } else {
                 let dt = t - sim.time();
-                sim.timed_step(map, dt, &mut None, &mut Timer::new("goto-time"));
+                METRICS.write().unwrap().estimate = Some(RunEstimate::new(sim.time(), t));
+                sim.set_periodic_callback(Duration::minutes(1));
+                sim.timed_step(
+                    map,
+                    dt,
+                    &mut Some(Box::new(UpdateMetrics)),
+                    &mut Timer::new("goto-time"),
+                );
+                sim.unset_periodic_callback();
+                METRICS.write().unwrap().estimate = None;
                 Ok(format!("it's now {}", t))
             }
         }
@@ -309,6 +356,24 @@ fn handle_command(
                 })
                 .collect(),
         })),
+        "/data/write-agent-snapshot" => {
+            let name = params.get("path").ok_or("missing the path parameter")?;
+            // Don't let clients write anywhere outside of data/
+            if !Path::new(name)
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+            {
+                return Err(format!("{} must be a relative path inside data/", name).into());
+            }
+            let path = abstutil::path(name.clone());
+            let snapshot = sim.agent_snapshot(map);
+            snapshot.write_to_file(&path)?;
+            Ok(format!(
+                "wrote {} agents to {}",
+                snapshot.num_agents(),
+                path
+            ))
+        }
         "/data/get-road-thruput" => Ok(abstutil::to_json(&RoadThroughput {
             counts: sim
                 .get_analytics()
@@ -402,7 +467,22 @@ struct TrafficSignalState {
     waiting: Vec<(AgentID, TurnID, Time)>,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize)]
+struct Progress {
+    time: Time,
+    /// The time the current goto-time is heading to, if one is running
+    goto_time: Option<Time>,
+    finished_trips: usize,
+    unfinished_trips: usize,
+    /// Trips that haven't reached their departure time yet
+    trips_not_started: usize,
+    /// Simulated seconds per real second, over about the last minute
+    speed: Option<f64>,
+    /// Roughly how much real time the current goto-time needs to finish
+    estimated_time_left: Option<Duration>,
+}
+
+#[derive(Clone, Deserialize)]
 struct LoadSim {
     scenario: String,
     modifiers: Vec<ScenarioModifier>,
@@ -411,16 +491,18 @@ struct LoadSim {
     #[serde(skip_deserializing)]
     rng_seed: u64,
     #[serde(skip_deserializing)]
+    missing_data: MissingDataPolicy,
+    #[serde(skip_deserializing)]
     opts: SimOptions,
 }
 
 impl LoadSim {
-    fn setup(&self, timer: &mut Timer) -> (Map, Sim) {
+    fn setup(&self, timer: &mut Timer) -> Result<(Map, Sim), String> {
         let mut scenario: Scenario = abstutil::read_binary(self.scenario.clone(), timer);
 
         let mut map = Map::new(scenario.map_name.path(), timer);
         if let Some(perma) = self.edits.clone() {
-            let edits = perma.to_edits(&map).unwrap();
+            let edits = perma.to_edits(&map)?;
             map.must_apply_edits(edits, timer);
             map.recalculate_pathfinding_after_edits(timer);
         }
@@ -430,13 +512,173 @@ impl LoadSim {
         }
 
         let mut rng = XorShiftRng::seed_from_u64(self.rng_seed);
+        let scenario = scenario.fix_missing_data(&map, self.missing_data, &mut rng, timer)?;
         let mut sim = Sim::new(&map, self.opts.clone(), timer);
         scenario.instantiate(&mut sim, &map, &mut rng, timer);
 
-        (map, sim)
+        Ok((map, sim))
+    }
+}
+
+/// A snapshot of how the simulation is progressing, for monitoring long runs. A stalled
+/// simulation shows up as a stale `abst_last_update_timestamp_seconds` or a low step rate.
+struct Metrics {
+    sim_time: Time,
+    steps: usize,
+    active_agents: usize,
+    steps_per_second: f64,
+    finished_trips: usize,
+    unfinished_trips: usize,
+    trips_not_started: usize,
+    // Only while a goto-time runs
+    estimate: Option<RunEstimate>,
+    // When the last update happened, in real time
+    last_update: Instant,
+    last_update_unix: f64,
+}
+
+impl Metrics {
+    fn new() -> Metrics {
+        Metrics {
+            sim_time: Time::START_OF_DAY,
+            steps: 0,
+            active_agents: 0,
+            steps_per_second: 0.0,
+            finished_trips: 0,
+            unfinished_trips: 0,
+            trips_not_started: 0,
+            estimate: None,
+            last_update: Instant::now(),
+            last_update_unix: 0.0,
+        }
+    }
+
+    fn update(&mut self, sim: &Sim) {
+        let elapsed = self.last_update.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            // The count resets when the simulation does
+            self.steps_per_second = (sim.step_count().saturating_sub(self.steps) as f64) / elapsed;
+        }
+        self.sim_time = sim.time();
+        self.steps = sim.step_count();
+        self.active_agents = sim.num_active_agents();
+        let (finished, unfinished) = sim.num_trips();
+        self.finished_trips = finished;
+        self.unfinished_trips = unfinished;
+        self.trips_not_started = sim.num_trips_not_started();
+        if let Some(ref mut estimate) = self.estimate {
+            estimate.update(self.sim_time);
+        }
+        self.last_update = Instant::now();
+        self.last_update_unix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|dt| dt.as_secs_f64())
+            .unwrap_or(0.0);
+    }
+
+    fn progress(&self) -> Progress {
+        Progress {
+            time: self.sim_time,
+            goto_time: self.estimate.as_ref().map(|e| e.end_time()),
+            finished_trips: self.finished_trips,
+            unfinished_trips: self.unfinished_trips,
+            trips_not_started: self.trips_not_started,
+            speed: self.estimate.as_ref().and_then(|e| e.speed()),
+            estimated_time_left: self
+                .estimate
+                .as_ref()
+                .and_then(|e| e.time_left(self.sim_time)),
+        }
+    }
+
+    fn to_prometheus(&self) -> String {
+        let mut metrics = vec![
+            (
+                "abst_sim_time_seconds",
+                "gauge",
+                "Simulated time since midnight",
+                (self.sim_time - Time::START_OF_DAY).inner_seconds(),
+            ),
+            (
+                "abst_sim_steps_total",
+                "counter",
+                "Steps the simulation has taken",
+                self.steps as f64,
+            ),
+            (
+                "abst_sim_active_agents",
+                "gauge",
+                "Agents currently moving",
+                self.active_agents as f64,
+            ),
+            (
+                "abst_sim_steps_per_second",
+                "gauge",
+                "Steps per real second since the previous update",
+                self.steps_per_second,
+            ),
+            (
+                "abst_sim_trips_not_started",
+                "gauge",
+                "Trips that haven't reached their departure time yet",
+                self.trips_not_started as f64,
+            ),
+            (
+                "abst_last_update_timestamp_seconds",
+                "gauge",
+                "Unix time when these metrics were last updated",
+                self.last_update_unix,
+            ),
+        ];
+        if let Some(dt) = self
+            .estimate
+            .as_ref()
+            .and_then(|e| e.time_left(self.sim_time))
+        {
+            metrics.push((
+                "abst_estimated_seconds_left",
+                "gauge",
+                "Roughly how much real time the current goto-time needs to finish",
+                dt.inner_seconds(),
+            ));
+        }
+        if let Some(bytes) = resident_memory_bytes() {
+            metrics.push((
+                "abst_resident_memory_bytes",
+                "gauge",
+                "Resident memory used by this process",
+                bytes as f64,
+            ));
+        }
+
+        let mut out = String::new();
+        for (name, kind, help, value) in metrics {
+            out.push_str(&format!("# HELP {} {}\n", name, help));
+            out.push_str(&format!("# TYPE {} {}\n", name, kind));
+            out.push_str(&format!("{} {}\n", name, value));
+        }
+        out
+    }
+}
+
+/// Keeps METRICS current while a long goto-time runs.
+struct UpdateMetrics;
+
+impl SimCallback for UpdateMetrics {
+    fn run(&mut self, sim: &Sim, _: &Map) -> bool {
+        METRICS.write().unwrap().update(sim);
+        false
     }
 }
 
+/// Only works on Linux
+fn resident_memory_bytes() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb = line.split_whitespace().nth(1)?.parse::<usize>().ok()?;
+    Some(kb * 1024)
+}
+
 fn export_geometry(map: &Map, i: IntersectionID) -> geojson::GeoJson {
     use geojson::{Feature, FeatureCollection, GeoJson};
 
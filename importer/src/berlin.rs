@@ -66,6 +66,7 @@ pub fn osm_to_raw(name: &str, timer: &mut Timer, config: &ImporterConfiguration)
                 driving_side: map_model::DrivingSide::Right,
                 bikes_can_use_bus_lanes: true,
                 inferred_sidewalks: true,
+                walk_on_shoulders: false,
             },
 
             onstreet_parking: convert_osm::OnstreetParking::JustOSM,
@@ -73,6 +74,7 @@ pub fn osm_to_raw(name: &str, timer: &mut Timer, config: &ImporterConfiguration)
             private_offstreet_parking: convert_osm::PrivateOffstreetParking::FixedPerBldg(3),
             elevation: None,
             include_railroads: true,
+            paths_through_areas: convert_osm::PathsThroughAreas::None,
         },
         timer,
     );
@@ -0,0 +1,135 @@
+use std::num::NonZeroU32;
+
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+
+use abstutil::{CmdArgs, Timer};
+use geom::{Distance, Duration};
+use map_model::Map;
+use sim::{AnonymizeOptions, Scenario};
+
+/// Anonymizes a scenario, so that travel demand derived from sensitive origin-destination data can
+/// be shared. Optionally encrypts the result with a passphrase for internal distribution; the
+/// `--decrypt` mode turns an encrypted file back into a normal scenario.
+///
+/// ```text
+/// anonymize_scenario --input=data/system/seattle/scenarios/montlake/weekday.bin
+///     --output=weekday_anonymized.bin [--cell_size=500] [--min_flow=5] [--jitter_minutes=15]
+///     [--rng_seed=42] [--passphrase_file=secret.txt]
+/// anonymize_scenario --decrypt --input=weekday_anonymized.bin --output=weekday.bin
+///     --passphrase_file=secret.txt
+/// ```
+fn main() {
+    let mut args = CmdArgs::new();
+    let input = args.required("--input");
+    let output = args.required("--output");
+    let passphrase = args.optional("--passphrase_file").map(|path| {
+        String::from_utf8(abstutil::slurp_file(&path).unwrap())
+            .unwrap()
+            .trim()
+            .to_string()
+    });
+
+    if args.enabled("--decrypt") {
+        args.done();
+        let passphrase = passphrase.expect("--decrypt needs --passphrase_file");
+        let plaintext = decrypt(abstutil::slurp_file(&input).unwrap(), &passphrase).unwrap();
+        let scenario: Scenario = abstutil::from_binary(&plaintext).unwrap();
+        abstutil::write_binary(output, &scenario);
+        return;
+    }
+
+    let mut opts = AnonymizeOptions::default();
+    if let Some(x) = args.optional_parse("--cell_size", |s| s.parse::<f64>()) {
+        opts.cell_size = Distance::meters(x);
+    }
+    if let Some(x) = args.optional_parse("--min_flow", |s| s.parse::<usize>()) {
+        opts.min_flow = x;
+    }
+    if let Some(x) = args.optional_parse("--jitter_minutes", |s| s.parse::<usize>()) {
+        opts.max_time_jitter = Duration::minutes(x);
+    }
+    let mut rng = XorShiftRng::seed_from_u64(
+        args.optional_parse("--rng_seed", |s| s.parse::<u64>())
+            .unwrap_or(42),
+    );
+    args.done();
+
+    let mut timer = Timer::new("anonymize scenario");
+    let scenario: Scenario = abstutil::read_binary(input, &mut timer);
+    let map = Map::new(scenario.map_name.path(), &mut timer);
+    let scenario = scenario.anonymize(&map, &opts, &mut rng, &mut timer);
+
+    if let Some(passphrase) = passphrase {
+        let ciphertext = encrypt(abstutil::to_binary(&scenario), &passphrase).unwrap();
+        std::fs::write(&output, ciphertext).unwrap();
+    } else {
+        abstutil::write_binary(output.clone(), &scenario);
+    }
+    println!("Wrote {}", output);
+}
+
+// Encrypted files start with this, followed by the salt, nonce, and ciphertext.
+const MAGIC: &[u8] = b"ABSTENC1";
+const SALT_LEN: usize = 16;
+const PBKDF2_ITERATIONS: u32 = 100_000;
+
+fn make_key(passphrase: &str, salt: &[u8]) -> Result<LessSafeKey, String> {
+    let mut key = [0; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(PBKDF2_ITERATIONS).unwrap(),
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+    let key = UnboundKey::new(&CHACHA20_POLY1305, &key).map_err(|_| "bad key".to_string())?;
+    Ok(LessSafeKey::new(key))
+}
+
+fn encrypt(mut plaintext: Vec<u8>, passphrase: &str) -> Result<Vec<u8>, String> {
+    let rng = SystemRandom::new();
+    let mut salt = [0; SALT_LEN];
+    let mut nonce = [0; NONCE_LEN];
+    rng.fill(&mut salt)
+        .map_err(|_| "couldn't generate salt".to_string())?;
+    rng.fill(&mut nonce)
+        .map_err(|_| "couldn't generate nonce".to_string())?;
+
+    make_key(passphrase, &salt)?
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::empty(),
+            &mut plaintext,
+        )
+        .map_err(|_| "encryption failed".to_string())?;
+
+    let mut result = Vec::new();
+    result.extend_from_slice(MAGIC);
+    result.extend_from_slice(&salt);
+    result.extend_from_slice(&nonce);
+    result.extend(plaintext);
+    Ok(result)
+}
+
+fn decrypt(raw: Vec<u8>, passphrase: &str) -> Result<Vec<u8>, String> {
+    if raw.len() < MAGIC.len() + SALT_LEN + NONCE_LEN || &raw[0..MAGIC.len()] != MAGIC {
+        return Err("not an encrypted scenario".to_string());
+    }
+    let salt = &raw[MAGIC.len()..MAGIC.len() + SALT_LEN];
+    let mut nonce = [0; NONCE_LEN];
+    nonce.copy_from_slice(&raw[MAGIC.len() + SALT_LEN..MAGIC.len() + SALT_LEN + NONCE_LEN]);
+    let mut ciphertext = raw[MAGIC.len() + SALT_LEN + NONCE_LEN..].to_vec();
+
+    let plaintext = make_key(passphrase, salt)?
+        .open_in_place(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::empty(),
+            &mut ciphertext,
+        )
+        .map_err(|_| "wrong passphrase or corrupt file".to_string())?;
+    Ok(plaintext.to_vec())
+}
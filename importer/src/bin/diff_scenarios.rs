@@ -0,0 +1,40 @@
+use abstutil::{CmdArgs, Timer};
+use sim::Scenario;
+
+/// Finds what a variation changes about a base scenario, like the extra trips for a stadium
+/// event, and describes it. The difference can be saved and later applied to the base with
+/// `merge_scenarios --diffs`, even after the base has been regenerated.
+///
+/// ```text
+/// diff_scenarios --base=weekday.bin --variant=weekday_with_game.bin --output=game.json
+/// ```
+fn main() {
+    let mut args = CmdArgs::new();
+    let base_path = args.required("--base");
+    let variant_path = args.required("--variant");
+    let output = args.optional("--output");
+    args.done();
+
+    let mut timer = Timer::new("diff scenarios");
+    let base: Scenario = read(&base_path, &mut timer);
+    let variant: Scenario = read(&variant_path, &mut timer);
+    match base.diff(&variant) {
+        Ok(diff) => {
+            for line in diff.describe() {
+                println!("{}", line);
+            }
+            if let Some(path) = output {
+                abstutil::write_json(path, &diff);
+            }
+        }
+        Err(err) => {
+            println!("Can't compare: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn read(path: &str, timer: &mut Timer) -> Scenario {
+    abstutil::read_object(path.to_string(), timer)
+        .unwrap_or_else(|err| panic!("Couldn't read {}: {}", path, err))
+}
@@ -1,9 +1,27 @@
+//! Prints a scenario as JSON. With `--summary`, prints totals per mode and neighborhood, departures
+//! per hour, and parked cars instead, as tables or, with `--json`, as JSON.
+
 use abstutil::{CmdArgs, Timer};
+use map_model::Map;
 use sim::Scenario;
 
 fn main() {
     let mut args = CmdArgs::new();
-    let scenario: Scenario = abstutil::read_binary(args.required_free(), &mut Timer::throwaway());
-    println!("{}", abstutil::to_json(&scenario));
+    let summary = args.enabled("--summary");
+    let json = args.enabled("--json");
+    let mut timer = Timer::throwaway();
+    let scenario: Scenario = abstutil::read_binary(args.required_free(), &mut timer);
     args.done();
+
+    if summary {
+        let map = Map::new(scenario.map_name.path(), &mut timer);
+        let summary = scenario.summarize(&map);
+        if json {
+            println!("{}", abstutil::to_json(&summary));
+        } else {
+            println!("{}", summary.to_table());
+        }
+    } else {
+        println!("{}", abstutil::to_json(&scenario));
+    }
 }
@@ -0,0 +1,104 @@
+use geojson::feature::Id;
+use geojson::{Feature, FeatureCollection, GeoJson};
+
+use abstutil::{CmdArgs, Timer};
+use map_model::{Lane, Map, Turn};
+
+/// Exports the lane-level geometry of a map as GeoJSON, so the cleaned-up lanes derived from OSM
+/// can be consumed by other tools, like driving simulators. Every lane becomes a LineString along
+/// its center, with its width and the lanes it connects to. Every turn becomes a LineString too,
+/// describing how lanes connect through intersections.
+fn main() {
+    let mut args = CmdArgs::new();
+    let map = Map::new(args.required_free(), &mut Timer::new("export lanes"));
+    let path = args
+        .optional("--output")
+        .unwrap_or_else(|| format!("{}_lanes.json", map.get_name().map));
+    args.done();
+
+    let mut features = Vec::new();
+    for l in map.all_lanes() {
+        features.push(lane(l, &map));
+    }
+    for t in map.all_turns().values() {
+        features.push(turn(t, &map));
+    }
+    let geojson = GeoJson::from(FeatureCollection {
+        bbox: None,
+        features,
+        foreign_members: None,
+    });
+    abstutil::write_json(path.clone(), &geojson);
+    println!("Wrote {}", path);
+}
+
+fn lane(lane: &Lane, map: &Map) -> Feature {
+    let road = map.get_r(lane.parent);
+    let mut properties = serde_json::Map::new();
+    properties.insert("kind".to_string(), "lane".into());
+    properties.insert("id".to_string(), lane.id.0.into());
+    properties.insert("road".to_string(), road.id.0.into());
+    properties.insert("osm_way_id".to_string(), road.orig_id.osm_way_id.0.into());
+    properties.insert("type".to_string(), format!("{:?}", lane.lane_type).into());
+    properties.insert(
+        "direction".to_string(),
+        format!("{:?}", road.dir(lane.id)).into(),
+    );
+    properties.insert("width_meters".to_string(), lane.width.inner_meters().into());
+    properties.insert(
+        "length_meters".to_string(),
+        lane.length().inner_meters().into(),
+    );
+    properties.insert(
+        "speed_limit_mps".to_string(),
+        road.speed_limit.inner_meters_per_second().into(),
+    );
+    properties.insert("src_i".to_string(), lane.src_i.0.into());
+    properties.insert("dst_i".to_string(), lane.dst_i.0.into());
+    properties.insert(
+        "successors".to_string(),
+        serde_json::value::Value::Array(
+            map.get_turns_from_lane(lane.id)
+                .into_iter()
+                .map(|t| t.id.dst.0.into())
+                .collect(),
+        ),
+    );
+    properties.insert(
+        "predecessors".to_string(),
+        serde_json::value::Value::Array(
+            map.get_turns_to_lane(lane.id)
+                .into_iter()
+                .map(|t| t.id.src.0.into())
+                .collect(),
+        ),
+    );
+
+    Feature {
+        bbox: None,
+        geometry: Some(lane.lane_center_pts.to_geojson(Some(map.get_gps_bounds()))),
+        id: Some(Id::String(format!("lane_{}", lane.id.0))),
+        properties: Some(properties),
+        foreign_members: None,
+    }
+}
+
+fn turn(turn: &Turn, map: &Map) -> Feature {
+    let mut properties = serde_json::Map::new();
+    properties.insert("kind".to_string(), "turn".into());
+    properties.insert("intersection".to_string(), turn.id.parent.0.into());
+    properties.insert("src".to_string(), turn.id.src.0.into());
+    properties.insert("dst".to_string(), turn.id.dst.0.into());
+    properties.insert("type".to_string(), format!("{:?}", turn.turn_type).into());
+
+    Feature {
+        bbox: None,
+        geometry: Some(turn.geom.to_geojson(Some(map.get_gps_bounds()))),
+        id: Some(Id::String(format!(
+            "turn_{}_{}_{}",
+            turn.id.parent.0, turn.id.src.0, turn.id.dst.0
+        ))),
+        properties: Some(properties),
+        foreign_members: None,
+    }
+}
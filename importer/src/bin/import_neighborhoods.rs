@@ -0,0 +1,33 @@
+use abstutil::CmdArgs;
+use geom::LonLat;
+
+/// Imports neighborhood boundaries from GeoJSON, like census tracts or city planning zones. Every
+/// polygon needs a `name` property. Each one is saved as a .poly file, which the neighborhood
+/// partition tool picks up for that city.
+///
+/// ```text
+/// import_neighborhoods --input=census_tracts.geojson --city=seattle
+/// ```
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = CmdArgs::new();
+    let input = args.required("--input");
+    let city = args.required("--city");
+    args.done();
+
+    let dir = abstutil::path_neighborhoods(&city);
+    std::fs::create_dir_all(&dir)?;
+    let polygons = LonLat::read_geojson_polygons(&input)?;
+    for (name, mut pts) in polygons {
+        if pts.len() < 3 {
+            println!("Skipping {}, which has too few points", name);
+            continue;
+        }
+        if pts[0] != *pts.last().unwrap() {
+            pts.push(pts[0]);
+        }
+        let path = format!("{}/{}.poly", dir, name.replace('/', "_"));
+        LonLat::write_osmosis_polygon(&path, &pts)?;
+        println!("Wrote {}", path);
+    }
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use std::collections::BTreeMap;
+
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+
+use abstutil::{CmdArgs, Timer};
+use map_model::{BuildingID, Map};
+use sim::{ODMatrix, Scenario};
+
+/// Builds a scenario from an origin-destination matrix. The config is an `ODMatrix` as JSON,
+/// naming the two CSV files. Its neighborhoods can be left out and read from a file saved by the
+/// partitioning tool instead.
+///
+/// ```text
+/// import_od_matrix --map=data/system/seattle/maps/montlake.bin --config=od.json
+///     [--neighborhoods=seattle_montlake_neighborhoods.json] [--rng_seed=42]
+/// ```
+///
+/// A config looks like this, with times in seconds since midnight:
+///
+/// ```text
+/// {
+///   "scenario_name": "planning_model",
+///   "od_path": "od.csv",
+///   "zones_path": "zones.csv",
+///   "start_time": 25200.0,
+///   "stop_time": 32400.0,
+///   "departures": "Uniform",
+///   "percent_driving": 0.6,
+///   "percent_biking": 0.1,
+///   "percent_use_transit": 0.2
+/// }
+/// ```
+fn main() {
+    let mut args = CmdArgs::new();
+    let map_path = args.required("--map");
+    let config_path = args.required("--config");
+    let neighborhoods_path = args.optional("--neighborhoods");
+    let mut rng = XorShiftRng::seed_from_u64(
+        args.optional_parse("--rng_seed", |s| s.parse::<u64>())
+            .unwrap_or(42),
+    );
+    args.done();
+
+    let mut timer = Timer::new("import OD matrix");
+    let map = Map::new(map_path, &mut timer);
+    let mut od: ODMatrix = abstutil::read_json(config_path, &mut timer);
+    if let Some(path) = neighborhoods_path {
+        let neighborhoods: BTreeMap<String, Vec<BuildingID>> =
+            abstutil::read_json(path, &mut timer);
+        od.neighborhoods.extend(neighborhoods);
+    }
+
+    match Scenario::from_od_matrix(&od, &map, &mut rng, &mut timer) {
+        Ok(scenario) => {
+            println!(
+                "{} people make trips in {}",
+                abstutil::prettyprint_usize(scenario.people.len()),
+                scenario.scenario_name
+            );
+            scenario.save();
+        }
+        Err(err) => {
+            println!("Couldn't build a scenario from {}: {}", od.od_path, err);
+            std::process::exit(1);
+        }
+    }
+}
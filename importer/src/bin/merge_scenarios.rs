@@ -0,0 +1,44 @@
+use abstutil::{CmdArgs, Timer};
+use map_model::Map;
+use sim::{Scenario, ScenarioDiff};
+
+/// Combines separately authored scenarios on the same map into one, like base commutes plus a
+/// stadium event. Variations saved by `diff_scenarios` can be layered on top, in order.
+///
+/// ```text
+/// merge_scenarios --inputs=weekday.bin,game_day.bin --output=weekday_with_game.bin
+/// merge_scenarios --inputs=weekday.bin --diffs=game.json,road_closure.json --output=variant.bin
+/// ```
+fn main() {
+    let mut args = CmdArgs::new();
+    let inputs = args.required("--inputs");
+    let diffs = args.optional("--diffs");
+    let output = args.required("--output");
+    args.done();
+
+    let mut timer = Timer::new("merge scenarios");
+    let scenarios: Vec<Scenario> = inputs
+        .split(',')
+        .map(|path| abstutil::read_binary(path.to_string(), &mut timer))
+        .collect();
+    let map = Map::new(scenarios[0].map_name.path(), &mut timer);
+    let mut result = Scenario::merge(scenarios, &map);
+    for path in diffs.iter().flat_map(|x| x.split(',')) {
+        let diff: ScenarioDiff = abstutil::read_json(path.to_string(), &mut timer);
+        result = result.and_then(|scenario| diff.apply(&scenario));
+    }
+    match result {
+        Ok(scenario) => {
+            abstutil::write_binary(output.clone(), &scenario);
+            println!(
+                "Wrote {} with {} people",
+                output,
+                abstutil::prettyprint_usize(scenario.people.len())
+            );
+        }
+        Err(err) => {
+            println!("Can't merge: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
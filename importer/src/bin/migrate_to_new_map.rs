@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+
+use abstutil::{CmdArgs, Timer};
+use map_model::{BuildingID, Map, MapEdits, MapMigration, MigrationReport};
+use sim::Scenario;
+
+/// After a map is imported again, checks everything saved against the previous version of it --
+/// map edits, scenarios, and neighborhood partitions -- and fixes up IDs that changed. Pass a copy
+/// of the map from before the import. Nothing is overwritten without `--write`.
+///
+/// ```text
+/// migrate_to_new_map --old_map=montlake_before.bin --new_map=data/system/seattle/maps/montlake.bin
+///     [--neighborhoods=montlake_neighborhoods.json] [--write]
+/// ```
+fn main() {
+    let mut args = CmdArgs::new();
+    let old_map_path = args.required("--old_map");
+    let new_map_path = args.required("--new_map");
+    let neighborhoods = args.optional("--neighborhoods");
+    let write = args.enabled("--write");
+    args.done();
+
+    let mut timer = Timer::new("migrate to new map");
+    let old_map = Map::new(old_map_path, &mut timer);
+    let new_map = Map::new(new_map_path, &mut timer);
+    let migration = MapMigration::new(&old_map, &new_map);
+    let mut all_clean = true;
+
+    for path in abstutil::list_all_objects(abstutil::path_all_edits(new_map.get_name()))
+        .into_iter()
+        .map(|name| abstutil::path_edits(new_map.get_name(), &name))
+    {
+        // Read the edits as the old map understood them, which also upgrades old formats
+        let edits = match MapEdits::load(&old_map, path.clone(), &mut timer) {
+            Ok(edits) => edits,
+            Err(err) => {
+                println!("{}: couldn't load against the old map: {}", path, err);
+                all_clean = false;
+                continue;
+            }
+        };
+        let (edits, report) = migration.edits(edits.to_permanent(&old_map));
+        all_clean &= finish(&path, &report);
+        if write && !report.is_clean() {
+            abstutil::write_json(path, &edits);
+        }
+    }
+
+    for name in abstutil::list_all_objects(abstutil::path_all_scenarios(new_map.get_name())) {
+        let path = abstutil::path_scenario(new_map.get_name(), &name);
+        let scenario: Scenario = abstutil::read_binary(path.clone(), &mut timer);
+        let (scenario, report) = scenario.migrate(&migration);
+        all_clean &= finish(&path, &report);
+        if write && !report.is_clean() {
+            abstutil::write_binary(path, &scenario);
+        }
+    }
+
+    if let Some(path) = neighborhoods {
+        let partition: BTreeMap<String, Vec<BuildingID>> =
+            abstutil::read_json(path.clone(), &mut timer);
+        let mut report = MigrationReport::default();
+        let mut fixed: BTreeMap<String, Vec<BuildingID>> = BTreeMap::new();
+        for (zone, bldgs) in partition {
+            let mut moved = 0;
+            for b in bldgs {
+                match migration.building(b) {
+                    Ok(id) => {
+                        if id == b {
+                            report.unchanged += 1;
+                        } else {
+                            moved += 1;
+                        }
+                        fixed.entry(zone.clone()).or_default().push(id);
+                    }
+                    Err(err) => {
+                        report.needs_attention.push(format!("{}: {}", zone, err));
+                    }
+                }
+            }
+            if moved > 0 {
+                report
+                    .migrated
+                    .push(format!("{}: {} buildings have new IDs", zone, moved));
+            }
+        }
+        all_clean &= finish(&path, &report);
+        if write && !report.is_clean() {
+            abstutil::write_json(path, &fixed);
+        }
+    }
+
+    if !all_clean && !write {
+        println!("\nNothing was changed. Pass --write to save the migrated files.");
+    }
+}
+
+/// Prints the report, returning true if nothing needed to change.
+fn finish(path: &str, report: &MigrationReport) -> bool {
+    for line in report.describe(path) {
+        println!("{}", line);
+    }
+    report.is_clean()
+}
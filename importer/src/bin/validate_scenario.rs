@@ -0,0 +1,30 @@
+//! Checks a scenario against its map and lists everything that'll keep it from running as
+//! intended, before spending time on a simulation. Exits with an error if the scenario can't be
+//! instantiated at all.
+
+use abstutil::{CmdArgs, Timer};
+use map_model::Map;
+use sim::Scenario;
+
+fn main() {
+    let mut args = CmdArgs::new();
+    let mut timer = Timer::new("validate scenario");
+    let path = args.required_free();
+    let scenario: Scenario = abstutil::read_object(path.clone(), &mut timer)
+        .unwrap_or_else(|err| panic!("Couldn't read {}: {}", path, err));
+    args.done();
+
+    let map = Map::new(scenario.map_name.path(), &mut timer);
+    let problems = scenario.validate(&map);
+    for problem in &problems {
+        println!("{:?}: {}", problem.kind, problem);
+    }
+    println!(
+        "{} problems with {}",
+        abstutil::prettyprint_usize(problems.len()),
+        scenario.scenario_name
+    );
+    if problems.iter().any(|x| x.is_fatal()) {
+        std::process::exit(1);
+    }
+}
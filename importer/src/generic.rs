@@ -25,6 +25,11 @@ pub struct GenericCityImporter {
     pub elevation: Option<String>,
     /// OSM railway=rail will be included as light rail if so. Cosmetic only.
     pub include_railroads: bool,
+    pub paths_through_areas: convert_osm::PathsThroughAreas,
+    /// If provided, import bus routes, stops, and schedules from this GTFS feed (a .zip), in
+    /// addition to the routes mapped in OSM.
+    #[serde(default)]
+    pub gtfs_url: Option<String>,
 }
 
 impl GenericCityImporter {
@@ -45,6 +50,9 @@ impl GenericCityImporter {
                 .unwrap()
         );
         download(config, &local_osm_file, &self.osm_url);
+        if let Some(ref url) = self.gtfs_url {
+            download(config, &format!("input/{}/gtfs/", name.city), url);
+        }
 
         osmconvert(
             &local_osm_file,
@@ -65,6 +73,7 @@ impl GenericCityImporter {
                 private_offstreet_parking: self.private_offstreet_parking.clone(),
                 elevation: self.elevation.clone(),
                 include_railroads: self.include_railroads,
+                paths_through_areas: self.paths_through_areas.clone(),
             },
             timer,
         );
@@ -0,0 +1,334 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fs::File;
+
+use serde::Deserialize;
+
+use abstutil::{prettyprint_usize, MultiMap, Timer};
+use geom::{Distance, FindClosest, LonLat, Time};
+use map_model::{BusRouteID, LaneID, Map, PathConstraints, Position};
+
+// Stops farther than this from any sidewalk aren't in the map
+const MAX_DIST_TO_SIDEWALK: Distance = Distance::const_meters(50.0);
+
+/// Imports bus routes, stops, and schedules from a GTFS feed, unzipped into a directory. See
+/// https://developers.google.com/transit/gtfs/reference.
+///
+/// - Only buses are imported, not trains, trams, or ferries.
+/// - Every stop is snapped to the closest sidewalk, and buses stop in the closest lane they can
+///   use on the same road. Stops outside the map are skipped, so a route just covers the part
+///   inside the map.
+/// - Trips of one route visiting the same stops in the same order become one route in the map,
+///   with a bus starting at each trip's departure time from its first stop inside the map.
+/// - Only services running on Wednesdays are used, as a typical weekday. If the feed only has a
+///   calendar_dates.txt, the Wednesday with the most service is used.
+///
+/// Routes mapped in OSM with the same short name as a route in the feed are replaced. Trips that
+/// can't be understood are skipped. The pathfinder is updated afterwards.
+pub fn import(map: &mut Map, dir: &str, timer: &mut Timer) -> Result<(), Box<dyn Error>> {
+    timer.start("read GTFS");
+    let stops = snap_stops(map, dir)?;
+    let routes: BTreeMap<String, RouteRecord> = read::<RouteRecord>(dir, "routes.txt")?
+        .into_iter()
+        .filter(|r| r.route_type == 3)
+        .map(|r| (r.route_id.clone(), r))
+        .collect();
+    let services = weekday_services(dir)?;
+    let trips: BTreeMap<String, TripRecord> = read::<TripRecord>(dir, "trips.txt")?
+        .into_iter()
+        .filter(|t| {
+            routes.contains_key(&t.route_id)
+                && services
+                    .as_ref()
+                    .map(|s| s.contains(&t.service_id))
+                    .unwrap_or(true)
+        })
+        .map(|t| (t.trip_id.clone(), t))
+        .collect();
+
+    let mut stop_times_per_trip: BTreeMap<String, Vec<StopTimeRecord>> = BTreeMap::new();
+    for rec in read::<StopTimeRecord>(dir, "stop_times.txt")? {
+        if trips.contains_key(&rec.trip_id) {
+            stop_times_per_trip
+                .entry(rec.trip_id.clone())
+                .or_default()
+                .push(rec);
+        }
+    }
+    timer.stop("read GTFS");
+
+    // Group trips by route and the stops they visit in the map, remembering when each one
+    // reaches its first stop
+    let mut patterns: MultiMap<(String, Vec<String>), (Time, String)> = MultiMap::new();
+    for (trip_id, stop_times) in stop_times_per_trip {
+        match trip_pattern(&stops, stop_times) {
+            Ok(Some((stop_ids, depart))) => {
+                let trip = &trips[&trip_id];
+                patterns.insert(
+                    (trip.route_id.clone(), stop_ids),
+                    (depart, trip.trip_headsign.clone().unwrap_or_default()),
+                );
+            }
+            // The trip doesn't go through the map
+            Ok(None) => {}
+            Err(err) => {
+                timer.warn(format!("Skipping GTFS trip {}: {}", trip_id, err));
+            }
+        }
+    }
+
+    let mut num_routes = 0;
+    let mut names_used: BTreeSet<String> = BTreeSet::new();
+    // Short names of the routes imported, like "43"
+    let mut imported: BTreeSet<String> = BTreeSet::new();
+    for ((route_id, stop_ids), departures) in patterns.consume() {
+        let route = &routes[&route_id];
+        let headsign = departures.iter().next().unwrap().1.clone();
+        let mut spawn_times: Vec<Time> = departures.into_iter().map(|(t, _)| t).collect();
+        spawn_times.sort();
+        spawn_times.dedup();
+
+        let short_name = if route.route_short_name.is_empty() {
+            route.route_long_name.clone()
+        } else {
+            route.route_short_name.clone()
+        };
+        let mut name = if headsign.is_empty() {
+            short_name.clone()
+        } else {
+            format!("{} to {}", short_name, headsign)
+        };
+        // Variations of a route toward the same place need different names
+        if names_used.contains(&name) {
+            let mut variant = 2;
+            while names_used.contains(&format!("{} (variant {})", name, variant)) {
+                variant += 1;
+            }
+            name = format!("{} (variant {})", name, variant);
+        }
+
+        let positions = stop_ids.iter().map(|id| stops[id]).collect();
+        match map.import_bus_route(name.clone(), positions, spawn_times) {
+            Ok(_) => {
+                names_used.insert(name);
+                imported.insert(short_name);
+                num_routes += 1;
+            }
+            Err(err) => {
+                timer.warn(format!("Skipping GTFS route {}: {}", name, err));
+            }
+        }
+    }
+    timer.note(format!(
+        "Imported {} bus routes from GTFS",
+        prettyprint_usize(num_routes)
+    ));
+
+    // The schedules in the feed are more detailed than the routes mapped in OSM, so drop the OSM
+    // version of any route the feed covers.
+    let duplicates: BTreeSet<BusRouteID> = map
+        .all_bus_routes()
+        .iter()
+        .filter(|r| {
+            r.route_type == PathConstraints::Bus
+                && r.osm_rel_id.0 >= 0
+                && imported.contains(&r.short_name)
+        })
+        .map(|r| r.id)
+        .collect();
+    timer.note(format!(
+        "Replaced {} bus routes from OSM with GTFS",
+        prettyprint_usize(duplicates.len())
+    ));
+    map.remove_bus_routes(&duplicates);
+
+    map.rebuild_pathfinder(timer);
+    Ok(())
+}
+
+// The stops a trip visits in the map, and when it departs from the first one. None if the trip
+// doesn't spend long enough in the map to matter.
+fn trip_pattern(
+    stops: &BTreeMap<String, Position>,
+    mut stop_times: Vec<StopTimeRecord>,
+) -> Result<Option<(Vec<String>, Time)>, String> {
+    stop_times.sort_by_key(|st| st.stop_sequence);
+    // Don't visit the same stop twice in a row; some feeds repeat a stop to show a layover
+    let mut stop_ids: Vec<String> = stop_times
+        .iter()
+        .filter(|st| stops.contains_key(&st.stop_id))
+        .map(|st| st.stop_id.clone())
+        .collect();
+    stop_ids.dedup();
+    if stop_ids.len() < 2 {
+        return Ok(None);
+    }
+    // Only some stops have times. If the first stop in the map doesn't, use the last time before
+    // it; the first stop of every trip should have one.
+    let first_idx = stop_times
+        .iter()
+        .position(|st| st.stop_id == stop_ids[0])
+        .ok_or_else(|| format!("stop {} isn't in the trip", stop_ids[0]))?;
+    let departure = &stop_times[..=first_idx]
+        .iter()
+        .rev()
+        .find(|st| !st.departure_time.is_empty())
+        .ok_or("no departure time")?
+        .departure_time;
+    let depart =
+        Time::parse(departure).map_err(|err| format!("departs at {}: {}", departure, err))?;
+    Ok(Some((stop_ids, depart)))
+}
+
+// Where buses stop for each GTFS stop inside the map
+fn snap_stops(map: &Map, dir: &str) -> Result<BTreeMap<String, Position>, Box<dyn Error>> {
+    let mut closest: FindClosest<LaneID> = FindClosest::new(map.get_bounds());
+    for l in map.all_lanes() {
+        if l.is_sidewalk() {
+            closest.add(l.id, l.lane_center_pts.points());
+        }
+    }
+
+    let mut stops = BTreeMap::new();
+    for rec in read::<StopRecord>(dir, "stops.txt")? {
+        // Skip stations, entrances, and other things that aren't where vehicles stop
+        if rec.location_type.unwrap_or(0) != 0 {
+            continue;
+        }
+        let gps = LonLat::new(rec.stop_lon, rec.stop_lat);
+        if !map.get_gps_bounds().contains(gps) {
+            continue;
+        }
+        let pt = gps.to_pt(map.get_gps_bounds());
+        if let Some((sidewalk, pt)) = closest.closest_pt(pt, MAX_DIST_TO_SIDEWALK) {
+            let lane = map.get_l(sidewalk);
+            let driving = map.get_parent(sidewalk).find_closest_lane(
+                sidewalk,
+                |l| PathConstraints::Bus.can_use(l, map),
+                map,
+            );
+            if let (Some((dist, _)), Some(driving)) =
+                (lane.lane_center_pts.dist_along_of_point(pt), driving)
+            {
+                stops.insert(
+                    rec.stop_id,
+                    Position::new(sidewalk, dist).equiv_pos(driving, map),
+                );
+            }
+        }
+    }
+    Ok(stops)
+}
+
+// The services running on a typical weekday. None if the feed doesn't say when services run.
+fn weekday_services(dir: &str) -> Result<Option<BTreeSet<String>>, Box<dyn Error>> {
+    let has_calendar = std::path::Path::new(&format!("{}/calendar.txt", dir)).exists();
+    let has_dates = std::path::Path::new(&format!("{}/calendar_dates.txt", dir)).exists();
+    if !has_calendar && !has_dates {
+        return Ok(None);
+    }
+
+    let mut services = BTreeSet::new();
+    if has_calendar {
+        for rec in read::<CalendarRecord>(dir, "calendar.txt")? {
+            if rec.wednesday == 1 {
+                services.insert(rec.service_id);
+            }
+        }
+    }
+    // Some feeds only list each date a service runs. Use the Wednesday with the most service.
+    if services.is_empty() && has_dates {
+        let mut per_date: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for rec in read::<CalendarDateRecord>(dir, "calendar_dates.txt")? {
+            if rec.exception_type == 1 && is_wednesday(&rec.date) {
+                per_date.entry(rec.date).or_default().insert(rec.service_id);
+            }
+        }
+        if let Some((_, busiest)) = per_date.into_iter().max_by_key(|(_, s)| s.len()) {
+            services = busiest;
+        }
+    }
+    Ok(Some(services))
+}
+
+// Dates look like 20200923. Malformed dates aren't Wednesdays.
+fn is_wednesday(date: &str) -> bool {
+    if date.len() != 8 {
+        return false;
+    }
+    let (y, m, d) = match (
+        date[0..4].parse::<usize>(),
+        date[4..6].parse::<usize>(),
+        date[6..8].parse::<usize>(),
+    ) {
+        (Ok(y), Ok(m), Ok(d)) if (1..=12).contains(&m) && y > 0 => (y, m, d),
+        _ => return false,
+    };
+    // Sakamoto's method, where 0 is Sunday
+    let offsets = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+    let y = if m < 3 { y - 1 } else { y };
+    (y + y / 4 - y / 100 + y / 400 + offsets[m - 1] + d) % 7 == 3
+}
+
+fn read<T: serde::de::DeserializeOwned>(dir: &str, file: &str) -> Result<Vec<T>, Box<dyn Error>> {
+    let path = format!("{}/{}", dir, file);
+    let mut results = Vec::new();
+    for rec in csv::Reader::from_reader(
+        File::open(&path).map_err(|err| format!("Can't open {}: {}", path, err))?,
+    )
+    .deserialize()
+    {
+        results.push(rec.map_err(|err| format!("Bad row in {}: {}", path, err))?);
+    }
+    Ok(results)
+}
+
+#[derive(Deserialize)]
+struct StopRecord {
+    stop_id: String,
+    stop_lat: f64,
+    stop_lon: f64,
+    #[serde(default)]
+    location_type: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct RouteRecord {
+    route_id: String,
+    #[serde(default)]
+    route_short_name: String,
+    #[serde(default)]
+    route_long_name: String,
+    route_type: usize,
+}
+
+#[derive(Deserialize)]
+struct TripRecord {
+    route_id: String,
+    service_id: String,
+    trip_id: String,
+    #[serde(default)]
+    trip_headsign: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StopTimeRecord {
+    trip_id: String,
+    departure_time: String,
+    stop_id: String,
+    stop_sequence: usize,
+}
+
+#[derive(Deserialize)]
+struct CalendarRecord {
+    service_id: String,
+    wednesday: usize,
+}
+
+#[derive(Deserialize)]
+struct CalendarDateRecord {
+    service_id: String,
+    date: String,
+    // 1 means the service runs that date, 2 means it doesn't
+    exception_type: usize,
+}
@@ -35,6 +35,7 @@ pub fn osm_to_raw(name: &str, timer: &mut abstutil::Timer, config: &ImporterConf
                 driving_side: map_model::DrivingSide::Left,
                 bikes_can_use_bus_lanes: true,
                 inferred_sidewalks: true,
+                walk_on_shoulders: false,
             },
 
             onstreet_parking: convert_osm::OnstreetParking::JustOSM,
@@ -42,6 +43,7 @@ pub fn osm_to_raw(name: &str, timer: &mut abstutil::Timer, config: &ImporterConf
             private_offstreet_parking: convert_osm::PrivateOffstreetParking::FixedPerBldg(10),
             elevation: None,
             include_railroads: true,
+            paths_through_areas: convert_osm::PathsThroughAreas::None,
         },
         timer,
     );
@@ -10,6 +10,7 @@ mod berlin;
 mod configuration;
 mod dependencies;
 mod generic;
+mod gtfs;
 mod leeds;
 mod london;
 mod seattle;
@@ -177,6 +178,26 @@ fn main() {
                 timer.start(format!("add GTFS schedules for {}", name.describe()));
                 seattle::add_gtfs_schedules(&mut map);
                 timer.stop(format!("add GTFS schedules for {}", name.describe()));
+            } else if let Ok(city_cfg) = abstutil::maybe_read_json::<generic::GenericCityImporter>(
+                format!("importer/config/{}/cfg.json", name.city),
+                &mut timer,
+            ) {
+                if city_cfg.gtfs_url.is_some() {
+                    timer.start(format!("import GTFS for {}", name.describe()));
+                    match gtfs::import(
+                        &mut map,
+                        &abstutil::path(format!("input/{}/gtfs", name.city)),
+                        &mut timer,
+                    ) {
+                        Ok(()) => {
+                            map.save();
+                        }
+                        Err(err) => {
+                            timer.warn(format!("Skipping GTFS for {}: {}", name.describe(), err));
+                        }
+                    }
+                    timer.stop(format!("import GTFS for {}", name.describe()));
+                }
             }
 
             Some(map)
@@ -242,13 +263,17 @@ fn oneshot(
                 },
                 bikes_can_use_bus_lanes: true,
                 inferred_sidewalks,
+                walk_on_shoulders: true,
             },
 
             onstreet_parking: convert_osm::OnstreetParking::JustOSM,
             public_offstreet_parking: convert_osm::PublicOffstreetParking::None,
-            private_offstreet_parking: convert_osm::PrivateOffstreetParking::FixedPerBldg(1),
+            private_offstreet_parking: convert_osm::PrivateOffstreetParking::FromTags {
+                default: 1,
+            },
             elevation: None,
             include_railroads: true,
+            paths_through_areas: convert_osm::PathsThroughAreas::ParksAndPlazas,
         },
         &mut timer,
     );
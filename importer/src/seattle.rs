@@ -111,6 +111,7 @@ pub fn osm_to_raw(name: &str, timer: &mut abstutil::Timer, config: &ImporterConf
                 driving_side: map_model::DrivingSide::Right,
                 bikes_can_use_bus_lanes: true,
                 inferred_sidewalks: true,
+                walk_on_shoulders: false,
             },
 
             onstreet_parking: convert_osm::OnstreetParking::Blockface(abstutil::path(
@@ -132,6 +133,7 @@ pub fn osm_to_raw(name: &str, timer: &mut abstutil::Timer, config: &ImporterConf
             elevation: Some(abstutil::path("input/seattle/N47W122.hgt")),
             // They mess up 16th and E Marginal badly enough to cause gridlock.
             include_railroads: false,
+            paths_through_areas: convert_osm::PathsThroughAreas::None,
         },
         timer,
     );
@@ -1,9 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use abstutil::{prettyprint_usize, MultiMap, Parallelism, Timer};
 use geom::LonLat;
 use map_model::{osm, BuildingID, IntersectionID, Map, PathConstraints, PathRequest, PathStep};
-use sim::{IndividTrip, OrigPersonID, PersonSpec, Scenario, TripEndpoint, TripMode};
+use sim::{
+    BridgeOpenings, IndividTrip, OrigPersonID, ParkingPrices, PersonSpec, Scenario, TrainSchedule,
+    TransitFares, TripEndpoint, TripMode,
+};
 
 use crate::soundcast::popdat::{Endpoint, OrigTrip, PopDat};
 
@@ -305,6 +308,12 @@ pub fn make_weekday_scenario(
         map_name: map.get_name().clone(),
         people,
         only_seed_buses: None,
+        parking_prices: ParkingPrices::free(),
+        transit_fares: TransitFares::free(),
+        train_schedule: TrainSchedule::none(),
+        bridge_openings: BridgeOpenings::none(),
+        abandon_trips_after: None,
+        idle_parked_cars: BTreeMap::new(),
     }
     .remove_weird_schedules()
 }
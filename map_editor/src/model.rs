@@ -220,6 +220,7 @@ impl Model {
                 // TODO If this isn't a synthetic map, load the elevation data and grab a real
                 // value.
                 elevation: Distance::ZERO,
+                rail_crossing: false,
             },
         );
         self.intersection_added(id, ctx);
@@ -83,6 +83,7 @@ pub struct ColorScheme {
     road_center_line: Color,
     pub light_rail_track: Color,
     pub private_road: Color,
+    pub work_zone: Color,
     unzoomed_highway: Color,
     unzoomed_arterial: Color,
     unzoomed_residential: Color,
@@ -114,6 +115,7 @@ pub struct ColorScheme {
     pub grass: Fill,
     pub water: Fill,
     pub median_strip: Fill,
+    pub pedestrian_plaza: Fill,
 
     // Unzoomed dynamic elements
     pub unzoomed_car: Color,
@@ -204,6 +206,7 @@ impl ColorScheme {
             road_center_line: Color::YELLOW,
             light_rail_track: hex("#844204"),
             private_road: hex("#F0B0C0"),
+            work_zone: hex("#F5821F"),
             unzoomed_highway: Color::rgb(232, 146, 162),
             unzoomed_arterial: Color::rgb(255, 199, 62),
             unzoomed_residential: Color::WHITE,
@@ -235,6 +238,7 @@ impl ColorScheme {
             grass: hex("#94C84A").into(),
             water: Color::rgb(164, 200, 234).into(),
             median_strip: Color::CYAN.into(),
+            pedestrian_plaza: hex("#DDDDE8").into(),
 
             // Unzoomed dynamic elements
             unzoomed_car: hex("#A32015"),
@@ -59,13 +59,17 @@ pub trait AppLike {
             return false;
         }
         self.mut_opts().color_scheme = cs;
+        self.rerender_color_scheme(ctx);
+        true
+    }
+
+    /// Rebuild the colors and everything drawn with them after `opts().color_scheme` changes.
+    fn rerender_color_scheme(&mut self, ctx: &mut EventCtx) {
         *self.mut_cs() = ColorScheme::new(ctx, self.opts().color_scheme);
 
         ctx.loading_screen("rerendering map colors", |ctx, timer| {
             *self.mut_draw_map() = DrawMap::new(ctx, self.map(), self.opts(), self.cs(), timer);
         });
-
-        true
     }
 }
 
@@ -40,6 +40,10 @@ pub struct Options {
     pub dont_draw_time_warp: bool,
     /// The delay threshold to halt on when jumping to the next delay
     pub jump_to_delay: Duration,
+    /// Show a few key numbers about the whole simulation, with a little history of each
+    pub show_kpis: bool,
+    /// Which of those numbers to mark as missing a goal. Challenges may use their own.
+    pub kpi_targets: KpiTargets,
 
     /// Display roads and buildings in an alternate language, if possible. None means to use the
     /// OSM native name.
@@ -63,6 +67,8 @@ impl Options {
             time_increment: Duration::minutes(10),
             dont_draw_time_warp: false,
             jump_to_delay: Duration::minutes(5),
+            show_kpis: false,
+            kpi_targets: KpiTargets::default(),
 
             language: None,
             units: UnitFmt {
@@ -101,6 +107,18 @@ impl Options {
     }
 }
 
+/// Goals for the key numbers about a simulation. Each one is optional; numbers without a goal are
+/// just shown.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct KpiTargets {
+    /// The average time finished trips spent waiting at intersections
+    pub max_avg_delay: Option<Duration>,
+    /// The percent of transit vehicles leaving on time after a layover
+    pub min_transit_on_time: Option<f64>,
+    /// Total CO2 emitted so far, in kilograms
+    pub max_co2_kg: Option<f64>,
+}
+
 /// Different ways of drawing traffic signals. The names of these aren't super meaningful...
 #[derive(Clone, PartialEq, Debug)]
 pub enum TrafficSignalStyle {
@@ -119,12 +137,312 @@ pub enum CameraAngle {
     Abstract,
 }
 
-pub struct OptionsPanel {
+/// Ties widgets in a settings panel to fields of `Options`. Each widget is created showing the
+/// current value, and `apply` copies every changed value back, running any callbacks needed to
+/// rebuild things that depend on that option.
+pub struct OptionBindings<A: AppLike> {
+    bindings: Vec<Binding<A>>,
+}
+
+struct Binding<A> {
+    // Copies the widget's value into the options, returning true if it changed
+    update: Box<dyn Fn(&Panel, &mut Options) -> bool>,
+    on_change: Option<Box<dyn Fn(&mut EventCtx, &mut A)>>,
+}
+
+impl<A: AppLike> OptionBindings<A> {
+    pub fn new() -> OptionBindings<A> {
+        OptionBindings {
+            bindings: Vec::new(),
+        }
+    }
+
+    /// A dropdown editing one option. The label also names the widget.
+    pub fn dropdown<T: 'static + PartialEq + Clone + std::fmt::Debug>(
+        &mut self,
+        ctx: &EventCtx,
+        app: &A,
+        label: &str,
+        get: fn(&Options) -> T,
+        set: fn(&mut Options, T),
+        choices: Vec<Choice<T>>,
+    ) -> Widget {
+        let name = label.to_string();
+        self.bindings.push(Binding {
+            update: Box::new(move |panel, opts| {
+                let value = panel.dropdown_value(&name);
+                if get(opts) == value {
+                    return false;
+                }
+                set(opts, value);
+                true
+            }),
+            on_change: None,
+        });
+        Widget::row(vec![
+            format!("{}:", label).draw_text(ctx),
+            Widget::dropdown(ctx, label, get(app.opts()), choices),
+        ])
+    }
+
+    /// A checkbox editing one option. The label also names the widget.
+    pub fn checkbox(
+        &mut self,
+        ctx: &EventCtx,
+        app: &A,
+        label: &str,
+        get: fn(&Options) -> bool,
+        set: fn(&mut Options, bool),
+    ) -> Widget {
+        self.bindings.push(Binding {
+            update: bool_update(label, get, set),
+            on_change: None,
+        });
+        Checkbox::checkbox(ctx, label, None, get(app.opts()))
+    }
+
+    /// A toggle between two labelled states editing one option, which is true when the left label
+    /// is picked. The name identifies the widget.
+    pub fn toggle(
+        &mut self,
+        ctx: &EventCtx,
+        app: &A,
+        (name, left, right): (&str, &str, &str),
+        get: fn(&Options) -> bool,
+        set: fn(&mut Options, bool),
+    ) -> Widget {
+        self.bindings.push(Binding {
+            update: bool_update(name, get, set),
+            on_change: None,
+        });
+        Checkbox::toggle(ctx, name, left, right, None, get(app.opts()))
+    }
+
+    /// Runs after `apply` changes the option bound most recently, to rebuild whatever depends on
+    /// it. The app already has the new options.
+    pub fn on_change<F: 'static + Fn(&mut EventCtx, &mut A)>(&mut self, f: F) {
+        self.bindings.last_mut().unwrap().on_change = Some(Box::new(f));
+    }
+
+    /// Copies the value of every bound widget into the app's options, then runs the callbacks for
+    /// options that changed.
+    pub fn apply(&self, ctx: &mut EventCtx, app: &mut A, panel: &Panel) {
+        let mut opts = app.opts().clone();
+        let changed: Vec<&Binding<A>> = self
+            .bindings
+            .iter()
+            .filter(|b| (b.update)(panel, &mut opts))
+            .collect();
+        *app.mut_opts() = opts;
+        for b in changed {
+            if let Some(ref f) = b.on_change {
+                f(ctx, app);
+            }
+        }
+    }
+}
+
+fn bool_update(
+    name: &str,
+    get: fn(&Options) -> bool,
+    set: fn(&mut Options, bool),
+) -> Box<dyn Fn(&Panel, &mut Options) -> bool> {
+    let name = name.to_string();
+    Box::new(move |panel, opts| {
+        let value = panel.is_checked(&name);
+        if get(opts) == value {
+            return false;
+        }
+        set(opts, value);
+        true
+    })
+}
+
+pub struct OptionsPanel<A: AppLike> {
     panel: Panel,
+    bindings: OptionBindings<A>,
 }
 
-impl OptionsPanel {
-    pub fn new<A: AppLike>(ctx: &mut EventCtx, app: &A) -> Box<dyn State<A>> {
+impl<A: AppLike + 'static> OptionsPanel<A> {
+    pub fn new(ctx: &mut EventCtx, app: &A) -> Box<dyn State<A>> {
+        let mut bindings = OptionBindings::new();
+
+        let appearance = vec![
+            {
+                let w = bindings.dropdown(
+                    ctx,
+                    app,
+                    "Traffic signal rendering",
+                    |o| o.traffic_signal_style.clone(),
+                    |o, x| o.traffic_signal_style = x,
+                    vec![
+                        Choice::new("Default (Brian's style)", TrafficSignalStyle::BAP),
+                        Choice::new("Yuwen's style", TrafficSignalStyle::Yuwen),
+                        Choice::new(
+                            "arrows showing individual turns (to debug)",
+                            TrafficSignalStyle::IndividualTurnArrows,
+                        ),
+                    ],
+                );
+                bindings.on_change(|_, app| {
+                    println!("Rerendering traffic signals...");
+                    for i in &mut app.mut_draw_map().intersections {
+                        *i.draw_traffic_signal.borrow_mut() = None;
+                    }
+                });
+                w
+            },
+            {
+                let w = bindings.dropdown(
+                    ctx,
+                    app,
+                    "Camera angle",
+                    |o| o.camera_angle.clone(),
+                    |o, x| o.camera_angle = x,
+                    vec![
+                        Choice::new("Top-down", CameraAngle::TopDown),
+                        Choice::new("Isometric (northeast)", CameraAngle::IsometricNE),
+                        Choice::new("Isometric (northwest)", CameraAngle::IsometricNW),
+                        Choice::new("Isometric (southeast)", CameraAngle::IsometricSE),
+                        Choice::new("Isometric (southwest)", CameraAngle::IsometricSW),
+                        Choice::new("Abstract (just symbols)", CameraAngle::Abstract),
+                    ],
+                );
+                bindings.on_change(rerender_buildings);
+                w
+            },
+            {
+                let w = bindings.dropdown(
+                    ctx,
+                    app,
+                    "Color scheme",
+                    |o| o.color_scheme,
+                    |o, x| o.color_scheme = x,
+                    ColorSchemeChoice::choices(),
+                );
+                bindings.on_change(|ctx, app| {
+                    // If the player picks a different scheme, don't undo it later.
+                    app.mut_opts().toggle_day_night_colors = false;
+                    app.rerender_color_scheme(ctx);
+                });
+                w
+            },
+            bindings.dropdown(
+                ctx,
+                app,
+                "Camera zoom to switch to unzoomed view",
+                |o| o.min_zoom_for_detail,
+                |o, x| o.min_zoom_for_detail = x,
+                vec![
+                    Choice::new("1.0", 1.0),
+                    Choice::new("2.0", 2.0),
+                    Choice::new("3.0", 3.0),
+                    Choice::new("4.0", 4.0),
+                    Choice::new("5.0", 5.0),
+                    Choice::new("6.0", 6.0),
+                ],
+            ),
+            {
+                let w = bindings.dropdown(
+                    ctx,
+                    app,
+                    "Language",
+                    |o| o.language.clone(),
+                    |o, x| o.language = x,
+                    {
+                        let mut choices = Vec::new();
+                        choices.push(Choice::new("Map native language", None));
+                        for lang in app.map().get_languages() {
+                            choices.push(Choice::new(lang, Some(lang.to_string())));
+                        }
+                        choices
+                    },
+                );
+                bindings.on_change(|_, app| {
+                    for r in &mut app.mut_draw_map().roads {
+                        r.clear_rendering();
+                    }
+                });
+                w
+            },
+            bindings.toggle(
+                ctx,
+                app,
+                ("metric / imperial units", "metric", "imperial"),
+                |o| o.units.metric,
+                |o, x| o.units.metric = x,
+            ),
+        ];
+
+        let kpis = vec![
+            bindings.checkbox(
+                ctx,
+                app,
+                "Show key numbers about the simulation",
+                |o| o.show_kpis,
+                |o, x| o.show_kpis = x,
+            ),
+            bindings.dropdown(
+                ctx,
+                app,
+                "Goal for average delay per trip",
+                |o| o.kpi_targets.max_avg_delay,
+                |o, x| o.kpi_targets.max_avg_delay = x,
+                vec![
+                    Choice::new("none", None),
+                    Choice::new("under 30s", Some(Duration::seconds(30.0))),
+                    Choice::new("under 1 minute", Some(Duration::minutes(1))),
+                    Choice::new("under 2 minutes", Some(Duration::minutes(2))),
+                    Choice::new("under 5 minutes", Some(Duration::minutes(5))),
+                ],
+            ),
+            bindings.dropdown(
+                ctx,
+                app,
+                "Goal for transit on time",
+                |o| o.kpi_targets.min_transit_on_time,
+                |o, x| o.kpi_targets.min_transit_on_time = x,
+                vec![
+                    Choice::new("none", None),
+                    Choice::new("at least 70%", Some(70.0)),
+                    Choice::new("at least 80%", Some(80.0)),
+                    Choice::new("at least 90%", Some(90.0)),
+                    Choice::new("at least 95%", Some(95.0)),
+                ],
+            ),
+            bindings.dropdown(
+                ctx,
+                app,
+                "Goal for CO2 emitted",
+                |o| o.kpi_targets.max_co2_kg,
+                |o, x| o.kpi_targets.max_co2_kg = x,
+                vec![
+                    Choice::new("none", None),
+                    Choice::new("under 100kg", Some(100.0)),
+                    Choice::new("under 1 tonne", Some(1_000.0)),
+                    Choice::new("under 10 tonnes", Some(10_000.0)),
+                    Choice::new("under 100 tonnes", Some(100_000.0)),
+                ],
+            ),
+        ];
+
+        let debug = vec![
+            bindings.checkbox(
+                ctx,
+                app,
+                "Enable developer mode",
+                |o| o.dev,
+                |o, x| o.dev = x,
+            ),
+            bindings.checkbox(
+                ctx,
+                app,
+                "Draw all agents to debug geometry (Slow!)",
+                |o| o.debug_all_agents,
+                |o, x| o.debug_all_agents = x,
+            ),
+        ];
+
         Box::new(OptionsPanel {
             panel: Panel::new(Widget::col(vec![
                 Widget::custom_row(vec![
@@ -167,108 +485,22 @@ impl OptionsPanel {
                 .bg(app.cs().section_bg)
                 .padding(8),
                 "Appearance".draw_text(ctx),
-                Widget::col(vec![
-                    Widget::row(vec![
-                        "Traffic signal rendering:".draw_text(ctx),
-                        Widget::dropdown(
-                            ctx,
-                            "Traffic signal rendering",
-                            app.opts().traffic_signal_style.clone(),
-                            vec![
-                                Choice::new("Default (Brian's style)", TrafficSignalStyle::BAP),
-                                Choice::new("Yuwen's style", TrafficSignalStyle::Yuwen),
-                                Choice::new(
-                                    "arrows showing individual turns (to debug)",
-                                    TrafficSignalStyle::IndividualTurnArrows,
-                                ),
-                            ],
-                        ),
-                    ]),
-                    Widget::row(vec![
-                        "Camera angle:".draw_text(ctx),
-                        Widget::dropdown(
-                            ctx,
-                            "Camera angle",
-                            app.opts().camera_angle.clone(),
-                            vec![
-                                Choice::new("Top-down", CameraAngle::TopDown),
-                                Choice::new("Isometric (northeast)", CameraAngle::IsometricNE),
-                                Choice::new("Isometric (northwest)", CameraAngle::IsometricNW),
-                                Choice::new("Isometric (southeast)", CameraAngle::IsometricSE),
-                                Choice::new("Isometric (southwest)", CameraAngle::IsometricSW),
-                                Choice::new("Abstract (just symbols)", CameraAngle::Abstract),
-                            ],
-                        ),
-                    ]),
-                    Widget::row(vec![
-                        "Color scheme:".draw_text(ctx),
-                        Widget::dropdown(
-                            ctx,
-                            "Color scheme",
-                            app.opts().color_scheme,
-                            ColorSchemeChoice::choices(),
-                        ),
-                    ]),
-                    Widget::row(vec![
-                        "Camera zoom to switch to unzoomed view".draw_text(ctx),
-                        Widget::dropdown(
-                            ctx,
-                            "min zoom",
-                            app.opts().min_zoom_for_detail,
-                            vec![
-                                Choice::new("1.0", 1.0),
-                                Choice::new("2.0", 2.0),
-                                Choice::new("3.0", 3.0),
-                                Choice::new("4.0", 4.0),
-                                Choice::new("5.0", 5.0),
-                                Choice::new("6.0", 6.0),
-                            ],
-                        ),
-                    ]),
-                    Widget::row(vec![
-                        "Language".draw_text(ctx),
-                        Widget::dropdown(ctx, "language", app.opts().language.clone(), {
-                            let mut choices = Vec::new();
-                            choices.push(Choice::new("Map native language", None));
-                            for lang in app.map().get_languages() {
-                                choices.push(Choice::new(lang, Some(lang.to_string())));
-                            }
-                            choices
-                        }),
-                    ]),
-                    Checkbox::toggle(
-                        ctx,
-                        "metric / imperial units",
-                        "metric",
-                        "imperial",
-                        None,
-                        app.opts().units.metric,
-                    ),
-                ])
-                .bg(app.cs().section_bg)
-                .padding(8),
+                Widget::col(appearance).bg(app.cs().section_bg).padding(8),
+                "Key numbers".draw_text(ctx),
+                Widget::col(kpis).bg(app.cs().section_bg).padding(8),
                 "Debug".draw_text(ctx),
-                Widget::col(vec![
-                    Checkbox::checkbox(ctx, "Enable developer mode", None, app.opts().dev),
-                    Checkbox::checkbox(
-                        ctx,
-                        "Draw all agents to debug geometry (Slow!)",
-                        None,
-                        app.opts().debug_all_agents,
-                    ),
-                ])
-                .bg(app.cs().section_bg)
-                .padding(8),
+                Widget::col(debug).bg(app.cs().section_bg).padding(8),
                 Btn::text_bg2("Apply")
                     .build_def(ctx, Key::Enter)
                     .centered_horiz(),
             ]))
             .build(ctx),
+            bindings,
         })
     }
 }
 
-impl<A: AppLike> State<A> for OptionsPanel {
+impl<A: AppLike + 'static> State<A> for OptionsPanel<A> {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut A) -> widgetry::Transition<A> {
         match self.panel.event(ctx) {
             Outcome::Clicked(x) => match x.as_ref() {
@@ -276,12 +508,6 @@ impl<A: AppLike> State<A> for OptionsPanel {
                     return widgetry::Transition::Pop;
                 }
                 "Apply" => {
-                    let mut opts = app.opts().clone();
-                    opts.dev = self.panel.is_checked("Enable developer mode");
-                    opts.debug_all_agents = self
-                        .panel
-                        .is_checked("Draw all agents to debug geometry (Slow!)");
-
                     ctx.canvas.invert_scroll = self
                         .panel
                         .is_checked("Invert direction of vertical scrolling");
@@ -294,64 +520,7 @@ impl<A: AppLike> State<A> for OptionsPanel {
                     ctx.canvas.edge_auto_panning = self.panel.is_checked("autopan");
                     ctx.canvas.gui_scroll_speed = self.panel.spinner("gui_scroll_speed") as usize;
 
-                    let style = self.panel.dropdown_value("Traffic signal rendering");
-                    if opts.traffic_signal_style != style {
-                        opts.traffic_signal_style = style;
-                        println!("Rerendering traffic signals...");
-                        for i in &mut app.mut_draw_map().intersections {
-                            *i.draw_traffic_signal.borrow_mut() = None;
-                        }
-                    }
-
-                    let camera_angle = self.panel.dropdown_value("Camera angle");
-                    if opts.camera_angle != camera_angle {
-                        opts.camera_angle = camera_angle;
-                        ctx.loading_screen("rerendering buildings", |ctx, timer| {
-                            let mut all_buildings = GeomBatch::new();
-                            let mut all_building_paths = GeomBatch::new();
-                            let mut all_building_outlines = GeomBatch::new();
-                            timer
-                                .start_iter("rendering buildings", app.map().all_buildings().len());
-                            for b in app.map().all_buildings() {
-                                timer.next();
-                                DrawBuilding::new(
-                                    ctx,
-                                    b,
-                                    app.map(),
-                                    app.cs(),
-                                    &opts,
-                                    &mut all_buildings,
-                                    &mut all_building_paths,
-                                    &mut all_building_outlines,
-                                );
-                            }
-                            timer.start("upload geometry");
-                            app.mut_draw_map().draw_all_buildings = all_buildings.upload(ctx);
-                            app.mut_draw_map().draw_all_building_paths =
-                                all_building_paths.upload(ctx);
-                            app.mut_draw_map().draw_all_building_outlines =
-                                all_building_outlines.upload(ctx);
-                            timer.stop("upload geometry");
-                        });
-                    }
-
-                    if app.change_color_scheme(ctx, self.panel.dropdown_value("Color scheme")) {
-                        // If the player picks a different scheme, don't undo it later.
-                        opts.toggle_day_night_colors = false;
-                    }
-
-                    opts.min_zoom_for_detail = self.panel.dropdown_value("min zoom");
-                    opts.units.metric = self.panel.is_checked("metric / imperial units");
-
-                    let language = self.panel.dropdown_value("language");
-                    if language != opts.language {
-                        opts.language = language;
-                        for r in &mut app.mut_draw_map().roads {
-                            r.clear_rendering();
-                        }
-                    }
-
-                    *app.mut_opts() = opts;
+                    self.bindings.apply(ctx, app, &self.panel);
 
                     return widgetry::Transition::Pop;
                 }
@@ -368,3 +537,30 @@ impl<A: AppLike> State<A> for OptionsPanel {
         self.panel.draw(g);
     }
 }
+
+fn rerender_buildings<A: AppLike>(ctx: &mut EventCtx, app: &mut A) {
+    ctx.loading_screen("rerendering buildings", |ctx, timer| {
+        let mut all_buildings = GeomBatch::new();
+        let mut all_building_paths = GeomBatch::new();
+        let mut all_building_outlines = GeomBatch::new();
+        timer.start_iter("rendering buildings", app.map().all_buildings().len());
+        for b in app.map().all_buildings() {
+            timer.next();
+            DrawBuilding::new(
+                ctx,
+                b,
+                app.map(),
+                app.cs(),
+                app.opts(),
+                &mut all_buildings,
+                &mut all_building_paths,
+                &mut all_building_outlines,
+            );
+        }
+        timer.start("upload geometry");
+        app.mut_draw_map().draw_all_buildings = all_buildings.upload(ctx);
+        app.mut_draw_map().draw_all_building_paths = all_building_paths.upload(ctx);
+        app.mut_draw_map().draw_all_building_outlines = all_building_outlines.upload(ctx);
+        timer.stop("upload geometry");
+    });
+}
@@ -14,6 +14,10 @@ use crate::render::{
 };
 use crate::AppLike;
 
+/// When more agents than this are visible at once, draw them without details, to keep the UI
+/// responsive.
+const MAX_DETAILED_AGENTS: usize = 1000;
+
 pub struct AgentCache {
     /// This is controlled almost entirely by the minimap panel. It has no meaning in edit mode.
     pub unzoomed_agents: UnzoomedAgents,
@@ -21,6 +25,8 @@ pub struct AgentCache {
     // This time applies to agents_per_on. unzoomed has its own possibly separate Time!
     time: Option<Time>,
     agents_per_on: HashMap<Traversable, Vec<Box<dyn Renderable>>>,
+    // Are agents in agents_per_on drawn without details?
+    simple: bool,
     // when either of (time, unzoomed agent filters) change, recalculate (a quadtree of all agents,
     // draw all agents)
     unzoomed: Option<(Time, UnzoomedAgents, QuadTree<AgentID>, Drawable)>,
@@ -32,6 +38,7 @@ impl AgentCache {
             unzoomed_agents: UnzoomedAgents::new(cs),
             time: None,
             agents_per_on: HashMap::new(),
+            simple: false,
             unzoomed: None,
         }
     }
@@ -59,13 +66,17 @@ impl AgentCache {
 
         let mut list: Vec<Box<dyn Renderable>> = Vec::new();
         for c in sim.get_draw_cars(on, map).into_iter() {
-            list.push(draw_vehicle(c, map, prerender, cs));
+            list.push(draw_vehicle(c, map, prerender, cs, self.simple));
         }
         let (loners, crowds) = sim.get_draw_peds(on, map);
         for p in loners {
-            list.push(Box::new(DrawPedestrian::new(
-                p, step_count, map, prerender, cs,
-            )));
+            if self.simple {
+                list.push(Box::new(DrawPedestrian::new_simple(p, map, prerender, cs)));
+            } else {
+                list.push(Box::new(DrawPedestrian::new(
+                    p, step_count, map, prerender, cs,
+                )));
+            }
         }
         for c in crowds {
             list.push(Box::new(DrawPedCrowd::new(c, map, prerender, cs)));
@@ -79,6 +90,19 @@ impl AgentCache {
         self.agents_per_on.insert(on, list);
     }
 
+    /// After populating everything visible, pass in how many agents that was. If the level of
+    /// detail needs to change, everything is cleared and this returns true; the caller should
+    /// populate again.
+    pub fn set_num_visible(&mut self, num: usize) -> bool {
+        let simple = num > MAX_DETAILED_AGENTS;
+        if simple == self.simple {
+            return false;
+        }
+        self.simple = simple;
+        self.agents_per_on.clear();
+        true
+    }
+
     /// If the sim time has changed or the unzoomed agent filters have been modified, recalculate
     /// the quadtree and drawable for all unzoomed agents.
     pub fn calculate_unzoomed_agents<P: AsRef<Prerender>>(
@@ -134,7 +158,7 @@ impl AgentCache {
             let mut cnt = 0;
             for input in app.sim().get_all_draw_cars(app.map()) {
                 cnt += 1;
-                draw_vehicle(input, app.map(), g.prerender, app.cs());
+                draw_vehicle(input, app.map(), g.prerender, app.cs(), false);
             }
             println!(
                 "At {}, debugged {} cars",
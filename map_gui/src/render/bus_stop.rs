@@ -1,5 +1,5 @@
 use geom::{Angle, Circle, Distance, Line, Polygon, Pt2D};
-use map_model::{BusStop, BusStopID, Map};
+use map_model::{BusStop, BusStopID, DrivingSide, Map};
 use widgetry::{Drawable, EventCtx, GeomBatch, GfxCtx};
 
 use crate::colors::ColorScheme;
@@ -19,10 +19,14 @@ pub struct DrawBusStop {
 impl DrawBusStop {
     pub fn new(ctx: &EventCtx, stop: &BusStop, map: &Map, cs: &ColorScheme) -> DrawBusStop {
         let (pt, angle) = stop.sidewalk_pos.pt_and_angle(map);
-        let center = pt.project_away(
-            map.get_l(stop.sidewalk_pos.lane()).width / 2.0,
-            angle.rotate_degs(90.0),
-        );
+        // Sidewalks point the same way as the traffic next to them, so the outer edge is on the
+        // right when driving on the right, and on the left otherwise.
+        let outside = if map.get_config().driving_side == DrivingSide::Right {
+            angle.rotate_degs(90.0)
+        } else {
+            angle.rotate_degs(-90.0)
+        };
+        let center = pt.project_away(map.get_l(stop.sidewalk_pos.lane()).width / 2.0, outside);
 
         let mut icon = GeomBatch::new();
         icon.append(
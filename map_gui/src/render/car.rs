@@ -8,6 +8,7 @@ use crate::render::{DrawOptions, Renderable, OUTLINE_THICKNESS};
 use crate::{AppLike, ID};
 
 const CAR_WIDTH: Distance = Distance::const_meters(1.75);
+const BIKE_WIDTH: Distance = Distance::const_meters(0.8);
 
 pub struct DrawCar {
     pub id: CarID,
@@ -162,6 +163,7 @@ impl DrawCar {
             }
         }
 
+        let zorder = body_zorder(&input, map);
         if let Some(line) = input.label {
             // If the vehicle is temporarily too short, just skip the label.
             if let Ok((pt, angle)) = input
@@ -178,15 +180,6 @@ impl DrawCar {
             }
         }
 
-        // TODO Technically some of the body may need to be at different zorders during
-        // transitions, but that's way too much effort
-        let zorder = input
-            .partly_on
-            .into_iter()
-            .chain(vec![input.on])
-            .map(|on| on.get_zorder(map))
-            .max()
-            .unwrap();
         DrawCar {
             id: input.id,
             body: input.body,
@@ -195,6 +188,43 @@ impl DrawCar {
             draw_default: prerender.upload(draw_default),
         }
     }
+
+    /// Just the body, without wheels, turn signals, brake lights, or labels. Used for bikes too,
+    /// when there are too many agents on screen to draw them in detail.
+    pub fn new_simple(
+        input: DrawCarInput,
+        map: &Map,
+        prerender: &Prerender,
+        cs: &ColorScheme,
+    ) -> DrawCar {
+        let width = if input.id.1 == VehicleType::Bike {
+            BIKE_WIDTH
+        } else {
+            CAR_WIDTH
+        };
+        let body_polygon = input.body.make_polygons(width);
+        let mut draw_default = GeomBatch::new();
+        draw_default.push(zoomed_color_car(&input, cs), body_polygon.clone());
+        DrawCar {
+            id: input.id,
+            zorder: body_zorder(&input, map),
+            body: input.body,
+            body_polygon,
+            draw_default: prerender.upload(draw_default),
+        }
+    }
+}
+
+// TODO Technically some of the body may need to be at different zorders during transitions, but
+// that's way too much effort
+fn body_zorder(input: &DrawCarInput, map: &Map) -> isize {
+    input
+        .partly_on
+        .iter()
+        .chain(vec![&input.on])
+        .map(|on| on.get_zorder(map))
+        .max()
+        .unwrap()
 }
 
 impl Renderable for DrawCar {
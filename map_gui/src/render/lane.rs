@@ -1,7 +1,7 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
 
-use geom::{Angle, ArrowCap, Distance, Line, PolyLine, Polygon, Pt2D};
+use geom::{Angle, ArrowCap, Distance, Line, PolyLine, Polygon, Pt2D, Ring};
 use map_model::{
     Direction, DrivingSide, Lane, LaneID, LaneType, Map, Road, RoadID, TurnID, PARKING_SPOT_LENGTH,
 };
@@ -76,8 +76,15 @@ impl DrawLane {
                 draw.extend(general_road_marking, calculate_driving_lines(lane, road));
                 draw.extend(general_road_marking, calculate_turn_markings(map, lane));
                 draw.extend(general_road_marking, calculate_one_way_markings(lane, road));
+                if let Some(bar) = calculate_stop_bar(map, lane) {
+                    draw.push(general_road_marking, bar);
+                }
+            }
+            LaneType::Biking => {
+                if let Some(bar) = calculate_stop_bar(map, lane) {
+                    draw.push(general_road_marking, bar);
+                }
             }
-            LaneType::Biking => {}
             LaneType::SharedLeftTurn => {
                 let thickness = Distance::meters(0.25);
                 draw.push(
@@ -178,6 +185,10 @@ impl DrawLane {
             }
         }
 
+        for (start, end, _) in road.work_zones_on(lane) {
+            draw_work_zone(&mut draw, g, app, lane, start, end);
+        }
+
         if road.is_private() {
             draw.push(app.cs().private_road.alpha(0.5), self.polygon.clone());
         }
@@ -190,6 +201,52 @@ impl DrawLane {
     }
 }
 
+/// Shade the closed part of the lane, narrowing to a point at the upstream end where drivers
+/// merge, with a sign before it.
+fn draw_work_zone(
+    draw: &mut GeomBatch,
+    g: &mut GfxCtx,
+    app: &dyn AppLike,
+    lane: &Lane,
+    start: Distance,
+    end: Distance,
+) {
+    let pl = &lane.lane_center_pts;
+    let start = start.max(Distance::ZERO);
+    let end = end.min(pl.length());
+    if end <= start {
+        return;
+    }
+    let color = app.cs().work_zone;
+    let taper = Distance::meters(15.0).min((end - start) / 2.0);
+    if let Ok(slice) = pl.maybe_exact_slice(start + taper, end) {
+        draw.push(color.alpha(0.7), slice.make_polygons(lane.width));
+    }
+    let (tip, _) = pl.must_dist_along(start);
+    let (base, angle) = pl.must_dist_along(start + taper);
+    let half = lane.width / 2.0;
+    if let Ok(ring) = Ring::new(vec![
+        tip,
+        base.project_away(half, angle.rotate_degs(90.0)),
+        base.project_away(half, angle.rotate_degs(-90.0)),
+        tip,
+    ]) {
+        draw.push(color.alpha(0.7), ring.to_polygon());
+    }
+
+    let sign_dist = start - Distance::meters(10.0);
+    if sign_dist > Distance::ZERO {
+        let (pt, angle) = pl.must_dist_along(sign_dist);
+        draw.append(
+            GeomBatch::load_svg(g.prerender, "system/assets/map/under_construction.svg")
+                .scale(0.05)
+                .rotate_around_batch_center(angle.shortest_rotation_towards(Angle::degrees(-90.0)))
+                .autocrop()
+                .centered_on(pt),
+        );
+    }
+}
+
 impl Renderable for DrawLane {
     fn get_id(&self) -> ID {
         ID::Lane(self.id)
@@ -314,21 +371,33 @@ fn calculate_turn_markings(map: &Map, lane: &Lane) -> Vec<Polygon> {
         return Vec::new();
     }
 
-    // Does this lane connect to every other possible outbound lane of the same type, excluding
-    // U-turns to the same road? If so, then there's nothing unexpected to communicate.
+    // Does every lane approaching the intersection alongside this one connect to every other
+    // possible outbound lane of the same type, excluding U-turns to the same road? If so, then
+    // there's nothing unexpected to communicate. If any one of them is restricted, mark all of
+    // them, so drivers can tell which lane to pick.
     let i = map.get_i(lane.dst_i);
-    if i.outgoing_lanes.iter().all(|l| {
-        let l = map.get_l(*l);
-        l.lane_type != lane.lane_type
-            || l.parent == lane.parent
-            || map
-                .maybe_get_t(TurnID {
-                    parent: i.id,
-                    src: lane.id,
-                    dst: l.id,
-                })
-                .is_some()
-    }) {
+    let parent = map.get_r(lane.parent);
+    let dir = parent.dir(lane.id);
+    let connects_everywhere = |src: LaneID| {
+        i.outgoing_lanes.iter().all(|l| {
+            let l = map.get_l(*l);
+            l.lane_type != lane.lane_type
+                || l.parent == lane.parent
+                || map
+                    .maybe_get_t(TurnID {
+                        parent: i.id,
+                        src,
+                        dst: l.id,
+                    })
+                    .is_some()
+        })
+    };
+    if parent
+        .lanes_ltr()
+        .into_iter()
+        .filter(|(_, d, lt)| *d == dir && *lt == lane.lane_type)
+        .all(|(l, _, _)| connects_everywhere(l))
+    {
         return Vec::new();
     }
 
@@ -366,6 +435,33 @@ fn calculate_turn_markings(map: &Map, lane: &Lane) -> Vec<Polygon> {
     results
 }
 
+/// A line across the end of the lane, where vehicles wait for a traffic signal or stop sign.
+fn calculate_stop_bar(map: &Map, lane: &Lane) -> Option<Polygon> {
+    let thickness = Distance::meters(0.5);
+    if lane.length() < thickness * 2.0 {
+        return None;
+    }
+    let i = map.get_i(lane.dst_i);
+    let must_stop = if i.is_traffic_signal() {
+        true
+    } else if let Some(ss) = map.maybe_get_stop_sign(i.id) {
+        ss.roads
+            .get(&lane.parent)
+            .map(|road| road.must_stop)
+            .unwrap_or(false)
+    } else {
+        false
+    };
+    if !must_stop {
+        return None;
+    }
+    Some(
+        lane.lane_center_pts
+            .exact_slice(lane.length() - thickness, lane.length())
+            .make_polygons(lane.width),
+    )
+}
+
 fn calculate_one_way_markings(lane: &Lane, parent: &Road) -> Vec<Polygon> {
     let mut results = Vec::new();
     let lanes = parent.lanes_ltr();
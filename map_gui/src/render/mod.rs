@@ -10,6 +10,7 @@ pub use crate::render::agents::{AgentCache, UnzoomedAgents};
 pub use crate::render::area::DrawArea;
 use crate::render::bike::DrawBike;
 pub use crate::render::building::DrawBuilding;
+pub use crate::render::bus_stop::DrawBusStop;
 use crate::render::car::DrawCar;
 pub use crate::render::intersection::{calculate_corners, DrawIntersection};
 pub use crate::render::map::DrawMap;
@@ -63,8 +64,11 @@ fn draw_vehicle(
     map: &Map,
     prerender: &Prerender,
     cs: &ColorScheme,
+    simple: bool,
 ) -> Box<dyn Renderable> {
-    if input.id.1 == VehicleType::Bike {
+    if simple {
+        Box::new(DrawCar::new_simple(input, map, prerender, cs))
+    } else if input.id.1 == VehicleType::Bike {
         Box::new(DrawBike::new(input, map, prerender, cs))
     } else {
         Box::new(DrawCar::new(input, map, prerender, cs))
@@ -50,6 +50,34 @@ impl DrawPedestrian {
         }
     }
 
+    /// Just a dot, without feet, hands, or a head. Used when there are too many agents on screen
+    /// to draw them in detail.
+    pub fn new_simple(
+        input: DrawPedestrianInput,
+        map: &Map,
+        prerender: &Prerender,
+        cs: &ColorScheme,
+    ) -> DrawPedestrian {
+        let radius = SIDEWALK_THICKNESS / 4.0;
+        let body_circle = Circle::new(input.pos, radius);
+        let mut draw_default = GeomBatch::new();
+        draw_default.push(
+            if input.preparing_bike {
+                cs.ped_preparing_bike_body
+            } else {
+                cs.rotating_color_agents(input.id.0)
+            },
+            body_circle.to_polygon(),
+        );
+
+        DrawPedestrian {
+            id: input.id,
+            body_circle,
+            zorder: input.on.get_zorder(map),
+            draw_default: prerender.upload(draw_default),
+        }
+    }
+
     pub fn geometry(
         batch: &mut GeomBatch,
         cs: &ColorScheme,
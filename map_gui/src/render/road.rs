@@ -1,7 +1,7 @@
 use std::cell::RefCell;
 
-use geom::{Distance, Polygon, Pt2D};
-use map_model::{LaneType, Map, Road, RoadID};
+use geom::{Circle, Distance, Polygon, Pt2D};
+use map_model::{DrivingSide, LaneType, Map, Road, RoadID, TrafficCalmingKind};
 use widgetry::{Drawable, GeomBatch, GfxCtx, Line, Text};
 
 use crate::render::{DrawOptions, Renderable};
@@ -75,6 +75,49 @@ impl Renderable for DrawRoad {
                     }
                 }
             }
+
+            let half_width = r.get_half_width(app.map());
+            for tc in &r.traffic_calming {
+                let (pt, angle) = r.center_pts.must_dist_along(tc.dist_along);
+                let left = angle.rotate_degs(-90.0);
+                let right = angle.rotate_degs(90.0);
+                if tc.kind == TrafficCalmingKind::Chicane {
+                    // Two curb extensions from opposite sides, each a third of the way across
+                    let len = tc.kind.length() / 4.0;
+                    for (along, side) in &[(angle.opposite(), left), (angle, right)] {
+                        let edge = pt.project_away(len, *along).project_away(half_width, *side);
+                        batch.push(
+                            color,
+                            geom::Line::must_new(
+                                edge,
+                                edge.project_away(half_width * 2.0 / 3.0, side.opposite()),
+                            )
+                            .make_polygons(len),
+                        );
+                    }
+                } else if tc.kind == TrafficCalmingKind::SpeedCamera {
+                    // Just a post at the curb, on the side that traffic drives
+                    let curb = if app.map().get_config().driving_side == DrivingSide::Right {
+                        right
+                    } else {
+                        left
+                    };
+                    batch.push(
+                        color,
+                        Circle::new(pt.project_away(half_width, curb), Distance::meters(1.0))
+                            .to_polygon(),
+                    );
+                } else {
+                    batch.push(
+                        color.alpha(0.5),
+                        geom::Line::must_new(
+                            pt.project_away(half_width, left),
+                            pt.project_away(half_width, right),
+                        )
+                        .make_polygons(tc.kind.length()),
+                    );
+                }
+            }
             *draw_center_line = Some(g.prerender.upload(batch));
         }
         g.redraw(draw_center_line.as_ref().unwrap());
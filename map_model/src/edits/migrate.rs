@@ -0,0 +1,376 @@
+use std::collections::HashMap;
+
+use geom::{Distance, FindClosest, Pt2D};
+
+use crate::edits::perma::{PermanentEditCmd, PermanentEditIntersection};
+use crate::raw::OriginalRoad;
+use crate::{osm, BuildingID, IntersectionID, Map, PermanentMapEdits};
+
+// How far something can move between imports and still be considered the same thing
+const MAX_DRIFT: Distance = Distance::const_meters(10.0);
+
+/// When a map is imported again from newer OSM data, anything saved against the old version might
+/// refer to roads, intersections, or buildings that changed. This matches things from the old map
+/// to the new one, first by OSM ID and then by position.
+pub struct MapMigration<'a> {
+    old: &'a Map,
+    new: &'a Map,
+    bldgs_by_osm: HashMap<osm::OsmID, BuildingID>,
+    intersections_by_osm: HashMap<osm::NodeID, IntersectionID>,
+    closest_bldg: FindClosest<BuildingID>,
+    closest_border: FindClosest<IntersectionID>,
+}
+
+/// What happened while migrating one file.
+#[derive(Default)]
+pub struct MigrationReport {
+    /// Still valid without any changes
+    pub unchanged: usize,
+    /// Fixed automatically, with a description of each fix
+    pub migrated: Vec<String>,
+    /// Couldn't be fixed and was removed; somebody should look at these
+    pub needs_attention: Vec<String>,
+}
+
+impl MigrationReport {
+    pub fn is_clean(&self) -> bool {
+        self.migrated.is_empty() && self.needs_attention.is_empty()
+    }
+
+    /// A summary line, then one line per migrated or removed thing.
+    pub fn describe(&self, label: &str) -> Vec<String> {
+        let mut lines = vec![format!(
+            "{}: {} unchanged, {} migrated, {} need attention",
+            label,
+            self.unchanged,
+            self.migrated.len(),
+            self.needs_attention.len()
+        )];
+        for x in &self.migrated {
+            lines.push(format!("  migrated: {}", x));
+        }
+        for x in &self.needs_attention {
+            lines.push(format!("  NEEDS ATTENTION: {}", x));
+        }
+        lines
+    }
+}
+
+impl<'a> MapMigration<'a> {
+    pub fn new(old: &'a Map, new: &'a Map) -> MapMigration<'a> {
+        let mut closest_bldg = FindClosest::new(new.get_bounds());
+        for b in new.all_buildings() {
+            closest_bldg.add(b.id, b.polygon.points());
+        }
+        let mut closest_border = FindClosest::new(new.get_bounds());
+        for i in new.all_intersections() {
+            if i.is_border() {
+                closest_border.add(i.id, i.polygon.points());
+            }
+        }
+        MapMigration {
+            old,
+            new,
+            bldgs_by_osm: new
+                .all_buildings()
+                .iter()
+                .map(|b| (b.orig_id, b.id))
+                .collect(),
+            intersections_by_osm: new
+                .all_intersections()
+                .iter()
+                .map(|i| (i.orig_id, i.id))
+                .collect(),
+            closest_bldg,
+            closest_border,
+        }
+    }
+
+    /// Converts a point in the old map to the same place in the new one. The map boundaries may
+    /// have changed.
+    fn translate(&self, pt: Pt2D) -> Pt2D {
+        pt.to_gps(self.old.get_gps_bounds())
+            .to_pt(self.new.get_gps_bounds())
+    }
+
+    /// A building in the old map that's also in the new one, by OSM ID or position.
+    pub fn building(&self, b: BuildingID) -> Result<BuildingID, String> {
+        let old = self
+            .old
+            .maybe_get_b(b)
+            .ok_or_else(|| format!("{} isn't in the old map", b))?;
+        if let Some(id) = self.bldgs_by_osm.get(&old.orig_id) {
+            return Ok(*id);
+        }
+        self.closest_bldg
+            .closest_pt(self.translate(old.polygon.center()), MAX_DRIFT)
+            .map(|(id, _)| id)
+            .ok_or_else(|| format!("{} ({}) isn't in the new map", b, old.orig_id))
+    }
+
+    /// A border intersection in the old map that's also a border in the new one, by OSM ID or
+    /// position.
+    pub fn border(&self, i: IntersectionID) -> Result<IntersectionID, String> {
+        if i.0 >= self.old.all_intersections().len() {
+            return Err(format!("{} isn't in the old map", i));
+        }
+        let old = self.old.get_i(i);
+        if let Some(id) = self.intersections_by_osm.get(&old.orig_id) {
+            if self.new.get_i(*id).is_border() {
+                return Ok(*id);
+            }
+        }
+        self.closest_border
+            .closest_pt(self.translate(old.polygon.center()), MAX_DRIFT)
+            .map(|(id, _)| id)
+            .ok_or_else(|| format!("border {} ({}) isn't in the new map", i, old.orig_id))
+    }
+
+    fn osm_intersection(&self, node: osm::NodeID) -> Result<osm::NodeID, String> {
+        if self.new.find_i_by_osm_id(node).is_ok() {
+            return Ok(node);
+        }
+        let old = self.old.get_i(self.old.find_i_by_osm_id(node)?);
+        let pt = self.translate(old.polygon.center());
+        self.new
+            .all_intersections()
+            .iter()
+            .filter(|i| i.polygon.center().dist_to(pt) <= MAX_DRIFT)
+            .min_by(|a, b| {
+                a.polygon
+                    .center()
+                    .dist_to(pt)
+                    .partial_cmp(&b.polygon.center().dist_to(pt))
+                    .unwrap()
+            })
+            .map(|i| i.orig_id)
+            .ok_or_else(|| format!("nothing near intersection {} in the new map", node))
+    }
+
+    /// Roads are matched by their endpoints, in the same direction, since edits to lanes depend on
+    /// the direction.
+    fn osm_road(&self, r: OriginalRoad) -> Result<OriginalRoad, String> {
+        if self.new.find_r_by_osm_id(r).is_ok() {
+            return Ok(r);
+        }
+        let old = self.old.get_r(self.old.find_r_by_osm_id(r)?);
+        let pt1 = self.translate(old.center_pts.first_pt());
+        let pt2 = self.translate(old.center_pts.last_pt());
+        self.new
+            .all_roads()
+            .iter()
+            .find(|road| {
+                road.center_pts.first_pt().dist_to(pt1) <= MAX_DRIFT
+                    && road.center_pts.last_pt().dist_to(pt2) <= MAX_DRIFT
+            })
+            .map(|road| road.orig_id)
+            .ok_or_else(|| format!("{} was split, merged, or removed in the new map", r))
+    }
+
+    fn edit_cmd(&self, cmd: PermanentEditCmd) -> Result<PermanentEditCmd, String> {
+        Ok(match cmd {
+            PermanentEditCmd::ChangeRoad { r, new, old } => PermanentEditCmd::ChangeRoad {
+                r: self.osm_road(r)?,
+                new,
+                old,
+            },
+            PermanentEditCmd::ChangeIntersection { i, new, old } => {
+                PermanentEditCmd::ChangeIntersection {
+                    i: self.osm_intersection(i)?,
+                    new: self.edit_intersection(new)?,
+                    old: self.edit_intersection(old)?,
+                }
+            }
+            PermanentEditCmd::ChangeIntersectionCapacity { i, old, new } => {
+                PermanentEditCmd::ChangeIntersectionCapacity {
+                    i: self.osm_intersection(i)?,
+                    old,
+                    new,
+                }
+            }
+            PermanentEditCmd::ChangeCrosswalks { i, old, new } => {
+                PermanentEditCmd::ChangeCrosswalks {
+                    i: self.osm_intersection(i)?,
+                    old: old
+                        .into_iter()
+                        .map(|r| self.osm_road(r))
+                        .collect::<Result<_, String>>()?,
+                    new: new
+                        .into_iter()
+                        .map(|r| self.osm_road(r))
+                        .collect::<Result<_, String>>()?,
+                }
+            }
+            PermanentEditCmd::ChangeRouteDetours {
+                osm_rel_id,
+                old,
+                new,
+            } => PermanentEditCmd::ChangeRouteDetours {
+                osm_rel_id,
+                old: old
+                    .into_iter()
+                    .map(|r| self.osm_road(r))
+                    .collect::<Result<_, String>>()?,
+                new: new
+                    .into_iter()
+                    .map(|r| self.osm_road(r))
+                    .collect::<Result<_, String>>()?,
+            },
+            PermanentEditCmd::CreateRoute {
+                name,
+                stops,
+                spawn_times,
+                avoid_roads,
+            } => PermanentEditCmd::CreateRoute {
+                name,
+                stops,
+                spawn_times,
+                avoid_roads: avoid_roads
+                    .into_iter()
+                    .map(|r| self.osm_road(r))
+                    .collect::<Result<_, String>>()?,
+            },
+            // Bus routes are only identified by their OSM relation, and stops are already stored
+            // by position
+            PermanentEditCmd::ChangeRouteSchedule { .. }
+            | PermanentEditCmd::ChangeRouteStops { .. } => cmd,
+            PermanentEditCmd::Bulk { cmds } => PermanentEditCmd::Bulk {
+                cmds: cmds
+                    .into_iter()
+                    .map(|cmd| self.edit_cmd(cmd))
+                    .collect::<Result<_, String>>()?,
+            },
+        })
+    }
+
+    fn edit_intersection(
+        &self,
+        edit: PermanentEditIntersection,
+    ) -> Result<PermanentEditIntersection, String> {
+        Ok(match edit {
+            PermanentEditIntersection::StopSign { must_stop } => {
+                PermanentEditIntersection::StopSign {
+                    must_stop: must_stop
+                        .into_iter()
+                        .map(|(r, stop)| Ok((self.osm_road(r)?, stop)))
+                        .collect::<Result<_, String>>()?,
+                }
+            }
+            x => x,
+        })
+    }
+
+    /// Keeps the edits that still work on the new map, and tries to fix the ones that don't.
+    /// Anything that can't be fixed is removed.
+    pub fn edits(&self, mut edits: PermanentMapEdits) -> (PermanentMapEdits, MigrationReport) {
+        let mut report = MigrationReport::default();
+        let mut commands = Vec::new();
+        // Later commands may refer to routes and stops that earlier ones create
+        let mut converted = Vec::new();
+        for cmd in edits.commands.drain(..) {
+            let err = match cmd.clone().to_cmd(self.new, &converted) {
+                Ok(result) => {
+                    report.unchanged += 1;
+                    commands.push(cmd);
+                    converted.push(result);
+                    continue;
+                }
+                Err(err) => err,
+            };
+            let description = cmd.describe();
+            match self.edit_cmd(cmd).and_then(|fixed| {
+                fixed
+                    .clone()
+                    .to_cmd(self.new, &converted)
+                    .map(|result| (fixed, result))
+            }) {
+                Ok((fixed, result)) => {
+                    report
+                        .migrated
+                        .push(format!("{} (was: {})", description, err));
+                    commands.push(fixed);
+                    converted.push(result);
+                }
+                Err(why) => {
+                    report
+                        .needs_attention
+                        .push(format!("{}: {}", description, why));
+                }
+            }
+        }
+        edits.commands = commands;
+
+        let mut road_notes = Vec::new();
+        for (r, note) in edits.road_notes.drain(..) {
+            match self.osm_road(r) {
+                Ok(fixed) => {
+                    if fixed == r {
+                        report.unchanged += 1;
+                    } else {
+                        report
+                            .migrated
+                            .push(format!("note on road {} moved to {}", r, fixed));
+                    }
+                    road_notes.push((fixed, note));
+                }
+                Err(why) => {
+                    report
+                        .needs_attention
+                        .push(format!("note on road {}: {}", r, why));
+                }
+            }
+        }
+        edits.road_notes = road_notes;
+
+        let mut intersection_notes = Vec::new();
+        for (i, note) in edits.intersection_notes.drain(..) {
+            match self.osm_intersection(i) {
+                Ok(fixed) => {
+                    if fixed == i {
+                        report.unchanged += 1;
+                    } else {
+                        report
+                            .migrated
+                            .push(format!("note on intersection {} moved to {}", i, fixed));
+                    }
+                    intersection_notes.push((fixed, note));
+                }
+                Err(why) => {
+                    report
+                        .needs_attention
+                        .push(format!("note on intersection {}: {}", i, why));
+                }
+            }
+        }
+        edits.intersection_notes = intersection_notes;
+
+        edits.map_name = self.new.get_name().clone();
+        (edits, report)
+    }
+}
+
+impl PermanentEditCmd {
+    fn describe(&self) -> String {
+        match self {
+            PermanentEditCmd::ChangeRoad { r, .. } => format!("change road {}", r),
+            PermanentEditCmd::ChangeIntersection { i, .. } => format!("change intersection {}", i),
+            PermanentEditCmd::ChangeRouteSchedule { osm_rel_id, .. } => {
+                format!("change schedule of route {}", osm_rel_id)
+            }
+            PermanentEditCmd::ChangeRouteStops { osm_rel_id, .. } => {
+                format!("change stops of route {}", osm_rel_id)
+            }
+            PermanentEditCmd::ChangeRouteDetours { osm_rel_id, .. } => {
+                format!("change detours of route {}", osm_rel_id)
+            }
+            PermanentEditCmd::ChangeIntersectionCapacity { i, .. } => {
+                format!("change capacity of intersection {}", i)
+            }
+            PermanentEditCmd::ChangeCrosswalks { i, .. } => {
+                format!("change crosswalks at intersection {}", i)
+            }
+            PermanentEditCmd::CreateRoute { name, .. } => format!("create route {}", name),
+            PermanentEditCmd::Bulk { cmds } => format!("{} changes at once", cmds.len()),
+        }
+    }
+}
@@ -4,20 +4,23 @@
 
 use std::collections::{BTreeMap, BTreeSet};
 
+pub use migrate::{MapMigration, MigrationReport};
 pub use perma::PermanentMapEdits;
 use serde::{Deserialize, Serialize};
 
-use abstutil::{retain_btreemap, retain_btreeset, Timer};
+use abstutil::{retain_btreemap, retain_btreeset, Tags, Timer};
 use geom::{Speed, Time};
 
 use crate::make::initial::lane_specs::get_lane_specs_ltr;
 use crate::{
-    connectivity, AccessRestrictions, BusRouteID, ControlStopSign, ControlTrafficSignal, Direction,
-    IntersectionID, IntersectionType, LaneID, LaneType, Map, MapConfig, PathConstraints,
-    Pathfinder, Road, RoadID, TurnID, Zone,
+    connectivity, AccessRestrictions, BusRoute, BusRouteID, BusStop, BusStopID, ControlStopSign,
+    ControlTrafficSignal, Direction, IntersectionCapacity, IntersectionID, IntersectionType,
+    LaneID, LaneType, Map, MapConfig, PathConstraints, Pathfinder, Road, RoadID, TrafficCalming,
+    TurnID, WorkZone, Zone,
 };
 
 mod compat;
+mod migrate;
 mod perma;
 
 /// Represents changes to a map. Note this isn't serializable -- that's what `PermanentMapEdits`
@@ -37,11 +40,29 @@ pub struct MapEdits {
     pub changed_roads: BTreeSet<RoadID>,
     pub original_intersections: BTreeMap<IntersectionID, EditIntersection>,
     pub changed_routes: BTreeSet<BusRouteID>,
+    /// Routes that don't exist in the original map, with the stops each one introduced
+    pub created_routes: BTreeMap<BusRouteID, Vec<BusStop>>,
+    pub changed_capacities: BTreeSet<IntersectionID>,
+    pub changed_crosswalks: BTreeSet<IntersectionID>,
 
     /// Some edits are included in the game by default, in data/system/proposals, as "community
     /// proposals." They require a description and may have a link to a write-up.
     pub proposal_description: Vec<String>,
     pub proposal_link: Option<String>,
+
+    /// Notes explaining why parts of the proposal were changed. Notes don't affect the map, and
+    /// they can be attached to something that wasn't edited at all.
+    pub road_notes: BTreeMap<RoadID, EditNote>,
+    pub intersection_notes: BTreeMap<IntersectionID, EditNote>,
+}
+
+/// Some text attached to a road or intersection in a proposal, so reviewers can understand why
+/// something changed there.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EditNote {
+    pub text: String,
+    /// Photos or write-ups elsewhere
+    pub links: Vec<String>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -58,17 +79,30 @@ pub struct EditRoad {
     pub lanes_ltr: Vec<(LaneType, Direction)>,
     pub speed_limit: Speed,
     pub access_restrictions: AccessRestrictions,
+    // Edits saved before traffic calming existed don't have this
+    #[serde(default)]
+    pub traffic_calming: Vec<TrafficCalming>,
+    #[serde(default)]
+    pub work_zones: Vec<WorkZone>,
 }
 
 impl EditRoad {
     pub fn get_orig_from_osm(r: &Road, cfg: &MapConfig) -> EditRoad {
+        EditRoad::from_osm_tags(&r.osm_tags, cfg)
+    }
+
+    /// How a road with these OSM tags would be configured, to preview corrections to its tags.
+    pub fn from_osm_tags(tags: &Tags, cfg: &MapConfig) -> EditRoad {
         EditRoad {
-            lanes_ltr: get_lane_specs_ltr(&r.osm_tags, cfg)
+            lanes_ltr: get_lane_specs_ltr(tags, cfg)
                 .into_iter()
                 .map(|spec| (spec.lt, spec.dir))
                 .collect(),
-            speed_limit: r.speed_limit_from_osm(),
-            access_restrictions: r.access_restrictions_from_osm(),
+            speed_limit: Road::speed_limit_from_osm(tags),
+            access_restrictions: Road::access_restrictions_from_osm(tags),
+            // TODO Import traffic_calming from OSM nodes
+            traffic_calming: Vec::new(),
+            work_zones: Vec::new(),
         }
     }
 
@@ -101,6 +135,12 @@ impl EditRoad {
         if self.access_restrictions != other.access_restrictions {
             changes.push(format!("access restrictions"));
         }
+        if self.traffic_calming != other.traffic_calming {
+            changes.push(format!("traffic calming"));
+        }
+        if self.work_zones != other.work_zones {
+            changes.push("work zones".to_string());
+        }
         changes
     }
 }
@@ -122,6 +162,41 @@ pub enum EditCmd {
         old: Vec<Time>,
         new: Vec<Time>,
     },
+    ChangeRouteStops {
+        id: BusRouteID,
+        old: Vec<BusStopID>,
+        new: Vec<BusStopID>,
+    },
+    /// The roads that a route detours around
+    ChangeRouteDetours {
+        id: BusRouteID,
+        old: BTreeSet<RoadID>,
+        new: BTreeSet<RoadID>,
+    },
+    ChangeIntersectionCapacity {
+        i: IntersectionID,
+        old: IntersectionCapacity,
+        new: IntersectionCapacity,
+    },
+    /// The roads that pedestrians can't cross at an intersection
+    ChangeCrosswalks {
+        i: IntersectionID,
+        old: BTreeSet<RoadID>,
+        new: BTreeSet<RoadID>,
+    },
+    /// A brand new route, along with the stops it introduces. Build this with
+    /// `Map::create_bus_route_cmd`.
+    CreateRoute {
+        route: BusRoute,
+        new_stops: Vec<BusStop>,
+    },
+    /// Only produced by undoing `CreateRoute`, so it's never kept in `MapEdits`
+    DeleteRoute {
+        route: BusRoute,
+        new_stops: Vec<BusStop>,
+    },
+    /// Many changes made at once, like to every road in a corridor. These're undone together.
+    Bulk { cmds: Vec<EditCmd> },
 }
 
 pub struct EditEffects {
@@ -137,12 +212,17 @@ impl MapEdits {
             edits_name: "TODO temporary".to_string(),
             proposal_description: Vec::new(),
             proposal_link: None,
+            road_notes: BTreeMap::new(),
+            intersection_notes: BTreeMap::new(),
             commands: Vec::new(),
             merge_zones: true,
 
             changed_roads: BTreeSet::new(),
             original_intersections: BTreeMap::new(),
             changed_routes: BTreeSet::new(),
+            created_routes: BTreeMap::new(),
+            changed_capacities: BTreeSet::new(),
+            changed_crosswalks: BTreeSet::new(),
         }
     }
 
@@ -162,7 +242,7 @@ impl MapEdits {
 
     fn save(&self, map: &Map) {
         // If untitled and empty, don't actually save anything.
-        if self.edits_name.starts_with("Untitled Proposal") && self.commands.is_empty() {
+        if self.edits_name.starts_with("Untitled Proposal") && self.is_empty() {
             return;
         }
 
@@ -172,12 +252,20 @@ impl MapEdits {
         );
     }
 
+    /// No commands or notes
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty() && self.road_notes.is_empty() && self.intersection_notes.is_empty()
+    }
+
     fn update_derived(&mut self, map: &Map) {
         self.changed_roads.clear();
         self.original_intersections.clear();
         self.changed_routes.clear();
+        self.created_routes.clear();
+        self.changed_capacities.clear();
+        self.changed_crosswalks.clear();
 
-        for cmd in &self.commands {
+        for cmd in self.commands.iter().flat_map(|cmd| cmd.flatten()) {
             match cmd {
                 EditCmd::ChangeRoad { r, .. } => {
                     self.changed_roads.insert(*r);
@@ -187,9 +275,25 @@ impl MapEdits {
                         self.original_intersections.insert(*i, old.clone());
                     }
                 }
-                EditCmd::ChangeRouteSchedule { id, .. } => {
-                    self.changed_routes.insert(*id);
+                EditCmd::ChangeRouteSchedule { id, .. }
+                | EditCmd::ChangeRouteStops { id, .. }
+                | EditCmd::ChangeRouteDetours { id, .. } => {
+                    // Later changes to a new route get folded into creating it
+                    if !self.created_routes.contains_key(id) {
+                        self.changed_routes.insert(*id);
+                    }
+                }
+                EditCmd::CreateRoute { route, new_stops } => {
+                    self.created_routes.insert(route.id, new_stops.clone());
+                }
+                EditCmd::DeleteRoute { .. } => unreachable!(),
+                EditCmd::ChangeIntersectionCapacity { i, .. } => {
+                    self.changed_capacities.insert(*i);
                 }
+                EditCmd::ChangeCrosswalks { i, .. } => {
+                    self.changed_crosswalks.insert(*i);
+                }
+                EditCmd::Bulk { .. } => unreachable!(),
             }
         }
 
@@ -202,11 +306,30 @@ impl MapEdits {
         retain_btreeset(&mut self.changed_routes, |br| {
             let r = map.get_br(*br);
             r.spawn_times != r.orig_spawn_times
+                || r.stops != r.orig_stops
+                || !r.avoid_roads.is_empty()
+        });
+        retain_btreeset(&mut self.changed_capacities, |i| {
+            map.get_i(*i).capacity != IntersectionCapacity::default()
+        });
+        retain_btreeset(&mut self.changed_crosswalks, |i| {
+            let i = map.get_i(*i);
+            i.no_crosswalks != i.orig_no_crosswalks
         });
     }
 
     /// Assumes update_derived has been called.
     pub fn compress(&mut self, map: &Map) {
+        // Other commands may refer to the new routes and stops, so create them first.
+        for (id, new_stops) in &self.created_routes {
+            let mut route = map.get_br(*id).clone();
+            route.orig_stops = route.stops.clone();
+            route.orig_spawn_times = route.spawn_times.clone();
+            self.commands.push(EditCmd::CreateRoute {
+                route,
+                new_stops: new_stops.clone(),
+            });
+        }
         for r in &self.changed_roads {
             self.commands.push(EditCmd::ChangeRoad {
                 r: *r,
@@ -223,10 +346,41 @@ impl MapEdits {
         }
         for r in &self.changed_routes {
             let r = map.get_br(*r);
-            self.commands.push(EditCmd::ChangeRouteSchedule {
-                id: r.id,
-                new: r.spawn_times.clone(),
-                old: r.orig_spawn_times.clone(),
+            if r.spawn_times != r.orig_spawn_times {
+                self.commands.push(EditCmd::ChangeRouteSchedule {
+                    id: r.id,
+                    new: r.spawn_times.clone(),
+                    old: r.orig_spawn_times.clone(),
+                });
+            }
+            if r.stops != r.orig_stops {
+                self.commands.push(EditCmd::ChangeRouteStops {
+                    id: r.id,
+                    new: r.stops.clone(),
+                    old: r.orig_stops.clone(),
+                });
+            }
+            if !r.avoid_roads.is_empty() {
+                self.commands.push(EditCmd::ChangeRouteDetours {
+                    id: r.id,
+                    new: r.avoid_roads.clone(),
+                    old: BTreeSet::new(),
+                });
+            }
+        }
+        for i in &self.changed_capacities {
+            self.commands.push(EditCmd::ChangeIntersectionCapacity {
+                i: *i,
+                old: IntersectionCapacity::default(),
+                new: map.get_i(*i).capacity.clone(),
+            });
+        }
+        for i in &self.changed_crosswalks {
+            let i = map.get_i(*i);
+            self.commands.push(EditCmd::ChangeCrosswalks {
+                i: i.id,
+                old: i.orig_no_crosswalks.clone(),
+                new: i.no_crosswalks.clone(),
             });
         }
     }
@@ -241,6 +395,8 @@ impl MapEdits {
             // What exactly changed?
             if r.speed_limit != orig.speed_limit
                 || r.access_restrictions != orig.access_restrictions
+                || r.traffic_calming != orig.traffic_calming
+                || r.work_zones != orig.work_zones
             {
                 roads.insert(r.id);
             } else {
@@ -291,10 +447,72 @@ impl EditCmd {
             EditCmd::ChangeRouteSchedule { id, .. } => {
                 format!("reschedule route {}", map.get_br(*id).short_name)
             }
+            EditCmd::ChangeRouteStops { id, old, new } => {
+                let old: BTreeSet<&BusStopID> = old.iter().collect();
+                let new: BTreeSet<&BusStopID> = new.iter().collect();
+                for bs in new.difference(&old) {
+                    details.push(format!("serve {}", map.get_bs(**bs).name));
+                }
+                for bs in old.difference(&new) {
+                    details.push(format!("skip {}", map.get_bs(**bs).name));
+                }
+                format!("reroute route {}", map.get_br(*id).short_name)
+            }
+            EditCmd::ChangeRouteDetours { id, old, new } => {
+                for r in new.difference(old) {
+                    details.push(format!("avoid {}", map.get_r(*r).get_name(None)));
+                }
+                for r in old.difference(new) {
+                    details.push(format!("use {} again", map.get_r(*r).get_name(None)));
+                }
+                format!("detour route {}", map.get_br(*id).short_name)
+            }
+            EditCmd::ChangeIntersectionCapacity { i, old, new } => {
+                details = new.diff(old);
+                format!("capacity of intersection #{}", i.0)
+            }
+            EditCmd::ChangeCrosswalks { i, old, new } => {
+                for r in old.difference(new) {
+                    details.push(format!(
+                        "add crosswalk over {}",
+                        map.get_r(*r).get_name(None)
+                    ));
+                }
+                for r in new.difference(old) {
+                    details.push(format!(
+                        "remove crosswalk over {}",
+                        map.get_r(*r).get_name(None)
+                    ));
+                }
+                format!("crosswalks at intersection #{}", i.0)
+            }
+            EditCmd::CreateRoute { route, new_stops } => {
+                details.push(format!(
+                    "{} stops, {} of them new",
+                    route.stops.len(),
+                    new_stops.len()
+                ));
+                format!("create route {}", route.short_name)
+            }
+            EditCmd::DeleteRoute { route, .. } => format!("delete route {}", route.short_name),
+            EditCmd::Bulk { cmds } => {
+                for cmd in cmds {
+                    details.push(cmd.describe(map).0);
+                }
+                format!("{} changes at once", cmds.len())
+            }
         };
         (summary, details)
     }
 
+    /// The individual changes, looking inside bulk edits
+    pub fn flatten(&self) -> Vec<&EditCmd> {
+        match self {
+            EditCmd::Bulk { cmds } => cmds.iter().flat_map(|cmd| cmd.flatten()).collect(),
+            _ => vec![self],
+        }
+    }
+
     // Must be idempotent
     fn apply(&self, effects: &mut EditEffects, map: &mut Map, timer: &mut Timer) {
         match self {
@@ -306,6 +524,8 @@ impl EditCmd {
                 let road = &mut map.roads[r.0];
                 road.speed_limit = new.speed_limit;
                 road.access_restrictions = new.access_restrictions.clone();
+                road.traffic_calming = new.traffic_calming.clone();
+                road.work_zones = new.work_zones.clone();
                 assert_eq!(road.lanes_ltr.len(), new.lanes_ltr.len());
                 for (idx, (lt, dir)) in new.lanes_ltr.clone().into_iter().enumerate() {
                     let lane = &mut map.lanes[(road.lanes_ltr[idx].0).0];
@@ -379,6 +599,40 @@ impl EditCmd {
             EditCmd::ChangeRouteSchedule { id, new, .. } => {
                 map.bus_routes[id.0].spawn_times = new.clone();
             }
+            EditCmd::ChangeRouteStops { id, new, .. } => {
+                map.bus_routes[id.0].stops = new.clone();
+            }
+            EditCmd::ChangeRouteDetours { id, new, .. } => {
+                map.bus_routes[id.0].avoid_roads = new.clone();
+            }
+            EditCmd::ChangeIntersectionCapacity { i, new, .. } => {
+                map.intersections[i.0].capacity = new.clone();
+            }
+            EditCmd::ChangeCrosswalks { i, new, .. } => {
+                if &map.get_i(*i).no_crosswalks == new {
+                    return;
+                }
+                map.intersections[i.0].no_crosswalks = new.clone();
+                effects.changed_intersections.insert(*i);
+                recalculate_turns(*i, map, effects, timer);
+            }
+            EditCmd::CreateRoute { route, new_stops } => {
+                if map.bus_routes.len() > route.id.0 {
+                    return;
+                }
+                map.insert_bus_route(route.clone(), new_stops.clone());
+            }
+            EditCmd::DeleteRoute { route, new_stops } => {
+                if map.bus_routes.len() <= route.id.0 {
+                    return;
+                }
+                map.delete_bus_route(route.id, new_stops);
+            }
+            EditCmd::Bulk { cmds } => {
+                for cmd in cmds {
+                    cmd.apply(effects, map, timer);
+                }
+            }
         }
     }
 
@@ -399,6 +653,33 @@ impl EditCmd {
                 old: new,
                 new: old,
             },
+            EditCmd::ChangeRouteStops { id, old, new } => EditCmd::ChangeRouteStops {
+                id,
+                old: new,
+                new: old,
+            },
+            EditCmd::ChangeRouteDetours { id, old, new } => EditCmd::ChangeRouteDetours {
+                id,
+                old: new,
+                new: old,
+            },
+            EditCmd::ChangeIntersectionCapacity { i, old, new } => {
+                EditCmd::ChangeIntersectionCapacity {
+                    i,
+                    old: new,
+                    new: old,
+                }
+            }
+            EditCmd::ChangeCrosswalks { i, old, new } => EditCmd::ChangeCrosswalks {
+                i,
+                old: new,
+                new: old,
+            },
+            EditCmd::CreateRoute { route, new_stops } => EditCmd::DeleteRoute { route, new_stops },
+            EditCmd::DeleteRoute { route, new_stops } => EditCmd::CreateRoute { route, new_stops },
+            EditCmd::Bulk { cmds } => EditCmd::Bulk {
+                cmds: cmds.into_iter().rev().map(|cmd| cmd.undo()).collect(),
+            },
         }
     }
 }
@@ -478,7 +759,7 @@ impl Map {
     }
 
     pub fn unsaved_edits(&self) -> bool {
-        self.edits.edits_name.starts_with("Untitled Proposal") && !self.edits.commands.is_empty()
+        self.edits.edits_name.starts_with("Untitled Proposal") && !self.edits.is_empty()
     }
 
     pub fn get_r_edit(&self, r: RoadID) -> EditRoad {
@@ -491,6 +772,8 @@ impl Map {
                 .collect(),
             speed_limit: r.speed_limit,
             access_restrictions: r.access_restrictions.clone(),
+            traffic_calming: r.traffic_calming.clone(),
+            work_zones: r.work_zones.clone(),
         }
     }
 
@@ -562,6 +845,19 @@ impl Map {
                 BTreeSet::new(),
             );
         }
+        // Changing only the name or notes doesn't affect anything else
+        if self.edits.commands == new_edits.commands
+            && self.edits.merge_zones == new_edits.merge_zones
+        {
+            new_edits.update_derived(self);
+            self.edits = new_edits;
+            return (
+                BTreeSet::new(),
+                BTreeSet::new(),
+                BTreeSet::new(),
+                BTreeSet::new(),
+            );
+        }
 
         let mut effects = EditEffects::new();
 
@@ -2,12 +2,16 @@ use std::collections::{BTreeMap, BTreeSet};
 
 use serde::{Deserialize, Serialize};
 
-use abstutil::{deserialize_btreemap, serialize_btreemap, MapName};
-use geom::Time;
+use abstutil::{deserialize_btreemap, serialize_btreemap, MapName, Timer};
+use geom::{Distance, HashablePt2D, LonLat, Time};
 
-use crate::edits::{EditCmd, EditIntersection, EditRoad, MapEdits};
+use crate::edits::{EditCmd, EditIntersection, EditNote, EditRoad, MapEdits};
+use crate::make::match_points_to_lanes;
 use crate::raw::OriginalRoad;
-use crate::{osm, ControlStopSign, IntersectionID, Map};
+use crate::{
+    osm, BusRouteID, BusStopID, ControlStopSign, IntersectionCapacity, IntersectionID, Map,
+    PathConstraints, Position,
+};
 
 /// MapEdits are converted to this before serializing. Referencing things like LaneID in a Map won't
 /// work if the basemap is rebuilt from new OSM data, so instead we use stabler OSM IDs that're less
@@ -17,7 +21,7 @@ pub struct PermanentMapEdits {
     pub map_name: MapName,
     pub edits_name: String,
     pub version: usize,
-    commands: Vec<PermanentEditCmd>,
+    pub(crate) commands: Vec<PermanentEditCmd>,
     /// If false, adjacent roads with the same AccessRestrictions will not be merged into the same
     /// Zone; every Road will be its own Zone. This is used to experiment with a per-road cap. Note
     /// this is a map-wide setting.
@@ -27,6 +31,12 @@ pub struct PermanentMapEdits {
     pub proposal_description: Vec<String>,
     /// The link is optional even for proposals
     pub proposal_link: Option<String>,
+
+    /// Added without bumping the version, since older edits just don't have any notes
+    #[serde(default)]
+    pub(crate) road_notes: Vec<(OriginalRoad, EditNote)>,
+    #[serde(default)]
+    pub(crate) intersection_notes: Vec<(osm::NodeID, EditNote)>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -59,6 +69,38 @@ pub enum PermanentEditCmd {
         old: Vec<Time>,
         new: Vec<Time>,
     },
+    /// Bus stops aren't stable across map rebuilds, so they're identified by their position.
+    ChangeRouteStops {
+        osm_rel_id: osm::RelationID,
+        old: Vec<LonLat>,
+        new: Vec<LonLat>,
+    },
+    ChangeRouteDetours {
+        osm_rel_id: osm::RelationID,
+        old: BTreeSet<OriginalRoad>,
+        new: BTreeSet<OriginalRoad>,
+    },
+    ChangeIntersectionCapacity {
+        i: osm::NodeID,
+        old: IntersectionCapacity,
+        new: IntersectionCapacity,
+    },
+    ChangeCrosswalks {
+        i: osm::NodeID,
+        old: BTreeSet<OriginalRoad>,
+        new: BTreeSet<OriginalRoad>,
+    },
+    /// New routes and stops aren't stable across map rebuilds, so the stops are identified by
+    /// where buses stop along the road.
+    CreateRoute {
+        name: String,
+        stops: Vec<LonLat>,
+        spawn_times: Vec<Time>,
+        avoid_roads: BTreeSet<OriginalRoad>,
+    },
+    Bulk {
+        cmds: Vec<PermanentEditCmd>,
+    },
 }
 
 impl EditCmd {
@@ -81,12 +123,58 @@ impl EditCmd {
                     new: new.clone(),
                 }
             }
+            EditCmd::ChangeRouteStops { id, old, new } => PermanentEditCmd::ChangeRouteStops {
+                osm_rel_id: map.get_br(*id).osm_rel_id,
+                old: old.iter().map(|bs| stop_to_gps(map, *bs)).collect(),
+                new: new.iter().map(|bs| stop_to_gps(map, *bs)).collect(),
+            },
+            EditCmd::ChangeRouteDetours { id, old, new } => PermanentEditCmd::ChangeRouteDetours {
+                osm_rel_id: map.get_br(*id).osm_rel_id,
+                old: old.iter().map(|r| map.get_r(*r).orig_id).collect(),
+                new: new.iter().map(|r| map.get_r(*r).orig_id).collect(),
+            },
+            EditCmd::ChangeIntersectionCapacity { i, old, new } => {
+                PermanentEditCmd::ChangeIntersectionCapacity {
+                    i: map.get_i(*i).orig_id,
+                    old: old.clone(),
+                    new: new.clone(),
+                }
+            }
+            EditCmd::ChangeCrosswalks { i, old, new } => PermanentEditCmd::ChangeCrosswalks {
+                i: map.get_i(*i).orig_id,
+                old: old.iter().map(|r| map.get_r(*r).orig_id).collect(),
+                new: new.iter().map(|r| map.get_r(*r).orig_id).collect(),
+            },
+            EditCmd::CreateRoute { route, .. } => PermanentEditCmd::CreateRoute {
+                name: route.full_name.clone(),
+                stops: route
+                    .stops
+                    .iter()
+                    .map(|bs| {
+                        map.get_bs(*bs)
+                            .driving_pos
+                            .pt(map)
+                            .to_gps(map.get_gps_bounds())
+                    })
+                    .collect(),
+                spawn_times: route.spawn_times.clone(),
+                avoid_roads: route
+                    .avoid_roads
+                    .iter()
+                    .map(|r| map.get_r(*r).orig_id)
+                    .collect(),
+            },
+            EditCmd::DeleteRoute { .. } => unreachable!(),
+            EditCmd::Bulk { cmds } => PermanentEditCmd::Bulk {
+                cmds: cmds.iter().map(|cmd| cmd.to_perma(map)).collect(),
+            },
         }
     }
 }
 
 impl PermanentEditCmd {
-    pub fn to_cmd(self, map: &Map) -> Result<EditCmd, String> {
+    /// Routes and stops created by the `earlier` commands can be referenced.
+    pub fn to_cmd(self, map: &Map, earlier: &[EditCmd]) -> Result<EditCmd, String> {
         match self {
             PermanentEditCmd::ChangeRoad { r, new, old } => {
                 let id = map.find_r_by_osm_id(r)?;
@@ -120,15 +208,152 @@ impl PermanentEditCmd {
                 old,
                 new,
             } => {
-                let id = map
-                    .find_br(osm_rel_id)
-                    .ok_or(format!("can't find {}", osm_rel_id))?;
+                let id = find_route(map, earlier, osm_rel_id)?;
                 Ok(EditCmd::ChangeRouteSchedule { id, old, new })
             }
+            PermanentEditCmd::ChangeRouteStops {
+                osm_rel_id,
+                old,
+                new,
+            } => {
+                let id = find_route(map, earlier, osm_rel_id)?;
+                Ok(EditCmd::ChangeRouteStops {
+                    id,
+                    old: old
+                        .into_iter()
+                        .map(|gps| stop_from_gps(map, earlier, gps))
+                        .collect::<Result<Vec<_>, String>>()?,
+                    new: new
+                        .into_iter()
+                        .map(|gps| stop_from_gps(map, earlier, gps))
+                        .collect::<Result<Vec<_>, String>>()?,
+                })
+            }
+            PermanentEditCmd::ChangeRouteDetours {
+                osm_rel_id,
+                old,
+                new,
+            } => {
+                let id = find_route(map, earlier, osm_rel_id)?;
+                let roads = |set: BTreeSet<OriginalRoad>| {
+                    set.into_iter()
+                        .map(|orig| map.find_r_by_osm_id(orig))
+                        .collect::<Result<BTreeSet<_>, String>>()
+                };
+                Ok(EditCmd::ChangeRouteDetours {
+                    id,
+                    old: roads(old)?,
+                    new: roads(new)?,
+                })
+            }
+            PermanentEditCmd::ChangeIntersectionCapacity { i, old, new } => {
+                let id = map.find_i_by_osm_id(i)?;
+                Ok(EditCmd::ChangeIntersectionCapacity { i: id, old, new })
+            }
+            PermanentEditCmd::ChangeCrosswalks { i, old, new } => {
+                let id = map.find_i_by_osm_id(i)?;
+                let roads = |set: BTreeSet<OriginalRoad>| {
+                    set.into_iter()
+                        .map(|orig| {
+                            let r = map.find_r_by_osm_id(orig)?;
+                            if map.get_r(r).src_i != id && map.get_r(r).dst_i != id {
+                                return Err(format!("{} doesn't touch {}", orig, i));
+                            }
+                            Ok(r)
+                        })
+                        .collect::<Result<BTreeSet<_>, String>>()
+                };
+                Ok(EditCmd::ChangeCrosswalks {
+                    i: id,
+                    old: roads(old)?,
+                    new: roads(new)?,
+                })
+            }
+            PermanentEditCmd::CreateRoute {
+                name,
+                stops,
+                spawn_times,
+                avoid_roads,
+            } => {
+                let stops = bus_positions_from_gps(map, &stops)?;
+                let (mut route, new_stops) =
+                    map.plan_bus_route(name, stops, spawn_times, earlier)?;
+                route.avoid_roads = avoid_roads
+                    .into_iter()
+                    .map(|orig| map.find_r_by_osm_id(orig))
+                    .collect::<Result<_, String>>()?;
+                Ok(EditCmd::CreateRoute { route, new_stops })
+            }
+            PermanentEditCmd::Bulk { cmds } => Ok(EditCmd::Bulk {
+                cmds: cmds
+                    .into_iter()
+                    .map(|cmd| cmd.to_cmd(map, earlier))
+                    .collect::<Result<Vec<_>, String>>()?,
+            }),
         }
     }
 }
 
+fn stop_to_gps(map: &Map, bs: BusStopID) -> LonLat {
+    map.get_bs(bs)
+        .sidewalk_pos
+        .pt(map)
+        .to_gps(map.get_gps_bounds())
+}
+
+fn stop_from_gps(map: &Map, earlier: &[EditCmd], gps: LonLat) -> Result<BusStopID, String> {
+    let pt = gps.to_pt(map.get_gps_bounds());
+    map.transit_after(earlier)
+        .1
+        .into_iter()
+        .find(|bs| {
+            bs.sidewalk_pos
+                .pt(map)
+                .approx_eq(pt, Distance::meters(10.0))
+        })
+        .map(|bs| bs.id)
+        .ok_or(format!("can't find a bus stop near {}", gps))
+}
+
+fn find_route(
+    map: &Map,
+    earlier: &[EditCmd],
+    osm_rel_id: osm::RelationID,
+) -> Result<BusRouteID, String> {
+    map.transit_after(earlier)
+        .0
+        .into_iter()
+        .find(|r| r.osm_rel_id == osm_rel_id)
+        .map(|r| r.id)
+        .ok_or(format!("can't find {}", osm_rel_id))
+}
+
+fn bus_positions_from_gps(map: &Map, stops: &[LonLat]) -> Result<Vec<Position>, String> {
+    let pts: Vec<HashablePt2D> = stops
+        .iter()
+        .map(|gps| gps.to_pt(map.get_gps_bounds()).to_hashable())
+        .collect();
+    let matches = match_points_to_lanes(
+        map.get_bounds(),
+        pts.iter().cloned().collect(),
+        map.all_lanes(),
+        |l| PathConstraints::Bus.can_use(l, map),
+        Distance::ZERO,
+        Distance::meters(10.0),
+        &mut Timer::throwaway(),
+    );
+    stops
+        .iter()
+        .zip(pts)
+        .map(|(gps, pt)| {
+            matches
+                .get(&pt)
+                .cloned()
+                .ok_or(format!("can't find a lane for buses near {}", gps))
+        })
+        .collect()
+}
+
 impl MapEdits {
     /// Encode the edits in a permanent format, referring to more-stable OSM IDs.
     pub fn to_permanent(&self, map: &Map) -> PermanentMapEdits {
@@ -139,6 +364,16 @@ impl MapEdits {
             version: 4,
             proposal_description: self.proposal_description.clone(),
             proposal_link: self.proposal_link.clone(),
+            road_notes: self
+                .road_notes
+                .iter()
+                .map(|(r, note)| (map.get_r(*r).orig_id, note.clone()))
+                .collect(),
+            intersection_notes: self
+                .intersection_notes
+                .iter()
+                .map(|(i, note)| (map.get_i(*i).orig_id, note.clone()))
+                .collect(),
             commands: self.commands.iter().map(|cmd| cmd.to_perma(map)).collect(),
             merge_zones: self.merge_zones,
         }
@@ -153,17 +388,31 @@ impl PermanentMapEdits {
             edits_name: self.edits_name,
             proposal_description: self.proposal_description,
             proposal_link: self.proposal_link,
-            commands: self
-                .commands
-                .into_iter()
-                .map(|cmd| cmd.to_cmd(map))
-                .collect::<Result<Vec<EditCmd>, String>>()?,
+            commands: Vec::new(),
             merge_zones: self.merge_zones,
+            road_notes: self
+                .road_notes
+                .into_iter()
+                .map(|(r, note)| Ok((map.find_r_by_osm_id(r)?, note)))
+                .collect::<Result<_, String>>()?,
+            intersection_notes: self
+                .intersection_notes
+                .into_iter()
+                .map(|(i, note)| Ok((map.find_i_by_osm_id(i)?, note)))
+                .collect::<Result<_, String>>()?,
 
             changed_roads: BTreeSet::new(),
             original_intersections: BTreeMap::new(),
             changed_routes: BTreeSet::new(),
+            created_routes: BTreeMap::new(),
+            changed_capacities: BTreeSet::new(),
+            changed_crosswalks: BTreeSet::new(),
         };
+        // Later commands may refer to routes and stops that earlier ones create
+        for cmd in self.commands {
+            let cmd = cmd.to_cmd(map, &edits.commands)?;
+            edits.commands.push(cmd);
+        }
         edits.update_derived(map);
         Ok(edits)
     }
@@ -175,17 +424,31 @@ impl PermanentMapEdits {
             edits_name: self.edits_name,
             proposal_description: self.proposal_description,
             proposal_link: self.proposal_link,
-            commands: self
-                .commands
+            commands: Vec::new(),
+            merge_zones: self.merge_zones,
+            road_notes: self
+                .road_notes
                 .into_iter()
-                .filter_map(|cmd| cmd.to_cmd(map).ok())
+                .filter_map(|(r, note)| Some((map.find_r_by_osm_id(r).ok()?, note)))
+                .collect(),
+            intersection_notes: self
+                .intersection_notes
+                .into_iter()
+                .filter_map(|(i, note)| Some((map.find_i_by_osm_id(i).ok()?, note)))
                 .collect(),
-            merge_zones: self.merge_zones,
 
             changed_roads: BTreeSet::new(),
             original_intersections: BTreeMap::new(),
             changed_routes: BTreeSet::new(),
+            created_routes: BTreeMap::new(),
+            changed_capacities: BTreeSet::new(),
+            changed_crosswalks: BTreeSet::new(),
         };
+        for cmd in self.commands {
+            if let Ok(cmd) = cmd.to_cmd(map, &edits.commands) {
+                edits.commands.push(cmd);
+            }
+        }
         edits.update_derived(map);
         edits
     }
@@ -13,6 +13,7 @@
 //! - b = building
 //! - br = bus route
 //! - bs = bus stop
+//! - fr = ferry route
 //! - i = intersection
 //! - l = lane
 //! - pl = parking lot
@@ -33,20 +34,26 @@ use geom::{Bounds, Distance, GPSBounds, Polygon};
 
 pub use crate::city::City;
 pub use crate::edits::{
-    EditCmd, EditEffects, EditIntersection, EditRoad, MapEdits, PermanentMapEdits,
+    EditCmd, EditEffects, EditIntersection, EditNote, EditRoad, MapEdits, MapMigration,
+    MigrationReport, PermanentMapEdits,
 };
 pub use crate::map::{DrivingSide, MapConfig};
 pub use crate::objects::area::{Area, AreaID, AreaType};
 pub use crate::objects::building::{
-    Amenity, Building, BuildingID, BuildingType, NamePerLanguage, OffstreetParking,
+    Amenity, Building, BuildingID, BuildingType, BuildingUse, NamePerLanguage, OffstreetParking,
 };
 pub use crate::objects::bus_stop::{BusRoute, BusRouteID, BusStop, BusStopID};
-pub use crate::objects::intersection::{Intersection, IntersectionID, IntersectionType};
+pub use crate::objects::ferry::{FerryRoute, FerryRouteID};
+pub use crate::objects::intersection::{
+    Intersection, IntersectionCapacity, IntersectionID, IntersectionType,
+};
 pub use crate::objects::lane::{
     Lane, LaneID, LaneType, PARKING_LOT_SPOT_LENGTH, PARKING_SPOT_LENGTH,
 };
 pub use crate::objects::parking_lot::{ParkingLot, ParkingLotID};
-pub use crate::objects::road::{DirectedRoadID, Direction, Road, RoadID};
+pub use crate::objects::road::{
+    DirectedRoadID, Direction, Road, RoadID, TrafficCalming, TrafficCalmingKind, WorkZone,
+};
 pub use crate::objects::stop_signs::{ControlStopSign, RoadWithStopSign};
 pub use crate::objects::traffic_signals::{ControlTrafficSignal, PhaseType, Stage};
 pub use crate::objects::turn::{
@@ -56,6 +63,7 @@ pub use crate::objects::zone::{AccessRestrictions, Zone};
 pub use crate::pathfind::uber_turns::{IntersectionCluster, UberTurn, UberTurnGroup};
 use crate::pathfind::Pathfinder;
 pub use crate::pathfind::{Path, PathConstraints, PathRequest, PathStep};
+pub use crate::query::RoadQuery;
 pub use crate::traversable::{Position, Traversable};
 
 mod city;
@@ -66,6 +74,7 @@ mod map;
 mod objects;
 pub mod osm;
 mod pathfind;
+mod query;
 pub mod raw;
 mod traversable;
 
@@ -94,6 +103,7 @@ pub struct Map {
     )]
     bus_stops: BTreeMap<BusStopID, BusStop>,
     bus_routes: Vec<BusRoute>,
+    ferry_routes: Vec<FerryRoute>,
     areas: Vec<Area>,
     parking_lots: Vec<ParkingLot>,
     boundary_polygon: Polygon,
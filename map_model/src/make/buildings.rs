@@ -9,7 +9,7 @@ use geom::{Distance, HashablePt2D, Line, Polygon};
 use crate::make::match_points_to_lanes;
 use crate::raw::RawBuilding;
 use crate::{
-    osm, Amenity, Building, BuildingID, BuildingType, LaneID, Map, NamePerLanguage,
+    osm, Amenity, Building, BuildingID, BuildingType, BuildingUse, LaneID, Map, NamePerLanguage,
     OffstreetParking,
 };
 
@@ -99,6 +99,7 @@ pub fn make_all_buildings(
                     b.polygon.area(),
                     &mut rng,
                 ),
+                bldg_use: classify_use(&b.osm_tags, &b.amenities),
                 parking: if let Some(n) = b.public_garage_name.clone() {
                     OffstreetParking::PublicGarage(n, b.num_parking_spots)
                 } else {
@@ -151,6 +152,53 @@ fn get_address(tags: &Tags, sidewalk: LaneID, map: &Map) -> String {
     }
 }
 
+fn classify_use(tags: &Tags, amenities: &[Amenity]) -> BuildingUse {
+    let schools = vec!["school", "university", "college", "kindergarten"];
+    if tags.is_any("building", schools.clone())
+        || amenities
+            .iter()
+            .any(|a| schools.contains(&a.amenity_type.as_str()))
+    {
+        return BuildingUse::School;
+    }
+    if tags.is_any("building", vec!["retail", "supermarket", "kiosk"]) || !amenities.is_empty() {
+        return BuildingUse::Retail;
+    }
+    if tags.is_any("building", vec!["office", "commercial", "civic", "public"])
+        || tags.contains_key("office")
+    {
+        return BuildingUse::Office;
+    }
+    if tags.is_any("building", vec!["industrial", "warehouse", "manufacture"])
+        || tags.is("landuse", "industrial")
+    {
+        return BuildingUse::Industrial;
+    }
+    if tags.is_any(
+        "building",
+        vec![
+            "house",
+            "detached",
+            "semidetached_house",
+            "farm",
+            "hut",
+            "static_caravan",
+            "cabin",
+            "apartments",
+            "terrace",
+            "residential",
+            "dormitory",
+        ],
+    ) {
+        return BuildingUse::Residential;
+    }
+    // Most buildings tagged as just "building=yes" are houses
+    if tags.is("building", "yes") {
+        return BuildingUse::Residential;
+    }
+    BuildingUse::Other
+}
+
 fn classify_bldg(
     tags: &Tags,
     amenities: &Vec<Amenity>,
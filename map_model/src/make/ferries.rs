@@ -0,0 +1,54 @@
+use std::collections::BTreeMap;
+
+use abstutil::Timer;
+use geom::{Duration, Time};
+
+use crate::raw::RawFerryRoute;
+use crate::{osm, FerryRoute, FerryRouteID, IntersectionID, Map};
+
+pub fn make_ferry_routes(
+    map: &mut Map,
+    raw_routes: &[RawFerryRoute],
+    intersection_id_mapping: &BTreeMap<osm::NodeID, IntersectionID>,
+    timer: &mut Timer,
+) {
+    for raw in raw_routes {
+        // Terminals might've been removed along with disconnected roads
+        let terminals: Vec<IntersectionID> = raw
+            .terminals
+            .iter()
+            .filter_map(|i| intersection_id_mapping.get(i))
+            .filter(|i| map.get_i(**i).is_border())
+            .cloned()
+            .collect();
+        if terminals.is_empty() {
+            timer.warn(format!(
+                "Skipping ferry {} ({}); none of its terminals survived",
+                raw.name, raw.osm_way_id
+            ));
+            continue;
+        }
+
+        map.ferry_routes.push(FerryRoute {
+            id: FerryRouteID(map.ferry_routes.len()),
+            name: raw.name.clone(),
+            osm_way_id: raw.osm_way_id,
+            terminals,
+            crossing_time: raw.crossing_time,
+            departures: departures(raw.headway),
+            allows_vehicles: raw.allows_vehicles,
+        });
+    }
+}
+
+// Ferries run from 6am to 10pm
+fn departures(headway: Duration) -> Vec<Time> {
+    let last = Time::START_OF_DAY + Duration::hours(22);
+    let mut times = Vec::new();
+    let mut t = Time::START_OF_DAY + Duration::hours(6);
+    while t <= last {
+        times.push(t);
+        t += headway;
+    }
+    times
+}
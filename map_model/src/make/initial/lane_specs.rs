@@ -275,7 +275,10 @@ pub fn get_lane_specs_ltr(tags: &Tags, cfg: &MapConfig) -> Vec<LaneSpec> {
         need_back_shoulder = false;
     }
 
-    if cfg.inferred_sidewalks {
+    // Without inferred sidewalks, only fall back to shoulders where no sidewalk is tagged at all.
+    // sidewalk=separate means a footway was mapped, which pedestrians should use instead.
+    let shoulder_fallback = cfg.walk_on_shoulders && !tags.contains_key(osm::SIDEWALK);
+    if cfg.inferred_sidewalks || shoulder_fallback {
         if need_fwd_shoulder {
             fwd_side.push(fwd(LaneType::Shoulder));
         }
@@ -437,6 +440,7 @@ mod tests {
                 driving_side,
                 bikes_can_use_bus_lanes: true,
                 inferred_sidewalks: true,
+                walk_on_shoulders: false,
             };
             let actual = get_lane_specs_ltr(&tags(input.clone()), &cfg);
             let actual_lt = actual
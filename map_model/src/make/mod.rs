@@ -10,12 +10,14 @@ use crate::pathfind::Pathfinder;
 use crate::raw::{OriginalRoad, RawMap};
 use crate::{
     connectivity, osm, AccessRestrictions, Area, AreaID, AreaType, ControlStopSign,
-    ControlTrafficSignal, Direction, Intersection, IntersectionID, IntersectionType, Lane, LaneID,
-    Map, MapEdits, Movement, PathConstraints, Position, Road, RoadID, Zone,
+    ControlTrafficSignal, Direction, Intersection, IntersectionCapacity, IntersectionID,
+    IntersectionType, Lane, LaneID, Map, MapEdits, Movement, PathConstraints, Position, Road,
+    RoadID, Zone,
 };
 
 mod bridges;
 mod buildings;
+mod ferries;
 pub mod initial;
 mod medians;
 mod parking_lots;
@@ -49,6 +51,7 @@ impl Map {
             buildings: Vec::new(),
             bus_stops: BTreeMap::new(),
             bus_routes: Vec::new(),
+            ferry_routes: Vec::new(),
             areas: Vec::new(),
             parking_lots: Vec::new(),
             zones: Vec::new(),
@@ -85,6 +88,14 @@ impl Map {
                 incoming_lanes: Vec::new(),
                 outgoing_lanes: Vec::new(),
                 roads: i.roads.iter().map(|id| road_id_mapping[id]).collect(),
+                capacity: IntersectionCapacity::default(),
+                no_crosswalks: BTreeSet::new(),
+                orig_no_crosswalks: BTreeSet::new(),
+                rail_crossing: raw
+                    .intersections
+                    .get(&i.id)
+                    .map(|i| i.rail_crossing)
+                    .unwrap_or(false),
             });
             intersection_id_mapping.insert(i.id, id);
         }
@@ -144,9 +155,11 @@ impl Map {
                     0
                 },
                 access_restrictions: AccessRestrictions::new(),
+                traffic_calming: Vec::new(),
+                work_zones: Vec::new(),
             };
-            road.speed_limit = road.speed_limit_from_osm();
-            road.access_restrictions = road.access_restrictions_from_osm();
+            road.speed_limit = Road::speed_limit_from_osm(&road.osm_tags);
+            road.access_restrictions = Road::access_restrictions_from_osm(&road.osm_tags);
 
             let mut total_back_width = Distance::ZERO;
             for lane in &r.lane_specs_ltr {
@@ -232,6 +245,14 @@ impl Map {
             }
         }
 
+        timer.start("guess where crosswalks are missing");
+        for idx in 0..map.intersections.len() {
+            let missing = walking_turns::find_missing_crosswalks(&map, &map.intersections[idx]);
+            map.intersections[idx].no_crosswalks = missing.clone();
+            map.intersections[idx].orig_no_crosswalks = missing;
+        }
+        timer.stop("guess where crosswalks are missing");
+
         let mut all_turns = Vec::new();
         for i in &map.intersections {
             if i.is_border() || i.is_closed() {
@@ -253,6 +274,30 @@ impl Map {
             map.turns.insert(t.id, t);
         }
 
+        // Missing crosswalks shouldn't cut off part of the map for pedestrians. Restore all of the
+        // crosswalks around anywhere that wound up disconnected.
+        timer.start("restore crosswalks needed for connectivity");
+        let restore: BTreeSet<IntersectionID> =
+            connectivity::find_scc(&map, PathConstraints::Pedestrian)
+                .1
+                .into_iter()
+                .flat_map(|l| vec![map.get_l(l).src_i, map.get_l(l).dst_i])
+                .filter(|i| !map.get_i(*i).no_crosswalks.is_empty())
+                .collect();
+        for id in restore {
+            let i = &mut map.intersections[id.0];
+            i.no_crosswalks.clear();
+            i.orig_no_crosswalks.clear();
+            for t in std::mem::take(&mut i.turns) {
+                map.turns.remove(&t);
+            }
+            for t in turns::make_all_turns(&map, map.get_i(id), timer) {
+                map.intersections[id.0].turns.insert(t.id);
+                map.turns.insert(t.id, t);
+            }
+        }
+        timer.stop("restore crosswalks needed for connectivity");
+
         timer.start("find blackholes");
         for l in connectivity::find_scc(&map, PathConstraints::Car).1 {
             map.lanes[l.0].driving_blackhole = true;
@@ -333,6 +378,7 @@ impl Map {
         for id in map.bus_stops.keys() {
             assert!(!map.get_routes_serving_stop(*id).is_empty());
         }
+        ferries::make_ferry_routes(&mut map, &raw.ferry_routes, &intersection_id_mapping, timer);
 
         if build_ch {
             timer.start("setup ContractionHierarchyPathfinder");
@@ -348,7 +394,7 @@ impl Map {
 
 /// Snap points to an exact Position along the nearest lane. If the result doesn't contain a
 /// requested point, then there was no matching lane close enough.
-fn match_points_to_lanes<F: Fn(&Lane) -> bool>(
+pub(crate) fn match_points_to_lanes<F: Fn(&Lane) -> bool>(
     bounds: &Bounds,
     pts: HashSet<HashablePt2D>,
     lanes: &Vec<Lane>,
@@ -174,6 +174,7 @@ fn three_way(map: &Map, i: IntersectionID) -> Option<ControlTrafficSignal> {
         return None;
     }
     let mut ts = new(i, map);
+    let (curbside, crossing) = turn_types(map);
 
     // Picture a T intersection. Use turn angles to figure out the "main" two roads.
     let straight = ts
@@ -192,15 +193,15 @@ fn three_way(map: &Map, i: IntersectionID) -> Option<ControlTrafficSignal> {
         vec![
             vec![
                 (vec![north, south], TurnType::Straight, PROTECTED),
-                (vec![north, south], TurnType::Right, YIELD),
-                (vec![north, south], TurnType::Left, YIELD),
-                (vec![east], TurnType::Right, YIELD),
+                (vec![north, south], curbside, YIELD),
+                (vec![north, south], crossing, YIELD),
+                (vec![east], curbside, YIELD),
             ],
             vec![
                 (vec![east], TurnType::Straight, PROTECTED),
-                (vec![east], TurnType::Right, YIELD),
-                (vec![east], TurnType::Left, YIELD),
-                (vec![north, south], TurnType::Right, YIELD),
+                (vec![east], curbside, YIELD),
+                (vec![east], crossing, YIELD),
+                (vec![north, south], curbside, YIELD),
             ],
         ],
     );
@@ -222,21 +223,22 @@ fn four_way_four_stage(map: &Map, i: IntersectionID) -> Option<ControlTrafficSig
     // Four-stage with protected lefts, right turn on red (except for the protected lefts),
     // turning cars yield to peds
     let mut ts = new(i, map);
+    let (curbside, crossing) = turn_types(map);
     make_stages(
         &mut ts,
         vec![
             vec![
                 (vec![north, south], TurnType::Straight, PROTECTED),
-                (vec![north, south], TurnType::Right, YIELD),
-                (vec![east, west], TurnType::Right, YIELD),
+                (vec![north, south], curbside, YIELD),
+                (vec![east, west], curbside, YIELD),
             ],
-            vec![(vec![north, south], TurnType::Left, PROTECTED)],
+            vec![(vec![north, south], crossing, PROTECTED)],
             vec![
                 (vec![east, west], TurnType::Straight, PROTECTED),
-                (vec![east, west], TurnType::Right, YIELD),
-                (vec![north, south], TurnType::Right, YIELD),
+                (vec![east, west], curbside, YIELD),
+                (vec![north, south], curbside, YIELD),
             ],
-            vec![(vec![east, west], TurnType::Left, PROTECTED)],
+            vec![(vec![east, west], crossing, PROTECTED)],
         ],
     );
     Some(ts)
@@ -255,20 +257,21 @@ fn four_way_two_stage(map: &Map, i: IntersectionID) -> Option<ControlTrafficSign
 
     // Two-stage with no protected lefts, right turn on red, turning cars yielding to peds
     let mut ts = new(i, map);
+    let (curbside, crossing) = turn_types(map);
     make_stages(
         &mut ts,
         vec![
             vec![
                 (vec![north, south], TurnType::Straight, PROTECTED),
-                (vec![north, south], TurnType::Right, YIELD),
-                (vec![north, south], TurnType::Left, YIELD),
-                (vec![east, west], TurnType::Right, YIELD),
+                (vec![north, south], curbside, YIELD),
+                (vec![north, south], crossing, YIELD),
+                (vec![east, west], curbside, YIELD),
             ],
             vec![
                 (vec![east, west], TurnType::Straight, PROTECTED),
-                (vec![east, west], TurnType::Right, YIELD),
-                (vec![east, west], TurnType::Left, YIELD),
-                (vec![north, south], TurnType::Right, YIELD),
+                (vec![east, west], curbside, YIELD),
+                (vec![east, west], crossing, YIELD),
+                (vec![north, south], curbside, YIELD),
             ],
         ],
     );
@@ -294,6 +297,7 @@ fn four_oneways(map: &Map, i: IntersectionID) -> Option<ControlTrafficSignal> {
 
     // TODO This may not generalize...
     let mut ts = new(i, map);
+    let (curbside, crossing) = turn_types(map);
     make_stages(
         &mut ts,
         vec![
@@ -301,24 +305,31 @@ fn four_oneways(map: &Map, i: IntersectionID) -> Option<ControlTrafficSignal> {
                 (vec![r1], TurnType::Straight, PROTECTED),
                 // TODO Technically, upgrade to protected if there's no opposing crosswalk --
                 // even though it doesn't matter much.
-                (vec![r1], TurnType::Right, YIELD),
-                (vec![r1], TurnType::Left, YIELD),
-                (vec![r1], TurnType::Right, YIELD),
+                (vec![r1], curbside, YIELD),
+                (vec![r1], crossing, YIELD),
+                (vec![r1], curbside, YIELD),
                 // TODO Refactor
             ],
             vec![
                 (vec![r2], TurnType::Straight, PROTECTED),
                 // TODO Technically, upgrade to protected if there's no opposing crosswalk --
                 // even though it doesn't matter much.
-                (vec![r2], TurnType::Right, YIELD),
-                (vec![r2], TurnType::Left, YIELD),
-                (vec![r2], TurnType::Right, YIELD),
+                (vec![r2], curbside, YIELD),
+                (vec![r2], crossing, YIELD),
+                (vec![r2], curbside, YIELD),
             ],
         ],
     );
     Some(ts)
 }
 
+// When driving on the right, right turns can go on red and left turns may need protection. It's the
+// opposite when driving on the left.
+fn turn_types(map: &Map) -> (TurnType, TurnType) {
+    let side = map.get_config().driving_side;
+    (side.curbside_turn(), side.crossing_turn())
+}
+
 fn all_walk_all_yield(map: &Map, i: IntersectionID) -> ControlTrafficSignal {
     let mut ts = new(i, map);
 
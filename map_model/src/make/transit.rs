@@ -1,12 +1,14 @@
-use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 
 use abstutil::Timer;
 use geom::{Distance, Duration, FindClosest, HashablePt2D, Time};
 
 use crate::make::match_points_to_lanes;
+use crate::pathfind::{ContractionHierarchyPathfinder, Pathfinder};
 use crate::raw::{RawBusRoute, RawBusStop};
 use crate::{
-    BusRoute, BusRouteID, BusStop, BusStopID, LaneID, LaneType, Map, PathConstraints, Position,
+    osm, BusRoute, BusRouteID, BusStop, BusStopID, DrivingSide, EditCmd, LaneID, LaneType, Map,
+    PathConstraints, PathRequest, Position,
 };
 
 /// Construct the final model of bus/train stops and routes. This is quite broken currently, so not
@@ -133,7 +135,9 @@ fn make_route(
         short_name: r.short_name.clone(),
         osm_rel_id: r.osm_rel_id,
         gtfs_trip_marker: r.gtfs_trip_marker.clone(),
-        stops,
+        stops: stops.clone(),
+        orig_stops: stops,
+        avoid_roads: BTreeSet::new(),
         route_type,
         start,
         end_border,
@@ -181,6 +185,236 @@ fn make_route(
     Ok(())
 }
 
+impl Map {
+    /// Plan a brand new bus route, after the map has been built. Each stop is a position along a
+    /// lane that buses can use; a new stop is created there unless an existing one is close by.
+    /// Nothing changes until the command is applied through `MapEdits`. The pathfinder must be
+    /// up-to-date.
+    pub fn create_bus_route_cmd(
+        &self,
+        name: String,
+        stops: Vec<Position>,
+        spawn_times: Vec<Time>,
+    ) -> Result<EditCmd, String> {
+        let (route, new_stops) =
+            self.plan_bus_route(name, stops.clone(), spawn_times, &self.edits.commands)?;
+
+        // Make sure the route is connected
+        let mut reqs = vec![PathRequest {
+            start: Position::start(route.start),
+            end: stops[0],
+            constraints: PathConstraints::Bus,
+        }];
+        for pair in stops.windows(2) {
+            reqs.push(PathRequest {
+                start: pair[0],
+                end: pair[1],
+                constraints: PathConstraints::Bus,
+            });
+        }
+        for req in reqs {
+            if self.pathfind(req.clone()).is_none() {
+                return Err(format!("no path for a bus: {}", req));
+            }
+        }
+
+        Ok(EditCmd::CreateRoute { route, new_stops })
+    }
+
+    /// Like `create_bus_route_cmd`, but as if only the `earlier` commands were applied to the
+    /// original map, instead of the current edits. Doesn't check for paths between the stops.
+    pub(crate) fn plan_bus_route(
+        &self,
+        name: String,
+        stops: Vec<Position>,
+        spawn_times: Vec<Time>,
+        earlier: &[EditCmd],
+    ) -> Result<(BusRoute, Vec<BusStop>), String> {
+        if stops.len() < 2 {
+            return Err("a route needs at least two stops".to_string());
+        }
+        if spawn_times.is_empty() {
+            return Err("no buses are scheduled".to_string());
+        }
+        let start = pick_start_lane(stops[0], PathConstraints::Bus, self)?;
+
+        let (routes, existing) = self.transit_after(earlier);
+        let mut stop_ids = Vec::new();
+        let mut new_stops: Vec<BusStop> = Vec::new();
+        for driving_pos in stops {
+            if let Some(bs) = existing.iter().find(|bs| {
+                !bs.is_train_stop
+                    && bs.driving_pos.lane() == driving_pos.lane()
+                    && (bs.driving_pos.dist_along() - driving_pos.dist_along()).abs()
+                        < Distance::meters(10.0)
+            }) {
+                stop_ids.push(bs.id);
+                continue;
+            }
+
+            let sidewalk = self
+                .get_parent(driving_pos.lane())
+                .find_closest_lane(driving_pos.lane(), |l| l.is_sidewalk(), self)
+                .ok_or_else(|| format!("no sidewalk near {}", driving_pos))?;
+            // Orphaned stops were removed while importing, so the indices may have gaps
+            let id = BusStopID {
+                sidewalk,
+                idx: existing
+                    .iter()
+                    .map(|bs| bs.id)
+                    .chain(new_stops.iter().map(|bs| bs.id))
+                    .filter(|id| id.sidewalk == sidewalk)
+                    .map(|id| id.idx + 1)
+                    .max()
+                    .unwrap_or(0),
+            };
+            new_stops.push(BusStop {
+                id,
+                name: format!("{} (new stop)", self.get_parent(sidewalk).get_name(None)),
+                driving_pos,
+                sidewalk_pos: driving_pos.equiv_pos(sidewalk, self),
+                is_train_stop: false,
+            });
+            stop_ids.push(id);
+        }
+
+        let route = BusRoute {
+            id: BusRouteID(routes.len()),
+            full_name: name.clone(),
+            short_name: name,
+            gtfs_trip_marker: None,
+            // Not from OSM. Negative IDs won't collide with a real relation.
+            osm_rel_id: osm::RelationID(
+                routes
+                    .iter()
+                    .map(|r| r.osm_rel_id.0)
+                    .min()
+                    .unwrap_or(0)
+                    .min(0)
+                    - 1,
+            ),
+            stops: stop_ids.clone(),
+            orig_stops: stop_ids,
+            avoid_roads: BTreeSet::new(),
+            start,
+            end_border: None,
+            route_type: PathConstraints::Bus,
+            spawn_times: spawn_times.clone(),
+            orig_spawn_times: spawn_times,
+        };
+        Ok((route, new_stops))
+    }
+
+    /// The routes and stops that would exist if only the `earlier` commands were applied to the
+    /// original map, instead of the current edits. Created routes are the only edits that add
+    /// routes or stops.
+    pub(crate) fn transit_after<'a>(
+        &'a self,
+        earlier: &'a [EditCmd],
+    ) -> (Vec<&'a BusRoute>, Vec<&'a BusStop>) {
+        let mut current_stops = BTreeSet::new();
+        let mut num_current_routes = 0;
+        for cmd in self.edits.commands.iter().flat_map(|cmd| cmd.flatten()) {
+            if let EditCmd::CreateRoute { new_stops, .. } = cmd {
+                num_current_routes += 1;
+                current_stops.extend(new_stops.iter().map(|bs| bs.id));
+            }
+        }
+
+        let mut routes: Vec<&BusRoute> = self.bus_routes
+            [..self.bus_routes.len() - num_current_routes]
+            .iter()
+            .collect();
+        let mut stops: Vec<&BusStop> = self
+            .bus_stops
+            .values()
+            .filter(|bs| !current_stops.contains(&bs.id))
+            .collect();
+        for cmd in earlier.iter().flat_map(|cmd| cmd.flatten()) {
+            if let EditCmd::CreateRoute { route, new_stops } = cmd {
+                routes.push(route);
+                stops.extend(new_stops);
+            }
+        }
+        (routes, stops)
+    }
+
+    /// Add a route and the stops it introduces. Route IDs are indices, so the route must come
+    /// last.
+    pub(crate) fn insert_bus_route(&mut self, route: BusRoute, new_stops: Vec<BusStop>) {
+        assert_eq!(route.id.0, self.bus_routes.len());
+        for bs in new_stops {
+            self.lanes[bs.id.sidewalk.0].bus_stops.insert(bs.id);
+            self.bus_stops.insert(bs.id, bs);
+        }
+        self.bus_routes.push(route);
+    }
+
+    /// Undo `insert_bus_route`. Only the newest route can be deleted this way.
+    pub(crate) fn delete_bus_route(&mut self, id: BusRouteID, new_stops: &[BusStop]) {
+        assert_eq!(id.0 + 1, self.bus_routes.len());
+        self.bus_routes.pop();
+        for bs in new_stops {
+            self.bus_stops.remove(&bs.id);
+            self.lanes[bs.id.sidewalk.0].bus_stops.remove(&bs.id);
+        }
+    }
+
+    /// Add a route while importing a map, making it part of the original map instead of an edit.
+    /// Call `rebuild_pathfinder` after all routes are added or removed.
+    pub fn import_bus_route(
+        &mut self,
+        name: String,
+        stops: Vec<Position>,
+        spawn_times: Vec<Time>,
+    ) -> Result<BusRouteID, String> {
+        match self.create_bus_route_cmd(name, stops, spawn_times)? {
+            EditCmd::CreateRoute { route, new_stops } => {
+                let id = route.id;
+                self.insert_bus_route(route, new_stops);
+                Ok(id)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Remove bus routes while importing a map, along with any stops no other route serves. The
+    /// remaining routes are renumbered. Call `rebuild_pathfinder` afterwards.
+    pub fn remove_bus_routes(&mut self, remove: &BTreeSet<BusRouteID>) {
+        if remove.is_empty() {
+            return;
+        }
+        let mut routes = std::mem::take(&mut self.bus_routes);
+        routes.retain(|r| !remove.contains(&r.id));
+        for (idx, mut r) in routes.into_iter().enumerate() {
+            r.id = BusRouteID(idx);
+            self.bus_routes.push(r);
+        }
+
+        for id in self
+            .bus_stops
+            .keys()
+            .filter(|id| self.get_routes_serving_stop(**id).is_empty())
+            .cloned()
+            .collect::<Vec<_>>()
+        {
+            self.bus_stops.remove(&id);
+            self.lanes[id.sidewalk.0].bus_stops.remove(&id);
+        }
+    }
+
+    /// After importing or removing routes, the stops that pedestrians can use changed, so build
+    /// the pathfinder from scratch.
+    pub fn rebuild_pathfinder(&mut self, timer: &mut Timer) {
+        if let Pathfinder::CH(_) = self.pathfinder {
+            timer.start("setup ContractionHierarchyPathfinder");
+            let ch = ContractionHierarchyPathfinder::new(self, timer);
+            self.pathfinder = Pathfinder::CH(ch);
+            timer.stop("setup ContractionHierarchyPathfinder");
+        }
+    }
+}
+
 struct Matcher {
     // TODO Eventually, maybe also map to a station building too
     sidewalk_pts: HashMap<HashablePt2D, Position>,
@@ -254,12 +488,15 @@ impl Matcher {
         // We already figured out what side of the road we're on
         let (r, dir) = stop.matched_road.unwrap();
         let r = map.get_r(map.find_r_by_osm_id(r)?);
-        // Prefer the rightmost match. DON'T use find_closest_lane here; we only want one side of
-        // the road.
+        // Prefer the match closest to the curb. DON'T use find_closest_lane here; we only want one
+        // side of the road.
+        let mut lanes = r.children(dir);
+        if map.get_config().driving_side == DrivingSide::Right {
+            lanes.reverse();
+        }
         let l = map.get_l(
-            r.children(dir)
+            lanes
                 .iter()
-                .rev()
                 .find(|(l, _)| route_type.can_use(map.get_l(*l), map))
                 .ok_or_else(|| format!("{} {}, doesn't have a bus or driving lane", r.id, dir))?
                 .0,
@@ -4,10 +4,13 @@ use abstutil::{wraparound_get, Timer};
 use geom::{Distance, Line, PolyLine, Pt2D, Ring};
 
 use crate::{
-    Direction, DrivingSide, Intersection, IntersectionID, Lane, LaneID, LaneType, Map, Road, Turn,
-    TurnID, TurnType,
+    osm, Direction, DrivingSide, Intersection, IntersectionID, IntersectionType, Lane, LaneID,
+    LaneType, Map, Road, RoadID, Turn, TurnID, TurnType,
 };
 
+// Unsignalized crossings over arterials at least this many lanes wide are assumed to be unmarked
+const MIN_LANES_WITHOUT_CROSSWALK: usize = 4;
+
 /// Generate Crosswalk and SharedSidewalkCorner (places where two sidewalks directly meet) turns
 pub fn make_walking_turns(map: &Map, i: &Intersection, timer: &mut Timer) -> Vec<Turn> {
     if i.is_footway(map) {
@@ -24,6 +27,7 @@ pub fn make_walking_turns(map: &Map, i: &Intersection, timer: &mut Timer) -> Vec
         .map(|id| &all_roads[id.0])
         .collect();
     let mut result: Vec<Turn> = Vec::new();
+    let crossable = |crossed: &[&Road]| crossed.iter().all(|r| !i.no_crosswalks.contains(&r.id));
 
     // I'm a bit confused when to do -1 and +1 honestly, but this works in practice. Angle sorting
     // may be a little backwards.
@@ -34,8 +38,10 @@ pub fn make_walking_turns(map: &Map, i: &Intersection, timer: &mut Timer) -> Vec
     };
 
     if roads.len() == 2 {
-        if let Some(turns) = make_degenerate_crosswalks(i.id, lanes, roads[0], roads[1]) {
-            result.extend(turns);
+        if crossable(&roads) {
+            if let Some(turns) = make_degenerate_crosswalks(i.id, lanes, roads[0], roads[1]) {
+                result.extend(turns);
+            }
         }
         // TODO Argh, duplicate logic for SharedSidewalkCorners
         for idx1 in 0..roads.len() {
@@ -89,7 +95,9 @@ pub fn make_walking_turns(map: &Map, i: &Intersection, timer: &mut Timer) -> Vec
         if let Some(l1) = get_sidewalk(lanes, roads[idx1].incoming_lanes(i.id)) {
             // Make the crosswalk to the other side
             if let Some(l2) = get_sidewalk(lanes, roads[idx1].outgoing_lanes(i.id)) {
-                result.extend(make_crosswalks(i.id, l1, l2).into_iter().flatten());
+                if crossable(&[roads[idx1]]) {
+                    result.extend(make_crosswalks(i.id, l1, l2).into_iter().flatten());
+                }
             }
 
             // Find the shared corner
@@ -118,28 +126,32 @@ pub fn make_walking_turns(map: &Map, i: &Intersection, timer: &mut Timer) -> Vec
             ) {
                 // Adjacent road is missing a sidewalk on the near side, but has one on the far
                 // side
-                result.extend(make_crosswalks(i.id, l1, l2).into_iter().flatten());
+                if crossable(&[*wraparound_get(&roads, (idx1 as isize) + idx_offset)]) {
+                    result.extend(make_crosswalks(i.id, l1, l2).into_iter().flatten());
+                }
             } else {
                 // We may need to add a crosswalk over this intermediate road that has no
                 // sidewalks at all. There might be a few in the way -- think highway onramps.
                 // TODO Refactor and loop until we find something to connect it to?
-                if let Some(l2) = get_sidewalk(
-                    lanes,
-                    wraparound_get(&roads, (idx1 as isize) + 2 * idx_offset).outgoing_lanes(i.id),
-                ) {
-                    result.extend(make_crosswalks(i.id, l1, l2).into_iter().flatten());
-                } else if let Some(l2) = get_sidewalk(
-                    lanes,
-                    wraparound_get(&roads, (idx1 as isize) + 2 * idx_offset).incoming_lanes(i.id),
-                ) {
-                    result.extend(make_crosswalks(i.id, l1, l2).into_iter().flatten());
+                let r1 = *wraparound_get(&roads, (idx1 as isize) + idx_offset);
+                let r2 = *wraparound_get(&roads, (idx1 as isize) + 2 * idx_offset);
+                if let Some(l2) = get_sidewalk(lanes, r2.outgoing_lanes(i.id)) {
+                    if crossable(&[r1]) {
+                        result.extend(make_crosswalks(i.id, l1, l2).into_iter().flatten());
+                    }
+                } else if let Some(l2) = get_sidewalk(lanes, r2.incoming_lanes(i.id)) {
+                    if crossable(&[r1, r2]) {
+                        result.extend(make_crosswalks(i.id, l1, l2).into_iter().flatten());
+                    }
                 } else if roads.len() > 3 {
                     if let Some(l2) = get_sidewalk(
                         lanes,
                         wraparound_get(&roads, (idx1 as isize) + 3 * idx_offset)
                             .outgoing_lanes(i.id),
                     ) {
-                        result.extend(make_crosswalks(i.id, l1, l2).into_iter().flatten());
+                        if crossable(&[r1, r2]) {
+                            result.extend(make_crosswalks(i.id, l1, l2).into_iter().flatten());
+                        }
                     }
                 }
             }
@@ -149,6 +161,39 @@ pub fn make_walking_turns(map: &Map, i: &Intersection, timer: &mut Timer) -> Vec
     result
 }
 
+/// Guesses which roads meeting at an intersection don't have a crosswalk over them. OSM rarely maps
+/// crossings completely, so this goes by road class and control: traffic signals and intersections
+/// between similar roads get crosswalks everywhere, but a wide arterial or highway crossed at an
+/// unsignalized side street usually doesn't. Edits can override the guess per crossing.
+pub fn find_missing_crosswalks(map: &Map, i: &Intersection) -> BTreeSet<RoadID> {
+    let mut missing = BTreeSet::new();
+    if i.intersection_type != IntersectionType::StopSign || i.roads.len() < 3 {
+        return missing;
+    }
+    let roads: Vec<&Road> = i.roads.iter().map(|r| map.get_r(*r)).collect();
+    let has_local_road = roads
+        .iter()
+        .any(|r| r.get_rank() == osm::RoadRank::Local && !r.is_footway());
+    for r in roads {
+        let wide = r
+            .lanes_ltr()
+            .into_iter()
+            .filter(|(_, _, lt)| *lt == LaneType::Driving || *lt == LaneType::Bus)
+            .count()
+            >= MIN_LANES_WITHOUT_CROSSWALK;
+        match r.get_rank() {
+            osm::RoadRank::Highway => {
+                missing.insert(r.id);
+            }
+            osm::RoadRank::Arterial if wide && has_local_road => {
+                missing.insert(r.id);
+            }
+            _ => {}
+        }
+    }
+    missing
+}
+
 // TODO Need to filter out extraneous crosswalks. Why weren't they being created before?
 pub fn _make_walking_turns_v2(map: &Map, i: &Intersection, timer: &mut Timer) -> Vec<Turn> {
     let driving_side = map.config.driving_side;
@@ -11,9 +11,10 @@ use geom::{Bounds, Distance, GPSBounds, Polygon, Pt2D, Ring, Time};
 use crate::raw::{OriginalRoad, RawMap};
 use crate::{
     osm, Area, AreaID, Building, BuildingID, BuildingType, BusRoute, BusRouteID, BusStop,
-    BusStopID, ControlStopSign, ControlTrafficSignal, Intersection, IntersectionID, Lane, LaneID,
-    LaneType, Map, MapEdits, MovementID, OffstreetParking, ParkingLot, ParkingLotID, Path,
-    PathConstraints, PathRequest, Pathfinder, Position, Road, RoadID, Turn, TurnID, TurnType, Zone,
+    BusStopID, ControlStopSign, ControlTrafficSignal, FerryRoute, FerryRouteID, Intersection,
+    IntersectionID, Lane, LaneID, LaneType, Map, MapEdits, MovementID, OffstreetParking,
+    ParkingLot, ParkingLotID, Path, PathConstraints, PathRequest, Pathfinder, Position, Road,
+    RoadID, Turn, TurnID, TurnType, Zone,
 };
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -26,6 +27,11 @@ pub struct MapConfig {
     /// false, no sidewalks will be inferred if not tagged in OSM, and separate sidewalks will be
     /// included.
     pub inferred_sidewalks: bool,
+    /// If true and sidewalks aren't inferred, roads without any tagged sidewalks get walkable
+    /// shoulders instead, so pedestrians can still reach everything. Walking on a shoulder is
+    /// penalized in pathfinding and may be slower in the simulation.
+    #[serde(default)]
+    pub walk_on_shoulders: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
@@ -34,6 +40,25 @@ pub enum DrivingSide {
     Left,
 }
 
+impl DrivingSide {
+    /// The turn that stays on the same side of the road, not crossing oncoming traffic. Right turns
+    /// when driving on the right.
+    pub fn curbside_turn(self) -> TurnType {
+        match self {
+            DrivingSide::Right => TurnType::Right,
+            DrivingSide::Left => TurnType::Left,
+        }
+    }
+
+    /// The turn that crosses oncoming traffic. Left turns when driving on the right.
+    pub fn crossing_turn(self) -> TurnType {
+        match self {
+            DrivingSide::Right => TurnType::Left,
+            DrivingSide::Left => TurnType::Right,
+        }
+    }
+}
+
 impl Map {
     pub fn new(path: String, timer: &mut Timer) -> Map {
         if path.contains("/maps/") {
@@ -145,6 +170,7 @@ impl Map {
             buildings: Vec::new(),
             bus_stops: BTreeMap::new(),
             bus_routes: Vec::new(),
+            ferry_routes: Vec::new(),
             areas: Vec::new(),
             parking_lots: Vec::new(),
             zones: Vec::new(),
@@ -163,6 +189,7 @@ impl Map {
                 driving_side: DrivingSide::Right,
                 bikes_can_use_bus_lanes: true,
                 inferred_sidewalks: true,
+                walk_on_shoulders: false,
             },
             pathfinder: Pathfinder::Dijkstra,
             pathfinder_dirty: false,
@@ -433,6 +460,22 @@ impl Map {
         &self.bus_routes
     }
 
+    pub fn get_fr(&self, route: FerryRouteID) -> &FerryRoute {
+        &self.ferry_routes[route.0]
+    }
+
+    pub fn all_ferry_routes(&self) -> &Vec<FerryRoute> {
+        &self.ferry_routes
+    }
+
+    /// All ferry routes that can be boarded at a border intersection
+    pub fn get_ferry_routes_at(&self, i: IntersectionID) -> Vec<&FerryRoute> {
+        self.ferry_routes
+            .iter()
+            .filter(|r| r.terminals.contains(&i))
+            .collect()
+    }
+
     pub fn get_bus_route(&self, name: &str) -> Option<&BusRoute> {
         self.bus_routes.iter().find(|r| r.full_name == name)
     }
@@ -553,6 +596,43 @@ impl Map {
         self.pathfinder.pathfind_avoiding_lanes(req, avoid, self)
     }
 
+    /// Like `pathfind_avoiding_lanes`, but also avoids lanes closed by work zones right now. The
+    /// lane where the path starts, and the one where it ends if that's before the work zone, can
+    /// still be used.
+    pub fn pathfind_avoiding_work_zones(
+        &self,
+        req: PathRequest,
+        mut avoid: BTreeSet<LaneID>,
+        now: Time,
+    ) -> Option<Path> {
+        avoid.extend(self.closed_lanes(now));
+        avoid.remove(&req.start.lane());
+        let end = self.get_l(req.end.lane());
+        if self
+            .get_r(end.parent)
+            .lane_closed_before(end, req.end.dist_along(), now)
+            .is_none()
+        {
+            avoid.remove(&end.id);
+        }
+        self.pathfind_avoiding_lanes(req, avoid)
+    }
+
+    /// All lanes closed by work zones at some time of day
+    pub fn closed_lanes(&self, now: Time) -> BTreeSet<LaneID> {
+        let mut lanes = BTreeSet::new();
+        // Work zones only come from edits
+        for r in &self.get_edits().changed_roads {
+            let road = self.get_r(*r);
+            for (l, _, _) in road.lanes_ltr() {
+                if road.lane_closed_until(l, now).is_some() {
+                    lanes.insert(l);
+                }
+            }
+        }
+        lanes
+    }
+
     pub fn should_use_transit(
         &self,
         start: Position,
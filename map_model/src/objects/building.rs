@@ -40,6 +40,8 @@ pub struct Building {
     pub label_center: Pt2D,
     pub amenities: Vec<Amenity>,
     pub bldg_type: BuildingType,
+    /// What the building is mostly used for, classified from OSM tags while importing
+    pub bldg_use: BuildingUse,
     pub parking: OffstreetParking,
     /// Depending on options while importing, these might be empty, to save file space.
     pub osm_tags: Tags,
@@ -80,6 +82,49 @@ pub enum BuildingType {
     Empty,
 }
 
+/// What kind of place a building is, for deciding how many trips it attracts. Unlike
+/// `BuildingType`, this doesn't estimate how many people live or work there.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BuildingUse {
+    Residential,
+    Office,
+    Retail,
+    School,
+    Industrial,
+    /// Anything else, like sheds, garages, churches, and untagged buildings
+    Other,
+}
+
+impl fmt::Display for BuildingUse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                BuildingUse::Residential => "residential",
+                BuildingUse::Office => "office",
+                BuildingUse::Retail => "retail",
+                BuildingUse::School => "school",
+                BuildingUse::Industrial => "industrial",
+                BuildingUse::Other => "other",
+            }
+        )
+    }
+}
+
+impl BuildingUse {
+    pub fn all() -> Vec<BuildingUse> {
+        vec![
+            BuildingUse::Residential,
+            BuildingUse::Office,
+            BuildingUse::Retail,
+            BuildingUse::School,
+            BuildingUse::Industrial,
+            BuildingUse::Other,
+        ]
+    }
+}
+
 impl BuildingType {
     pub fn has_residents(&self) -> bool {
         match self {
@@ -133,6 +178,11 @@ impl Building {
         self.sidewalk_pos.lane()
     }
 
+    /// Square meters of floor space across all levels
+    pub fn floor_area(&self) -> f64 {
+        self.levels * self.polygon.area()
+    }
+
     pub fn house_number(&self) -> Option<String> {
         let num = self.address.split(" ").next().unwrap();
         if num != "???" {
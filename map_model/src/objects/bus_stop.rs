@@ -1,14 +1,15 @@
 //! Bus stops and routes.
 // TODO Rename public transit -- these also cover light rail now.
 
+use std::collections::BTreeSet;
 use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_usize, serialize_usize};
-use geom::Time;
+use geom::{Distance, Time};
 
-use crate::{osm, LaneID, Map, PathConstraints, PathRequest, Position};
+use crate::{osm, LaneID, Map, Path, PathConstraints, PathRequest, Position, RoadID};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct BusStopID {
@@ -50,7 +51,7 @@ pub struct BusStop {
     pub is_train_stop: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct BusRoute {
     pub id: BusRouteID,
     pub full_name: String,
@@ -58,6 +59,11 @@ pub struct BusRoute {
     pub gtfs_trip_marker: Option<String>,
     pub osm_rel_id: osm::RelationID,
     pub stops: Vec<BusStopID>,
+    /// The stops before any map edits rerouted this.
+    pub orig_stops: Vec<BusStopID>,
+    /// Map edits can detour the route around these roads, like for construction. Vehicles still
+    /// use them to reach a stop on one of them. Originally empty.
+    pub avoid_roads: BTreeSet<RoadID>,
     /// May be a border or not. If not, is long enough for buses to spawn fully.
     pub start: LaneID,
     pub end_border: Option<LaneID>,
@@ -71,13 +77,18 @@ pub struct BusRoute {
 
 impl BusRoute {
     pub fn all_steps(&self, map: &Map) -> Vec<PathRequest> {
+        self.all_steps_with_stops(&self.stops, map)
+    }
+
+    /// Like all_steps, but for a proposed list of stops, to check a route edit before applying it.
+    pub fn all_steps_with_stops(&self, stops: &[BusStopID], map: &Map) -> Vec<PathRequest> {
         let mut steps = Vec::new();
         steps.push(PathRequest {
             start: Position::start(self.start),
-            end: map.get_bs(self.stops[0]).driving_pos,
+            end: map.get_bs(stops[0]).driving_pos,
             constraints: self.route_type,
         });
-        for pair in self.stops.windows(2) {
+        for pair in stops.windows(2) {
             steps.push(PathRequest {
                 start: map.get_bs(pair[0]).driving_pos,
                 end: map.get_bs(pair[1]).driving_pos,
@@ -86,7 +97,7 @@ impl BusRoute {
         }
         if let Some(end) = self.end_border {
             steps.push(PathRequest {
-                start: map.get_bs(*self.stops.last().unwrap()).driving_pos,
+                start: map.get_bs(*stops.last().unwrap()).driving_pos,
                 end: Position::end(end, map),
                 constraints: self.route_type,
             });
@@ -94,6 +105,42 @@ impl BusRoute {
         steps
     }
 
+    /// Can somebody board at `from` and ride to `to`? If `to` is None, they mean to ride off the
+    /// map at the end of the route.
+    pub fn serves(&self, from: BusStopID, to: Option<BusStopID>) -> bool {
+        match self.stops.iter().position(|bs| *bs == from) {
+            Some(idx) => match to {
+                Some(to) => self.stops[idx + 1..].contains(&to),
+                None => self.end_border.is_some(),
+            },
+            None => false,
+        }
+    }
+
+    /// Finds the path for one step of the route, detouring around `avoid_roads`.
+    pub fn pathfind(&self, req: PathRequest, map: &Map) -> Option<Path> {
+        BusRoute::pathfind_avoiding(req, &self.avoid_roads, map)
+    }
+
+    /// Like `pathfind`, but for a proposed set of roads to avoid, to check a route edit before
+    /// applying it.
+    pub fn pathfind_avoiding(
+        req: PathRequest,
+        avoid_roads: &BTreeSet<RoadID>,
+        map: &Map,
+    ) -> Option<Path> {
+        if avoid_roads.is_empty() {
+            return map.pathfind(req);
+        }
+        let mut avoid = BTreeSet::new();
+        for r in avoid_roads {
+            avoid.extend(map.get_r(*r).all_lanes());
+        }
+        avoid.remove(&req.start.lane());
+        avoid.remove(&req.end.lane());
+        map.pathfind_avoiding_lanes(req, avoid)
+    }
+
     pub fn plural_noun(&self) -> &'static str {
         if self.route_type == PathConstraints::Bus {
             "buses"
@@ -101,4 +148,30 @@ impl BusRoute {
             "trains"
         }
     }
+
+    /// Finds the route running the opposite direction that a vehicle could continue on after
+    /// reaching the end of this one. It has the same name and starts near where this route ends.
+    /// Routes ending at a border don't turn around.
+    pub fn find_reverse(&self, map: &Map) -> Option<BusRouteID> {
+        if self.end_border.is_some() {
+            return None;
+        }
+        let end = map.get_bs(*self.stops.last().unwrap()).driving_pos.pt(map);
+        map.all_bus_routes()
+            .iter()
+            .filter(|r| {
+                r.id != self.id
+                    && r.short_name == self.short_name
+                    && r.route_type == self.route_type
+            })
+            .map(|r| {
+                (
+                    r.id,
+                    map.get_bs(r.stops[0]).driving_pos.pt(map).dist_to(end),
+                )
+            })
+            .filter(|(_, dist)| *dist <= Distance::meters(500.0))
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(id, _)| id)
+    }
 }
@@ -0,0 +1,55 @@
+//! Ferries carry people, and sometimes their vehicles, across water. The route itself isn't part of
+//! the road network; each terminal is a border intersection where agents leave the map to board.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use abstutil::{deserialize_usize, serialize_usize};
+use geom::{Duration, Time};
+
+use crate::{osm, IntersectionID};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct FerryRouteID(
+    #[serde(
+        serialize_with = "serialize_usize",
+        deserialize_with = "deserialize_usize"
+    )]
+    pub usize,
+);
+
+impl fmt::Display for FerryRouteID {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FerryRoute #{}", self.0)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FerryRoute {
+    pub id: FerryRouteID,
+    pub name: String,
+    pub osm_way_id: osm::WayID,
+    /// The terminals that're on the map, always border intersections. If there's only one, the
+    /// other end of the route is off-map.
+    pub terminals: Vec<IntersectionID>,
+    /// How long the ferry takes to cross, not counting the wait to board
+    pub crossing_time: Duration,
+    /// Non-empty, times in order for one day when a ferry leaves each terminal.
+    pub departures: Vec<Time>,
+    /// If false, only pedestrians can board.
+    pub allows_vehicles: bool,
+}
+
+impl FerryRoute {
+    /// When does the next ferry leave, if there are any more today?
+    pub fn next_departure(&self, now: Time) -> Option<Time> {
+        self.departures.iter().find(|t| **t >= now).cloned()
+    }
+
+    /// If somebody boards at one terminal, where do they get off? None if the other end of the
+    /// route is off-map.
+    pub fn other_terminal(&self, from: IntersectionID) -> Option<IntersectionID> {
+        self.terminals.iter().find(|i| **i != from).cloned()
+    }
+}
@@ -4,7 +4,7 @@ use std::fmt;
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_usize, serialize_usize};
-use geom::{Distance, Polygon};
+use geom::{Distance, Duration, Polygon};
 
 use crate::{osm, DirectedRoadID, LaneID, Map, PathConstraints, Road, RoadID, TurnID};
 
@@ -54,6 +54,68 @@ pub struct Intersection {
 
     // TODO Maybe DirectedRoadIDs
     pub roads: BTreeSet<RoadID>,
+
+    pub capacity: IntersectionCapacity,
+    /// Roads that pedestrians can't cross here, because there's no crosswalk over them. Guessed
+    /// during import, then possibly edited.
+    pub no_crosswalks: BTreeSet<RoadID>,
+    /// no_crosswalks as guessed during import, before any edits.
+    pub orig_no_crosswalks: BTreeSet<RoadID>,
+    /// Railroad tracks cross at the same level here, so nobody can pass while a train goes by.
+    pub rail_crossing: bool,
+}
+
+/// Tunables limiting how many vehicles make it through an intersection, so the simulation can be
+/// calibrated against observed capacities. There's no data for these in OSM, so they start at zero,
+/// which doesn't hold anybody back.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct IntersectionCapacity {
+    /// At a traffic signal, nobody may start a turn for this long after each stage begins, letting
+    /// the intersection clear.
+    pub all_red_clearance: Duration,
+    /// At a traffic signal, vehicles wait this much longer after the all-red clearance before
+    /// starting a turn, like drivers reacting to the green light.
+    pub startup_lost_time: Duration,
+    /// The minimum time between vehicles from the same lane starting a turn.
+    pub saturation_headway: Duration,
+    /// If true, vehicles won't start a turn unless there's room for them in the lane they're
+    /// headed to, so they don't block the box. If false, they go anyway. If unset, the
+    /// simulation's options decide.
+    #[serde(default)]
+    pub dont_block_the_box: Option<bool>,
+}
+
+impl IntersectionCapacity {
+    /// Describes anything that differs from `other`.
+    pub fn diff(&self, other: &IntersectionCapacity) -> Vec<String> {
+        let mut changes = Vec::new();
+        if self.all_red_clearance != other.all_red_clearance {
+            changes.push(format!(
+                "all-red clearance: {} -> {}",
+                other.all_red_clearance, self.all_red_clearance
+            ));
+        }
+        if self.startup_lost_time != other.startup_lost_time {
+            changes.push(format!(
+                "startup lost time: {} -> {}",
+                other.startup_lost_time, self.startup_lost_time
+            ));
+        }
+        if self.saturation_headway != other.saturation_headway {
+            changes.push(format!(
+                "saturation headway: {} -> {}",
+                other.saturation_headway, self.saturation_headway
+            ));
+        }
+        if self.dont_block_the_box != other.dont_block_the_box {
+            changes.push(match self.dont_block_the_box {
+                Some(true) => "don't block the box".to_string(),
+                Some(false) => "allow blocking the box".to_string(),
+                None => "default blocking the box behavior".to_string(),
+            });
+        }
+        changes
+    }
 }
 
 impl Intersection {
@@ -1,10 +1,11 @@
+use std::collections::BTreeSet;
 use std::fmt;
 
 use enumset::EnumSet;
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_usize, serialize_usize, Tags};
-use geom::{Distance, PolyLine, Polygon, Speed};
+use geom::{Distance, PolyLine, Polygon, Speed, Time};
 
 use crate::raw::{OriginalRoad, RestrictionType};
 use crate::{
@@ -101,6 +102,9 @@ pub struct Road {
     pub orig_id: OriginalRoad,
     pub speed_limit: Speed,
     pub access_restrictions: AccessRestrictions,
+    /// Sorted by distance along center_pts
+    pub traffic_calming: Vec<TrafficCalming>,
+    pub work_zones: Vec<WorkZone>,
     pub zorder: isize,
 
     /// Invariant: A road must contain at least one child
@@ -149,8 +153,8 @@ impl Road {
         self.find_closest_lane(parking, |l| l.is_driving(), map)
     }
 
-    pub(crate) fn speed_limit_from_osm(&self) -> Speed {
-        if let Some(limit) = self.osm_tags.get(osm::MAXSPEED) {
+    pub(crate) fn speed_limit_from_osm(tags: &Tags) -> Speed {
+        if let Some(limit) = tags.get(osm::MAXSPEED) {
             if let Ok(kmph) = limit.parse::<f64>() {
                 return Speed::km_per_hour(kmph);
             }
@@ -166,17 +170,14 @@ impl Road {
         }
 
         // These're half reasonable guesses. Better to explicitly tag in OSM.
-        if self
-            .osm_tags
-            .is_any(osm::HIGHWAY, vec!["primary", "secondary", "motorway_link"])
-        {
+        if tags.is_any(osm::HIGHWAY, vec!["primary", "secondary", "motorway_link"]) {
             return Speed::miles_per_hour(40.0);
         }
-        if self.osm_tags.is(osm::HIGHWAY, "living_street") {
+        if tags.is(osm::HIGHWAY, "living_street") {
             // about 12mph
             return Speed::km_per_hour(20.0);
         }
-        if self.is_service() {
+        if tags.is(osm::HIGHWAY, "service") {
             return Speed::miles_per_hour(10.0);
         }
         Speed::miles_per_hour(20.0)
@@ -231,6 +232,114 @@ impl Road {
         }
     }
 
+    /// Where traffic calming is along a lane of this road, measured along the lane.
+    pub fn traffic_calming_on(&self, lane: &Lane) -> Vec<(Distance, TrafficCalmingKind)> {
+        let road_len = self.center_pts.length();
+        self.traffic_calming
+            .iter()
+            .map(|tc| {
+                // Lanes aren't exactly as long as the road, so scale
+                let pct = tc.dist_along / road_len;
+                let pct = if self.dir(lane.id) == Direction::Fwd {
+                    pct
+                } else {
+                    1.0 - pct
+                };
+                (lane.length() * pct, tc.kind)
+            })
+            .collect()
+    }
+
+    /// Where work zones close a lane of this road, as a range measured along the lane. The start
+    /// is always upstream.
+    pub fn work_zones_on(&self, lane: &Lane) -> Vec<(Distance, Distance, &WorkZone)> {
+        let road_len = self.center_pts.length();
+        let idx = self.offset(lane.id);
+        self.work_zones
+            .iter()
+            .filter(|wz| wz.closed_lanes.contains(&idx))
+            .map(|wz| {
+                let (pct1, pct2) = (wz.start_dist / road_len, wz.end_dist / road_len);
+                let (pct1, pct2) = if self.dir(lane.id) == Direction::Fwd {
+                    (pct1, pct2)
+                } else {
+                    (1.0 - pct2, 1.0 - pct1)
+                };
+                (lane.length() * pct1, lane.length() * pct2, wz)
+            })
+            .collect()
+    }
+
+    /// If a work zone closes any part of this lane right now, returns when it reopens.
+    pub fn lane_closed_until(&self, l: LaneID, now: Time) -> Option<Time> {
+        if self.work_zones.is_empty() {
+            return None;
+        }
+        let idx = self.offset(l);
+        self.work_zones
+            .iter()
+            .filter(|wz| wz.is_active(now) && wz.closed_lanes.contains(&idx))
+            .map(|wz| wz.end_time)
+            .max()
+    }
+
+    /// Like `lane_closed_until`, but only counts work zones starting before some distance along
+    /// the lane. Somebody stopping before a work zone can still use the lane.
+    pub fn lane_closed_before(&self, lane: &Lane, dist: Distance, now: Time) -> Option<Time> {
+        if self.work_zones.is_empty() {
+            return None;
+        }
+        self.work_zones_on(lane)
+            .into_iter()
+            .filter(|(start, _, wz)| wz.is_active(now) && *start < dist)
+            .map(|(_, _, wz)| wz.end_time)
+            .max()
+    }
+
+    /// Where drivers slow down for work zones along a lane of this road right now, measured along
+    /// the lane. This covers the lanes left open next to a work zone, starting a bit before it
+    /// where traffic merges, and the approach to the work zone in a closed lane.
+    pub fn work_zone_slow_zones(&self, lane: &Lane, now: Time) -> Vec<(Distance, Distance, Speed)> {
+        let mut zones = Vec::new();
+        if self.work_zones.is_empty() {
+            return zones;
+        }
+        let road_len = self.center_pts.length();
+        let dir = self.dir(lane.id);
+        let idx = self.offset(lane.id);
+        for wz in &self.work_zones {
+            // Only lanes going the same way as a closed lane are affected
+            if !wz.is_active(now)
+                || !wz.closed_lanes.iter().any(|other| {
+                    self.lanes_ltr
+                        .get(*other)
+                        .map(|(_, d, _)| *d == dir)
+                        .unwrap_or(false)
+                })
+            {
+                continue;
+            }
+            let (pct1, pct2) = (wz.start_dist / road_len, wz.end_dist / road_len);
+            let (pct1, pct2) = if dir == Direction::Fwd {
+                (pct1, pct2)
+            } else {
+                (1.0 - pct2, 1.0 - pct1)
+            };
+            let start = lane.length() * pct1;
+            let end = if wz.closed_lanes.contains(&idx) {
+                start
+            } else {
+                lane.length() * pct2
+            };
+            zones.push((
+                start - WorkZone::TAPER,
+                end,
+                WorkZone::max_speed(self.speed_limit),
+            ));
+        }
+        zones
+    }
+
     pub fn get_half_width(&self, map: &Map) -> Distance {
         self.all_lanes()
             .into_iter()
@@ -336,6 +445,11 @@ impl Road {
         self.osm_tags.is(osm::HIGHWAY, "service")
     }
 
+    /// Drawbridges, swing bridges, and other bridges that periodically open for boats
+    pub fn is_movable_bridge(&self) -> bool {
+        self.osm_tags.is("bridge", "movable") || self.osm_tags.contains_key("bridge:movable")
+    }
+
     pub fn common_endpt(&self, other: &Road) -> IntersectionID {
         if self.src_i == other.src_i || self.src_i == other.dst_i {
             self.src_i
@@ -350,12 +464,12 @@ impl Road {
         self.access_restrictions != AccessRestrictions::new()
     }
 
-    pub(crate) fn access_restrictions_from_osm(&self) -> AccessRestrictions {
-        let allow_through_traffic = if self.osm_tags.is("access", "private") {
+    pub(crate) fn access_restrictions_from_osm(tags: &Tags) -> AccessRestrictions {
+        let allow_through_traffic = if tags.is("access", "private") {
             EnumSet::new()
-        } else if self.osm_tags.is(osm::HIGHWAY, "living_street") {
+        } else if tags.is(osm::HIGHWAY, "living_street") {
             let mut allow = PathConstraints::Pedestrian | PathConstraints::Bike;
-            if self.osm_tags.is("psv", "yes") || self.osm_tags.is("bus", "yes") {
+            if tags.is("psv", "yes") || tags.is("bus", "yes") {
                 allow |= PathConstraints::Bus;
             }
             allow
@@ -408,8 +522,8 @@ impl Road {
         result
     }
 
-    /// lane must belong to this road. Offset 0 is the centermost lane on each side of a road, then
-    /// it counts up from there.
+    /// lane must belong to this road. Offset 0 is the leftmost lane in the direction of travel, then
+    /// it counts up from there. When driving on the right, that's the centermost lane.
     pub(crate) fn dir_and_offset(&self, lane: LaneID) -> (Direction, usize) {
         for &dir in [Direction::Fwd, Direction::Back].iter() {
             if let Some(idx) = self.children(dir).iter().position(|pair| pair.0 == lane) {
@@ -419,6 +533,9 @@ impl Road {
         panic!("{} doesn't contain {}", self.id, lane);
     }
 
+    /// Lanes going in one direction, ordered from left to right in the direction of travel. When
+    /// driving on the right, this goes from the center of the road out; when driving on the left,
+    /// it goes from the curb in.
     // TODO Deprecated
     pub(crate) fn children(&self, dir: Direction) -> Vec<(LaneID, LaneType)> {
         if dir == Direction::Fwd {
@@ -428,7 +545,7 @@ impl Road {
         }
     }
 
-    /// Returns lanes from the "center" going out
+    /// Like children, ordered left to right in the direction of travel
     pub(crate) fn incoming_lanes(&self, i: IntersectionID) -> Vec<(LaneID, LaneType)> {
         if self.src_i == i {
             self.children_backwards()
@@ -439,7 +556,7 @@ impl Road {
         }
     }
 
-    /// Returns lanes from the "center" going out
+    /// Like children, ordered left to right in the direction of travel
     pub(crate) fn outgoing_lanes(&self, i: IntersectionID) -> Vec<(LaneID, LaneType)> {
         if self.src_i == i {
             self.children_forwards()
@@ -450,3 +567,118 @@ impl Road {
         }
     }
 }
+
+/// Something placed along a road to slow down vehicles
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum TrafficCalmingKind {
+    SpeedHump,
+    /// A crosswalk raised to the level of the sidewalk
+    RaisedCrossing,
+    /// Alternating curb extensions, forcing vehicles to weave
+    Chicane,
+    /// Marked enforcement. Nothing physically slows vehicles down, but drivers who'd otherwise
+    /// speed keep to the speed limit near it.
+    SpeedCamera,
+}
+
+impl TrafficCalmingKind {
+    pub fn all() -> Vec<TrafficCalmingKind> {
+        vec![
+            TrafficCalmingKind::SpeedHump,
+            TrafficCalmingKind::RaisedCrossing,
+            TrafficCalmingKind::Chicane,
+            TrafficCalmingKind::SpeedCamera,
+        ]
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            TrafficCalmingKind::SpeedHump => "speed hump",
+            TrafficCalmingKind::RaisedCrossing => "raised crossing",
+            TrafficCalmingKind::Chicane => "chicane",
+            TrafficCalmingKind::SpeedCamera => "speed camera",
+        }
+    }
+
+    /// How fast vehicles can go near this, on a road with some speed limit
+    pub fn max_speed(self, speed_limit: Speed) -> Speed {
+        match self {
+            TrafficCalmingKind::SpeedHump => Speed::miles_per_hour(15.0),
+            TrafficCalmingKind::RaisedCrossing => Speed::miles_per_hour(15.0),
+            TrafficCalmingKind::Chicane => Speed::miles_per_hour(20.0),
+            TrafficCalmingKind::SpeedCamera => speed_limit,
+        }
+    }
+
+    /// How long the physical feature is, along the road
+    pub fn length(self) -> Distance {
+        match self {
+            TrafficCalmingKind::SpeedHump => Distance::meters(4.0),
+            TrafficCalmingKind::RaisedCrossing => Distance::meters(6.0),
+            TrafficCalmingKind::Chicane => Distance::meters(20.0),
+            TrafficCalmingKind::SpeedCamera => Distance::meters(1.0),
+        }
+    }
+
+    /// Vehicles slow down for this distance centered on the feature, to account for braking
+    /// before and accelerating after.
+    pub fn slow_zone(self) -> Distance {
+        match self {
+            // Drivers see the warning signs well ahead, and don't speed up again right after
+            TrafficCalmingKind::SpeedCamera => Distance::meters(200.0),
+            _ => self.length() + Distance::meters(30.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TrafficCalming {
+    pub kind: TrafficCalmingKind,
+    /// Along the road's center_pts
+    pub dist_along: Distance,
+}
+
+/// Construction closing some lanes along part of a road, for part of the day. Vehicles only change
+/// lanes at intersections, so while it's active, nobody can enter a closed lane unless they stop
+/// before the closed part. Drivers merge into the remaining lanes before reaching the road, and
+/// anybody already in a closed lane finishes driving through. Everybody going the same way slows
+/// down past the work zone, starting a bit before it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorkZone {
+    /// Indices into the road's lanes, from left to right
+    pub closed_lanes: BTreeSet<usize>,
+    /// Along the road's center_pts
+    pub start_dist: Distance,
+    pub end_dist: Distance,
+    /// The simulation only covers one day, so these are just times of day.
+    pub start_time: Time,
+    pub end_time: Time,
+}
+
+impl WorkZone {
+    /// How far before the work zone drivers start slowing down and merging
+    pub const TAPER: Distance = Distance::const_meters(50.0);
+
+    /// How fast vehicles can go past a work zone, on a road with some speed limit
+    pub fn max_speed(speed_limit: Speed) -> Speed {
+        speed_limit.min(Speed::miles_per_hour(20.0))
+    }
+
+    pub fn is_active(&self, now: Time) -> bool {
+        now >= self.start_time && now < self.end_time
+    }
+
+    /// Like "2 lanes closed from 9:00AM to 3:00PM"
+    pub fn describe(&self) -> String {
+        format!(
+            "{} closed from {} to {}",
+            if self.closed_lanes.len() == 1 {
+                "1 lane".to_string()
+            } else {
+                format!("{} lanes", self.closed_lanes.len())
+            },
+            self.start_time.ampm_tostring(),
+            self.end_time.ampm_tostring()
+        )
+    }
+}
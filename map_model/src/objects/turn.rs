@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 use abstutil::MultiMap;
 use geom::{Angle, Distance, PolyLine, Pt2D};
 
-use crate::{DirectedRoadID, Direction, IntersectionID, LaneID, Map};
+use crate::{DirectedRoadID, Direction, DrivingSide, IntersectionID, LaneID, LaneType, Map};
 
 /// Turns are uniquely identified by their (src, dst) lanes and their parent intersection.
 /// Intersection is needed to distinguish crosswalks that exist at two ends of a sidewalk.
@@ -125,17 +125,17 @@ impl Turn {
         let from_idx = {
             let mut cnt = 0;
             let r = map.get_r(from.parent);
-            for (l, lt) in r.children(r.dir(from.id)).iter().rev() {
-                if from.lane_type != *lt {
+            for (l, lt) in curb_to_center(r.children(r.dir(from.id)), map) {
+                if from.lane_type != lt {
                     continue;
                 }
                 if map
-                    .get_turns_from_lane(*l)
+                    .get_turns_from_lane(l)
                     .into_iter()
                     .any(|t| map.get_l(t.id.dst).parent == to.parent)
                 {
                     cnt += 1;
-                    if from.id == *l {
+                    if from.id == l {
                         break;
                     }
                 }
@@ -148,12 +148,12 @@ impl Turn {
         let to_idx = {
             let mut cnt = 0;
             let r = map.get_r(to.parent);
-            for (l, lt) in r.children(r.dir(to.id)).iter().rev() {
-                if to.lane_type != *lt {
+            for (l, lt) in curb_to_center(r.children(r.dir(to.id)), map) {
+                if to.lane_type != lt {
                     continue;
                 }
                 cnt += 1;
-                if to.id == *l {
+                if to.id == l {
                     break;
                 }
             }
@@ -172,13 +172,21 @@ impl Turn {
         // matter.
         let lt_cost = if to.is_biking() || to.is_bus() { 0 } else { 1 };
 
-        // Keep right (in the US)
+        // Keep to the curb (right in the US)
         let slow_lane = if to_idx > 1 { 1 } else { 0 };
 
         (lt_cost, lc_cost, slow_lane)
     }
 }
 
+// Reorders lanes from Road::children, so the one farthest from the center line is first.
+fn curb_to_center(mut lanes: Vec<(LaneID, LaneType)>, map: &Map) -> Vec<(LaneID, LaneType)> {
+    if map.get_config().driving_side == DrivingSide::Right {
+        lanes.reverse();
+    }
+    lanes
+}
+
 /// One road usually has 4 crosswalks, each a singleton Movement. We need all of the information
 /// here to keep each crosswalk separate.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -11,7 +11,7 @@ use enumset::EnumSet;
 use petgraph::graphmap::DiGraphMap;
 use serde::{Deserialize, Serialize};
 
-use crate::pathfind::{driving_cost, walking_cost, WalkingNode};
+use crate::pathfind::{driving_cost, walking_cost, walking_lane_cost, WalkingNode};
 use crate::{
     IntersectionID, LaneID, Map, Path, PathConstraints, PathRequest, PathStep, RoadID, TurnID,
 };
@@ -112,7 +112,7 @@ impl Zone {
             for l in map.get_r(*r).all_lanes() {
                 let l = map.get_l(l);
                 if l.is_walkable() {
-                    let cost = walking_cost(l.length());
+                    let cost = walking_lane_cost(l, map);
                     let n1 = WalkingNode::SidewalkEndpoint(l.id, true);
                     let n2 = WalkingNode::SidewalkEndpoint(l.id, false);
                     graph.add_edge(n1, n2, cost);
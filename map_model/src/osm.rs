@@ -1,9 +1,12 @@
 //! Useful utilities for working with OpenStreetMap.
 
+use std::collections::BTreeMap;
 use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
+use abstutil::{deserialize_btreemap, serialize_btreemap, Tags, Timer};
+
 // These are common OSM keys. Keys used in just one or two places don't really need to be defined
 // here.
 
@@ -30,6 +33,11 @@ pub const ENDPT_BACK: &str = "abst:endpt_back";
 // Any roads might have these.
 pub const INFERRED_PARKING: &str = "abst:parking_inferred";
 pub const INFERRED_SIDEWALKS: &str = "abst:sidewalks_inferred";
+// Footways generated across a park or plaza. The value is the area's OSM ID.
+pub const PATH_THROUGH_AREA: &str = "abst:path_through_area";
+// Stairs, footbridges, underpasses, and the footways leading to them, kept even though sidewalks
+// are inferred.
+pub const WALKING_LINK: &str = "abst:walking_link";
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub enum RoadRank {
@@ -98,3 +106,44 @@ impl OsmID {
         }
     }
 }
+
+/// Local corrections to OSM tags, like a way tagged with the wrong number of lanes. These're made
+/// in the game and re-applied every time the importer runs, until they're fixed upstream.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TagFixes {
+    /// An empty value removes that tag.
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    pub ways: BTreeMap<WayID, BTreeMap<String, String>>,
+}
+
+impl TagFixes {
+    /// Loads the fixes for a city, or none if nothing's been fixed there yet.
+    pub fn load(city: &str, timer: &mut Timer) -> TagFixes {
+        abstutil::maybe_read_json(abstutil::path_osm_fixes(city), timer).unwrap_or_default()
+    }
+
+    pub fn save(&self, city: &str) {
+        abstutil::write_json(abstutil::path_osm_fixes(city), self);
+    }
+
+    /// Overrides tags on one way. Returns true if anything was fixed.
+    pub fn apply(&self, id: WayID, tags: &mut Tags) -> bool {
+        let fixes = match self.ways.get(&id) {
+            Some(fixes) => fixes,
+            None => {
+                return false;
+            }
+        };
+        for (k, v) in fixes {
+            if v.is_empty() {
+                tags.remove(k);
+            } else {
+                tags.insert(k.clone(), v.clone());
+            }
+        }
+        true
+    }
+}
@@ -6,7 +6,7 @@ use petgraph::graphmap::DiGraphMap;
 
 use crate::pathfind::driving::driving_cost;
 use crate::pathfind::walking::{
-    one_step_walking_path, walking_cost, walking_path_to_steps, WalkingNode,
+    one_step_walking_path, walking_cost, walking_lane_cost, walking_path_to_steps, WalkingNode,
 };
 use crate::{LaneID, Map, Path, PathConstraints, PathRequest, PathStep, TurnID};
 
@@ -46,7 +46,7 @@ pub fn pathfind_avoiding_lanes(
     avoid: BTreeSet<LaneID>,
     map: &Map,
 ) -> Option<Path> {
-    assert_eq!(req.constraints, PathConstraints::Car);
+    assert_ne!(req.constraints, PathConstraints::Pedestrian);
     let mut graph: DiGraphMap<LaneID, TurnID> = DiGraphMap::new();
     for l in map.all_lanes() {
         if req.constraints.can_use(l, map) && !avoid.contains(&l.id) {
@@ -88,7 +88,7 @@ pub fn build_graph_for_pedestrians(map: &Map) -> DiGraphMap<WalkingNode, usize>
     let mut graph: DiGraphMap<WalkingNode, usize> = DiGraphMap::new();
     for l in map.all_lanes() {
         if l.is_walkable() {
-            let cost = walking_cost(l.length());
+            let cost = walking_lane_cost(l, map);
             let n1 = WalkingNode::SidewalkEndpoint(l.id, true);
             let n2 = WalkingNode::SidewalkEndpoint(l.id, false);
             graph.add_edge(n1, n2, cost);
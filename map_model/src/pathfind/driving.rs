@@ -7,10 +7,13 @@ use serde::{Deserialize, Serialize};
 use thread_local::ThreadLocal;
 
 use abstutil::MultiMap;
+use geom::Distance;
 
 use crate::pathfind::node_map::{deserialize_nodemap, NodeMap};
 use crate::pathfind::uber_turns::{IntersectionCluster, UberTurn};
-use crate::{Lane, LaneID, Map, Path, PathConstraints, PathRequest, PathStep, Turn, TurnID};
+use crate::{
+    Lane, LaneID, Map, Path, PathConstraints, PathRequest, PathStep, Traversable, Turn, TurnID,
+};
 
 #[derive(Serialize, Deserialize)]
 pub struct VehiclePathfinder {
@@ -225,7 +228,12 @@ pub fn driving_cost(lane: &Lane, turn: &Turn, constraints: PathConstraints, map:
     let base = match constraints {
         PathConstraints::Car | PathConstraints::Train => {
             // Prefer slightly longer route on faster roads
-            let t1 = lane.length() / map.get_r(lane.parent).speed_limit;
+            let t1 = Traversable::Lane(lane.id).crossing_time(
+                Distance::ZERO,
+                lane.length(),
+                map.get_r(lane.parent).speed_limit,
+                map,
+            );
             let t2 = turn.geom.length() / map.get_parent(turn.id.dst).speed_limit;
             (t1 + t2).inner_seconds()
         }
@@ -252,7 +260,12 @@ pub fn driving_cost(lane: &Lane, turn: &Turn, constraints: PathConstraints, map:
         }
         PathConstraints::Bus => {
             // Like Car, but prefer bus lanes.
-            let t1 = lane.length() / map.get_r(lane.parent).speed_limit;
+            let t1 = Traversable::Lane(lane.id).crossing_time(
+                Distance::ZERO,
+                lane.length(),
+                map.get_r(lane.parent).speed_limit,
+                map,
+            );
             let t2 = turn.geom.length() / map.get_parent(turn.id.dst).speed_limit;
             let lt_penalty = if lane.is_bus() {
                 1.0
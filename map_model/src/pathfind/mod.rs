@@ -7,12 +7,12 @@ use enumset::EnumSetType;
 use serde::{Deserialize, Serialize};
 
 use abstutil::Timer;
-use geom::{Distance, PolyLine, EPSILON_DIST};
+use geom::{Distance, PolyLine, Time, EPSILON_DIST};
 
 pub use self::ch::ContractionHierarchyPathfinder;
 pub use self::dijkstra::{build_graph_for_pedestrians, build_graph_for_vehicles};
 pub use self::driving::driving_cost;
-pub use self::walking::{walking_cost, WalkingNode};
+pub use self::walking::{walking_cost, walking_lane_cost, WalkingNode};
 use crate::{
     osm, BusRouteID, BusStopID, Lane, LaneID, LaneType, Map, Position, Traversable, TurnID,
     UberTurn,
@@ -307,6 +307,35 @@ impl Path {
         self.steps[self.steps.len() - 1]
     }
 
+    /// Where the path ends along the last step
+    pub fn end_dist(&self) -> Distance {
+        self.end_dist
+    }
+
+    /// If work zones close any lane along the rest of this path right now, returns when the last
+    /// of them reopens. The current step doesn't count, and the last step only counts if a work
+    /// zone starts before the path ends.
+    pub fn closed_by_work_zones(&self, now: Time, map: &Map) -> Option<Time> {
+        let last_idx = self.steps.len() - 1;
+        self.steps
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter_map(|(idx, step)| match step {
+                PathStep::Lane(l) => {
+                    let lane = map.get_l(*l);
+                    let dist = if idx == last_idx {
+                        self.end_dist
+                    } else {
+                        lane.length()
+                    };
+                    map.get_r(lane.parent).lane_closed_before(lane, dist, now)
+                }
+                _ => None,
+            })
+            .max()
+    }
+
     /// dist_ahead is unlimited when None.
     pub fn trace(
         &self,
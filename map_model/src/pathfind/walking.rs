@@ -13,10 +13,13 @@ use geom::{Distance, Speed};
 use crate::pathfind::driving::VehiclePathfinder;
 use crate::pathfind::node_map::{deserialize_nodemap, NodeMap};
 use crate::{
-    BusRoute, BusRouteID, BusStopID, IntersectionID, LaneID, Map, Path, PathConstraints,
+    osm, BusRoute, BusRouteID, BusStopID, IntersectionID, Lane, LaneID, Map, Path, PathConstraints,
     PathRequest, PathStep, Position,
 };
 
+// A 10% grade makes walking 50% more expensive
+const GRADE_PENALTY: f64 = 5.0;
+
 #[derive(Serialize, Deserialize)]
 pub struct SidewalkPathfinder {
     #[serde(serialize_with = "serialize_32", deserialize_with = "deserialize_32")]
@@ -108,6 +111,18 @@ impl SidewalkPathfinder {
         bus_graph: &VehiclePathfinder,
         train_graph: &VehiclePathfinder,
     ) {
+        // Creating a route may have added stops. Then the nodes change, so start over. Stops
+        // removed by undoing that just leave behind nodes without any edges.
+        if self.use_transit
+            && map
+                .all_bus_stops()
+                .keys()
+                .any(|bs| !self.nodes.contains(WalkingNode::RideBus(*bs)))
+        {
+            *self = SidewalkPathfinder::new(map, true, bus_graph, train_graph);
+            return;
+        }
+
         // The NodeMap is all sidewalks, bus stops, and borders -- it won't change. So we can also
         // reuse the node ordering.
         let input_graph =
@@ -239,11 +254,7 @@ fn make_input_graph(
                 .allow_through_traffic
                 .contains(PathConstraints::Pedestrian)
         {
-            let mut cost = walking_cost(l.length());
-            // TODO Tune this penalty, along with many others.
-            if l.is_shoulder() {
-                cost *= 2;
-            }
+            let cost = walking_lane_cost(l, map);
             let n1 = nodes.get(WalkingNode::SidewalkEndpoint(l.id, true));
             let n2 = nodes.get(WalkingNode::SidewalkEndpoint(l.id, false));
             input_graph.add_edge(n1, n2, cost);
@@ -391,6 +402,23 @@ pub fn walking_cost(dist: Distance) -> usize {
     (time.inner_seconds().round() as usize).max(1)
 }
 
+/// The cost of walking the full length of a sidewalk or shoulder. Walking along the edge of the
+/// road is uncomfortable, so shoulders are only used when there's no reasonable sidewalk nearby.
+pub fn walking_lane_cost(lane: &Lane, map: &Map) -> usize {
+    let road = map.get_r(lane.parent);
+    let mut cost = walking_cost(lane.length());
+    // TODO Tune these penalties, along with many others.
+    if lane.is_shoulder() {
+        cost *= 2;
+    }
+    if road.osm_tags.is(osm::HIGHWAY, "steps") {
+        cost *= 2;
+    }
+    // Going up or down steep hills is slower. The graph is undirected, so just use the magnitude.
+    let grade = road.percent_grade(map).abs();
+    ((cost as f64) * (1.0 + GRADE_PENALTY * grade)).round() as usize
+}
+
 pub fn walking_path_to_steps(path: Vec<WalkingNode>, map: &Map) -> Vec<PathStep> {
     let mut steps: Vec<PathStep> = Vec::new();
 
@@ -0,0 +1,96 @@
+//! Pick out roads matching some conditions, like "residential streets faster than 25mph in this
+//! neighborhood," for bulk edits, layers, and exports.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use geom::{Distance, Polygon, Speed};
+
+use crate::{LaneID, LaneType, Map, Road, RoadID};
+
+/// Conditions on a road, combined with `and`, `or`, and `not`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum RoadQuery {
+    All,
+    /// At least one lane of this type
+    HasLaneType(LaneType),
+    /// An OSM tag with exactly this value
+    Tag(String, String),
+    /// An OSM tag with any value
+    HasTag(String),
+    FasterThan(Speed),
+    SlowerThan(Speed),
+    LongerThan(Distance),
+    ShorterThan(Distance),
+    /// The middle of the road is inside this polygon, like a neighborhood boundary
+    Within(Polygon),
+    And(Vec<RoadQuery>),
+    Or(Vec<RoadQuery>),
+    Not(Box<RoadQuery>),
+}
+
+impl RoadQuery {
+    pub fn matches(&self, r: &Road) -> bool {
+        match self {
+            RoadQuery::All => true,
+            RoadQuery::HasLaneType(lt) => r.lanes_ltr().iter().any(|(_, _, x)| x == lt),
+            RoadQuery::Tag(k, v) => r.osm_tags.is(k, v),
+            RoadQuery::HasTag(k) => r.osm_tags.contains_key(k),
+            RoadQuery::FasterThan(speed) => r.speed_limit > *speed,
+            RoadQuery::SlowerThan(speed) => r.speed_limit < *speed,
+            RoadQuery::LongerThan(dist) => r.center_pts.length() > *dist,
+            RoadQuery::ShorterThan(dist) => r.center_pts.length() < *dist,
+            RoadQuery::Within(polygon) => polygon.contains_pt(r.center_pts.middle()),
+            RoadQuery::And(queries) => queries.iter().all(|q| q.matches(r)),
+            RoadQuery::Or(queries) => queries.iter().any(|q| q.matches(r)),
+            RoadQuery::Not(q) => !q.matches(r),
+        }
+    }
+
+    /// All roads matching the query
+    pub fn roads(&self, map: &Map) -> BTreeSet<RoadID> {
+        map.all_roads()
+            .iter()
+            .filter(|r| self.matches(r))
+            .map(|r| r.id)
+            .collect()
+    }
+
+    /// The lanes of one type on all roads matching the query
+    pub fn lanes(&self, map: &Map, lt: LaneType) -> BTreeSet<LaneID> {
+        let mut lanes = BTreeSet::new();
+        for r in self.roads(map) {
+            for (l, _, x) in map.get_r(r).lanes_ltr() {
+                if x == lt {
+                    lanes.insert(l);
+                }
+            }
+        }
+        lanes
+    }
+
+    pub fn and(self, other: RoadQuery) -> RoadQuery {
+        match self {
+            RoadQuery::And(mut queries) => {
+                queries.push(other);
+                RoadQuery::And(queries)
+            }
+            q => RoadQuery::And(vec![q, other]),
+        }
+    }
+
+    pub fn or(self, other: RoadQuery) -> RoadQuery {
+        match self {
+            RoadQuery::Or(mut queries) => {
+                queries.push(other);
+                RoadQuery::Or(queries)
+            }
+            q => RoadQuery::Or(vec![q, other]),
+        }
+    }
+
+    pub fn negate(self) -> RoadQuery {
+        RoadQuery::Not(Box::new(self))
+    }
+}
@@ -9,7 +9,7 @@ use petgraph::graphmap::DiGraphMap;
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_btreemap, serialize_btreemap, MapName, Tags, Timer};
-use geom::{Circle, Distance, GPSBounds, PolyLine, Polygon, Pt2D};
+use geom::{Circle, Distance, Duration, GPSBounds, PolyLine, Polygon, Pt2D};
 
 use crate::make::initial::lane_specs::get_lane_specs_ltr;
 use crate::{
@@ -35,6 +35,7 @@ pub struct RawMap {
     )]
     pub buildings: BTreeMap<osm::OsmID, RawBuilding>,
     pub bus_routes: Vec<RawBusRoute>,
+    pub ferry_routes: Vec<RawFerryRoute>,
     pub areas: Vec<RawArea>,
     pub parking_lots: Vec<RawParkingLot>,
     pub parking_aisles: Vec<(osm::WayID, Vec<Pt2D>)>,
@@ -91,6 +92,7 @@ impl RawMap {
             intersections: BTreeMap::new(),
             buildings: BTreeMap::new(),
             bus_routes: Vec::new(),
+            ferry_routes: Vec::new(),
             areas: Vec::new(),
             parking_lots: Vec::new(),
             parking_aisles: Vec::new(),
@@ -101,6 +103,7 @@ impl RawMap {
                 driving_side: DrivingSide::Right,
                 bikes_can_use_bus_lanes: true,
                 inferred_sidewalks: true,
+                walk_on_shoulders: false,
             },
         }
     }
@@ -310,6 +313,8 @@ pub struct RawIntersection {
     pub point: Pt2D,
     pub intersection_type: IntersectionType,
     pub elevation: Distance,
+    /// Does a road or path cross railroad tracks here?
+    pub rail_crossing: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -378,6 +383,18 @@ pub struct RawBusRoute {
     pub all_pts: Vec<(osm::NodeID, Pt2D)>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RawFerryRoute {
+    pub name: String,
+    pub osm_way_id: osm::WayID,
+    /// Border intersections at the ends of the route inside the map. Non-empty.
+    pub terminals: Vec<osm::NodeID>,
+    pub crossing_time: Duration,
+    /// How often a ferry leaves each terminal during the day
+    pub headway: Duration,
+    pub allows_vehicles: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RawBusStop {
     pub name: String,
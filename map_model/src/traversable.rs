@@ -2,7 +2,7 @@ use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
-use geom::{Angle, Distance, PolyLine, Pt2D, Speed};
+use geom::{Angle, Distance, Duration, PolyLine, Pt2D, Speed, Time};
 
 use crate::{LaneID, Map, TurnID};
 
@@ -208,6 +208,81 @@ impl Traversable {
         }
     }
 
+    /// How long it takes to go from start to end at some speed, also slowing down for any traffic
+    /// calming along the way. The speed may be over the speed limit, for drivers who speed. Work
+    /// zones only exist for part of the day, so they're not counted.
+    pub fn crossing_time(
+        &self,
+        start: Distance,
+        end: Distance,
+        speed: Speed,
+        map: &Map,
+    ) -> Duration {
+        self.crossing_profile(start, end, speed, None, map)
+            .last()
+            .unwrap()
+            .1
+    }
+
+    /// Like `crossing_time`, but also says when each slow zone along the way is reached. Returns
+    /// distances along this, paired with the time it takes to get there from start. The first is
+    /// always start and the last is always end, and the speed is constant between each pair.
+    /// Where slow zones overlap, only the slowest one counts. If a time is given, work zones active
+    /// then also slow things down.
+    pub fn crossing_profile(
+        &self,
+        start: Distance,
+        end: Distance,
+        speed: Speed,
+        now: Option<Time>,
+        map: &Map,
+    ) -> Vec<(Distance, Duration)> {
+        let mut zones = Vec::new();
+        if let Traversable::Lane(l) = self {
+            let lane = map.get_l(*l);
+            let road = map.get_r(lane.parent);
+            for (dist, kind) in road.traffic_calming_on(lane) {
+                let max_speed = kind.max_speed(road.speed_limit);
+                if max_speed < speed {
+                    let half = kind.slow_zone() / 2.0;
+                    zones.push((dist - half, dist + half, max_speed));
+                }
+            }
+            if let Some(now) = now {
+                zones.extend(
+                    road.work_zone_slow_zones(lane, now)
+                        .into_iter()
+                        .filter(|(_, _, max_speed)| *max_speed < speed),
+                );
+            }
+        }
+
+        let mut dists = vec![start, end];
+        for (zone_start, zone_end, _) in &zones {
+            for dist in [*zone_start, *zone_end].iter() {
+                if *dist > start && *dist < end {
+                    dists.push(*dist);
+                }
+            }
+        }
+        dists.sort();
+        dists.dedup();
+
+        let mut profile = vec![(start, Duration::ZERO)];
+        for pair in dists.windows(2) {
+            let middle = (pair[0] + pair[1]) / 2.0;
+            let mut max_speed = speed;
+            for (zone_start, zone_end, zone_speed) in &zones {
+                if middle >= *zone_start && middle <= *zone_end {
+                    max_speed = max_speed.min(*zone_speed);
+                }
+            }
+            let so_far = profile.last().unwrap().1;
+            profile.push((pair[1], so_far + (pair[1] - pair[0]) / max_speed));
+        }
+        profile
+    }
+
     pub fn get_zorder(&self, map: &Map) -> isize {
         match *self {
             Traversable::Lane(id) => map.get_parent(id).zorder,
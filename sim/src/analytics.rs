@@ -5,14 +5,34 @@ use serde::{Deserialize, Serialize};
 use abstutil::Counter;
 use geom::{Distance, Duration, Time};
 use map_model::{
-    BusRouteID, BusStopID, CompressedMovementID, IntersectionID, LaneID, Map, MovementID,
-    ParkingLotID, Path, PathRequest, RoadID, Traversable, TurnID,
+    BusRouteID, BusStopID, CompressedMovementID, DirectedRoadID, IntersectionID, LaneID, Map,
+    MovementID, ParkingLotID, Path, PathRequest, RoadID, Traversable, TurnID,
 };
 
 use crate::{
-    AgentID, AgentType, AlertLocation, CarID, Event, ParkingSpot, TripID, TripMode, TripPhaseType,
+    AgentID, AgentType, AlertLocation, CarID, Event, ParkingSpot, PersonID, TripID, TripMode,
+    TripPhaseType,
 };
 
+/// How long measurements of the speed along a lane are remembered
+const LANE_SPEED_WINDOW: Duration = Duration::const_seconds(5.0 * 60.0);
+// Average tailpipe emissions of a passenger car and a diesel bus
+const CAR_CO2_GRAMS_PER_KM: f64 = 250.0;
+const BUS_CO2_GRAMS_PER_KM: f64 = 1300.0;
+
+fn start_of_minute(time: Time) -> Time {
+    Time::START_OF_DAY + Duration::seconds(60.0 * (time.inner_seconds() / 60.0).floor())
+}
+
+/// How trips that finished in two runs changed mode between them.
+pub struct ModeShifts {
+    /// Keyed by (mode before, mode after)
+    pub changed: BTreeMap<(TripMode, TripMode), Vec<TripID>>,
+    pub unchanged: usize,
+    /// Trips with the same ID, but taken by different people in each run
+    pub unmatched: usize,
+}
+
 /// As a simulation runs, different pieces emit Events. The Analytics object listens to these,
 /// organizing and storing some information from them. The UI queries Analytics to draw time-series
 /// and display statistics.
@@ -24,6 +44,9 @@ use crate::{
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Analytics {
     pub road_thruput: TimeSeriesCount<RoadID>,
+    /// Like road_thruput, but split by the direction agents travel along the road. Used for
+    /// screenline and cordon counts.
+    pub directed_road_thruput: TimeSeriesCount<DirectedRoadID>,
     pub intersection_thruput: TimeSeriesCount<IntersectionID>,
     // TODO For traffic signals, intersection_thruput could theoretically use this. But that
     // requires occasionally expensive or complicated summing or merging over all directions of an
@@ -33,25 +56,44 @@ pub struct Analytics {
     /// Most fields in Analytics are cumulative over time, but this is just for the current moment
     /// in time.
     pub demand: BTreeMap<MovementID, usize>,
+    /// Also just for the current moment: for each lane, recent (time, fraction of free-flow speed)
+    /// measurements from vehicles crossing it. Old measurements are dropped as new ones arrive.
+    #[serde(skip_serializing, skip_deserializing)]
+    recent_lane_speeds: BTreeMap<LaneID, VecDeque<(Time, f64)>>,
 
     // TODO Reconsider this one
     pub bus_arrivals: Vec<(Time, CarID, BusRouteID, BusStopID)>,
     /// For each passenger boarding, how long did they wait at the stop?
     pub passengers_boarding: BTreeMap<BusStopID, Vec<(Time, BusRouteID, Duration)>>,
     pub passengers_alighting: BTreeMap<BusStopID, Vec<(Time, BusRouteID)>>,
+    /// For each route, when riders boarded and the fare they paid, in cents. Free boardings
+    /// aren't recorded.
+    pub fares_paid: BTreeMap<BusRouteID, Vec<(Time, usize)>>,
 
     pub started_trips: BTreeMap<TripID, Time>,
+    /// Who took each trip. Trip IDs are assigned in the order a scenario lists people, so this is
+    /// used to check that a trip really is the same one when comparing two runs.
+    pub trip_people: BTreeMap<TripID, PersonID>,
     /// Finish time, ID, mode, trip duration if successful (or None if cancelled)
     pub finished_trips: Vec<(Time, TripID, TripMode, Option<Duration>)>,
+    /// When somebody gave up on a trip, and how long they'd been waiting. These trips are also
+    /// in finished_trips as cancelled.
+    pub abandoned_trips: Vec<(Time, TripID, TripMode, Duration)>,
 
     /// Records how long was spent waiting at each turn (Intersection) for a given trip
     /// Over a certain threshold
     /// TripID, [(TurnID, Time Waiting In Seconds)]
     pub trip_intersection_delays: BTreeMap<TripID, BTreeMap<TurnID, u8>>,
+    /// The total time each trip has spent waiting at intersections, with no threshold
+    pub trip_delays: BTreeMap<TripID, Duration>,
     /// Records the average speed/maximum speed for each lane
     /// If it is over a certain threshold (<95% of max speed)
     /// TripID, [(LaneID, Percent of maximum speed as an integer (0-100)]
     pub lane_speed_percentage: BTreeMap<TripID, BTreeMap<LaneID, u8>>,
+    /// For each road, the fraction of free-flow speed that vehicles managed while crossing it,
+    /// bucketed by minute. Each entry is (start of the minute, sum of fractions, number of
+    /// measurements), ordered by time. Minutes without any measurements are missing.
+    pub road_speed_ratios: BTreeMap<RoadID, Vec<(Time, f64, usize)>>,
 
     // TODO This subsumes finished_trips
     pub trip_log: Vec<(Time, TripID, Option<PathRequest>, TripPhaseType)>,
@@ -59,11 +101,20 @@ pub struct Analytics {
     // TODO Transit riders aren't represented here yet, just the vehicle they're riding.
     /// Only for traffic signals. The u8 is the movement index from a CompressedMovementID.
     pub intersection_delays: BTreeMap<IntersectionID, Vec<(u8, Time, Duration, AgentType)>>,
+    /// For each movable bridge road, how long agents waited for it to close before crossing
+    pub bridge_delays: BTreeMap<RoadID, Vec<(Time, Duration, AgentType)>>,
+    /// For each lane, when its queue stopped backing up into the upstream intersection, and how
+    /// long that had lasted. Spillbacks still happening aren't here; ask the Sim.
+    pub spillbacks: BTreeMap<LaneID, Vec<(Time, Duration)>>,
 
     /// Per parking lane or lot, when does a spot become filled (true) or free (false)
     pub parking_lane_changes: BTreeMap<LaneID, Vec<(Time, bool)>>,
     pub parking_lot_changes: BTreeMap<ParkingLotID, Vec<(Time, bool)>>,
 
+    /// How far each type of agent has traveled. Every lane entered is counted in full, even when
+    /// a trip starts or ends partway along it.
+    pub distance_traveled: BTreeMap<AgentType, Distance>,
+
     pub(crate) alerts: Vec<(Time, AlertLocation, String)>,
 
     /// After we restore from a savestate, don't record anything. This is only going to make sense
@@ -76,18 +127,28 @@ impl Analytics {
     pub fn new(record_anything: bool) -> Analytics {
         Analytics {
             road_thruput: TimeSeriesCount::new(),
+            directed_road_thruput: TimeSeriesCount::new(),
             intersection_thruput: TimeSeriesCount::new(),
             traffic_signal_thruput: TimeSeriesCount::new(),
             demand: BTreeMap::new(),
+            recent_lane_speeds: BTreeMap::new(),
             bus_arrivals: Vec::new(),
             passengers_boarding: BTreeMap::new(),
             passengers_alighting: BTreeMap::new(),
+            fares_paid: BTreeMap::new(),
             started_trips: BTreeMap::new(),
+            trip_people: BTreeMap::new(),
             finished_trips: Vec::new(),
+            abandoned_trips: Vec::new(),
             trip_intersection_delays: BTreeMap::new(),
+            trip_delays: BTreeMap::new(),
+            distance_traveled: BTreeMap::new(),
             lane_speed_percentage: BTreeMap::new(),
+            road_speed_ratios: BTreeMap::new(),
             trip_log: Vec::new(),
             intersection_delays: BTreeMap::new(),
+            bridge_delays: BTreeMap::new(),
+            spillbacks: BTreeMap::new(),
             parking_lane_changes: BTreeMap::new(),
             parking_lot_changes: BTreeMap::new(),
             alerts: Vec::new(),
@@ -96,6 +157,19 @@ impl Analytics {
     }
 
     pub fn event(&mut self, ev: Event, time: Time, map: &Map) {
+        // Live measurements are useful even after restoring from a savestate
+        if let Event::LaneSpeedPercentage(_, l, avg_speed, max_speed) = ev {
+            let samples = self.recent_lane_speeds.entry(l).or_default();
+            samples.push_back((time, avg_speed / max_speed));
+            while samples
+                .front()
+                .map(|(t, _)| *t + LANE_SPEED_WINDOW < time)
+                .unwrap_or(false)
+            {
+                samples.pop_front();
+            }
+        }
+
         if !self.record_anything {
             return;
         }
@@ -106,6 +180,10 @@ impl Analytics {
                 Traversable::Lane(l) => {
                     self.road_thruput
                         .record(time, map.get_l(l).parent, a.to_type(), 1);
+                    *self
+                        .distance_traveled
+                        .entry(a.to_type())
+                        .or_insert(Distance::ZERO) += map.get_l(l).length();
                     if let Some(n) = passengers {
                         self.road_thruput.record(
                             time,
@@ -118,6 +196,23 @@ impl Analytics {
                 Traversable::Turn(t) => {
                     self.intersection_thruput
                         .record(time, t.parent, a.to_type(), 1);
+
+                    // Pedestrians can walk either way along a sidewalk, so figure out direction
+                    // from the side of the lane the turn leads to.
+                    let dst = map.get_l(t.dst);
+                    let mut dir = map.get_r(dst.parent).dir(dst.id);
+                    if dst.src_i != t.parent {
+                        dir = dir.opposite();
+                    }
+                    let dr = DirectedRoadID {
+                        id: dst.parent,
+                        dir,
+                    };
+                    self.directed_road_thruput.record(time, dr, a.to_type(), 1);
+                    if let Some(n) = passengers {
+                        self.directed_road_thruput
+                            .record(time, dr, AgentType::TransitRider, n);
+                    }
                     if let Some(n) = passengers {
                         self.intersection_thruput.record(
                             time,
@@ -171,10 +266,17 @@ impl Analytics {
                 .or_insert_with(Vec::new)
                 .push((time, route));
         }
+        if let Event::PassengerPaysFare(_, route, _, cents) = ev {
+            self.fares_paid
+                .entry(route)
+                .or_default()
+                .push((time, cents));
+        }
 
         // Started trips
-        if let Event::TripPhaseStarting(id, _, _, _) = ev {
+        if let Event::TripPhaseStarting(id, person, _, _) = ev {
             self.started_trips.entry(id).or_insert(time);
+            self.trip_people.entry(id).or_insert(person);
         }
 
         // Finished trips
@@ -190,10 +292,13 @@ impl Analytics {
         } else if let Event::TripCancelled(id, mode) = ev {
             self.started_trips.entry(id).or_insert(time);
             self.finished_trips.push((time, id, mode, None));
+        } else if let Event::TripAbandoned(id, mode, waited) = ev {
+            self.abandoned_trips.push((time, id, mode, waited));
         }
 
         // Trip Intersection delay
         if let Event::TripIntersectionDelay(trip_id, turn_id, agent, delay) = ev {
+            *self.trip_delays.entry(trip_id).or_insert(Duration::ZERO) += delay;
             match agent {
                 AgentID::Car(_) => {
                     if delay > Duration::seconds(30.0) {
@@ -223,6 +328,21 @@ impl Analytics {
                     .or_insert_with(BTreeMap::new)
                     .insert(lane_id, speed_percent);
             }
+
+            let minute = start_of_minute(time);
+            let buckets = self
+                .road_speed_ratios
+                .entry(map.get_l(lane_id).parent)
+                .or_default();
+            match buckets.last_mut() {
+                Some((t, sum, cnt)) if *t == minute => {
+                    *sum += avg_speed / max_speed;
+                    *cnt += 1;
+                }
+                _ => {
+                    buckets.push((minute, avg_speed / max_speed, 1));
+                }
+            }
         }
 
         // Intersection delays
@@ -233,6 +353,17 @@ impl Analytics {
                 .push((id.idx, time, delay, agent.to_type()));
         }
 
+        if let Event::BridgeOpeningDelay(turn, agent, delay) = ev {
+            self.bridge_delays
+                .entry(map.get_l(turn.dst).parent)
+                .or_default()
+                .push((time, delay, agent.to_type()));
+        }
+
+        if let Event::SpillbackEnded(l, duration) = ev {
+            self.spillbacks.entry(l).or_default().push((time, duration));
+        }
+
         // Parking spot changes
         if let Event::CarReachedParkingSpot(_, spot) = ev {
             if let ParkingSpot::Onstreet(l, _) = spot {
@@ -338,6 +469,54 @@ impl Analytics {
         results
     }
 
+    /// Compares the mode of every trip that finished in both worlds. Trips are only paired up when
+    /// the same person took them in both runs, so a scenario that changed which people exist
+    /// doesn't produce nonsense.
+    pub fn mode_shifts(&self, now: Time, before: &Analytics) -> ModeShifts {
+        let mut after = BTreeMap::new();
+        for (t, id, mode, maybe_dt) in &self.finished_trips {
+            if *t > now {
+                break;
+            }
+            if maybe_dt.is_some() {
+                after.insert(*id, *mode);
+            }
+        }
+
+        let mut shifts = ModeShifts {
+            changed: BTreeMap::new(),
+            unchanged: 0,
+            unmatched: 0,
+        };
+        for (t, id, mode, maybe_dt) in &before.finished_trips {
+            if *t > now {
+                break;
+            }
+            if maybe_dt.is_none() {
+                continue;
+            }
+            let same_person = self.trip_people.get(id) == before.trip_people.get(id);
+            match after.remove(id) {
+                Some(new_mode) if same_person => {
+                    if new_mode == *mode {
+                        shifts.unchanged += 1;
+                    } else {
+                        shifts
+                            .changed
+                            .entry((*mode, new_mode))
+                            .or_default()
+                            .push(*id);
+                    }
+                }
+                Some(_) => {
+                    shifts.unmatched += 1;
+                }
+                None => {}
+            }
+        }
+        shifts
+    }
+
     /// If calling on prebaked Analytics, be careful to pass in an unedited map, to match how the
     /// simulation was originally run. Otherwise the paths may be nonsense.
     pub fn get_trip_phases(&self, trip: TripID, map: &Map) -> Vec<TripPhase> {
@@ -435,6 +614,120 @@ impl Analytics {
         pts
     }
 
+    /// Among trips that finished successfully up to some time, the average time they spent waiting
+    /// at intersections. None if no trips have finished yet.
+    pub fn average_trip_delay(&self, now: Time) -> Option<Duration> {
+        let mut sum = Duration::ZERO;
+        let mut cnt = 0;
+        for (t, id, _, dt) in &self.finished_trips {
+            if *t > now {
+                break;
+            }
+            if dt.is_some() {
+                sum += self.trip_delays.get(id).cloned().unwrap_or(Duration::ZERO);
+                cnt += 1;
+            }
+        }
+        if cnt == 0 {
+            None
+        } else {
+            Some(sum / (cnt as f64))
+        }
+    }
+
+    /// A rough estimate of the CO2 emitted by cars and buses so far, in kilograms. Bikes and
+    /// trains don't count; trains are assumed to be electric.
+    pub fn co2_emissions_kg(&self) -> f64 {
+        self.distance_traveled
+            .iter()
+            .map(|(agent_type, dist)| {
+                let grams_per_km = match agent_type {
+                    AgentType::Car => CAR_CO2_GRAMS_PER_KM,
+                    AgentType::Bus => BUS_CO2_GRAMS_PER_KM,
+                    _ => 0.0,
+                };
+                grams_per_km * dist.inner_meters() / 1_000_000.0
+            })
+            .sum()
+    }
+
+    /// How many times cars parked on the street and in parking lots, up to some time. Cars already
+    /// parked at the start of the day aren't counted.
+    pub fn parking_choices(&self, now: Time) -> (usize, usize) {
+        let count = |changes: &Vec<(Time, bool)>| {
+            changes
+                .iter()
+                .filter(|(t, filled)| *filled && *t > Time::START_OF_DAY && *t <= now)
+                .count()
+        };
+        (
+            self.parking_lane_changes.values().map(count).sum(),
+            self.parking_lot_changes.values().map(count).sum(),
+        )
+    }
+
+    /// Fares collected on each route up to some time, in cents, bucketed by the hour of boarding.
+    /// The first bucket is midnight to 1am. Routes that haven't collected anything are missing.
+    pub fn fare_revenue_per_hour(&self, now: Time) -> BTreeMap<BusRouteID, Vec<usize>> {
+        let mut results = BTreeMap::new();
+        for (route, fares) in &self.fares_paid {
+            let mut per_hour: Vec<usize> = Vec::new();
+            for (t, cents) in fares {
+                if *t > now {
+                    continue;
+                }
+                let hour = t.get_parts().0;
+                if per_hour.len() <= hour {
+                    per_hour.resize(hour + 1, 0);
+                }
+                per_hour[hour] += *cents;
+            }
+            if !per_hour.is_empty() {
+                results.insert(*route, per_hour);
+            }
+        }
+        results
+    }
+
+    /// Total fares collected on each route up to some time, in cents
+    pub fn fare_revenue_per_route(&self, now: Time) -> BTreeMap<BusRouteID, usize> {
+        self.fare_revenue_per_hour(now)
+            .into_iter()
+            .map(|(route, per_hour)| (route, per_hour.into_iter().sum()))
+            .collect()
+    }
+
+    /// For every lane that vehicles finished crossing recently, the average fraction of their
+    /// free-flow speed that they managed. 1.0 means no congestion at all.
+    pub fn recent_lane_speeds(&self, now: Time) -> BTreeMap<LaneID, f64> {
+        let mut results = BTreeMap::new();
+        for (l, samples) in &self.recent_lane_speeds {
+            let recent: Vec<f64> = samples
+                .iter()
+                .filter(|(t, _)| *t + LANE_SPEED_WINDOW >= now && *t <= now)
+                .map(|(_, pct)| *pct)
+                .collect();
+            if !recent.is_empty() {
+                results.insert(*l, recent.iter().sum::<f64>() / (recent.len() as f64));
+            }
+        }
+        results
+    }
+
+    /// For every road that vehicles crossed during the minute containing `time`, the average
+    /// fraction of their free-flow speed that they managed.
+    pub fn road_speed_ratios_at(&self, time: Time) -> BTreeMap<RoadID, f64> {
+        let minute = start_of_minute(time);
+        let mut results = BTreeMap::new();
+        for (r, buckets) in &self.road_speed_ratios {
+            if let Ok(idx) = buckets.binary_search_by_key(&minute, |(t, _, _)| *t) {
+                let (_, sum, cnt) = buckets[idx];
+                results.insert(*r, sum / (cnt as f64));
+            }
+        }
+        results
+    }
+
     /// Returns the free spots over time
     pub fn parking_lane_availability(
         &self,
@@ -0,0 +1,191 @@
+//! Runs a scenario before and after some map edits with several different random seeds, then
+//! estimates whether the change in trip times is bigger than the noise between seeds. Comparing
+//! just one run of each tends to overstate small effects.
+//!
+//! > cargo run --release --bin ab_test -- --scenario=data/system/seattle/scenarios/montlake/weekday.bin --edits=data/player/edits/seattle/montlake/proposal.json --seeds=10
+//!
+//! Instead of or in addition to edits, `--treatment_modifiers` applies more scenario modifiers only
+//! to the second set of runs, to compare assumptions like `[{"ChangeCarOwnership":-20}]`.
+
+use std::collections::BTreeMap;
+
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+
+use abstutil::{prettyprint_usize, CmdArgs, Timer};
+use geom::Duration;
+use map_model::{Map, MapEdits};
+use sim::{Scenario, ScenarioModifier, Sim, SimFlags, SimOptions, TripID};
+
+// Two-sided 95% critical values of Student's t-distribution, indexed by degrees of freedom - 1
+const T_CRITICAL: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+    2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+    2.052, 2.048, 2.045, 2.042,
+];
+
+fn main() {
+    let mut args = CmdArgs::new();
+    let scenario_path = args.required("--scenario");
+    let edits_path = args.optional("--edits");
+    let num_seeds = args
+        .optional_parse("--seeds", |s| s.parse::<u64>())
+        .unwrap_or(10);
+    let hours = args
+        .optional_parse("--hours", |s| s.parse::<usize>())
+        .unwrap_or(24);
+    let modifiers: Vec<ScenarioModifier> = args
+        .optional_parse("--scenario_modifiers", |s| {
+            abstutil::from_json(&s.to_string().into_bytes())
+        })
+        .unwrap_or_default();
+    let treatment_modifiers: Vec<ScenarioModifier> = args
+        .optional_parse("--treatment_modifiers", |s| {
+            abstutil::from_json(&s.to_string().into_bytes())
+        })
+        .unwrap_or_default();
+    let opts = SimOptions::from_args(&mut args, SimFlags::RNG_SEED);
+    args.done();
+    assert!(
+        num_seeds >= 2,
+        "Need at least 2 seeds to estimate the noise"
+    );
+    assert!(
+        edits_path.is_some() || !treatment_modifiers.is_empty(),
+        "Need --edits, --treatment_modifiers, or both"
+    );
+
+    let mut timer = Timer::new("A/B test");
+    let mut scenario: Scenario = abstutil::read_binary(scenario_path, &mut timer);
+    let mut map = Map::new(scenario.map_name.path(), &mut timer);
+    for m in &modifiers {
+        scenario = m.apply(&map, scenario);
+    }
+    let seeds: Vec<u64> = (0..num_seeds).map(|i| SimFlags::RNG_SEED + i).collect();
+
+    let mut baseline = Vec::new();
+    for seed in &seeds {
+        timer.start(format!("baseline with seed {}", seed));
+        baseline.push(run(&mut map, &scenario, &opts, *seed, hours, &mut timer));
+        timer.stop(format!("baseline with seed {}", seed));
+    }
+
+    if let Some(edits_path) = edits_path {
+        let edits = MapEdits::load(&map, edits_path.clone(), &mut timer)
+            .unwrap_or_else(|err| panic!("Couldn't load edits {}: {}", edits_path, err));
+        map.must_apply_edits(edits, &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+    }
+    let mut treatment = scenario.clone();
+    for m in &treatment_modifiers {
+        treatment = m.apply(&map, treatment);
+    }
+
+    // For every seed, (change in the mean duration of trips finishing in both runs, change in the
+    // number of finished trips, change in transit fares collected in dollars)
+    let mut deltas = Vec::new();
+    for (seed, (before, fares_before)) in seeds.iter().zip(baseline) {
+        timer.start(format!("treatment with seed {}", seed));
+        let (after, fares_after) = run(&mut map, &treatment, &opts, *seed, hours, &mut timer);
+        timer.stop(format!("treatment with seed {}", seed));
+
+        let mut sum_before = Duration::ZERO;
+        let mut sum_after = Duration::ZERO;
+        let mut num_both = 0;
+        for (id, dt) in &after {
+            if let Some(dt_before) = before.get(id) {
+                sum_before += *dt_before;
+                sum_after += *dt;
+                num_both += 1;
+            }
+        }
+        let mean_delta = if num_both == 0 {
+            0.0
+        } else {
+            (sum_after - sum_before).inner_seconds() / (num_both as f64)
+        };
+        let finished_delta = (after.len() as f64) - (before.len() as f64);
+        println!(
+            "Seed {}: trips finishing in both runs changed by {:.1}s on average, {} trips \
+             finished before, {} after",
+            seed,
+            mean_delta,
+            prettyprint_usize(before.len()),
+            prettyprint_usize(after.len())
+        );
+        let fares_delta = ((fares_after as f64) - (fares_before as f64)) / 100.0;
+        if fares_before > 0 || fares_after > 0 {
+            println!(
+                "  Transit fares collected: ${:.2} before, ${:.2} after",
+                (fares_before as f64) / 100.0,
+                (fares_after as f64) / 100.0
+            );
+        }
+        deltas.push((mean_delta, finished_delta, fares_delta));
+    }
+
+    println!();
+    report(
+        "Change in average trip time (seconds)",
+        deltas.iter().map(|(x, _, _)| *x).collect(),
+    );
+    report(
+        "Change in number of finished trips",
+        deltas.iter().map(|(_, x, _)| *x).collect(),
+    );
+    if deltas.iter().any(|(_, _, x)| *x != 0.0) {
+        report(
+            "Change in transit fares collected (dollars)",
+            deltas.iter().map(|(_, _, x)| *x).collect(),
+        );
+    }
+}
+
+/// Returns the duration of every trip that finished, and the total transit fares collected in
+/// cents
+fn run(
+    map: &mut Map,
+    scenario: &Scenario,
+    opts: &SimOptions,
+    seed: u64,
+    hours: usize,
+    timer: &mut Timer,
+) -> (BTreeMap<TripID, Duration>, usize) {
+    let mut opts = opts.clone();
+    opts.alerts = sim::AlertHandler::Silence;
+    let mut sim = Sim::new(map, opts, timer);
+    let mut rng = XorShiftRng::seed_from_u64(seed);
+    scenario.instantiate(&mut sim, map, &mut rng, timer);
+    sim.timed_step(map, Duration::hours(hours), &mut None, timer);
+
+    let analytics = sim.get_analytics();
+    let times = analytics
+        .finished_trips
+        .iter()
+        .filter_map(|(_, id, _, dt)| dt.map(|dt| (*id, dt)))
+        .collect();
+    let fares = analytics.fare_revenue_per_route(sim.time()).values().sum();
+    (times, fares)
+}
+
+/// Prints the mean and a 95% confidence interval of the per-seed differences, and whether the
+/// difference could just be noise.
+fn report(label: &str, samples: Vec<f64>) {
+    let n = samples.len() as f64;
+    let mean = samples.iter().sum::<f64>() / n;
+    let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    let t = T_CRITICAL.get(samples.len() - 2).cloned().unwrap_or(1.96);
+    let margin = t * (variance / n).sqrt();
+    let (low, high) = (mean - margin, mean + margin);
+
+    println!("{}", label);
+    println!(
+        "  mean {:.2}, 95% confidence interval [{:.2}, {:.2}]",
+        mean, low, high
+    );
+    if low <= 0.0 && high >= 0.0 {
+        println!("  This could just be noise between random seeds");
+    } else {
+        println!("  This difference is unlikely to be noise");
+    }
+}
@@ -0,0 +1,170 @@
+//! Runs two simulations side-by-side and reports the first event where they disagree. Use this to
+//! track down nondeterminism, or to find exactly where some edits start to matter.
+//!
+//! To compare a scenario with and without edits or extra scenario modifiers, using the same seed:
+//!
+//! > cargo run --release --bin diff_sims -- --scenario=data/system/seattle/scenarios/montlake/weekday.bin --edits=data/player/edits/seattle/montlake/proposal.json
+//!
+//! With neither, both runs should be identical; any difference is nondeterminism.
+//!
+//! To compare against a different version of the code, first record an event log with that
+//! version (`run_scenario --event_log`), then pass it in with `--compare_log`. The simulation is
+//! then only run once, with the current code, and checked against the recording as it goes.
+
+use std::collections::VecDeque;
+
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+
+use abstutil::{CmdArgs, Timer};
+use geom::{Duration, Time};
+use map_model::{Map, MapEdits};
+use sim::{EventLog, EventLogPosition, Scenario, ScenarioModifier, Sim, SimFlags, SimOptions};
+
+// How many matching events to print before the divergence, for context
+const CONTEXT: usize = 5;
+
+fn main() {
+    let mut args = CmdArgs::new();
+    let scenario_path = args.required("--scenario");
+    let edits_path = args.optional("--edits");
+    let compare_log = args.optional("--compare_log");
+    let hours = args
+        .optional_parse("--hours", |s| s.parse::<usize>())
+        .unwrap_or(24);
+    let step = args
+        .optional_parse("--step", |s| s.parse::<f64>())
+        .map(Duration::seconds)
+        .unwrap_or_else(|| Duration::seconds(1.0));
+    let modifiers: Vec<ScenarioModifier> = args
+        .optional_parse("--scenario_modifiers", |s| {
+            abstutil::from_json(&s.to_string().into_bytes())
+        })
+        .unwrap_or_default();
+    let treatment_modifiers: Vec<ScenarioModifier> = args
+        .optional_parse("--treatment_modifiers", |s| {
+            abstutil::from_json(&s.to_string().into_bytes())
+        })
+        .unwrap_or_default();
+    let seed = args
+        .optional_parse("--rng_seed", |s| s.parse::<u64>())
+        .unwrap_or(SimFlags::RNG_SEED);
+    let mut opts = SimOptions::from_args(&mut args, seed);
+    args.done();
+    assert!(
+        compare_log.is_none() || (edits_path.is_none() && treatment_modifiers.is_empty()),
+        "--compare_log can't be combined with --edits or --treatment_modifiers"
+    );
+    opts.alerts = sim::AlertHandler::Silence;
+    opts.record_event_log = true;
+
+    let mut timer = Timer::new("diff simulations");
+    let mut scenario: Scenario = abstutil::read_binary(scenario_path, &mut timer);
+    let map1 = Map::new(scenario.map_name.path(), &mut timer);
+    for m in &modifiers {
+        scenario = m.apply(&map1, scenario);
+    }
+    let mut sim1 = start(&map1, &scenario, &opts, seed, &mut timer);
+
+    // The other side is either a second simulation, or a recording of one
+    let mut other = if let Some(path) = compare_log {
+        Other::Recorded(Box::new(
+            EventLog::load(path.clone(), &mut timer)
+                .unwrap_or_else(|err| panic!("Couldn't load event log {}: {}", path, err)),
+        ))
+    } else {
+        let mut map2 = Map::new(scenario.map_name.path(), &mut timer);
+        if let Some(edits_path) = edits_path {
+            let edits = MapEdits::load(&map2, edits_path.clone(), &mut timer)
+                .unwrap_or_else(|err| panic!("Couldn't load edits {}: {}", edits_path, err));
+            map2.must_apply_edits(edits, &mut timer);
+            map2.recalculate_pathfinding_after_edits(&mut timer);
+        }
+        let mut treatment = scenario.clone();
+        for m in &treatment_modifiers {
+            treatment = m.apply(&map2, treatment);
+        }
+        let sim2 = start(&map2, &treatment, &opts, seed, &mut timer);
+        Other::Live(Box::new((map2, sim2)))
+    };
+
+    let end_time = Time::START_OF_DAY + Duration::hours(hours);
+    let mut pos1 = EventLogPosition::default();
+    let mut pos2 = EventLogPosition::default();
+    let mut matched = VecDeque::new();
+    let mut num_matched = 0;
+    timer.start("run in lockstep");
+    while sim1.time() < end_time {
+        let dt = step.min(end_time - sim1.time());
+        sim1.timed_step(&map1, dt, &mut None, &mut Timer::throwaway());
+        if let Other::Live(ref mut live) = other {
+            let (ref map2, ref mut sim2) = **live;
+            sim2.timed_step(map2, dt, &mut None, &mut Timer::throwaway());
+        }
+        let now = sim1.time();
+
+        let events1 = sim1.get_event_log().unwrap().read_until(&mut pos1, now);
+        let events2 = other.log().read_until(&mut pos2, now);
+        for idx in 0..events1.len().max(events2.len()) {
+            let ev1 = events1.get(idx);
+            let ev2 = events2.get(idx);
+            if ev1 == ev2 {
+                matched.push_back(ev1.unwrap().clone());
+                if matched.len() > CONTEXT {
+                    matched.pop_front();
+                }
+                num_matched += 1;
+                continue;
+            }
+
+            timer.stop("run in lockstep");
+            println!(
+                "Diverged during the step ending at {}, after {} identical events",
+                now,
+                abstutil::prettyprint_usize(num_matched)
+            );
+            println!("Last events in common:");
+            for (t, ev) in matched {
+                println!("  {}: {:?}", t, ev);
+            }
+            println!("First run: {}", describe(ev1));
+            println!("Other run: {}", describe(ev2));
+            std::process::exit(1);
+        }
+    }
+    timer.stop("run in lockstep");
+    println!(
+        "No divergence through {}, after {} identical events",
+        end_time,
+        abstutil::prettyprint_usize(num_matched)
+    );
+}
+
+// Both sides are big, so box them to keep the enum small
+enum Other {
+    Live(Box<(Map, Sim)>),
+    Recorded(Box<EventLog>),
+}
+
+impl Other {
+    fn log(&self) -> &EventLog {
+        match self {
+            Other::Live(live) => live.1.get_event_log().unwrap(),
+            Other::Recorded(log) => log,
+        }
+    }
+}
+
+fn start(map: &Map, scenario: &Scenario, opts: &SimOptions, seed: u64, timer: &mut Timer) -> Sim {
+    let mut sim = Sim::new(map, opts.clone(), timer);
+    let mut rng = XorShiftRng::seed_from_u64(seed);
+    scenario.instantiate(&mut sim, map, &mut rng, timer);
+    sim
+}
+
+fn describe<T: std::fmt::Debug>(ev: Option<&(Time, T)>) -> String {
+    match ev {
+        Some((t, ev)) => format!("{}: {:?}", t, ev),
+        None => "nothing else happened during this step".to_string(),
+    }
+}
@@ -0,0 +1,107 @@
+//! Runs a scenario before some map edits, then repeatedly with the edits, each time changing how
+//! many people make each trip based on how much faster or slower it got. Capacity expansions then
+//! show induced demand, instead of the same trips just getting shorter.
+//!
+//! > cargo run --release --bin induced_demand -- --scenario=data/system/seattle/scenarios/montlake/weekday.bin --edits=data/player/edits/seattle/montlake/proposal.json --elasticity=-0.5 --iterations=5
+//!
+//! Each iteration moves the number of trips partway towards what the last run's travel times
+//! imply (the method of successive averages), so it settles down instead of oscillating. Pass
+//! `--output` to save the final scenario.
+
+use std::collections::BTreeMap;
+
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+
+use abstutil::{prettyprint_usize, CmdArgs, Timer};
+use geom::Duration;
+use map_model::{Map, MapEdits};
+use sim::{ElasticDemand, ODPair, Scenario, Sim, SimFlags, SimOptions, TripID};
+
+fn main() {
+    let mut args = CmdArgs::new();
+    let scenario_path = args.required("--scenario");
+    let edits_path = args.required("--edits");
+    let elastic = ElasticDemand {
+        elasticity: args
+            .optional_parse("--elasticity", |s| s.parse::<f64>())
+            .unwrap_or(-0.5),
+    };
+    let iterations = args
+        .optional_parse("--iterations", |s| s.parse::<usize>())
+        .unwrap_or(5);
+    let hours = args
+        .optional_parse("--hours", |s| s.parse::<usize>())
+        .unwrap_or(24);
+    let output = args.optional("--output");
+    let opts = SimOptions::from_args(&mut args, SimFlags::RNG_SEED);
+    args.done();
+
+    let mut timer = Timer::new("induced demand");
+    let scenario: Scenario = abstutil::read_binary(scenario_path, &mut timer);
+    let mut map = Map::new(scenario.map_name.path(), &mut timer);
+
+    timer.start("baseline");
+    let before = scenario.travel_time_per_od(&run(&map, &scenario, &opts, hours, &mut timer));
+    timer.stop("baseline");
+
+    let edits = MapEdits::load(&map, edits_path.clone(), &mut timer)
+        .unwrap_or_else(|err| panic!("Couldn't load edits {}: {}", edits_path, err));
+    map.must_apply_edits(edits, &mut timer);
+    map.recalculate_pathfinding_after_edits(&mut timer);
+
+    let mut factors: BTreeMap<ODPair, f64> = BTreeMap::new();
+    let mut current = scenario.clone();
+    for iter in 1..=iterations {
+        timer.start(format!("iteration {}", iter));
+        let after = current.travel_time_per_od(&run(&map, &current, &opts, hours, &mut timer));
+        timer.stop(format!("iteration {}", iter));
+
+        // Move each factor 1/iter of the way to the new one
+        for (od, target) in elastic.demand_factors(&before, &after) {
+            let factor = factors.entry(od).or_insert(1.0);
+            *factor += (target - *factor) / (iter as f64);
+        }
+        // The same RNG every time, so people only come and go because the factors changed
+        let mut rng = XorShiftRng::seed_from_u64(SimFlags::RNG_SEED);
+        current = elastic.apply(&scenario, &factors, &mut rng);
+
+        println!(
+            "Iteration {}: {} trips, compared to {} originally",
+            iter,
+            prettyprint_usize(count_trips(&current)),
+            prettyprint_usize(count_trips(&scenario))
+        );
+    }
+
+    if let Some(path) = output {
+        current.scenario_name = format!("{} with induced demand", scenario.scenario_name);
+        abstutil::write_binary(path, &current);
+    }
+}
+
+/// Returns the duration of every trip that finished
+fn run(
+    map: &Map,
+    scenario: &Scenario,
+    opts: &SimOptions,
+    hours: usize,
+    timer: &mut Timer,
+) -> BTreeMap<TripID, Duration> {
+    let mut opts = opts.clone();
+    opts.alerts = sim::AlertHandler::Silence;
+    let mut sim = Sim::new(map, opts, timer);
+    let mut rng = XorShiftRng::seed_from_u64(SimFlags::RNG_SEED);
+    scenario.instantiate(&mut sim, map, &mut rng, timer);
+    sim.timed_step(map, Duration::hours(hours), &mut None, timer);
+
+    sim.get_analytics()
+        .finished_trips
+        .iter()
+        .filter_map(|(_, id, _, dt)| dt.map(|dt| (*id, dt)))
+        .collect()
+}
+
+fn count_trips(scenario: &Scenario) -> usize {
+    scenario.people.iter().map(|p| p.trips.len()).sum()
+}
@@ -51,5 +51,9 @@ fn main() {
             &mut None,
             &mut abstutil::Timer::new("run simulation"),
         );
+        // Only if --event_log was passed in
+        sim.save_event_log();
+        // Only if --record_playback was passed in
+        sim.save_playback();
     }
 }
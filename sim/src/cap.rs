@@ -84,7 +84,8 @@ impl CapSimState {
     }
 
     /// Before the driving portion of a trip begins, check that the desired path doesn't exceed any
-    /// caps. If so, attempt to reroute around.
+    /// caps. If so, attempt to reroute around. Also route around movable bridges that're currently
+    /// open and lanes closed by work zones, if possible.
     pub fn validate_path(
         &mut self,
         req: &PathRequest,
@@ -95,6 +96,20 @@ impl CapSimState {
         intersections: &IntersectionSimState,
         map: &Map,
     ) -> Result<Path, String> {
+        let open_bridges = intersections.open_bridge_lanes(now, map);
+        let path = if path
+            .get_steps()
+            .iter()
+            .any(|step| matches!(step, PathStep::Lane(l) if open_bridges.contains(l)))
+            || path.closed_by_work_zones(now, map).is_some()
+        {
+            // If there's no other way, just wait for the bridge or work zone
+            map.pathfind_avoiding_work_zones(req.clone(), open_bridges, now)
+                .unwrap_or(path)
+        } else {
+            path
+        };
+
         if let Some(ref avoid) = self.avoid_congestion {
             if let Some((turn, delay)) = avoid.path_crosses_delay(now, &path, intersections, map) {
                 *capped = true;
@@ -0,0 +1,591 @@
+use std::error::Error;
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use geom::{Duration, Speed, Time};
+use map_model::{
+    BuildingID, BusRouteID, BusStopID, CompressedMovementID, IntersectionID, LaneID, Path,
+    PathRequest, Traversable, TurnID,
+};
+
+use crate::{
+    AgentID, AlertLocation, CarID, Event, ParkingSpot, PersonID, TripID, TripMode, TripPhaseType,
+};
+
+/// Every Event emitted during a simulation, kept so that analyses nobody thought of before the run
+/// can still be done afterwards.
+///
+/// Events are stored by field, not by event. Each column only holds values for the events that
+/// have that field, in the order the events happened, so there's no padding for variants that
+/// don't use a field. The columns compress well, because neighboring values tend to be similar.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct EventLog {
+    times: Vec<Time>,
+    kinds: Vec<EventKind>,
+
+    cars: Vec<CarID>,
+    people: Vec<PersonID>,
+    agents: Vec<AgentID>,
+    maybe_agents: Vec<Option<AgentID>>,
+    trips: Vec<TripID>,
+    modes: Vec<TripMode>,
+    lanes: Vec<LaneID>,
+    turns: Vec<TurnID>,
+    intersections: Vec<IntersectionID>,
+    movements: Vec<CompressedMovementID>,
+    buildings: Vec<BuildingID>,
+    routes: Vec<BusRouteID>,
+    stops: Vec<BusStopID>,
+    spots: Vec<ParkingSpot>,
+    durations: Vec<Duration>,
+    speeds: Vec<Speed>,
+    counts: Vec<Option<usize>>,
+    cents: Vec<usize>,
+    phases: Vec<TripPhaseType>,
+    path_requests: Vec<Option<PathRequest>>,
+    paths: Vec<Path>,
+    alerts: Vec<(AlertLocation, String)>,
+}
+
+/// Which variant of Event is stored in each row. AgentEntersTraversable is split by lane and turn.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+enum EventKind {
+    CarReachedParkingSpot,
+    CarLeftParkingSpot,
+    BusArrivedAtStop,
+    BusDepartedFromStop,
+    PassengerBoardsTransit,
+    PassengerAlightsTransit,
+    PassengerPassedUp,
+    PassengerPaysFare,
+    PersonEntersBuilding,
+    PersonLeavesBuilding,
+    PersonLeavesMap,
+    PersonEntersMap,
+    PedReachedParkingSpot,
+    BikeStoppedAtSidewalk,
+    AgentEntersLane,
+    AgentEntersTurn,
+    IntersectionDelayMeasured,
+    TripFinished,
+    TripCancelled,
+    TripAbandoned,
+    TripPhaseStarting,
+    TripIntersectionDelay,
+    BridgeOpeningDelay,
+    SpillbackStarted,
+    SpillbackEnded,
+    LaneSpeedPercentage,
+    PathAmended,
+    Alert,
+}
+
+impl EventLog {
+    pub fn new() -> EventLog {
+        EventLog::default()
+    }
+
+    pub(crate) fn push(&mut self, time: Time, ev: &Event) {
+        self.times.push(time);
+        let kind = match ev.clone() {
+            Event::CarReachedParkingSpot(car, spot) => {
+                self.cars.push(car);
+                self.spots.push(spot);
+                EventKind::CarReachedParkingSpot
+            }
+            Event::CarLeftParkingSpot(car, spot) => {
+                self.cars.push(car);
+                self.spots.push(spot);
+                EventKind::CarLeftParkingSpot
+            }
+            Event::BusArrivedAtStop(car, route, stop) => {
+                self.cars.push(car);
+                self.routes.push(route);
+                self.stops.push(stop);
+                EventKind::BusArrivedAtStop
+            }
+            Event::BusDepartedFromStop(car, route, stop) => {
+                self.cars.push(car);
+                self.routes.push(route);
+                self.stops.push(stop);
+                EventKind::BusDepartedFromStop
+            }
+            Event::PassengerBoardsTransit(person, car, route, stop, waiting) => {
+                self.people.push(person);
+                self.cars.push(car);
+                self.routes.push(route);
+                self.stops.push(stop);
+                self.durations.push(waiting);
+                EventKind::PassengerBoardsTransit
+            }
+            Event::PassengerAlightsTransit(person, car, route, stop) => {
+                self.people.push(person);
+                self.cars.push(car);
+                self.routes.push(route);
+                self.stops.push(stop);
+                EventKind::PassengerAlightsTransit
+            }
+            Event::PassengerPassedUp(person, car, route, stop) => {
+                self.people.push(person);
+                self.cars.push(car);
+                self.routes.push(route);
+                self.stops.push(stop);
+                EventKind::PassengerPassedUp
+            }
+            Event::PassengerPaysFare(person, route, stop, cents) => {
+                self.people.push(person);
+                self.routes.push(route);
+                self.stops.push(stop);
+                self.cents.push(cents);
+                EventKind::PassengerPaysFare
+            }
+            Event::PersonEntersBuilding(person, b) => {
+                self.people.push(person);
+                self.buildings.push(b);
+                EventKind::PersonEntersBuilding
+            }
+            Event::PersonLeavesBuilding(person, b) => {
+                self.people.push(person);
+                self.buildings.push(b);
+                EventKind::PersonLeavesBuilding
+            }
+            Event::PersonLeavesMap(person, agent, i) => {
+                self.people.push(person);
+                self.maybe_agents.push(agent);
+                self.intersections.push(i);
+                EventKind::PersonLeavesMap
+            }
+            Event::PersonEntersMap(person, agent, i) => {
+                self.people.push(person);
+                self.agents.push(agent);
+                self.intersections.push(i);
+                EventKind::PersonEntersMap
+            }
+            Event::PedReachedParkingSpot(ped, spot) => {
+                self.agents.push(AgentID::Pedestrian(ped));
+                self.spots.push(spot);
+                EventKind::PedReachedParkingSpot
+            }
+            Event::BikeStoppedAtSidewalk(car, l) => {
+                self.cars.push(car);
+                self.lanes.push(l);
+                EventKind::BikeStoppedAtSidewalk
+            }
+            Event::AgentEntersTraversable(agent, on, passengers) => {
+                self.agents.push(agent);
+                self.counts.push(passengers);
+                match on {
+                    Traversable::Lane(l) => {
+                        self.lanes.push(l);
+                        EventKind::AgentEntersLane
+                    }
+                    Traversable::Turn(t) => {
+                        self.turns.push(t);
+                        EventKind::AgentEntersTurn
+                    }
+                }
+            }
+            Event::IntersectionDelayMeasured(movement, delay, agent) => {
+                self.movements.push(movement);
+                self.durations.push(delay);
+                self.agents.push(agent);
+                EventKind::IntersectionDelayMeasured
+            }
+            Event::TripFinished {
+                trip,
+                mode,
+                total_time,
+                blocked_time,
+            } => {
+                self.trips.push(trip);
+                self.modes.push(mode);
+                self.durations.push(total_time);
+                self.durations.push(blocked_time);
+                EventKind::TripFinished
+            }
+            Event::TripCancelled(trip, mode) => {
+                self.trips.push(trip);
+                self.modes.push(mode);
+                EventKind::TripCancelled
+            }
+            Event::TripAbandoned(trip, mode, waited) => {
+                self.trips.push(trip);
+                self.modes.push(mode);
+                self.durations.push(waited);
+                EventKind::TripAbandoned
+            }
+            Event::TripPhaseStarting(trip, person, req, phase) => {
+                self.trips.push(trip);
+                self.people.push(person);
+                self.path_requests.push(req);
+                self.phases.push(phase);
+                EventKind::TripPhaseStarting
+            }
+            Event::TripIntersectionDelay(trip, turn, agent, delay) => {
+                self.trips.push(trip);
+                self.turns.push(turn);
+                self.agents.push(agent);
+                self.durations.push(delay);
+                EventKind::TripIntersectionDelay
+            }
+            Event::BridgeOpeningDelay(turn, agent, delay) => {
+                self.turns.push(turn);
+                self.agents.push(agent);
+                self.durations.push(delay);
+                EventKind::BridgeOpeningDelay
+            }
+            Event::SpillbackStarted(l) => {
+                self.lanes.push(l);
+                EventKind::SpillbackStarted
+            }
+            Event::SpillbackEnded(l, duration) => {
+                self.lanes.push(l);
+                self.durations.push(duration);
+                EventKind::SpillbackEnded
+            }
+            Event::LaneSpeedPercentage(trip, l, avg, max) => {
+                self.trips.push(trip);
+                self.lanes.push(l);
+                self.speeds.push(avg);
+                self.speeds.push(max);
+                EventKind::LaneSpeedPercentage
+            }
+            Event::PathAmended(path) => {
+                self.paths.push(path);
+                EventKind::PathAmended
+            }
+            Event::Alert(loc, msg) => {
+                self.alerts.push((loc, msg));
+                EventKind::Alert
+            }
+        };
+        self.kinds.push(kind);
+    }
+
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+
+    /// Replays every event in order.
+    pub fn iter(&self) -> EventLogIter<'_> {
+        EventLogIter {
+            log: self,
+            row: 0,
+            cursors: Cursors::default(),
+        }
+    }
+
+    /// Every event that happened up to and including `until`, starting from `pos`, which is then
+    /// advanced past them. Lets a caller read new events as a simulation runs, without replaying
+    /// the whole log each time.
+    pub fn read_until(&self, pos: &mut EventLogPosition, until: Time) -> Vec<(Time, Event)> {
+        let mut iter = EventLogIter {
+            log: self,
+            row: pos.row,
+            cursors: pos.cursors.clone(),
+        };
+        let mut events = Vec::new();
+        while iter.row < self.times.len() && self.times[iter.row] <= until {
+            events.push(iter.next().unwrap());
+        }
+        pos.row = iter.row;
+        pos.cursors = iter.cursors;
+        events
+    }
+
+    /// All events that happened on a lane, that involve a turn from or to the lane, or that
+    /// involve a parking spot along the lane.
+    pub fn events_touching_lane(&self, l: LaneID) -> Vec<(Time, Event)> {
+        self.iter()
+            .filter(|(_, ev)| match ev {
+                Event::CarReachedParkingSpot(_, spot)
+                | Event::CarLeftParkingSpot(_, spot)
+                | Event::PedReachedParkingSpot(_, spot) => {
+                    matches!(spot, ParkingSpot::Onstreet(lane, _) if *lane == l)
+                }
+                Event::BikeStoppedAtSidewalk(_, lane)
+                | Event::SpillbackStarted(lane)
+                | Event::SpillbackEnded(lane, _)
+                | Event::LaneSpeedPercentage(_, lane, _, _) => *lane == l,
+                Event::AgentEntersTraversable(_, on, _) => match on {
+                    Traversable::Lane(lane) => *lane == l,
+                    Traversable::Turn(t) => t.src == l || t.dst == l,
+                },
+                Event::TripIntersectionDelay(_, t, _, _) | Event::BridgeOpeningDelay(t, _, _) => {
+                    t.src == l || t.dst == l
+                }
+                _ => false,
+            })
+            .collect()
+    }
+
+    /// Every time a bus serving this route arrived at a stop.
+    pub fn bus_arrivals(&self, route: BusRouteID) -> Vec<(Time, CarID, BusStopID)> {
+        self.iter()
+            .filter_map(|(time, ev)| match ev {
+                Event::BusArrivedAtStop(bus, r, stop) if r == route => Some((time, bus, stop)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Every time a full vehicle serving this route left somebody waiting at a stop.
+    pub fn pass_ups(&self, route: BusRouteID) -> Vec<(Time, PersonID, CarID, BusStopID)> {
+        self.iter()
+            .filter_map(|(time, ev)| match ev {
+                Event::PassengerPassedUp(person, bus, r, stop) if r == route => {
+                    Some((time, person, bus, stop))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Every event involving one person, directly or through their trips.
+    pub fn events_for_person(&self, person: PersonID) -> Vec<(Time, Event)> {
+        let mut trips = Vec::new();
+        let mut results = Vec::new();
+        for (time, ev) in self.iter() {
+            let relevant = match ev {
+                Event::PassengerBoardsTransit(p, _, _, _, _)
+                | Event::PassengerAlightsTransit(p, _, _, _)
+                | Event::PassengerPassedUp(p, _, _, _)
+                | Event::PassengerPaysFare(p, _, _, _)
+                | Event::PersonEntersBuilding(p, _)
+                | Event::PersonLeavesBuilding(p, _)
+                | Event::PersonLeavesMap(p, _, _)
+                | Event::PersonEntersMap(p, _, _) => p == person,
+                Event::TripPhaseStarting(trip, p, _, _) => {
+                    if p == person && !trips.contains(&trip) {
+                        trips.push(trip);
+                    }
+                    p == person
+                }
+                Event::TripFinished { trip, .. }
+                | Event::TripCancelled(trip, _)
+                | Event::TripAbandoned(trip, _, _)
+                | Event::TripIntersectionDelay(trip, _, _, _)
+                | Event::LaneSpeedPercentage(trip, _, _, _) => trips.contains(&trip),
+                _ => false,
+            };
+            if relevant {
+                results.push((time, ev));
+            }
+        }
+        results
+    }
+
+    /// Writes the log as compressed binary.
+    pub fn save(&self, path: String) -> Result<(), Box<dyn Error>> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        encoder.write_all(&abstutil::to_binary(self))?;
+        let compressed = encoder.finish()?;
+        abstutil::write_binary(path, &compressed);
+        Ok(())
+    }
+
+    pub fn load(path: String, timer: &mut abstutil::Timer) -> Result<EventLog, Box<dyn Error>> {
+        let compressed: Vec<u8> = abstutil::maybe_read_binary(path, timer)?;
+        let mut decoder = flate2::read::GzDecoder::new(&compressed[..]);
+        let mut raw = Vec::new();
+        decoder.read_to_end(&mut raw)?;
+        abstutil::from_binary(&raw)
+    }
+}
+
+/// How far somebody has read through an EventLog. Starts at the beginning.
+#[derive(Clone, Default)]
+pub struct EventLogPosition {
+    row: usize,
+    cursors: Cursors,
+}
+
+// How far into each column the iterator has read
+#[derive(Clone, Default)]
+struct Cursors {
+    cars: usize,
+    people: usize,
+    agents: usize,
+    maybe_agents: usize,
+    trips: usize,
+    modes: usize,
+    lanes: usize,
+    turns: usize,
+    intersections: usize,
+    movements: usize,
+    buildings: usize,
+    routes: usize,
+    stops: usize,
+    spots: usize,
+    durations: usize,
+    speeds: usize,
+    counts: usize,
+    cents: usize,
+    phases: usize,
+    path_requests: usize,
+    paths: usize,
+    alerts: usize,
+}
+
+pub struct EventLogIter<'a> {
+    log: &'a EventLog,
+    row: usize,
+    cursors: Cursors,
+}
+
+// Reads the next value of one column
+macro_rules! take {
+    ($iter:expr, $column:ident) => {{
+        let value = $iter.log.$column[$iter.cursors.$column].clone();
+        $iter.cursors.$column += 1;
+        value
+    }};
+}
+
+impl<'a> Iterator for EventLogIter<'a> {
+    type Item = (Time, Event);
+
+    fn next(&mut self) -> Option<(Time, Event)> {
+        let time = *self.log.times.get(self.row)?;
+        let ev = match self.log.kinds[self.row] {
+            EventKind::CarReachedParkingSpot => {
+                Event::CarReachedParkingSpot(take!(self, cars), take!(self, spots))
+            }
+            EventKind::CarLeftParkingSpot => {
+                Event::CarLeftParkingSpot(take!(self, cars), take!(self, spots))
+            }
+            EventKind::BusArrivedAtStop => {
+                Event::BusArrivedAtStop(take!(self, cars), take!(self, routes), take!(self, stops))
+            }
+            EventKind::BusDepartedFromStop => Event::BusDepartedFromStop(
+                take!(self, cars),
+                take!(self, routes),
+                take!(self, stops),
+            ),
+            EventKind::PassengerBoardsTransit => Event::PassengerBoardsTransit(
+                take!(self, people),
+                take!(self, cars),
+                take!(self, routes),
+                take!(self, stops),
+                take!(self, durations),
+            ),
+            EventKind::PassengerAlightsTransit => Event::PassengerAlightsTransit(
+                take!(self, people),
+                take!(self, cars),
+                take!(self, routes),
+                take!(self, stops),
+            ),
+            EventKind::PassengerPassedUp => Event::PassengerPassedUp(
+                take!(self, people),
+                take!(self, cars),
+                take!(self, routes),
+                take!(self, stops),
+            ),
+            EventKind::PassengerPaysFare => Event::PassengerPaysFare(
+                take!(self, people),
+                take!(self, routes),
+                take!(self, stops),
+                take!(self, cents),
+            ),
+            EventKind::PersonEntersBuilding => {
+                Event::PersonEntersBuilding(take!(self, people), take!(self, buildings))
+            }
+            EventKind::PersonLeavesBuilding => {
+                Event::PersonLeavesBuilding(take!(self, people), take!(self, buildings))
+            }
+            EventKind::PersonLeavesMap => Event::PersonLeavesMap(
+                take!(self, people),
+                take!(self, maybe_agents),
+                take!(self, intersections),
+            ),
+            EventKind::PersonEntersMap => Event::PersonEntersMap(
+                take!(self, people),
+                take!(self, agents),
+                take!(self, intersections),
+            ),
+            EventKind::PedReachedParkingSpot => match take!(self, agents) {
+                AgentID::Pedestrian(ped) => Event::PedReachedParkingSpot(ped, take!(self, spots)),
+                x => panic!("PedReachedParkingSpot stored for {:?}", x),
+            },
+            EventKind::BikeStoppedAtSidewalk => {
+                Event::BikeStoppedAtSidewalk(take!(self, cars), take!(self, lanes))
+            }
+            EventKind::AgentEntersLane => {
+                let agent = take!(self, agents);
+                let passengers = take!(self, counts);
+                Event::AgentEntersTraversable(
+                    agent,
+                    Traversable::Lane(take!(self, lanes)),
+                    passengers,
+                )
+            }
+            EventKind::AgentEntersTurn => {
+                let agent = take!(self, agents);
+                let passengers = take!(self, counts);
+                Event::AgentEntersTraversable(
+                    agent,
+                    Traversable::Turn(take!(self, turns)),
+                    passengers,
+                )
+            }
+            EventKind::IntersectionDelayMeasured => Event::IntersectionDelayMeasured(
+                take!(self, movements),
+                take!(self, durations),
+                take!(self, agents),
+            ),
+            EventKind::TripFinished => Event::TripFinished {
+                trip: take!(self, trips),
+                mode: take!(self, modes),
+                total_time: take!(self, durations),
+                blocked_time: take!(self, durations),
+            },
+            EventKind::TripCancelled => {
+                Event::TripCancelled(take!(self, trips), take!(self, modes))
+            }
+            EventKind::TripAbandoned => Event::TripAbandoned(
+                take!(self, trips),
+                take!(self, modes),
+                take!(self, durations),
+            ),
+            EventKind::TripPhaseStarting => Event::TripPhaseStarting(
+                take!(self, trips),
+                take!(self, people),
+                take!(self, path_requests),
+                take!(self, phases),
+            ),
+            EventKind::TripIntersectionDelay => Event::TripIntersectionDelay(
+                take!(self, trips),
+                take!(self, turns),
+                take!(self, agents),
+                take!(self, durations),
+            ),
+            EventKind::BridgeOpeningDelay => Event::BridgeOpeningDelay(
+                take!(self, turns),
+                take!(self, agents),
+                take!(self, durations),
+            ),
+            EventKind::SpillbackStarted => Event::SpillbackStarted(take!(self, lanes)),
+            EventKind::SpillbackEnded => {
+                Event::SpillbackEnded(take!(self, lanes), take!(self, durations))
+            }
+            EventKind::LaneSpeedPercentage => Event::LaneSpeedPercentage(
+                take!(self, trips),
+                take!(self, lanes),
+                take!(self, speeds),
+                take!(self, speeds),
+            ),
+            EventKind::PathAmended => Event::PathAmended(take!(self, paths)),
+            EventKind::Alert => {
+                let (loc, msg) = take!(self, alerts);
+                Event::Alert(loc, msg)
+            }
+        };
+        self.row += 1;
+        Some((time, ev))
+    }
+}
@@ -26,6 +26,10 @@ pub enum Event {
     /// How long waiting at the stop?
     PassengerBoardsTransit(PersonID, CarID, BusRouteID, BusStopID, Duration),
     PassengerAlightsTransit(PersonID, CarID, BusRouteID, BusStopID),
+    /// A full vehicle left somebody waiting at the stop.
+    PassengerPassedUp(PersonID, CarID, BusRouteID, BusStopID),
+    /// The fare paid when boarding, in cents. Only happens when the fare isn't free.
+    PassengerPaysFare(PersonID, BusRouteID, BusStopID, usize),
 
     PersonEntersBuilding(PersonID, BuildingID),
     PersonLeavesBuilding(PersonID, BuildingID),
@@ -49,9 +53,18 @@ pub enum Event {
         blocked_time: Duration,
     },
     TripCancelled(TripID, TripMode),
+    /// Somebody gave up on a trip after waiting this long, for a bus, for parking, or in gridlock.
+    /// The trip is also cancelled.
+    TripAbandoned(TripID, TripMode, Duration),
     TripPhaseStarting(TripID, PersonID, Option<PathRequest>, TripPhaseType),
     /// TripID, TurnID (Where the delay was encountered), Time spent waiting at that turn
     TripIntersectionDelay(TripID, TurnID, AgentID, Duration),
+    /// TurnID (onto the bridge), time spent waiting for a movable bridge to close
+    BridgeOpeningDelay(TurnID, AgentID, Duration),
+    /// The queue on a lane has backed up into the upstream intersection
+    SpillbackStarted(LaneID),
+    /// The queue on a lane no longer reaches the upstream intersection, after this long
+    SpillbackEnded(LaneID, Duration),
     /// TripID, LaneID (Where the delay was encountered), Average Speed, Max Speed
     LaneSpeedPercentage(TripID, LaneID, Speed, Speed),
 
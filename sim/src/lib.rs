@@ -28,24 +28,37 @@ pub use crate::render::{
     UnzoomedAgent,
 };
 
-pub use self::analytics::{Analytics, TripPhase};
+pub use self::analytics::{Analytics, ModeShifts, TripPhase};
 pub(crate) use self::cap::CapSimState;
+pub use self::event_log::{EventLog, EventLogIter, EventLogPosition};
 pub(crate) use self::events::Event;
 pub use self::events::{AlertLocation, TripPhaseType};
 pub(crate) use self::make::TripSpec;
 pub use self::make::{
-    BorderSpawnOverTime, ExternalPerson, ExternalTrip, ExternalTripEndpoint, IndividTrip,
-    PersonSpec, Scenario, ScenarioGenerator, ScenarioModifier, SimFlags, SpawnOverTime,
-    TripPurpose,
+    Activity, AnonymizeOptions, AreaVisits, BikeShareConfig, BikeShareUsage, BorderSpawnOverTime,
+    BridgeOpenings, CapacityUsage, DepartureDistribution, DestinationWeights, ElasticDemand,
+    EventOrigin, ExternalPerson, ExternalTrip, ExternalTripEndpoint, FareZone, IndividTrip,
+    MissingData, MissingDataPolicy, NeighborhoodSummary, ODMatrix, ODPair, ParkingPrices,
+    PersonSpec, Persona, PhaseVolume, Population, PricingZone, ProblemKind, Resident, Scenario,
+    ScenarioDiff, ScenarioGenerator, ScenarioModifier, ScenarioPhase, ScenarioProblem,
+    ScenarioSummary, SeedParkedCars, SimFlags, SpawnOverTime, SpawnParams, ThroughTraffic,
+    TrainSchedule, TransitFares, TripPurpose, VenueEvent,
 };
 pub(crate) use self::mechanics::{
     DrivingSimState, IntersectionSimState, ParkingSim, ParkingSimState, WalkingSimState,
+    WAIT_AT_STOP_SIGN,
 };
 pub(crate) use self::pandemic::PandemicModel;
+pub use self::playback::{Playback, PlaybackAgent, PlaybackFrame};
 pub(crate) use self::recorder::TrafficRecorder;
 pub(crate) use self::router::{ActionAtEnd, Router};
 pub(crate) use self::scheduler::{Command, Scheduler};
-pub use self::sim::{AgentProperties, AlertHandler, DelayCause, Sim, SimCallback, SimOptions};
+pub use self::sim::{
+    AgentProperties, AlertHandler, BlockedOn, DelayCause, RunEstimate, Sim, SimCallback,
+    SimOptions, Speeding, Subsystem,
+};
+pub use self::snapshot::AgentSnapshot;
+pub use self::timetable::Timetable;
 pub(crate) use self::transit::TransitSimState;
 pub use self::trips::{CommutersVehiclesCounts, Person, PersonState, TripInfo, TripResult};
 pub use self::trips::{TripEndpoint, TripMode};
@@ -53,15 +66,19 @@ pub(crate) use self::trips::{TripLeg, TripManager};
 
 mod analytics;
 mod cap;
+mod event_log;
 mod events;
 mod make;
 mod mechanics;
 mod pandemic;
+mod playback;
 mod recorder;
 mod render;
 mod router;
 mod scheduler;
 mod sim;
+pub mod snapshot;
+mod timetable;
 mod transit;
 mod trips;
 
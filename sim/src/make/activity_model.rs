@@ -279,7 +279,7 @@ fn create_prole(
     })
 }
 
-fn select_trip_mode(distance: Distance, rng: &mut XorShiftRng) -> TripMode {
+pub(crate) fn select_trip_mode(distance: Distance, rng: &mut XorShiftRng) -> TripMode {
     // TODO Make this probabilistic
     // for example probability of walking currently has massive differences
     // at thresholds, it would be nicer to change this gradually
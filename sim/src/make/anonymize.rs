@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use abstutil::{prettyprint_usize, Counter, Timer};
+use geom::{Distance, Duration, Time};
+use map_model::{BuildingID, Map};
+
+use crate::{PersonSpec, Scenario, TripEndpoint};
+
+/// Controls how much detail is removed from a Scenario before sharing it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AnonymizeOptions {
+    /// Buildings are grouped into square cells of this size. Every trip endpoint is moved to a
+    /// random building in the same cell.
+    pub cell_size: Distance,
+    /// People with any trip between two cells with fewer than this many trips total are removed,
+    /// since small flows can identify individuals.
+    pub min_flow: usize,
+    /// Each person's entire schedule is shifted by a random amount up to this much, earlier or
+    /// later.
+    pub max_time_jitter: Duration,
+}
+
+impl AnonymizeOptions {
+    pub fn default() -> AnonymizeOptions {
+        AnonymizeOptions {
+            cell_size: Distance::meters(500.0),
+            min_flow: 5,
+            max_time_jitter: Duration::minutes(15),
+        }
+    }
+}
+
+type Cell = (isize, isize);
+
+impl Scenario {
+    /// Produces a Scenario that can be shared more widely than the original origin-destination
+    /// data. Trip endpoints are generalized to a neighborhood-sized cell, departure times are
+    /// jittered, small flows are removed, and original person IDs are dropped. Borders aren't
+    /// modified, since they're not sensitive.
+    pub fn anonymize(
+        mut self,
+        map: &Map,
+        opts: &AnonymizeOptions,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) -> Scenario {
+        let mut bldgs_per_cell: BTreeMap<Cell, Vec<BuildingID>> = BTreeMap::new();
+        let mut cell_per_bldg: BTreeMap<BuildingID, Cell> = BTreeMap::new();
+        for b in map.all_buildings() {
+            let pt = b.polygon.center();
+            let cell = (
+                (pt.x() / opts.cell_size.inner_meters()).floor() as isize,
+                (pt.y() / opts.cell_size.inner_meters()).floor() as isize,
+            );
+            bldgs_per_cell
+                .entry(cell)
+                .or_insert_with(Vec::new)
+                .push(b.id);
+            cell_per_bldg.insert(b.id, cell);
+        }
+
+        // The scenario might've been made for a different version of the map
+        let orig_people = self.people.len();
+        self.people.retain(|person| {
+            std::iter::once(&person.origin)
+                .chain(person.trips.iter().map(|trip| &trip.destination))
+                .all(|endpt| match endpt {
+                    TripEndpoint::Bldg(b) => cell_per_bldg.contains_key(b),
+                    _ => true,
+                })
+        });
+        if self.people.len() != orig_people {
+            timer.warn(format!(
+                "Removed {} people with trips to buildings that aren't in the map",
+                prettyprint_usize(orig_people - self.people.len())
+            ));
+        }
+
+        let to_zone = |endpt: &TripEndpoint| match endpt {
+            TripEndpoint::Bldg(b) => cell_per_bldg.get(b).cloned(),
+            _ => None,
+        };
+
+        // Find the small flows
+        let mut flows: Counter<(Option<Cell>, Option<Cell>)> = Counter::new();
+        for person in &self.people {
+            let mut from = to_zone(&person.origin);
+            for trip in &person.trips {
+                let to = to_zone(&trip.destination);
+                flows.inc((from, to));
+                from = to;
+            }
+        }
+        let orig_people = self.people.len();
+        self.people.retain(|person| {
+            let mut from = to_zone(&person.origin);
+            for trip in &person.trips {
+                let to = to_zone(&trip.destination);
+                if flows.get((from, to)) < opts.min_flow {
+                    return false;
+                }
+                from = to;
+            }
+            true
+        });
+        timer.note(format!(
+            "Removed {} of {} people with trips in small flows",
+            prettyprint_usize(orig_people - self.people.len()),
+            prettyprint_usize(orig_people)
+        ));
+
+        timer.start_iter("anonymize people", self.people.len());
+        for person in &mut self.people {
+            timer.next();
+            person.orig_id = None;
+            jitter_endpoints(person, &bldgs_per_cell, &cell_per_bldg, rng);
+            jitter_times(person, opts.max_time_jitter, rng);
+        }
+        self
+    }
+}
+
+/// Moves every building to a random building in the same cell. The same building always moves to
+/// the same replacement for one person, so that returning home still works.
+fn jitter_endpoints(
+    person: &mut PersonSpec,
+    bldgs_per_cell: &BTreeMap<Cell, Vec<BuildingID>>,
+    cell_per_bldg: &BTreeMap<BuildingID, Cell>,
+    rng: &mut XorShiftRng,
+) {
+    let mut replacements: BTreeMap<BuildingID, BuildingID> = BTreeMap::new();
+    let mut replace = |endpt: &mut TripEndpoint| {
+        if let TripEndpoint::Bldg(b) = endpt {
+            if let Some(new) = replacements.get(b) {
+                *b = *new;
+                return;
+            }
+            // Avoid mapping two different original buildings to the same one, or consecutive
+            // trips might wind up starting and ending at the same place.
+            let choices: Vec<BuildingID> = match cell_per_bldg.get(b) {
+                Some(cell) => bldgs_per_cell[cell]
+                    .iter()
+                    .filter(|x| !replacements.values().any(|used| used == *x))
+                    .cloned()
+                    .collect(),
+                None => Vec::new(),
+            };
+            let new = choices.choose(rng).cloned().unwrap_or(*b);
+            replacements.insert(*b, new);
+            *b = new;
+        }
+    };
+    replace(&mut person.origin);
+    for trip in &mut person.trips {
+        replace(&mut trip.destination);
+    }
+}
+
+/// Shifts the entire schedule by the same amount, preserving the order of trips.
+fn jitter_times(person: &mut PersonSpec, max_jitter: Duration, rng: &mut XorShiftRng) {
+    if person.trips.is_empty() || max_jitter == Duration::ZERO {
+        return;
+    }
+    let max = max_jitter.inner_seconds();
+    // Don't start before midnight
+    let earliest = (person.trips[0].depart - Time::START_OF_DAY).inner_seconds();
+    let offset = Duration::seconds(rng.gen_range(-max, max).max(-earliest));
+    for trip in &mut person.trips {
+        trip.depart += offset;
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use geom::{Distance, Duration, Speed, Time};
+use map_model::{BuildingID, Map};
+
+use crate::{Scenario, SidewalkSpot, TripEndpoint, TripMode};
+
+/// Parameters for a dockless bike or scooter share system. Shared vehicles start the day scattered
+/// among random buildings. Somebody who'd otherwise walk or take transit uses one if it's parked
+/// close enough to where they start and the trip is the right length, and leaves it wherever they
+/// end up. Distances and durations are whole numbers, so this can be part of a ScenarioModifier.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Serialize, Deserialize)]
+pub struct BikeShareConfig {
+    pub num_vehicles: usize,
+    /// How far somebody will walk to pick up a vehicle
+    pub max_walk_meters: usize,
+    /// Trips shorter than this, in a straight line, just walk
+    pub min_trip_meters: usize,
+    /// Trips longer than this, in a straight line, don't use a shared vehicle
+    pub max_trip_meters: usize,
+    /// Every so many hours, the operator moves every idle vehicle back to where it started the
+    /// day. 0 means never.
+    pub rebalance_every_hours: usize,
+    pub rng_seed: u8,
+}
+
+/// What happened to the shared vehicles over the day.
+#[derive(Clone, Debug)]
+pub struct BikeShareUsage {
+    /// When and where somebody picked up a vehicle
+    pub pickups: Vec<(Time, BuildingID)>,
+    /// When and where somebody left a vehicle. This is estimated, not the real time of arrival in
+    /// the simulation.
+    pub dropoffs: Vec<(Time, BuildingID)>,
+    /// When the operator rebalanced the system, and how many vehicles were moved
+    pub rebalancing: Vec<(Time, usize)>,
+    /// When and where somebody could've used a shared vehicle, but none were close enough
+    pub unmet_demand: Vec<(Time, BuildingID)>,
+}
+
+// Just used to estimate when a vehicle is free again
+const WALKING_SPEED: Speed = Speed::const_meters_per_second(1.34);
+const RIDING_SPEED: Speed = Speed::const_meters_per_second(4.5);
+
+impl BikeShareConfig {
+    pub fn default() -> BikeShareConfig {
+        BikeShareConfig {
+            num_vehicles: 500,
+            max_walk_meters: 300,
+            min_trip_meters: 800,
+            max_trip_meters: 5000,
+            rebalance_every_hours: 6,
+            rng_seed: 42,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "add {} shared bikes or scooters, used for {}m to {}m trips starting within {}m",
+            self.num_vehicles, self.min_trip_meters, self.max_trip_meters, self.max_walk_meters
+        )
+    }
+
+    /// Replaces some walking and transit trips with trips using a shared vehicle. Each of these
+    /// becomes a walk to wherever the vehicle is parked, then a bike ride to the original
+    /// destination. Trips claim vehicles in order of departure.
+    pub fn apply(&self, map: &Map, mut s: Scenario) -> (Scenario, BikeShareUsage) {
+        let mut usage = BikeShareUsage {
+            pickups: Vec::new(),
+            dropoffs: Vec::new(),
+            rebalancing: Vec::new(),
+            unmet_demand: Vec::new(),
+        };
+
+        // Vehicles can only be left where a bike can start or end a trip
+        let usable: Vec<BuildingID> = map
+            .all_buildings()
+            .iter()
+            .filter(|b| SidewalkSpot::bike_rack(b.id, map).is_some())
+            .map(|b| b.id)
+            .collect();
+        if usable.is_empty() || self.num_vehicles == 0 {
+            return (s, usage);
+        }
+        let mut rng = XorShiftRng::seed_from_u64(self.rng_seed as u64);
+        let home: Vec<BuildingID> = (0..self.num_vehicles)
+            .map(|_| *usable.choose(&mut rng).unwrap())
+            .collect();
+        // Where each vehicle is parked, and when it gets there
+        let mut fleet: Vec<(BuildingID, Time)> =
+            home.iter().map(|b| (*b, Time::START_OF_DAY)).collect();
+
+        let dist = |b1: BuildingID, b2: BuildingID| {
+            map.get_b(b1)
+                .polygon
+                .center()
+                .dist_to(map.get_b(b2).polygon.center())
+        };
+        let min_trip = Distance::meters(self.min_trip_meters as f64);
+        let max_trip = Distance::meters(self.max_trip_meters as f64);
+        let max_walk = Distance::meters(self.max_walk_meters as f64);
+
+        // (departure, person, trip, from, to)
+        let mut candidates = Vec::new();
+        for (person_idx, person) in s.people.iter().enumerate() {
+            let mut from = person.origin.clone();
+            for (trip_idx, trip) in person.trips.iter().enumerate() {
+                if let (TripEndpoint::Bldg(b1), TripEndpoint::Bldg(b2)) = (&from, &trip.destination)
+                {
+                    if !trip.cancelled
+                        && (trip.mode == TripMode::Walk || trip.mode == TripMode::Transit)
+                        && dist(*b1, *b2) >= min_trip
+                        && dist(*b1, *b2) <= max_trip
+                    {
+                        candidates.push((trip.depart, person_idx, trip_idx, *b1, *b2));
+                    }
+                }
+                from = trip.destination.clone();
+            }
+        }
+        candidates.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let mut next_rebalance = if self.rebalance_every_hours == 0 {
+            None
+        } else {
+            Some(Time::START_OF_DAY + Duration::hours(self.rebalance_every_hours))
+        };
+        // (person, trip) => where the vehicle they use is parked
+        let mut use_vehicle: BTreeMap<(usize, usize), BuildingID> = BTreeMap::new();
+        for (depart, person_idx, trip_idx, from, to) in candidates {
+            while let Some(t) = next_rebalance {
+                if t > depart {
+                    break;
+                }
+                let mut moved = 0;
+                for (idx, (parked_at, free_at)) in fleet.iter_mut().enumerate() {
+                    if *free_at <= t && *parked_at != home[idx] {
+                        *parked_at = home[idx];
+                        moved += 1;
+                    }
+                }
+                usage.rebalancing.push((t, moved));
+                next_rebalance = Some(t + Duration::hours(self.rebalance_every_hours));
+            }
+
+            let closest = fleet
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, free_at))| *free_at <= depart)
+                .map(|(idx, (parked_at, _))| (idx, dist(from, *parked_at)))
+                .filter(|(_, walk)| *walk <= max_walk)
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+            if let Some((idx, walk)) = closest {
+                let parked_at = fleet[idx].0;
+                // If it's already at the destination, there's no point
+                if parked_at == to {
+                    continue;
+                }
+                let arrive = depart + walk / WALKING_SPEED + dist(parked_at, to) / RIDING_SPEED;
+                fleet[idx] = (to, arrive);
+                usage.pickups.push((depart, parked_at));
+                usage.dropoffs.push((arrive, to));
+                use_vehicle.insert((person_idx, trip_idx), parked_at);
+            } else {
+                usage.unmet_demand.push((depart, from));
+            }
+        }
+
+        // Go backwards, so inserting the walk to the vehicle doesn't mess up later indices
+        for ((person_idx, trip_idx), parked_at) in use_vehicle.into_iter().rev() {
+            let person = &mut s.people[person_idx];
+            let from = if trip_idx == 0 {
+                person.origin.clone()
+            } else {
+                person.trips[trip_idx - 1].destination.clone()
+            };
+            let next_depart = person.trips.get(trip_idx + 1).map(|t| t.depart);
+
+            let trip = &mut person.trips[trip_idx];
+            trip.mode = TripMode::Bike;
+            trip.modified = true;
+            if from == TripEndpoint::Bldg(parked_at) {
+                continue;
+            }
+            let mut walk = trip.clone();
+            walk.mode = TripMode::Walk;
+            walk.destination = TripEndpoint::Bldg(parked_at);
+            let mut walk_time = match from {
+                TripEndpoint::Bldg(b) => dist(b, parked_at) / WALKING_SPEED,
+                _ => unreachable!(),
+            };
+            if walk_time < Duration::seconds(1.0) {
+                walk_time = Duration::seconds(1.0);
+            }
+            // The ride must still start before the next trip. If the walk runs long, the ride just
+            // starts late.
+            if let Some(next) = next_depart {
+                if walk.depart + walk_time >= next {
+                    walk_time = (next - walk.depart) / 2.0;
+                }
+            }
+            trip.depart = walk.depart + walk_time;
+            person.trips.insert(trip_idx, walk);
+        }
+
+        (s, usage)
+    }
+}
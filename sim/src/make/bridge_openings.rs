@@ -0,0 +1,58 @@
+use std::collections::BTreeMap;
+
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use geom::{Duration, Time};
+use map_model::{osm, Map, RoadID};
+
+/// Movable bridges open for boats to pass. While a bridge is up, nobody can start crossing it;
+/// drivers who haven't left yet route around it if they can, and everybody else queues.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BridgeOpenings {
+    /// Each time a road is blocked. Every road making up a bridge should be listed.
+    pub openings: Vec<(RoadID, Time)>,
+    /// How long a bridge stays open each time
+    pub duration: Duration,
+}
+
+impl BridgeOpenings {
+    /// Bridges never open.
+    pub fn none() -> BridgeOpenings {
+        BridgeOpenings {
+            openings: Vec::new(),
+            duration: Duration::minutes(10),
+        }
+    }
+
+    /// Replace the schedule with openings at random times for every movable bridge on the map.
+    /// Each bridge opens independently, but all of the roads making up one bridge open together.
+    pub fn randomize(
+        &mut self,
+        per_day: usize,
+        duration: Duration,
+        map: &Map,
+        rng: &mut XorShiftRng,
+    ) {
+        self.openings.clear();
+        self.duration = duration;
+
+        let mut bridges: BTreeMap<osm::WayID, Vec<RoadID>> = BTreeMap::new();
+        for r in map.all_roads() {
+            if r.is_movable_bridge() {
+                bridges.entry(r.orig_id.osm_way_id).or_default().push(r.id);
+            }
+        }
+        for roads in bridges.values() {
+            for _ in 0..per_day {
+                let t = Time::START_OF_DAY
+                    + Duration::seconds(rng.gen_range(0.0, Duration::hours(24).inner_seconds()));
+                for r in roads {
+                    self.openings.push((*r, t));
+                }
+            }
+        }
+        self.openings.sort_by_key(|(_, t)| *t);
+    }
+}
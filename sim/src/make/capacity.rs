@@ -0,0 +1,149 @@
+//! Synthetic populations sometimes send far more people to work or school in one building than
+//! could possibly fit there. This moves the extra people to the closest building with room left.
+
+use std::collections::BTreeMap;
+
+use abstutil::prettyprint_usize;
+use geom::Pt2D;
+use map_model::{Building, BuildingID, BuildingType, BuildingUse, Map};
+
+use crate::{Scenario, TripEndpoint, TripPurpose};
+
+// Floor space per student, in square meters
+const SCHOOL_SPACE_PER_STUDENT: f64 = 10.0;
+
+/// What happened while fitting a scenario to building capacities.
+#[derive(Clone, Debug, Default)]
+pub struct CapacityUsage {
+    /// How many people were sent to a different building, because theirs was full
+    pub reassigned: usize,
+    /// For each full building, how many people couldn't be sent anywhere else with room left.
+    /// Their trips are unchanged.
+    pub unallocated: BTreeMap<BuildingID, usize>,
+}
+
+impl CapacityUsage {
+    pub fn describe(&self) -> String {
+        format!(
+            "{} people moved to a building with room, {} people had nowhere to go",
+            prettyprint_usize(self.reassigned),
+            prettyprint_usize(self.unallocated.values().sum())
+        )
+    }
+}
+
+/// How many people can work in a building, as estimated while importing.
+fn job_capacity(b: &Building) -> usize {
+    match b.bldg_type {
+        BuildingType::Commercial(workers) | BuildingType::ResidentialCommercial(_, workers) => {
+            workers
+        }
+        BuildingType::Residential(_) | BuildingType::Empty => 0,
+    }
+}
+
+/// How many students can attend a building, based on its floor area. Only schools have room.
+fn student_capacity(b: &Building) -> usize {
+    if b.bldg_use == BuildingUse::School {
+        (b.floor_area() / SCHOOL_SPACE_PER_STUDENT) as usize
+    } else {
+        0
+    }
+}
+
+/// The room left in every building for one trip purpose.
+struct Capacities {
+    remaining: BTreeMap<BuildingID, usize>,
+    // Buildings with some room left, for finding the closest one
+    open: Vec<(BuildingID, Pt2D)>,
+}
+
+impl Capacities {
+    fn new(map: &Map, capacity: fn(&Building) -> usize) -> Capacities {
+        let mut remaining = BTreeMap::new();
+        let mut open = Vec::new();
+        for b in map.all_buildings() {
+            let n = capacity(b);
+            remaining.insert(b.id, n);
+            if n > 0 {
+                open.push((b.id, b.polygon.center()));
+            }
+        }
+        Capacities { remaining, open }
+    }
+
+    /// Returns false if the building is already full.
+    fn take(&mut self, b: BuildingID) -> bool {
+        match self.remaining.get_mut(&b) {
+            Some(n) if *n > 0 => {
+                *n -= 1;
+                if *n == 0 {
+                    self.open.retain(|(id, _)| *id != b);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn closest_open(&self, pt: Pt2D) -> Option<BuildingID> {
+        self.open
+            .iter()
+            .min_by(|(_, pt1), (_, pt2)| pt1.dist_to(pt).partial_cmp(&pt2.dist_to(pt)).unwrap())
+            .map(|(b, _)| *b)
+    }
+}
+
+impl Scenario {
+    /// Makes sure no building has more people working or going to school there than it has room
+    /// for. People claim room in the order they appear in the scenario, and anybody who doesn't
+    /// fit goes to the closest building with room left. Every trip a person makes to the same
+    /// workplace or school goes to the same replacement. Other trip purposes aren't limited.
+    pub fn respect_capacities(mut self, map: &Map) -> (Scenario, CapacityUsage) {
+        let mut usage = CapacityUsage::default();
+        let mut capacities = [
+            Capacities::new(map, job_capacity),
+            Capacities::new(map, student_capacity),
+        ];
+
+        for person in &mut self.people {
+            // (work or school, original building) to where the person actually goes
+            let mut assigned: BTreeMap<(usize, BuildingID), BuildingID> = BTreeMap::new();
+            for trip in &mut person.trips {
+                let idx = match trip.purpose {
+                    TripPurpose::Work => 0,
+                    TripPurpose::School => 1,
+                    _ => continue,
+                };
+                let orig = match trip.destination {
+                    TripEndpoint::Bldg(b) => b,
+                    _ => continue,
+                };
+
+                let b = if let Some(b) = assigned.get(&(idx, orig)) {
+                    *b
+                } else {
+                    let caps = &mut capacities[idx];
+                    let b = if caps.take(orig) {
+                        orig
+                    } else if let Some(b) = caps.closest_open(map.get_b(orig).polygon.center()) {
+                        caps.take(b);
+                        usage.reassigned += 1;
+                        b
+                    } else {
+                        *usage.unallocated.entry(orig).or_insert(0) += 1;
+                        orig
+                    };
+                    assigned.insert((idx, orig), b);
+                    b
+                };
+                if b != orig {
+                    trip.destination = TripEndpoint::Bldg(b);
+                    trip.modified = true;
+                }
+            }
+        }
+
+        (self, usage)
+    }
+}
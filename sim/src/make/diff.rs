@@ -0,0 +1,255 @@
+//! A variation on a scenario -- a few hundred extra stadium trips, some commuters switching to
+//! transit -- is usually small compared to the scenario it's based on. Keeping just the
+//! difference means the base can be regenerated without losing the variation.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use abstutil::{prettyprint_usize, Counter, MapName};
+use geom::{Duration, Time};
+use map_model::BuildingID;
+
+use crate::{
+    BridgeOpenings, ParkingPrices, PersonSpec, Scenario, TrainSchedule, TransitFares, TripMode,
+};
+
+/// Everything needed to turn one scenario into another on the same map.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ScenarioDiff {
+    pub map_name: MapName,
+    /// The name of the scenario this was made from, just for describing it
+    pub base_name: String,
+    /// The name of the resulting scenario
+    pub scenario_name: String,
+    /// People only in the result
+    pub added: Vec<PersonSpec>,
+    /// People only in the base. They're matched exactly, so the diff can still be applied to a
+    /// base where other people have changed.
+    pub removed: Vec<PersonSpec>,
+
+    // Each of these is None if the setting is the same in both.
+    pub only_seed_buses: Option<Option<BTreeSet<String>>>,
+    pub parking_prices: Option<ParkingPrices>,
+    pub transit_fares: Option<TransitFares>,
+    pub train_schedule: Option<TrainSchedule>,
+    pub bridge_openings: Option<BridgeOpenings>,
+    pub abandon_trips_after: Option<Option<Duration>>,
+    pub idle_parked_cars: Option<BTreeMap<BuildingID, usize>>,
+}
+
+impl Scenario {
+    /// Finds the people and settings that differ between this scenario and another one on the
+    /// same map. Applying the result to this scenario produces the other one, except people may be
+    /// in a different order.
+    pub fn diff(&self, other: &Scenario) -> Result<ScenarioDiff, String> {
+        if self.map_name != other.map_name {
+            return Err(format!(
+                "{} is for {}, but {} is for {}",
+                self.scenario_name,
+                self.map_name.describe(),
+                other.scenario_name,
+                other.map_name.describe()
+            ));
+        }
+
+        let mut unmatched = index_people(&self.people);
+        let mut added = Vec::new();
+        for person in &other.people {
+            if let Some(indices) = unmatched.get_mut(&abstutil::to_json_terse(person)) {
+                if indices.pop().is_some() {
+                    continue;
+                }
+            }
+            added.push(person.clone());
+        }
+        let mut removed_indices: Vec<usize> = unmatched.into_iter().flat_map(|(_, v)| v).collect();
+        removed_indices.sort();
+        let removed = removed_indices
+            .into_iter()
+            .map(|idx| self.people[idx].clone())
+            .collect();
+
+        Ok(ScenarioDiff {
+            map_name: self.map_name.clone(),
+            base_name: self.scenario_name.clone(),
+            scenario_name: other.scenario_name.clone(),
+            added,
+            removed,
+
+            only_seed_buses: changed(&self.only_seed_buses, &other.only_seed_buses),
+            parking_prices: changed(&self.parking_prices, &other.parking_prices),
+            transit_fares: changed(&self.transit_fares, &other.transit_fares),
+            train_schedule: changed(&self.train_schedule, &other.train_schedule),
+            bridge_openings: changed(&self.bridge_openings, &other.bridge_openings),
+            abandon_trips_after: changed(&self.abandon_trips_after, &other.abandon_trips_after),
+            idle_parked_cars: changed(&self.idle_parked_cars, &other.idle_parked_cars),
+        })
+    }
+}
+
+impl ScenarioDiff {
+    /// Applies this variation on top of a base scenario. Fails if the base is for a different
+    /// map, or if somebody this removes isn't in the base.
+    pub fn apply(&self, base: &Scenario) -> Result<Scenario, String> {
+        if base.map_name != self.map_name {
+            return Err(format!(
+                "{} is for {}, not {}",
+                base.scenario_name,
+                base.map_name.describe(),
+                self.map_name.describe()
+            ));
+        }
+
+        let mut unmatched = index_people(&base.people);
+        let mut remove = vec![false; base.people.len()];
+        for (idx, person) in self.removed.iter().enumerate() {
+            match unmatched
+                .get_mut(&abstutil::to_json_terse(person))
+                .and_then(|indices| indices.pop())
+            {
+                Some(base_idx) => {
+                    remove[base_idx] = true;
+                }
+                None => {
+                    return Err(format!(
+                        "removed person #{} (orig_id {:?}) isn't in {}",
+                        idx, person.orig_id, base.scenario_name
+                    ));
+                }
+            }
+        }
+
+        let mut result = base.clone();
+        result.scenario_name = self.scenario_name.clone();
+        result.people = base
+            .people
+            .iter()
+            .zip(remove)
+            .filter(|(_, remove)| !remove)
+            .map(|(person, _)| person.clone())
+            .chain(self.added.iter().cloned())
+            .collect();
+        if let Some(ref x) = self.only_seed_buses {
+            result.only_seed_buses = x.clone();
+        }
+        if let Some(ref x) = self.parking_prices {
+            result.parking_prices = x.clone();
+        }
+        if let Some(ref x) = self.transit_fares {
+            result.transit_fares = x.clone();
+        }
+        if let Some(ref x) = self.train_schedule {
+            result.train_schedule = x.clone();
+        }
+        if let Some(ref x) = self.bridge_openings {
+            result.bridge_openings = x.clone();
+        }
+        if let Some(x) = self.abandon_trips_after {
+            result.abandon_trips_after = x;
+        }
+        if let Some(ref x) = self.idle_parked_cars {
+            result.idle_parked_cars = x.clone();
+        }
+        Ok(result)
+    }
+
+    /// A few lines summarizing the variation, like "adds 500 people, making 1,000 trips (1,000
+    /// driving) departing between 6:30PM and 7:00PM".
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = vec![format!("{} -> {}", self.base_name, self.scenario_name)];
+        if !self.added.is_empty() {
+            lines.push(format!("adds {}", describe_people(&self.added)));
+        }
+        if !self.removed.is_empty() {
+            lines.push(format!("removes {}", describe_people(&self.removed)));
+        }
+        if let Some(ref x) = self.only_seed_buses {
+            lines.push(match x {
+                Some(routes) => format!("only runs {} bus routes", routes.len()),
+                None => "runs all bus routes".to_string(),
+            });
+        }
+        if self.parking_prices.is_some() {
+            lines.push("changes parking prices".to_string());
+        }
+        if self.transit_fares.is_some() {
+            lines.push("changes transit fares".to_string());
+        }
+        if self.train_schedule.is_some() {
+            lines.push("changes the train schedule".to_string());
+        }
+        if self.bridge_openings.is_some() {
+            lines.push("changes when bridges open".to_string());
+        }
+        if let Some(x) = self.abandon_trips_after {
+            lines.push(match x {
+                Some(dt) => format!("people give up on trips after waiting {}", dt),
+                None => "people never give up on trips".to_string(),
+            });
+        }
+        if let Some(ref x) = self.idle_parked_cars {
+            lines.push(format!(
+                "{} cars stay parked all day",
+                prettyprint_usize(x.values().sum())
+            ));
+        }
+        if lines.len() == 1 {
+            lines.push("no changes".to_string());
+        }
+        lines
+    }
+}
+
+// People are compared by their JSON, since PersonSpec contains floats and can't be hashed.
+fn index_people(people: &[PersonSpec]) -> BTreeMap<String, Vec<usize>> {
+    let mut index: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (idx, person) in people.iter().enumerate().rev() {
+        index
+            .entry(abstutil::to_json_terse(person))
+            .or_default()
+            .push(idx);
+    }
+    index
+}
+
+fn changed<T: Clone + Serialize>(before: &T, after: &T) -> Option<T> {
+    if abstutil::to_json_terse(before) == abstutil::to_json_terse(after) {
+        None
+    } else {
+        Some(after.clone())
+    }
+}
+
+fn describe_people(people: &[PersonSpec]) -> String {
+    let mut modes = Counter::new();
+    let mut earliest: Option<Time> = None;
+    let mut latest: Option<Time> = None;
+    for person in people {
+        for trip in &person.trips {
+            modes.inc(trip.mode);
+            earliest = Some(earliest.map_or(trip.depart, |t| t.min(trip.depart)));
+            latest = Some(latest.map_or(trip.depart, |t| t.max(trip.depart)));
+        }
+    }
+    let mut txt = format!(
+        "{} people, making {} trips",
+        prettyprint_usize(people.len()),
+        prettyprint_usize(modes.sum())
+    );
+    if let (Some(t1), Some(t2)) = (earliest, latest) {
+        let by_mode: Vec<String> = TripMode::all()
+            .into_iter()
+            .filter(|m| modes.get(*m) > 0)
+            .map(|m| format!("{} {}", prettyprint_usize(modes.get(m)), m.ongoing_verb()))
+            .collect();
+        txt = format!(
+            "{} ({}) departing between {} and {}",
+            txt,
+            by_mode.join(", "),
+            t1.ampm_tostring(),
+            t2.ampm_tostring()
+        );
+    }
+    txt
+}
@@ -0,0 +1,105 @@
+//! With a fixed set of trips, making a road faster only ever shortens the same trips. In reality,
+//! more people travel when it gets easier -- induced demand -- and fewer when it gets harder. This
+//! scales how many people make each trip by how much its travel time changed.
+
+use std::collections::BTreeMap;
+
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use geom::Duration;
+
+use crate::{Scenario, TripEndpoint, TripID, TripMode};
+
+/// Where somebody starts, where their first trip goes, and how they get there. People are grouped
+/// this way to compare travel times between runs, since the people themselves change.
+pub type ODPair = (TripEndpoint, TripEndpoint, TripMode);
+
+/// How the number of trips responds to travel times.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ElasticDemand {
+    /// The percent change in trips for a 1% change in travel time. Usually negative; -0.5 means
+    /// trips that get 10% slower are made about 5% less often.
+    pub elasticity: f64,
+}
+
+impl ElasticDemand {
+    /// For every group of people making the same trip in both runs, how much to scale the number
+    /// of them. Groups missing from either run aren't changed.
+    pub fn demand_factors(
+        &self,
+        before: &BTreeMap<ODPair, Duration>,
+        after: &BTreeMap<ODPair, Duration>,
+    ) -> BTreeMap<ODPair, f64> {
+        let mut factors = BTreeMap::new();
+        for (od, t1) in before {
+            if let Some(t2) = after.get(od) {
+                if *t1 > Duration::ZERO {
+                    factors.insert(od.clone(), (*t2 / *t1).powf(self.elasticity));
+                }
+            }
+        }
+        factors
+    }
+
+    /// Copies the scenario, scaling the number of people in each group by its factor. Each
+    /// person is kept, dropped, or repeated at random, so the expected number of people in a group
+    /// matches the factor even when the group is small.
+    pub fn apply(
+        &self,
+        scenario: &Scenario,
+        factors: &BTreeMap<ODPair, f64>,
+        rng: &mut XorShiftRng,
+    ) -> Scenario {
+        let mut result = scenario.clone();
+        result.people.clear();
+        for person in &scenario.people {
+            let factor = person
+                .trips
+                .first()
+                .and_then(|trip| {
+                    factors.get(&(person.origin.clone(), trip.destination.clone(), trip.mode))
+                })
+                .cloned()
+                .unwrap_or(1.0);
+            let copies =
+                (factor.trunc() as usize) + if rng.gen_bool(factor.fract()) { 1 } else { 0 };
+            for _ in 0..copies {
+                result.people.push(person.clone());
+            }
+        }
+        result
+    }
+}
+
+impl Scenario {
+    /// The average total time that the people making each first trip spent traveling, given the
+    /// duration of every trip that finished in a run of this scenario. Assumes nobody else was in
+    /// the simulation, so trip IDs match the order people and their trips are listed. People with
+    /// any trip that didn't finish aren't counted.
+    pub fn travel_time_per_od(
+        &self,
+        finished: &BTreeMap<TripID, Duration>,
+    ) -> BTreeMap<ODPair, Duration> {
+        let mut sums: BTreeMap<ODPair, (Duration, usize)> = BTreeMap::new();
+        let mut next_id = 0;
+        for person in &self.people {
+            let ids = (next_id..next_id + person.trips.len()).map(TripID);
+            next_id += person.trips.len();
+            let total = ids
+                .map(|id| finished.get(&id).cloned())
+                .collect::<Option<Vec<Duration>>>();
+            if let (Some(trip), Some(total)) = (person.trips.first(), total) {
+                let entry = sums
+                    .entry((person.origin.clone(), trip.destination.clone(), trip.mode))
+                    .or_insert((Duration::ZERO, 0));
+                entry.0 += total.into_iter().sum();
+                entry.1 += 1;
+            }
+        }
+        sums.into_iter()
+            .map(|(od, (sum, cnt))| (od, sum / (cnt as f64)))
+            .collect()
+    }
+}
@@ -1,18 +1,27 @@
 //! This is a much more primitive way to randomly generate trips. activity_model.rs has something
 //! more realistic.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
+use rand::distributions::{Distribution, WeightedIndex};
 use rand::seq::SliceRandom;
 use rand::Rng;
+use rand_distr::{Normal, Triangular};
 use rand_xorshift::XorShiftRng;
+use serde::de::{self, Deserializer};
 use serde::{Deserialize, Serialize};
 
 use abstutil::Timer;
-use geom::{Duration, Time};
-use map_model::{IntersectionID, Map};
+use geom::{Distance, Duration, FindClosest, FindContained, Polygon, Speed, Time};
+use map_model::{
+    osm, AreaID, AreaType, Building, BuildingID, BuildingUse, Intersection, IntersectionID, Map,
+    RoadID,
+};
 
-use crate::{IndividTrip, PersonSpec, Scenario, TripEndpoint, TripMode, TripPurpose};
+use crate::{
+    IndividTrip, MissingDataPolicy, PersonSpec, Scenario, ScenarioPhase, TripEndpoint, TripMode,
+    TripPurpose,
+};
 
 // TODO This can be simplified dramatically.
 
@@ -23,6 +32,116 @@ pub struct ScenarioGenerator {
     pub only_seed_buses: Option<BTreeSet<String>>,
     pub spawn_over_time: Vec<SpawnOverTime>,
     pub border_spawn_over_time: Vec<BorderSpawnOverTime>,
+    /// Areas (usually parks) that people visit and then return home from
+    #[serde(default)]
+    pub area_visits: Vec<AreaVisits>,
+    /// Big events at one building, like a game or a concert
+    #[serde(default)]
+    pub venue_events: Vec<VenueEvent>,
+    /// When a trip can go anywhere, how to choose the building. If unset, every building is
+    /// equally likely.
+    #[serde(default)]
+    pub destination_weights: Option<DestinationWeights>,
+    /// Vehicles driving straight through the map, from one border to another
+    #[serde(default)]
+    pub through_traffic: Vec<ThroughTraffic>,
+    /// Cars parked at home all day, besides the ones people drive
+    #[serde(default)]
+    pub seed_parked_cars: Option<SeedParkedCars>,
+    /// Named parts of the day, each with spawn blocks timed from the start of the phase
+    #[serde(default)]
+    pub phases: Vec<ScenarioPhase>,
+}
+
+/// Weights destination buildings by their use, floor area, and amenities, so more trips go to a
+/// big office building or a row of shops than to a garden shed.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct DestinationWeights {
+    /// Multiplied by the floor area in square meters. Uses that aren't listed never attract trips,
+    /// except for their amenities.
+    pub per_use: BTreeMap<BuildingUse, f64>,
+    /// Added for each amenity -- a cafe, a dentist -- in the building, since these attract trips
+    /// even when the building is small or isn't tagged with a use
+    #[serde(default)]
+    pub per_amenity: f64,
+}
+
+impl DestinationWeights {
+    /// Roughly how many trips per square meter each use attracts, relative to housing
+    pub fn typical() -> DestinationWeights {
+        let mut per_use = BTreeMap::new();
+        per_use.insert(BuildingUse::Residential, 1.0);
+        per_use.insert(BuildingUse::Office, 2.0);
+        per_use.insert(BuildingUse::Retail, 5.0);
+        per_use.insert(BuildingUse::School, 3.0);
+        per_use.insert(BuildingUse::Industrial, 0.5);
+        per_use.insert(BuildingUse::Other, 0.1);
+        DestinationWeights {
+            per_use,
+            per_amenity: 500.0,
+        }
+    }
+
+    /// How strongly a building attracts trips, relative to others
+    pub fn weight(&self, b: &Building) -> f64 {
+        self.per_use.get(&b.bldg_use).cloned().unwrap_or(0.0) * b.floor_area()
+            + self.per_amenity * (b.amenities.len() as f64)
+    }
+}
+
+/// Picks the building for trips that can go anywhere.
+struct Destinations {
+    weights: Option<DestinationWeights>,
+    // None means uniformly random
+    weighted: Option<WeightedIndex<f64>>,
+}
+
+impl Destinations {
+    fn new(weights: &Option<DestinationWeights>, map: &Map, timer: &mut Timer) -> Destinations {
+        let weighted = weights.as_ref().and_then(|weights| {
+            let per_bldg = map.all_buildings().iter().map(|b| weights.weight(b));
+            match WeightedIndex::new(per_bldg) {
+                Ok(x) => Some(x),
+                Err(err) => {
+                    timer.warn(format!(
+                        "Can't weight destinations ({}), so picking uniformly",
+                        err
+                    ));
+                    None
+                }
+            }
+        });
+        Destinations {
+            weights: weights.clone(),
+            weighted,
+        }
+    }
+
+    /// Picks one of a few buildings, using the same weights as anywhere else
+    fn pick_from(
+        &self,
+        rng: &mut XorShiftRng,
+        choices: &[BuildingID],
+        map: &Map,
+    ) -> Option<BuildingID> {
+        if let Some(ref weights) = self.weights {
+            // If none of the choices attract trips, fall back to picking uniformly
+            if let Ok(weighted) =
+                WeightedIndex::new(choices.iter().map(|b| weights.weight(map.get_b(*b))))
+            {
+                return Some(choices[weighted.sample(rng)]);
+            }
+        }
+        choices.choose(rng).cloned()
+    }
+
+    fn pick(&self, rng: &mut XorShiftRng, map: &Map) -> TripEndpoint {
+        let b = match self.weighted {
+            Some(ref weighted) => map.all_buildings()[weighted.sample(rng)].id,
+            None => map.all_buildings().choose(rng).unwrap().id,
+        };
+        TripEndpoint::Bldg(b)
+    }
 }
 
 // SpawnOverTime and BorderSpawnOverTime should be kept separate. Agents in SpawnOverTime pick
@@ -31,10 +150,23 @@ pub struct ScenarioGenerator {
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct SpawnOverTime {
     pub num_agents: usize,
-    // TODO use https://docs.rs/rand/0.5.5/rand/distributions/struct.Normal.html
     pub start_time: Time,
     pub stop_time: Time,
+    /// How departures are spread out between start_time and stop_time
+    #[serde(default)]
+    pub departures: DepartureDistribution,
+    /// People leave from one of these buildings. If this is empty, they can leave from anywhere.
+    #[serde(default)]
+    pub origins: Vec<BuildingID>,
     pub goal: Option<TripEndpoint>,
+    /// If there's no goal, people go to one of these buildings. If this is empty too, they can go
+    /// anywhere.
+    #[serde(default)]
+    pub destinations: Vec<BuildingID>,
+    /// Instead of one trip to the goal, people visit each of these in order and then go home. The
+    /// goal and destinations are ignored if this is set.
+    #[serde(default)]
+    pub activities: Vec<Activity>,
     pub percent_driving: f64,
     pub percent_biking: f64,
     pub percent_use_transit: f64,
@@ -46,26 +178,223 @@ pub struct BorderSpawnOverTime {
     pub num_cars: usize,
     pub num_bikes: usize,
     pub percent_use_transit: f64,
-    // TODO use https://docs.rs/rand/0.5.5/rand/distributions/struct.Normal.html
     pub start_time: Time,
     pub stop_time: Time,
+    /// How departures are spread out between start_time and stop_time
+    #[serde(default)]
+    pub departures: DepartureDistribution,
     pub start_from_border: IntersectionID,
     pub goal: Option<TripEndpoint>,
+    /// If there's no goal, people go to one of these buildings. If this is empty too, they can go
+    /// anywhere.
+    #[serde(default)]
+    pub destinations: Vec<BuildingID>,
+}
+
+/// One stop in a chain of trips
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Activity {
+    pub purpose: TripPurpose,
+    /// The activity happens at one of these buildings. If this is empty, it can be anywhere.
+    #[serde(default)]
+    pub places: Vec<BuildingID>,
+    /// How long people stay before heading to the next stop. If they arrive later than expected,
+    /// the next trip waits until they get there, so they may stay less than this.
+    pub dwell: Duration,
+}
+
+/// Within a spawn block's window, when do people leave?
+#[derive(Clone, Copy, Serialize, Debug, PartialEq)]
+pub enum DepartureDistribution {
+    /// Any time in the window is equally likely
+    Uniform,
+    /// Most people leave around the mean. Times drawn outside the window are drawn again, so the
+    /// mean and standard deviation of the result are only approximate.
+    Normal { mean: Time, stddev: Duration },
+    /// Departures ramp up steadily from the start of the window to the peak, then taper off until
+    /// the end, like a rush hour.
+    Peaked { peak: Time },
+}
+
+// Spawn blocks usually come from hand-written JSON, so catch a bad stddev while loading, instead of
+// panicking in the middle of generating.
+impl<'de> Deserialize<'de> for DepartureDistribution {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DepartureDistribution, D::Error> {
+        let dist = match SavedDepartureDistribution::deserialize(deserializer)? {
+            SavedDepartureDistribution::Uniform => DepartureDistribution::Uniform,
+            SavedDepartureDistribution::Normal { mean, stddev } => {
+                DepartureDistribution::Normal { mean, stddev }
+            }
+            SavedDepartureDistribution::Peaked { peak } => DepartureDistribution::Peaked { peak },
+        };
+        if let DepartureDistribution::Normal { stddev, .. } = dist {
+            if stddev <= Duration::ZERO {
+                return Err(de::Error::custom(format!(
+                    "departure time stddev must be positive, not {}",
+                    stddev
+                )));
+            }
+        }
+        Ok(dist)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "DepartureDistribution")]
+enum SavedDepartureDistribution {
+    Uniform,
+    Normal { mean: Time, stddev: Duration },
+    Peaked { peak: Time },
+}
+
+impl Default for DepartureDistribution {
+    fn default() -> DepartureDistribution {
+        DepartureDistribution::Uniform
+    }
+}
+
+impl DepartureDistribution {
+    fn sample(&self, rng: &mut XorShiftRng, low: Time, high: Time) -> Time {
+        match *self {
+            DepartureDistribution::Uniform => rand_time(rng, low, high),
+            DepartureDistribution::Normal { mean, stddev } => {
+                assert!(high > low);
+                let normal = Normal::new(mean.inner_seconds(), stddev.inner_seconds())
+                    .expect("Departure time stddev must be positive");
+                // If the mean is far outside the window, almost every draw misses, so eventually
+                // give up
+                for _ in 0..100 {
+                    let secs = normal.sample(rng);
+                    if secs >= low.inner_seconds() && secs < high.inner_seconds() {
+                        return Time::START_OF_DAY + Duration::seconds(secs);
+                    }
+                }
+                mean.max(low).min(high)
+            }
+            DepartureDistribution::Peaked { peak } => {
+                assert!(high > low);
+                let triangular = Triangular::new(
+                    low.inner_seconds(),
+                    high.inner_seconds(),
+                    peak.max(low).min(high).inner_seconds(),
+                )
+                .unwrap();
+                Time::START_OF_DAY + Duration::seconds(triangular.sample(rng))
+            }
+        }
+    }
+}
+
+/// Traffic passing through the map without stopping, which dominates maps of arterial corridors.
+/// Each vehicle enters at a random border and leaves at a different one. Borders on bigger roads
+/// are more likely, so most of this traffic enters and leaves by highways and arterials.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ThroughTraffic {
+    pub num_cars: usize,
+    pub num_bikes: usize,
+    pub start_time: Time,
+    pub stop_time: Time,
+    /// How departures are spread out between start_time and stop_time
+    #[serde(default)]
+    pub departures: DepartureDistribution,
+}
+
+/// Cars that stay parked at home all day. Their owners don't go anywhere, but the cars still take
+/// up parking that everybody else competes for.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub enum SeedParkedCars {
+    /// Every residential building gets a number of cars picked with these weights. The first is
+    /// the weight of no cars, the second of one car, and so on.
+    PerBuilding(Vec<usize>),
+    /// Scales with each residential building's floor area, so apartment blocks get more cars than
+    /// houses. Cars that don't fit in the building's own off-street parking spill onto its road,
+    /// but never more than the road has spots left after the cars people drive.
+    ByFloorArea { cars_per_100_sq_meters: f64 },
+}
+
+/// People leave a random building, go to the edge of an area, stay there a while, then return.
+/// Trips can only end at buildings, so visitors go to a building near the edge of the area.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct AreaVisits {
+    pub area: AreaID,
+    pub num_visitors: usize,
+    pub start_time: Time,
+    pub stop_time: Time,
+    pub min_stay: Duration,
+    pub max_stay: Duration,
+    pub percent_driving: f64,
+    pub percent_biking: f64,
+    pub percent_use_transit: f64,
+}
+
+/// Everybody attending an event at a venue, like a stadium or concert hall, arrives in a pulse
+/// before it starts and leaves in a pulse after it ends. This produces both the crowds walking to
+/// and from the venue and the demand for parking around it.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct VenueEvent {
+    pub venue: BuildingID,
+    pub num_attendees: usize,
+    pub start_time: Time,
+    pub end_time: Time,
+    /// Attendees set off from home within this long before the start, more of them close to it.
+    pub arrival_window: Duration,
+    /// Attendees head home within this long after the end, most of them right away.
+    pub departure_window: Duration,
+    pub percent_driving: f64,
+    pub percent_biking: f64,
+    pub percent_use_transit: f64,
+    /// Where attendees come from. If this is empty, they live anywhere on the map.
+    pub origins: Vec<EventOrigin>,
+}
+
+/// Some share of an event's attendees live in buildings inside this polygon.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct EventOrigin {
+    pub name: String,
+    pub polygon: Polygon,
+    /// Relative to the other origins of the same event
+    pub weight: f64,
+    /// By default, a building belongs to the origin if its center is inside the polygon. If this
+    /// is set, it belongs if at least this fraction of its footprint (from 0 to 1) is inside
+    /// instead, so buildings straddling the boundary aren't left out.
+    #[serde(default)]
+    pub min_overlap: Option<f64>,
 }
 
 impl ScenarioGenerator {
+    /// Spawn blocks referring to anything missing from the map are left out, with a warning.
     // TODO may need to fork the RNG a bit more
     pub fn generate(&self, map: &Map, rng: &mut XorShiftRng, timer: &mut Timer) -> Scenario {
+        // This also moves everything out of phases. Skipping can't fail.
+        let (generator, report) = self
+            .clone()
+            .handle_missing_data(map, MissingDataPolicy::Skip)
+            .unwrap();
+        for problem in report {
+            timer.warn(problem.to_string());
+        }
+        generator.generate_without_problems(map, rng, timer)
+    }
+
+    fn generate_without_problems(
+        &self,
+        map: &Map,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) -> Scenario {
         let mut scenario = Scenario::empty(map, &self.scenario_name);
         scenario.only_seed_buses = self.only_seed_buses.clone();
 
         timer.start(format!("Generating scenario {}", self.scenario_name));
+        let destinations = Destinations::new(&self.destination_weights, map, timer);
 
         for s in &self.spawn_over_time {
             timer.start_iter("SpawnOverTime each agent", s.num_agents);
             for _ in 0..s.num_agents {
                 timer.next();
-                s.spawn_agent(rng, &mut scenario, map);
+                s.spawn_agent(rng, &mut scenario, &destinations, map);
             }
         }
 
@@ -78,59 +407,88 @@ impl ScenarioGenerator {
                 } else {
                     TripMode::Walk
                 };
-                s.spawn(rng, &mut scenario, mode, map);
+                s.spawn(rng, &mut scenario, mode, &destinations, map);
             }
             for _ in 0..s.num_cars {
-                s.spawn(rng, &mut scenario, TripMode::Drive, map);
+                s.spawn(rng, &mut scenario, TripMode::Drive, &destinations, map);
             }
             for _ in 0..s.num_bikes {
-                s.spawn(rng, &mut scenario, TripMode::Bike, map);
+                s.spawn(rng, &mut scenario, TripMode::Bike, &destinations, map);
             }
         }
 
+        timer.start_iter("ThroughTraffic", self.through_traffic.len());
+        for s in &self.through_traffic {
+            timer.next();
+            s.spawn_all(rng, &mut scenario, map, timer);
+        }
+
+        if !self.area_visits.is_empty() {
+            let mut closest_bldg = FindClosest::new(map.get_bounds());
+            for b in map.all_buildings() {
+                closest_bldg.add(b.id, b.polygon.points());
+            }
+            timer.start_iter("AreaVisits", self.area_visits.len());
+            for s in &self.area_visits {
+                timer.next();
+                let entrances = bldgs_near_area(map, &closest_bldg, s.area);
+                if entrances.is_empty() {
+                    timer.warn(format!(
+                        "No buildings near {}, so nobody can visit it",
+                        s.area
+                    ));
+                    continue;
+                }
+                for _ in 0..s.num_visitors {
+                    s.spawn(rng, &mut scenario, &entrances, map);
+                }
+            }
+        }
+
+        // Built once for all events, since matching every building against every origin is slow
+        let mut homes = FindContained::new(map.get_bounds());
+        if !self.venue_events.is_empty() {
+            for b in map.all_buildings() {
+                homes.add_area(b.id, b.label_center, &b.polygon);
+            }
+        }
+        timer.start_iter("VenueEvents", self.venue_events.len());
+        for s in &self.venue_events {
+            timer.next();
+            let origins = s.origin_bldgs(&homes, map, timer);
+            for _ in 0..s.num_attendees {
+                s.spawn(rng, &mut scenario, &origins, map);
+            }
+        }
+
+        if let Some(ref s) = self.seed_parked_cars {
+            s.seed(rng, &mut scenario, map, timer);
+        }
+
         timer.stop(format!("Generating scenario {}", self.scenario_name));
         scenario.remove_weird_schedules()
     }
 
-    pub fn small_run(map: &Map) -> ScenarioGenerator {
-        let mut s = ScenarioGenerator {
-            scenario_name: "small_run".to_string(),
-            only_seed_buses: None,
-            spawn_over_time: vec![SpawnOverTime {
-                num_agents: 100,
-                start_time: Time::START_OF_DAY,
-                stop_time: Time::START_OF_DAY + Duration::seconds(5.0),
-                goal: None,
-                percent_driving: 0.5,
-                percent_biking: 0.5,
-                percent_use_transit: 0.5,
-            }],
-            // If there are no sidewalks/driving lanes at a border, scenario instantiation will
-            // just warn and skip them.
-            border_spawn_over_time: map
-                .all_incoming_borders()
-                .into_iter()
-                .map(|i| BorderSpawnOverTime {
-                    num_peds: 10,
-                    num_cars: 10,
-                    num_bikes: 10,
-                    start_time: Time::START_OF_DAY,
-                    stop_time: Time::START_OF_DAY + Duration::seconds(5.0),
-                    start_from_border: i.id,
-                    goal: None,
-                    percent_use_transit: 0.5,
-                })
-                .collect(),
-        };
-        for i in map.all_outgoing_borders() {
-            s.spawn_over_time.push(SpawnOverTime {
-                num_agents: 10,
-                start_time: Time::START_OF_DAY,
-                stop_time: Time::START_OF_DAY + Duration::seconds(5.0),
-                goal: Some(TripEndpoint::Border(i.id)),
-                percent_driving: 0.5,
-                percent_biking: 0.5,
-                percent_use_transit: 0.5,
+    /// Lots of people visit every park during the middle of the day, in proportion to its size.
+    pub fn sunny_day(map: &Map) -> ScenarioGenerator {
+        let mut s = ScenarioGenerator::empty("sunny_day");
+        s.only_seed_buses = None;
+        for a in map.all_areas() {
+            if a.area_type != AreaType::Park {
+                continue;
+            }
+            // 20 visitors per hectare, but at least a few for tiny parks
+            let hectares = a.polygon.area() / 10_000.0;
+            s.area_visits.push(AreaVisits {
+                area: a.id,
+                num_visitors: ((20.0 * hectares) as usize).max(5),
+                start_time: Time::START_OF_DAY + Duration::hours(10),
+                stop_time: Time::START_OF_DAY + Duration::hours(17),
+                min_stay: Duration::minutes(30),
+                max_stay: Duration::hours(3),
+                percent_driving: 0.3,
+                percent_biking: 0.3,
+                percent_use_transit: 0.3,
             });
         }
         s
@@ -142,59 +500,532 @@ impl ScenarioGenerator {
             only_seed_buses: Some(BTreeSet::new()),
             spawn_over_time: Vec::new(),
             border_spawn_over_time: Vec::new(),
+            area_visits: Vec::new(),
+            venue_events: Vec::new(),
+            destination_weights: None,
+            through_traffic: Vec::new(),
+            seed_parked_cars: None,
+            phases: Vec::new(),
         }
     }
 }
 
 impl SpawnOverTime {
-    fn spawn_agent(&self, rng: &mut XorShiftRng, scenario: &mut Scenario, map: &Map) {
-        let depart = rand_time(rng, self.start_time, self.stop_time);
+    fn spawn_agent(
+        &self,
+        rng: &mut XorShiftRng,
+        scenario: &mut Scenario,
+        destinations: &Destinations,
+        map: &Map,
+    ) {
+        let depart = self.departures.sample(rng, self.start_time, self.stop_time);
         // Note that it's fine for agents to start/end at the same building. Later we might
         // want a better assignment of people per household, or workers per office building.
-        let from_bldg = map.all_buildings().choose(rng).unwrap().id;
-        let mode = if rng.gen_bool(self.percent_driving) {
-            TripMode::Drive
-        } else if rng.gen_bool(self.percent_biking) {
-            TripMode::Bike
-        } else if rng.gen_bool(self.percent_use_transit) {
-            TripMode::Transit
+        let from_bldg = if self.origins.is_empty() {
+            map.all_buildings().choose(rng).unwrap().id
         } else {
-            TripMode::Walk
+            *self.origins.choose(rng).unwrap()
         };
+        let mode = pick_mode(
+            rng,
+            self.percent_driving,
+            self.percent_biking,
+            self.percent_use_transit,
+        );
+        if !self.activities.is_empty() {
+            scenario.people.push(PersonSpec {
+                orig_id: None,
+                origin: TripEndpoint::Bldg(from_bldg),
+                trips: self.chain_trips(rng, depart, from_bldg, mode, destinations, map),
+            });
+            return;
+        }
         scenario.people.push(PersonSpec {
             orig_id: None,
             origin: TripEndpoint::Bldg(from_bldg),
             trips: vec![IndividTrip::new(
                 depart,
                 TripPurpose::Shopping,
-                self.goal.clone().unwrap_or_else(|| {
-                    TripEndpoint::Bldg(map.all_buildings().choose(rng).unwrap().id)
-                }),
+                self.goal
+                    .clone()
+                    .unwrap_or_else(|| pick_goal(rng, &self.destinations, destinations, map)),
                 mode,
             )],
         });
     }
+
+    /// Visits every activity in order, then heads back home. The whole chain uses one mode, so
+    /// somebody who drives keeps the same car all day.
+    fn chain_trips(
+        &self,
+        rng: &mut XorShiftRng,
+        mut depart: Time,
+        home: BuildingID,
+        mode: TripMode,
+        destinations: &Destinations,
+        map: &Map,
+    ) -> Vec<IndividTrip> {
+        let mut trips = Vec::new();
+        let mut at = home;
+        for activity in &self.activities {
+            let next = match pick_goal(rng, &activity.places, destinations, map) {
+                TripEndpoint::Bldg(b) => b,
+                _ => unreachable!(),
+            };
+            trips.push(IndividTrip::new(
+                depart,
+                activity.purpose,
+                TripEndpoint::Bldg(next),
+                mode,
+            ));
+            depart = depart + estimate_trip_time(at, next, mode, map) + activity.dwell;
+            at = next;
+        }
+        trips.push(IndividTrip::new(
+            depart,
+            TripPurpose::Home,
+            TripEndpoint::Bldg(home),
+            mode,
+        ));
+        trips
+    }
 }
 
 impl BorderSpawnOverTime {
-    fn spawn(&self, rng: &mut XorShiftRng, scenario: &mut Scenario, mode: TripMode, map: &Map) {
-        let depart = rand_time(rng, self.start_time, self.stop_time);
+    fn spawn(
+        &self,
+        rng: &mut XorShiftRng,
+        scenario: &mut Scenario,
+        mode: TripMode,
+        destinations: &Destinations,
+        map: &Map,
+    ) {
+        let depart = self.departures.sample(rng, self.start_time, self.stop_time);
         scenario.people.push(PersonSpec {
             orig_id: None,
             origin: TripEndpoint::Border(self.start_from_border),
             trips: vec![IndividTrip::new(
                 depart,
                 TripPurpose::Shopping,
-                self.goal.clone().unwrap_or_else(|| {
-                    TripEndpoint::Bldg(map.all_buildings().choose(rng).unwrap().id)
-                }),
+                self.goal
+                    .clone()
+                    .unwrap_or_else(|| pick_goal(rng, &self.destinations, destinations, map)),
                 mode,
             )],
         });
     }
 }
 
+impl ThroughTraffic {
+    fn spawn_all(
+        &self,
+        rng: &mut XorShiftRng,
+        scenario: &mut Scenario,
+        map: &Map,
+        timer: &mut Timer,
+    ) {
+        for (mode, num) in &[
+            (TripMode::Drive, self.num_cars),
+            (TripMode::Bike, self.num_bikes),
+        ] {
+            if *num == 0 {
+                continue;
+            }
+            let entrances = BorderChoice::new(
+                map.all_incoming_borders()
+                    .into_iter()
+                    .filter(|i| TripEndpoint::Border(i.id).check_start(*mode, map).is_ok()),
+                map,
+            );
+            let exits = BorderChoice::new(
+                map.all_outgoing_borders()
+                    .into_iter()
+                    .filter(|i| TripEndpoint::Border(i.id).check_end(*mode, map).is_ok()),
+                map,
+            );
+            let (entrances, exits) = match (entrances, exits) {
+                (Some(x), Some(y)) => (x, y),
+                _ => {
+                    timer.warn(format!(
+                        "No borders to {} into and out of the map, so there's no through traffic",
+                        mode.verb()
+                    ));
+                    continue;
+                }
+            };
+            for _ in 0..*num {
+                let from = entrances.pick(rng);
+                // Don't turn around and leave the same way. If that's the only way out, give up
+                // eventually.
+                let to = match (0..10).map(|_| exits.pick(rng)).find(|i| *i != from) {
+                    Some(i) => i,
+                    None => {
+                        continue;
+                    }
+                };
+                let depart = self.departures.sample(rng, self.start_time, self.stop_time);
+                scenario.people.push(PersonSpec {
+                    orig_id: None,
+                    origin: TripEndpoint::Border(from),
+                    trips: vec![IndividTrip::new(
+                        depart,
+                        TripPurpose::Work,
+                        TripEndpoint::Border(to),
+                        *mode,
+                    )],
+                });
+            }
+        }
+    }
+}
+
+/// Picks borders, weighted by how big the road at each one is
+struct BorderChoice {
+    borders: Vec<IntersectionID>,
+    weighted: WeightedIndex<f64>,
+}
+
+impl BorderChoice {
+    fn new<'a, I: Iterator<Item = &'a Intersection>>(
+        borders: I,
+        map: &Map,
+    ) -> Option<BorderChoice> {
+        let mut ids = Vec::new();
+        let mut weights = Vec::new();
+        for i in borders {
+            let rank = i
+                .roads
+                .iter()
+                .map(|r| map.get_r(*r).get_rank())
+                .max()
+                .unwrap_or(osm::RoadRank::Local);
+            ids.push(i.id);
+            weights.push(match rank {
+                osm::RoadRank::Highway => 10.0,
+                osm::RoadRank::Arterial => 4.0,
+                osm::RoadRank::Local => 1.0,
+            });
+        }
+        Some(BorderChoice {
+            borders: ids,
+            weighted: WeightedIndex::new(weights).ok()?,
+        })
+    }
+
+    fn pick(&self, rng: &mut XorShiftRng) -> IntersectionID {
+        self.borders[self.weighted.sample(rng)]
+    }
+}
+
+impl SeedParkedCars {
+    fn seed(&self, rng: &mut XorShiftRng, scenario: &mut Scenario, map: &Map, timer: &mut Timer) {
+        let homes = map
+            .all_buildings()
+            .iter()
+            .filter(|b| b.bldg_type.has_residents());
+        match self {
+            SeedParkedCars::PerBuilding(weights) => {
+                let weighted = match WeightedIndex::new(weights) {
+                    Ok(x) => x,
+                    Err(err) => {
+                        timer.warn(format!("Can't seed parked cars ({})", err));
+                        return;
+                    }
+                };
+                for b in homes {
+                    let cnt = weighted.sample(rng);
+                    if cnt > 0 {
+                        scenario.idle_parked_cars.insert(b.id, cnt);
+                    }
+                }
+            }
+            SeedParkedCars::ByFloorArea {
+                cars_per_100_sq_meters,
+            } => {
+                let road = |b: BuildingID| map.get_l(map.get_b(b).sidewalk()).parent;
+                let driven = scenario.count_parked_cars_per_bldg();
+
+                // How many on-street spots each road has left after the cars people drive
+                let mut curb: BTreeMap<RoadID, usize> = BTreeMap::new();
+                for l in map.all_lanes() {
+                    if l.is_parking() {
+                        *curb.entry(l.parent).or_insert(0) += l.number_parking_spots();
+                    }
+                }
+                for (b, cnt) in driven.borrow() {
+                    let overflow = cnt.saturating_sub(map.get_b(*b).num_parking_spots());
+                    if let Some(spots) = curb.get_mut(&road(*b)) {
+                        *spots = spots.saturating_sub(overflow);
+                    }
+                }
+
+                // Per road, each building and how many cars go inside and on the street
+                let mut per_road: BTreeMap<RoadID, Vec<(BuildingID, usize, usize)>> =
+                    BTreeMap::new();
+                for b in homes {
+                    let wanted = (b.floor_area() / 100.0 * cars_per_100_sq_meters).round() as usize;
+                    if wanted == 0 {
+                        continue;
+                    }
+                    let inside = wanted.min(b.num_parking_spots().saturating_sub(driven.get(b.id)));
+                    per_road
+                        .entry(road(b.id))
+                        .or_default()
+                        .push((b.id, inside, wanted - inside));
+                }
+
+                // If the buildings along a road want more street parking than there is, they
+                // each get a proportional share
+                for (r, bldgs) in per_road {
+                    let room = curb.get(&r).cloned().unwrap_or(0);
+                    let wanted: usize = bldgs.iter().map(|(_, _, outside)| *outside).sum();
+                    let share = if wanted > room {
+                        (room as f64) / (wanted as f64)
+                    } else {
+                        1.0
+                    };
+                    for (b, inside, outside) in bldgs {
+                        let cnt = inside + ((outside as f64) * share).floor() as usize;
+                        if cnt > 0 {
+                            scenario.idle_parked_cars.insert(b, cnt);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn pick_goal(
+    rng: &mut XorShiftRng,
+    choices: &[BuildingID],
+    destinations: &Destinations,
+    map: &Map,
+) -> TripEndpoint {
+    match destinations.pick_from(rng, choices, map) {
+        Some(b) => TripEndpoint::Bldg(b),
+        None => destinations.pick(rng, map),
+    }
+}
+
+impl AreaVisits {
+    fn spawn(
+        &self,
+        rng: &mut XorShiftRng,
+        scenario: &mut Scenario,
+        entrances: &[BuildingID],
+        map: &Map,
+    ) {
+        let depart = rand_time(rng, self.start_time, self.stop_time);
+        let stay = if self.max_stay > self.min_stay {
+            Duration::seconds(
+                rng.gen_range(self.min_stay.inner_seconds(), self.max_stay.inner_seconds()),
+            )
+        } else {
+            self.min_stay
+        };
+        let home = TripEndpoint::Bldg(map.all_buildings().choose(rng).unwrap().id);
+        let mode = pick_mode(
+            rng,
+            self.percent_driving,
+            self.percent_biking,
+            self.percent_use_transit,
+        );
+        // Assume visitors take less than half an hour to get there
+        scenario.people.push(PersonSpec {
+            orig_id: None,
+            origin: home.clone(),
+            trips: vec![
+                IndividTrip::new(
+                    depart,
+                    TripPurpose::Recreation,
+                    TripEndpoint::Bldg(*entrances.choose(rng).unwrap()),
+                    mode,
+                ),
+                IndividTrip::new(
+                    depart + Duration::minutes(30) + stay,
+                    TripPurpose::Home,
+                    home,
+                    mode,
+                ),
+            ],
+        });
+    }
+}
+
+impl EventOrigin {
+    /// Does the building belong to this origin? See `min_overlap`.
+    pub(crate) fn contains_bldg(&self, b: &Building) -> bool {
+        if let Some(min_overlap) = self.min_overlap {
+            let inside: f64 = self
+                .polygon
+                .intersection(&b.polygon)
+                .iter()
+                .map(|p| p.area())
+                .sum();
+            inside >= min_overlap * b.polygon.area()
+        } else {
+            self.polygon.contains_pt(b.label_center)
+        }
+    }
+}
+
+impl VenueEvent {
+    /// The homes in each origin with a positive weight, along with a way to pick an origin. None
+    /// means attendees can live anywhere.
+    fn origin_bldgs(
+        &self,
+        all_homes: &FindContained<BuildingID>,
+        map: &Map,
+        timer: &mut Timer,
+    ) -> Option<(Vec<Vec<BuildingID>>, WeightedIndex<f64>)> {
+        let mut bldgs = Vec::new();
+        let mut weights = Vec::new();
+        for origin in &self.origins {
+            let mut homes = if origin.min_overlap.is_some() {
+                all_homes
+                    .overlapping(&origin.polygon)
+                    .into_iter()
+                    .filter(|b| origin.contains_bldg(map.get_b(*b)))
+                    .collect()
+            } else {
+                all_homes.contained_in(&origin.polygon)
+            };
+            // The quadtree doesn't return them in a stable order
+            homes.sort();
+            if homes.is_empty() || origin.weight <= 0.0 {
+                timer.warn(format!(
+                    "Nobody can come to an event at {} from {}",
+                    self.venue, origin.name
+                ));
+                continue;
+            }
+            bldgs.push(homes);
+            weights.push(origin.weight);
+        }
+        // If none of the origins work out, fall back to anywhere
+        let weighted = WeightedIndex::new(weights).ok()?;
+        Some((bldgs, weighted))
+    }
+
+    fn spawn(
+        &self,
+        rng: &mut XorShiftRng,
+        scenario: &mut Scenario,
+        origins: &Option<(Vec<Vec<BuildingID>>, WeightedIndex<f64>)>,
+        map: &Map,
+    ) {
+        // Squaring bunches the times up close to the start and end of the event
+        let arrival_window = self
+            .arrival_window
+            .min(self.start_time - Time::START_OF_DAY);
+        let arrive = self.start_time - arrival_window * rng.gen::<f64>().powi(2);
+        let leave = self.end_time + self.departure_window * rng.gen::<f64>().powi(2);
+        let home = match origins {
+            Some((bldgs, weighted)) => *bldgs[weighted.sample(rng)].choose(rng).unwrap(),
+            None => map.all_buildings().choose(rng).unwrap().id,
+        };
+        let home = TripEndpoint::Bldg(home);
+        let mode = pick_mode(
+            rng,
+            self.percent_driving,
+            self.percent_biking,
+            self.percent_use_transit,
+        );
+        scenario.people.push(PersonSpec {
+            orig_id: None,
+            origin: home.clone(),
+            trips: vec![
+                IndividTrip::new(
+                    arrive,
+                    TripPurpose::Recreation,
+                    TripEndpoint::Bldg(self.venue),
+                    mode,
+                ),
+                IndividTrip::new(leave, TripPurpose::Home, home, mode),
+            ],
+        });
+    }
+}
+
+/// Buildings close to the edge of an area, where visitors can start and end trips.
+fn bldgs_near_area(
+    map: &Map,
+    closest_bldg: &FindClosest<BuildingID>,
+    area: AreaID,
+) -> Vec<BuildingID> {
+    let mut bldgs = BTreeSet::new();
+    if let Some(a) = map.maybe_get_a(area) {
+        for pt in a.polygon.points() {
+            if let Some((b, _)) = closest_bldg.closest_pt(*pt, Distance::meters(50.0)) {
+                bldgs.insert(b);
+            }
+        }
+    }
+    bldgs.into_iter().collect()
+}
+
+fn pick_mode(
+    rng: &mut XorShiftRng,
+    percent_driving: f64,
+    percent_biking: f64,
+    percent_use_transit: f64,
+) -> TripMode {
+    if rng.gen_bool(percent_driving) {
+        TripMode::Drive
+    } else if rng.gen_bool(percent_biking) {
+        TripMode::Bike
+    } else if rng.gen_bool(percent_use_transit) {
+        TripMode::Transit
+    } else {
+        TripMode::Walk
+    }
+}
+
+/// A rough guess of how long a trip takes, used to schedule the next trip in a chain. Roads aren't
+/// straight lines, and people need a few minutes to get going and to park.
+fn estimate_trip_time(from: BuildingID, to: BuildingID, mode: TripMode, map: &Map) -> Duration {
+    let dist = map
+        .get_b(from)
+        .label_center
+        .dist_to(map.get_b(to).label_center);
+    let speed = match mode {
+        TripMode::Walk => Speed::meters_per_second(1.34),
+        TripMode::Bike => Speed::miles_per_hour(10.0),
+        TripMode::Transit => Speed::miles_per_hour(12.0),
+        TripMode::Drive => Speed::miles_per_hour(20.0),
+    };
+    Duration::minutes(5) + (1.5 * dist) / speed
+}
+
 fn rand_time(rng: &mut XorShiftRng, low: Time, high: Time) -> Time {
     assert!(high > low);
     Time::START_OF_DAY + Duration::seconds(rng.gen_range(low.inner_seconds(), high.inner_seconds()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reject_bad_stddev() {
+        let parse = |stddev: &str| {
+            abstutil::from_json::<DepartureDistribution>(
+                &format!(r#"{{"Normal": {{"mean": 28800.0, "stddev": {}}}}}"#, stddev).into_bytes(),
+            )
+        };
+        assert!(parse("0.0").is_err());
+        assert!(parse("-600.0").is_err());
+        assert_eq!(
+            parse("600.0").unwrap(),
+            DepartureDistribution::Normal {
+                mean: Time::START_OF_DAY + Duration::hours(8),
+                stddev: Duration::minutes(10),
+            }
+        );
+        assert_eq!(
+            abstutil::from_json::<DepartureDistribution>(&br#""Uniform""#.to_vec()).unwrap(),
+            DepartureDistribution::Uniform
+        );
+    }
+}
@@ -1,10 +1,12 @@
+use std::collections::BTreeMap;
+
 use rand::SeedableRng;
 use rand_xorshift::XorShiftRng;
 
-use abstutil::{CmdArgs, MapName};
+use abstutil::{CmdArgs, MapName, Timer};
 use map_model::{Map, MapEdits};
 
-use crate::{Scenario, ScenarioModifier, Sim, SimOptions};
+use crate::{MissingDataPolicy, Scenario, ScenarioModifier, Sim, SimOptions};
 
 /// SimFlags specifies a simulation to setup.
 #[derive(Clone)]
@@ -15,7 +17,14 @@ pub struct SimFlags {
     /// - some kind of map: start an empty simulation on the map
     pub load: String,
     pub modifiers: Vec<ScenarioModifier>,
+    /// Values for the variables in a JSON scenario file
+    pub params: BTreeMap<String, String>,
+    /// The map for a JSON scenario file, overriding the one named in the file or implied by where
+    /// the file is
+    pub map: Option<String>,
     pub rng_seed: u64,
+    /// What to do when a scenario refers to something missing from the map
+    pub missing_data: MissingDataPolicy,
     pub opts: SimOptions,
 }
 
@@ -31,12 +40,27 @@ impl SimFlags {
                 abstutil::from_json(&s.to_string().into_bytes())
             })
             .unwrap_or_else(Vec::new);
+        // A file of parameters, with individual values overridden on the command line
+        let mut params: BTreeMap<String, String> = args
+            .optional("--scenario_params_file")
+            .map(|path| abstutil::read_json(path, &mut Timer::throwaway()))
+            .unwrap_or_default();
+        if let Some(overrides) = args.optional_parse("--scenario_params", |s| {
+            abstutil::from_json::<BTreeMap<String, String>>(&s.to_string().into_bytes())
+        }) {
+            params.extend(overrides);
+        }
         SimFlags {
             load: args
                 .optional_free()
                 .unwrap_or_else(|| MapName::seattle("montlake").path()),
             modifiers,
+            params,
+            map: args.optional("--map"),
             rng_seed,
+            missing_data: args
+                .optional_parse("--missing_data", MissingDataPolicy::parse)
+                .unwrap_or_default(),
             opts: SimOptions::from_args(args, rng_seed),
         }
     }
@@ -46,7 +70,10 @@ impl SimFlags {
         SimFlags {
             load: MapName::seattle("montlake").path(),
             modifiers: Vec::new(),
+            params: BTreeMap::new(),
+            map: None,
             rng_seed: SimFlags::RNG_SEED,
+            missing_data: MissingDataPolicy::Abort,
             opts: SimOptions::new(run_name),
         }
     }
@@ -82,19 +109,37 @@ impl SimFlags {
             sim.restore_paths(&map, timer);
 
             (map, sim, rng)
-        } else if self.load.contains("/scenarios/") {
+        } else if self.load.contains("/scenarios/") || self.load.ends_with(".json") {
             timer.note(format!(
                 "Seeding the simulation from scenario {}",
                 self.load
             ));
 
-            let mut scenario: Scenario = abstutil::read_binary(self.load.clone(), timer);
-
-            let map = Map::new(scenario.map_name.path(), timer);
+            let (mut scenario, map) = if self.load.ends_with(".json") {
+                // JSON scenarios may have variables to fill in, and can live anywhere
+                let map_path = match self.map {
+                    Some(ref path) => path.clone(),
+                    None => Scenario::template_map_name(&self.load, &self.params)
+                        .unwrap_or_else(|err| panic!("Couldn't load {}: {}", self.load, err))
+                        .path(),
+                };
+                let map = Map::new(map_path, timer);
+                let scenario =
+                    Scenario::load_template(&self.load, &self.params, &map, &mut rng, timer)
+                        .unwrap_or_else(|err| panic!("Couldn't load {}: {}", self.load, err));
+                (scenario, map)
+            } else {
+                let scenario: Scenario = abstutil::read_binary(self.load.clone(), timer);
+                let map = Map::new(scenario.map_name.path(), timer);
+                (scenario, map)
+            };
 
             for m in &self.modifiers {
                 scenario = m.apply(&map, scenario);
             }
+            let scenario = scenario
+                .fix_missing_data(&map, self.missing_data, &mut rng, timer)
+                .unwrap_or_else(|err| panic!("{}", err));
 
             if opts.run_name == "unnamed" {
                 opts.run_name = scenario.scenario_name.clone();
@@ -0,0 +1,142 @@
+//! Travel demand is easier to author in pieces -- the usual commutes, a stadium event, people
+//! detouring around construction -- and then combine for one particular day.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use map_model::{BuildingID, Map};
+
+use crate::Scenario;
+
+impl Scenario {
+    /// Combines several scenarios on the same map into one with everybody in them. Fails if two
+    /// scenarios have the same name, if more than one sets parking prices, transit fares, trains,
+    /// or a limit on waiting, or if together they seed more cars at a building than it has room for when each
+    /// one alone fits.
+    pub fn merge(scenarios: Vec<Scenario>, map: &Map) -> Result<Scenario, String> {
+        if scenarios.is_empty() {
+            return Err("No scenarios to merge".to_string());
+        }
+        let mut names: Vec<String> = Vec::new();
+        for s in &scenarios {
+            if &s.map_name != map.get_name() {
+                return Err(format!(
+                    "{} is for {}, not {}",
+                    s.scenario_name,
+                    s.map_name.describe(),
+                    map.get_name().describe()
+                ));
+            }
+            if names.contains(&s.scenario_name) {
+                return Err(format!(
+                    "More than one scenario is called {}",
+                    s.scenario_name
+                ));
+            }
+            names.push(s.scenario_name.clone());
+        }
+        check_seeded_parking(&scenarios, map)?;
+
+        let mut merged = Scenario::empty(map, &names.join(" + "));
+        merged.only_seed_buses = Some(BTreeSet::new());
+        // Which scenario set each of these, for reporting conflicts
+        let mut prices_from: Option<String> = None;
+        let mut fares_from: Option<String> = None;
+        let mut trains_from: Option<String> = None;
+        let mut abandon_from: Option<String> = None;
+        for s in scenarios {
+            let name = s.scenario_name;
+            merged.people.extend(s.people);
+            for (b, cnt) in s.idle_parked_cars {
+                *merged.idle_parked_cars.entry(b).or_insert(0) += cnt;
+            }
+            merged.only_seed_buses = match (merged.only_seed_buses.take(), s.only_seed_buses) {
+                (Some(mut routes), Some(more)) => {
+                    routes.extend(more);
+                    Some(routes)
+                }
+                _ => None,
+            };
+
+            if !s.parking_prices.zones.is_empty() || s.parking_prices.lot_cents > 0 {
+                if let Some(other) = prices_from {
+                    return Err(format!("{} and {} both set parking prices", other, name));
+                }
+                prices_from = Some(name.clone());
+                merged.parking_prices = s.parking_prices;
+            }
+            if !s.transit_fares.is_free() {
+                if let Some(other) = fares_from {
+                    return Err(format!("{} and {} both set transit fares", other, name));
+                }
+                fares_from = Some(name.clone());
+                merged.transit_fares = s.transit_fares;
+            }
+            if !s.train_schedule.trains.is_empty() {
+                if let Some(other) = trains_from {
+                    return Err(format!("{} and {} both schedule trains", other, name));
+                }
+                trains_from = Some(name.clone());
+                merged.train_schedule = s.train_schedule;
+            }
+            if !s.bridge_openings.openings.is_empty() {
+                if !merged.bridge_openings.openings.is_empty()
+                    && merged.bridge_openings.duration != s.bridge_openings.duration
+                {
+                    return Err(format!(
+                        "{} opens bridges for {}, but others open them for {}",
+                        name, s.bridge_openings.duration, merged.bridge_openings.duration
+                    ));
+                }
+                merged.bridge_openings.duration = s.bridge_openings.duration;
+                merged
+                    .bridge_openings
+                    .openings
+                    .extend(s.bridge_openings.openings);
+            }
+            if let Some(dt) = s.abandon_trips_after {
+                if let Some(other) = abandon_from {
+                    return Err(format!(
+                        "{} and {} both limit how long people wait",
+                        other, name
+                    ));
+                }
+                abandon_from = Some(name.clone());
+                merged.abandon_trips_after = Some(dt);
+            }
+        }
+        Ok(merged)
+    }
+}
+
+/// Each scenario seeds cars parked near the buildings where people start driving. If several
+/// scenarios seed cars at the same building and there are no longer enough spots there, the cars
+/// would spill onto the street, changing the parking that each scenario was made with.
+fn check_seeded_parking(scenarios: &[Scenario], map: &Map) -> Result<(), String> {
+    let mut per_bldg: BTreeMap<BuildingID, Vec<(&str, usize)>> = BTreeMap::new();
+    for s in scenarios {
+        for (b, cnt) in s.count_parked_cars_per_bldg().consume() {
+            per_bldg.entry(b).or_default().push((&s.scenario_name, cnt));
+        }
+    }
+    for (b, sources) in per_bldg {
+        if sources.len() < 2 {
+            continue;
+        }
+        let capacity = map.get_b(b).num_parking_spots();
+        let total: usize = sources.iter().map(|(_, cnt)| *cnt).sum();
+        if total > capacity && sources.iter().all(|(_, cnt)| *cnt <= capacity) {
+            return Err(format!(
+                "{} has {} parking spots, but {} together park {} cars there",
+                b,
+                capacity,
+                sources
+                    .into_iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>()
+                    .join(" and "),
+                total
+            ));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,494 @@
+use std::fmt;
+
+use rand::seq::SliceRandom;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use abstutil::{prettyprint_usize, retain_btreeset, Timer};
+use map_model::{BuildingID, Map, MapMigration, MigrationReport};
+
+use crate::{PersonSpec, Scenario, ScenarioGenerator, TripEndpoint};
+
+/// What to do when a scenario refers to something that isn't in the map, usually because the map
+/// was imported again after the scenario was made.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MissingDataPolicy {
+    /// Refuse to use the scenario at all
+    Abort,
+    /// Leave out anybody (or any spawn block) with a problem
+    Skip,
+    /// Replace anything missing with somewhere random on the map, and only leave out things that
+    /// can't be fixed that way
+    SubstituteAnywhere,
+}
+
+impl std::default::Default for MissingDataPolicy {
+    fn default() -> MissingDataPolicy {
+        MissingDataPolicy::Abort
+    }
+}
+
+impl MissingDataPolicy {
+    pub fn parse(x: &str) -> Result<MissingDataPolicy, String> {
+        match x {
+            "abort" => Ok(MissingDataPolicy::Abort),
+            "skip" => Ok(MissingDataPolicy::Skip),
+            "substitute" => Ok(MissingDataPolicy::SubstituteAnywhere),
+            _ => Err(format!(
+                "Bad missing data policy {}. Must be abort|skip|substitute",
+                x
+            )),
+        }
+    }
+}
+
+/// One problem found in a scenario or scenario generator.
+#[derive(Clone, Debug)]
+pub struct MissingData {
+    /// The person or spawn block with the problem
+    pub source: String,
+    pub problem: String,
+    /// If true, something else was substituted and the source was kept. Otherwise, it was left
+    /// out.
+    pub substituted: bool,
+}
+
+impl fmt::Display for MissingData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} ({})",
+            self.source,
+            self.problem,
+            if self.substituted {
+                "substituted somewhere random"
+            } else {
+                "left out"
+            }
+        )
+    }
+}
+
+/// One problem per line
+pub(crate) fn describe_all(problems: &[MissingData]) -> String {
+    problems
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Describes why an endpoint doesn't exist in the map.
+fn check_endpoint(endpt: &TripEndpoint, map: &Map) -> Result<(), String> {
+    match endpt {
+        TripEndpoint::Bldg(b) => {
+            if b.0 >= map.all_buildings().len() {
+                return Err(format!("{} doesn't exist", b));
+            }
+        }
+        TripEndpoint::Border(i) => {
+            if i.0 >= map.all_intersections().len() {
+                return Err(format!("{} doesn't exist", i));
+            }
+            if !map.get_i(*i).is_border() {
+                return Err(format!("{} isn't a border", i));
+            }
+        }
+        TripEndpoint::SuddenlyAppear(pos) => {
+            if pos.lane().0 >= map.all_lanes().len() {
+                return Err(format!("{} doesn't exist", pos.lane()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reports any buildings in a spawn block's list that don't exist. Returns false if the whole
+/// block should be left out; otherwise drops the missing ones, leaving an empty list (meaning
+/// anywhere) if none remain.
+fn check_bldgs(
+    bldgs: &mut Vec<BuildingID>,
+    field: &str,
+    source: &str,
+    substitute: bool,
+    map: &Map,
+    report: &mut Vec<MissingData>,
+) -> bool {
+    let num_before = bldgs.len();
+    bldgs.retain(|b| b.0 < map.all_buildings().len());
+    if bldgs.len() == num_before {
+        return true;
+    }
+    report.push(MissingData {
+        source: source.to_string(),
+        problem: format!(
+            "{}: {} buildings don't exist",
+            field,
+            prettyprint_usize(num_before - bldgs.len())
+        ),
+        substituted: substitute,
+    });
+    substitute
+}
+
+impl Scenario {
+    /// Finds everything in this scenario that refers to something not in the map, or that
+    /// otherwise can't be instantiated. Nothing is changed. Bus routes to seed that don't exist
+    /// aren't a problem; they're just not seeded.
+    pub fn find_missing_data(&self, map: &Map) -> Vec<MissingData> {
+        let mut problems = Vec::new();
+        for (idx, person) in self.people.iter().enumerate() {
+            if let Err(problem) = person.check_references(map) {
+                problems.push(MissingData {
+                    source: describe_person(idx, person),
+                    problem,
+                    substituted: false,
+                });
+            }
+        }
+        problems
+    }
+
+    /// Deals with everything that `find_missing_data` would report, according to the policy.
+    /// Returns the fixed scenario and a report of what changed, or all of the problems if the
+    /// policy is to abort.
+    pub fn handle_missing_data(
+        mut self,
+        map: &Map,
+        policy: MissingDataPolicy,
+        rng: &mut XorShiftRng,
+    ) -> Result<(Scenario, Vec<MissingData>), Vec<MissingData>> {
+        if policy == MissingDataPolicy::Abort {
+            let problems = self.find_missing_data(map);
+            return if problems.is_empty() {
+                Ok((self, problems))
+            } else {
+                Err(problems)
+            };
+        }
+
+        let mut report = Vec::new();
+        let mut people = Vec::new();
+        for (idx, mut person) in self.people.into_iter().enumerate() {
+            let problem = match person.check_references(map) {
+                Ok(()) => {
+                    people.push(person);
+                    continue;
+                }
+                Err(problem) => problem,
+            };
+            let substituted = policy == MissingDataPolicy::SubstituteAnywhere
+                && person.substitute_missing(map, rng)
+                && person.check_references(map).is_ok();
+            report.push(MissingData {
+                source: describe_person(idx, &person),
+                problem,
+                substituted,
+            });
+            if substituted {
+                people.push(person);
+            }
+        }
+        self.people = people;
+
+        // These would be silently skipped anyway, but mention them
+        let missing_routes = self.find_missing_bus_routes(map);
+        if let Some(ref mut routes) = self.only_seed_buses {
+            retain_btreeset(routes, |name| map.get_bus_route(name).is_some());
+        }
+        report.extend(missing_routes);
+
+        Ok((self, report))
+    }
+
+    /// Like `handle_missing_data`, but notes everything that changed in the timer, and describes
+    /// all of the problems if the policy is to abort and anything's wrong.
+    pub fn fix_missing_data(
+        self,
+        map: &Map,
+        policy: MissingDataPolicy,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) -> Result<Scenario, String> {
+        let name = self.scenario_name.clone();
+        match self.handle_missing_data(map, policy, rng) {
+            Ok((scenario, report)) => {
+                for problem in report {
+                    timer.warn(problem.to_string());
+                }
+                Ok(scenario)
+            }
+            Err(problems) => Err(format!(
+                "{} refers to things missing from the map. Use --missing_data=skip or \
+                 --missing_data=substitute to work around this.\n{}",
+                name,
+                describe_all(&problems)
+            )),
+        }
+    }
+
+    /// This scenario was made for `migration`'s old map. Moves everybody to the same buildings and
+    /// borders in the new map. People whose endpoints can't be found are left out.
+    pub fn migrate(mut self, migration: &MapMigration) -> (Scenario, MigrationReport) {
+        let mut report = MigrationReport::default();
+        let mut num_moved = 0;
+        let mut people = Vec::new();
+        for (idx, mut person) in self.people.into_iter().enumerate() {
+            let before = (person.origin.clone(), person.trips.clone());
+            match person.migrate(migration) {
+                Ok(()) => {
+                    if before.0 == person.origin
+                        && before
+                            .1
+                            .iter()
+                            .zip(person.trips.iter())
+                            .all(|(a, b)| a.destination == b.destination)
+                    {
+                        report.unchanged += 1;
+                    } else {
+                        num_moved += 1;
+                    }
+                    people.push(person);
+                }
+                Err(err) => {
+                    report.needs_attention.push(format!(
+                        "{}: {}",
+                        describe_person(idx, &person),
+                        err
+                    ));
+                }
+            }
+        }
+        if num_moved > 0 {
+            report.migrated.push(format!(
+                "{} people had buildings or borders with new IDs",
+                prettyprint_usize(num_moved)
+            ));
+        }
+        self.people = people;
+        (self, report)
+    }
+
+    fn find_missing_bus_routes(&self, map: &Map) -> Vec<MissingData> {
+        let mut problems = Vec::new();
+        if let Some(ref routes) = self.only_seed_buses {
+            for name in routes {
+                if map.get_bus_route(name).is_none() {
+                    problems.push(MissingData {
+                        source: "buses to seed".to_string(),
+                        problem: format!("bus route {} doesn't exist", name),
+                        substituted: false,
+                    });
+                }
+            }
+        }
+        problems
+    }
+}
+
+pub(crate) fn describe_person(idx: usize, person: &PersonSpec) -> String {
+    if let Some(id) = person.orig_id {
+        format!("person #{} ({:?})", idx, id)
+    } else {
+        format!("person #{}", idx)
+    }
+}
+
+impl PersonSpec {
+    fn migrate(&mut self, migration: &MapMigration) -> Result<(), String> {
+        let mut endpts = vec![&mut self.origin];
+        for trip in &mut self.trips {
+            endpts.push(&mut trip.destination);
+        }
+        for endpt in endpts {
+            *endpt = match endpt {
+                TripEndpoint::Bldg(b) => TripEndpoint::Bldg(migration.building(*b)?),
+                TripEndpoint::Border(i) => TripEndpoint::Border(migration.border(*i)?),
+                TripEndpoint::SuddenlyAppear(_) => {
+                    return Err(
+                        "starts somewhere along a lane, which can't be migrated".to_string()
+                    );
+                }
+            };
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_references(&self, map: &Map) -> Result<(), String> {
+        check_endpoint(&self.origin, map)?;
+        for trip in &self.trips {
+            check_endpoint(&trip.destination, map)?;
+        }
+        self.check_schedule()
+    }
+
+    /// Replaces every missing endpoint with a random building. Returns false if there are no
+    /// buildings to use.
+    fn substitute_missing(&mut self, map: &Map, rng: &mut XorShiftRng) -> bool {
+        let mut endpts = vec![&mut self.origin];
+        for trip in &mut self.trips {
+            endpts.push(&mut trip.destination);
+        }
+        for endpt in endpts {
+            if check_endpoint(endpt, map).is_err() {
+                match map.all_buildings().choose(rng) {
+                    Some(b) => {
+                        *endpt = TripEndpoint::Bldg(b.id);
+                    }
+                    None => {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+impl ScenarioGenerator {
+    /// Finds every spawn block that refers to something not in the map. Nothing is changed.
+    pub fn find_missing_data(&self, map: &Map) -> Vec<MissingData> {
+        self.clone()
+            .handle_missing_data(map, MissingDataPolicy::Abort)
+            .err()
+            .unwrap_or_default()
+    }
+
+    /// Deals with spawn blocks that refer to something not in the map, according to the policy.
+    /// Substituting replaces a missing goal with anywhere on the map, but a block spawning from a
+    /// missing border, visiting a missing area, or held at a missing venue is always left out.
+    /// Returns the fixed generator and a report of what changed, or all of the problems if the
+    /// policy is to abort. Blocks in phases are moved to the top-level lists first (see
+    /// `without_phases`), and are reported by their position there. `generate` skips problems
+    /// already, so call this first to pick a different policy.
+    pub fn handle_missing_data(
+        mut self,
+        map: &Map,
+        policy: MissingDataPolicy,
+    ) -> Result<(ScenarioGenerator, Vec<MissingData>), Vec<MissingData>> {
+        self = self.without_phases();
+        let substitute = policy == MissingDataPolicy::SubstituteAnywhere;
+        let mut report = Vec::new();
+
+        let mut spawn_over_time = Vec::new();
+        for (idx, mut s) in self.spawn_over_time.into_iter().enumerate() {
+            if let Some(Err(problem)) = s.goal.as_ref().map(|goal| check_endpoint(goal, map)) {
+                report.push(MissingData {
+                    source: format!("spawn_over_time #{}", idx),
+                    problem: format!("goal: {}", problem),
+                    substituted: substitute,
+                });
+                if !substitute {
+                    continue;
+                }
+                s.goal = None;
+            }
+            let source = format!("spawn_over_time #{}", idx);
+            if !check_bldgs(
+                &mut s.origins,
+                "origins",
+                &source,
+                substitute,
+                map,
+                &mut report,
+            ) || !check_bldgs(
+                &mut s.destinations,
+                "destinations",
+                &source,
+                substitute,
+                map,
+                &mut report,
+            ) {
+                continue;
+            }
+            if !s.activities.iter_mut().enumerate().all(|(i, a)| {
+                check_bldgs(
+                    &mut a.places,
+                    &format!("activity #{} places", i),
+                    &source,
+                    substitute,
+                    map,
+                    &mut report,
+                )
+            }) {
+                continue;
+            }
+            spawn_over_time.push(s);
+        }
+        self.spawn_over_time = spawn_over_time;
+
+        let mut border_spawn_over_time = Vec::new();
+        for (idx, mut s) in self.border_spawn_over_time.into_iter().enumerate() {
+            let source = format!("border_spawn_over_time #{}", idx);
+            if let Err(problem) = check_endpoint(&TripEndpoint::Border(s.start_from_border), map) {
+                report.push(MissingData {
+                    source,
+                    problem: format!("start: {}", problem),
+                    substituted: false,
+                });
+                continue;
+            }
+            if let Some(Err(problem)) = s.goal.as_ref().map(|goal| check_endpoint(goal, map)) {
+                report.push(MissingData {
+                    source: source.clone(),
+                    problem: format!("goal: {}", problem),
+                    substituted: substitute,
+                });
+                if !substitute {
+                    continue;
+                }
+                s.goal = None;
+            }
+            if !check_bldgs(
+                &mut s.destinations,
+                "destinations",
+                &source,
+                substitute,
+                map,
+                &mut report,
+            ) {
+                continue;
+            }
+            border_spawn_over_time.push(s);
+        }
+        self.border_spawn_over_time = border_spawn_over_time;
+
+        // There's nowhere sensible to substitute for a missing area
+        let mut area_visits = Vec::new();
+        for (idx, s) in self.area_visits.into_iter().enumerate() {
+            if map.maybe_get_a(s.area).is_none() {
+                report.push(MissingData {
+                    source: format!("area_visits #{}", idx),
+                    problem: format!("{} doesn't exist", s.area),
+                    substituted: false,
+                });
+                continue;
+            }
+            area_visits.push(s);
+        }
+        self.area_visits = area_visits;
+
+        // Likewise, the event has to happen somewhere in particular
+        let mut venue_events = Vec::new();
+        for (idx, s) in self.venue_events.into_iter().enumerate() {
+            if let Err(problem) = check_endpoint(&TripEndpoint::Bldg(s.venue), map) {
+                report.push(MissingData {
+                    source: format!("venue_events #{}", idx),
+                    problem: format!("venue: {}", problem),
+                    substituted: false,
+                });
+                continue;
+            }
+            venue_events.push(s);
+        }
+        self.venue_events = venue_events;
+
+        if policy == MissingDataPolicy::Abort && !report.is_empty() {
+            for problem in &mut report {
+                problem.substituted = false;
+            }
+            return Err(report);
+        }
+        Ok((self, report))
+    }
+}
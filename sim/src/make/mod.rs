@@ -4,23 +4,69 @@
 use rand::{RngCore, SeedableRng};
 use rand_xorshift::XorShiftRng;
 
+pub use self::anonymize::AnonymizeOptions;
+pub use self::bike_share::{BikeShareConfig, BikeShareUsage};
+pub use self::bridge_openings::BridgeOpenings;
+pub use self::capacity::CapacityUsage;
+pub use self::diff::ScenarioDiff;
+pub use self::elastic::{ElasticDemand, ODPair};
 pub use self::external::{ExternalPerson, ExternalTrip, ExternalTripEndpoint};
-pub use self::generator::{BorderSpawnOverTime, ScenarioGenerator, SpawnOverTime};
+pub use self::generator::{
+    Activity, AreaVisits, BorderSpawnOverTime, DepartureDistribution, DestinationWeights,
+    EventOrigin, ScenarioGenerator, SeedParkedCars, SpawnOverTime, ThroughTraffic, VenueEvent,
+};
 pub use self::load::SimFlags;
+pub use self::missing_data::{MissingData, MissingDataPolicy};
 pub use self::modifier::ScenarioModifier;
+pub use self::od_matrix::ODMatrix;
+pub use self::parking_prices::{ParkingPrices, PricingZone};
+pub use self::persona::Persona;
+pub use self::phases::{PhaseVolume, ScenarioPhase};
+pub use self::population::{Population, Resident};
+pub use self::presets::SpawnParams;
 pub use self::scenario::{IndividTrip, PersonSpec, Scenario, TripPurpose};
 pub(crate) use self::spawner::TripSpec;
+pub use self::summary::{NeighborhoodSummary, ScenarioSummary};
+pub use self::train_schedule::TrainSchedule;
+pub use self::transit_fares::{FareZone, TransitFares};
+pub use self::validate::{ProblemKind, ScenarioProblem};
 
 mod activity_model;
+mod anonymize;
+mod bike_share;
+mod bridge_openings;
+mod capacity;
+mod diff;
+mod elastic;
 mod external;
 mod generator;
 mod load;
+mod merge;
+mod missing_data;
 mod modifier;
+mod od_matrix;
+mod parking_prices;
+mod persona;
+mod phases;
+mod population;
+mod presets;
+mod scale;
 mod scenario;
 mod spawner;
+mod summary;
+mod template;
+mod train_schedule;
+mod transit_fares;
+mod validate;
 
 /// Need to explain this trick -- basically keeps consistency between two different simulations when
 /// each one might make slightly different sequences of calls to the RNG.
 pub fn fork_rng(base_rng: &mut XorShiftRng) -> XorShiftRng {
     XorShiftRng::seed_from_u64(base_rng.next_u64())
 }
+
+/// Like `fork_rng`, but for one of many items, like a person in a scenario. The result only
+/// depends on the base seed and the item's index, not on how much randomness any other item used.
+pub fn indexed_rng(base_seed: u64, idx: usize) -> XorShiftRng {
+    XorShiftRng::seed_from_u64(base_seed ^ (idx as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
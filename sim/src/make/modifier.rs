@@ -1,12 +1,14 @@
 use std::collections::BTreeSet;
 
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
 
 use abstutil::Timer;
-use geom::{Duration, Time};
+use geom::{Distance, Duration, Pt2D, Time};
 use map_model::Map;
 
-use crate::{Scenario, TripMode};
+use crate::{BikeShareConfig, PersonSpec, PricingZone, Scenario, TripEndpoint, TripMode};
 
 /// Transforms an existing Scenario before instantiating it.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
@@ -21,6 +23,31 @@ pub enum ScenarioModifier {
     },
     /// Scenario name
     AddExtraTrips(String),
+    BikeShare(BikeShareConfig),
+    /// Charge the same price, in cents, to park on any street, overriding any existing zones, and
+    /// a price to park in any lot.
+    PriceParking {
+        onstreet_cents: usize,
+        lot_cents: usize,
+    },
+    /// Don't send more people to work or school in a building than it has room for
+    RespectCapacities,
+    /// Change the number of people who own a car by some percent, from -100 to 100. People who
+    /// lose their car make every trip some other way, depending on how far it is. People who gain
+    /// a car drive for every trip. Since parked cars are seeded for everybody who drives from a
+    /// building, this changes those too.
+    ChangeCarOwnership(isize),
+    /// Run this many trains per hour through every rail crossing, all day long
+    TrainsPerHour(usize),
+    /// Open every movable bridge this many times a day, at random, for some number of minutes
+    OpenBridges {
+        per_day: usize,
+        minutes: usize,
+    },
+    /// People give up on trips after being stuck waiting for this long
+    AbandonTripsAfter(Duration),
+    /// Keep this percent of people, for a quicker run. Over 100 repeats some people.
+    SamplePeople(usize),
 }
 
 impl ScenarioModifier {
@@ -83,6 +110,50 @@ impl ScenarioModifier {
                 }
                 s
             }
+            ScenarioModifier::BikeShare(config) => config.apply(map, s).0,
+            ScenarioModifier::PriceParking {
+                onstreet_cents,
+                lot_cents,
+            } => {
+                s.parking_prices.zones.insert(
+                    0,
+                    PricingZone {
+                        name: "everywhere".to_string(),
+                        polygon: map.get_boundary_polygon().clone(),
+                        onstreet_cents: *onstreet_cents,
+                    },
+                );
+                s.parking_prices.lot_cents = *lot_cents;
+                s
+            }
+            ScenarioModifier::RespectCapacities => {
+                let (s, usage) = s.respect_capacities(map);
+                info!("Respecting building capacities: {}", usage.describe());
+                s
+            }
+            ScenarioModifier::ChangeCarOwnership(pct) => change_car_ownership(s, *pct, map),
+            ScenarioModifier::TrainsPerHour(n) => {
+                s.train_schedule.set_trains_per_hour(*n);
+                s
+            }
+            ScenarioModifier::OpenBridges { per_day, minutes } => {
+                // The same bridge openings every time the modifier is applied
+                let mut rng = XorShiftRng::seed_from_u64(42);
+                s.bridge_openings
+                    .randomize(*per_day, Duration::minutes(*minutes), map, &mut rng);
+                s
+            }
+            ScenarioModifier::AbandonTripsAfter(threshold) => {
+                s.abandon_trips_after = Some(*threshold);
+                s
+            }
+            ScenarioModifier::SamplePeople(pct) => {
+                s.scenario_name = format!("{} ({}% of people)", s.scenario_name, pct);
+                // The same people every time the modifier is applied
+                let mut rng = XorShiftRng::seed_from_u64(42);
+                s.sample(&mut rng, (*pct as f64) / 100.0);
+                s
+            }
         }
     }
 
@@ -104,6 +175,36 @@ impl ScenarioModifier {
                 to_mode.map(|m| m.verb())
             ),
             ScenarioModifier::AddExtraTrips(name) => format!("Add extra trips from {}", name),
+            ScenarioModifier::BikeShare(config) => config.describe(),
+            ScenarioModifier::PriceParking {
+                onstreet_cents,
+                lot_cents,
+            } => format!(
+                "charge ${:.2} to park on the street and ${:.2} in parking lots",
+                (*onstreet_cents as f64) / 100.0,
+                (*lot_cents as f64) / 100.0
+            ),
+            ScenarioModifier::RespectCapacities => {
+                "limit people working or going to school in a building to its capacity".to_string()
+            }
+            ScenarioModifier::ChangeCarOwnership(pct) => {
+                if *pct < 0 {
+                    format!("{}% fewer people own a car", -pct)
+                } else {
+                    format!("{}% more people own a car", pct)
+                }
+            }
+            ScenarioModifier::TrainsPerHour(n) => {
+                format!("run {} trains per hour through rail crossings", n)
+            }
+            ScenarioModifier::OpenBridges { per_day, minutes } => format!(
+                "open movable bridges {} times a day for {} minutes each",
+                per_day, minutes
+            ),
+            ScenarioModifier::AbandonTripsAfter(threshold) => {
+                format!("give up on trips after waiting {}", threshold)
+            }
+            ScenarioModifier::SamplePeople(pct) => format!("only simulate {}% of people", pct),
         }
     }
 }
@@ -134,3 +235,63 @@ fn repeat_days(mut s: Scenario, days: usize) -> Scenario {
     }
     s
 }
+
+fn change_car_ownership(mut s: Scenario, pct: isize, map: &Map) -> Scenario {
+    let owns_car = |person: &PersonSpec| person.trips.iter().any(|t| t.mode == TripMode::Drive);
+    let num_owners = s.people.iter().filter(|p| owns_car(p)).count();
+    let num_changes = num_owners * (pct.abs().min(100) as usize) / 100;
+
+    // Like ChangeMode, pick people in a way that's "stable" as the percentage changes, but also
+    // spread out through the scenario.
+    let mut candidates: Vec<usize> = (0..s.people.len())
+        .filter(|idx| {
+            let person = &s.people[*idx];
+            if pct < 0 {
+                owns_car(person)
+            } else {
+                // Somebody who appears somewhere in the middle of the map is already in some
+                // vehicle
+                !owns_car(person)
+                    && !matches!(person.origin, TripEndpoint::SuddenlyAppear(_))
+                    && !person.trips.is_empty()
+            }
+        })
+        .collect();
+    candidates.sort_by_key(|idx| (*idx % 100, *idx));
+
+    for idx in candidates.into_iter().take(num_changes) {
+        let person = &mut s.people[idx];
+        let mut from = endpoint_pt(&person.origin, map);
+        for trip in &mut person.trips {
+            let to = endpoint_pt(&trip.destination, map);
+            let mode = if pct > 0 {
+                TripMode::Drive
+            } else if trip.mode != TripMode::Drive {
+                trip.mode
+            } else {
+                let dist = from.dist_to(to);
+                if dist < Distance::miles(1.0) {
+                    TripMode::Walk
+                } else if dist < Distance::miles(3.0) {
+                    TripMode::Bike
+                } else {
+                    TripMode::Transit
+                }
+            };
+            if mode != trip.mode {
+                trip.mode = mode;
+                trip.modified = true;
+            }
+            from = to;
+        }
+    }
+    s
+}
+
+fn endpoint_pt(endpt: &TripEndpoint, map: &Map) -> Pt2D {
+    match endpt {
+        TripEndpoint::Bldg(b) => map.get_b(*b).polygon.center(),
+        TripEndpoint::Border(i) => map.get_i(*i).polygon.center(),
+        TripEndpoint::SuddenlyAppear(pos) => pos.pt(map),
+    }
+}
@@ -0,0 +1,158 @@
+//! Transportation planners often describe travel demand as an origin-destination (OD) matrix:
+//! how many trips go from each zone to every other zone. This turns one of those into spawn
+//! blocks, so a scenario can be built from a planning model's output without writing any code.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+use geom::Time;
+use map_model::{BuildingID, IntersectionID, Map};
+
+use crate::{
+    BorderSpawnOverTime, DepartureDistribution, ScenarioGenerator, SpawnOverTime, TripEndpoint,
+};
+
+/// Where to find an OD matrix, and how to turn its trip counts into people. The `import_od_matrix`
+/// tool reads this from a JSON file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ODMatrix {
+    pub scenario_name: String,
+    /// A CSV file with `origin`, `destination`, and `trips` columns, naming zones. Fractional
+    /// trip counts are rounded, and repeated pairs are added together.
+    pub od_path: String,
+    /// A CSV file with `zone` and `neighborhood` columns. The neighborhood is either one of the
+    /// `neighborhoods` below, or a border intersection like `i42`, for zones outside the map.
+    pub zones_path: String,
+    /// The buildings in each neighborhood, like what the partitioning tool saves
+    #[serde(default)]
+    pub neighborhoods: BTreeMap<String, Vec<BuildingID>>,
+    pub start_time: Time,
+    pub stop_time: Time,
+    pub departures: DepartureDistribution,
+    pub percent_driving: f64,
+    pub percent_biking: f64,
+    pub percent_use_transit: f64,
+}
+
+#[derive(Deserialize)]
+struct ODRow {
+    origin: String,
+    destination: String,
+    trips: f64,
+}
+
+#[derive(Deserialize)]
+struct ZoneRow {
+    zone: String,
+    neighborhood: String,
+}
+
+enum Location {
+    Bldgs(Vec<BuildingID>),
+    Border(IntersectionID),
+}
+
+impl ScenarioGenerator {
+    /// Expands an OD matrix into one spawn block per pair of zones. Fails if any zone isn't
+    /// mapped to a neighborhood or border that exists.
+    pub fn from_od_matrix(od: &ODMatrix, map: &Map) -> Result<ScenarioGenerator, Box<dyn Error>> {
+        let mut zones: BTreeMap<String, String> = BTreeMap::new();
+        for rec in csv::Reader::from_path(&od.zones_path)?.deserialize() {
+            let rec: ZoneRow = rec?;
+            zones.insert(rec.zone, rec.neighborhood);
+        }
+
+        let mut trips: BTreeMap<(String, String), f64> = BTreeMap::new();
+        for rec in csv::Reader::from_path(&od.od_path)?.deserialize() {
+            let rec: ODRow = rec?;
+            if rec.trips < 0.0 {
+                return Err(format!(
+                    "{} trips from {} to {}",
+                    rec.trips, rec.origin, rec.destination
+                )
+                .into());
+            }
+            *trips.entry((rec.origin, rec.destination)).or_insert(0.0) += rec.trips;
+        }
+
+        let mut s = ScenarioGenerator::empty(&od.scenario_name);
+        s.only_seed_buses = None;
+        for ((origin, destination), num) in trips {
+            let num = num.round() as usize;
+            if num == 0 {
+                continue;
+            }
+            let from = od.locate(&zones, &origin, map)?;
+            let to = od.locate(&zones, &destination, map)?;
+            let (goal, destinations) = match to {
+                Location::Bldgs(bldgs) => (None, bldgs),
+                Location::Border(i) => (Some(TripEndpoint::Border(i)), Vec::new()),
+            };
+            match from {
+                Location::Bldgs(origins) => {
+                    s.spawn_over_time.push(SpawnOverTime {
+                        num_agents: num,
+                        start_time: od.start_time,
+                        stop_time: od.stop_time,
+                        departures: od.departures,
+                        origins,
+                        goal,
+                        destinations,
+                        activities: Vec::new(),
+                        percent_driving: od.percent_driving,
+                        percent_biking: od.percent_biking,
+                        percent_use_transit: od.percent_use_transit,
+                    });
+                }
+                Location::Border(i) => {
+                    // People entering the map have to already be driving, biking, or walking
+                    let num_cars = ((num as f64) * od.percent_driving).round() as usize;
+                    let num_bikes =
+                        (((num as f64) * od.percent_biking).round() as usize).min(num - num_cars);
+                    s.border_spawn_over_time.push(BorderSpawnOverTime {
+                        num_peds: num - num_cars - num_bikes,
+                        num_cars,
+                        num_bikes,
+                        percent_use_transit: od.percent_use_transit,
+                        start_time: od.start_time,
+                        stop_time: od.stop_time,
+                        departures: od.departures,
+                        start_from_border: i,
+                        goal,
+                        destinations,
+                    });
+                }
+            }
+        }
+        Ok(s)
+    }
+}
+
+impl ODMatrix {
+    fn locate(
+        &self,
+        zones: &BTreeMap<String, String>,
+        zone: &str,
+        map: &Map,
+    ) -> Result<Location, String> {
+        let name = zones
+            .get(zone)
+            .ok_or_else(|| format!("Zone {} isn't in {}", zone, self.zones_path))?;
+        if let Some(bldgs) = self.neighborhoods.get(name) {
+            if bldgs.is_empty() {
+                return Err(format!("Neighborhood {} has no buildings", name));
+            }
+            return Ok(Location::Bldgs(bldgs.clone()));
+        }
+        if let Some(idx) = name.strip_prefix('i').and_then(|x| x.parse::<usize>().ok()) {
+            return match map.maybe_get_i(IntersectionID(idx)) {
+                Some(i) if i.is_border() => Ok(Location::Border(i.id)),
+                Some(_) => Err(format!("Zone {} uses {}, which isn't a border", zone, name)),
+                None => Err(format!("Zone {} uses {}, which doesn't exist", zone, name)),
+            };
+        }
+        Err(format!("Zone {} is in unknown neighborhood {}", zone, name))
+    }
+}
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+use geom::{Polygon, Pt2D};
+
+/// What drivers pay to park. Drivers choose between parking on the street and in a parking lot by
+/// trading off the price against how far they'd have to walk and how long they'd keep searching.
+/// Prices are for one stay, since drivers don't know in advance how long they'll stay.
+/// Private off-street spots are always free.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ParkingPrices {
+    /// On-street parking inside each zone costs this much. If zones overlap, the first one wins.
+    /// Anywhere outside all of the zones is free.
+    pub zones: Vec<PricingZone>,
+    /// The price of any parking lot, in cents
+    pub lot_cents: usize,
+    /// How much drivers would pay to save an hour of walking or searching for parking, in cents
+    pub value_of_time_cents_per_hour: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PricingZone {
+    pub name: String,
+    pub polygon: Polygon,
+    pub onstreet_cents: usize,
+}
+
+impl ParkingPrices {
+    /// Nobody pays to park anywhere. Drivers just take the first free spot they find near their
+    /// destination.
+    pub fn free() -> ParkingPrices {
+        ParkingPrices {
+            zones: Vec::new(),
+            lot_cents: 0,
+            value_of_time_cents_per_hour: 2000,
+        }
+    }
+
+    pub fn is_free(&self) -> bool {
+        self.lot_cents == 0 && self.zones.iter().all(|z| z.onstreet_cents == 0)
+    }
+
+    /// The price of parking on the street at some point, in cents
+    pub fn onstreet_cents(&self, pt: Pt2D) -> usize {
+        self.zones
+            .iter()
+            .find(|z| z.polygon.contains_pt(pt))
+            .map(|z| z.onstreet_cents)
+            .unwrap_or(0)
+    }
+}
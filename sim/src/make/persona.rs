@@ -0,0 +1,58 @@
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use geom::{Distance, Speed};
+
+/// Roughly what stage of life somebody is in. This affects how fast they walk and how far they're
+/// willing to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Persona {
+    Child,
+    Adult,
+    Senior,
+}
+
+impl Persona {
+    /// Picks a persona in roughly the proportion they make up of the population.
+    pub fn sample(rng: &mut XorShiftRng) -> Persona {
+        let x = rng.gen_range(0.0, 1.0);
+        if x < 0.2 {
+            Persona::Child
+        } else if x < 0.85 {
+            Persona::Adult
+        } else {
+            Persona::Senior
+        }
+    }
+
+    /// Never faster than Scenario::max_ped_speed.
+    pub fn walking_speed(self, rng: &mut XorShiftRng) -> Speed {
+        let (low, high) = match self {
+            Persona::Child => (1.8, 2.8),
+            Persona::Adult => (2.2, 3.0),
+            Persona::Senior => (1.5, 2.4),
+        };
+        Speed::miles_per_hour(rng.gen_range(low, high))
+    }
+
+    /// The farthest somebody will walk for an entire trip, in a straight line, before giving up and
+    /// trying some other way.
+    pub fn max_walking_distance(self, rng: &mut XorShiftRng) -> Distance {
+        let (low, high) = match self {
+            Persona::Child => (0.5, 1.0),
+            Persona::Adult => (1.0, 2.5),
+            Persona::Senior => (0.3, 1.0),
+        };
+        Distance::miles(rng.gen_range(low, high))
+    }
+
+    /// The range of ages, in years, for this persona
+    pub fn ages(self) -> (usize, usize) {
+        match self {
+            Persona::Child => (5, 18),
+            Persona::Adult => (18, 65),
+            Persona::Senior => (65, 100),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! A day of traffic usually has a few distinct parts -- the morning peak, midday, the evening
+//! peak -- each with its own mix of trips. Spawn blocks inside a phase are timed relative to the
+//! phase, so moving or stretching a phase doesn't mean recomputing every block's times.
+
+use serde::{Deserialize, Serialize};
+
+use geom::Time;
+use map_model::Map;
+
+use crate::{
+    BorderSpawnOverTime, DepartureDistribution, ScenarioGenerator, SpawnOverTime, ThroughTraffic,
+};
+
+/// One named part of the day, with its own spawn blocks.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ScenarioPhase {
+    pub name: String,
+    pub start_time: Time,
+    pub stop_time: Time,
+    /// The start and stop times of these blocks count from the start of the phase, so a block
+    /// from midnight to 1am starts with the phase and lasts an hour. Blocks are cut off when the
+    /// phase stops.
+    #[serde(default)]
+    pub spawn_over_time: Vec<SpawnOverTime>,
+    #[serde(default)]
+    pub border_spawn_over_time: Vec<BorderSpawnOverTime>,
+    #[serde(default)]
+    pub through_traffic: Vec<ThroughTraffic>,
+}
+
+/// How much traffic one phase of a full day has. See `ScenarioGenerator::full_day`.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct PhaseVolume {
+    pub name: String,
+    pub start_time: Time,
+    pub stop_time: Time,
+    /// People leaving a random building for a random destination
+    pub num_agents: usize,
+    /// Cars entering from every incoming border
+    pub num_cars_per_border: usize,
+    pub percent_driving: f64,
+    pub percent_biking: f64,
+    pub percent_use_transit: f64,
+}
+
+impl ScenarioPhase {
+    /// Converts a time relative to the start of this phase into a time of day, never after the
+    /// phase stops.
+    fn absolute(&self, t: Time) -> Time {
+        (self.start_time + (t - Time::START_OF_DAY)).min(self.stop_time)
+    }
+}
+
+impl ScenarioGenerator {
+    /// Moves the spawn blocks of every phase into the top-level lists, with their times
+    /// converted to times of day. The blocks from phases come after any that were already there,
+    /// in the order of the phases.
+    pub fn without_phases(&self) -> ScenarioGenerator {
+        let mut result = self.clone();
+        for phase in result.phases.drain(..) {
+            for mut s in phase.spawn_over_time.clone() {
+                s.start_time = phase.absolute(s.start_time);
+                s.stop_time = phase.absolute(s.stop_time);
+                result.spawn_over_time.push(s);
+            }
+            for mut s in phase.border_spawn_over_time.clone() {
+                s.start_time = phase.absolute(s.start_time);
+                s.stop_time = phase.absolute(s.stop_time);
+                result.border_spawn_over_time.push(s);
+            }
+            for mut s in phase.through_traffic.clone() {
+                s.start_time = phase.absolute(s.start_time);
+                s.stop_time = phase.absolute(s.stop_time);
+                result.through_traffic.push(s);
+            }
+        }
+        result
+    }
+
+    /// A whole day made of phases, each with one spawn block for people leaving buildings and one
+    /// for cars entering from every incoming border. Departures are spread evenly over each phase.
+    pub fn full_day(name: &str, volumes: Vec<PhaseVolume>, map: &Map) -> ScenarioGenerator {
+        let mut s = ScenarioGenerator::empty(name);
+        s.only_seed_buses = None;
+        for v in volumes {
+            let duration = Time::START_OF_DAY + (v.stop_time - v.start_time);
+            let mut phase = ScenarioPhase {
+                name: v.name,
+                start_time: v.start_time,
+                stop_time: v.stop_time,
+                spawn_over_time: Vec::new(),
+                border_spawn_over_time: Vec::new(),
+                through_traffic: Vec::new(),
+            };
+            if v.num_agents > 0 {
+                phase.spawn_over_time.push(SpawnOverTime {
+                    num_agents: v.num_agents,
+                    start_time: Time::START_OF_DAY,
+                    stop_time: duration,
+                    departures: DepartureDistribution::Uniform,
+                    origins: Vec::new(),
+                    goal: None,
+                    destinations: Vec::new(),
+                    activities: Vec::new(),
+                    percent_driving: v.percent_driving,
+                    percent_biking: v.percent_biking,
+                    percent_use_transit: v.percent_use_transit,
+                });
+            }
+            if v.num_cars_per_border > 0 {
+                for i in map.all_incoming_borders() {
+                    phase.border_spawn_over_time.push(BorderSpawnOverTime {
+                        num_peds: 0,
+                        num_cars: v.num_cars_per_border,
+                        num_bikes: 0,
+                        percent_use_transit: 0.0,
+                        start_time: Time::START_OF_DAY,
+                        stop_time: duration,
+                        departures: DepartureDistribution::Uniform,
+                        start_from_border: i.id,
+                        goal: None,
+                        destinations: Vec::new(),
+                    });
+                }
+            }
+            s.phases.push(phase);
+        }
+        s
+    }
+}
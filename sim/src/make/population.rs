@@ -0,0 +1,138 @@
+//! Most scenarios pick random buildings for every trip, so changing the scenario a bit, or
+//! comparing runs before and after map edits, can shuffle who's even living on the map. A
+//! Population fixes the residents of a map once, and scenarios built from it refer back to them,
+//! so the same person can be followed across runs.
+
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use abstutil::{prettyprint_usize, MapName, Timer};
+use geom::{Duration, Time};
+use map_model::{BuildingID, BuildingType, Map};
+
+use crate::make::activity_model::select_trip_mode;
+use crate::{IndividTrip, OrigPersonID, PersonSpec, Scenario, TripEndpoint, TripMode, TripPurpose};
+
+/// Everybody living on one map
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Population {
+    pub map_name: MapName,
+    pub residents: Vec<Resident>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Resident {
+    pub home: BuildingID,
+    /// People without a job on the map stay home, or work somewhere off the map
+    pub work: Option<BuildingID>,
+    pub owns_car: bool,
+}
+
+impl Population {
+    /// Fills every home on the map to capacity, then hands out the jobs on the map to residents
+    /// until they run out.
+    pub fn generate(map: &Map, rng: &mut XorShiftRng) -> Population {
+        let mut homes = Vec::new();
+        let mut jobs = Vec::new();
+        for b in map.all_buildings() {
+            match b.bldg_type {
+                BuildingType::Residential(residents) => {
+                    for _ in 0..residents {
+                        homes.push(b.id);
+                    }
+                }
+                BuildingType::ResidentialCommercial(residents, workers) => {
+                    for _ in 0..residents {
+                        homes.push(b.id);
+                    }
+                    for _ in 0..workers {
+                        jobs.push(b.id);
+                    }
+                }
+                BuildingType::Commercial(workers) => {
+                    for _ in 0..workers {
+                        jobs.push(b.id);
+                    }
+                }
+                BuildingType::Empty => {}
+            }
+        }
+        jobs.shuffle(rng);
+
+        let residents = homes
+            .into_iter()
+            .map(|home| Resident {
+                home,
+                work: jobs.pop().filter(|work| *work != home),
+                // TODO Vary this by neighborhood, once there's data for it
+                owns_car: rng.gen_bool(0.7),
+            })
+            .collect();
+        Population {
+            map_name: map.get_name().clone(),
+            residents,
+        }
+    }
+
+    /// Loads the saved population for a map, generating and saving it the first time.
+    pub fn load_or_generate(map: &Map, timer: &mut Timer) -> Population {
+        let path = abstutil::path_population(map.get_name());
+        if let Ok(population) = abstutil::maybe_read_binary::<Population>(path.clone(), timer) {
+            return population;
+        }
+        // Always the same people for a map, even if the population file is deleted
+        let population = Population::generate(map, &mut XorShiftRng::seed_from_u64(42));
+        timer.note(format!(
+            "Generated {} residents for {}",
+            prettyprint_usize(population.residents.len()),
+            map.get_name().describe()
+        ));
+        abstutil::write_binary(path, &population);
+        population
+    }
+
+    /// Everybody with a job on the map goes there in the morning and comes home in the
+    /// evening. Each person's `orig_id` is their home and their index in the population, so
+    /// they can be matched up between runs.
+    pub fn commutes(&self, map: &Map, rng: &mut XorShiftRng) -> Scenario {
+        let mut s = Scenario::empty(map, "residents commuting");
+        s.only_seed_buses = None;
+        for (idx, resident) in self.residents.iter().enumerate() {
+            let work = if let Some(b) = resident.work {
+                b
+            } else {
+                continue;
+            };
+            let dist = map
+                .get_b(resident.home)
+                .label_center
+                .dist_to(map.get_b(work).label_center);
+            let mut mode = select_trip_mode(dist, rng);
+            if mode == TripMode::Drive && !resident.owns_car {
+                mode = TripMode::Transit;
+            }
+            let depart_am = Time::START_OF_DAY
+                + Duration::hours(7)
+                + Duration::seconds(rng.gen_range(0.0, 3.0 * 3600.0));
+            let depart_pm = Time::START_OF_DAY
+                + Duration::hours(17)
+                + Duration::seconds(rng.gen_range(0.0, 2.0 * 3600.0));
+            s.people.push(PersonSpec {
+                orig_id: Some(OrigPersonID(resident.home.0, idx)),
+                origin: TripEndpoint::Bldg(resident.home),
+                trips: vec![
+                    IndividTrip::new(depart_am, TripPurpose::Work, TripEndpoint::Bldg(work), mode),
+                    IndividTrip::new(
+                        depart_pm,
+                        TripPurpose::Home,
+                        TripEndpoint::Bldg(resident.home),
+                        mode,
+                    ),
+                ],
+            });
+        }
+        s
+    }
+}
@@ -0,0 +1,136 @@
+//! Simple random scenarios described by a handful of numbers instead of spawn blocks, so tests,
+//! tools, and the UI can share them by name.
+
+use serde::{Deserialize, Serialize};
+
+use geom::{Duration, Time};
+use map_model::Map;
+
+use crate::{
+    BorderSpawnOverTime, DepartureDistribution, ScenarioGenerator, SeedParkedCars, SpawnOverTime,
+    TripEndpoint,
+};
+
+/// Unrealistic trips starting all at once, useful for testing and for seeing a map busy with
+/// traffic right away.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct SpawnParams {
+    /// People leaving a random building for a random destination
+    pub num_agents: usize,
+    /// Pedestrians, cars, and bikes entering from each incoming border, each. The same number of
+    /// people leave the map through each outgoing border.
+    pub num_per_border: usize,
+    /// Everybody departs between midnight and this long after
+    pub duration: Duration,
+    pub percent_driving: f64,
+    pub percent_biking: f64,
+    pub percent_use_transit: f64,
+    /// Cars parked at home all day, picked with these weights per residential building. See
+    /// `SeedParkedCars::PerBuilding`.
+    #[serde(default)]
+    pub parked_cars_per_bldg: Option<Vec<usize>>,
+}
+
+impl SpawnParams {
+    /// A few hundred trips in the first few seconds
+    pub fn small() -> SpawnParams {
+        SpawnParams {
+            num_agents: 100,
+            num_per_border: 10,
+            duration: Duration::seconds(5.0),
+            percent_driving: 0.5,
+            percent_biking: 0.5,
+            percent_use_transit: 0.5,
+            parked_cars_per_bldg: None,
+        }
+    }
+
+    /// Thousands of trips over the first hour, competing with cars parked all day
+    pub fn big() -> SpawnParams {
+        SpawnParams {
+            num_agents: 1000,
+            num_per_border: 50,
+            duration: Duration::hours(1),
+            percent_driving: 0.5,
+            percent_biking: 0.2,
+            percent_use_transit: 0.3,
+            parked_cars_per_bldg: Some(vec![2, 3, 1]),
+        }
+    }
+
+    /// The names of every preset, in the order `preset` knows them.
+    pub fn preset_names() -> Vec<&'static str> {
+        vec!["small_run", "big_run"]
+    }
+
+    pub fn preset(name: &str) -> Option<SpawnParams> {
+        match name {
+            "small_run" => Some(SpawnParams::small()),
+            "big_run" => Some(SpawnParams::big()),
+            _ => None,
+        }
+    }
+}
+
+impl ScenarioGenerator {
+    pub fn from_params(name: &str, params: &SpawnParams, map: &Map) -> ScenarioGenerator {
+        let mut s = ScenarioGenerator::empty(name);
+        s.only_seed_buses = None;
+        let start_time = Time::START_OF_DAY;
+        let stop_time = Time::START_OF_DAY + params.duration;
+        let spawn = |goal: Option<TripEndpoint>, num_agents: usize| SpawnOverTime {
+            num_agents,
+            start_time,
+            stop_time,
+            departures: DepartureDistribution::Uniform,
+            origins: Vec::new(),
+            goal,
+            destinations: Vec::new(),
+            activities: Vec::new(),
+            percent_driving: params.percent_driving,
+            percent_biking: params.percent_biking,
+            percent_use_transit: params.percent_use_transit,
+        };
+
+        s.spawn_over_time.push(spawn(None, params.num_agents));
+        // If there are no sidewalks/driving lanes at a border, scenario instantiation will just
+        // warn and skip them.
+        for i in map.all_incoming_borders() {
+            s.border_spawn_over_time.push(BorderSpawnOverTime {
+                num_peds: params.num_per_border,
+                num_cars: params.num_per_border,
+                num_bikes: params.num_per_border,
+                percent_use_transit: params.percent_use_transit,
+                start_time,
+                stop_time,
+                departures: DepartureDistribution::Uniform,
+                start_from_border: i.id,
+                goal: None,
+                destinations: Vec::new(),
+            });
+        }
+        for i in map.all_outgoing_borders() {
+            s.spawn_over_time.push(spawn(
+                Some(TripEndpoint::Border(i.id)),
+                params.num_per_border,
+            ));
+        }
+        s.seed_parked_cars = params
+            .parked_cars_per_bldg
+            .clone()
+            .map(SeedParkedCars::PerBuilding);
+        s
+    }
+
+    pub fn small_run(map: &Map) -> ScenarioGenerator {
+        ScenarioGenerator::from_params("small_run", &SpawnParams::small(), map)
+    }
+
+    /// One of the `SpawnParams` presets, or `sunny_day`. None if the name isn't a preset.
+    pub fn preset(name: &str, map: &Map) -> Option<ScenarioGenerator> {
+        if name == "sunny_day" {
+            return Some(ScenarioGenerator::sunny_day(map));
+        }
+        SpawnParams::preset(name).map(|params| ScenarioGenerator::from_params(name, &params, map))
+    }
+}
@@ -0,0 +1,77 @@
+//! Huge scenarios are slow to iterate on. These shrink one down to a fraction of the people for
+//! quick experiments, then the full version can be run for results.
+
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+
+use abstutil::retain_btreemap;
+
+use crate::{Scenario, ScenarioGenerator};
+
+impl ScenarioGenerator {
+    /// Multiplies the number of people in every spawn block, area visit, venue event, and stream
+    /// of through traffic by the factor, rounding to the nearest person.
+    pub fn scale(&mut self, factor: f64) {
+        assert!(factor >= 0.0);
+        let scale = |n: usize| ((n as f64) * factor).round() as usize;
+        for s in &mut self.spawn_over_time {
+            s.num_agents = scale(s.num_agents);
+        }
+        for s in &mut self.border_spawn_over_time {
+            s.num_peds = scale(s.num_peds);
+            s.num_cars = scale(s.num_cars);
+            s.num_bikes = scale(s.num_bikes);
+        }
+        for a in &mut self.area_visits {
+            a.num_visitors = scale(a.num_visitors);
+        }
+        for e in &mut self.venue_events {
+            e.num_attendees = scale(e.num_attendees);
+        }
+        for s in &mut self.through_traffic {
+            s.num_cars = scale(s.num_cars);
+            s.num_bikes = scale(s.num_bikes);
+        }
+        for phase in &mut self.phases {
+            for s in &mut phase.spawn_over_time {
+                s.num_agents = scale(s.num_agents);
+            }
+            for s in &mut phase.border_spawn_over_time {
+                s.num_peds = scale(s.num_peds);
+                s.num_cars = scale(s.num_cars);
+                s.num_bikes = scale(s.num_bikes);
+            }
+            for s in &mut phase.through_traffic {
+                s.num_cars = scale(s.num_cars);
+                s.num_bikes = scale(s.num_bikes);
+            }
+        }
+    }
+}
+
+impl Scenario {
+    /// Randomly keeps about this fraction of people, with their whole schedules, and of the cars
+    /// parked all day. A fraction over 1 grows the scenario instead, with some people repeated.
+    pub fn sample(&mut self, rng: &mut XorShiftRng, fraction: f64) {
+        assert!(fraction >= 0.0);
+        let whole = fraction.trunc() as usize;
+        let extra = fraction.fract();
+        let mut people = Vec::new();
+        for person in self.people.drain(..) {
+            let copies = whole + if rng.gen_bool(extra) { 1 } else { 0 };
+            for _ in 0..copies {
+                people.push(person.clone());
+            }
+        }
+        self.people = people;
+
+        for cnt in self.idle_parked_cars.values_mut() {
+            let mut scaled = 0;
+            for _ in 0..*cnt {
+                scaled += whole + if rng.gen_bool(extra) { 1 } else { 0 };
+            }
+            *cnt = scaled;
+        }
+        retain_btreemap(&mut self.idle_parked_cars, |_, cnt| *cnt > 0);
+    }
+}
@@ -1,23 +1,27 @@
-use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::error::Error;
 use std::fmt;
 
 use rand::seq::SliceRandom;
-use rand::{Rng, SeedableRng};
+use rand::{Rng, RngCore, SeedableRng};
 use rand_xorshift::XorShiftRng;
-use serde::{Deserialize, Serialize};
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
 
 use abstutil::{prettyprint_usize, Counter, MapName, Parallelism, Timer};
-use geom::{Distance, Speed, Time};
+use geom::{Distance, Duration, Speed, Time};
 use map_model::{BuildingID, Map, OffstreetParking, RoadID};
 
-use crate::make::fork_rng;
+use crate::make::missing_data::describe_all;
+use crate::make::{fork_rng, indexed_rng};
 use crate::{
-    OrigPersonID, ParkingSpot, Sim, TripEndpoint, TripInfo, TripMode, TripSpec, Vehicle,
-    VehicleSpec, VehicleType, BIKE_LENGTH, MAX_CAR_LENGTH, MIN_CAR_LENGTH,
+    BridgeOpenings, MissingData, ODMatrix, OrigPersonID, ParkingPrices, ParkingSpot, Persona,
+    ScenarioGenerator, Sim, TrainSchedule, TransitFares, TripEndpoint, TripInfo, TripMode,
+    TripSpec, Vehicle, VehicleSpec, VehicleType, BIKE_LENGTH, MAX_CAR_LENGTH, MIN_CAR_LENGTH,
 };
 
 /// A Scenario describes all the input to a simulation. Usually a scenario covers one day.
-#[derive(Clone, Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Debug)]
 pub struct Scenario {
     pub scenario_name: String,
     pub map_name: MapName,
@@ -25,6 +29,141 @@ pub struct Scenario {
     pub people: Vec<PersonSpec>,
     /// None means seed all buses. Otherwise the route name must be present here.
     pub only_seed_buses: Option<BTreeSet<String>>,
+    pub parking_prices: ParkingPrices,
+    pub transit_fares: TransitFares,
+    pub train_schedule: TrainSchedule,
+    pub bridge_openings: BridgeOpenings,
+    /// If somebody's stuck waiting for this long -- for a bus, for parking, or in gridlock -- they
+    /// give up on their trip. None means people wait forever.
+    pub abandon_trips_after: Option<Duration>,
+    /// Cars that stay parked at a building all day, owned by people who don't go anywhere.
+    /// Without these, the only parked cars are the ones somebody drives.
+    pub idle_parked_cars: BTreeMap<BuildingID, usize>,
+}
+
+// Scenarios saved before parking prices were added stop after only_seed_buses, and the settings
+// after it take their defaults. JSON can just leave fields out, but bincode isn't self-describing,
+// so an old binary file runs out of input where parking_prices should start.
+impl<'de> Deserialize<'de> for Scenario {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Scenario, D::Error> {
+        if deserializer.is_human_readable() {
+            let s = SavedScenario::deserialize(deserializer)?;
+            return Ok(Scenario {
+                scenario_name: s.scenario_name,
+                map_name: s.map_name,
+                people: s.people,
+                only_seed_buses: s.only_seed_buses,
+                parking_prices: s.parking_prices,
+                transit_fares: s.transit_fares,
+                train_schedule: s.train_schedule,
+                bridge_openings: s.bridge_openings,
+                abandon_trips_after: s.abandon_trips_after,
+                idle_parked_cars: s.idle_parked_cars,
+            });
+        }
+        deserializer.deserialize_struct("Scenario", SCENARIO_FIELDS, BinaryScenarioVisitor)
+    }
+}
+
+const SCENARIO_FIELDS: &[&str] = &[
+    "scenario_name",
+    "map_name",
+    "people",
+    "only_seed_buses",
+    "parking_prices",
+    "transit_fares",
+    "train_schedule",
+    "bridge_openings",
+    "abandon_trips_after",
+    "idle_parked_cars",
+];
+
+#[derive(Deserialize)]
+struct SavedScenario {
+    scenario_name: String,
+    map_name: MapName,
+    people: Vec<PersonSpec>,
+    only_seed_buses: Option<BTreeSet<String>>,
+    #[serde(default = "ParkingPrices::free")]
+    parking_prices: ParkingPrices,
+    #[serde(default = "TransitFares::free")]
+    transit_fares: TransitFares,
+    #[serde(default = "TrainSchedule::none")]
+    train_schedule: TrainSchedule,
+    #[serde(default = "BridgeOpenings::none")]
+    bridge_openings: BridgeOpenings,
+    #[serde(default)]
+    abandon_trips_after: Option<Duration>,
+    #[serde(default)]
+    idle_parked_cars: BTreeMap<BuildingID, usize>,
+}
+
+struct BinaryScenarioVisitor;
+
+impl<'de> Visitor<'de> for BinaryScenarioVisitor {
+    type Value = Scenario;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("struct Scenario")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Scenario, A::Error> {
+        let scenario_name = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let map_name = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        let people = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+        let only_seed_buses = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(3, &self))?;
+        let mut scenario =
+            Scenario::with_default_settings(scenario_name, map_name, people, only_seed_buses);
+        // An old file ends here. bincode reports that as an error, so running out of input is
+        // fine, but anything else is still a real failure.
+        match seq.next_element() {
+            Ok(Some(x)) => {
+                scenario.parking_prices = x;
+            }
+            Ok(None) => {
+                return Ok(scenario);
+            }
+            Err(err) => {
+                if is_end_of_input(&err) {
+                    return Ok(scenario);
+                }
+                return Err(err);
+            }
+        }
+        scenario.transit_fares = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(5, &self))?;
+        scenario.train_schedule = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(6, &self))?;
+        scenario.bridge_openings = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(7, &self))?;
+        scenario.abandon_trips_after = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(8, &self))?;
+        scenario.idle_parked_cars = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(9, &self))?;
+        Ok(scenario)
+    }
+}
+
+// bincode wraps the I/O error, but only exposes it through the deprecated cause(), which can't be
+// downcast. Every flavor of std::io::Error names its kind in the Debug output.
+#[allow(deprecated)]
+fn is_end_of_input<E: de::Error>(err: &E) -> bool {
+    err.cause().map_or(false, |cause| {
+        format!("{:?}", cause).contains("UnexpectedEof")
+    })
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -106,12 +245,40 @@ impl fmt::Display for TripPurpose {
 }
 
 impl Scenario {
+    /// Panics if the scenario refers to anything missing from the map. Use `handle_missing_data`
+    /// first to avoid this.
     pub fn instantiate(&self, sim: &mut Sim, map: &Map, rng: &mut XorShiftRng, timer: &mut Timer) {
-        self.instantiate_without_retries(sim, map, rng, true, timer);
+        if let Err(problems) = self.instantiate_without_retries(sim, map, rng, true, timer) {
+            panic!(
+                "{} can't be instantiated on this map:\n{}",
+                self.scenario_name,
+                describe_all(&problems)
+            );
+        }
     }
 
     /// If retry_if_no_room is false, any vehicles that fail to spawn because of something else in
-    /// the way will just wind up as cancelled trips.
+    /// the way will just wind up as cancelled trips. If the scenario refers to anything missing
+    /// from the map, nothing happens, and all of the problems are returned. Other problems found
+    /// by `validate` are just warnings.
+    ///
+    /// People get PersonIDs in the order they're listed, and their trips get TripIDs in order,
+    /// starting after anybody already in the simulation. Each person's randomness (their persona,
+    /// vehicles, and where they spawn) only depends on the RNG seed and their position in the
+    /// list. So instantiating the same scenario with the same seed on an edited map produces the
+    /// same people taking the same trips with the same IDs, even if things happen at different
+    /// times, or modifiers changed how some of them travel.
+    ///
+    /// Nothing here depends on the iteration order of a hash container, so the same scenario and
+    /// seed always produce an identical simulation. Everything happens in this order:
+    ///
+    /// 1. Scenario-wide settings, like parking prices and bridge openings
+    /// 2. Buses, in the order of the map's routes
+    /// 3. People, in the order they're listed, each with their own RNG
+    /// 4. Cars parked all day, by building ID, each building with its own RNG
+    /// 5. Shuffling all parked cars with a forked RNG, then seeding each near their building with
+    ///    its own forked RNG
+    /// 6. Spawning trips, in the same order as the people
     pub fn instantiate_without_retries(
         &self,
         sim: &mut Sim,
@@ -119,9 +286,23 @@ impl Scenario {
         rng: &mut XorShiftRng,
         retry_if_no_room: bool,
         timer: &mut Timer,
-    ) {
+    ) -> Result<(), Vec<MissingData>> {
+        let problems = self.find_missing_data(map);
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        // Anything else just means some trips will be cancelled or some settings won't apply
+        for problem in self.validate(map) {
+            timer.warn(problem.to_string());
+        }
+
         // Any case where map edits could change the calls to the RNG, we have to fork.
         sim.set_name(self.scenario_name.clone());
+        sim.set_parking_prices(&self.parking_prices, map);
+        sim.set_transit_fares(&self.transit_fares);
+        sim.schedule_trains(&self.train_schedule);
+        sim.schedule_bridge_openings(&self.bridge_openings);
+        sim.set_trip_abandonment(self.abandon_trips_after);
 
         timer.start(format!("Instantiating {}", self.scenario_name));
 
@@ -141,16 +322,22 @@ impl Scenario {
         timer.start_iter("trips for People", self.people.len());
         let mut parked_cars: Vec<(Vehicle, BuildingID)> = Vec::new();
         let mut schedule_trips = Vec::new();
-        for p in &self.people {
+        let base_seed = rng.next_u64();
+        for (idx, p) in self.people.iter().enumerate() {
             timer.next();
 
-            if let Err(err) = p.check_schedule() {
-                panic!("{}", err);
-            }
-
+            let mut person_rng = indexed_rng(base_seed, idx);
             let (vehicle_specs, cars_initially_parked_at, vehicle_foreach_trip) =
-                p.get_vehicles(rng);
-            let person = sim.new_person(p.orig_id, Scenario::rand_ped_speed(rng), vehicle_specs);
+                p.get_vehicles(&mut person_rng);
+            let persona = Persona::sample(&mut person_rng);
+            let person = sim.new_person(
+                p.orig_id,
+                persona,
+                persona.walking_speed(&mut person_rng),
+                persona.max_walking_distance(&mut person_rng),
+                vehicle_specs,
+            );
+            let max_walking_distance = person.max_walking_distance;
             for (idx, b) in cars_initially_parked_at {
                 parked_cars.push((person.vehicles[idx].clone(), b));
             }
@@ -158,13 +345,14 @@ impl Scenario {
             for (t, maybe_idx) in p.trips.iter().zip(vehicle_foreach_trip) {
                 // The RNG call might change over edits for picking the spawning lane from a border
                 // with multiple choices for a vehicle type.
-                let mut tmp_rng = fork_rng(rng);
+                let mut tmp_rng = fork_rng(&mut person_rng);
                 let spec = match TripSpec::maybe_new(
                     from.clone(),
                     t.destination.clone(),
                     t.mode,
                     maybe_idx.map(|idx| person.vehicles[idx].id),
                     retry_if_no_room,
+                    max_walking_distance,
                     &mut tmp_rng,
                     map,
                 ) {
@@ -203,12 +391,35 @@ impl Scenario {
             |(p, spec, info)| spec.to_plan(p, info, map),
         );
 
-        // parked_cars is stable over map edits, so don't fork.
-        parked_cars.shuffle(rng);
+        if !self.idle_parked_cars.is_empty() {
+            let base_seed = rng.next_u64();
+            for (b, cnt) in &self.idle_parked_cars {
+                // Only depends on the building, so other buildings changing doesn't matter
+                let mut bldg_rng = indexed_rng(base_seed, b.0);
+                for _ in 0..*cnt {
+                    let persona = Persona::sample(&mut bldg_rng);
+                    let person = sim.new_person(
+                        None,
+                        persona,
+                        persona.walking_speed(&mut bldg_rng),
+                        persona.max_walking_distance(&mut bldg_rng),
+                        vec![Scenario::rand_car(&mut bldg_rng)],
+                    );
+                    let (id, car) = (person.id, person.vehicles[0].clone());
+                    sim.stay_inside(id, *b);
+                    parked_cars.push((car, *b));
+                }
+            }
+        }
+
+        // parked_cars only depends on the scenario, but fork anyway, so that changing how the
+        // shuffle uses randomness can't affect anything after it.
+        parked_cars.shuffle(&mut fork_rng(rng));
         seed_parked_cars(parked_cars, sim, map, rng, timer);
 
         sim.spawn_trips(results, map, timer);
         timer.stop(format!("Instantiating {}", self.scenario_name));
+        Ok(())
     }
 
     pub fn save(&self) {
@@ -219,14 +430,45 @@ impl Scenario {
     }
 
     pub fn empty(map: &Map, name: &str) -> Scenario {
+        Scenario::with_default_settings(
+            name.to_string(),
+            map.get_name().clone(),
+            Vec::new(),
+            Some(BTreeSet::new()),
+        )
+    }
+
+    fn with_default_settings(
+        scenario_name: String,
+        map_name: MapName,
+        people: Vec<PersonSpec>,
+        only_seed_buses: Option<BTreeSet<String>>,
+    ) -> Scenario {
         Scenario {
-            scenario_name: name.to_string(),
-            map_name: map.get_name().clone(),
-            people: Vec::new(),
-            only_seed_buses: Some(BTreeSet::new()),
+            scenario_name,
+            map_name,
+            people,
+            only_seed_buses,
+            parking_prices: ParkingPrices::free(),
+            transit_fares: TransitFares::free(),
+            train_schedule: TrainSchedule::none(),
+            bridge_openings: BridgeOpenings::none(),
+            abandon_trips_after: None,
+            idle_parked_cars: BTreeMap::new(),
         }
     }
 
+    /// Reads an origin-destination matrix and generates the people making those trips. See
+    /// `ScenarioGenerator::from_od_matrix` to inspect or save the spawn blocks instead.
+    pub fn from_od_matrix(
+        od: &ODMatrix,
+        map: &Map,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) -> Result<Scenario, Box<dyn Error>> {
+        Ok(ScenarioGenerator::from_od_matrix(od, map)?.generate(map, rng, timer))
+    }
+
     fn rand_car(rng: &mut XorShiftRng) -> VehicleSpec {
         let length = Scenario::rand_dist(rng, MIN_CAR_LENGTH, MAX_CAR_LENGTH);
         VehicleSpec {
@@ -265,9 +507,6 @@ impl Scenario {
         ))
     }
 
-    pub fn rand_ped_speed(rng: &mut XorShiftRng) -> Speed {
-        Scenario::rand_speed(rng, Speed::miles_per_hour(2.0), Speed::miles_per_hour(3.0))
-    }
     pub fn max_ped_speed() -> Speed {
         Speed::miles_per_hour(3.0)
     }
@@ -282,6 +521,9 @@ impl Scenario {
                 per_bldg.inc(b);
             }
         }
+        for (b, cnt) in &self.idle_parked_cars {
+            per_bldg.add(*b, *cnt);
+        }
         per_bldg
     }
 
@@ -359,6 +601,8 @@ fn seed_parked_cars(
     let mut ok = true;
     let total_cars = parked_cars.len();
     let mut seeded = 0;
+    // Cars in a driveway or garage don't compete for the curb, so keep track of the split
+    let (mut offstreet, mut onstreet, mut lots) = (0, 0, 0);
     for (vehicle, b) in parked_cars {
         timer.next();
         if !ok {
@@ -366,6 +610,11 @@ fn seed_parked_cars(
         }
         if let Some(spot) = find_spot_near_building(b, &mut open_spots_per_road, map) {
             seeded += 1;
+            match spot {
+                ParkingSpot::Offstreet(_, _) => offstreet += 1,
+                ParkingSpot::Onstreet(_, _) => onstreet += 1,
+                ParkingSpot::Lot(_, _) => lots += 1,
+            }
             sim.seed_parked_car(vehicle, spot);
         } else {
             timer.warn(format!(
@@ -376,6 +625,13 @@ fn seed_parked_cars(
             ok = false;
         }
     }
+    timer.note(format!(
+        "Seeded {} parked cars: {} off-street, {} on-street, {} in parking lots",
+        prettyprint_usize(seeded),
+        prettyprint_usize(offstreet),
+        prettyprint_usize(onstreet),
+        prettyprint_usize(lots)
+    ));
 }
 
 // Pick a parking spot for this building. If the building's road has a free spot, use it. If not,
@@ -387,7 +643,7 @@ fn find_spot_near_building(
     map: &Map,
 ) -> Option<ParkingSpot> {
     let mut roads_queue: VecDeque<RoadID> = VecDeque::new();
-    let mut visited: HashSet<RoadID> = HashSet::new();
+    let mut visited: BTreeSet<RoadID> = BTreeSet::new();
     {
         let start = map.building_to_road(b).id;
         roads_queue.push_back(start);
@@ -424,7 +680,7 @@ fn find_spot_near_building(
 
 impl PersonSpec {
     /// Verify that a person's trips make sense
-    fn check_schedule(&self) -> Result<(), String> {
+    pub(crate) fn check_schedule(&self) -> Result<(), String> {
         for pair in self.trips.windows(2) {
             if pair[0].depart >= pair[1].depart {
                 return Err(format!(
@@ -537,3 +793,86 @@ impl PersonSpec {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // What Scenario looked like before parking prices were added
+    #[derive(Serialize)]
+    struct OldScenario {
+        scenario_name: String,
+        map_name: MapName,
+        people: Vec<PersonSpec>,
+        only_seed_buses: Option<BTreeSet<String>>,
+    }
+
+    fn old_scenario() -> OldScenario {
+        OldScenario {
+            scenario_name: "weekday".to_string(),
+            map_name: MapName::seattle("montlake"),
+            people: vec![PersonSpec {
+                orig_id: None,
+                origin: TripEndpoint::Bldg(BuildingID(1)),
+                trips: vec![IndividTrip::new(
+                    Time::START_OF_DAY,
+                    TripPurpose::Work,
+                    TripEndpoint::Bldg(BuildingID(2)),
+                    TripMode::Walk,
+                )],
+            }],
+            only_seed_buses: None,
+        }
+    }
+
+    #[test]
+    fn test_load_old_scenarios() {
+        let binary: Scenario =
+            abstutil::from_binary(&abstutil::to_binary(&old_scenario())).unwrap();
+        let json: Scenario =
+            abstutil::from_json(&abstutil::to_json(&old_scenario()).into_bytes()).unwrap();
+        for s in &[binary, json] {
+            assert_eq!(s.scenario_name, "weekday");
+            assert_eq!(s.people.len(), 1);
+            assert!(s.only_seed_buses.is_none());
+            assert!(s.transit_fares.is_free());
+            assert!(s.abandon_trips_after.is_none());
+            assert!(s.idle_parked_cars.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_corrupt_settings_fail_to_load() {
+        // One parking zone whose name isn't valid UTF-8
+        let mut raw = abstutil::to_binary(&old_scenario());
+        raw.extend(abstutil::to_binary(&1_u64));
+        raw.extend(abstutil::to_binary(&1_u64));
+        raw.push(0xFF);
+        assert!(abstutil::from_binary::<Scenario>(&raw).is_err());
+
+        // But a file that simply stops early is an old one
+        let mut truncated = abstutil::to_binary(&old_scenario());
+        truncated.extend(abstutil::to_binary(&1_u64));
+        assert!(abstutil::from_binary::<Scenario>(&truncated).is_ok());
+    }
+
+    #[test]
+    fn test_round_trip_settings() {
+        let old = old_scenario();
+        let mut s = Scenario::with_default_settings(
+            old.scenario_name,
+            old.map_name,
+            old.people,
+            old.only_seed_buses,
+        );
+        s.abandon_trips_after = Some(Duration::minutes(30));
+        s.idle_parked_cars.insert(BuildingID(3), 2);
+
+        let binary: Scenario = abstutil::from_binary(&abstutil::to_binary(&s)).unwrap();
+        let json: Scenario = abstutil::from_json(&abstutil::to_json(&s).into_bytes()).unwrap();
+        for s in &[binary, json] {
+            assert_eq!(s.abandon_trips_after, Some(Duration::minutes(30)));
+            assert_eq!(s.idle_parked_cars[&BuildingID(3)], 2);
+        }
+    }
+}
@@ -5,6 +5,7 @@ use rand::seq::SliceRandom;
 use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
 
+use geom::Distance;
 use map_model::{BuildingID, BusRouteID, BusStopID, Map, PathConstraints, PathRequest, Position};
 
 use crate::{
@@ -257,6 +258,7 @@ impl TripSpec {
         mode: TripMode,
         use_vehicle: Option<CarID>,
         retry_if_no_room: bool,
+        max_walking_distance: Distance,
         rng: &mut XorShiftRng,
         map: &Map,
     ) -> Result<TripSpec, String> {
@@ -325,8 +327,17 @@ impl TripSpec {
                         maybe_stop2,
                     }
                 } else {
-                    //timer.warn(format!("{:?} not actually using transit, because pathfinding
-                    // didn't find any useful route", trip));
+                    // There's no useful transit route. Walk instead, but only if it's not too far.
+                    let dist = start
+                        .sidewalk_pos
+                        .pt(map)
+                        .dist_to(goal.sidewalk_pos.pt(map));
+                    if dist > max_walking_distance {
+                        return Err(format!(
+                            "no useful transit route, and walking {} is too far",
+                            dist
+                        ));
+                    }
                     TripSpec::JustWalking { start, goal }
                 }
             }
@@ -335,6 +346,44 @@ impl TripSpec {
 }
 
 impl TripEndpoint {
+    /// Checks if a trip using this mode could start here, without making the trip. Only borders
+    /// might not have the lanes needed.
+    pub(crate) fn check_start(&self, mode: TripMode, map: &Map) -> Result<(), String> {
+        match mode {
+            TripMode::Drive | TripMode::Bike => {
+                if let TripEndpoint::Border(i) = self {
+                    let has_lanes = map
+                        .get_i(*i)
+                        .some_outgoing_road(map)
+                        .map(|dr| !dr.lanes(mode.to_constraints(), map).is_empty())
+                        .unwrap_or(false);
+                    if !has_lanes {
+                        return Err(format!(
+                            "can't start a {} trip from {}",
+                            mode.ongoing_verb(),
+                            i
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            TripMode::Walk | TripMode::Transit => self.start_sidewalk_spot(map).map(|_| ()),
+        }
+    }
+
+    /// Checks if a trip using this mode could end here, without making the trip.
+    pub(crate) fn check_end(&self, mode: TripMode, map: &Map) -> Result<(), String> {
+        if let TripEndpoint::SuddenlyAppear(pos) = self {
+            return Err(format!("trips can't end partway along {}", pos.lane()));
+        }
+        match mode {
+            TripMode::Drive | TripMode::Bike => {
+                self.driving_goal(mode.to_constraints(), map).map(|_| ())
+            }
+            TripMode::Walk | TripMode::Transit => self.end_sidewalk_spot(map).map(|_| ()),
+        }
+    }
+
     fn start_sidewalk_spot(&self, map: &Map) -> Result<SidewalkSpot, String> {
         match self {
             TripEndpoint::Bldg(b) => Ok(SidewalkSpot::building(*b, map)),
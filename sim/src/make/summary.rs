@@ -0,0 +1,255 @@
+//! A readable overview of a scenario, so the demand can be sanity-checked before spending time on
+//! a simulation.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use abstutil::{prettyprint_usize, Counter};
+use geom::{Duration, LonLat, Polygon, Ring, Time};
+use map_model::{BuildingID, Map};
+
+use crate::{Scenario, TripEndpoint, TripMode};
+
+// Names used for places not in any neighborhood
+const OUTSIDE_MAP: &str = "outside the map";
+const ELSEWHERE: &str = "not in a neighborhood";
+
+/// Totals describing a scenario. Cancelled trips aren't counted.
+#[derive(Clone, Serialize, Debug)]
+pub struct ScenarioSummary {
+    pub scenario_name: String,
+    pub num_people: usize,
+    pub trips_per_mode: BTreeMap<TripMode, usize>,
+    /// Keyed by the neighborhood name. Trips to or from borders are under "outside the map".
+    pub per_neighborhood: BTreeMap<String, NeighborhoodSummary>,
+    /// The number of trips departing each hour, starting with midnight to 1am. Scenarios may run
+    /// past midnight, so there can be more than 24.
+    pub departures_per_hour: Vec<usize>,
+    /// Cars parked at the start of the day, both the ones people will drive and the ones that stay
+    /// parked all day
+    pub parked_cars: usize,
+}
+
+#[derive(Clone, Serialize, Debug, Default)]
+pub struct NeighborhoodSummary {
+    pub trips_from: BTreeMap<TripMode, usize>,
+    pub trips_to: BTreeMap<TripMode, usize>,
+    pub parked_cars: usize,
+}
+
+impl Scenario {
+    /// Summarizes the scenario, grouping places by the neighborhood boundaries imported for the
+    /// map's city. If there aren't any, every building is grouped together.
+    pub fn summarize(&self, map: &Map) -> ScenarioSummary {
+        self.summarize_by(map, &load_neighborhoods(map))
+    }
+
+    /// Summarizes the scenario, grouping places by some named areas. Where areas overlap, the
+    /// first one containing a building wins.
+    pub fn summarize_by(&self, map: &Map, neighborhoods: &[(String, Polygon)]) -> ScenarioSummary {
+        let mut bldg_to_neighborhood: BTreeMap<BuildingID, String> = BTreeMap::new();
+        for b in map.all_buildings() {
+            let center = b.polygon.center();
+            if let Some((name, _)) = neighborhoods
+                .iter()
+                .find(|(_, polygon)| polygon.contains_pt(center))
+            {
+                bldg_to_neighborhood.insert(b.id, name.clone());
+            }
+        }
+        let neighborhood = |endpt: &TripEndpoint| -> String {
+            match endpt {
+                TripEndpoint::Bldg(b) => bldg_to_neighborhood
+                    .get(b)
+                    .cloned()
+                    .unwrap_or_else(|| ELSEWHERE.to_string()),
+                TripEndpoint::Border(_) => OUTSIDE_MAP.to_string(),
+                TripEndpoint::SuddenlyAppear(pos) => {
+                    let pt = pos.pt(map);
+                    neighborhoods
+                        .iter()
+                        .find(|(_, polygon)| polygon.contains_pt(pt))
+                        .map(|(name, _)| name.clone())
+                        .unwrap_or_else(|| ELSEWHERE.to_string())
+                }
+            }
+        };
+
+        let mut trips_per_mode: Counter<TripMode> = Counter::new();
+        let mut per_neighborhood: BTreeMap<String, NeighborhoodSummary> = BTreeMap::new();
+        let mut departures_per_hour = Vec::new();
+        for person in &self.people {
+            let mut from = &person.origin;
+            for trip in &person.trips {
+                if !trip.cancelled {
+                    trips_per_mode.inc(trip.mode);
+                    *per_neighborhood
+                        .entry(neighborhood(from))
+                        .or_default()
+                        .trips_from
+                        .entry(trip.mode)
+                        .or_insert(0) += 1;
+                    *per_neighborhood
+                        .entry(neighborhood(&trip.destination))
+                        .or_default()
+                        .trips_to
+                        .entry(trip.mode)
+                        .or_insert(0) += 1;
+
+                    let hour = trip.depart.get_parts().0;
+                    if departures_per_hour.len() <= hour {
+                        departures_per_hour.resize(hour + 1, 0);
+                    }
+                    departures_per_hour[hour] += 1;
+                }
+                from = &trip.destination;
+            }
+        }
+        if departures_per_hour.len() < 24 {
+            departures_per_hour.resize(24, 0);
+        }
+
+        let mut parked_cars = 0;
+        for (b, cnt) in self.count_parked_cars_per_bldg().consume() {
+            parked_cars += cnt;
+            per_neighborhood
+                .entry(neighborhood(&TripEndpoint::Bldg(b)))
+                .or_default()
+                .parked_cars += cnt;
+        }
+
+        ScenarioSummary {
+            scenario_name: self.scenario_name.clone(),
+            num_people: self.people.len(),
+            trips_per_mode: trips_per_mode.consume(),
+            per_neighborhood,
+            departures_per_hour,
+            parked_cars,
+        }
+    }
+}
+
+impl ScenarioSummary {
+    /// The summary as plain text tables, for printing.
+    pub fn to_table(&self) -> String {
+        let modes = TripMode::all();
+        let mut lines = vec![
+            format!(
+                "{}: {} people, {} parked cars at the start of the day",
+                self.scenario_name,
+                prettyprint_usize(self.num_people),
+                prettyprint_usize(self.parked_cars)
+            ),
+            String::new(),
+        ];
+
+        let mut header = vec!["".to_string()];
+        let mut row = vec!["trips".to_string()];
+        for mode in &modes {
+            header.push(mode.noun().to_string());
+            row.push(prettyprint_usize(count(&self.trips_per_mode, *mode)));
+        }
+        header.push("total".to_string());
+        row.push(prettyprint_usize(self.trips_per_mode.values().sum()));
+        lines.extend(table(vec![header, row]));
+        lines.push(String::new());
+
+        let mut header = vec!["neighborhood".to_string()];
+        for mode in &modes {
+            header.push(format!("{} from", mode.noun()));
+            header.push(format!("{} to", mode.noun()));
+        }
+        header.push("parked cars".to_string());
+        let mut rows = vec![header];
+        for (name, n) in &self.per_neighborhood {
+            let mut row = vec![name.clone()];
+            for mode in &modes {
+                row.push(prettyprint_usize(count(&n.trips_from, *mode)));
+                row.push(prettyprint_usize(count(&n.trips_to, *mode)));
+            }
+            row.push(prettyprint_usize(n.parked_cars));
+            rows.push(row);
+        }
+        lines.extend(table(rows));
+        lines.push(String::new());
+
+        let mut rows = vec![vec!["departing".to_string(), "trips".to_string()]];
+        for (hour, cnt) in self.departures_per_hour.iter().enumerate() {
+            rows.push(vec![
+                (Time::START_OF_DAY + Duration::hours(hour)).ampm_tostring(),
+                prettyprint_usize(*cnt),
+            ]);
+        }
+        // A histogram, with the busiest hour 40 characters long
+        let max = self.departures_per_hour.iter().max().cloned().unwrap_or(0);
+        for (line, cnt) in table(rows)
+            .into_iter()
+            .zip(std::iter::once(0).chain(self.departures_per_hour.iter().cloned()))
+        {
+            let bar = (40 * cnt).checked_div(max).unwrap_or(0);
+            lines.push(
+                format!("{} {}", line, "#".repeat(bar))
+                    .trim_end()
+                    .to_string(),
+            );
+        }
+
+        lines.join("\n")
+    }
+}
+
+fn count(per_mode: &BTreeMap<TripMode, usize>, mode: TripMode) -> usize {
+    per_mode.get(&mode).cloned().unwrap_or(0)
+}
+
+// Pads every column to the width of its longest cell. The first column is left-aligned, and the
+// rest are right-aligned.
+fn table(rows: Vec<Vec<String>>) -> Vec<String> {
+    let mut widths: Vec<usize> = Vec::new();
+    for row in &rows {
+        for (idx, cell) in row.iter().enumerate() {
+            if widths.len() <= idx {
+                widths.push(0);
+            }
+            widths[idx] = widths[idx].max(cell.chars().count());
+        }
+    }
+    rows.into_iter()
+        .map(|row| {
+            row.into_iter()
+                .enumerate()
+                .map(|(idx, cell)| {
+                    if idx == 0 {
+                        format!("{:<width$}", cell, width = widths[idx])
+                    } else {
+                        format!("{:>width$}", cell, width = widths[idx])
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+        })
+        .collect()
+}
+
+// Neighborhood boundaries are imported per city as .poly files. Unreadable ones are skipped.
+fn load_neighborhoods(map: &Map) -> Vec<(String, Polygon)> {
+    let mut results = Vec::new();
+    for path in abstutil::list_dir(abstutil::path_neighborhoods(map.get_city_name())) {
+        if !path.ends_with(".poly") {
+            continue;
+        }
+        let pts = match LonLat::read_osmosis_polygon(&path) {
+            Ok(pts) => map.get_gps_bounds().convert(&pts),
+            Err(err) => {
+                warn!("Skipping {}: {}", path, err);
+                continue;
+            }
+        };
+        match Ring::new(pts) {
+            Ok(ring) => results.push((abstutil::basename(&path), ring.to_polygon())),
+            Err(err) => warn!("Skipping {}: {}", path, err),
+        }
+    }
+    results
+}
@@ -0,0 +1,104 @@
+//! Scenario files can use named variables like `${PEAK_CARS}`, filled in when they're loaded, so
+//! a family of related scenarios can share one file instead of being copied and hand-edited.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+
+use rand_xorshift::XorShiftRng;
+
+use serde::Deserialize;
+
+use abstutil::{MapName, Timer};
+use map_model::Map;
+
+use crate::{Scenario, ScenarioGenerator};
+
+/// Replaces every `${NAME}` in the template with that parameter's value. The value is inserted
+/// as-is, so a variable can stand for a number, part of a string, or any other bit of JSON. Fails
+/// if any variable doesn't have a value.
+fn fill_template(template: &str, params: &BTreeMap<String, String>) -> Result<String, String> {
+    let mut result = String::new();
+    let mut missing = BTreeSet::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| format!("Unclosed variable starting at {}", &rest[start..]))?;
+        let name = &after[..end];
+        match params.get(name) {
+            Some(value) => result.push_str(value),
+            None => {
+                missing.insert(name.to_string());
+            }
+        }
+        rest = &after[end + 1..];
+    }
+    result.push_str(rest);
+
+    if !missing.is_empty() {
+        return Err(format!(
+            "No value for {}",
+            missing.into_iter().collect::<Vec<_>>().join(", ")
+        ));
+    }
+    Ok(result)
+}
+
+impl Scenario {
+    /// Loads a JSON scenario file, filling in its variables. The file can describe the people
+    /// directly, or be a `ScenarioGenerator`, which is then generated on this map.
+    pub fn load_template(
+        path: &str,
+        params: &BTreeMap<String, String>,
+        map: &Map,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) -> Result<Scenario, Box<dyn Error>> {
+        let filled = read_template(path, params)?;
+        let scenario_err = match abstutil::from_json::<Scenario>(&filled) {
+            Ok(scenario) => {
+                return Ok(scenario);
+            }
+            Err(err) => err,
+        };
+        match abstutil::from_json::<ScenarioGenerator>(&filled) {
+            Ok(generator) => Ok(generator.generate(map, rng, timer)),
+            Err(generator_err) => Err(format!(
+                "{} isn't a scenario ({}) or a scenario generator ({})",
+                path, scenario_err, generator_err
+            )
+            .into()),
+        }
+    }
+
+    /// Figures out what map a JSON scenario file is for. Scenarios name their map; otherwise the
+    /// file has to be in that map's scenario directory.
+    pub fn template_map_name(
+        path: &str,
+        params: &BTreeMap<String, String>,
+    ) -> Result<MapName, Box<dyn Error>> {
+        #[derive(Deserialize)]
+        struct JustMapName {
+            map_name: MapName,
+        }
+        if let Ok(x) = abstutil::from_json::<JustMapName>(&read_template(path, params)?) {
+            return Ok(x.map_name);
+        }
+        abstutil::parse_scenario_path(path)
+            .map(|(name, _)| name)
+            .ok_or_else(|| {
+                format!(
+                    "{} doesn't name a map and isn't in a scenarios directory; pass --map",
+                    path
+                )
+                .into()
+            })
+    }
+}
+
+fn read_template(path: &str, params: &BTreeMap<String, String>) -> Result<Vec<u8>, Box<dyn Error>> {
+    let raw = String::from_utf8(abstutil::slurp_file(path)?)?;
+    Ok(fill_template(&raw, params)?.into_bytes())
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+use geom::{Duration, Time};
+
+/// Trains passing through every at-grade rail crossing on the map. While a train goes by, nobody
+/// can cross the tracks, so road traffic queues up and pedestrians wait.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TrainSchedule {
+    /// When each train reaches the crossings
+    pub trains: Vec<Time>,
+    /// How long the crossings stay closed for each train, including the gates lowering and rising
+    pub closure: Duration,
+}
+
+impl TrainSchedule {
+    /// No trains run, so rail crossings never close.
+    pub fn none() -> TrainSchedule {
+        TrainSchedule {
+            trains: Vec::new(),
+            closure: Duration::minutes(2),
+        }
+    }
+
+    /// Replace the schedule with trains evenly spaced through the whole day.
+    pub fn set_trains_per_hour(&mut self, per_hour: usize) {
+        self.trains.clear();
+        if per_hour == 0 {
+            return;
+        }
+        let headway = Duration::hours(1) / (per_hour as f64);
+        let end = Time::START_OF_DAY + Duration::hours(24);
+        let mut t = Time::START_OF_DAY + headway / 2.0;
+        while t < end {
+            self.trains.push(t);
+            t += headway;
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+use geom::{Polygon, Pt2D};
+
+/// What transit riders pay each time they board a bus or train. Riders don't consider the fare
+/// when choosing how to travel; this is only used to count up revenue.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransitFares {
+    /// Boarding at any stop outside all of the zones costs this much, in cents
+    pub flat_cents: usize,
+    /// Boarding at a stop inside a zone costs that zone's fare instead. If zones overlap, the
+    /// first one wins.
+    pub zones: Vec<FareZone>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FareZone {
+    pub name: String,
+    pub polygon: Polygon,
+    pub cents: usize,
+}
+
+impl TransitFares {
+    /// Nobody pays to ride transit.
+    pub fn free() -> TransitFares {
+        TransitFares {
+            flat_cents: 0,
+            zones: Vec::new(),
+        }
+    }
+
+    pub fn is_free(&self) -> bool {
+        self.flat_cents == 0 && self.zones.iter().all(|z| z.cents == 0)
+    }
+
+    /// The fare for boarding at a stop at some point, in cents
+    pub fn cents(&self, pt: Pt2D) -> usize {
+        self.zones
+            .iter()
+            .find(|z| z.polygon.contains_pt(pt))
+            .map(|z| z.cents)
+            .unwrap_or(self.flat_cents)
+    }
+}
@@ -0,0 +1,153 @@
+use std::fmt;
+
+use map_model::Map;
+
+use crate::make::missing_data::describe_person;
+use crate::{Scenario, ScenarioGenerator};
+
+/// Something that'll keep a scenario from running as intended, found before running it.
+#[derive(Clone, Debug)]
+pub struct ScenarioProblem {
+    /// The person, zone, or spawn block with the problem
+    pub source: String,
+    pub kind: ProblemKind,
+    pub problem: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProblemKind {
+    /// Refers to something not in the map. The scenario can't be instantiated until this is
+    /// handled; see `MissingDataPolicy`.
+    MissingFromMap,
+    /// A bus route to seed isn't in the map, so it won't run
+    MissingBusRoute,
+    /// A trip starts or ends at a border without the lanes its mode needs, so it'll be cancelled
+    UnusableBorder,
+    /// A named area, like a parking pricing zone, a transit fare zone, or where an event's
+    /// attendees come from, doesn't contain anything it could apply to
+    EmptyArea,
+}
+
+impl ScenarioProblem {
+    /// Instantiating the scenario fails because of this. Everything else just means some trips
+    /// or settings won't have any effect.
+    pub fn is_fatal(&self) -> bool {
+        self.kind == ProblemKind::MissingFromMap
+    }
+}
+
+impl fmt::Display for ScenarioProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.source, self.problem)
+    }
+}
+
+impl Scenario {
+    /// Finds everything that'll keep this scenario from running as intended on the map, without
+    /// changing anything. `instantiate` refuses to start with fatal problems, and only warns about
+    /// the rest, so check this first to get all of them at once.
+    pub fn validate(&self, map: &Map) -> Vec<ScenarioProblem> {
+        let mut problems = Vec::new();
+        for (idx, person) in self.people.iter().enumerate() {
+            let source = describe_person(idx, person);
+            if let Err(problem) = person.check_references(map) {
+                problems.push(ScenarioProblem {
+                    source,
+                    kind: ProblemKind::MissingFromMap,
+                    problem,
+                });
+                continue;
+            }
+            let mut from = &person.origin;
+            for (trip_idx, trip) in person.trips.iter().enumerate() {
+                if trip.cancelled {
+                    from = &trip.destination;
+                    continue;
+                }
+                if let Err(problem) = from
+                    .check_start(trip.mode, map)
+                    .and_then(|_| trip.destination.check_end(trip.mode, map))
+                {
+                    problems.push(ScenarioProblem {
+                        source: source.clone(),
+                        kind: ProblemKind::UnusableBorder,
+                        problem: format!("trip #{}: {}", trip_idx, problem),
+                    });
+                }
+                from = &trip.destination;
+            }
+        }
+
+        if let Some(ref routes) = self.only_seed_buses {
+            for name in routes {
+                if map.get_bus_route(name).is_none() {
+                    problems.push(ScenarioProblem {
+                        source: "buses to seed".to_string(),
+                        kind: ProblemKind::MissingBusRoute,
+                        problem: format!("bus route {} doesn't exist", name),
+                    });
+                }
+            }
+        }
+
+        for zone in &self.parking_prices.zones {
+            if !map
+                .all_lanes()
+                .iter()
+                .any(|l| l.is_parking() && zone.polygon.contains_pt(l.lane_center_pts.middle()))
+            {
+                problems.push(ScenarioProblem {
+                    source: format!("parking pricing zone {}", zone.name),
+                    kind: ProblemKind::EmptyArea,
+                    problem: "there's no on-street parking inside".to_string(),
+                });
+            }
+        }
+
+        for zone in &self.transit_fares.zones {
+            if !map
+                .all_bus_stops()
+                .values()
+                .any(|bs| zone.polygon.contains_pt(bs.sidewalk_pos.pt(map)))
+            {
+                problems.push(ScenarioProblem {
+                    source: format!("transit fare zone {}", zone.name),
+                    kind: ProblemKind::EmptyArea,
+                    problem: "there are no transit stops inside".to_string(),
+                });
+            }
+        }
+
+        problems
+    }
+}
+
+impl ScenarioGenerator {
+    /// Finds every spawn block that refers to something not in the map, and every place people
+    /// are supposed to come from that doesn't contain any buildings. Nothing is changed.
+    pub fn validate(&self, map: &Map) -> Vec<ScenarioProblem> {
+        let mut problems: Vec<ScenarioProblem> = self
+            .find_missing_data(map)
+            .into_iter()
+            .map(|x| ScenarioProblem {
+                source: x.source,
+                kind: ProblemKind::MissingFromMap,
+                problem: x.problem,
+            })
+            .collect();
+
+        for (idx, s) in self.venue_events.iter().enumerate() {
+            for origin in &s.origins {
+                if !map.all_buildings().iter().any(|b| origin.contains_bldg(b)) {
+                    problems.push(ScenarioProblem {
+                        source: format!("venue_events #{}", idx),
+                        kind: ProblemKind::EmptyArea,
+                        problem: format!("origin {} doesn't contain any buildings", origin.name),
+                    });
+                }
+            }
+        }
+
+        problems
+    }
+}
@@ -2,7 +2,7 @@ use std::collections::VecDeque;
 
 use serde::{Deserialize, Serialize};
 
-use geom::{Distance, Duration, PolyLine, Time};
+use geom::{Distance, Duration, PolyLine, Speed, Time};
 use map_model::{Direction, Map, Traversable};
 
 use crate::{
@@ -21,6 +21,8 @@ pub(crate) struct Car {
     pub trip_and_person: Option<(TripID, PersonID)>,
     pub started_at: Time,
     pub total_blocked_time: Duration,
+    /// How many times the speed limit this car goes; more than 1 for speeding drivers
+    pub speeding: f64,
 
     /// In reverse order -- most recently left is first. The sum length of these must be >=
     /// vehicle.length.
@@ -48,12 +50,32 @@ impl Car {
         map: &Map,
     ) -> CarState {
         let on = self.router.head();
-        let mut speed = on.speed_limit(map);
+        let mut profile = on.crossing_profile(
+            dist_int.start,
+            dist_int.end,
+            self.speed_on(on, map),
+            Some(start_time),
+            map,
+        );
+        let dt = profile.last().unwrap().1;
+        // Only remember where the car slows down if it actually does
+        if profile.len() <= 2 {
+            profile.clear();
+        }
+        CarState::Crossing(
+            TimeInterval::new(start_time, start_time + dt),
+            dist_int,
+            profile,
+        )
+    }
+
+    /// How fast this car goes on something, not counting any traffic calming
+    pub fn speed_on(&self, on: Traversable, map: &Map) -> Speed {
+        let mut speed = on.speed_limit(map) * self.speeding;
         if let Some(s) = self.vehicle.max_speed {
             speed = speed.min(s);
         }
-        let dt = (dist_int.end - dist_int.start) / speed;
-        CarState::Crossing(TimeInterval::new(start_time, start_time + dt), dist_int)
+        speed
     }
 
     pub fn get_draw_car(
@@ -214,7 +236,7 @@ impl Car {
             status: match self.state {
                 CarState::Queued { .. } => CarStatus::Moving,
                 CarState::WaitingToAdvance { .. } => CarStatus::Moving,
-                CarState::Crossing(_, _) => CarStatus::Moving,
+                CarState::Crossing(..) => CarStatus::Moving,
                 CarState::Unparking(_, _, _) => CarStatus::Moving,
                 CarState::Parking(_, _, _) => CarStatus::Moving,
                 // Changing color for idling buses is helpful
@@ -253,7 +275,9 @@ impl Car {
 /// state machine encoded here.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub(crate) enum CarState {
-    Crossing(TimeInterval, DistanceInterval),
+    /// If the car slows down somewhere along the way, the last part says where (see
+    /// `Traversable::crossing_profile`). Otherwise, it's empty and the speed is constant.
+    Crossing(TimeInterval, DistanceInterval, Vec<(Distance, Duration)>),
     Queued {
         blocked_since: Time,
     },
@@ -269,7 +293,7 @@ pub(crate) enum CarState {
 impl CarState {
     pub fn get_end_time(&self) -> Time {
         match self {
-            CarState::Crossing(ref time_int, _) => time_int.end,
+            CarState::Crossing(ref time_int, _, _) => time_int.end,
             CarState::Queued { .. } => unreachable!(),
             CarState::WaitingToAdvance { .. } => unreachable!(),
             CarState::Unparking(_, _, ref time_int) => time_int.end,
@@ -287,3 +311,30 @@ impl CarState {
         }
     }
 }
+
+/// Where the front of a crossing car is at some time. It's not always proportional to the time
+/// elapsed, since the car may slow down somewhere along the way.
+pub(crate) fn crossing_front(
+    time_int: &TimeInterval,
+    dist_int: &DistanceInterval,
+    profile: &[(Distance, Duration)],
+    now: Time,
+) -> Distance {
+    // TODO Why percent_clamp_end? We process car updates in any order, so we might calculate this
+    // before moving this car from Crossing to another state.
+    let percent = time_int.percent_clamp_end(now);
+    if profile.is_empty() {
+        return dist_int.lerp(percent);
+    }
+    let elapsed = percent * profile.last().unwrap().1;
+    for pair in profile.windows(2) {
+        let ((dist1, t1), (dist2, t2)) = (pair[0], pair[1]);
+        if elapsed <= t2 {
+            if t2 == t1 {
+                return dist2;
+            }
+            return dist1 + (elapsed - t1) / (t2 - t1) * (dist2 - dist1);
+        }
+    }
+    dist_int.end
+}
@@ -2,24 +2,26 @@ use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 
 use serde::{Deserialize, Serialize};
 
-use abstutil::{deserialize_hashmap, serialize_hashmap, FixedMap, IndexableKey};
+use abstutil::{
+    deserialize_btreemap, deserialize_hashmap, serialize_btreemap, serialize_hashmap, FixedMap,
+    IndexableKey,
+};
 use geom::{Distance, Duration, PolyLine, Speed, Time};
-use map_model::{IntersectionID, LaneID, Map, Path, PathStep, Traversable};
+use map_model::{
+    BuildingID, IntersectionID, LaneID, Map, ParkingLotID, Path, PathStep, Traversable,
+};
 
 use crate::mechanics::car::{Car, CarState};
 use crate::mechanics::Queue;
 use crate::sim::Ctx;
 use crate::{
-    ActionAtEnd, AgentID, AgentProperties, CarID, Command, CreateCar, DelayCause, DistanceInterval,
+    ActionAtEnd, AgentID, AgentProperties, BlockedOn, CarID, Command, CreateCar, DistanceInterval,
     DrawCarInput, Event, IntersectionSimState, ParkedCar, ParkingSim, ParkingSpot, PersonID,
-    SimOptions, TimeInterval, TransitSimState, TripID, TripManager, UnzoomedAgent, Vehicle,
-    WalkingSimState, FOLLOWING_DISTANCE,
+    SimOptions, Speeding, TimeInterval, TransitSimState, TripID, TripManager, UnzoomedAgent,
+    Vehicle, WalkingSimState, FOLLOWING_DISTANCE,
 };
 
-const TIME_TO_WAIT_AT_BUS_STOP: Duration = Duration::const_seconds(10.0);
-
 // TODO Do something else.
-pub const BLIND_RETRY_TO_CREEP_FORWARDS: Duration = Duration::const_seconds(0.1);
 pub const BLIND_RETRY_TO_REACH_END_DIST: Duration = Duration::const_seconds(5.0);
 
 /// Simulates vehicles!
@@ -42,11 +44,36 @@ pub(crate) struct DrivingSimState {
 
     recalc_lanechanging: bool,
     handle_uber_turns: bool,
+    retry_granularity: Duration,
+    intersection_retry_granularity: Duration,
 
     time_to_unpark_onstreet: Duration,
     time_to_park_onstreet: Duration,
     time_to_unpark_offstreet: Duration,
     time_to_park_offstreet: Duration,
+
+    garage_exit_headway: Option<Duration>,
+    // When the next car can leave each garage
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    garage_exits: BTreeMap<Garage, Time>,
+    // Cars waiting inside a garage for their turn to leave
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    garage_departures: BTreeMap<CarID, Time>,
+
+    speeding: Option<Speeding>,
+}
+
+/// Somewhere off-street that cars leave through one entrance
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum Garage {
+    Building(BuildingID),
+    Lot(ParkingLotID),
 }
 
 // Mutations
@@ -58,11 +85,19 @@ impl DrivingSimState {
             events: Vec::new(),
             recalc_lanechanging: opts.recalc_lanechanging,
             handle_uber_turns: opts.handle_uber_turns,
+            retry_granularity: opts.retry_granularity,
+            intersection_retry_granularity: opts.effective_intersection_retry_granularity(),
 
             time_to_unpark_onstreet: Duration::seconds(10.0),
             time_to_park_onstreet: Duration::seconds(15.0),
             time_to_unpark_offstreet: Duration::seconds(5.0),
             time_to_park_offstreet: Duration::seconds(5.0),
+
+            garage_exit_headway: opts.garage_exit_headway,
+            garage_exits: BTreeMap::new(),
+            garage_departures: BTreeMap::new(),
+
+            speeding: opts.speeding.clone(),
         };
         if opts.infinite_parking {
             sim.time_to_unpark_offstreet = Duration::seconds(0.1);
@@ -85,6 +120,38 @@ impl DrivingSimState {
         sim
     }
 
+    /// Only one car at a time can leave a garage, so if this car is leaving one that's busy,
+    /// returns when it gets its turn. Until then, it waits inside and its trip is delayed.
+    pub fn wait_to_exit_garage(&mut self, now: Time, params: &CreateCar) -> Option<Time> {
+        let headway = self.garage_exit_headway?;
+        let garage = match params.maybe_parked_car.as_ref()?.spot {
+            ParkingSpot::Onstreet(_, _) => {
+                return None;
+            }
+            ParkingSpot::Offstreet(b, _) => Garage::Building(b),
+            ParkingSpot::Lot(pl, _) => Garage::Lot(pl),
+        };
+        if let Some(turn) = self.garage_departures.get(&params.vehicle.id) {
+            // Already waited. If the street outside is full, the car keeps its turn and retries.
+            if now >= *turn {
+                return None;
+            }
+            return Some(*turn);
+        }
+        let turn = self
+            .garage_exits
+            .get(&garage)
+            .cloned()
+            .unwrap_or(now)
+            .max(now);
+        self.garage_exits.insert(garage, turn + headway);
+        if turn == now {
+            return None;
+        }
+        self.garage_departures.insert(params.vehicle.id, turn);
+        Some(turn)
+    }
+
     /// None if it worked, otherwise returns the CreateCar unmodified for possible retry.
     pub fn start_car_on_lane(
         &mut self,
@@ -107,6 +174,11 @@ impl DrivingSimState {
             &self.cars,
             &self.queues,
         ) {
+            let speeding = self
+                .speeding
+                .as_ref()
+                .map(|s| s.sample(params.vehicle.id))
+                .unwrap_or(1.0);
             let mut car = Car {
                 vehicle: params.vehicle,
                 router: params.router,
@@ -116,8 +188,10 @@ impl DrivingSimState {
                 started_at: now,
                 total_blocked_time: Duration::ZERO,
                 trip_and_person: params.trip_and_person,
+                speeding,
             };
             if let Some(p) = params.maybe_parked_car {
+                self.garage_departures.remove(&car.vehicle.id);
                 let delay = match p.spot {
                     ParkingSpot::Onstreet(_, _) => self.time_to_unpark_onstreet,
                     ParkingSpot::Offstreet(_, _) | ParkingSpot::Lot(_, _) => {
@@ -132,14 +206,18 @@ impl DrivingSimState {
             } else {
                 // Have to do this early
                 if car.router.last_step() {
-                    match car.router.maybe_handle_end(
+                    let started = ctx.start_parking();
+                    let action = car.router.maybe_handle_end(
+                        now,
                         params.start_dist,
                         &car.vehicle,
                         ctx.parking,
                         ctx.map,
                         car.trip_and_person,
                         &mut self.events,
-                    ) {
+                    );
+                    ctx.record_parking(started);
+                    match action {
                         None | Some(ActionAtEnd::GotoLaneEnd) => {}
                         x => {
                             panic!(
@@ -226,7 +304,7 @@ impl DrivingSimState {
             // checker, temporarily move one of them out of the map.
             let mut car = self.cars.remove(&id).unwrap();
             // Responsibility of update_car to manage scheduling stuff!
-            need_distances = self.update_car_without_distances(&mut car, now, ctx, transit);
+            need_distances = self.update_car_without_distances(&mut car, now, ctx, trips, transit);
             self.cars.insert(id, car);
         }
 
@@ -261,10 +339,11 @@ impl DrivingSimState {
         car: &mut Car,
         now: Time,
         ctx: &mut Ctx,
+        trips: &mut TripManager,
         transit: &mut TransitSimState,
     ) -> bool {
         match car.state {
-            CarState::Crossing(time_int, dist_int) => {
+            CarState::Crossing(time_int, dist_int, _) => {
                 let time_cross = now - time_int.start;
                 if time_cross > Duration::ZERO {
                     let avg_speed = Speed::from_dist_time(dist_int.length(), time_cross);
@@ -295,6 +374,7 @@ impl DrivingSimState {
                     car.state = CarState::WaitingToAdvance { blocked_since: now };
                     if self.recalc_lanechanging {
                         car.router.opportunistically_lanechange(
+                            now,
                             &self.queues,
                             ctx.map,
                             self.handle_uber_turns,
@@ -309,7 +389,9 @@ impl DrivingSimState {
                     // something weird like vanishing or re-parking immediately (quite unlikely),
                     // the next loop will pick that up. Just trigger the side effect of choosing an
                     // end_dist.
+                    let started = ctx.start_parking();
                     car.router.maybe_handle_end(
+                        now,
                         front,
                         &car.vehicle,
                         ctx.parking,
@@ -317,13 +399,20 @@ impl DrivingSimState {
                         car.trip_and_person,
                         &mut self.events,
                     );
+                    ctx.record_parking(started);
                 }
                 car.state = car.crossing_state(front, now, ctx.map);
                 ctx.scheduler
                     .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
             }
             CarState::IdlingAtStop(dist, _) => {
-                car.router = transit.bus_departed_from_stop(car.vehicle.id, ctx.map);
+                if let Some(dt) = transit.extra_dwell(car.vehicle.id) {
+                    car.state = CarState::IdlingAtStop(dist, TimeInterval::new(now, now + dt));
+                    ctx.scheduler
+                        .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
+                    return false;
+                }
+                car.router = transit.bus_departed_from_stop(now, car.vehicle.id, trips, ctx);
                 self.events
                     .push(Event::PathAmended(car.router.get_path().clone()));
                 car.state = car.crossing_state(dist, now, ctx.map);
@@ -360,7 +449,7 @@ impl DrivingSimState {
                         CarState::WaitingToAdvance { .. } => unreachable!(),
                         // They weren't blocked. Note that there's no way the Crossing state could
                         // jump forwards here; the leader is still in front of them.
-                        CarState::Crossing(_, _)
+                        CarState::Crossing(..)
                         | CarState::Unparking(_, _, _)
                         | CarState::Parking(_, _, _)
                         | CarState::IdlingAtStop(_, _) => {}
@@ -370,15 +459,13 @@ impl DrivingSimState {
             CarState::Queued { .. } => unreachable!(),
             CarState::WaitingToAdvance { blocked_since } => {
                 // 'car' is the leader.
+                car.router.avoid_work_zones(now, ctx.map, &mut self.events);
                 let from = car.router.head();
                 let goto = car.router.next();
                 assert!(from != goto);
 
                 if let Traversable::Turn(t) = goto {
-                    let mut speed = goto.speed_limit(ctx.map);
-                    if let Some(s) = car.vehicle.max_speed {
-                        speed = speed.min(s);
-                    }
+                    let speed = car.speed_on(goto, ctx.map);
                     if !ctx.intersections.maybe_start_turn(
                         AgentID::Car(car.vehicle.id),
                         t,
@@ -410,13 +497,17 @@ impl DrivingSimState {
                 // We do NOT need to update the follower. If they were Queued, they'll remain that
                 // way, until laggy_head is None.
 
+                // Advancing might start searching for parking
+                let started = ctx.start_parking();
                 let last_step = car.router.advance(
+                    now,
                     &car.vehicle,
                     ctx.parking,
                     ctx.map,
                     car.trip_and_person,
                     &mut self.events,
                 );
+                ctx.record_parking(started);
                 car.total_blocked_time += now - blocked_since;
                 car.state = car.crossing_state(Distance::ZERO, now, ctx.map);
                 ctx.scheduler
@@ -476,19 +567,23 @@ impl DrivingSimState {
         let our_dist = dists[idx].1;
 
         match car.state {
-            CarState::Crossing(_, _)
+            CarState::Crossing(..)
             | CarState::Unparking(_, _, _)
             | CarState::IdlingAtStop(_, _)
             | CarState::WaitingToAdvance { .. } => unreachable!(),
             CarState::Queued { blocked_since } => {
-                match car.router.maybe_handle_end(
+                let started = ctx.start_parking();
+                let action = car.router.maybe_handle_end(
+                    now,
                     our_dist,
                     &car.vehicle,
                     ctx.parking,
                     ctx.map,
                     car.trip_and_person,
                     &mut self.events,
-                ) {
+                );
+                ctx.record_parking(started);
+                match action {
                     Some(ActionAtEnd::VanishAtBorder(i)) => {
                         car.total_blocked_time += now - blocked_since;
                         // Don't do this for buses
@@ -557,11 +652,11 @@ impl DrivingSimState {
                     }
                     Some(ActionAtEnd::BusAtStop) => {
                         car.total_blocked_time += now - blocked_since;
-                        if transit.bus_arrived_at_stop(now, car.vehicle.id, trips, walking, ctx) {
-                            car.state = CarState::IdlingAtStop(
-                                our_dist,
-                                TimeInterval::new(now, now + TIME_TO_WAIT_AT_BUS_STOP),
-                            );
+                        if let Some(dt) =
+                            transit.bus_arrived_at_stop(now, car.vehicle.id, trips, walking, ctx)
+                        {
+                            car.state =
+                                CarState::IdlingAtStop(our_dist, TimeInterval::new(now, now + dt));
                             ctx.scheduler
                                 .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
                             true
@@ -581,7 +676,7 @@ impl DrivingSimState {
                         /*
                         // If this car wasn't blocked at all, when would it reach its goal?
                         let ideal_end_time = match car.crossing_state(our_dist, now, map) {
-                            CarState::Crossing(time_int, _) => time_int.end,
+                            CarState::Crossing(time_int, _, _) => time_int.end,
                             _ => unreachable!(),
                         };
                         if ideal_end_time == now {
@@ -645,6 +740,29 @@ impl DrivingSimState {
         car.vehicle
     }
 
+    /// Remove a vehicle whose driver gave up on their trip. Unlike `delete_car`, this releases
+    /// everything the car held at any intersection, including uber-turn reservations and requests
+    /// waiting on a bridge, and wakes up anybody who was waiting on them.
+    pub fn abandon_car(&mut self, c: CarID, now: Time, ctx: &mut Ctx) -> Vehicle {
+        let dists = self.queues[&self.cars[&c].router.head()].get_car_positions(
+            now,
+            &self.cars,
+            &self.queues,
+        );
+        let idx = dists.iter().position(|(id, _)| *id == c).unwrap();
+        let mut car = self.cars.remove(&c).unwrap();
+
+        if let Traversable::Turn(_) = car.router.head() {
+            let queue = self.queues.get_mut(&car.router.head()).unwrap();
+            queue.reserved_length += car.vehicle.length + FOLLOWING_DISTANCE;
+        }
+        self.delete_car_internal(&mut car, dists, idx, now, ctx);
+        ctx.scheduler.cancel(Command::UpdateCar(c));
+        ctx.intersections
+            .agent_abandoned(now, AgentID::Car(c), ctx.scheduler, ctx.map);
+        car.vehicle
+    }
+
     fn delete_car_internal(
         &mut self,
         car: &mut Car,
@@ -658,13 +776,9 @@ impl DrivingSimState {
             assert_eq!(queue.cars.remove(idx).unwrap(), car.vehicle.id);
             // trim_last_steps doesn't actually include the current queue!
             queue.free_reserved_space(car);
-            let i = match queue.id {
-                Traversable::Lane(l) => ctx.map.get_l(l).src_i,
-                Traversable::Turn(t) => t.parent,
-            };
             if !ctx.handling_live_edits {
                 ctx.intersections
-                    .space_freed(now, i, ctx.scheduler, ctx.map);
+                    .space_freed(now, queue, ctx.scheduler, ctx.map);
             }
         }
 
@@ -673,7 +787,7 @@ impl DrivingSimState {
         // We might be vanishing while partly clipping into other stuff.
         self.trim_last_steps(car, now, car.last_steps.len(), ctx);
 
-        // We might've scheduled one of those using BLIND_RETRY_TO_CREEP_FORWARDS.
+        // We might've scheduled one of those using retry_granularity.
         ctx.scheduler
             .cancel(Command::UpdateLaggyHead(car.vehicle.id));
 
@@ -694,7 +808,7 @@ impl DrivingSimState {
                         Command::UpdateCar(follower_id),
                     );
                 }
-                CarState::Crossing(_, _) => {
+                CarState::Crossing(..) => {
                     // If the follower was still Crossing, they might not've been blocked by leader
                     // yet. In that case, recalculating their Crossing state is a no-op.
                     follower.state = follower.crossing_state(follower_dist, now, ctx.map);
@@ -770,11 +884,19 @@ impl DrivingSimState {
                 // If we look up car positions before this retry happens, weird things can
                 // happen -- the laggy head could be well clear of the old queue by then. Make
                 // sure to handle that there. Consequences of this retry being long? A follower
-                // will wait a bit before advancing.
-                ctx.scheduler.push(
-                    now + BLIND_RETRY_TO_CREEP_FORWARDS,
-                    Command::UpdateLaggyHead(id),
-                );
+                // will wait a bit before advancing. If the tail is still inside an intersection,
+                // it's blocking conflicting turns, so check more often.
+                let retry = if self.cars[&id]
+                    .last_steps
+                    .iter()
+                    .any(|on| matches!(on, Traversable::Turn(_)))
+                {
+                    self.intersection_retry_granularity
+                } else {
+                    self.retry_granularity
+                };
+                ctx.scheduler
+                    .push(now + retry, Command::UpdateLaggyHead(id));
             }
         }
     }
@@ -797,15 +919,11 @@ impl DrivingSimState {
                         ctx.handling_live_edits,
                     );
                 }
-                Traversable::Lane(l) => {
+                Traversable::Lane(_) => {
                     old_queue.free_reserved_space(car);
                     if !ctx.handling_live_edits {
-                        ctx.intersections.space_freed(
-                            now,
-                            ctx.map.get_l(l).src_i,
-                            ctx.scheduler,
-                            ctx.map,
-                        );
+                        ctx.intersections
+                            .space_freed(now, old_queue, ctx.scheduler, ctx.map);
                     }
                 }
             }
@@ -826,6 +944,7 @@ impl DrivingSimState {
                                 follower.state = CarState::WaitingToAdvance { blocked_since };
                                 if self.recalc_lanechanging && !ctx.handling_live_edits {
                                     follower.router.opportunistically_lanechange(
+                                        now,
                                         &self.queues,
                                         ctx.map,
                                         self.handle_uber_turns,
@@ -838,7 +957,7 @@ impl DrivingSimState {
                         CarState::WaitingToAdvance { .. } => unreachable!(),
                         // They weren't blocked. Note that there's no way the Crossing state could
                         // jump forwards here; the leader vanished from the end of the traversable.
-                        CarState::Crossing(_, _)
+                        CarState::Crossing(..)
                         | CarState::Unparking(_, _, _)
                         | CarState::Parking(_, _, _)
                         | CarState::IdlingAtStop(_, _) => {}
@@ -979,6 +1098,14 @@ impl DrivingSimState {
         }
     }
 
+    /// How far the front of each car on something is along it
+    pub fn get_car_positions_on(&self, now: Time, on: Traversable) -> Vec<(CarID, Distance)> {
+        match self.queues.get(&on) {
+            Some(q) => q.get_car_positions(now, &self.cars, &self.queues),
+            None => Vec::new(),
+        }
+    }
+
     pub fn debug_car(&self, id: CarID) {
         if let Some(ref car) = self.cars.get(&id) {
             println!("{}", abstutil::to_json(car));
@@ -1164,17 +1291,58 @@ impl DrivingSimState {
         }
     }
 
+    /// Cars on a trip that've been stuck in traffic or searching for parking for at least this
+    /// long. Nothing remembers when a car started searching for parking, so `searching_since`
+    /// tracks when each car was first seen searching by a previous call.
+    pub fn find_stuck_cars(
+        &self,
+        now: Time,
+        threshold: Duration,
+        searching_since: &mut BTreeMap<CarID, Time>,
+    ) -> Vec<(CarID, TripID, Duration)> {
+        let mut stuck = Vec::new();
+        let mut still_searching = BTreeMap::new();
+        for c in self.cars.values() {
+            let trip = match c.trip_and_person {
+                Some((trip, _)) => trip,
+                None => continue,
+            };
+            let searching = if c.router.is_parking() {
+                let since = searching_since.get(&c.vehicle.id).cloned().unwrap_or(now);
+                still_searching.insert(c.vehicle.id, since);
+                now - since
+            } else {
+                Duration::ZERO
+            };
+            // Don't interrupt somebody in the middle of parking or unparking
+            let can_abandon = match c.state {
+                CarState::Crossing(..)
+                | CarState::Queued { .. }
+                | CarState::WaitingToAdvance { .. } => true,
+                CarState::Unparking(_, _, _)
+                | CarState::Parking(_, _, _)
+                | CarState::IdlingAtStop(_, _) => false,
+            };
+            let delay = c.state.time_spent_waiting(now).max(searching);
+            if can_abandon && delay >= threshold {
+                stuck.push((c.vehicle.id, trip, delay));
+            }
+        }
+        *searching_since = still_searching;
+        stuck
+    }
+
     pub fn debug_queue_lengths(&self, l: LaneID) -> Option<(Distance, Distance)> {
         let queue = self.queues.get(&Traversable::Lane(l))?;
         Some((queue.reserved_length, queue.geom_len))
     }
 
-    pub fn get_blocked_by_graph(
+    pub fn get_blocked_on(
         &self,
         now: Time,
         map: &Map,
         intersections: &IntersectionSimState,
-    ) -> BTreeMap<AgentID, (Duration, DelayCause)> {
+    ) -> BTreeMap<AgentID, (Duration, BlockedOn)> {
         let mut graph = BTreeMap::new();
 
         // Just look for every case where somebody is behind someone else, whether or not they're
@@ -1186,7 +1354,7 @@ impl DrivingSimState {
                         AgentID::Car(*next),
                         (
                             self.cars[&head].state.time_spent_waiting(now),
-                            DelayCause::Agent(AgentID::Car(head)),
+                            BlockedOn::LeadVehicle(head),
                         ),
                     );
                 }
@@ -1196,13 +1364,22 @@ impl DrivingSimState {
                     AgentID::Car(*tail),
                     (
                         self.cars[tail].state.time_spent_waiting(now),
-                        DelayCause::Agent(AgentID::Car(*head)),
+                        BlockedOn::LeadVehicle(*head),
                     ),
                 );
             }
         }
 
-        intersections.populate_blocked_by(now, &mut graph, map, &self.cars, &self.queues);
+        intersections.populate_blocked_on(now, &mut graph, map, &self.cars, &self.queues);
+
+        // Anybody searching for parking who isn't stuck behind something more specific
+        for car in self.cars.values() {
+            if let Some((b, since)) = car.router.parking_search() {
+                graph
+                    .entry(AgentID::Car(car.vehicle.id))
+                    .or_insert((now - since, BlockedOn::NoParking(b)));
+            }
+        }
         graph
     }
 }
@@ -1,24 +1,28 @@
+use std::collections::btree_map::Entry;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 use serde::{Deserialize, Serialize};
 
 use abstutil::{
-    deserialize_btreemap, prettyprint_usize, retain_btreeset, serialize_btreemap, FixedMap,
+    deserialize_btreemap, prettyprint_usize, retain_btreemap, retain_btreeset, serialize_btreemap,
+    FixedMap,
 };
-use geom::{Duration, Time};
+use geom::{Distance, Duration, Time};
 use map_model::{
     ControlStopSign, ControlTrafficSignal, Intersection, IntersectionID, LaneID, Map, PhaseType,
-    Traversable, TurnID, TurnPriority, TurnType,
+    RoadID, Traversable, TurnID, TurnPriority, TurnType,
 };
 
 use crate::mechanics::car::Car;
 use crate::mechanics::Queue;
 use crate::{
-    AgentID, AlertLocation, CarID, Command, DelayCause, Event, Scheduler, SimOptions, Speed,
+    AgentID, AlertLocation, BlockedOn, CarID, Command, Event, Scheduler, SimOptions, Speed,
 };
 
-const WAIT_AT_STOP_SIGN: Duration = Duration::const_seconds(0.5);
+pub(crate) const WAIT_AT_STOP_SIGN: Duration = Duration::const_seconds(0.5);
 const WAIT_BEFORE_YIELD_AT_TRAFFIC_SIGNAL: Duration = Duration::const_seconds(0.2);
+// A group of pedestrians waiting at a crosswalk doesn't step off the curb all at once.
+const CROSSWALK_PLATOON_HEADWAY: Duration = Duration::const_seconds(0.5);
 
 /// Manages conflicts at intersections. When an agent has reached the end of a lane, they call
 /// maybe_start_turn to make a Request. Based on the intersection type (stop sign, traffic signal,
@@ -36,9 +40,35 @@ pub(crate) struct IntersectionSimState {
     break_turn_conflict_cycles: bool,
     handle_uber_turns: bool,
     disable_turn_conflicts: bool,
+    retry_granularity: Duration,
     // (x, y) means x is blocked by y. It's a many-to-many relationship. TODO Better data
     // structure.
     blocked_by: BTreeSet<(CarID, CarID)>,
+    // Rail crossings closed for a passing train, until some time
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    rail_closed_until: BTreeMap<IntersectionID, Time>,
+    // Movable bridges opened for boats, until some time
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    bridge_open_until: BTreeMap<RoadID, Time>,
+    // When each request first got stuck waiting for a bridge to close
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    waiting_for_bridge: BTreeMap<Request, Time>,
+    // Lanes whose queue has backed up into the upstream intersection, since when, and the length
+    // of the vehicle that couldn't fit
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    spillback_since: BTreeMap<LaneID, (Time, Distance)>,
     events: Vec<Event>,
 
     // Count how many calls to maybe_start_turn there are aside from the initial call. Break down
@@ -63,6 +93,19 @@ struct State {
     // complete the entire sequence. This is especially necessary since groups of traffic signals
     // are not yet configured as one.
     reserved: BTreeSet<Request>,
+    // When the last vehicle from each incoming lane started a turn. Only tracked when the
+    // intersection has a saturation headway.
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    last_vehicle_from: BTreeMap<LaneID, Time>,
+    // When the last pedestrian who had been waiting started each crosswalk
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    last_ped_on: BTreeMap<TurnID, Time>,
 
     signal: Option<SignalState>,
 }
@@ -71,6 +114,9 @@ struct State {
 struct SignalState {
     current_stage: usize,
     stage_ends_at: Time,
+    /// The stage that ran before this one, which is the same as `current_stage` when an adaptive
+    /// stage repeats.
+    previous_stage: usize,
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Clone, Debug)]
@@ -89,7 +135,12 @@ impl IntersectionSimState {
             break_turn_conflict_cycles: opts.break_turn_conflict_cycles,
             handle_uber_turns: opts.handle_uber_turns,
             disable_turn_conflicts: opts.disable_turn_conflicts,
+            retry_granularity: opts.effective_intersection_retry_granularity(),
             blocked_by: BTreeSet::new(),
+            rail_closed_until: BTreeMap::new(),
+            bridge_open_until: BTreeMap::new(),
+            waiting_for_bridge: BTreeMap::new(),
+            spillback_since: BTreeMap::new(),
             events: Vec::new(),
 
             total_repeat_requests: 0,
@@ -106,6 +157,8 @@ impl IntersectionSimState {
                 accepted: BTreeSet::new(),
                 waiting: BTreeMap::new(),
                 reserved: BTreeSet::new(),
+                last_vehicle_from: BTreeMap::new(),
+                last_ped_on: BTreeMap::new(),
                 signal: None,
             };
             if i.is_traffic_signal() {
@@ -142,6 +195,7 @@ impl IntersectionSimState {
     pub fn cancel_request(&mut self, agent: AgentID, turn: TurnID) {
         let state = self.state.get_mut(&turn.parent).unwrap();
         state.waiting.remove(&Request { agent, turn });
+        self.waiting_for_bridge.remove(&Request { agent, turn });
         if self.break_turn_conflict_cycles {
             if let AgentID::Car(car) = agent {
                 retain_btreeset(&mut self.blocked_by, |(c1, c2)| *c1 != car && *c2 != car);
@@ -149,13 +203,23 @@ impl IntersectionSimState {
         }
     }
 
-    pub fn space_freed(
-        &mut self,
-        now: Time,
-        i: IntersectionID,
-        scheduler: &mut Scheduler,
-        map: &Map,
-    ) {
+    /// Some vehicle's reserved space in the queue is gone, so anybody waiting to enter it might
+    /// fit now.
+    pub fn space_freed(&mut self, now: Time, queue: &Queue, scheduler: &mut Scheduler, map: &Map) {
+        let i = match queue.id {
+            Traversable::Lane(l) => {
+                // The spillback is over as soon as the vehicle that couldn't fit would, even if
+                // it doesn't get to try the turn until later.
+                if let Some((since, length)) = self.spillback_since.get(&l).cloned() {
+                    if queue.room_for_length(length) {
+                        self.spillback_since.remove(&l);
+                        self.events.push(Event::SpillbackEnded(l, now - since));
+                    }
+                }
+                map.get_l(l).src_i
+            }
+            Traversable::Turn(t) => t.parent,
+        };
         self.wakeup_waiting(now, i, scheduler, map);
     }
 
@@ -171,6 +235,34 @@ impl IntersectionSimState {
         state.reserved.remove(&Request { agent, turn });
     }
 
+    /// Somebody gave up on their trip. Forget every request, accepted turn, and reservation they
+    /// had anywhere, and let anybody waiting on them try again.
+    pub fn agent_abandoned(
+        &mut self,
+        now: Time,
+        agent: AgentID,
+        scheduler: &mut Scheduler,
+        map: &Map,
+    ) {
+        let mut touched = Vec::new();
+        for state in self.state.values_mut() {
+            let before = state.accepted.len() + state.waiting.len() + state.reserved.len();
+            retain_btreeset(&mut state.accepted, |req| req.agent != agent);
+            retain_btreemap(&mut state.waiting, |req, _| req.agent != agent);
+            retain_btreeset(&mut state.reserved, |req| req.agent != agent);
+            if state.accepted.len() + state.waiting.len() + state.reserved.len() != before {
+                touched.push(state.id);
+            }
+        }
+        retain_btreemap(&mut self.waiting_for_bridge, |req, _| req.agent != agent);
+        if let AgentID::Car(car) = agent {
+            self.vehicle_gone(car);
+        }
+        for i in touched {
+            self.wakeup_waiting(now, i, scheduler, map);
+        }
+    }
+
     fn wakeup_waiting(&self, now: Time, i: IntersectionID, scheduler: &mut Scheduler, map: &Map) {
         let mut all: Vec<(Request, Time)> = self.state[&i]
             .waiting
@@ -233,12 +325,30 @@ impl IntersectionSimState {
         // deterministically) orders commands with the same time.
         for req in yielding {
             scheduler.update(
-                now + Duration::seconds(0.1),
+                now + self.retry_granularity,
                 Command::update_agent(req.agent),
             );
         }
     }
 
+    /// A train starts passing through every rail crossing. Nobody can start a turn there until it's
+    /// gone; anybody who tries in the meantime retries when the crossing opens.
+    pub fn close_rail_crossings(&mut self, until: Time, map: &Map) {
+        for i in map.all_intersections() {
+            if i.rail_crossing {
+                let closed = self.rail_closed_until.entry(i.id).or_insert(until);
+                *closed = (*closed).max(until);
+            }
+        }
+    }
+
+    /// A movable bridge opens. Nobody can start a turn onto the road until it closes again; anybody
+    /// who tries in the meantime retries then.
+    pub fn open_bridge(&mut self, r: RoadID, until: Time) {
+        let open = self.bridge_open_until.entry(r).or_insert(until);
+        *open = (*open).max(until);
+    }
+
     /// This is only triggered for traffic signals.
     pub fn update_intersection(
         &mut self,
@@ -254,6 +364,7 @@ impl IntersectionSimState {
         // Switch to a new stage?
         assert_eq!(now, signal_state.stage_ends_at);
         let old_stage = &signal.stages[signal_state.current_stage];
+        signal_state.previous_stage = signal_state.current_stage;
         match old_stage.phase_type {
             PhaseType::Fixed(_) => {
                 signal_state.current_stage += 1;
@@ -326,6 +437,52 @@ impl IntersectionSimState {
             self.total_repeat_requests += 1;
         }
 
+        // Even walking straight along the sidewalk crosses the tracks
+        if let Some(until) = self.rail_closed_until.get(&turn.parent) {
+            if now < *until {
+                scheduler.push(*until, Command::update_agent(agent));
+                if repeat_request {
+                    self.not_allowed_requests += 1;
+                }
+                return false;
+            }
+        }
+        // Anybody already on the bridge can finish crossing, but nobody else can get on
+        if let Some(until) = self.bridge_open_until.get(&map.get_l(turn.dst).parent) {
+            if now < *until {
+                scheduler.push(*until, Command::update_agent(agent));
+                self.waiting_for_bridge.entry(req.clone()).or_insert(now);
+                if repeat_request {
+                    self.not_allowed_requests += 1;
+                }
+                return false;
+            }
+        }
+        // Likewise, anybody already in a lane closed by a work zone can finish, but nobody else
+        // can enter it, unless they're stopping before the closed part
+        let dst = map.get_l(turn.dst);
+        let stop_dist = maybe_cars_and_queues
+            .as_ref()
+            .and_then(|(car, _, _)| {
+                let path = car.router.get_path();
+                if path.get_steps().len() == 3 {
+                    Some(path.end_dist())
+                } else {
+                    None
+                }
+            })
+            .unwrap_or_else(|| dst.length());
+        if let Some(until) = map
+            .get_r(dst.parent)
+            .lane_closed_before(dst, stop_dist, now)
+        {
+            scheduler.push(until, Command::update_agent(agent));
+            if repeat_request {
+                self.not_allowed_requests += 1;
+            }
+            return false;
+        }
+
         let shared_sidewalk_corner =
             map.get_t(req.turn).turn_type == TurnType::SharedSidewalkCorner;
 
@@ -372,6 +529,38 @@ impl IntersectionSimState {
             return false;
         }
 
+        // Vehicles from the same lane can't leave right on each other's bumpers.
+        let headway = map.get_i(turn.parent).capacity.saturation_headway;
+        if let AgentID::Car(_) = agent {
+            if let Some(last) = self.state[&turn.parent].last_vehicle_from.get(&turn.src) {
+                if now < *last + headway {
+                    scheduler.push(*last + headway, Command::update_agent(agent));
+                    if repeat_request {
+                        self.not_allowed_requests += 1;
+                    }
+                    return false;
+                }
+            }
+        }
+        // Pedestrians who bunched up waiting for the light cross as a platoon, one after another.
+        // The crosswalk stays occupied longer for a bigger group, holding up turning vehicles.
+        // Somebody who didn't have to wait just walks across.
+        let platoon = repeat_request
+            && matches!(agent, AgentID::Pedestrian(_))
+            && map.get_t(turn).turn_type == TurnType::Crosswalk;
+        if platoon {
+            if let Some(last) = self.state[&turn.parent].last_ped_on.get(&turn) {
+                if now < *last + CROSSWALK_PLATOON_HEADWAY {
+                    scheduler.push(
+                        *last + CROSSWALK_PLATOON_HEADWAY,
+                        Command::update_agent(agent),
+                    );
+                    self.not_allowed_requests += 1;
+                    return false;
+                }
+            }
+        }
+
         // Lock the entire uber-turn.
         if self.handle_uber_turns {
             if let Some(ut) = maybe_cars_and_queues
@@ -406,12 +595,24 @@ impl IntersectionSimState {
                 && (car.router.get_path().currently_inside_ut().is_some()
                     || car.router.get_path().about_to_start_ut().is_some());
             let queue = queues.get_mut(&Traversable::Lane(turn.dst)).unwrap();
-            if !queue.try_to_reserve_entry(
-                car,
-                !self.dont_block_the_box
-                    || allow_block_the_box(map.get_i(turn.parent))
-                    || inside_ut,
-            ) {
+            // Whether or not the vehicle is allowed to block the box, the queue reaching all the
+            // way back into the intersection is worth noting. It lasts until a vehicle can next
+            // fit.
+            if queue.room_for_car(car) {
+                if let Some((since, _)) = self.spillback_since.remove(&turn.dst) {
+                    self.events
+                        .push(Event::SpillbackEnded(turn.dst, now - since));
+                }
+            } else if let Entry::Vacant(entry) = self.spillback_since.entry(turn.dst) {
+                entry.insert((now, car.vehicle.length));
+                self.events.push(Event::SpillbackStarted(turn.dst));
+            }
+            let i = map.get_i(turn.parent);
+            let allow_blocking = match i.capacity.dont_block_the_box {
+                Some(enforced) => !enforced,
+                None => !self.dont_block_the_box || allow_block_the_box(i),
+            };
+            if !queue.try_to_reserve_entry(car, allow_blocking || inside_ut) {
                 if self.break_turn_conflict_cycles {
                     // TODO Should we run the detector here?
                     if let Some(c) = queue.laggy_head {
@@ -448,6 +649,10 @@ impl IntersectionSimState {
         // for stop signs too.
         let state = self.state.get_mut(&turn.parent).unwrap();
         let delay = now - state.waiting.remove(&req).unwrap();
+        if let Some(start) = self.waiting_for_bridge.remove(&req) {
+            self.events
+                .push(Event::BridgeOpeningDelay(turn, agent, now - start));
+        }
         // SharedSidewalkCorner are always no-conflict, immediate turns; they're not interesting.
         if !shared_sidewalk_corner {
             if let Some(ts) = map.maybe_get_traffic_signal(state.id) {
@@ -458,6 +663,19 @@ impl IntersectionSimState {
                 ));
             }
         }
+        match agent {
+            AgentID::Car(_) => {
+                if headway > Duration::ZERO {
+                    state.last_vehicle_from.insert(turn.src, now);
+                }
+            }
+            AgentID::Pedestrian(_) => {
+                if platoon {
+                    state.last_ped_on.insert(turn, now);
+                }
+            }
+            AgentID::BusPassenger(_, _) => {}
+        }
         state.accepted.insert(req);
         if self.break_turn_conflict_cycles {
             if let AgentID::Car(car) = agent {
@@ -573,6 +791,17 @@ impl IntersectionSimState {
             .collect()
     }
 
+    /// All lanes belonging to movable bridges that're currently open
+    pub fn open_bridge_lanes(&self, now: Time, map: &Map) -> BTreeSet<LaneID> {
+        let mut lanes = BTreeSet::new();
+        for (r, until) in &self.bridge_open_until {
+            if now < *until {
+                lanes.extend(map.get_r(*r).all_lanes());
+            }
+        }
+        lanes
+    }
+
     pub fn get_blocked_by(&self, a: AgentID) -> HashSet<AgentID> {
         let mut blocked_by = HashSet::new();
         if let AgentID::Car(c) = a {
@@ -604,6 +833,13 @@ impl IntersectionSimState {
         candidates
     }
 
+    pub fn current_spillbacks(&self) -> Vec<(LaneID, Time)> {
+        self.spillback_since
+            .iter()
+            .map(|(l, (t, _))| (*l, *t))
+            .collect()
+    }
+
     pub fn current_stage_and_remaining_time(
         &self,
         now: Time,
@@ -642,10 +878,10 @@ impl IntersectionSimState {
         ]
     }
 
-    pub fn populate_blocked_by(
+    pub fn populate_blocked_on(
         &self,
         now: Time,
-        graph: &mut BTreeMap<AgentID, (Duration, DelayCause)>,
+        graph: &mut BTreeMap<AgentID, (Duration, BlockedOn)>,
         map: &Map,
         cars: &FixedMap<CarID, Car>,
         queues: &HashMap<Traversable, Queue>,
@@ -657,17 +893,26 @@ impl IntersectionSimState {
                 // In the absence of other explanations, the agent must be pausing at a stop sign
                 // or before making an unprotected movement, aka, in the middle of
                 // WAIT_AT_STOP_SIGN or WAIT_BEFORE_YIELD_AT_TRAFFIC_SIGNAL.
-                let mut cause = DelayCause::Intersection(state.id);
-                if let Some(other) = state.accepted.iter().find(|other| {
+                let mut cause = BlockedOn::Yielding(state.id);
+                let mut red_light = false;
+                if let Some(ref signal_state) = state.signal {
+                    let signal = map.get_traffic_signal(state.id);
+                    red_light = signal.stages[signal_state.current_stage]
+                        .get_priority_of_turn(req.turn, signal)
+                        == TurnPriority::Banned;
+                }
+                if red_light {
+                    cause = BlockedOn::RedSignal(state.id);
+                } else if let Some(other) = state.accepted.iter().find(|other| {
                     turn.conflicts_with(map.get_t(other.turn)) || turn.id == other.turn
                 }) {
-                    cause = DelayCause::Agent(other.agent);
+                    cause = BlockedOn::TurnConflict(other.agent, other.turn);
                 } else if let AgentID::Car(car) = req.agent {
                     let queue = &queues[&Traversable::Lane(req.turn.dst)];
                     if !queue.room_for_car(cars.get(&car).unwrap()) {
                         // TODO Or it's reserved due to an uber turn or something
                         let blocker = queue.cars.back().cloned().or(queue.laggy_head).unwrap();
-                        cause = DelayCause::Agent(AgentID::Car(blocker));
+                        cause = BlockedOn::FullLane(req.turn.dst, blocker);
                     }
                 }
                 graph.insert(req.agent, (now - *started_at, cause));
@@ -743,6 +988,31 @@ impl IntersectionSimState {
             return false;
         }
 
+        // Nobody starts during the all-red clearance at the beginning of each stage, and vehicles
+        // take a little longer to get going. Movements that were already protected last stage
+        // never turned red, so they just keep going.
+        let still_protected = our_priority == TurnPriority::Protected
+            && signal
+                .stages
+                .get(signal_state.previous_stage)
+                .map(|prev| prev.get_priority_of_turn(req.turn, signal) == TurnPriority::Protected)
+                .unwrap_or(false);
+        if !still_protected {
+            let capacity = &map.get_i(req.turn.parent).capacity;
+            let mut go_at =
+                signal_state.stage_ends_at - full_stage_duration + capacity.all_red_clearance;
+            if let AgentID::Car(_) = req.agent {
+                go_at += capacity.startup_lost_time;
+            }
+            if now < go_at {
+                // Like below, we have "ownership" of scheduling for req.agent.
+                if let Some(s) = scheduler {
+                    s.push(go_at, Command::update_agent(req.agent));
+                }
+                return false;
+            }
+        }
+
         if our_priority == TurnPriority::Yield
             && now < our_time + WAIT_BEFORE_YIELD_AT_TRAFFIC_SIGNAL
         {
@@ -892,6 +1162,7 @@ impl SignalState {
         let mut state = SignalState {
             current_stage: 0,
             stage_ends_at: now,
+            previous_stage: 0,
         };
 
         let signal = map.get_traffic_signal(id);
@@ -909,6 +1180,11 @@ impl SignalState {
                 }
             } else {
                 state.stage_ends_at = now + dt - offset;
+                state.previous_stage = if state.current_stage == 0 {
+                    signal.stages.len() - 1
+                } else {
+                    state.current_stage - 1
+                };
                 break;
             }
         }
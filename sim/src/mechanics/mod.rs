@@ -1,5 +1,5 @@
 pub(crate) use self::driving::DrivingSimState;
-pub(crate) use self::intersection::IntersectionSimState;
+pub(crate) use self::intersection::{IntersectionSimState, WAIT_AT_STOP_SIGN};
 pub(crate) use self::parking::{ParkingSim, ParkingSimState};
 pub(crate) use self::queue::Queue;
 pub(crate) use self::walking::WalkingSimState;
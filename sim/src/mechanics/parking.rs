@@ -7,13 +7,22 @@ use abstutil::{
     deserialize_btreemap, deserialize_multimap, retain_btreemap, serialize_btreemap,
     serialize_multimap, MultiMap, Timer,
 };
-use geom::{Distance, PolyLine, Pt2D};
+use geom::{Distance, Duration, FindContained, PolyLine, Pt2D, Speed};
 use map_model::{
     BuildingID, Lane, LaneID, LaneType, Map, OffstreetParking, ParkingLotID, PathConstraints,
     PathStep, Position, Traversable, TurnID,
 };
 
-use crate::{CarID, CarStatus, DrawCarInput, Event, ParkedCar, ParkingSpot, PersonID, Vehicle};
+use crate::{
+    CarID, CarStatus, DrawCarInput, Event, ParkedCar, ParkingPrices, ParkingSpot, PersonID, Vehicle,
+};
+
+// Just used to estimate the cost of a parking spot
+const SEARCHING_SPEED: Speed = Speed::const_meters_per_second(5.0);
+const WALKING_SPEED: Speed = Speed::const_meters_per_second(1.34);
+// When parking has a price, how much farther than the first free spot drivers will consider
+// driving to find a cheaper one
+const MAX_EXTRA_SEARCH: Distance = Distance::const_meters(1000.0);
 
 /// Manages the state of parked cars. There are two implementations:
 /// - NormalParkingSimState allows only one vehicle per ParkingSpot defined in the map
@@ -69,6 +78,20 @@ pub trait ParkingSim {
     fn collect_events(&mut self) -> Vec<Event>;
     fn all_parked_car_positions(&self, map: &Map) -> Vec<(Position, PersonID)>;
     fn bldg_to_parked_cars(&self, b: BuildingID) -> Vec<CarID>;
+
+    fn set_prices(&mut self, prices: &ParkingPrices, map: &Map);
+    /// If parking is free everywhere, drivers just take the first spot they find. Otherwise, they
+    /// choose the spot with the lowest choice_cost.
+    fn is_priced(&self) -> bool;
+    /// How bad a driver thinks it'd be to park in this spot, after driving search_dist farther,
+    /// then walking to the target. The price is converted to time using the value of time.
+    fn choice_cost(
+        &self,
+        spot: ParkingSpot,
+        search_dist: Distance,
+        target: BuildingID,
+        map: &Map,
+    ) -> Duration;
 }
 
 #[enum_dispatch]
@@ -141,9 +164,19 @@ pub struct NormalParkingSimState {
     )]
     driving_to_lots: MultiMap<LaneID, ParkingLotID>,
 
+    // None means parking is free everywhere
+    prices: Option<SpotPrices>,
+
     events: Vec<Event>,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+struct SpotPrices {
+    config: ParkingPrices,
+    // Cents to park once along each parking lane that isn't free
+    onstreet: BTreeMap<LaneID, usize>,
+}
+
 impl NormalParkingSimState {
     fn new(map: &Map, timer: &mut Timer) -> NormalParkingSimState {
         let mut sim = NormalParkingSimState {
@@ -158,6 +191,8 @@ impl NormalParkingSimState {
             num_spots_per_lot: BTreeMap::new(),
             driving_to_lots: MultiMap::new(),
 
+            prices: None,
+
             events: Vec::new(),
         };
         for l in map.all_lanes() {
@@ -203,6 +238,10 @@ impl ParkingSim for NormalParkingSimState {
         self.driving_to_offstreet = new.driving_to_offstreet;
         self.num_spots_per_lot = new.num_spots_per_lot;
         self.driving_to_lots = new.driving_to_lots;
+        // Parking lanes might've changed
+        if let Some(prices) = self.prices.take() {
+            self.set_prices(&prices.config, map);
+        }
 
         // For every spot filled or reserved before, make sure that same spot still exists. If not,
         // evict that car.
@@ -543,33 +582,45 @@ impl ParkingSim for NormalParkingSimState {
         // deterministic.
         let mut queue: BinaryHeap<(Distance, LaneID)> = BinaryHeap::new();
         queue.push((Distance::ZERO, start));
+        // When parking is priced, keep looking a bit past the first free spot for a cheaper one.
+        // (cost, lane, spot, position)
+        let mut best: Option<(Duration, LaneID, ParkingSpot, Position)> = None;
+        let mut search_limit: Option<Distance> = None;
 
         while !queue.is_empty() {
             let (dist_so_far, current) = queue.pop().unwrap();
+            if let Some(limit) = search_limit {
+                // Every spot from here on costs at least this much to reach
+                if -dist_so_far > limit
+                    || -dist_so_far / SEARCHING_SPEED >= best.as_ref().unwrap().0
+                {
+                    break;
+                }
+            }
             // If the current lane has a spot open, we wouldn't be asking. This can happen if a spot
             // opens up on the 'start' lane, but behind the car.
             if current != start {
-                // Pick the closest to the start of the lane, since that's closest to where we came
-                // from
-                if let Some((spot, pos)) = self
-                    .get_all_free_spots(Position::start(current), vehicle, target, map)
+                let candidates =
+                    self.get_all_free_spots(Position::start(current), vehicle, target, map);
+                if self.prices.is_some() {
+                    for (spot, pos) in candidates {
+                        let cost =
+                            self.choice_cost(spot, -dist_so_far + pos.dist_along(), target, map);
+                        if best.as_ref().map(|(c, _, _, _)| cost < *c).unwrap_or(true) {
+                            best = Some((cost, current, spot, pos));
+                        }
+                    }
+                    if best.is_some() && search_limit.is_none() {
+                        search_limit = Some(-dist_so_far + MAX_EXTRA_SEARCH);
+                    }
+                } else if let Some((spot, pos)) = candidates
                     .into_iter()
+                    // Pick the closest to the start of the lane, since that's closest to where we
+                    // came from
                     .min_by_key(|(_, pos)| pos.dist_along())
                 {
-                    let mut steps = vec![PathStep::Lane(current)];
-                    let mut current = current;
-                    loop {
-                        if current == start {
-                            // Don't include PathStep::Lane(start)
-                            steps.pop();
-                            steps.reverse();
-                            return Some((steps, spot, pos));
-                        }
-                        let turn = backrefs[&current];
-                        steps.push(PathStep::Turn(turn));
-                        steps.push(PathStep::Lane(turn.src));
-                        current = turn.src;
-                    }
+                    best = Some((Duration::ZERO, current, spot, pos));
+                    break;
                 }
             }
             for turn in map.get_turns_for(current, PathConstraints::Car) {
@@ -582,7 +633,20 @@ impl ParkingSim for NormalParkingSimState {
             }
         }
 
-        None
+        let (_, mut current, spot, pos) = best?;
+        let mut steps = vec![PathStep::Lane(current)];
+        loop {
+            if current == start {
+                // Don't include PathStep::Lane(start)
+                steps.pop();
+                steps.reverse();
+                return Some((steps, spot, pos));
+            }
+            let turn = backrefs[&current];
+            steps.push(PathStep::Turn(turn));
+            steps.push(PathStep::Lane(turn.src));
+            current = turn.src;
+        }
     }
 
     fn collect_events(&mut self) -> Vec<Event> {
@@ -611,6 +675,62 @@ impl ParkingSim for NormalParkingSimState {
         }
         cars
     }
+
+    fn set_prices(&mut self, prices: &ParkingPrices, map: &Map) {
+        if prices.is_free() {
+            self.prices = None;
+            return;
+        }
+        let mut lanes = FindContained::new(map.get_bounds());
+        for l in self.onstreet_lanes.keys() {
+            lanes.add(*l, map.get_l(*l).lane_center_pts.middle());
+        }
+        let mut onstreet = BTreeMap::new();
+        // If zones overlap, the first one wins, even if it's free
+        for zone in &prices.zones {
+            for l in lanes.contained_in(&zone.polygon) {
+                onstreet.entry(l).or_insert(zone.onstreet_cents);
+            }
+        }
+        retain_btreemap(&mut onstreet, |_, cents| *cents > 0);
+        self.prices = Some(SpotPrices {
+            config: prices.clone(),
+            onstreet,
+        });
+    }
+
+    fn is_priced(&self) -> bool {
+        self.prices.is_some()
+    }
+
+    fn choice_cost(
+        &self,
+        spot: ParkingSpot,
+        search_dist: Distance,
+        target: BuildingID,
+        map: &Map,
+    ) -> Duration {
+        let cents = match (&self.prices, spot) {
+            (None, _) | (_, ParkingSpot::Offstreet(_, _)) => 0,
+            (Some(prices), ParkingSpot::Onstreet(l, _)) => {
+                prices.onstreet.get(&l).cloned().unwrap_or(0)
+            }
+            (Some(prices), ParkingSpot::Lot(_, _)) => prices.config.lot_cents,
+        };
+        let value_of_time = self
+            .prices
+            .as_ref()
+            .map(|p| p.config.value_of_time_cents_per_hour.max(1))
+            .unwrap_or(1);
+        let walk_dist = self
+            .spot_to_sidewalk_pos(spot, map)
+            .pt(map)
+            .dist_to(map.get_b(target).sidewalk_pos.pt(map));
+
+        search_dist / SEARCHING_SPEED
+            + walk_dist / WALKING_SPEED
+            + Duration::seconds(3600.0 * (cents as f64) / (value_of_time as f64))
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -1001,4 +1121,15 @@ impl ParkingSim for InfiniteParkingSimState {
         }
         cars
     }
+
+    // Every building has room for everybody, so there's nothing to choose between
+    fn set_prices(&mut self, _: &ParkingPrices, _: &Map) {}
+
+    fn is_priced(&self) -> bool {
+        false
+    }
+
+    fn choice_cost(&self, _: ParkingSpot, _: Distance, _: BuildingID, _: &Map) -> Duration {
+        Duration::ZERO
+    }
 }
@@ -6,7 +6,7 @@ use abstutil::FixedMap;
 use geom::{Distance, Time};
 use map_model::{Map, Traversable};
 
-use crate::mechanics::car::{Car, CarState};
+use crate::mechanics::car::{crossing_front, Car, CarState};
 use crate::{CarID, VehicleType, FOLLOWING_DISTANCE};
 
 /// A Queue of vehicles on a single lane or turn. No over-taking or lane-changing. This is where
@@ -174,10 +174,8 @@ impl Queue {
                     assert_eq!(bound, self.geom_len);
                     self.geom_len
                 }
-                CarState::Crossing(ref time_int, ref dist_int) => {
-                    // TODO Why percent_clamp_end? We process car updates in any order, so we might
-                    // calculate this before moving this car from Crossing to another state.
-                    dist_int.lerp(time_int.percent_clamp_end(now)).min(bound)
+                CarState::Crossing(ref time_int, ref dist_int, ref profile) => {
+                    crossing_front(time_int, dist_int, profile, now).min(bound)
                 }
                 CarState::Unparking(front, _, _) => front,
                 CarState::Parking(front, _, _) => front,
@@ -252,8 +250,12 @@ impl Queue {
     }
 
     pub fn room_for_car(&self, car: &Car) -> bool {
+        self.room_for_length(car.vehicle.length)
+    }
+
+    pub fn room_for_length(&self, length: Distance) -> bool {
         self.reserved_length == Distance::ZERO
-            || self.reserved_length + car.vehicle.length + FOLLOWING_DISTANCE < self.geom_len
+            || self.reserved_length + length + FOLLOWING_DISTANCE < self.geom_len
     }
 
     pub fn free_reserved_space(&mut self, car: &Car) {
@@ -310,7 +312,7 @@ fn dump_cars(
         let car = &cars[id];
         println!("- {} @ {} (length {})", id, dist, car.vehicle.length);
         match car.state {
-            CarState::Crossing(ref time_int, ref dist_int) => {
+            CarState::Crossing(ref time_int, ref dist_int, _) => {
                 println!(
                     "  Going {} .. {} during {} .. {}",
                     dist_int.start, dist_int.end, time_int.start, time_int.end
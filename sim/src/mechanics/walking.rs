@@ -13,8 +13,8 @@ use crate::sim::Ctx;
 use crate::{
     AgentID, AgentProperties, Command, CommutersVehiclesCounts, CreatePedestrian, DistanceInterval,
     DrawPedCrowdInput, DrawPedestrianInput, Event, IntersectionSimState, ParkedCar, ParkingSpot,
-    PedCrowdLocation, PedestrianID, PersonID, Scheduler, SidewalkPOI, SidewalkSpot, TimeInterval,
-    TransitSimState, TripID, TripManager, UnzoomedAgent,
+    PedCrowdLocation, PedestrianID, PersonID, Scheduler, SidewalkPOI, SidewalkSpot, SimOptions,
+    TimeInterval, TransitSimState, TripID, TripManager, UnzoomedAgent,
 };
 
 const TIME_TO_START_BIKING: Duration = Duration::const_seconds(30.0);
@@ -32,14 +32,16 @@ pub(crate) struct WalkingSimState {
     )]
     peds_per_traversable: MultiMap<Traversable, PedestrianID>,
     events: Vec<Event>,
+    shoulder_walking_speed: f64,
 }
 
 impl WalkingSimState {
-    pub fn new() -> WalkingSimState {
+    pub fn new(opts: &SimOptions) -> WalkingSimState {
         WalkingSimState {
             peds: FixedMap::new(),
             peds_per_traversable: MultiMap::new(),
             events: Vec::new(),
+            shoulder_walking_speed: opts.shoulder_walking_speed,
         }
     }
 
@@ -68,6 +70,7 @@ impl WalkingSimState {
                 ),
             ),
             speed: params.speed,
+            shoulder_speed: params.speed * self.shoulder_walking_speed,
             total_blocked_time: Duration::ZERO,
             started_at: now,
             path: params.path,
@@ -336,6 +339,15 @@ impl WalkingSimState {
         };
     }
 
+    /// The pedestrian stops waiting for a bus, because a live edit means the route won't take them
+    /// where they're going anymore.
+    pub fn ped_stopped_waiting_for_bus(&mut self, id: PedestrianID) {
+        let ped = self.peds.remove(&id).unwrap();
+        assert!(matches!(ped.state, PedState::WaitingForBus(_, _)));
+        self.peds_per_traversable
+            .remove(ped.path.current_step().as_traversable(), id);
+    }
+
     /// Abruptly remove a pedestrian from the simulation. They may be in any arbitrary state, like
     /// in the middle of a turn.
     pub fn delete_ped(&mut self, id: PedestrianID, ctx: &mut Ctx) {
@@ -353,6 +365,17 @@ impl WalkingSimState {
         }
     }
 
+    /// Remove a pedestrian who gave up on their trip, releasing anything they held at
+    /// intersections and waking up anybody waiting on them.
+    pub fn abandon_ped(&mut self, id: PedestrianID, now: Time, ctx: &mut Ctx) {
+        let ped = self.peds.remove(&id).unwrap();
+        self.peds_per_traversable
+            .remove(ped.path.current_step().as_traversable(), id);
+        ctx.scheduler.cancel(Command::UpdatePed(id));
+        ctx.intersections
+            .agent_abandoned(now, AgentID::Pedestrian(id), ctx.scheduler, ctx.map);
+    }
+
     pub fn debug_ped(&self, id: PedestrianID) {
         if let Some(ped) = self.peds.get(&id) {
             println!("{}", abstutil::to_json(ped));
@@ -550,6 +573,22 @@ impl WalkingSimState {
         }
     }
 
+    /// Pedestrians who've been waiting to cross or for a bus for at least this long
+    pub fn find_stuck_peds(
+        &self,
+        now: Time,
+        threshold: Duration,
+    ) -> Vec<(PedestrianID, TripID, Duration)> {
+        let mut stuck = Vec::new();
+        for p in self.peds.values() {
+            let delay = p.state.time_spent_waiting(now);
+            if delay > Duration::ZERO && delay >= threshold {
+                stuck.push((p.id, p.trip, delay));
+            }
+        }
+        stuck
+    }
+
     pub fn populate_commuter_counts(&self, cnts: &mut CommutersVehiclesCounts) {
         for p in self.peds.values() {
             match p.goal.connection {
@@ -586,6 +625,8 @@ struct Pedestrian {
     id: PedestrianID,
     state: PedState,
     speed: Speed,
+    // Used instead of speed while walking along a shoulder
+    shoulder_speed: Speed,
     total_blocked_time: Duration,
     // TODO organize analytics better.
     started_at: Time,
@@ -609,8 +650,14 @@ impl Pedestrian {
                 PathStep::Turn(t) => map.get_t(t).geom.length(),
             }
         };
+        let speed = match self.path.current_step() {
+            PathStep::Lane(l) | PathStep::ContraflowLane(l) if map.get_l(l).is_shoulder() => {
+                self.shoulder_speed
+            }
+            _ => self.speed,
+        };
         let dist_int = DistanceInterval::new_walking(start_dist, end_dist);
-        let time_int = TimeInterval::new(start_time, start_time + dist_int.length() / self.speed);
+        let time_int = TimeInterval::new(start_time, start_time + dist_int.length() / speed);
         PedState::Crossing(dist_int, time_int)
     }
 
@@ -732,7 +779,10 @@ impl Pedestrian {
                 PedState::WaitingToTurn(_, _) => Some(self.path.next_step().as_turn()),
                 _ => None,
             },
-            preparing_bike: matches!(self.state, PedState::StartingToBike(_, _, _) | PedState::FinishingBiking(_, _, _)),
+            preparing_bike: matches!(
+                self.state,
+                PedState::StartingToBike(_, _, _) | PedState::FinishingBiking(_, _, _)
+            ),
             waiting_for_bus: matches!(self.state, PedState::WaitingForBus(_, _)),
             on,
         }
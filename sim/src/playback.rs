@@ -0,0 +1,146 @@
+use std::error::Error;
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use abstutil::MapName;
+use geom::{Angle, Distance, Duration, Pt2D, Time};
+use map_model::Map;
+
+use crate::{AgentID, CarStatus, EventLog, Sim};
+
+/// Where every moving agent was at regular intervals during a run, and optionally every event.
+/// This is enough to animate the run later without loading or stepping a simulation, which is much
+/// cheaper for showing big scenarios on slow machines. Parked cars aren't included.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Playback {
+    pub map_name: MapName,
+    /// How much simulated time passes between frames
+    pub frequency: Duration,
+    pub frames: Vec<PlaybackFrame>,
+    /// Only present if the simulation was also recording its event log.
+    pub events: Option<EventLog>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PlaybackFrame {
+    pub time: Time,
+    /// Sorted by ID
+    pub agents: Vec<PlaybackAgent>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PlaybackAgent {
+    pub id: AgentID,
+    /// The front of a vehicle, or the center of a pedestrian
+    pub pos: Pt2D,
+    pub facing: Angle,
+    /// Zero for pedestrians
+    pub length: Distance,
+    pub waiting_for_turn: bool,
+}
+
+impl Playback {
+    pub(crate) fn new(map: &Map, frequency: Duration) -> Playback {
+        Playback {
+            map_name: map.get_name().clone(),
+            frequency,
+            frames: Vec::new(),
+            events: None,
+        }
+    }
+
+    pub(crate) fn capture(&mut self, sim: &Sim, map: &Map) {
+        let mut agents = Vec::new();
+        for car in sim.get_all_draw_cars(map) {
+            if car.status == CarStatus::Parked {
+                continue;
+            }
+            let front = car.body.last_line();
+            agents.push(PlaybackAgent {
+                id: AgentID::Car(car.id),
+                pos: front.pt2(),
+                facing: front.angle(),
+                length: car.body.length(),
+                waiting_for_turn: car.waiting_for_turn.is_some(),
+            });
+        }
+        for ped in sim.get_all_draw_peds(map) {
+            agents.push(PlaybackAgent {
+                id: AgentID::Pedestrian(ped.id),
+                pos: ped.pos,
+                facing: ped.facing,
+                length: Distance::ZERO,
+                waiting_for_turn: ped.waiting_for_turn.is_some(),
+            });
+        }
+        agents.sort_by_key(|a| a.id);
+        self.frames.push(PlaybackFrame {
+            time: sim.time(),
+            agents,
+        });
+    }
+
+    /// The time of the first and last frame
+    pub fn time_range(&self) -> Option<(Time, Time)> {
+        Some((self.frames.first()?.time, self.frames.last()?.time))
+    }
+
+    /// Where every agent was at some time, smoothly interpolating between frames. Agents only
+    /// present in one of the surrounding frames are shown where they were in the earlier one.
+    pub fn agents_at(&self, time: Time) -> Vec<PlaybackAgent> {
+        let idx = match self
+            .frames
+            .binary_search_by(|f| f.time.partial_cmp(&time).unwrap())
+        {
+            Ok(idx) => {
+                return self.frames[idx].agents.clone();
+            }
+            Err(0) => {
+                return Vec::new();
+            }
+            Err(idx) => idx - 1,
+        };
+        let before = &self.frames[idx];
+        let after = if let Some(f) = self.frames.get(idx + 1) {
+            f
+        } else {
+            return before.agents.clone();
+        };
+        let pct = (time - before.time) / (after.time - before.time);
+
+        // Both lists are sorted, so walk through them together
+        let mut result = Vec::new();
+        let mut next = after.agents.iter().peekable();
+        for agent in &before.agents {
+            while next.peek().map(|a| a.id < agent.id).unwrap_or(false) {
+                next.next();
+            }
+            let mut agent = agent.clone();
+            if let Some(later) = next.peek().filter(|a| a.id == agent.id) {
+                agent.pos = Pt2D::new(
+                    agent.pos.x() + pct * (later.pos.x() - agent.pos.x()),
+                    agent.pos.y() + pct * (later.pos.y() - agent.pos.y()),
+                );
+            }
+            result.push(agent);
+        }
+        result
+    }
+
+    pub fn save(&self, path: String) -> Result<(), Box<dyn Error>> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        encoder.write_all(&abstutil::to_binary(self))?;
+        let compressed = encoder.finish()?;
+        abstutil::write_binary(path, &compressed);
+        Ok(())
+    }
+
+    pub fn load(path: String, timer: &mut abstutil::Timer) -> Result<Playback, Box<dyn Error>> {
+        let compressed: Vec<u8> = abstutil::maybe_read_binary(path, timer)?;
+        let mut decoder = flate2::read::GzDecoder::new(&compressed[..]);
+        let mut raw = Vec::new();
+        decoder.read_to_end(&mut raw)?;
+        abstutil::from_binary(&raw)
+    }
+}
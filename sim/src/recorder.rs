@@ -1,11 +1,12 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 use geom::Time;
 use map_model::{IntersectionID, Map, PathStep, Position, Traversable};
 
 use crate::{
-    AgentID, DrivingSimState, Event, IndividTrip, PersonSpec, Scenario, TripEndpoint, TripID,
-    TripManager, TripMode, TripPurpose, VehicleType,
+    AgentID, BridgeOpenings, DrivingSimState, Event, IndividTrip, ParkingPrices, PersonSpec,
+    Scenario, TrainSchedule, TransitFares, TripEndpoint, TripID, TripManager, TripMode,
+    TripPurpose, VehicleType,
 };
 
 /// Records trips beginning and ending at a specified set of intersections. This can be used to
@@ -92,6 +93,12 @@ impl TrafficRecorder {
             map_name: map.get_name().clone(),
             people,
             only_seed_buses: None,
+            parking_prices: ParkingPrices::free(),
+            transit_fares: TransitFares::free(),
+            train_schedule: TrainSchedule::none(),
+            bridge_openings: BridgeOpenings::none(),
+            abandon_trips_after: None,
+            idle_parked_cars: BTreeMap::new(),
         }
         .save();
     }
@@ -1,11 +1,11 @@
 //! For vehicles only, not pedestrians. Follows a Path from map_model, but can opportunistically
 //! lane-change to avoid a slow lane, can can handle re-planning to look for available parking.
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
 use serde::{Deserialize, Serialize};
 
-use geom::Distance;
+use geom::{Distance, Time};
 use map_model::{
     BuildingID, IntersectionID, LaneID, Map, Path, PathConstraints, PathRequest, PathStep,
     Position, Traversable, TurnID,
@@ -23,6 +23,9 @@ pub(crate) struct Router {
     path: Path,
     goal: Goal,
     owner: CarID,
+    /// A work zone closed part of the path and there was no other way, so don't look again until
+    /// then
+    no_detour_until: Option<Time>,
 }
 
 #[derive(Debug)]
@@ -43,7 +46,8 @@ enum Goal {
         spot: Option<(ParkingSpot, Distance)>,
         /// No parking available at all!
         stuck_end_dist: Option<Distance>,
-        started_looking: bool,
+        /// When the car first had to pick a spot
+        started_looking: Option<Time>,
     },
     EndAtBorder {
         end_dist: Distance,
@@ -68,6 +72,7 @@ impl Router {
             path,
             goal: Goal::EndAtBorder { end_dist, i },
             owner,
+            no_detour_until: None,
         }
     }
     pub fn vanish_bus(owner: CarID, l: LaneID, map: &Map) -> Router {
@@ -79,6 +84,7 @@ impl Router {
                 i: lane.dst_i,
             },
             owner,
+            no_detour_until: None,
         }
     }
 
@@ -89,9 +95,10 @@ impl Router {
                 target: bldg,
                 spot: None,
                 stuck_end_dist: None,
-                started_looking: false,
+                started_looking: None,
             },
             owner,
+            no_detour_until: None,
         }
     }
 
@@ -100,6 +107,7 @@ impl Router {
             goal: Goal::BikeThenStop { goal },
             path,
             owner,
+            no_detour_until: None,
         }
     }
 
@@ -108,6 +116,7 @@ impl Router {
             path,
             goal: Goal::FollowBusRoute { end_dist },
             owner,
+            no_detour_until: None,
         }
     }
 
@@ -149,6 +158,7 @@ impl Router {
     /// Returns the step just finished
     pub fn advance(
         &mut self,
+        now: Time,
         vehicle: &Vehicle,
         parking: &ParkingSimState,
         map: &Map,
@@ -159,6 +169,7 @@ impl Router {
         if self.last_step() {
             // Do this to trigger the side-effect of looking for parking.
             self.maybe_handle_end(
+                now,
                 Distance::ZERO,
                 vehicle,
                 parking,
@@ -184,8 +195,10 @@ impl Router {
 
     /// Called when the car is Queued at the last step, or when they initially advance to the last
     /// step.
+    #[allow(clippy::too_many_arguments)]
     pub fn maybe_handle_end(
         &mut self,
+        now: Time,
         front: Distance,
         vehicle: &Vehicle,
         parking: &ParkingSimState,
@@ -221,7 +234,9 @@ impl Router {
                     None => true,
                 };
                 if need_new_spot {
-                    *started_looking = true;
+                    if started_looking.is_none() {
+                        *started_looking = Some(now);
+                    }
                     let current_lane = self.path.current_step().as_lane();
                     let candidates = parking.get_all_free_spots(
                         Position::new(current_lane, front),
@@ -229,26 +244,57 @@ impl Router {
                         target,
                         map,
                     );
-                    let best =
-                        if let Some((driving_pos, _)) = map.get_b(target).driving_connection(map) {
-                            if driving_pos.lane() == current_lane {
-                                let target_dist = driving_pos.dist_along();
-                                // Closest to the building
-                                candidates
-                                    .into_iter()
-                                    .min_by_key(|(_, pos)| (pos.dist_along() - target_dist).abs())
-                            } else {
-                                // Closest to the road endpoint, I guess
-                                candidates
-                                    .into_iter()
-                                    .min_by_key(|(_, pos)| pos.dist_along())
+                    // When parking is priced, compare the best spot here with the best one
+                    // elsewhere
+                    let mut elsewhere = None;
+                    let best = if parking.is_priced() {
+                        let here = candidates
+                            .into_iter()
+                            .map(|(spot, pos)| {
+                                let cost = parking.choice_cost(
+                                    spot,
+                                    pos.dist_along() - front,
+                                    target,
+                                    map,
+                                );
+                                (cost, spot, pos)
+                            })
+                            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+                        elsewhere =
+                            parking.path_to_free_parking_spot(current_lane, vehicle, target, map);
+                        let elsewhere_cost = elsewhere.as_ref().map(|(steps, spot, pos)| {
+                            let mut search_dist = map.get_l(current_lane).length() - front;
+                            // The last step is the lane with the spot
+                            for step in &steps[..steps.len() - 1] {
+                                search_dist += step.as_traversable().length(map);
                             }
+                            search_dist += pos.dist_along();
+                            parking.choice_cost(*spot, search_dist, target, map)
+                        });
+                        match (here, elsewhere_cost) {
+                            (Some((cost, _, _)), Some(other)) if other < cost => None,
+                            (here, _) => here.map(|(_, spot, pos)| (spot, pos)),
+                        }
+                    } else if let Some((driving_pos, _)) = map.get_b(target).driving_connection(map)
+                    {
+                        if driving_pos.lane() == current_lane {
+                            let target_dist = driving_pos.dist_along();
+                            // Closest to the building
+                            candidates
+                                .into_iter()
+                                .min_by_key(|(_, pos)| (pos.dist_along() - target_dist).abs())
                         } else {
                             // Closest to the road endpoint, I guess
                             candidates
                                 .into_iter()
                                 .min_by_key(|(_, pos)| pos.dist_along())
-                        };
+                        }
+                    } else {
+                        // Closest to the road endpoint, I guess
+                        candidates
+                            .into_iter()
+                            .min_by_key(|(_, pos)| pos.dist_along())
+                    };
                     if let Some((new_spot, new_pos)) = best {
                         if let Some((t, p)) = trip_and_person {
                             events.push(Event::TripPhaseStarting(
@@ -266,9 +312,16 @@ impl Router {
                         assert!(new_pos.dist_along() >= front);
                         *spot = Some((new_spot, new_pos.dist_along()));
                     } else {
-                        if let Some((new_path_steps, new_spot, new_pos)) =
-                            parking.path_to_free_parking_spot(current_lane, vehicle, target, map)
-                        {
+                        // When parking is priced, this search already happened
+                        if !parking.is_priced() {
+                            elsewhere = parking.path_to_free_parking_spot(
+                                current_lane,
+                                vehicle,
+                                target,
+                                map,
+                            );
+                        }
+                        if let Some((new_path_steps, new_spot, new_pos)) = elsewhere {
                             assert!(!new_path_steps.is_empty());
                             for step in new_path_steps {
                                 self.path.add(step, map);
@@ -328,8 +381,47 @@ impl Router {
         }
     }
 
+    /// Work zones may have closed lanes along the rest of the path since it was chosen. If so,
+    /// looks for another way, starting from the end of the current lane. Only cars do this;
+    /// everybody else, or a car with no other way, waits for the work zone to end.
+    pub fn avoid_work_zones(&mut self, now: Time, map: &Map, events: &mut Vec<Event>) {
+        if self.owner.1 != VehicleType::Car || self.path.currently_inside_ut().is_some() {
+            return;
+        }
+        if self.no_detour_until.map(|t| now < t).unwrap_or(false) {
+            return;
+        }
+        let current = match self.head() {
+            Traversable::Lane(l) => l,
+            Traversable::Turn(_) => {
+                return;
+            }
+        };
+        let until = match self.path.closed_by_work_zones(now, map) {
+            Some(t) => t,
+            None => {
+                return;
+            }
+        };
+        let req = PathRequest {
+            start: Position::end(current, map),
+            end: Position::new(self.path.last_step().as_lane(), self.path.end_dist()),
+            constraints: PathConstraints::Car,
+        };
+        match map.pathfind_avoiding_work_zones(req, BTreeSet::new(), now) {
+            Some(path) => {
+                self.path = path;
+                events.push(Event::PathAmended(self.path.clone()));
+            }
+            None => {
+                self.no_detour_until = Some(until);
+            }
+        }
+    }
+
     pub fn opportunistically_lanechange(
         &mut self,
+        now: Time,
         queues: &HashMap<Traversable, Queue>,
         map: &Map,
         handle_uber_turns: bool,
@@ -359,10 +451,15 @@ impl Router {
         // Look for other candidates, and assign a cost to each.
         let constraints = self.owner.1.to_constraints();
         let dir = parent.dir(orig_target_lane);
-        let (_, turn1, best_lane, turn2) = parent
+        // Work zones close lanes, so this is where drivers merge away from them
+        let (_, turn1, best_lane, turn2) = match parent
             .lanes_ltr()
             .into_iter()
-            .filter(|(l, d, _)| dir == *d && constraints.can_use(map.get_l(*l), map))
+            .filter(|(l, d, _)| {
+                dir == *d
+                    && constraints.can_use(map.get_l(*l), map)
+                    && parent.lane_closed_until(*l, now).is_none()
+            })
             .filter_map(|(l, _, _)| {
                 let t1 = TurnID {
                     parent: current_turn.parent,
@@ -412,7 +509,13 @@ impl Router {
                 (cost, turn1, l, turn2)
             })
             .min_by_key(|(cost, _, _, _)| *cost)
-            .unwrap();
+        {
+            Some(x) => x,
+            // Every lane is closed, so wait for the work zone to end
+            None => {
+                return;
+            }
+        };
         // TODO Only switch if the target queue is some amount better; don't oscillate
         // unnecessarily.
         if best_lane == orig_target_lane {
@@ -432,11 +535,24 @@ impl Router {
         match self.goal {
             Goal::ParkNearBuilding {
                 started_looking, ..
-            } => started_looking,
+            } => started_looking.is_some(),
             _ => false,
         }
     }
 
+    /// If the car is looking for parking, the building it's trying to park near and when it
+    /// started looking.
+    pub fn parking_search(&self) -> Option<(BuildingID, Time)> {
+        match self.goal {
+            Goal::ParkNearBuilding {
+                target,
+                started_looking,
+                ..
+            } => started_looking.map(|t| (target, t)),
+            _ => None,
+        }
+    }
+
     pub fn get_parking_spot_goal(&self) -> Option<&ParkingSpot> {
         match self.goal {
             Goal::ParkNearBuilding { ref spot, .. } => spot.as_ref().map(|(s, _)| s),
@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 
 use abstutil::Counter;
 use geom::{Duration, Histogram, Time};
-use map_model::{BusRouteID, IntersectionID, Path, PathRequest};
+use map_model::{BusRouteID, IntersectionID, Path, PathRequest, RoadID};
 
 use crate::{
     pandemic, AgentID, CarID, CreateCar, CreatePedestrian, PedestrianID, TripID, TripSpec,
@@ -27,6 +27,15 @@ pub(crate) enum Command {
     Pandemic(pandemic::Cmd),
     /// The Time is redundant, just used to dedupe commands
     StartBus(BusRouteID, Time),
+    /// A train starts passing through every rail crossing, closing them until this time
+    CloseRailCrossings(Time),
+    /// A movable bridge opens for boats, blocking the road until this time
+    OpenBridge(RoadID, Time),
+    /// Give up on trips where somebody's been stuck waiting for at least this long, then check
+    /// again later
+    AbandonStuckTrips(Duration),
+    /// Capture where every agent is for playback, then again after this long
+    CapturePlayback(Duration),
 }
 
 impl Command {
@@ -50,6 +59,10 @@ impl Command {
             Command::Callback(_) => CommandType::Callback,
             Command::Pandemic(ref p) => CommandType::Pandemic(p.clone()),
             Command::StartBus(r, t) => CommandType::StartBus(*r, *t),
+            Command::CloseRailCrossings(t) => CommandType::CloseRailCrossings(*t),
+            Command::OpenBridge(r, t) => CommandType::OpenBridge(*r, *t),
+            Command::AbandonStuckTrips(_) => CommandType::AbandonStuckTrips,
+            Command::CapturePlayback(_) => CommandType::CapturePlayback,
         }
     }
 
@@ -65,6 +78,11 @@ impl Command {
             Command::Callback(_) => SimpleCommandType::Callback,
             Command::Pandemic(_) => SimpleCommandType::Pandemic,
             Command::StartBus(_, _) => SimpleCommandType::StartBus,
+            Command::CloseRailCrossings(_) | Command::OpenBridge(_, _) => {
+                SimpleCommandType::Intersection
+            }
+            Command::AbandonStuckTrips(_) => SimpleCommandType::AbandonStuckTrips,
+            Command::CapturePlayback(_) => SimpleCommandType::CapturePlayback,
         }
     }
 }
@@ -81,6 +99,10 @@ enum CommandType {
     Callback,
     Pandemic(pandemic::Cmd),
     StartBus(BusRouteID, Time),
+    CloseRailCrossings(Time),
+    OpenBridge(RoadID, Time),
+    AbandonStuckTrips,
+    CapturePlayback,
 }
 
 /// A more compressed form of CommandType, just used for keeping stats on event processing.
@@ -94,6 +116,8 @@ enum SimpleCommandType {
     Callback,
     Pandemic,
     StartBus,
+    AbandonStuckTrips,
+    CapturePlayback,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Clone)]
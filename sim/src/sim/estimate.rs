@@ -0,0 +1,67 @@
+use std::collections::VecDeque;
+
+use instant::Instant;
+
+use geom::{Duration, Time};
+
+// Only measure speed over this much recent real time, since it changes as traffic builds up
+const WINDOW: Duration = Duration::const_seconds(60.0);
+
+/// Predicts how much longer, in real time, the simulation needs to reach some time, based on how
+/// fast it's been going recently. The simulation usually slows down as traffic builds up, so this
+/// is only a rough guide for planning around long runs.
+pub struct RunEstimate {
+    end_time: Time,
+    // (real time, simulated time)
+    samples: VecDeque<(Instant, Time)>,
+}
+
+impl RunEstimate {
+    pub fn new(now: Time, end_time: Time) -> RunEstimate {
+        let mut samples = VecDeque::new();
+        samples.push_back((Instant::now(), now));
+        RunEstimate { end_time, samples }
+    }
+
+    /// Call this periodically as the simulation advances.
+    pub fn update(&mut self, now: Time) {
+        self.samples.push_back((Instant::now(), now));
+        while self.samples.len() > 2 && Duration::realtime_elapsed(self.samples[0].0) > WINDOW {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn end_time(&self) -> Time {
+        self.end_time
+    }
+
+    /// Simulated seconds per real second, recently. None until there's been some progress.
+    pub fn speed(&self) -> Option<f64> {
+        let (real1, sim1) = self.samples.front()?;
+        let (real2, sim2) = self.samples.back()?;
+        let real = (*real2 - *real1).as_secs_f64();
+        if real <= 0.0 || sim2 <= sim1 {
+            return None;
+        }
+        Some((*sim2 - *sim1).inner_seconds() / real)
+    }
+
+    /// How much real time is left until the end time, at the recent speed.
+    pub fn time_left(&self, now: Time) -> Option<Duration> {
+        if now >= self.end_time {
+            return Some(Duration::ZERO);
+        }
+        let speed = self.speed()?;
+        Some(Duration::seconds(
+            (self.end_time - now).inner_seconds() / speed,
+        ))
+    }
+
+    /// Like "about 2h30m left", for printing progress.
+    pub fn describe(&self, now: Time) -> String {
+        match self.time_left(now) {
+            Some(dt) => format!("about {} left", dt.round_up(Duration::seconds(1.0))),
+            None => "no estimate yet".to_string(),
+        }
+    }
+}
@@ -1,34 +1,45 @@
 // This file has a jumbled mess of queries, setup, and mutating methods.
 
-use std::collections::{BTreeSet, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::panic;
 
 use instant::Instant;
-use rand::SeedableRng;
+use rand::{Rng, SeedableRng};
 use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
 
-use abstutil::{prettyprint_usize, serialized_size_bytes, CmdArgs, MapName, Parallelism, Timer};
+use abstutil::{
+    deserialize_btreemap, prettyprint_usize, serialize_btreemap, serialized_size_bytes, CmdArgs,
+    MapName, Parallelism, Timer,
+};
 use geom::{Distance, Duration, Speed, Time};
 use map_model::{
-    BuildingID, BusRoute, IntersectionID, LaneID, Map, ParkingLotID, Path, PathConstraints,
-    PathRequest, Position, Traversable,
+    BuildingID, BusRoute, EditCmd, IntersectionID, LaneID, Map, ParkingLotID, Path,
+    PathConstraints, PathRequest, Position, Traversable,
 };
 
-pub use self::queries::{AgentProperties, DelayCause};
+pub use self::estimate::RunEstimate;
+use self::profile::StepProfile;
+pub use self::profile::Subsystem;
+pub use self::queries::{AgentProperties, BlockedOn, DelayCause};
 use crate::{
-    AgentID, AlertLocation, Analytics, CapSimState, CarID, Command, CreateCar, DrivingSimState,
-    Event, IntersectionSimState, OrigPersonID, PandemicModel, ParkedCar, ParkingSim,
-    ParkingSimState, ParkingSpot, Person, PersonID, Router, Scheduler, SidewalkPOI, SidewalkSpot,
-    TrafficRecorder, TransitSimState, TripID, TripInfo, TripLeg, TripManager, TripPhaseType,
-    TripSpec, Vehicle, VehicleSpec, VehicleType, WalkingSimState, BUS_LENGTH, LIGHT_RAIL_LENGTH,
-    MIN_CAR_LENGTH, SPAWN_DIST,
+    AgentID, AlertLocation, Analytics, BridgeOpenings, CapSimState, CarID, Command, CreateCar,
+    DrivingSimState, Event, EventLog, IntersectionSimState, OrigPersonID, PandemicModel, ParkedCar,
+    ParkingPrices, ParkingSim, ParkingSimState, ParkingSpot, Person, PersonID, Persona, Playback,
+    Router, Scheduler, SidewalkPOI, SidewalkSpot, TrafficRecorder, TrainSchedule, TransitFares,
+    TransitSimState, TripID, TripInfo, TripLeg, TripManager, TripPhaseType, TripSpec, Vehicle,
+    VehicleSpec, VehicleType, WalkingSimState, BUS_LENGTH, LIGHT_RAIL_LENGTH, MIN_CAR_LENGTH,
+    SPAWN_DIST, WAIT_AT_STOP_SIGN,
 };
 
+mod estimate;
+mod profile;
 mod queries;
 
 // TODO Do something else.
 const BLIND_RETRY_TO_SPAWN: Duration = Duration::const_seconds(5.0);
+// How often to look for people who've been stuck long enough to give up
+const CHECK_FOR_ABANDONED_TRIPS: Duration = Duration::const_seconds(60.0);
 
 /// The Sim ties together all the pieces of the simulation. Its main property is the current time.
 #[derive(Serialize, Deserialize, Clone)]
@@ -40,6 +51,13 @@ pub struct Sim {
     transit: TransitSimState,
     cap: CapSimState,
     trips: TripManager,
+    // When each car was first noticed searching for parking. Only tracked when trips can be
+    // abandoned.
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    searching_for_parking: BTreeMap<CarID, Time>,
     #[serde(skip_serializing, skip_deserializing)]
     pandemic: Option<PandemicModel>,
     scheduler: Scheduler,
@@ -60,9 +78,17 @@ pub struct Sim {
     // This is created interactively, and there's no reason to preserve one for savestates.
     #[serde(skip_serializing, skip_deserializing)]
     recorder: Option<TrafficRecorder>,
+    // Can be huge, and it's saved separately
+    #[serde(skip_serializing, skip_deserializing)]
+    event_log: Option<EventLog>,
+    // Same
+    #[serde(skip_serializing, skip_deserializing)]
+    playback: Option<Playback>,
 
     #[serde(skip_serializing, skip_deserializing)]
     alerts: AlertHandler,
+    #[serde(skip_serializing, skip_deserializing)]
+    profile: Option<StepProfile>,
 }
 
 pub(crate) struct Ctx<'a> {
@@ -73,6 +99,22 @@ pub(crate) struct Ctx<'a> {
     pub map: &'a Map,
     /// If true, live map edits are being processed. Some regular work should maybe be skipped.
     pub handling_live_edits: bool,
+    /// Present while handling a command if the simulation is profiling steps.
+    pub profile: Option<&'a mut StepProfile>,
+}
+
+impl<'a> Ctx<'a> {
+    /// When profiling, call this before some work on parking, then pass the result to
+    /// `record_parking` afterwards.
+    pub fn start_parking(&self) -> Option<Instant> {
+        self.profile.as_ref().map(|_| Instant::now())
+    }
+
+    pub fn record_parking(&mut self, started: Option<Instant>) {
+        if let (Some(profile), Some(started)) = (self.profile.as_mut(), started) {
+            profile.record_parking(started);
+        }
+    }
 }
 
 /// Options controlling the traffic simulation.
@@ -85,7 +127,8 @@ pub struct SimOptions {
     /// immediately accept it. FIFO ordering, no balancing between different movements.
     pub use_freeform_policy_everywhere: bool,
     /// Prevent a vehicle from starting a turn if their target lane is already full, since this may
-    /// mean they'll get stuck blocking the intersection.
+    /// mean they'll get stuck blocking the intersection. Individual intersections can override
+    /// this with map edits.
     pub dont_block_the_box: bool,
     /// As a vehicle follows a route, opportunistically make small changes to use a different lane,
     /// based on some score of "least-loaded" lane.
@@ -114,6 +157,36 @@ pub struct SimOptions {
     /// Don't collect any analytics. Only useful for benchmarking and debugging gridlock more
     /// quickly.
     pub skip_analytics: bool,
+    /// Most things in the simulation happen exactly when they're scheduled, but in a few places an
+    /// agent can't know when it'll be unblocked and checks again later. This is how long a vehicle
+    /// whose tail still blocks a lane waits before checking again, and how long somebody yielding
+    /// at an intersection waits to retry. Smaller values model busy intersections more finely, at
+    /// the cost of processing more events. Retrying to spawn a vehicle with no room and retrying
+    /// to reach the end of a path still use fixed intervals.
+    pub retry_granularity: Duration,
+    /// If set, overrides retry_granularity for agents yielding at an intersection or stuck partly
+    /// inside one, where timing matters most. This allows coarse retries everywhere else to save
+    /// time, while keeping conflicting turns accurate. When unset, intersections use
+    /// retry_granularity, capped to how long agents yield at stop signs.
+    pub intersection_retry_granularity: Option<Duration>,
+    /// Keep every event that happens, so it can be saved and queried after the run.
+    pub record_event_log: bool,
+    /// Every this often, capture where every agent is, so the run can be played back later
+    /// without the simulation.
+    pub record_playback: Option<Duration>,
+    /// Pedestrians walking along a road shoulder, because the road has no sidewalk, move at this
+    /// fraction of their usual speed.
+    pub shoulder_walking_speed: f64,
+    /// If set, cars leave each parking garage and lot at most this often, like through a gate.
+    /// When many cars leave at once, like after an event, the rest wait inside, starting their
+    /// trips later.
+    pub garage_exit_headway: Option<Duration>,
+    /// If set, some drivers go faster than the speed limit, except near speed cameras.
+    pub speeding: Option<Speeding>,
+    /// Measure the real time each subsystem spends handling events, for
+    /// `Sim::describe_internal_stats`. This reads the clock around every event, so it's off by
+    /// default.
+    pub profile_steps: bool,
 }
 
 impl std::default::Default for SimOptions {
@@ -124,7 +197,7 @@ impl std::default::Default for SimOptions {
 
 impl SimOptions {
     pub fn from_args(args: &mut CmdArgs, rng_seed: u64) -> SimOptions {
-        SimOptions {
+        let opts = SimOptions {
             run_name: args
                 .optional("--run_name")
                 .unwrap_or_else(|| "unnamed".to_string()),
@@ -152,6 +225,62 @@ impl SimOptions {
             cancel_drivers_delay_threshold: args
                 .optional_parse("--cancel_drivers_delay_threshold", Duration::parse),
             skip_analytics: args.enabled("--skip_analytics"),
+            retry_granularity: args
+                .optional_parse("--retry_granularity", Duration::parse)
+                .unwrap_or(Duration::seconds(0.1)),
+            intersection_retry_granularity: args
+                .optional_parse("--intersection_retry_granularity", Duration::parse),
+            record_event_log: args.enabled("--event_log"),
+            record_playback: args.optional_parse("--record_playback", Duration::parse),
+            shoulder_walking_speed: args
+                .optional_parse("--shoulder_walking_speed", |x| x.parse::<f64>())
+                .unwrap_or(0.8),
+            garage_exit_headway: args.optional_parse("--garage_exit_headway", Duration::parse),
+            speeding: args
+                .optional_parse("--speeding", |x| x.parse::<f64>())
+                .map(|share| {
+                    if !(0.0..=1.0).contains(&share) {
+                        panic!("--speeding={} must be between 0 and 1", share);
+                    }
+                    Speeding {
+                        share,
+                        max_over: args
+                            .optional_parse("--max_speeding", |x| x.parse::<f64>())
+                            .unwrap_or(0.2),
+                    }
+                }),
+            profile_steps: args.enabled("--profile_steps"),
+        };
+        if let Err(err) = opts.validate() {
+            panic!("{}", err);
+        }
+        opts
+    }
+}
+
+/// Posted speed limits aren't always obeyed. Each car's driver either keeps to the limit or speeds
+/// by some amount, and keeps doing the same on every road and every trip. Near a speed camera,
+/// speeding drivers slow back down to the limit.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Speeding {
+    /// The fraction of drivers who speed, from 0 to 1
+    pub share: f64,
+    /// Speeding drivers go over the limit by a random fraction up to this; 0.2 means up to 20%
+    /// faster.
+    pub max_over: f64,
+}
+
+impl Speeding {
+    /// How many times the speed limit this car goes. The same car always gets the same answer.
+    pub fn sample(&self, car: CarID) -> f64 {
+        if car.1 != VehicleType::Car {
+            return 1.0;
+        }
+        let mut rng = XorShiftRng::seed_from_u64(car.0 as u64);
+        if rng.gen_bool(self.share) {
+            1.0 + rng.gen::<f64>() * self.max_over
+        } else {
+            1.0
         }
     }
 }
@@ -187,22 +316,100 @@ impl SimOptions {
             disable_turn_conflicts: false,
             cancel_drivers_delay_threshold: None,
             skip_analytics: false,
+            retry_granularity: Duration::seconds(0.1),
+            intersection_retry_granularity: None,
+            record_event_log: false,
+            record_playback: None,
+            shoulder_walking_speed: 0.8,
+            garage_exit_headway: None,
+            speeding: None,
+            profile_steps: false,
         }
     }
+
+    /// Some combinations of options make the model misbehave, without failing outright. Explain
+    /// the first problem found.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.retry_granularity <= Duration::ZERO {
+            return Err(format!(
+                "retry_granularity is {}, but must be positive",
+                self.retry_granularity
+            ));
+        }
+        if let Some(dt) = self.intersection_retry_granularity {
+            if dt <= Duration::ZERO {
+                return Err(format!(
+                    "intersection_retry_granularity is {}, but must be positive",
+                    dt
+                ));
+            }
+            // An agent yielding for less time than it waits between retries could miss the gap
+            // it's waiting for, and stop signs would then behave differently than configured.
+            if dt > WAIT_AT_STOP_SIGN {
+                return Err(format!(
+                    "intersection_retry_granularity is {}, which is too coarse to model yielding; \
+                     it must be at most {}",
+                    dt, WAIT_AT_STOP_SIGN
+                ));
+            }
+            if dt > self.retry_granularity {
+                return Err(format!(
+                    "intersection_retry_granularity is {}, but it's meant to be finer than \
+                     retry_granularity, which is {}",
+                    dt, self.retry_granularity
+                ));
+            }
+        }
+        if self.shoulder_walking_speed <= 0.0 {
+            return Err(format!(
+                "shoulder_walking_speed is {}, but must be positive",
+                self.shoulder_walking_speed
+            ));
+        }
+        if let Some(dt) = self.record_playback {
+            if dt <= Duration::ZERO {
+                return Err(format!("record_playback is {}, but must be positive", dt));
+            }
+        }
+        if let Some(dt) = self.garage_exit_headway {
+            if dt < Duration::ZERO {
+                return Err(format!(
+                    "garage_exit_headway is {}, but can't be negative",
+                    dt
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// How often agents retry at intersections. Unless this is set explicitly, intersections
+    /// retry at retry_granularity, but never more coarsely than yielding at a stop sign can be
+    /// modeled.
+    pub(crate) fn effective_intersection_retry_granularity(&self) -> Duration {
+        self.intersection_retry_granularity
+            .unwrap_or_else(|| self.retry_granularity.min(WAIT_AT_STOP_SIGN))
+    }
 }
 
 // Setup
 impl Sim {
     pub fn new(map: &Map, opts: SimOptions, timer: &mut Timer) -> Sim {
+        if let Err(err) = opts.validate() {
+            panic!("Bad SimOptions: {}", err);
+        }
         let mut scheduler = Scheduler::new();
+        if let Some(frequency) = opts.record_playback {
+            scheduler.push(Time::START_OF_DAY, Command::CapturePlayback(frequency));
+        }
         Sim {
             driving: DrivingSimState::new(map, &opts),
             parking: ParkingSimState::new(map, opts.infinite_parking, timer),
-            walking: WalkingSimState::new(),
+            walking: WalkingSimState::new(&opts),
             intersections: IntersectionSimState::new(map, &mut scheduler, &opts),
             transit: TransitSimState::new(map),
             cap: CapSimState::new(map, &opts),
             trips: TripManager::new(),
+            searching_for_parking: BTreeMap::new(),
             pandemic: if let Some(rng) = opts.enable_pandemic_model {
                 Some(PandemicModel::new(rng))
             } else {
@@ -219,6 +426,19 @@ impl Sim {
 
             analytics: Analytics::new(!opts.skip_analytics),
             recorder: None,
+            event_log: if opts.record_event_log {
+                Some(EventLog::new())
+            } else {
+                None
+            },
+            playback: opts
+                .record_playback
+                .map(|frequency| Playback::new(map, frequency)),
+            profile: if opts.profile_steps {
+                Some(StepProfile::default())
+            } else {
+                None
+            },
         }
     }
 
@@ -313,10 +533,21 @@ impl Sim {
     pub(crate) fn new_person(
         &mut self,
         orig_id: Option<OrigPersonID>,
+        persona: Persona,
         ped_speed: Speed,
+        max_walking_distance: Distance,
         vehicle_specs: Vec<VehicleSpec>,
     ) -> &Person {
-        self.trips.new_person(orig_id, ped_speed, vehicle_specs)
+        self.trips.new_person(
+            orig_id,
+            persona,
+            ped_speed,
+            max_walking_distance,
+            vehicle_specs,
+        )
+    }
+    pub(crate) fn stay_inside(&mut self, person: PersonID, b: BuildingID) {
+        self.trips.stay_inside(person, b);
     }
     pub(crate) fn seed_parked_car(&mut self, vehicle: Vehicle, spot: ParkingSpot) {
         self.parking.reserve_spot(spot, vehicle.id);
@@ -327,12 +558,59 @@ impl Sim {
         });
     }
 
+    /// Close rail crossings whenever a train passes. Trains scheduled before now are skipped.
+    pub fn schedule_trains(&mut self, schedule: &TrainSchedule) {
+        for t in &schedule.trains {
+            if *t >= self.time {
+                // Two trains at the same time just close the crossings once
+                self.scheduler
+                    .update(*t, Command::CloseRailCrossings(*t + schedule.closure));
+            }
+        }
+    }
+
+    /// Block movable bridges whenever they open. Openings before now are skipped.
+    pub fn schedule_bridge_openings(&mut self, schedule: &BridgeOpenings) {
+        for (r, t) in &schedule.openings {
+            if *t >= self.time {
+                self.scheduler
+                    .update(*t, Command::OpenBridge(*r, *t + schedule.duration));
+            }
+        }
+    }
+
+    /// Let people give up on trips after being stuck waiting for this long. None means people
+    /// wait forever.
+    pub fn set_trip_abandonment(&mut self, threshold: Option<Duration>) {
+        // Changing the threshold replaces the previous check, rather than scheduling a second one
+        self.scheduler
+            .cancel(Command::AbandonStuckTrips(Duration::ZERO));
+        if let Some(threshold) = threshold {
+            self.scheduler.update(
+                self.time + CHECK_FOR_ABANDONED_TRIPS,
+                Command::AbandonStuckTrips(threshold),
+            );
+        }
+    }
+
     pub(crate) fn seed_bus_route(&mut self, route: &BusRoute) {
         for t in &route.spawn_times {
             self.scheduler.push(*t, Command::StartBus(route.id, *t));
         }
     }
 
+    /// Start running a route created after the simulation began. Only vehicles scheduled from now
+    /// on will run.
+    pub fn start_new_bus_route(&mut self, route: &BusRoute, map: &Map) {
+        self.transit.handle_new_stops(map);
+        for t in &route.spawn_times {
+            if *t >= self.time {
+                // The route may already be running
+                self.scheduler.update(*t, Command::StartBus(route.id, *t));
+            }
+        }
+    }
+
     fn start_bus(&mut self, route: &BusRoute, map: &Map) {
         // Spawn one bus for the first leg.
         let (req, path) = self.transit.create_empty_route(route, map);
@@ -382,6 +660,16 @@ impl Sim {
     pub fn set_name(&mut self, name: String) {
         self.run_name = name;
     }
+
+    /// Changes what drivers pay to park, which affects where they choose to park from now on.
+    pub fn set_parking_prices(&mut self, prices: &ParkingPrices, map: &Map) {
+        self.parking.set_prices(prices, map);
+    }
+
+    /// Changes what transit riders pay each time they board from now on.
+    pub fn set_transit_fares(&mut self, fares: &TransitFares) {
+        self.transit.set_fares(fares);
+    }
 }
 
 // Running
@@ -437,6 +725,8 @@ impl Sim {
         let mut events = Vec::new();
         let mut halt = false;
 
+        let started = self.profile.as_ref().map(|_| Instant::now());
+        let subsystem = Subsystem::handling(&cmd);
         let mut ctx = Ctx {
             parking: &mut self.parking,
             intersections: &mut self.intersections,
@@ -444,6 +734,7 @@ impl Sim {
             scheduler: &mut self.scheduler,
             map,
             handling_live_edits: false,
+            profile: self.profile.as_mut(),
         };
 
         match cmd {
@@ -455,7 +746,10 @@ impl Sim {
                 // attempt, the path might've become invalid. TODO Skip this check
                 // most of the time.
                 let constraints = create_car.vehicle.vehicle_type.to_constraints();
-                let mut ok = true;
+                // A live edit may have also deleted the route a bus was about to serve
+                let mut ok = create_car
+                    .maybe_route
+                    .map_or(true, |r| ctx.map.maybe_get_br(r).is_some());
                 for step in create_car.router.get_path().get_steps() {
                     match step.as_traversable() {
                         Traversable::Lane(l) => {
@@ -473,13 +767,20 @@ impl Sim {
                     }
                 }
                 if !ok {
-                    self.trips.cancel_trip(
-                        self.time,
-                        create_car.trip_and_person.unwrap().0,
-                        "path is no longer valid after map edits".to_string(),
-                        Some(create_car.vehicle),
-                        &mut ctx,
-                    );
+                    // Buses have no trip to cancel; they just don't run
+                    if let Some((trip, _)) = create_car.trip_and_person {
+                        self.trips.cancel_trip(
+                            self.time,
+                            trip,
+                            "path is no longer valid after map edits".to_string(),
+                            Some(create_car.vehicle),
+                            &mut ctx,
+                        );
+                    }
+                } else if let Some(turn) = self.driving.wait_to_exit_garage(self.time, &create_car)
+                {
+                    self.scheduler
+                        .push(turn, Command::SpawnCar(create_car, retry_if_no_room));
                 } else {
                     // create_car contains a Path, which is expensive to clone. We need different
                     // parts of create_car after attempting start_car_on_lane.
@@ -626,13 +927,48 @@ impl Sim {
                     .unwrap()
                     .handle_cmd(self.time, cmd, &mut self.scheduler);
             }
-            Command::StartBus(r, _) => {
-                self.start_bus(map.get_br(r), map);
+            Command::StartBus(r, t) => {
+                // Live edits may have since deleted the route or rescheduled it
+                if let Some(route) = map.maybe_get_br(r) {
+                    if route.spawn_times.contains(&t) {
+                        self.start_bus(route, map);
+                    }
+                }
+            }
+            Command::CloseRailCrossings(until) => {
+                self.intersections.close_rail_crossings(until, map);
+            }
+            Command::OpenBridge(r, until) => {
+                self.intersections.open_bridge(r, until);
+            }
+            Command::AbandonStuckTrips(threshold) => {
+                self.abandon_stuck_trips(threshold, map);
+                self.scheduler.push(
+                    self.time + CHECK_FOR_ABANDONED_TRIPS,
+                    Command::AbandonStuckTrips(threshold),
+                );
+            }
+            Command::CapturePlayback(frequency) => {
+                // Savestates don't keep the recording, so stop capturing after loading one
+                if let Some(mut playback) = self.playback.take() {
+                    playback.capture(self, map);
+                    self.playback = Some(playback);
+                    self.scheduler
+                        .push(self.time + frequency, Command::CapturePlayback(frequency));
+                }
             }
         }
 
+        if let (Some(profile), Some(started)) = (self.profile.as_mut(), started) {
+            profile.record(subsystem, started);
+        }
+
         // Record events at precisely the time they occur.
+        let started = self.profile.as_ref().map(|_| Instant::now());
         self.dispatch_events(events, map);
+        if let (Some(profile), Some(started)) = (self.profile.as_mut(), started) {
+            profile.record(Subsystem::Events, started);
+        }
 
         halt
     }
@@ -651,6 +987,9 @@ impl Sim {
             if let Some(ref mut r) = self.recorder {
                 r.handle_event(self.time, &ev, map, &self.driving, &self.trips);
             }
+            if let Some(ref mut log) = self.event_log {
+                log.push(self.time, &ev);
+            }
 
             self.analytics.event(ev, self.time, map);
         }
@@ -666,6 +1005,7 @@ impl Sim {
         let end_time = self.time + dt;
         let start = Instant::now();
         let mut last_update = Instant::now();
+        let mut estimate = RunEstimate::new(self.time, end_time);
 
         timer.start(format!("Advance sim to {}", end_time));
         while self.time < end_time {
@@ -692,15 +1032,21 @@ impl Sim {
             }
             if Duration::realtime_elapsed(last_update) >= Duration::seconds(1.0) {
                 // TODO Not timer?
+                estimate.update(self.time);
                 println!(
-                    "- After {}, the sim is at {}. {} live agents",
+                    "- After {}, the sim is at {}. {} live agents, {} trips haven't started, {}",
                     Duration::realtime_elapsed(start),
                     self.time,
                     prettyprint_usize(self.num_active_agents()),
+                    prettyprint_usize(self.num_trips_not_started()),
+                    estimate.describe(self.time),
                 );
                 last_update = Instant::now();
             }
         }
+        if let Some(ref mut profile) = self.profile {
+            profile.finish_step();
+        }
         timer.stop(format!("Advance sim to {}", end_time));
     }
     pub fn tiny_step(&mut self, map: &Map, maybe_cb: &mut Option<Box<dyn SimCallback>>) {
@@ -745,6 +1091,9 @@ impl Sim {
                 }
             }
         }
+        if let Some(ref mut profile) = self.profile {
+            profile.finish_step();
+        }
     }
 
     pub fn dump_before_abort(&self) {
@@ -873,7 +1222,9 @@ impl Sim {
             scheduler: &mut self.scheduler,
             map,
             handling_live_edits: true,
+            profile: None,
         };
+        let mut cancelled_peds = BTreeSet::new();
         for (agent, trip) in affected {
             match agent {
                 AgentID::Car(car) => {
@@ -889,6 +1240,7 @@ impl Sim {
                     self.trips.trip_abruptly_cancelled(trip, AgentID::Car(car));
                 }
                 AgentID::Pedestrian(ped) => {
+                    cancelled_peds.insert(ped);
                     self.walking.delete_ped(ped, &mut ctx);
                     self.trips.cancel_trip(
                         self.time,
@@ -903,10 +1255,33 @@ impl Sim {
                 AgentID::BusPassenger(_, _) => unreachable!(),
             }
         }
+        for (car, route, riders) in self.transit.delete_removed_routes(map) {
+            for person in riders {
+                self.trips
+                    .transit_rider_cancelled(self.time, person, car, route, &mut ctx);
+            }
+            if self.driving.does_car_exist(car) {
+                self.driving.delete_car(car, self.time, &mut ctx);
+            }
+        }
+        for (ped, stop, waited) in self.transit.handle_live_edits(self.time, map) {
+            if !cancelled_peds.contains(&ped) {
+                self.walking.ped_stopped_waiting_for_bus(ped);
+                self.trips
+                    .transit_rider_rerouted(self.time, ped, stop, waited, &mut ctx);
+            }
+        }
 
         self.driving.handle_live_edits(map);
         self.intersections.handle_live_edits(map);
 
+        // Routes created by the edits may be new to this simulation
+        for cmd in &map.get_edits().commands {
+            if let EditCmd::CreateRoute { route, .. } = cmd {
+                self.start_new_bus_route(map.get_br(route.id), map);
+            }
+        }
+
         (num_trips_cancelled, num_parked_cars)
     }
 
@@ -977,6 +1352,50 @@ impl Sim {
     }
 }
 
+// Giving up
+impl Sim {
+    fn abandon_stuck_trips(&mut self, threshold: Duration, map: &Map) {
+        let stuck_cars =
+            self.driving
+                .find_stuck_cars(self.time, threshold, &mut self.searching_for_parking);
+        let stuck_peds = self.walking.find_stuck_peds(self.time, threshold);
+
+        let mut ctx = Ctx {
+            parking: &mut self.parking,
+            intersections: &mut self.intersections,
+            cap: &mut self.cap,
+            scheduler: &mut self.scheduler,
+            map,
+            handling_live_edits: false,
+            profile: self.profile.as_mut(),
+        };
+        for (car, trip, waited) in stuck_cars {
+            self.searching_for_parking.remove(&car);
+            let vehicle = self.driving.abandon_car(car, self.time, &mut ctx);
+            self.trips.abandon_trip(
+                self.time,
+                trip,
+                AgentID::Car(car),
+                waited,
+                Some(vehicle),
+                &mut ctx,
+            );
+        }
+        for (ped, trip, waited) in stuck_peds {
+            self.transit.ped_stopped_waiting(ped);
+            self.walking.abandon_ped(ped, self.time, &mut ctx);
+            self.trips.abandon_trip(
+                self.time,
+                trip,
+                AgentID::Pedestrian(ped),
+                waited,
+                None,
+                &mut ctx,
+            );
+        }
+    }
+}
+
 // Invasive debugging
 impl Sim {
     pub fn delete_car(&mut self, id: CarID, map: &Map) {
@@ -988,6 +1407,7 @@ impl Sim {
                 scheduler: &mut self.scheduler,
                 map,
                 handling_live_edits: false,
+                profile: None,
             };
             let vehicle = self.driving.delete_car(id, self.time, &mut ctx);
             self.trips.cancel_trip(
@@ -1043,3 +1463,40 @@ impl Sim {
         self.recorder.take().unwrap().save(map);
     }
 }
+
+// Event log
+impl Sim {
+    /// Only present if the simulation was started with `record_event_log`.
+    pub fn get_event_log(&self) -> Option<&EventLog> {
+        self.event_log.as_ref()
+    }
+
+    /// Returns the path written, if the event log is being recorded.
+    pub fn save_event_log(&self) -> Option<String> {
+        let path = format!(
+            "{}/event_log_{}.bin",
+            self.save_dir(),
+            self.time.as_filename()
+        );
+        if let Err(err) = self.event_log.as_ref()?.save(path.clone()) {
+            panic!("Couldn't save event log to {}: {}", path, err);
+        }
+        Some(path)
+    }
+
+    /// Returns the path written, if playback is being recorded. The event log is included, if
+    /// it's also being recorded.
+    pub fn save_playback(&self) -> Option<String> {
+        let path = format!(
+            "{}/playback_{}.bin",
+            self.save_dir(),
+            self.time.as_filename()
+        );
+        let mut playback = self.playback.clone()?;
+        playback.events = self.event_log.clone();
+        if let Err(err) = playback.save(path.clone()) {
+            panic!("Couldn't save playback to {}: {}", path, err);
+        }
+        Some(path)
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use instant::Instant;
+
+use geom::Duration;
+
+use crate::scheduler::Command;
+
+// Average over this many recent calls to step
+const WINDOW: usize = 20;
+
+/// The pieces of the simulation that handle events.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Subsystem {
+    Trips,
+    Driving,
+    Walking,
+    Transit,
+    Intersections,
+    /// Parking doesn't schedule its own events. This is the time drivers spend looking for a spot
+    /// and trips spend choosing and claiming one, which isn't counted towards Driving or Trips.
+    Parking,
+    /// Analytics, the pandemic model, and the traffic recorder reacting to events
+    Events,
+    Other,
+}
+
+impl Subsystem {
+    pub(crate) fn handling(cmd: &Command) -> Subsystem {
+        match cmd {
+            Command::StartTrip(_, _) | Command::AbandonStuckTrips(_) => Subsystem::Trips,
+            Command::SpawnCar(_, _) | Command::UpdateCar(_) | Command::UpdateLaggyHead(_) => {
+                Subsystem::Driving
+            }
+            Command::SpawnPed(_) | Command::UpdatePed(_) => Subsystem::Walking,
+            Command::StartBus(_, _) => Subsystem::Transit,
+            Command::UpdateIntersection(_)
+            | Command::CloseRailCrossings(_)
+            | Command::OpenBridge(_, _) => Subsystem::Intersections,
+            Command::Callback(_) | Command::Pandemic(_) | Command::CapturePlayback(_) => {
+                Subsystem::Other
+            }
+        }
+    }
+}
+
+/// Measures how much real time each subsystem spends during calls to step, so performance work
+/// can target the real bottleneck on a particular map. Only kept when `SimOptions::profile_steps`
+/// is set, since reading the clock around every command isn't free.
+#[derive(Clone, Default)]
+pub(crate) struct StepProfile {
+    // Real time spent and how many times each subsystem ran during the current step
+    current: BTreeMap<Subsystem, (Duration, usize)>,
+    recent: VecDeque<BTreeMap<Subsystem, (Duration, usize)>>,
+    // Time recorded for parking while handling the current command, to take away from whatever
+    // subsystem handled it
+    parking_in_cmd: Duration,
+}
+
+impl StepProfile {
+    /// Records one command handled by a subsystem, started at some time.
+    pub fn record(&mut self, subsystem: Subsystem, started: Instant) {
+        let dt = Duration::realtime_elapsed(started) - self.parking_in_cmd;
+        self.parking_in_cmd = Duration::ZERO;
+        let entry = self.current.entry(subsystem).or_insert((Duration::ZERO, 0));
+        entry.0 += dt;
+        entry.1 += 1;
+    }
+
+    /// Records some work on parking, done in the middle of handling a command.
+    pub fn record_parking(&mut self, started: Instant) {
+        let dt = Duration::realtime_elapsed(started);
+        self.parking_in_cmd += dt;
+        let entry = self
+            .current
+            .entry(Subsystem::Parking)
+            .or_insert((Duration::ZERO, 0));
+        entry.0 += dt;
+        entry.1 += 1;
+    }
+
+    pub fn finish_step(&mut self) {
+        if self.current.is_empty() {
+            return;
+        }
+        self.recent.push_back(std::mem::take(&mut self.current));
+        if self.recent.len() > WINDOW {
+            self.recent.pop_front();
+        }
+    }
+
+    /// For each subsystem, the average real time spent and number of times it ran per step.
+    pub fn averages(&self) -> Vec<(Subsystem, Duration, f64)> {
+        let mut totals: BTreeMap<Subsystem, (Duration, usize)> = BTreeMap::new();
+        for step in &self.recent {
+            for (subsystem, (dt, count)) in step {
+                let entry = totals.entry(*subsystem).or_insert((Duration::ZERO, 0));
+                entry.0 += *dt;
+                entry.1 += *count;
+            }
+        }
+        let n = self.recent.len() as f64;
+        totals
+            .into_iter()
+            .map(|(subsystem, (dt, count))| (subsystem, dt / n, (count as f64) / n))
+            .collect()
+    }
+}
@@ -1,6 +1,9 @@
 //! All sorts of read-only queries about a simulation
 
 use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
 
 use abstutil::Counter;
 use geom::{Distance, Duration, PolyLine, Pt2D, Time};
@@ -11,9 +14,10 @@ use map_model::{
 
 use crate::analytics::Window;
 use crate::{
-    AgentID, AgentType, Analytics, CarID, CommutersVehiclesCounts, DrawCarInput, DrawPedCrowdInput,
-    DrawPedestrianInput, OrigPersonID, PandemicModel, ParkedCar, ParkingSim, PedestrianID, Person,
-    PersonID, PersonState, Scenario, Sim, TripID, TripInfo, TripResult, UnzoomedAgent, VehicleType,
+    AgentID, AgentSnapshot, AgentType, Analytics, CarID, CommutersVehiclesCounts, DrawCarInput,
+    DrawPedCrowdInput, DrawPedestrianInput, OrigPersonID, PandemicModel, ParkedCar, ParkingSim,
+    PedestrianID, Person, PersonID, PersonState, Scenario, Sim, Subsystem, TripID, TripInfo,
+    TripResult, UnzoomedAgent, VehicleType,
 };
 
 // TODO Many of these just delegate to an inner piece. This is unorganized and hard to maintain.
@@ -34,6 +38,10 @@ impl Sim {
     pub fn num_trips(&self) -> (usize, usize) {
         self.trips.num_trips()
     }
+    /// Trips still waiting for their departure time
+    pub fn num_trips_not_started(&self) -> usize {
+        self.trips.num_trips_not_started()
+    }
     pub fn num_agents(&self) -> Counter<AgentType> {
         self.trips.num_agents(&self.transit)
     }
@@ -84,6 +92,11 @@ impl Sim {
         self.transit.get_passengers(car).len()
     }
 
+    /// How many passengers fit in a transit vehicle
+    pub fn transit_capacity(&self, car: CarID) -> usize {
+        self.transit.get_capacity(car)
+    }
+
     pub fn bus_route_id(&self, maybe_bus: CarID) -> Option<BusRouteID> {
         if maybe_bus.1 == VehicleType::Bus || maybe_bus.1 == VehicleType::Train {
             Some(self.transit.bus_route(maybe_bus))
@@ -269,9 +282,34 @@ impl Sim {
     /// For every agent that's currently not moving, figure out how long they've been waiting and
     /// why they're blocked.
     pub fn get_blocked_by_graph(&self, map: &Map) -> BTreeMap<AgentID, (Duration, DelayCause)> {
+        self.get_blocked_on(map)
+            .into_iter()
+            .filter_map(|(a, (dt, blocked))| blocked.delay_cause().map(|cause| (a, (dt, cause))))
+            .collect()
+    }
+
+    /// For every agent that's currently waiting on something, how long they've been waiting and
+    /// exactly what for. Includes drivers circling for parking.
+    pub fn get_blocked_on(&self, map: &Map) -> BTreeMap<AgentID, (Duration, BlockedOn)> {
         // Pedestrians can only be blocked at intersections, which is handled inside this call
         self.driving
-            .get_blocked_by_graph(self.time, map, &self.intersections)
+            .get_blocked_on(self.time, map, &self.intersections)
+    }
+
+    /// Every agent that's been waiting on something for at least this long, longest first
+    pub fn agents_blocked_for(
+        &self,
+        threshold: Duration,
+        map: &Map,
+    ) -> Vec<(AgentID, Duration, BlockedOn)> {
+        let mut list: Vec<(AgentID, Duration, BlockedOn)> = self
+            .get_blocked_on(map)
+            .into_iter()
+            .filter(|(_, (dt, _))| *dt >= threshold)
+            .map(|(a, (dt, blocked))| (a, dt, blocked))
+            .collect();
+        list.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        list
     }
 
     /// (bus, stop index it's coming from, percent to next stop, location)
@@ -292,6 +330,23 @@ impl Sim {
         results
     }
 
+    /// For vehicles that ran this route after laying over at the end of the reverse route, the
+    /// scheduled departure and how late they left.
+    pub fn get_schedule_adherence(&self, route: BusRouteID) -> &[(Time, Duration)] {
+        self.transit.get_schedule_adherence(route)
+    }
+
+    /// The percent of transit departures after a layover that left no later than the threshold.
+    /// None until any vehicle has laid over.
+    pub fn percent_transit_on_time(&self, threshold: Duration) -> Option<f64> {
+        let (on_time, total) = self.transit.on_time_departures(threshold);
+        if total == 0 {
+            None
+        } else {
+            Some(100.0 * (on_time as f64) / (total as f64))
+        }
+    }
+
     pub fn get_analytics(&self) -> &Analytics {
         &self.analytics
     }
@@ -308,6 +363,11 @@ impl Sim {
             .delayed_intersections(self.time, threshold)
     }
 
+    /// Lanes whose queue currently reaches back into the upstream intersection, and since when.
+    pub fn current_spillbacks(&self) -> Vec<(LaneID, Time)> {
+        self.intersections.current_spillbacks()
+    }
+
     pub fn bldg_to_people(&self, b: BuildingID) -> Vec<PersonID> {
         self.trips.bldg_to_people(b)
     }
@@ -404,9 +464,28 @@ impl Sim {
         let mut stats = self.scheduler.describe_stats();
         stats.push(String::new());
         stats.extend(self.intersections.describe_stats());
+        stats.push(String::new());
+        if self.profile.is_some() {
+            stats.push("real time per step, averaged over recent steps:".to_string());
+            for (subsystem, dt, count) in self.step_profile() {
+                stats.push(format!("{:?}: {} ({:.1} times)", subsystem, dt, count));
+            }
+        } else {
+            stats.push("run with --profile_steps to measure real time per step".to_string());
+        }
         stats
     }
 
+    /// For each subsystem, the real time spent and the number of times it ran, averaged over the
+    /// last few calls to `timed_step` or `time_limited_step`. Empty unless the simulation was
+    /// started with `profile_steps`.
+    pub fn step_profile(&self) -> Vec<(Subsystem, Duration, f64)> {
+        self.profile
+            .as_ref()
+            .map(|p| p.averages())
+            .unwrap_or_default()
+    }
+
     pub fn debug_queue_lengths(&self, l: LaneID) -> Option<(Distance, Distance)> {
         self.driving.debug_queue_lengths(l)
     }
@@ -429,6 +508,12 @@ impl Sim {
         self.walking.get_draw_ped(id, self.time, map)
     }
 
+    /// How far the front of each moving vehicle on a lane or turn is along it. Cheaper than
+    /// get_draw_cars when the geometry isn't needed.
+    pub fn get_car_positions_on(&self, on: Traversable) -> Vec<(CarID, Distance)> {
+        self.driving.get_car_positions_on(self.time, on)
+    }
+
     pub fn get_draw_cars(&self, on: Traversable, map: &Map) -> Vec<DrawCarInput> {
         let mut results = Vec::new();
         if let Traversable::Lane(l) = on {
@@ -469,6 +554,11 @@ impl Sim {
         result.extend(self.walking.get_unzoomed_agents(self.time, map));
         result
     }
+
+    /// Captures every agent in a compact binary layout for external visualizers.
+    pub fn agent_snapshot(&self, map: &Map) -> AgentSnapshot {
+        AgentSnapshot::new(self, map)
+    }
 }
 
 pub struct AgentProperties {
@@ -491,3 +581,48 @@ pub enum DelayCause {
     /// Waiting on a traffic signal to change, or pausing at a stop sign before proceeding
     Intersection(IntersectionID),
 }
+
+/// Exactly what an agent is waiting on. If there are multiple reasons, one is arbitrarily picked.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum BlockedOn {
+    /// Queued behind another vehicle
+    LeadVehicle(CarID),
+    /// Somebody else is doing a conflicting turn, or the same one
+    TurnConflict(AgentID, TurnID),
+    /// There's no room on the next lane, and this vehicle is at the back of it
+    FullLane(LaneID, CarID),
+    /// The traffic signal doesn't allow the turn right now
+    RedSignal(IntersectionID),
+    /// Pausing at a stop sign, before an unprotected turn, or while a new signal stage clears
+    Yielding(IntersectionID),
+    /// Looking for a free parking spot near the building
+    NoParking(BuildingID),
+}
+
+impl BlockedOn {
+    /// The cause as far as the graph of who's blocking whom is concerned, if there's somebody or
+    /// somewhere to point at.
+    pub fn delay_cause(self) -> Option<DelayCause> {
+        match self {
+            BlockedOn::LeadVehicle(c) | BlockedOn::FullLane(_, c) => {
+                Some(DelayCause::Agent(AgentID::Car(c)))
+            }
+            BlockedOn::TurnConflict(a, _) => Some(DelayCause::Agent(a)),
+            BlockedOn::RedSignal(i) | BlockedOn::Yielding(i) => Some(DelayCause::Intersection(i)),
+            BlockedOn::NoParking(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for BlockedOn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BlockedOn::LeadVehicle(c) => write!(f, "queued behind {}", c),
+            BlockedOn::TurnConflict(a, t) => write!(f, "waiting for {} doing {}", a, t),
+            BlockedOn::FullLane(l, c) => write!(f, "no room on {} behind {}", l, c),
+            BlockedOn::RedSignal(i) => write!(f, "red light at {}", i),
+            BlockedOn::Yielding(i) => write!(f, "yielding at {}", i),
+            BlockedOn::NoParking(b) => write!(f, "looking for parking near {}", b),
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! A compact snapshot of every agent's renderable state, encoded in a stable binary layout. This is
+//! meant for external visualizers (Unity, deck.gl, etc) that want to render the simulation every
+//! tick without paying for JSON serialization. The snapshot can be written to a file in shared
+//! memory (like `/dev/shm` on Linux) and memory-mapped by the consumer.
+//!
+//! Everything is little-endian. The file starts with a header:
+//!
+//! | offset | type  | field                                   |
+//! |--------|-------|-----------------------------------------|
+//! | 0      | [u8; 4] | magic bytes `ABSN`                    |
+//! | 4      | u32   | format version, currently 1             |
+//! | 8      | f64   | simulation time, in seconds since midnight |
+//! | 16     | u32   | number of agents                        |
+//! | 20     | u32   | size of each agent record in bytes (40) |
+//!
+//! Then one fixed-size record per agent:
+//!
+//! | offset | type | field                                               |
+//! |--------|------|-----------------------------------------------------|
+//! | 0      | u8   | kind: 0 car, 1 bike, 2 bus, 3 train, 4 pedestrian   |
+//! | 1      | u8   | flags, see the `FLAG_` constants                    |
+//! | 2      | u16  | reserved                                            |
+//! | 4      | u32  | the car or pedestrian ID                            |
+//! | 8      | u32  | the person ID, or `u32::MAX` for buses and trains   |
+//! | 12     | f32  | the angle the agent is facing, in degrees           |
+//! | 16     | f32  | x, in meters from the map's top-left corner         |
+//! | 20     | f32  | y, in meters from the map's top-left corner         |
+//! | 24     | f64  | longitude                                           |
+//! | 32     | f64  | latitude                                            |
+//!
+//! Colors aren't part of the simulation, so visualizers should pick them based on the kind and
+//! flags.
+
+use std::io::Write;
+
+use geom::Time;
+use map_model::Map;
+
+use crate::{AgentID, CarStatus, Sim, VehicleType};
+
+const MAGIC: &[u8; 4] = b"ABSN";
+const VERSION: u32 = 1;
+const HEADER_SIZE: usize = 24;
+const RECORD_SIZE: usize = 40;
+
+/// The agent is waiting to start a turn through an intersection.
+pub const FLAG_WAITING_FOR_TURN: u8 = 1 << 0;
+/// The car is parked.
+pub const FLAG_PARKED: u8 = 1 << 1;
+/// The car is looking for a parking spot.
+pub const FLAG_LOOKING_FOR_PARKING: u8 = 1 << 2;
+/// The pedestrian is getting on or off a bike.
+pub const FLAG_PREPARING_BIKE: u8 = 1 << 3;
+/// The pedestrian is waiting at a bus stop.
+pub const FLAG_WAITING_FOR_BUS: u8 = 1 << 4;
+
+/// See the module documentation for the binary layout.
+pub struct AgentSnapshot {
+    time: Time,
+    num_agents: usize,
+    records: Vec<u8>,
+}
+
+impl AgentSnapshot {
+    pub(crate) fn new(sim: &Sim, map: &Map) -> AgentSnapshot {
+        let gps_bounds = map.get_gps_bounds();
+        let mut snapshot = AgentSnapshot {
+            time: sim.time(),
+            num_agents: 0,
+            records: Vec::new(),
+        };
+
+        for car in sim.get_all_draw_cars(map) {
+            let kind = match car.id.1 {
+                VehicleType::Car => 0,
+                VehicleType::Bike => 1,
+                VehicleType::Bus => 2,
+                VehicleType::Train => 3,
+            };
+            let mut flags = 0;
+            if car.waiting_for_turn.is_some() {
+                flags |= FLAG_WAITING_FOR_TURN;
+            }
+            if car.status == CarStatus::Parked {
+                flags |= FLAG_PARKED;
+            }
+            if car.show_parking_intent {
+                flags |= FLAG_LOOKING_FOR_PARKING;
+            }
+            let person = if car.status == CarStatus::Parked {
+                sim.get_owner_of_car(car.id)
+            } else {
+                sim.agent_to_person(AgentID::Car(car.id))
+            };
+            let front = car.body.last_line();
+            snapshot.push(
+                kind,
+                flags,
+                car.id.0,
+                person.map(|p| p.0),
+                front.angle().normalized_degrees(),
+                front.pt2(),
+                front.pt2().to_gps(gps_bounds),
+            );
+        }
+
+        for ped in sim.get_all_draw_peds(map) {
+            let mut flags = 0;
+            if ped.waiting_for_turn.is_some() {
+                flags |= FLAG_WAITING_FOR_TURN;
+            }
+            if ped.preparing_bike {
+                flags |= FLAG_PREPARING_BIKE;
+            }
+            if ped.waiting_for_bus {
+                flags |= FLAG_WAITING_FOR_BUS;
+            }
+            snapshot.push(
+                4,
+                flags,
+                ped.id.0,
+                sim.agent_to_person(AgentID::Pedestrian(ped.id))
+                    .map(|p| p.0),
+                ped.facing.normalized_degrees(),
+                ped.pos,
+                ped.pos.to_gps(gps_bounds),
+            );
+        }
+
+        snapshot
+    }
+
+    fn push(
+        &mut self,
+        kind: u8,
+        flags: u8,
+        id: usize,
+        person: Option<usize>,
+        angle_degrees: f64,
+        pt: geom::Pt2D,
+        gps: geom::LonLat,
+    ) {
+        self.records.push(kind);
+        self.records.push(flags);
+        self.records.extend_from_slice(&0_u16.to_le_bytes());
+        self.records.extend_from_slice(&(id as u32).to_le_bytes());
+        self.records
+            .extend_from_slice(&person.map(|p| p as u32).unwrap_or(u32::MAX).to_le_bytes());
+        self.records
+            .extend_from_slice(&(angle_degrees as f32).to_le_bytes());
+        self.records
+            .extend_from_slice(&(pt.x() as f32).to_le_bytes());
+        self.records
+            .extend_from_slice(&(pt.y() as f32).to_le_bytes());
+        self.records.extend_from_slice(&gps.x().to_le_bytes());
+        self.records.extend_from_slice(&gps.y().to_le_bytes());
+        self.num_agents += 1;
+    }
+
+    pub fn num_agents(&self) -> usize {
+        self.num_agents
+    }
+
+    /// Encodes the header and all records.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.records.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.time.inner_seconds().to_le_bytes());
+        bytes.extend_from_slice(&(self.num_agents as u32).to_le_bytes());
+        bytes.extend_from_slice(&(RECORD_SIZE as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.records);
+        bytes
+    }
+
+    /// Writes the snapshot to a file. The file is replaced atomically, so a consumer polling it
+    /// never observes a partially written snapshot.
+    pub fn write_to_file(&self, path: &str) -> Result<(), std::io::Error> {
+        let tmp = format!("{}.tmp", path);
+        {
+            let mut file = std::fs::File::create(&tmp)?;
+            file.write_all(&self.to_bytes())?;
+        }
+        std::fs::rename(tmp, path)
+    }
+}
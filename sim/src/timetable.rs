@@ -0,0 +1,218 @@
+//! Stop-by-stop schedules for transit routes, from how long buses took between stops in the
+//! simulation. Agencies describe service this way, so edits to transit can be discussed with them.
+
+use std::collections::BTreeMap;
+
+use abstutil::prettyprint_usize;
+use geom::{Duration, Time};
+use map_model::{BusRoute, BusRouteID, BusStopID, Map, PathConstraints};
+
+use crate::{Analytics, CarID};
+
+/// When every scheduled vehicle of one route should reach each stop. Vehicles leave the first
+/// stop at the route's spawn times, then take the average simulated time to reach each later
+/// stop.
+#[derive(Clone, Debug)]
+pub struct Timetable {
+    pub route: BusRouteID,
+    pub stops: Vec<BusStopID>,
+    /// The average time from leaving the first stop to reaching each stop. None if no vehicle has
+    /// been observed making it there yet.
+    pub run_times: Vec<Option<Duration>>,
+    /// How many runs the averages come from
+    pub num_runs: usize,
+    /// For every vehicle leaving the first stop, the times at each stop. Stops without a run time
+    /// have None.
+    pub departures: Vec<Vec<Option<Time>>>,
+}
+
+impl Analytics {
+    /// Builds a timetable for a route from every bus observed so far. Fails if no vehicle has
+    /// reached the route's last stop yet.
+    pub fn timetable(&self, route: &BusRoute) -> Result<Timetable, String> {
+        // Every bus currently partway through a run: when it reached the first stop, and the index
+        // of the next stop it should reach
+        let mut current_runs: BTreeMap<CarID, (Time, usize)> = BTreeMap::new();
+        let mut offsets: Vec<Vec<Duration>> = vec![Vec::new(); route.stops.len()];
+        let mut num_runs = 0;
+        for (time, bus, r, stop) in &self.bus_arrivals {
+            if *r != route.id {
+                continue;
+            }
+            // Check the next expected stop first, so routes that loop back to their first stop
+            // still work
+            if let Some((start, idx)) = current_runs.get_mut(bus) {
+                if route.stops.get(*idx) == Some(stop) {
+                    offsets[*idx].push(*time - *start);
+                    *idx += 1;
+                    if *idx == route.stops.len() {
+                        num_runs += 1;
+                        current_runs.remove(bus);
+                    }
+                    continue;
+                }
+            }
+            if route.stops[0] == *stop {
+                offsets[0].push(Duration::ZERO);
+                current_runs.insert(*bus, (*time, 1));
+            }
+        }
+        if num_runs == 0 {
+            return Err(format!(
+                "no vehicle has finished a run of {} yet",
+                route.full_name
+            ));
+        }
+
+        let run_times: Vec<Option<Duration>> = offsets
+            .into_iter()
+            .map(|list| {
+                if list.is_empty() {
+                    None
+                } else {
+                    let n = list.len() as f64;
+                    Some(list.into_iter().fold(Duration::ZERO, |a, b| a + b) / n)
+                }
+            })
+            .collect();
+        let departures = route
+            .spawn_times
+            .iter()
+            .map(|t| run_times.iter().map(|dt| dt.map(|dt| *t + dt)).collect())
+            .collect();
+        Ok(Timetable {
+            route: route.id,
+            stops: route.stops.clone(),
+            run_times,
+            num_runs,
+            departures,
+        })
+    }
+}
+
+impl Timetable {
+    /// One row per stop and one column per vehicle, like a printed schedule
+    pub fn to_csv(&self, map: &Map) -> String {
+        let mut lines = Vec::new();
+        let mut header = vec!["stop".to_string(), "run time".to_string()];
+        for idx in 0..self.departures.len() {
+            header.push(format!("trip {}", idx + 1));
+        }
+        lines.push(header.join(","));
+        for (idx, bs) in self.stops.iter().enumerate() {
+            let mut row = vec![
+                csv_escape(&map.get_bs(*bs).name),
+                self.run_times[idx].map(gtfs_duration).unwrap_or_default(),
+            ];
+            for times in &self.departures {
+                row.push(times[idx].map(gtfs_time).unwrap_or_default());
+            }
+            lines.push(row.join(","));
+        }
+        lines.join("\n")
+    }
+
+    /// The files of a GTFS feed (https://developers.google.com/transit/gtfs/reference) describing
+    /// these timetables, keyed by filename. This is just a fragment of a feed; there's no
+    /// agency.txt or calendar.txt, and every trip uses a service called "proposal".
+    pub fn to_gtfs(timetables: &[Timetable], map: &Map) -> BTreeMap<String, String> {
+        let mut stops = vec!["stop_id,stop_name,stop_lat,stop_lon".to_string()];
+        let mut routes = vec!["route_id,route_short_name,route_long_name,route_type".to_string()];
+        let mut trips = vec!["route_id,service_id,trip_id".to_string()];
+        let mut stop_times =
+            vec!["trip_id,arrival_time,departure_time,stop_id,stop_sequence".to_string()];
+
+        // Stops don't have a simple numeric ID, so number them in the order they're used
+        let mut stops_used: Vec<BusStopID> = Vec::new();
+        for tt in timetables {
+            for bs in &tt.stops {
+                if !stops_used.contains(bs) {
+                    stops_used.push(*bs);
+                }
+            }
+        }
+        let stop_id = |bs: BusStopID| {
+            format!(
+                "stop{}",
+                stops_used.iter().position(|x| *x == bs).unwrap() + 1
+            )
+        };
+
+        for tt in timetables {
+            let route = map.get_br(tt.route);
+            let route_id = format!("route{}", route.id.0);
+            routes.push(format!(
+                "{},{},{},{}",
+                route_id,
+                csv_escape(&route.short_name),
+                csv_escape(&route.full_name),
+                // 3 is a bus, 0 is light rail
+                if route.route_type == PathConstraints::Bus {
+                    3
+                } else {
+                    0
+                }
+            ));
+            for (trip_idx, times) in tt.departures.iter().enumerate() {
+                let trip_id = format!("{}_trip{}", route_id, trip_idx + 1);
+                trips.push(format!("{},proposal,{}", route_id, trip_id));
+                for (idx, bs) in tt.stops.iter().enumerate() {
+                    let time = times[idx].map(gtfs_time).unwrap_or_default();
+                    stop_times.push(format!(
+                        "{},{},{},{},{}",
+                        trip_id,
+                        time,
+                        time,
+                        stop_id(*bs),
+                        idx + 1
+                    ));
+                }
+            }
+        }
+        for id in stops_used.iter().cloned() {
+            let bs = map.get_bs(id);
+            let gps = bs.sidewalk_pos.pt(map).to_gps(map.get_gps_bounds());
+            stops.push(format!(
+                "{},{},{},{}",
+                stop_id(id),
+                csv_escape(&bs.name),
+                gps.y(),
+                gps.x()
+            ));
+        }
+
+        let mut files = BTreeMap::new();
+        files.insert("stops.txt".to_string(), stops.join("\n"));
+        files.insert("routes.txt".to_string(), routes.join("\n"));
+        files.insert("trips.txt".to_string(), trips.join("\n"));
+        files.insert("stop_times.txt".to_string(), stop_times.join("\n"));
+        files
+    }
+
+    /// Like "42 trips, from 5 simulated runs"
+    pub fn describe(&self) -> String {
+        format!(
+            "{} trips, from {} simulated runs",
+            prettyprint_usize(self.departures.len()),
+            prettyprint_usize(self.num_runs)
+        )
+    }
+}
+
+// GTFS uses HH:MM:SS, with hours past 24 for service after midnight
+fn gtfs_time(t: Time) -> String {
+    let (hours, minutes, seconds, _) = t.get_parts();
+    format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+}
+
+fn gtfs_duration(dt: Duration) -> String {
+    gtfs_time(Time::START_OF_DAY + dt)
+}
+
+fn csv_escape(x: &str) -> String {
+    if x.contains(',') || x.contains('"') {
+        format!("\"{}\"", x.replace('"', "\"\""))
+    } else {
+        x.to_string()
+    }
+}
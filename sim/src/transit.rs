@@ -2,19 +2,34 @@ use std::collections::{BTreeMap, BTreeSet};
 
 use serde::{Deserialize, Serialize};
 
-use abstutil::{deserialize_btreemap, serialize_btreemap};
-use geom::Time;
-use map_model::{BusRoute, BusRouteID, BusStopID, Map, Path, PathRequest, Position};
+use abstutil::{deserialize_btreemap, retain_btreemap, retain_btreeset, serialize_btreemap};
+use geom::{Duration, Time};
+use map_model::{BusRoute, BusRouteID, BusStopID, Map, Path, PathRequest, Position, RoadID};
 
 use crate::sim::Ctx;
 use crate::{
-    CarID, Event, PedestrianID, PersonID, Router, TripID, TripManager, TripPhaseType, VehicleType,
-    WalkingSimState,
+    AgentID, CarID, Command, Event, PedestrianID, PersonID, Router, TransitFares, TripID,
+    TripManager, TripPhaseType, VehicleType, WalkingSimState,
 };
 
 // These index stops along a route, not stops along a single sidewalk.
 type StopIdx = usize;
 
+// A vehicle at a stop idles long enough to open and close its doors, plus time for every
+// passenger getting on or off.
+const TIME_TO_OPEN_AND_CLOSE_DOORS: Duration = Duration::const_seconds(5.0);
+const TIME_PER_BOARDING: Duration = Duration::const_seconds(3.0);
+const TIME_PER_ALIGHTING: Duration = Duration::const_seconds(2.0);
+// How many passengers fit, including standing
+const BUS_CAPACITY: usize = 80;
+const TRAIN_CAPACITY: usize = 250;
+// After reaching the end of a route, a vehicle rests at least this long before running the other
+// direction.
+const LAYOVER_RECOVERY_TIME: Duration = Duration::const_seconds(5.0 * 60.0);
+// A vehicle only lays over to take a departure in the reverse direction if it leaves within this
+// long. Otherwise the vehicle finishes and leaves the map, and the departure gets its own vehicle.
+const MAX_LAYOVER: Duration = Duration::const_seconds(30.0 * 60.0);
+
 #[derive(Serialize, Deserialize, Clone)]
 struct Stop {
     id: BusStopID,
@@ -27,6 +42,8 @@ struct Route {
     stops: Vec<Stop>,
     start: (PathRequest, Path),
     end_at_border: Option<(PathRequest, Path)>,
+    /// The roads the paths detour around
+    avoid_roads: BTreeSet<RoadID>,
     active_vehicles: BTreeSet<CarID>,
 }
 
@@ -36,6 +53,8 @@ struct Bus {
     route: BusRouteID,
     /// Where does each passenger want to deboard?
     passengers: Vec<(PersonID, Option<BusStopID>)>,
+    /// How many people boarded after the doors opened at the current stop
+    late_boardings: usize,
     state: BusState,
 }
 
@@ -43,6 +62,15 @@ struct Bus {
 enum BusState {
     DrivingToStop(StopIdx),
     AtStop(StopIdx),
+    /// Still driving to a stop that a live edit removed from the route. Nobody boards there.
+    /// Afterwards, the vehicle continues to the stop with this index in the edited route, or to
+    /// the end of the route if there's none.
+    DrivingToRemovedStop(BusStopID, Option<StopIdx>),
+    /// Letting riders off at a stop that a live edit removed from the route
+    AtRemovedStop(BusStopID, Option<StopIdx>),
+    /// At the last stop of the previous route, about to drive to the first stop of the current
+    /// one
+    LayingOver(PathRequest, Path),
     DrivingOffMap,
     Done,
 }
@@ -67,6 +95,15 @@ pub(crate) struct TransitSimState {
         deserialize_with = "deserialize_btreemap"
     )]
     peds_waiting: BTreeMap<BusStopID, Vec<(PedestrianID, BusRouteID, Option<BusStopID>, Time)>>,
+    /// Scheduled departures taken over by a vehicle finishing the reverse route
+    claimed_departures: BTreeSet<(BusRouteID, Time)>,
+    /// For departures after a layover, the scheduled time and how late the vehicle left
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    schedule_adherence: BTreeMap<BusRouteID, Vec<(Time, Duration)>>,
+    fares: TransitFares,
 
     events: Vec<Event>,
 }
@@ -83,74 +120,146 @@ impl TransitSimState {
             buses: BTreeMap::new(),
             routes: BTreeMap::new(),
             peds_waiting,
+            claimed_departures: BTreeSet::new(),
+            schedule_adherence: BTreeMap::new(),
+            fares: TransitFares::free(),
             events: Vec::new(),
         }
     }
 
-    /// Returns the path for the first leg.
-    pub fn create_empty_route(&mut self, bus_route: &BusRoute, map: &Map) -> (PathRequest, Path) {
-        if !self.routes.contains_key(&bus_route.id) {
-            assert!(bus_route.stops.len() > 1);
-            let mut stops = Vec::new();
-            for (idx, stop1_id) in bus_route.stops.iter().enumerate() {
-                let stop1 = map.get_bs(*stop1_id);
-                if idx == bus_route.stops.len() - 1 {
-                    stops.push(Stop {
-                        id: stop1.id,
-                        driving_pos: stop1.driving_pos,
-                        next_stop: None,
-                    });
-                    continue;
-                }
-                let req = PathRequest {
-                    start: stop1.driving_pos,
-                    end: map.get_bs(bus_route.stops[idx + 1]).driving_pos,
-                    constraints: bus_route.route_type,
+    pub fn set_fares(&mut self, fares: &TransitFares) {
+        self.fares = fares.clone();
+    }
+
+    /// Keep peds_waiting filled out for stops created after the simulation began.
+    pub fn handle_new_stops(&mut self, map: &Map) {
+        for bs in map.all_bus_stops().keys() {
+            self.peds_waiting.entry(*bs).or_default();
+        }
+    }
+
+    /// Forgets routes that undoing a live edit deleted from the map. Returns every vehicle that
+    /// served one of them, with its route and the riders aboard. Some of these vehicles may have
+    /// already left the map.
+    pub fn delete_removed_routes(&mut self, map: &Map) -> Vec<(CarID, BusRouteID, Vec<PersonID>)> {
+        retain_btreemap(&mut self.routes, |id, _| map.maybe_get_br(*id).is_some());
+        retain_btreeset(&mut self.claimed_departures, |(id, _)| {
+            map.maybe_get_br(*id).is_some()
+        });
+        retain_btreemap(&mut self.schedule_adherence, |id, _| {
+            map.maybe_get_br(*id).is_some()
+        });
+
+        let removed: Vec<CarID> = self
+            .buses
+            .values()
+            .filter(|bus| map.maybe_get_br(bus.route).is_none())
+            .map(|bus| bus.car)
+            .collect();
+        removed
+            .into_iter()
+            .map(|car| {
+                let bus = self.buses.remove(&car).unwrap();
+                let riders = bus.passengers.into_iter().map(|(p, _)| p).collect();
+                (car, bus.route, riders)
+            })
+            .collect()
+    }
+
+    /// Picks up live edits to the stops or detours of a route. Vehicles already running finish
+    /// driving to wherever they're headed, then follow the edited route from there. Riders bound
+    /// for a stop that's no longer ahead get off at the next stop instead. Returns everybody
+    /// waiting for a route that won't take them where they're going anymore, with how long they
+    /// waited; they should walk instead.
+    pub fn handle_live_edits(
+        &mut self,
+        now: Time,
+        map: &Map,
+    ) -> Vec<(PedestrianID, BusStopID, Duration)> {
+        let changed: Vec<BusRouteID> = self
+            .routes
+            .iter()
+            .filter(|(id, route)| {
+                let br = map.get_br(**id);
+                !route
+                    .stops
+                    .iter()
+                    .map(|stop| stop.id)
+                    .eq(br.stops.iter().cloned())
+                    || route.avoid_roads != br.avoid_roads
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        for id in changed {
+            let old = self.routes.remove(&id).unwrap();
+            let mut new = make_route(map.get_br(id), map);
+            for car in &old.active_vehicles {
+                let bus = self.buses.get_mut(car).unwrap();
+                bus.state = match bus.state {
+                    BusState::DrivingToStop(idx) => match new.find(old.stops[idx].id) {
+                        Some(idx) => BusState::DrivingToStop(idx),
+                        None => BusState::DrivingToRemovedStop(
+                            old.stops[idx].id,
+                            old.next_kept_stop(idx + 1, &new),
+                        ),
+                    },
+                    BusState::AtStop(idx) => match new.find(old.stops[idx].id) {
+                        Some(idx) => BusState::AtStop(idx),
+                        None => BusState::AtRemovedStop(
+                            old.stops[idx].id,
+                            old.next_kept_stop(idx + 1, &new),
+                        ),
+                    },
+                    BusState::DrivingToRemovedStop(stop, next) => match new.find(stop) {
+                        Some(idx) => BusState::DrivingToStop(idx),
+                        None => BusState::DrivingToRemovedStop(
+                            stop,
+                            next.and_then(|idx| old.next_kept_stop(idx, &new)),
+                        ),
+                    },
+                    BusState::AtRemovedStop(stop, next) => match new.find(stop) {
+                        Some(idx) => BusState::AtStop(idx),
+                        None => BusState::AtRemovedStop(
+                            stop,
+                            next.and_then(|idx| old.next_kept_stop(idx, &new)),
+                        ),
+                    },
+                    // The path to the first stop is checked when the layover ends, and vehicles
+                    // driving off the map don't stop anymore.
+                    ref x @ BusState::LayingOver(_, _)
+                    | ref x @ BusState::DrivingOffMap
+                    | ref x @ BusState::Done => x.clone(),
                 };
-                if let Some(path) = map.pathfind(req.clone()) {
-                    if path.is_empty() {
-                        panic!("Empty path between stops?! {}", req);
-                    }
-                    stops.push(Stop {
-                        id: stop1.id,
-                        driving_pos: stop1.driving_pos,
-                        next_stop: Some((req, path)),
-                    });
+            }
+            new.active_vehicles = old.active_vehicles;
+            self.routes.insert(id, new);
+        }
+
+        let mut stranded = Vec::new();
+        for (stop, waiting) in self.peds_waiting.iter_mut() {
+            waiting.retain(|(ped, route, maybe_stop2, started_waiting)| {
+                if map
+                    .maybe_get_br(*route)
+                    .map_or(false, |br| br.serves(*stop, *maybe_stop2))
+                {
+                    true
                 } else {
-                    panic!("No route between stops: {}", req);
+                    stranded.push((*ped, *stop, now - *started_waiting));
+                    false
                 }
-            }
-            let start_req = PathRequest {
-                start: Position::start(bus_route.start),
-                end: map.get_bs(bus_route.stops[0]).driving_pos,
-                constraints: bus_route.route_type,
-            };
-            let start = (
-                start_req.clone(),
-                map.pathfind(start_req).expect("no route to first stop"),
-            );
-            let end_at_border = if let Some(l) = bus_route.end_border {
-                let req = PathRequest {
-                    start: map.get_bs(*bus_route.stops.last().unwrap()).driving_pos,
-                    end: Position::end(l, map),
-                    constraints: bus_route.route_type,
-                };
-                let path = map
-                    .pathfind(req.clone())
-                    .expect("no route from last stop to border");
-                Some((req, path))
-            } else {
-                None
-            };
-            self.routes.insert(
-                bus_route.id,
-                Route {
-                    active_vehicles: BTreeSet::new(),
-                    stops,
-                    start,
-                    end_at_border,
-                },
-            );
+            });
+        }
+        // Nobody's left waiting at stops that were deleted along with their route
+        retain_btreemap(&mut self.peds_waiting, |stop, _| {
+            map.maybe_get_bs(*stop).is_some()
+        });
+        stranded
+    }
+
+    /// Returns the path for the first leg.
+    pub fn create_empty_route(&mut self, bus_route: &BusRoute, map: &Map) -> (PathRequest, Path) {
+        if !self.routes.contains_key(&bus_route.id) {
+            self.routes.insert(bus_route.id, make_route(bus_route, map));
         }
 
         self.routes[&bus_route.id].start.clone()
@@ -165,13 +274,14 @@ impl TransitSimState {
                 car: bus,
                 route: r,
                 passengers: Vec::new(),
+                late_boardings: 0,
                 state: BusState::DrivingToStop(0),
             },
         );
     }
 
-    /// If true, the bus is idling. If false, the bus actually arrived at a border and should now
-    /// vanish.
+    /// If Some, the bus idles for this long. If None, the bus actually arrived at a border and
+    /// should now vanish.
     pub fn bus_arrived_at_stop(
         &mut self,
         now: Time,
@@ -179,70 +289,37 @@ impl TransitSimState {
         trips: &mut TripManager,
         walking: &mut WalkingSimState,
         ctx: &mut Ctx,
-    ) -> bool {
+    ) -> Option<Duration> {
         let mut bus = self.buses.get_mut(&id).unwrap();
         match bus.state {
             BusState::DrivingToStop(stop_idx) => {
                 bus.state = BusState::AtStop(stop_idx);
-                let stop1 = self.routes[&bus.route].stops[stop_idx].id;
+                let route = &self.routes[&bus.route];
+                let stop1 = route.stops[stop_idx].id;
+                let end_of_route =
+                    route.stops[stop_idx].next_stop.is_none() && route.end_at_border.is_none();
                 self.events
                     .push(Event::BusArrivedAtStop(id, bus.route, stop1));
 
-                // Deboard existing passengers.
-                let mut still_riding = Vec::new();
-                for (person, maybe_stop2) in bus.passengers.drain(..) {
-                    if Some(stop1) == maybe_stop2 {
-                        trips.person_left_bus(now, person, bus.car, ctx);
-                        self.events.push(Event::PassengerAlightsTransit(
-                            person, bus.car, bus.route, stop1,
-                        ));
-                    } else {
-                        still_riding.push((person, maybe_stop2));
-                    }
-                }
-                bus.passengers = still_riding;
-
-                // Board new passengers.
-                let mut still_waiting = Vec::new();
-                for (ped, route, maybe_stop2, started_waiting) in
-                    self.peds_waiting.remove(&stop1).unwrap()
-                {
-                    if bus.route == route {
-                        let (trip, person) = trips.ped_boarded_bus(
-                            now,
-                            ped,
-                            bus.car,
-                            now - started_waiting,
-                            walking,
-                        );
-                        self.events.push(Event::PassengerBoardsTransit(
-                            person,
-                            bus.car,
-                            bus.route,
-                            stop1,
-                            now - started_waiting,
-                        ));
-                        self.events.push(Event::TripPhaseStarting(
-                            trip,
-                            person,
-                            Some(PathRequest {
-                                start: ctx.map.get_bs(stop1).driving_pos,
-                                end: if let Some(stop2) = maybe_stop2 {
-                                    ctx.map.get_bs(stop2).driving_pos
-                                } else {
-                                    self.routes[&route].end_at_border.as_ref().unwrap().0.end
-                                },
-                                constraints: bus.car.1.to_constraints(),
-                            }),
-                            TripPhaseType::RidingBus(route, stop1, bus.car),
-                        ));
-                        bus.passengers.push((person, maybe_stop2));
-                    } else {
-                        still_waiting.push((ped, route, maybe_stop2, started_waiting));
+                let alightings =
+                    self.alight_passengers(now, id, stop1, Some(stop_idx + 1), trips, ctx);
+                let boardings = self.board_waiting_passengers(now, id, stop1, trips, walking, ctx);
+                let dwell = TIME_TO_OPEN_AND_CLOSE_DOORS
+                    + (boardings as f64) * TIME_PER_BOARDING
+                    + (alightings as f64) * TIME_PER_ALIGHTING;
+                if end_of_route {
+                    if let Some(dt) = self.start_layover(now, id, stop1, trips, walking, ctx) {
+                        return Some(dt.max(dwell));
                     }
                 }
-                self.peds_waiting.insert(stop1, still_waiting);
-                true
+                Some(dwell)
+            }
+            BusState::DrivingToRemovedStop(stop1, next) => {
+                bus.state = BusState::AtRemovedStop(stop1, next);
+                self.events
+                    .push(Event::BusArrivedAtStop(id, bus.route, stop1));
+                let alightings = self.alight_passengers(now, id, stop1, next, trips, ctx);
+                Some(TIME_TO_OPEN_AND_CLOSE_DOORS + (alightings as f64) * TIME_PER_ALIGHTING)
             }
             BusState::DrivingOffMap => {
                 self.routes
@@ -261,17 +338,278 @@ impl TransitSimState {
                     }
                     trips.transit_rider_reached_border(now, person, id, ctx);
                 }
-                false
+                None
+            }
+            BusState::AtStop(_)
+            | BusState::AtRemovedStop(_, _)
+            | BusState::LayingOver(_, _)
+            | BusState::Done => unreachable!(),
+        }
+    }
+
+    /// Lets off everybody bound for this stop, plus anybody whose stop isn't ahead anymore after a
+    /// live edit. The stops still ahead start at the index `next`, or there are none. Returns how
+    /// many people got off.
+    fn alight_passengers(
+        &mut self,
+        now: Time,
+        id: CarID,
+        stop1: BusStopID,
+        next: Option<StopIdx>,
+        trips: &mut TripManager,
+        ctx: &mut Ctx,
+    ) -> usize {
+        let bus = self.buses.get_mut(&id).unwrap();
+        let ahead = match next {
+            Some(idx) => &self.routes[&bus.route].stops[idx..],
+            None => &[],
+        };
+        let mut still_riding = Vec::new();
+        let mut alightings = 0;
+        for (person, maybe_stop2) in bus.passengers.drain(..) {
+            match maybe_stop2 {
+                Some(stop2) if stop2 == stop1 || ahead.iter().all(|stop| stop.id != stop2) => {
+                    alightings += 1;
+                    trips.person_left_bus(now, person, bus.car, stop1, ctx);
+                    self.events.push(Event::PassengerAlightsTransit(
+                        person, bus.car, bus.route, stop1,
+                    ));
+                }
+                _ => {
+                    still_riding.push((person, maybe_stop2));
+                }
+            }
+        }
+        bus.passengers = still_riding;
+        alightings
+    }
+
+    /// Everybody waiting for this vehicle's route boards in the order they arrived at the stop,
+    /// until the vehicle is full. Anybody left behind keeps waiting for the next one. Returns how
+    /// many people boarded.
+    fn board_waiting_passengers(
+        &mut self,
+        now: Time,
+        id: CarID,
+        stop1: BusStopID,
+        trips: &mut TripManager,
+        walking: &mut WalkingSimState,
+        ctx: &mut Ctx,
+    ) -> usize {
+        let bus = self.buses.get_mut(&id).unwrap();
+        let capacity = capacity(bus.car.1);
+        let mut boardings = 0;
+        let mut still_waiting = Vec::new();
+        for (ped, route, maybe_stop2, started_waiting) in self.peds_waiting.remove(&stop1).unwrap()
+        {
+            if bus.route != route {
+                still_waiting.push((ped, route, maybe_stop2, started_waiting));
+                continue;
+            }
+            let person = match trips.agent_to_trip(AgentID::Pedestrian(ped)) {
+                Some(trip) => trips.trip_to_person(trip),
+                None => {
+                    println!(
+                        "WARNING: {} is waiting at {}, but isn't on a trip anymore",
+                        ped, stop1
+                    );
+                    continue;
+                }
+            };
+            if bus.passengers.len() >= capacity {
+                self.events
+                    .push(Event::PassengerPassedUp(person, bus.car, bus.route, stop1));
+                still_waiting.push((ped, route, maybe_stop2, started_waiting));
+            } else {
+                boardings += 1;
+                let (trip, person) =
+                    trips.ped_boarded_bus(now, ped, bus.car, now - started_waiting, walking);
+                self.events.push(Event::PassengerBoardsTransit(
+                    person,
+                    bus.car,
+                    bus.route,
+                    stop1,
+                    now - started_waiting,
+                ));
+                let fare = self
+                    .fares
+                    .cents(ctx.map.get_bs(stop1).sidewalk_pos.pt(ctx.map));
+                if fare > 0 {
+                    self.events
+                        .push(Event::PassengerPaysFare(person, bus.route, stop1, fare));
+                }
+                self.events.push(Event::TripPhaseStarting(
+                    trip,
+                    person,
+                    Some(PathRequest {
+                        start: ctx.map.get_bs(stop1).driving_pos,
+                        end: if let Some(stop2) = maybe_stop2 {
+                            ctx.map.get_bs(stop2).driving_pos
+                        } else {
+                            self.routes[&route].end_at_border.as_ref().unwrap().0.end
+                        },
+                        constraints: bus.car.1.to_constraints(),
+                    }),
+                    TripPhaseType::RidingBus(route, stop1, bus.car),
+                ));
+                bus.passengers.push((person, maybe_stop2));
             }
-            BusState::AtStop(_) | BusState::Done => unreachable!(),
         }
+        self.peds_waiting.insert(stop1, still_waiting);
+        boardings
     }
 
-    pub fn bus_departed_from_stop(&mut self, id: CarID, map: &Map) -> Router {
+    /// A vehicle reached the last stop of its route. If the reverse direction has a departure
+    /// coming up soon, take it over instead of vanishing. The vehicle rests for a recovery time, so
+    /// if it arrived late, it might leave late. Returns how long to idle.
+    fn start_layover(
+        &mut self,
+        now: Time,
+        id: CarID,
+        at: BusStopID,
+        trips: &mut TripManager,
+        walking: &mut WalkingSimState,
+        ctx: &mut Ctx,
+    ) -> Option<Duration> {
+        let from = self.buses[&id].route;
+        let to = ctx.map.get_br(from).find_reverse(ctx.map)?;
+        let scheduled = *ctx
+            .map
+            .get_br(to)
+            .spawn_times
+            .iter()
+            .find(|t| **t > now && !self.claimed_departures.contains(&(to, **t)))
+            .filter(|t| **t - now <= MAX_LAYOVER)?;
+
+        self.create_empty_route(ctx.map.get_br(to), ctx.map);
+        let first_stop = self.routes[&to].stops[0].id;
+        let state = if first_stop == at {
+            BusState::AtStop(0)
+        } else {
+            let req = PathRequest {
+                start: ctx.map.get_bs(at).driving_pos,
+                end: ctx.map.get_bs(first_stop).driving_pos,
+                constraints: ctx.map.get_br(to).route_type,
+            };
+            let path = ctx
+                .map
+                .get_br(to)
+                .pathfind(req.clone(), ctx.map)
+                .filter(|p| !p.is_empty())?;
+            BusState::LayingOver(req, path)
+        };
+
+        // This vehicle runs the departure, so don't spawn another one.
+        ctx.scheduler.cancel(Command::StartBus(to, scheduled));
+        self.claimed_departures.insert((to, scheduled));
+        let depart = scheduled.max(now + LAYOVER_RECOVERY_TIME);
+        self.schedule_adherence
+            .entry(to)
+            .or_default()
+            .push((scheduled, depart - scheduled));
+
+        self.routes
+            .get_mut(&from)
+            .unwrap()
+            .active_vehicles
+            .remove(&id);
+        self.routes.get_mut(&to).unwrap().active_vehicles.insert(id);
+        let bus = self.buses.get_mut(&id).unwrap();
+        bus.route = to;
+        bus.state = state;
+        if first_stop == at {
+            self.events.push(Event::BusArrivedAtStop(id, to, at));
+            self.board_waiting_passengers(now, id, at, trips, walking, ctx);
+        }
+
+        Some(depart - now)
+    }
+
+    pub fn bus_departed_from_stop(
+        &mut self,
+        now: Time,
+        id: CarID,
+        trips: &mut TripManager,
+        ctx: &mut Ctx,
+    ) -> Router {
+        let map = ctx.map;
         let mut bus = self.buses.get_mut(&id).unwrap();
         let route = self.routes.get_mut(&bus.route).unwrap();
         match bus.state {
-            BusState::DrivingToStop(_) | BusState::DrivingOffMap | BusState::Done => unreachable!(),
+            BusState::DrivingToStop(_)
+            | BusState::DrivingToRemovedStop(_, _)
+            | BusState::DrivingOffMap
+            | BusState::Done => unreachable!(),
+            BusState::LayingOver(ref req, ref path) => {
+                // A live edit may have changed the first stop or the detours since the layover
+                // started.
+                let first_stop = route.stops[0].driving_pos;
+                let (req, path) = if req.end == first_stop && route.avoid_roads.is_empty() {
+                    (req.clone(), Some(path.clone()))
+                } else {
+                    let req = PathRequest {
+                        start: req.start,
+                        end: first_stop,
+                        constraints: req.constraints,
+                    };
+                    let path = map
+                        .get_br(bus.route)
+                        .pathfind(req.clone(), map)
+                        .filter(|p| !p.is_empty());
+                    (req, path)
+                };
+                if let Some(path) = path {
+                    bus.state = BusState::DrivingToStop(0);
+                    Router::follow_bus_route(id, path, req.end.dist_along())
+                } else {
+                    // Nobody's aboard during a layover with a path.
+                    route.active_vehicles.remove(&id);
+                    bus.state = BusState::Done;
+                    Router::vanish_bus(id, req.start.lane(), map)
+                }
+            }
+            BusState::AtRemovedStop(stop, next) => {
+                self.events
+                    .push(Event::BusDepartedFromStop(id, bus.route, stop));
+                let start = map.get_bs(stop).driving_pos;
+                let end = match next {
+                    Some(idx) => Some((route.stops[idx].driving_pos, BusState::DrivingToStop(idx))),
+                    None => route
+                        .end_at_border
+                        .as_ref()
+                        .map(|(req, _)| (req.end, BusState::DrivingOffMap)),
+                };
+                if let Some((end, state)) = end {
+                    let req = PathRequest {
+                        start,
+                        end,
+                        constraints: map.get_br(bus.route).route_type,
+                    };
+                    if let Some(path) = map
+                        .get_br(bus.route)
+                        .pathfind(req.clone(), map)
+                        .filter(|p| !p.is_empty())
+                    {
+                        bus.state = state;
+                        return Router::follow_bus_route(id, path, req.end.dist_along());
+                    }
+                }
+
+                // There's no way to continue the route from here, so everybody gets off.
+                route.active_vehicles.remove(&id);
+                bus.state = BusState::Done;
+                for (person, maybe_stop2) in bus.passengers.drain(..) {
+                    if maybe_stop2.is_some() {
+                        trips.person_left_bus(now, person, bus.car, stop, ctx);
+                        self.events.push(Event::PassengerAlightsTransit(
+                            person, bus.car, bus.route, stop,
+                        ));
+                    } else {
+                        trips.transit_rider_reached_border(now, person, id, ctx);
+                    }
+                }
+                Router::vanish_bus(id, start.lane(), map)
+            }
             BusState::AtStop(stop_idx) => {
                 let stop = &route.stops[stop_idx];
                 self.events
@@ -301,7 +639,8 @@ impl TransitSimState {
         }
     }
 
-    /// Returns the bus if the pedestrian boarded immediately.
+    /// Returns the bus if the pedestrian boarded immediately. A full vehicle at the stop passes
+    /// them up.
     pub fn ped_waiting_for_bus(
         &mut self,
         now: Time,
@@ -317,12 +656,16 @@ impl TransitSimState {
         if let Some(route) = self.routes.get(&route_id) {
             for bus in &route.active_vehicles {
                 if let BusState::AtStop(idx) = self.buses[bus].state {
-                    if route.stops[idx].id == stop1 {
-                        self.buses
-                            .get_mut(bus)
-                            .unwrap()
-                            .passengers
-                            .push((person, maybe_stop2));
+                    if route.stops[idx].id != stop1 {
+                        continue;
+                    }
+                    if self.buses[bus].passengers.len() >= capacity(bus.1) {
+                        self.events
+                            .push(Event::PassengerPassedUp(person, *bus, route_id, stop1));
+                    } else {
+                        let vehicle = self.buses.get_mut(bus).unwrap();
+                        vehicle.passengers.push((person, maybe_stop2));
+                        vehicle.late_boardings += 1;
                         self.events.push(Event::TripPhaseStarting(
                             trip,
                             person,
@@ -355,10 +698,33 @@ impl TransitSimState {
         None
     }
 
+    /// Riders who boarded while the vehicle was already idling at a stop hold it there a bit
+    /// longer. If Some, keep idling this much longer before departing.
+    pub fn extra_dwell(&mut self, id: CarID) -> Option<Duration> {
+        let bus = self.buses.get_mut(&id).unwrap();
+        if bus.late_boardings == 0 {
+            return None;
+        }
+        let dt = (bus.late_boardings as f64) * TIME_PER_BOARDING;
+        bus.late_boardings = 0;
+        Some(dt)
+    }
+
+    /// Somebody waiting at a stop gave up.
+    pub fn ped_stopped_waiting(&mut self, ped: PedestrianID) {
+        for waiting in self.peds_waiting.values_mut() {
+            waiting.retain(|(p, _, _, _)| *p != ped);
+        }
+    }
+
     pub fn collect_events(&mut self) -> Vec<Event> {
         self.events.drain(..).collect()
     }
 
+    pub fn get_capacity(&self, bus: CarID) -> usize {
+        capacity(bus.1)
+    }
+
     pub fn get_passengers(&self, bus: CarID) -> &Vec<(PersonID, Option<BusStopID>)> {
         &self.buses[&bus].passengers
     }
@@ -382,6 +748,10 @@ impl TransitSimState {
                             }
                         }
                         BusState::AtStop(idx) => Some(idx),
+                        // The stop isn't on the route anymore
+                        BusState::DrivingToRemovedStop(_, _)
+                        | BusState::AtRemovedStop(_, _)
+                        | BusState::LayingOver(_, _) => None,
                         BusState::DrivingOffMap => Some(r.stops.len() - 1),
                         BusState::Done => unreachable!(),
                     };
@@ -410,6 +780,28 @@ impl TransitSimState {
         (buses, trains)
     }
 
+    /// Among departures after a layover on every route, how many left no later than the
+    /// threshold, and how many there were in total.
+    pub fn on_time_departures(&self, threshold: Duration) -> (usize, usize) {
+        let mut on_time = 0;
+        let mut total = 0;
+        for (_, late) in self.schedule_adherence.values().flatten() {
+            if *late <= threshold {
+                on_time += 1;
+            }
+            total += 1;
+        }
+        (on_time, total)
+    }
+
+    /// For departures after a layover, the scheduled time and how late the vehicle left.
+    pub fn get_schedule_adherence(&self, route: BusRouteID) -> &[(Time, Duration)] {
+        self.schedule_adherence
+            .get(&route)
+            .map(|list| list.as_slice())
+            .unwrap_or(&[])
+    }
+
     pub fn get_people_waiting_at_stop(
         &self,
         at: BusStopID,
@@ -417,3 +809,90 @@ impl TransitSimState {
         &self.peds_waiting[&at]
     }
 }
+
+fn capacity(vehicle_type: VehicleType) -> usize {
+    match vehicle_type {
+        VehicleType::Bus => BUS_CAPACITY,
+        VehicleType::Train => TRAIN_CAPACITY,
+        VehicleType::Car | VehicleType::Bike => unreachable!(),
+    }
+}
+
+impl Route {
+    fn find(&self, stop: BusStopID) -> Option<StopIdx> {
+        self.stops.iter().position(|s| s.id == stop)
+    }
+
+    /// The first stop at or after `idx` on this route that's also on `edited`, as an index into
+    /// `edited`.
+    fn next_kept_stop(&self, idx: StopIdx, edited: &Route) -> Option<StopIdx> {
+        self.stops
+            .get(idx..)?
+            .iter()
+            .find_map(|s| edited.find(s.id))
+    }
+}
+
+fn make_route(bus_route: &BusRoute, map: &Map) -> Route {
+    assert!(bus_route.stops.len() > 1);
+    let mut stops = Vec::new();
+    for (idx, stop1_id) in bus_route.stops.iter().enumerate() {
+        let stop1 = map.get_bs(*stop1_id);
+        if idx == bus_route.stops.len() - 1 {
+            stops.push(Stop {
+                id: stop1.id,
+                driving_pos: stop1.driving_pos,
+                next_stop: None,
+            });
+            continue;
+        }
+        let req = PathRequest {
+            start: stop1.driving_pos,
+            end: map.get_bs(bus_route.stops[idx + 1]).driving_pos,
+            constraints: bus_route.route_type,
+        };
+        if let Some(path) = bus_route.pathfind(req.clone(), map) {
+            if path.is_empty() {
+                panic!("Empty path between stops?! {}", req);
+            }
+            stops.push(Stop {
+                id: stop1.id,
+                driving_pos: stop1.driving_pos,
+                next_stop: Some((req, path)),
+            });
+        } else {
+            panic!("No route between stops: {}", req);
+        }
+    }
+    let start_req = PathRequest {
+        start: Position::start(bus_route.start),
+        end: map.get_bs(bus_route.stops[0]).driving_pos,
+        constraints: bus_route.route_type,
+    };
+    let start = (
+        start_req.clone(),
+        bus_route
+            .pathfind(start_req, map)
+            .expect("no route to first stop"),
+    );
+    let end_at_border = if let Some(l) = bus_route.end_border {
+        let req = PathRequest {
+            start: map.get_bs(*bus_route.stops.last().unwrap()).driving_pos,
+            end: Position::end(l, map),
+            constraints: bus_route.route_type,
+        };
+        let path = bus_route
+            .pathfind(req.clone(), map)
+            .expect("no route from last stop to border");
+        Some((req, path))
+    } else {
+        None
+    };
+    Route {
+        active_vehicles: BTreeSet::new(),
+        stops,
+        start,
+        end_at_border,
+        avoid_roads: bus_route.avoid_roads.clone(),
+    }
+}
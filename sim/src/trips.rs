@@ -12,7 +12,7 @@ use crate::sim::Ctx;
 use crate::{
     AgentID, AgentType, AlertLocation, CarID, Command, CreateCar, CreatePedestrian, DrivingGoal,
     Event, IndividTrip, OrigPersonID, ParkedCar, ParkingSim, ParkingSpot, PedestrianID, PersonID,
-    PersonSpec, Scenario, Scheduler, SidewalkPOI, SidewalkSpot, TransitSimState, TripID,
+    PersonSpec, Persona, Scenario, Scheduler, SidewalkPOI, SidewalkSpot, TransitSimState, TripID,
     TripPhaseType, TripPurpose, TripSpec, Vehicle, VehicleSpec, VehicleType, WalkingSimState,
 };
 
@@ -56,7 +56,9 @@ impl TripManager {
     pub fn new_person(
         &mut self,
         orig_id: Option<OrigPersonID>,
+        persona: Persona,
         ped_speed: Speed,
+        max_walking_distance: Distance,
         vehicle_specs: Vec<VehicleSpec>,
     ) -> &Person {
         let id = PersonID(self.people.len());
@@ -74,14 +76,25 @@ impl TripManager {
             // The first new_trip will set this properly.
             state: PersonState::OffMap,
             ped: PedestrianID(id.0),
+            persona,
             ped_speed,
+            max_walking_distance,
             vehicles,
             delayed_trips: Vec::new(),
             on_bus: None,
+            ferry_arrival: None,
         });
         self.get_person(id).unwrap()
     }
 
+    /// Somebody who won't take any trips spends the whole day inside a building.
+    pub fn stay_inside(&mut self, person: PersonID, b: BuildingID) {
+        let person = &mut self.people[person.0];
+        assert!(person.trips.is_empty());
+        person.state = PersonState::Inside(b);
+        self.events.push(Event::PersonEntersBuilding(person.id, b));
+    }
+
     pub fn new_car_id(&mut self) -> usize {
         let id = self.car_id_counter;
         self.car_id_counter += 1;
@@ -153,6 +166,20 @@ impl TripManager {
             ));
             return;
         }
+        if let Some((i, arrival)) = person.ferry_arrival.take() {
+            if self.trips[trip.0].info.start == TripEndpoint::Border(i) && now < arrival {
+                // Still crossing on the ferry
+                person.ferry_arrival = Some((i, arrival));
+                ctx.scheduler.push(arrival, Command::StartTrip(trip, spec));
+                self.events.push(Event::TripPhaseStarting(
+                    trip,
+                    person.id,
+                    None,
+                    TripPhaseType::DelayedStart,
+                ));
+                return;
+            }
+        }
         self.trips[trip.0].started = true;
 
         // Defer calculating the path until now, to handle live map edits.
@@ -754,12 +781,21 @@ impl TripManager {
 
         match trip.legs[0] {
             TripLeg::Walk(ref spot) => {
-                assert_eq!(*spot, SidewalkSpot::bus_stop(stop, ctx.map));
+                assert_eq!(spot.connection, SidewalkPOI::BusStop(stop));
             }
             _ => unreachable!(),
         }
         match trip.legs[1] {
             TripLeg::RideBus(route, maybe_stop2) => {
+                // The trip was planned before a live edit changed or deleted the route.
+                if !ctx
+                    .map
+                    .maybe_get_br(route)
+                    .map_or(false, |br| br.serves(stop, maybe_stop2))
+                {
+                    self.transit_rider_rerouted(now, ped, stop, Duration::ZERO, ctx);
+                    return None;
+                }
                 self.events.push(Event::TripPhaseStarting(
                     trip.id,
                     trip.person,
@@ -792,6 +828,57 @@ impl TripManager {
         }
     }
 
+    /// Somebody at a bus stop can't ride their route anymore, because of a live edit. They walk
+    /// the rest of the way from the stop instead, or give up if they were riding off the map.
+    pub fn transit_rider_rerouted(
+        &mut self,
+        now: Time,
+        ped: PedestrianID,
+        stop: BusStopID,
+        blocked_time: Duration,
+        ctx: &mut Ctx,
+    ) {
+        let trip = &mut self.trips[self
+            .active_trip_mode
+            .remove(&AgentID::Pedestrian(ped))
+            .unwrap()
+            .0];
+        trip.total_blocked_time += blocked_time;
+        // Start from where the stop was planned, since the stop itself may be gone
+        let start = match trip.legs.pop_front() {
+            Some(TripLeg::Walk(spot)) => {
+                assert_eq!(spot.connection, SidewalkPOI::BusStop(stop));
+                spot
+            }
+            _ => unreachable!(),
+        };
+        match trip.legs.pop_front() {
+            Some(TripLeg::RideBus(_, Some(_))) => {
+                if !trip.spawn_ped(
+                    now,
+                    start,
+                    &self.people[trip.person.0],
+                    ctx.map,
+                    ctx.scheduler,
+                    &mut self.events,
+                ) {
+                    self.unfinished_trips -= 1;
+                }
+            }
+            Some(TripLeg::RideBus(route, None)) => {
+                let id = trip.id;
+                self.cancel_trip(
+                    now,
+                    id,
+                    format!("{} doesn't leave the map from {} anymore", route, stop),
+                    None,
+                    ctx,
+                );
+            }
+            _ => unreachable!(),
+        }
+    }
+
     pub fn ped_boarded_bus(
         &mut self,
         now: Time,
@@ -817,19 +904,29 @@ impl TripManager {
     }
 
     // TODO Need to characterize delay the bus experienced
-    pub fn person_left_bus(&mut self, now: Time, person: PersonID, bus: CarID, ctx: &mut Ctx) {
+    /// The person gets off at `at`. That's usually where they were going, unless a live edit
+    /// removed their stop from the route.
+    pub fn person_left_bus(
+        &mut self,
+        now: Time,
+        person: PersonID,
+        bus: CarID,
+        at: BusStopID,
+        ctx: &mut Ctx,
+    ) {
         let trip = &mut self.trips[self
             .active_trip_mode
             .remove(&AgentID::BusPassenger(person, bus))
             .unwrap()
             .0];
-        let start = match trip.legs.pop_front().unwrap() {
-            TripLeg::RideBus(_, maybe_stop2) => SidewalkSpot::bus_stop(
-                maybe_stop2.expect("someone left a bus, even though they should've ridden off-map"),
-                ctx.map,
+        match trip.legs.pop_front().unwrap() {
+            TripLeg::RideBus(_, maybe_stop2) => assert!(
+                maybe_stop2.is_some(),
+                "someone left a bus, even though they should've ridden off-map"
             ),
             _ => unreachable!(),
-        };
+        }
+        let start = SidewalkSpot::bus_stop(at, ctx.map);
         self.people[person.0].on_bus.take().unwrap();
 
         if !trip.spawn_ped(
@@ -879,6 +976,7 @@ impl TripManager {
         self.people[trip.person.0].state = PersonState::OffMap;
 
         let id = trip.id;
+        self.maybe_board_ferry(now, id, i, false, ctx.map);
         self.trip_finished(now, id, ctx);
     }
 
@@ -909,6 +1007,30 @@ impl TripManager {
         self.trip_finished(now, id, ctx);
     }
 
+    /// Undoing a live edit deleted the route somebody was riding, along with the vehicle. Their
+    /// trip is cancelled.
+    pub fn transit_rider_cancelled(
+        &mut self,
+        now: Time,
+        person: PersonID,
+        bus: CarID,
+        route: BusRouteID,
+        ctx: &mut Ctx,
+    ) {
+        let id = self
+            .active_trip_mode
+            .remove(&AgentID::BusPassenger(person, bus))
+            .unwrap();
+        self.people[person.0].on_bus.take().unwrap();
+        self.cancel_trip(
+            now,
+            id,
+            format!("{} was deleted while riding it", route),
+            None,
+            ctx,
+        );
+    }
+
     pub fn car_or_bike_reached_border(
         &mut self,
         now: Time,
@@ -940,9 +1062,43 @@ impl TripManager {
         }
 
         let id = trip.id;
+        self.maybe_board_ferry(now, id, i, car.1 == VehicleType::Car, ctx.map);
         self.trip_finished(now, id, ctx);
     }
 
+    /// Somebody leaving the map at a ferry terminal waits for the next departure. If their next
+    /// trip starts from the other end of the route, it can't begin until the ferry gets there.
+    fn maybe_board_ferry(
+        &mut self,
+        now: Time,
+        trip: TripID,
+        i: IntersectionID,
+        with_car: bool,
+        map: &Map,
+    ) {
+        let trips = &self.trips;
+        let person = &mut self.people[trips[trip.0].person.0];
+        let next_start = person
+            .trips
+            .iter()
+            .skip_while(|t| **t != trip)
+            .nth(1)
+            .map(|t| trips[t.0].info.start.clone());
+        let dst = match next_start {
+            Some(TripEndpoint::Border(dst)) => dst,
+            _ => {
+                return;
+            }
+        };
+        person.ferry_arrival = map
+            .get_ferry_routes_at(i)
+            .into_iter()
+            .filter(|r| r.other_terminal(i) == Some(dst) && (r.allows_vehicles || !with_car))
+            .filter_map(|r| r.next_departure(now).map(|t| t + r.crossing_time))
+            .min_by(|a, b| a.partial_cmp(b).unwrap())
+            .map(|arrival| (dst, arrival));
+    }
+
     fn trip_finished(&mut self, now: Time, id: TripID, ctx: &mut Ctx) {
         let trip = &mut self.trips[id.0];
         assert!(trip.legs.is_empty());
@@ -1030,6 +1186,7 @@ impl TripManager {
             if vehicle.vehicle_type == VehicleType::Car {
                 if let TripEndpoint::Bldg(b) = trip.info.end {
                     let driving_lane = ctx.map.find_driving_lane_near_building(b);
+                    let started = ctx.start_parking();
                     if let Some(spot) = ctx
                         .parking
                         .get_all_free_spots(Position::start(driving_lane), &vehicle, b, ctx.map)
@@ -1065,6 +1222,7 @@ impl TripManager {
                             ),
                         ));
                     }
+                    ctx.record_parking(started);
                 }
             }
         } else {
@@ -1080,6 +1238,29 @@ impl TripManager {
         self.start_delayed_trip(now, person, ctx);
     }
 
+    /// Somebody gave up after waiting too long, and their agent has already been removed. Like any
+    /// cancelled trip, they're warped to the destination.
+    pub fn abandon_trip(
+        &mut self,
+        now: Time,
+        id: TripID,
+        agent: AgentID,
+        waited: Duration,
+        abandoned_vehicle: Option<Vehicle>,
+        ctx: &mut Ctx,
+    ) {
+        self.events
+            .push(Event::TripAbandoned(id, self.trips[id.0].info.mode, waited));
+        self.cancel_trip(
+            now,
+            id,
+            format!("gave up after waiting {}", waited),
+            abandoned_vehicle,
+            ctx,
+        );
+        self.trip_abruptly_cancelled(id, agent);
+    }
+
     pub fn trip_abruptly_cancelled(&mut self, trip: TripID, agent: AgentID) {
         assert_eq!(self.active_trip_mode.remove(&agent), Some(trip));
     }
@@ -1147,6 +1328,12 @@ impl TripManager {
             self.unfinished_trips,
         )
     }
+    pub fn num_trips_not_started(&self) -> usize {
+        self.trips
+            .iter()
+            .filter(|t| !t.started && t.info.cancellation_reason.is_none())
+            .count()
+    }
     pub fn num_agents(&self, transit: &TransitSimState) -> Counter<AgentType> {
         let mut cnt = Counter::new();
         for a in self.active_trip_mode.keys() {
@@ -1297,6 +1484,12 @@ impl TripManager {
     pub fn generate_scenario(&self, map: &Map, name: String) -> Scenario {
         let mut scenario = Scenario::empty(map, &name);
         for p in &self.people {
+            if p.trips.is_empty() {
+                if let PersonState::Inside(b) = p.state {
+                    *scenario.idle_parked_cars.entry(b).or_insert(0) += p.vehicles.len();
+                }
+                continue;
+            }
             scenario.people.push(PersonSpec {
                 orig_id: p.orig_id,
                 origin: self.trips[p.trips[0].0].info.start.clone(),
@@ -1590,12 +1783,18 @@ pub struct Person {
     pub state: PersonState,
 
     pub ped: PedestrianID,
+    pub persona: Persona,
     pub ped_speed: Speed,
+    /// Only used when deciding how to make a trip; people will still walk farther than this when
+    /// they have no other choice, like after parking far away.
+    pub max_walking_distance: Distance,
     /// Both cars and bikes
     pub vehicles: Vec<Vehicle>,
 
     delayed_trips: Vec<(TripID, TripSpec)>,
     on_bus: Option<CarID>,
+    /// Riding a ferry to this terminal, arriving at this time
+    ferry_arrival: Option<(IntersectionID, Time)>,
 }
 
 impl Person {
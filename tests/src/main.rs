@@ -7,13 +7,14 @@ use rand::seq::SliceRandom;
 
 use abstutil::{MapName, Timer};
 use geom::{Duration, Time};
-use map_model::{IntersectionID, Map};
+use map_model::{IntersectionID, Map, PathConstraints};
 use sim::{IndividTrip, PersonSpec, Scenario, TripEndpoint, TripMode, TripPurpose};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    test_lane_changing(&import_map(abstutil::path(
-        "../tests/input/lane_selection.osm",
-    )))?;
+    let lane_selection = import_map(abstutil::path("../tests/input/lane_selection.osm"));
+    test_lane_changing(&lane_selection)?;
+    test_deterministic_instantiation(&lane_selection)?;
+    test_transit_capacity()?;
     test_map_importer()?;
     check_proposals()?;
     smoke_test()?;
@@ -50,12 +51,14 @@ fn import_map(path: String) -> Map {
                 driving_side: map_model::DrivingSide::Right,
                 bikes_can_use_bus_lanes: true,
                 inferred_sidewalks: true,
+                walk_on_shoulders: false,
             },
             onstreet_parking: convert_osm::OnstreetParking::JustOSM,
             public_offstreet_parking: convert_osm::PublicOffstreetParking::None,
             private_offstreet_parking: convert_osm::PrivateOffstreetParking::FixedPerBldg(0),
             elevation: None,
             include_railroads: true,
+            paths_through_areas: convert_osm::PathsThroughAreas::None,
         },
         &mut timer,
     );
@@ -227,3 +230,143 @@ fn test_lane_changing(map: &Map) -> Result<(), String> {
 
     Ok(())
 }
+
+/// Instantiating the same scenario with the same RNG seed twice must produce identical
+/// simulations, or regression tests comparing results across runs can't be trusted.
+fn test_deterministic_instantiation(map: &Map) -> Result<(), String> {
+    // This map has no buildings, so everybody goes between borders
+    let mut rng = sim::SimFlags::for_test("deterministic_instantiation").make_rng();
+    let borders: Vec<IntersectionID> = map
+        .all_intersections()
+        .iter()
+        .filter(|i| i.is_border())
+        .map(|i| i.id)
+        .collect();
+    let mut scenario = Scenario::empty(map, "deterministic_instantiation");
+    for idx in 0..100 {
+        let from = *borders.choose(&mut rng).unwrap();
+        let to = *borders.choose(&mut rng).unwrap();
+        if from == to {
+            continue;
+        }
+        scenario.people.push(PersonSpec {
+            orig_id: None,
+            origin: TripEndpoint::Border(from),
+            trips: vec![IndividTrip::new(
+                Time::START_OF_DAY + Duration::seconds(idx as f64),
+                TripPurpose::Shopping,
+                TripEndpoint::Border(to),
+                *[TripMode::Drive, TripMode::Bike, TripMode::Walk]
+                    .choose(&mut rng)
+                    .unwrap(),
+            )],
+        });
+    }
+    check_deterministic(map, &scenario)?;
+
+    // Cover people starting from buildings, SpawnOverTime blocks, and seeding parked cars too
+    let mut timer = Timer::throwaway();
+    let montlake = map_model::Map::new(MapName::seattle("montlake").path(), &mut timer);
+    let scenario = sim::ScenarioGenerator::preset("big_run", &montlake)
+        .unwrap()
+        .generate(
+            &montlake,
+            &mut sim::SimFlags::for_test("deterministic_instantiation").make_rng(),
+            &mut timer,
+        );
+    check_deterministic(&montlake, &scenario)
+}
+
+/// Send more people to one bus stop than a bus can hold, and make sure the first bus leaves the
+/// extra riders behind.
+fn test_transit_capacity() -> Result<(), String> {
+    let mut timer = Timer::throwaway();
+    let map = map_model::Map::new(MapName::seattle("montlake").path(), &mut timer);
+    // Everybody waits at the first stop of a bus route, then rides it off the map
+    let route = map
+        .all_bus_routes()
+        .iter()
+        .find(|r| r.route_type == PathConstraints::Bus && r.end_border.is_some())
+        .ok_or("montlake has no bus routes leaving the map")?;
+    let stop = map.get_bs(route.stops[0]);
+    let border = map.get_l(route.end_border.unwrap()).dst_i;
+
+    // Matches the capacity of a bus in the simulation
+    let capacity = 80;
+    let riders = capacity + 20;
+    let mut scenario = Scenario::empty(&map, "transit_capacity");
+    scenario.only_seed_buses = None;
+    for _ in 0..riders {
+        scenario.people.push(PersonSpec {
+            orig_id: None,
+            origin: TripEndpoint::SuddenlyAppear(stop.sidewalk_pos),
+            trips: vec![IndividTrip::new(
+                Time::START_OF_DAY,
+                TripPurpose::Work,
+                TripEndpoint::Border(border),
+                TripMode::Transit,
+            )],
+        });
+    }
+
+    let mut opts = sim::SimOptions::new("test_transit_capacity");
+    opts.alerts = sim::AlertHandler::Silence;
+    opts.record_event_log = true;
+    let mut sim = sim::Sim::new(&map, opts, &mut timer);
+    let mut rng = sim::SimFlags::for_test("transit_capacity").make_rng();
+    scenario.instantiate(&mut sim, &map, &mut rng, &mut timer);
+    sim.timed_step(&map, Duration::minutes(30), &mut None, &mut timer);
+
+    // Look at the first vehicle to leave anybody behind, on whichever route people chose
+    let log = sim.get_event_log().unwrap();
+    let pass_ups: Vec<_> = map
+        .all_bus_routes()
+        .iter()
+        .flat_map(|r| log.pass_ups(r.id))
+        .collect();
+    let first_bus = pass_ups
+        .iter()
+        .min_by_key(|(time, _, _, _)| *time)
+        .map(|(_, _, bus, _)| *bus)
+        .ok_or("nobody was passed up by a full bus")?;
+    let left_behind: std::collections::BTreeSet<_> = pass_ups
+        .iter()
+        .filter(|(_, _, bus, _)| *bus == first_bus)
+        .map(|(_, person, _, _)| *person)
+        .collect();
+    if left_behind.len() != riders - capacity {
+        return Err(format!(
+            "{} riders waited for a bus that holds {}, but {} left {} behind",
+            riders,
+            capacity,
+            first_bus,
+            left_behind.len()
+        ));
+    }
+    Ok(())
+}
+
+// Instantiate and run the scenario twice with the same seed, and compare the results.
+fn check_deterministic(map: &Map, scenario: &Scenario) -> Result<(), String> {
+    let run = || {
+        let mut opts = sim::SimOptions::new("test_deterministic_instantiation");
+        opts.alerts = sim::AlertHandler::Silence;
+        let mut sim = sim::Sim::new(map, opts, &mut Timer::throwaway());
+        let mut rng = sim::SimFlags::for_test("deterministic_instantiation").make_rng();
+        scenario.instantiate(&mut sim, map, &mut rng, &mut Timer::throwaway());
+        sim.timed_step(
+            map,
+            Duration::minutes(10),
+            &mut None,
+            &mut Timer::throwaway(),
+        );
+        abstutil::to_binary(sim.get_analytics())
+    };
+    if run() != run() {
+        return Err(format!(
+            "Instantiating {} twice with the same seed produced different simulations",
+            scenario.scenario_name
+        ));
+    }
+    Ok(())
+}
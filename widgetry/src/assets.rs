@@ -1,4 +1,4 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 
 use lru::LruCache;
@@ -19,7 +19,9 @@ pub struct Assets {
     // because of borrowing.
     svg_cache: RefCell<HashMap<String, (GeomBatch, Bounds)>>,
     font_to_id: HashMap<Font, fontdb::ID>,
-    pub text_opts: Options,
+    pub text_opts: RefCell<Options>,
+    // Have the system's fonts been added to text_opts yet?
+    system_fonts_loaded: Cell<bool>,
 }
 
 impl Assets {
@@ -30,31 +32,23 @@ impl Assets {
             line_height_cache: RefCell::new(HashMap::new()),
             svg_cache: RefCell::new(HashMap::new()),
             font_to_id: HashMap::new(),
-            text_opts: Options::default(),
+            text_opts: RefCell::new(Options::default()),
+            system_fonts_loaded: Cell::new(false),
         };
         // TODO These paths are now hardcoded for WASM. This is reasonable, since the fonts
         // available are currently fixed anyway. For widgetry to become a library, need to figure
         // out how to override these.
-        a.text_opts.fontdb = fontdb::Database::new();
-        a.text_opts.fontdb.load_font_data(
+        let db = &mut a.text_opts.get_mut().fontdb;
+        *db = fontdb::Database::new();
+        db.load_font_data(
             include_bytes!("../../data/system/fonts/BungeeInline-Regular.ttf").to_vec(),
         );
-        a.text_opts
-            .fontdb
-            .load_font_data(include_bytes!("../../data/system/fonts/Bungee-Regular.ttf").to_vec());
-        a.text_opts
-            .fontdb
-            .load_font_data(include_bytes!("../../data/system/fonts/Overpass-Bold.ttf").to_vec());
-        a.text_opts.fontdb.load_font_data(
-            include_bytes!("../../data/system/fonts/OverpassMono-Bold.ttf").to_vec(),
-        );
-        a.text_opts.fontdb.load_font_data(
-            include_bytes!("../../data/system/fonts/Overpass-Regular.ttf").to_vec(),
-        );
-        a.text_opts.fontdb.load_font_data(
-            include_bytes!("../../data/system/fonts/Overpass-SemiBold.ttf").to_vec(),
-        );
-        a.text_opts.fontdb.load_font_data(
+        db.load_font_data(include_bytes!("../../data/system/fonts/Bungee-Regular.ttf").to_vec());
+        db.load_font_data(include_bytes!("../../data/system/fonts/Overpass-Bold.ttf").to_vec());
+        db.load_font_data(include_bytes!("../../data/system/fonts/OverpassMono-Bold.ttf").to_vec());
+        db.load_font_data(include_bytes!("../../data/system/fonts/Overpass-Regular.ttf").to_vec());
+        db.load_font_data(include_bytes!("../../data/system/fonts/Overpass-SemiBold.ttf").to_vec());
+        db.load_font_data(
             include_bytes!("../../data/system/fonts/ZCOOLXiaoWei-Regular.ttf").to_vec(),
         );
         for font in vec![
@@ -69,6 +63,7 @@ impl Assets {
             a.font_to_id.insert(
                 font,
                 a.text_opts
+                    .borrow()
                     .fontdb
                     .query(&fontdb::Query {
                         families: &vec![fontdb::Family::Name(font.family())],
@@ -97,6 +92,7 @@ impl Assets {
         // This seems to be missing line_gap, and line_gap is 0, so manually adjust here.
         let line_height = self
             .text_opts
+            .borrow()
             .fontdb
             .with_face_data(self.font_to_id[&font], |data, face_index| {
                 let font = ttf_parser::Face::from_slice(data, face_index).unwrap();
@@ -113,6 +109,40 @@ impl Assets {
         height
     }
 
+    /// The bundled fonts don't cover every script, like Arabic or Hebrew. When a glyph is missing,
+    /// usvg falls back to any other font that has it, so the first time some text needs a glyph
+    /// that the bundled fonts lack, make the system's fonts available too. Scanning them is slow,
+    /// so this isn't done at startup. There's no filesystem to search on the web.
+    pub(crate) fn load_fallback_fonts(&self, text: &str) {
+        if cfg!(target_arch = "wasm32") || self.system_fonts_loaded.get() {
+            return;
+        }
+        let missing = {
+            let opts = self.text_opts.borrow();
+            text.chars()
+                .filter(|c| !c.is_ascii() && !c.is_control())
+                .any(|c| {
+                    !opts.fontdb.faces().iter().any(|face| {
+                        opts.fontdb
+                            .with_face_data(face.id, |data, face_index| {
+                                ttf_parser::Face::from_slice(data, face_index)
+                                    .ok()
+                                    .and_then(|f| f.glyph_index(c))
+                                    .is_some()
+                            })
+                            .unwrap_or(false)
+                    })
+                })
+        };
+        if missing {
+            self.system_fonts_loaded.set(true);
+            let mut opts = self.text_opts.borrow_mut();
+            for dir in system_font_dirs() {
+                opts.fontdb.load_fonts_dir(dir);
+            }
+        }
+    }
+
     pub fn get_cached_text(&self, key: &String) -> Option<GeomBatch> {
         self.text_cache.borrow_mut().get(key).cloned()
     }
@@ -129,6 +159,23 @@ impl Assets {
     }
 }
 
+fn system_font_dirs() -> Vec<std::path::PathBuf> {
+    let mut dirs: Vec<std::path::PathBuf> = if cfg!(target_arch = "wasm32") {
+        Vec::new()
+    } else if cfg!(windows) {
+        vec!["C:\\Windows\\Fonts".into()]
+    } else if cfg!(target_os = "macos") {
+        vec!["/Library/Fonts".into(), "/System/Library/Fonts".into()]
+    } else {
+        vec!["/usr/share/fonts".into(), "/usr/local/share/fonts".into()]
+    };
+    if let Ok(home) = std::env::var("HOME") {
+        dirs.push(std::path::Path::new(&home).join(".local/share/fonts"));
+        dirs.push(std::path::Path::new(&home).join("Library/Fonts"));
+    }
+    dirs
+}
+
 impl std::convert::AsRef<Assets> for GfxCtx<'_> {
     fn as_ref(&self) -> &Assets {
         &self.prerender.assets
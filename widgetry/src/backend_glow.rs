@@ -115,6 +115,13 @@ pub struct Drawable {
     gl: Rc<glow::Context>,
 }
 
+impl Drawable {
+    /// How many triangles are drawn every time this is redrawn
+    pub fn num_triangles(&self) -> usize {
+        (self.num_indices / 3) as usize
+    }
+}
+
 impl Drop for Drawable {
     #[inline]
     fn drop(&mut self) {
@@ -354,6 +361,75 @@ impl PrerenderInnards {
     pub fn draw_finished(&self, gfc_ctx_innards: GfxCtxInnards) {
         self.window_adapter.draw_finished(gfc_ctx_innards)
     }
+
+    /// Runs `draw` against an offscreen buffer of this many physical pixels, instead of the
+    /// window. Returns the RGBA pixels drawn, starting with the top row.
+    pub fn render_offscreen<F: FnOnce()>(
+        &self,
+        width: u32,
+        height: u32,
+        draw: F,
+    ) -> Result<Vec<u8>, String> {
+        let (w, h) = (width as i32, height as i32);
+        let mut pixels = vec![0; (width * height * 4) as usize];
+        unsafe {
+            let framebuffer = self.gl.create_framebuffer()?;
+            self.gl
+                .bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
+            let mut renderbuffers = Vec::new();
+            for (format, attachment) in &[
+                (glow::RGBA8, glow::COLOR_ATTACHMENT0),
+                (glow::DEPTH_COMPONENT24, glow::DEPTH_ATTACHMENT),
+            ] {
+                let renderbuffer = self.gl.create_renderbuffer()?;
+                self.gl
+                    .bind_renderbuffer(glow::RENDERBUFFER, Some(renderbuffer));
+                self.gl
+                    .renderbuffer_storage(glow::RENDERBUFFER, *format, w, h);
+                self.gl.framebuffer_renderbuffer(
+                    glow::FRAMEBUFFER,
+                    *attachment,
+                    glow::RENDERBUFFER,
+                    Some(renderbuffer),
+                );
+                renderbuffers.push(renderbuffer);
+            }
+
+            let status = self.gl.check_framebuffer_status(glow::FRAMEBUFFER);
+            if status == glow::FRAMEBUFFER_COMPLETE {
+                self.gl.viewport(0, 0, w, h);
+                self.gl.scissor(0, 0, w, h);
+                draw();
+                self.gl.read_pixels(
+                    0,
+                    0,
+                    w,
+                    h,
+                    glow::RGBA,
+                    glow::UNSIGNED_BYTE,
+                    glow::PixelPackData::Slice(&mut pixels),
+                );
+            }
+
+            self.gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            self.gl.bind_renderbuffer(glow::RENDERBUFFER, None);
+            for renderbuffer in renderbuffers {
+                self.gl.delete_renderbuffer(renderbuffer);
+            }
+            self.gl.delete_framebuffer(framebuffer);
+            if status != glow::FRAMEBUFFER_COMPLETE {
+                return Err(format!("Offscreen framebuffer isn't complete: {}", status));
+            }
+        }
+
+        // OpenGL starts from the bottom row
+        Ok(pixels
+            .chunks(4 * width as usize)
+            .rev()
+            .flatten()
+            .cloned()
+            .collect())
+    }
 }
 
 /// Uploads a sprite sheet of textures to the GPU so they can be used by Fill::Texture and
@@ -7,7 +7,11 @@ pub use crate::backend_glow::Drawable;
 use crate::backend_glow::{GfxCtxInnards, PrerenderInnards};
 use crate::ScreenDims;
 
-pub fn setup(window_title: &str) -> (PrerenderInnards, winit::event_loop::EventLoop<()>) {
+// The canvas is always visible in the browser
+pub fn setup(
+    window_title: &str,
+    _visible: bool,
+) -> (PrerenderInnards, winit::event_loop::EventLoop<()>) {
     info!("Setting up widgetry");
 
     // This doesn't seem to work for the shader panics here, but later it does work. Huh.
@@ -1,4 +1,5 @@
 use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
 
 use geom::{Bounds, Polygon, Pt2D};
 
@@ -19,6 +20,29 @@ pub(crate) const SCREENSPACE_Z: f32 = 0.0;
 pub(crate) const MENU_Z: f32 = -1.0;
 pub(crate) const TOOLTIP_Z: f32 = -2.0;
 
+/// How many triangles were drawn in one frame, grouped by the layer passed to
+/// `GfxCtx::set_render_layer`. Anything drawn before a layer is set counts as "other".
+#[derive(Clone, Debug, Default)]
+pub struct RenderCosts {
+    pub per_layer: BTreeMap<&'static str, usize>,
+}
+
+impl RenderCosts {
+    pub fn total(&self) -> usize {
+        self.per_layer.values().sum()
+    }
+
+    /// From most to least expensive
+    pub fn describe(&self) -> Vec<String> {
+        let mut layers: Vec<(&&'static str, &usize)> = self.per_layer.iter().collect();
+        layers.sort_by_key(|(_, cnt)| std::cmp::Reverse(**cnt));
+        layers
+            .into_iter()
+            .map(|(layer, cnt)| format!("{}: {} triangles", layer, cnt))
+            .collect()
+    }
+}
+
 #[derive(Debug)]
 pub struct Uniforms {
     // (cam_x, cam_y, cam_zoom)
@@ -58,6 +82,8 @@ pub struct GfxCtx<'a> {
 
     pub(crate) num_draw_calls: usize,
     pub(crate) num_forks: usize,
+    render_layer: &'static str,
+    pub(crate) costs: RenderCosts,
 }
 
 impl<'a> GfxCtx<'a> {
@@ -76,6 +102,8 @@ impl<'a> GfxCtx<'a> {
             prerender,
             num_draw_calls: 0,
             num_forks: 0,
+            render_layer: "other",
+            costs: RenderCosts::default(),
             screencap_mode,
             naming_hint: None,
         }
@@ -133,6 +161,7 @@ impl<'a> GfxCtx<'a> {
         self.inner
             .redraw(obj, &self.uniforms, &self.prerender.inner);
         self.num_draw_calls += 1;
+        *self.costs.per_layer.entry(self.render_layer).or_insert(0) += obj.num_triangles();
 
         // println!("{:?}", backtrace::Backtrace::new());
     }
@@ -143,6 +172,11 @@ impl<'a> GfxCtx<'a> {
         self.unfork();
     }
 
+    /// Everything drawn from now until the next call counts towards this layer's rendering costs.
+    pub fn set_render_layer(&mut self, layer: &'static str) {
+        self.render_layer = layer;
+    }
+
     // TODO Stateful API :(
     pub fn enable_clipping(&mut self, rect: ScreenRectangle) {
         let scale_factor = self.prerender.get_scale_factor();
@@ -245,6 +279,8 @@ pub struct Prerender {
     pub(crate) assets: Assets,
     pub(crate) num_uploads: Cell<usize>,
     pub(crate) scale_factor: RefCell<f64>,
+    pub(crate) triangle_budget: Cell<Option<usize>>,
+    pub(crate) last_frame_costs: RefCell<RenderCosts>,
 }
 
 impl Prerender {
@@ -256,6 +292,20 @@ impl Prerender {
         self.actually_upload(false, batch)
     }
 
+    /// What the previous frame cost to draw
+    pub fn last_frame_costs(&self) -> RenderCosts {
+        self.last_frame_costs.borrow().clone()
+    }
+
+    pub fn get_triangle_budget(&self) -> Option<usize> {
+        self.triangle_budget.get()
+    }
+
+    /// Warns whenever a frame draws more triangles than this
+    pub fn set_triangle_budget(&self, budget: Option<usize>) {
+        self.triangle_budget.set(budget);
+    }
+
     pub fn get_total_bytes_uploaded(&self) -> usize {
         self.inner.total_bytes_uploaded.get()
     }
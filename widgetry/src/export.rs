@@ -0,0 +1,165 @@
+//! Writes a `GeomBatch` as a vector image, for printing or including in documents. Only plain
+//! colors can be written; gradients use their first color, and polygons filled with a texture are
+//! skipped.
+
+use std::fmt::Write;
+
+use geom::{Polygon, Pt2D};
+
+use crate::{Color, Fill, GeomBatch, ScreenDims};
+
+pub fn to_svg(batch: &GeomBatch, dims: ScreenDims) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = dims.width,
+        h = dims.height
+    )
+    .unwrap();
+    for (color, polygon) in colored_polygons(batch) {
+        write!(out, r#"<path fill="{}""#, color.to_hex()).unwrap();
+        if color.a < 1.0 {
+            write!(out, r#" fill-opacity="{}""#, color.a).unwrap();
+        }
+        out.push_str(r#" d=""#);
+        for [pt1, pt2, pt3] in triangles(polygon) {
+            write!(
+                out,
+                "M{:.2} {:.2}L{:.2} {:.2}L{:.2} {:.2}Z",
+                pt1.x(),
+                pt1.y(),
+                pt2.x(),
+                pt2.y(),
+                pt3.x(),
+                pt3.y()
+            )
+            .unwrap();
+        }
+        out.push_str("\"/>\n");
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+/// A single page, with one unit in the batch becoming one point (1/72 inch) on the page.
+pub fn to_pdf(batch: &GeomBatch, dims: ScreenDims) -> Vec<u8> {
+    // PDF's origin is the bottom-left, so flip everything vertically
+    let y = |pt: Pt2D| dims.height - pt.y();
+
+    let mut opacities: Vec<String> = Vec::new();
+    let mut content = String::new();
+    for (color, polygon) in colored_polygons(batch) {
+        let opacity = format!("{:.3}", color.a);
+        let idx = match opacities.iter().position(|x| x == &opacity) {
+            Some(idx) => idx,
+            None => {
+                opacities.push(opacity);
+                opacities.len() - 1
+            }
+        };
+        writeln!(
+            content,
+            "/GS{} gs {:.3} {:.3} {:.3} rg",
+            idx, color.r, color.g, color.b
+        )
+        .unwrap();
+        for [pt1, pt2, pt3] in triangles(polygon) {
+            writeln!(
+                content,
+                "{:.2} {:.2} m {:.2} {:.2} l {:.2} {:.2} l h",
+                pt1.x(),
+                y(pt1),
+                pt2.x(),
+                y(pt2),
+                pt3.x(),
+                y(pt3)
+            )
+            .unwrap();
+        }
+        content.push_str("f\n");
+    }
+
+    let graphics_states: Vec<String> = opacities
+        .iter()
+        .enumerate()
+        .map(|(idx, opacity)| format!("/GS{} << /ca {} >>", idx, opacity))
+        .collect();
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Contents 4 0 R /Resources \
+             << /ExtGState << {} >> >> >>",
+            dims.width,
+            dims.height,
+            graphics_states.join(" ")
+        ),
+        format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ),
+    ];
+
+    // The cross-reference table needs the byte offset of every object
+    let mut out = "%PDF-1.4\n".to_string();
+    let mut offsets = Vec::new();
+    for (idx, obj) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        write!(out, "{} 0 obj\n{}\nendobj\n", idx + 1, obj).unwrap();
+    }
+    let xref = out.len();
+    write!(out, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).unwrap();
+    for offset in offsets {
+        writeln!(out, "{:010} 00000 n ", offset).unwrap();
+    }
+    write!(
+        out,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    )
+    .unwrap();
+    out.into_bytes()
+}
+
+// In drawing order. Polygons are drawn in order, except ones with a z-offset closer to -1 go on
+// top.
+fn colored_polygons(batch: &GeomBatch) -> Vec<(Color, &Polygon)> {
+    let mut list: Vec<(f64, Color, &Polygon)> = batch
+        .list
+        .iter()
+        .filter_map(|(fill, polygon, z)| {
+            let color = match fill {
+                Fill::Color(c) => *c,
+                Fill::LinearGradient(lg) => lg.stops.first()?.1,
+                Fill::Texture(_) => {
+                    return None;
+                }
+                Fill::ColoredTexture(c, _) => *c,
+            };
+            Some((*z, color, polygon))
+        })
+        .collect();
+    list.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+    list.into_iter()
+        .map(|(_, color, polygon)| (color, polygon))
+        .collect()
+}
+
+// Every polygon is written as its triangles, all in one path, so the seams between them don't
+// show.
+fn triangles(polygon: &Polygon) -> Vec<[Pt2D; 3]> {
+    let (points, indices) = polygon.raw_for_rendering();
+    indices
+        .chunks(3)
+        .map(|tri| {
+            [
+                points[tri[0] as usize],
+                points[tri[1] as usize],
+                points[tri[2] as usize],
+            ]
+        })
+        .collect()
+}
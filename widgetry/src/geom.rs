@@ -2,7 +2,8 @@ use geom::{Angle, Bounds, Polygon, Pt2D};
 
 use crate::widgets::button::BtnBuilder;
 use crate::{
-    svg, Btn, Color, DeferDraw, Drawable, EventCtx, Fill, GfxCtx, Prerender, ScreenDims, Widget,
+    export, svg, Btn, Color, DeferDraw, Drawable, EventCtx, Fill, GfxCtx, Prerender, ScreenDims,
+    Widget,
 };
 
 /// A mutable builder for a group of colored polygons.
@@ -143,6 +144,18 @@ impl GeomBatch {
         svg::load_svg(prerender, filename).0
     }
 
+    /// Writes the batch as an SVG image of the given size, with the top-left at 0, 0. Textures
+    /// aren't written.
+    pub fn to_svg(&self, dims: ScreenDims) -> String {
+        export::to_svg(self, dims)
+    }
+
+    /// Writes the batch as a single-page PDF of the given size in points, with the top-left at 0,
+    /// 0. Textures aren't written.
+    pub fn to_pdf(&self, dims: ScreenDims) -> Vec<u8> {
+        export::to_pdf(self, dims)
+    }
+
     /// Transforms all colors in a batch.
     pub fn color(mut self, transformation: RewriteColor) -> GeomBatch {
         for (fancy, _, _) in &mut self.list {
@@ -31,7 +31,7 @@ pub use crate::app_state::{DrawBaselayer, SharedAppState, State, Transition};
 pub use crate::backend::Drawable;
 pub use crate::canvas::{Canvas, HorizontalAlignment, VerticalAlignment};
 pub use crate::color::{Color, Fill, LinearGradient, Texture};
-pub use crate::drawing::{GfxCtx, Prerender};
+pub use crate::drawing::{GfxCtx, Prerender, RenderCosts};
 pub use crate::event::{hotkeys, lctrl, Event, Key, MultiKey};
 pub use crate::event_ctx::{EventCtx, UpdateType};
 pub use crate::geom::{GeomBatch, RewriteColor};
@@ -40,6 +40,7 @@ pub use crate::runner::{run, Settings};
 pub use crate::screen_geom::{ScreenDims, ScreenPt, ScreenRectangle};
 pub use crate::style::Style;
 pub use crate::text::{Line, Text, TextExt, TextSpan};
+pub use crate::tools::form::{Form, FormChecks, FormValues};
 pub use crate::tools::warper::Warper;
 pub use crate::widgets::autocomplete::Autocomplete;
 pub(crate) use crate::widgets::button::Button;
@@ -73,6 +74,7 @@ mod color;
 mod drawing;
 mod event;
 mod event_ctx;
+mod export;
 mod geom;
 mod input;
 mod runner;
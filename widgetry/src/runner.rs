@@ -9,9 +9,11 @@ use geom::Duration;
 
 use crate::app_state::App;
 use crate::assets::Assets;
-use crate::tools::screenshot::screenshot_everything;
+use crate::drawing::RenderCosts;
+use crate::tools::screenshot::{screenshot_everything, screenshot_viewport};
 use crate::{
-    Canvas, Event, EventCtx, GfxCtx, Prerender, SharedAppState, Style, Text, UpdateType, UserInput,
+    Canvas, Event, EventCtx, GfxCtx, Prerender, ScreenDims, SharedAppState, Style, Text,
+    UpdateType, UserInput,
 };
 
 const UPDATE_FREQUENCY: std::time::Duration = std::time::Duration::from_millis(1000 / 30);
@@ -131,6 +133,20 @@ impl<A: SharedAppState> State<A> {
         }
         let naming_hint = g.naming_hint.take();
 
+        if let Some(budget) = prerender.triangle_budget.get() {
+            let total = g.costs.total();
+            // Only warn when a frame first goes over, not every frame after that
+            if total > budget && prerender.last_frame_costs.borrow().total() <= budget {
+                warn!(
+                    "Frame drew {} triangles, over the budget of {}. {}",
+                    total,
+                    budget,
+                    g.costs.describe().join(", ")
+                );
+            }
+        }
+        *prerender.last_frame_costs.borrow_mut() = std::mem::take(&mut g.costs);
+
         if false {
             println!(
                 "----- {} uploads, {} draw calls, {} forks -----",
@@ -151,6 +167,8 @@ pub struct Settings {
     scale_factor: Option<f64>,
     window_icon: Option<String>,
     loading_tips: Option<Text>,
+    triangle_budget: Option<usize>,
+    screenshot: Option<(String, ScreenDims)>,
 }
 
 impl Settings {
@@ -161,6 +179,8 @@ impl Settings {
             scale_factor: None,
             window_icon: None,
             loading_tips: None,
+            triangle_budget: None,
+            screenshot: None,
         }
     }
 
@@ -180,6 +200,17 @@ impl Settings {
     pub fn loading_tips(&mut self, txt: Text) {
         self.loading_tips = Some(txt);
     }
+
+    /// Warn about any frame that draws more triangles than this.
+    pub fn triangle_budget(&mut self, budget: usize) {
+        self.triangle_budget = Some(budget);
+    }
+
+    /// Instead of opening a window, draw the first frame offscreen at this size, save it as a PNG
+    /// file, and quit. Unless a scale factor is specified, the size is in physical pixels.
+    pub fn screenshot(&mut self, path: String, size: ScreenDims) {
+        self.screenshot = Some((path, size));
+    }
 }
 
 pub fn run<
@@ -189,7 +220,8 @@ pub fn run<
     settings: Settings,
     make_app: F,
 ) -> ! {
-    let (prerender_innards, event_loop) = crate::backend::setup(&settings.window_title);
+    let (prerender_innards, event_loop) =
+        crate::backend::setup(&settings.window_title, settings.screenshot.is_none());
 
     if let Some(ref path) = settings.window_icon {
         if !cfg!(target_arch = "wasm32") {
@@ -204,17 +236,27 @@ pub fn run<
         }
     }
 
-    let monitor_scale_factor = prerender_innards.monitor_scale_factor();
+    let monitor_scale_factor = if settings.screenshot.is_some() {
+        1.0
+    } else {
+        prerender_innards.monitor_scale_factor()
+    };
     let prerender = Prerender {
         assets: Assets::new(),
         num_uploads: Cell::new(0),
         inner: prerender_innards,
         scale_factor: RefCell::new(settings.scale_factor.unwrap_or(monitor_scale_factor)),
+        triangle_budget: Cell::new(settings.triangle_budget),
+        last_frame_costs: RefCell::new(RenderCosts::default()),
     };
     let mut style = Style::standard();
     style.loading_tips = settings.loading_tips.unwrap_or_else(Text::new);
 
-    let initial_size = prerender.window_size();
+    let initial_size = if let Some((_, size)) = settings.screenshot {
+        size
+    } else {
+        prerender.window_size()
+    };
     let mut canvas = Canvas::new(initial_size);
     prerender.window_resized(initial_size);
 
@@ -233,6 +275,19 @@ pub fn run<
 
     let mut state = State { canvas, app, style };
 
+    if let Some((path, _)) = settings.screenshot {
+        match screenshot_viewport(&mut state, &prerender, &path) {
+            Ok(()) => {
+                info!("Saved {}", path);
+                std::process::exit(0);
+            }
+            Err(err) => {
+                error!("Couldn't save {}: {}", path, err);
+                std::process::exit(1);
+            }
+        }
+    }
+
     let dump_raw_events = settings.dump_raw_events;
 
     let mut running = true;
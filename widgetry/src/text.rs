@@ -3,6 +3,7 @@ use std::fmt::Write;
 use std::hash::Hasher;
 
 use geom::{PolyLine, Polygon};
+use unicode_bidi::BidiInfo;
 
 use crate::assets::Assets;
 use crate::{
@@ -414,6 +415,21 @@ impl Text {
     }
 }
 
+// usvg reorders mixed-direction runs within a line, but always lays the line out with a
+// left-to-right base direction. For a line in a right-to-left script like Arabic or Hebrew, that
+// puts numbers and punctuation on the wrong side. Wrapping the line in these explicit formatting
+// characters only changes that base direction; the reordering itself is still up to usvg.
+const RIGHT_TO_LEFT_EMBEDDING: char = '\u{202B}';
+const POP_DIRECTIONAL_FORMATTING: char = '\u{202C}';
+
+/// Is the base direction of this line right-to-left, according to the Unicode bidi algorithm?
+fn is_rtl(line: &str) -> bool {
+    BidiInfo::new(line, None)
+        .paragraphs
+        .first()
+        .map_or(false, |para| para.level.is_rtl())
+}
+
 fn render_line(spans: Vec<TextSpan>, tolerance: f32, assets: &Assets) -> GeomBatch {
     // TODO This assumes size and font don't change mid-line. We might be able to support that now,
     // actually.
@@ -435,8 +451,21 @@ fn render_line(spans: Vec<TextSpan>, tolerance: f32, assets: &Assets) -> GeomBat
     )
     .unwrap();
 
+    let line: String = spans.iter().map(|span| span.text.as_str()).collect();
+    assets.load_fallback_fonts(&line);
+    let rtl = is_rtl(&line);
+    let num_spans = spans.len();
     let mut contents = String::new();
-    for span in spans {
+    for (idx, span) in spans.into_iter().enumerate() {
+        let mut text = htmlescape::encode_minimal(&span.text);
+        if rtl {
+            if idx == 0 {
+                text.insert(0, RIGHT_TO_LEFT_EMBEDDING);
+            }
+            if idx == num_spans - 1 {
+                text.push(POP_DIRECTIONAL_FORMATTING);
+            }
+        }
         write!(
             &mut contents,
             r##"<tspan fill="{}" {}>{}</tspan>"##,
@@ -447,13 +476,13 @@ fn render_line(spans: Vec<TextSpan>, tolerance: f32, assets: &Assets) -> GeomBat
             } else {
                 ""
             },
-            htmlescape::encode_minimal(&span.text)
+            text
         )
         .unwrap();
     }
     write!(&mut svg, "{}</text></svg>", contents).unwrap();
 
-    let svg_tree = match usvg::Tree::from_str(&svg, &assets.text_opts) {
+    let svg_tree = match usvg::Tree::from_str(&svg, &assets.text_opts.borrow()) {
         Ok(t) => t,
         Err(err) => panic!("render_line({}): {}", contents, err),
     };
@@ -532,14 +561,20 @@ impl TextSpan {
         )
             .unwrap();
 
+        assets.load_fallback_fonts(&self.text);
+        let mut text = htmlescape::encode_minimal(&self.text);
+        if is_rtl(&self.text) {
+            text.insert(0, RIGHT_TO_LEFT_EMBEDDING);
+            text.push(POP_DIRECTIONAL_FORMATTING);
+        }
         write!(
             &mut svg,
             r##"<textPath href="#txtpath">{}</textPath></text></svg>"##,
-            self.text
+            text
         )
         .unwrap();
 
-        let svg_tree = match usvg::Tree::from_str(&svg, &assets.text_opts) {
+        let svg_tree = match usvg::Tree::from_str(&svg, &assets.text_opts.borrow()) {
             Ok(t) => t,
             Err(err) => panic!("curvey({}): {}", self.text, err),
         };
@@ -0,0 +1,182 @@
+use crate::{Checkbox, Choice, Color, EventCtx, Line, Panel, Spinner, Text, TextExt, Widget};
+
+/// Declares a small form of named fields -- spinners, text boxes, dropdowns, and checkboxes --
+/// along with how to validate them. Each field is a row with a label, and is named, so the values
+/// can be read back out of the panel the form is placed in.
+///
+/// ```ignore
+/// let (widget, form) = Form::new()
+///     .text(ctx, "name", "Name", "New route", |x| {
+///         if x.is_empty() {
+///             Err("The route needs a name".to_string())
+///         } else {
+///             Ok(())
+///         }
+///     })
+///     .spinner(ctx, "headway", "Minutes between buses", (1, 120), 15)
+///     .build(ctx);
+/// ...
+/// match form.values(&self.panel) {
+///     Ok(values) => values.spinner("headway"),
+///     Err(errors) => { ... },
+/// }
+/// ```
+pub struct Form {
+    rows: Vec<Widget>,
+    checks: Vec<Check>,
+}
+
+type Check = Box<dyn Fn(&FormValues) -> Result<(), String>>;
+
+impl Form {
+    pub fn new() -> Form {
+        Form {
+            rows: Vec::new(),
+            checks: Vec::new(),
+        }
+    }
+
+    /// A whole number between `low` and `high`, inclusive
+    pub fn spinner(
+        mut self,
+        ctx: &EventCtx,
+        name: &str,
+        label: &str,
+        (low, high): (isize, isize),
+        default: isize,
+    ) -> Self {
+        self.rows.push(row(
+            ctx,
+            label,
+            Spinner::new(ctx, (low, high), default).named(name),
+        ));
+        self
+    }
+
+    /// Free-form text, checked with `validate`. Surrounding whitespace is trimmed first.
+    pub fn text<F: 'static + Fn(&str) -> Result<(), String>>(
+        mut self,
+        ctx: &EventCtx,
+        name: &str,
+        label: &str,
+        default: &str,
+        validate: F,
+    ) -> Self {
+        self.rows.push(row(
+            ctx,
+            label,
+            Widget::text_entry(ctx, default.to_string(), false).named(name),
+        ));
+        let name = name.to_string();
+        self.checks
+            .push(Box::new(move |values| validate(&values.text(&name))));
+        self
+    }
+
+    /// One of several choices. The label names the field.
+    pub fn dropdown<T: 'static + PartialEq + Clone + std::fmt::Debug>(
+        mut self,
+        ctx: &EventCtx,
+        label: &str,
+        default: T,
+        choices: Vec<Choice<T>>,
+    ) -> Self {
+        self.rows.push(row(
+            ctx,
+            label,
+            Widget::dropdown(ctx, label, default, choices),
+        ));
+        self
+    }
+
+    /// A switch that's on or off. The label names the field.
+    pub fn checkbox(mut self, ctx: &EventCtx, label: &str, default: bool) -> Self {
+        self.rows.push(Checkbox::switch(ctx, label, None, default));
+        self
+    }
+
+    /// Validates several fields together, like making sure a start comes before an end.
+    pub fn check<F: 'static + Fn(&FormValues) -> Result<(), String>>(mut self, check: F) -> Self {
+        self.checks.push(Box::new(check));
+        self
+    }
+
+    /// Returns the widget to place in a panel, and what's needed to read the values back out of
+    /// that panel later. Validation errors are shown in the widget.
+    pub fn build(mut self, ctx: &EventCtx) -> (Widget, FormChecks) {
+        self.rows.push(Text::new().draw(ctx).named(ERRORS));
+        (
+            Widget::col(self.rows),
+            FormChecks {
+                checks: self.checks,
+            },
+        )
+    }
+}
+
+fn row(ctx: &EventCtx, label: &str, widget: Widget) -> Widget {
+    Widget::row(vec![label.draw_text(ctx).centered_vert(), widget])
+}
+
+const ERRORS: &str = "form errors";
+
+/// Validates the values of a form built with `Form`.
+pub struct FormChecks {
+    checks: Vec<Check>,
+}
+
+impl FormChecks {
+    /// Returns the current values if they're all valid. Otherwise returns every problem.
+    pub fn values<'a>(&self, panel: &'a Panel) -> Result<FormValues<'a>, Vec<String>> {
+        let values = FormValues { panel };
+        let errors: Vec<String> = self
+            .checks
+            .iter()
+            .filter_map(|check| check(&values).err())
+            .collect();
+        if errors.is_empty() {
+            Ok(values)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validates the form, showing any problems in it. Call this whenever the panel changes.
+    pub fn update(&self, ctx: &mut EventCtx, panel: &mut Panel) -> bool {
+        let mut txt = Text::new();
+        let valid = match self.values(panel) {
+            Ok(_) => true,
+            Err(errors) => {
+                for err in errors {
+                    txt.add(Line(err).fg(Color::RED));
+                }
+                false
+            }
+        };
+        panel.replace(ctx, ERRORS, txt.draw(ctx));
+        valid
+    }
+}
+
+/// The current values of a form's fields, by name
+pub struct FormValues<'a> {
+    panel: &'a Panel,
+}
+
+impl<'a> FormValues<'a> {
+    pub fn spinner(&self, name: &str) -> isize {
+        self.panel.spinner(name)
+    }
+
+    pub fn text(&self, name: &str) -> String {
+        self.panel.text_box(name).trim().to_string()
+    }
+
+    pub fn dropdown<T: 'static + PartialEq + Clone>(&self, label: &str) -> T {
+        self.panel.dropdown_value(label)
+    }
+
+    pub fn checkbox(&self, label: &str) -> bool {
+        self.panel.is_checked(label)
+    }
+}
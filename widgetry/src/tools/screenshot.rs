@@ -54,6 +54,22 @@ pub(crate) fn screenshot_everything<A: SharedAppState>(
     finish(dir_path, filenames, num_tiles_x, num_tiles_y);
 }
 
+/// Draws the current viewport once, offscreen, and saves it as a PNG file.
+pub(crate) fn screenshot_viewport<A: SharedAppState>(
+    state: &mut State<A>,
+    prerender: &Prerender,
+    path: &str,
+) -> Result<(), String> {
+    let scale_factor = prerender.get_scale_factor();
+    let width = (state.canvas.window_width * scale_factor) as u32;
+    let height = (state.canvas.window_height * scale_factor) as u32;
+    let pixels = prerender.inner.render_offscreen(width, height, || {
+        state.draw(prerender, false);
+    })?;
+    image::save_buffer(path, &pixels, width, height, image::ColorType::Rgba8)
+        .map_err(|err| err.to_string())
+}
+
 fn screencap(filename: &str) -> bool {
     if !process::Command::new("scrot")
         .args(&[
@@ -5,7 +5,7 @@ use stretch::node::Stretch;
 use stretch::number::Number;
 use stretch::style::{Dimension, Style};
 
-use geom::{Percent, Polygon};
+use geom::{Duration, Percent, Polygon};
 
 use crate::widgets::Container;
 use crate::{
@@ -370,6 +370,9 @@ impl Panel {
     pub fn spinner(&self, name: &str) -> isize {
         self.find::<Spinner>(name).current
     }
+    pub fn duration_spinner(&self, name: &str) -> Duration {
+        self.find::<Spinner>(name).duration_value()
+    }
     pub fn modify_spinner(&mut self, name: &str, delta: isize) {
         self.find_mut::<Spinner>(name).modify(delta)
     }
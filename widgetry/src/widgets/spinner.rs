@@ -1,4 +1,4 @@
-use geom::{Polygon, Pt2D};
+use geom::{Duration, Polygon, Pt2D};
 
 use crate::{
     text, Btn, Button, EventCtx, GeomBatch, GfxCtx, Line, Outcome, ScreenDims, ScreenPt,
@@ -15,6 +15,9 @@ pub struct Spinner {
     low: isize,
     high: isize,
     pub current: isize,
+    /// If set, `current` counts steps of this size, and the value is displayed as a duration.
+    step: Option<Duration>,
+    text_width: f64,
 
     up: Button,
     down: Button,
@@ -24,7 +27,34 @@ pub struct Spinner {
 }
 
 impl Spinner {
-    pub fn new(ctx: &EventCtx, (low, high): (isize, isize), mut current: isize) -> Widget {
+    pub fn new(ctx: &EventCtx, (low, high): (isize, isize), current: isize) -> Widget {
+        Spinner::make(ctx, (low, high), current, None, TEXT_WIDTH)
+    }
+
+    /// A spinner for picking a duration between `low` and `high`, changing by `step` at a time.
+    /// Read it back with `Panel::duration_spinner`.
+    pub fn duration(
+        ctx: &EventCtx,
+        (low, high): (Duration, Duration),
+        step: Duration,
+        current: Duration,
+    ) -> Widget {
+        Spinner::make(
+            ctx,
+            ((low / step) as isize, (high / step) as isize),
+            (current / step).round() as isize,
+            Some(step),
+            2.0 * TEXT_WIDTH,
+        )
+    }
+
+    fn make(
+        ctx: &EventCtx,
+        (low, high): (isize, isize),
+        mut current: isize,
+        step: Option<Duration>,
+        text_width: f64,
+    ) -> Widget {
         let up = Btn::text_fg("↑")
             .build(ctx, "increase value", None)
             .take_btn();
@@ -33,7 +63,7 @@ impl Spinner {
             .take_btn();
 
         let dims = ScreenDims::new(
-            TEXT_WIDTH + up.get_dims().width,
+            text_width + up.get_dims().width,
             up.get_dims().height + down.get_dims().height,
         );
         if current < low {
@@ -47,6 +77,8 @@ impl Spinner {
             low,
             high,
             current,
+            step,
+            text_width,
 
             up,
             down,
@@ -56,6 +88,11 @@ impl Spinner {
         }))
     }
 
+    /// The current value of a spinner made with `Spinner::duration`.
+    pub fn duration_value(&self) -> Duration {
+        self.step.expect("not a duration spinner") * (self.current as f64)
+    }
+
     pub fn modify(&mut self, delta: isize) {
         self.current += delta;
         self.current = self.current.min(self.high);
@@ -73,9 +110,9 @@ impl WidgetImpl for Spinner {
         // here and use that. Wait, why can't we? ...
         self.top_left = top_left;
         self.up
-            .set_pos(ScreenPt::new(top_left.x + TEXT_WIDTH, top_left.y));
+            .set_pos(ScreenPt::new(top_left.x + self.text_width, top_left.y));
         self.down.set_pos(ScreenPt::new(
-            top_left.x + TEXT_WIDTH,
+            top_left.x + self.text_width,
             top_left.y + self.up.get_dims().height,
         ));
     }
@@ -119,10 +156,14 @@ impl WidgetImpl for Spinner {
             text::BG_COLOR,
             Polygon::rounded_rectangle(self.dims.width, self.dims.height, Some(5.0)),
         )]);
+        let label = match self.step {
+            Some(step) => format!("{}", step * (self.current as f64)),
+            None => self.current.to_string(),
+        };
         batch.append(
-            Text::from(Line(self.current.to_string()))
+            Text::from(Line(label))
                 .render_to_batch(g.prerender)
-                .centered_on(Pt2D::new(TEXT_WIDTH / 2.0, self.dims.height / 2.0)),
+                .centered_on(Pt2D::new(self.text_width / 2.0, self.dims.height / 2.0)),
         );
         let draw = g.upload(batch);
         g.redraw_at(self.top_left, &draw);